@@ -0,0 +1,303 @@
+//! The DEF data model.
+
+use arcstr::ArcStr;
+use serde::{Deserialize, Serialize};
+
+use crate::lex::Tokens;
+use crate::LefDefResult;
+
+mod read;
+mod write;
+
+/// The DEF version written by this crate.
+pub const DEF_VERSION: &str = "5.8";
+
+/// A placed and routed design.
+#[derive(Debug, Default, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct DefDesign {
+    pub name: ArcStr,
+    /// The number of database units per micron.
+    pub dbu_per_micron: u32,
+    /// The vertices of the die area.
+    ///
+    /// Two points specify a rectangle by its opposite corners.
+    pub die_area: Vec<DefPoint>,
+    pub components: Vec<DefComponent>,
+    pub pins: Vec<DefPin>,
+    pub special_nets: Vec<DefNet>,
+    pub nets: Vec<DefNet>,
+}
+
+/// A location in database units.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub struct DefPoint {
+    pub x: i64,
+    pub y: i64,
+}
+
+/// An orientation of a component or pin.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub enum DefOrient {
+    /// No rotation.
+    #[default]
+    N,
+    /// Rotated 90 degrees counter-clockwise.
+    W,
+    /// Rotated 180 degrees.
+    S,
+    /// Rotated 270 degrees counter-clockwise.
+    E,
+    /// Mirrored about the y-axis.
+    FN,
+    /// Mirrored about the line `y = x`.
+    FW,
+    /// Mirrored about the x-axis.
+    FS,
+    /// Mirrored about the line `y = -x`.
+    FE,
+}
+
+/// The placement status of a component or pin.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub enum DefPlacementStatus {
+    /// Placed, but movable by the place-and-route tool.
+    #[default]
+    Placed,
+    /// Fixed in place.
+    Fixed,
+    /// Fixed in place, and part of the cover (e.g. the pad ring).
+    Cover,
+}
+
+/// A placement of a component or pin.
+///
+/// Components are placed such that the lower left corner of their rotated
+/// placement boundary lies at `loc`.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub struct DefPlacement {
+    pub status: DefPlacementStatus,
+    pub loc: DefPoint,
+    pub orient: DefOrient,
+}
+
+/// An instance of a LEF macro.
+#[derive(Debug, Default, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct DefComponent {
+    pub name: ArcStr,
+    pub macro_name: ArcStr,
+    /// The component's placement, or [`None`] if it is unplaced.
+    pub placement: Option<DefPlacement>,
+}
+
+/// The direction of a [`DefPin`].
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub enum DefDirection {
+    Input,
+    Output,
+    InOut,
+    Feedthru,
+}
+
+/// A top-level pin of the design.
+#[derive(Debug, Default, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct DefPin {
+    pub name: ArcStr,
+    /// The net to which the pin connects.
+    pub net: ArcStr,
+    /// Whether or not the pin connects to a special net.
+    pub special: bool,
+    pub direction: Option<DefDirection>,
+    /// The pin's usage (e.g. `SIGNAL` or `POWER`), if specified.
+    pub usage: Option<String>,
+    pub ports: Vec<DefPinPort>,
+}
+
+/// A physical port of a [`DefPin`].
+#[derive(Debug, Default, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct DefPinPort {
+    /// The shapes of the port, relative to the port's placement.
+    pub shapes: Vec<DefLayerShape>,
+    pub placement: Option<DefPlacement>,
+}
+
+/// A shape on a specific layer.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct DefLayerShape {
+    pub layer: ArcStr,
+    pub shape: DefShape,
+}
+
+/// A DEF shape.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub enum DefShape {
+    /// A rectangle, specified by two opposite corners.
+    Rect(DefPoint, DefPoint),
+    Polygon(Vec<DefPoint>),
+}
+
+/// A regular or special net.
+#[derive(Debug, Default, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct DefNet {
+    pub name: ArcStr,
+    pub connections: Vec<DefConnection>,
+    /// The net's usage (e.g. `SIGNAL` or `POWER`), if specified.
+    pub usage: Option<String>,
+    pub wires: Vec<DefWire>,
+    /// Shapes not associated with a wire.
+    ///
+    /// Only special nets may contain shapes.
+    pub shapes: Vec<DefLayerShape>,
+}
+
+/// A connection of a net to a component pin or top-level pin.
+#[derive(Debug, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub enum DefConnection {
+    Component { component: ArcStr, pin: ArcStr },
+    Pin(ArcStr),
+}
+
+/// The routing status of a [`DefWire`].
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub enum DefWireStatus {
+    #[default]
+    Routed,
+    Fixed,
+    Cover,
+    NoShield,
+}
+
+/// A single routed wire.
+#[derive(Debug, Default, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct DefWire {
+    pub status: DefWireStatus,
+    pub layer: ArcStr,
+    /// The width of the wire.
+    ///
+    /// Only wires of special nets have an explicit width.
+    /// Wires of regular nets use the default width of their layer.
+    pub width: Option<i64>,
+    /// The shape of a special wire (e.g. `STRIPE` or `RING`), if specified.
+    pub shape: Option<String>,
+    pub points: Vec<DefRoutePoint>,
+}
+
+/// An element of a [`DefWire`]'s routing.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub enum DefRoutePoint {
+    /// A point on the wire's center line.
+    ///
+    /// If `ext` is [`None`], the wire extends past the point by half its width
+    /// for regular nets, and not at all for special nets.
+    Point { x: i64, y: i64, ext: Option<i64> },
+    /// A via, placed at the previous point.
+    Via(ArcStr),
+    /// A rectangle, specified relative to the previous point.
+    Rect {
+        dx0: i64,
+        dy0: i64,
+        dx1: i64,
+        dy1: i64,
+    },
+}
+
+impl DefPoint {
+    /// Creates a new [`DefPoint`].
+    pub const fn new(x: i64, y: i64) -> Self {
+        Self { x, y }
+    }
+}
+
+impl DefPlacement {
+    /// Creates a movable placement at `loc`.
+    pub fn placed(loc: DefPoint, orient: DefOrient) -> Self {
+        Self {
+            status: DefPlacementStatus::Placed,
+            loc,
+            orient,
+        }
+    }
+
+    /// Creates a fixed placement at `loc`.
+    pub fn fixed(loc: DefPoint, orient: DefOrient) -> Self {
+        Self {
+            status: DefPlacementStatus::Fixed,
+            loc,
+            orient,
+        }
+    }
+}
+
+impl DefDesign {
+    /// Creates a new, empty [`DefDesign`].
+    pub fn new(name: impl Into<ArcStr>, dbu_per_micron: u32) -> Self {
+        Self {
+            name: name.into(),
+            dbu_per_micron,
+            ..Default::default()
+        }
+    }
+
+    /// Parses a [`DefDesign`] from DEF source text.
+    pub fn parse(src: &str) -> LefDefResult<Self> {
+        read::DefReader::new(Tokens::new(src)).read_design()
+    }
+}
+
+impl DefWire {
+    /// Returns the rectangles covered by the wire, as pairs of opposite corners.
+    ///
+    /// Segments between consecutive points are expanded by half the wire width.
+    /// `default_width` is used if the wire does not have an explicit width;
+    /// segments are skipped if neither is available. Vias are ignored.
+    pub fn rects(&self, default_width: Option<i64>) -> Vec<(DefPoint, DefPoint)> {
+        let width = self.width.or(default_width);
+        let special = self.width.is_some();
+        let mut rects = Vec::new();
+        let mut prev: Option<(i64, i64, Option<i64>)> = None;
+        for point in self.points.iter() {
+            match *point {
+                DefRoutePoint::Point { x, y, ext } => {
+                    if let (Some((px, py, pext)), Some(width)) = (prev, width) {
+                        let half = width / 2;
+                        let default_ext = if special { 0 } else { half };
+                        let (ext0, ext1) =
+                            (pext.unwrap_or(default_ext), ext.unwrap_or(default_ext));
+                        let (lo, hi) = if (px, py) <= (x, y) {
+                            (ext0, ext1)
+                        } else {
+                            (ext1, ext0)
+                        };
+                        if px == x {
+                            rects.push((
+                                DefPoint::new(x - half, py.min(y) - lo),
+                                DefPoint::new(x + width - half, py.max(y) + hi),
+                            ));
+                        } else if py == y {
+                            rects.push((
+                                DefPoint::new(px.min(x) - lo, y - half),
+                                DefPoint::new(px.max(x) + hi, y + width - half),
+                            ));
+                        }
+                    }
+                    prev = Some((x, y, ext));
+                }
+                DefRoutePoint::Rect { dx0, dy0, dx1, dy1 } => {
+                    if let Some((px, py, _)) = prev {
+                        rects.push((
+                            DefPoint::new(px + dx0, py + dy0),
+                            DefPoint::new(px + dx1, py + dy1),
+                        ));
+                    }
+                }
+                DefRoutePoint::Via(_) => {}
+            }
+        }
+        rects
+    }
+}
+
+impl std::fmt::Display for DefDesign {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write::write_design(self, f)
+    }
+}
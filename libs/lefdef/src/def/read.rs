@@ -0,0 +1,376 @@
+//! DEF reading.
+
+use super::*;
+
+/// Reads a [`DefDesign`] from a stream of tokens.
+pub(crate) struct DefReader {
+    tokens: Tokens,
+}
+
+impl DefReader {
+    pub(crate) fn new(tokens: Tokens) -> Self {
+        Self { tokens }
+    }
+
+    pub(crate) fn read_design(&mut self) -> LefDefResult<DefDesign> {
+        let mut design = DefDesign::default();
+        while !self.tokens.is_empty() {
+            let keyword = self.tokens.next()?.to_string();
+            match keyword.as_str() {
+                "DESIGN" => {
+                    design.name = self.tokens.next()?.into();
+                    self.tokens.expect(";")?;
+                }
+                "UNITS" => {
+                    self.tokens.expect("DISTANCE")?;
+                    self.tokens.expect("MICRONS")?;
+                    design.dbu_per_micron = self.tokens.parse()?;
+                    self.tokens.expect(";")?;
+                }
+                "DIEAREA" => {
+                    while !self.tokens.eat(";") {
+                        design.die_area.push(self.read_point()?);
+                    }
+                }
+                "COMPONENTS" => {
+                    design.components = self.read_section(&keyword, Self::read_component)?
+                }
+                "PINS" => design.pins = self.read_section(&keyword, Self::read_pin)?,
+                "SPECIALNETS" => {
+                    design.special_nets = self.read_section(&keyword, |r| r.read_net(true))?
+                }
+                "NETS" => design.nets = self.read_section(&keyword, |r| r.read_net(false))?,
+                "END" => {
+                    self.tokens.expect("DESIGN")?;
+                    break;
+                }
+                "PROPERTYDEFINITIONS" => self.tokens.skip_block(&keyword)?,
+                "BEGINEXT" => while self.tokens.next()? != "ENDEXT" {},
+                _ => {
+                    // Sections are introduced by their name and item count.
+                    // Anything else is a single statement.
+                    let is_section = self
+                        .tokens
+                        .peek()
+                        .map(|t| t.parse::<u64>().is_ok())
+                        .unwrap_or(false)
+                        && self.tokens.peek_nth(1) == Some(";");
+                    if is_section {
+                        self.tokens.skip_block(&keyword)?;
+                    } else {
+                        self.tokens.skip_statement()?;
+                    }
+                }
+            }
+        }
+        Ok(design)
+    }
+
+    /// Reads a section of `-`-prefixed items, up to and including `END name`.
+    fn read_section<T>(
+        &mut self,
+        name: &str,
+        mut read_item: impl FnMut(&mut Self) -> LefDefResult<T>,
+    ) -> LefDefResult<Vec<T>> {
+        self.tokens.parse::<u64>()?;
+        self.tokens.expect(";")?;
+        let mut items = Vec::new();
+        loop {
+            if self.tokens.eat("END") {
+                self.tokens.expect(name)?;
+                return Ok(items);
+            }
+            self.tokens.expect("-")?;
+            items.push(read_item(self)?);
+        }
+    }
+
+    fn read_component(&mut self) -> LefDefResult<DefComponent> {
+        let name = self.tokens.next()?.into();
+        let macro_name = self.tokens.next()?.into();
+        let mut component = DefComponent {
+            name,
+            macro_name,
+            placement: None,
+        };
+        while self.tokens.eat("+") {
+            match self.tokens.next()? {
+                "UNPLACED" => component.placement = None,
+                status @ ("PLACED" | "FIXED" | "COVER") => {
+                    let status = placement_status(status);
+                    component.placement = Some(self.read_placement(status)?);
+                }
+                _ => self.skip_option()?,
+            }
+        }
+        self.tokens.expect(";")?;
+        Ok(component)
+    }
+
+    fn read_pin(&mut self) -> LefDefResult<DefPin> {
+        let mut pin = DefPin {
+            name: self.tokens.next()?.into(),
+            ..Default::default()
+        };
+        while self.tokens.eat("+") {
+            match self.tokens.next()? {
+                "NET" => pin.net = self.tokens.next()?.into(),
+                "SPECIAL" => pin.special = true,
+                "DIRECTION" => {
+                    pin.direction = match self.tokens.next()? {
+                        "INPUT" => Some(DefDirection::Input),
+                        "OUTPUT" => Some(DefDirection::Output),
+                        "INOUT" => Some(DefDirection::InOut),
+                        "FEEDTHRU" => Some(DefDirection::Feedthru),
+                        _ => None,
+                    }
+                }
+                "USE" => pin.usage = Some(self.tokens.next()?.to_string()),
+                "PORT" => pin.ports.push(DefPinPort::default()),
+                kind @ ("LAYER" | "POLYGON") => {
+                    let polygon = kind == "POLYGON";
+                    let shape = self.read_layer_shape(polygon)?;
+                    current_port(&mut pin).shapes.push(shape);
+                }
+                status @ ("PLACED" | "FIXED" | "COVER") => {
+                    let status = placement_status(status);
+                    current_port(&mut pin).placement = Some(self.read_placement(status)?);
+                }
+                _ => self.skip_option()?,
+            }
+        }
+        self.tokens.expect(";")?;
+        Ok(pin)
+    }
+
+    fn read_net(&mut self, special: bool) -> LefDefResult<DefNet> {
+        let mut net = DefNet {
+            name: self.tokens.next()?.into(),
+            ..Default::default()
+        };
+        while self.tokens.eat("(") {
+            let component = self.tokens.next()?.to_string();
+            let pin = self.tokens.next()?.into();
+            net.connections.push(if component == "PIN" {
+                DefConnection::Pin(pin)
+            } else {
+                DefConnection::Component {
+                    component: component.into(),
+                    pin,
+                }
+            });
+            while self.tokens.next()? != ")" {}
+        }
+        while self.tokens.eat("+") {
+            match self.tokens.next()? {
+                "USE" => net.usage = Some(self.tokens.next()?.to_string()),
+                status @ ("ROUTED" | "FIXED" | "COVER" | "NOSHIELD") => {
+                    let status = match status {
+                        "ROUTED" => DefWireStatus::Routed,
+                        "FIXED" => DefWireStatus::Fixed,
+                        "COVER" => DefWireStatus::Cover,
+                        _ => DefWireStatus::NoShield,
+                    };
+                    self.read_wires(status, special, &mut net.wires)?;
+                }
+                kind @ ("RECT" | "POLYGON") if special => {
+                    let polygon = kind == "POLYGON";
+                    net.shapes.push(self.read_layer_shape(polygon)?);
+                }
+                _ => self.skip_option()?,
+            }
+        }
+        self.tokens.expect(";")?;
+        Ok(net)
+    }
+
+    /// Reads one or more wires, separated by `NEW`.
+    fn read_wires(
+        &mut self,
+        status: DefWireStatus,
+        special: bool,
+        wires: &mut Vec<DefWire>,
+    ) -> LefDefResult<()> {
+        loop {
+            let mut wire = DefWire {
+                status,
+                layer: self.tokens.next()?.into(),
+                ..Default::default()
+            };
+            if special {
+                wire.width = Some(self.tokens.parse()?);
+            }
+            loop {
+                match self.tokens.peek() {
+                    Some("(") => {
+                        let (x, y, ext) = self.read_route_point(&wire.points)?;
+                        wire.points.push(DefRoutePoint::Point { x, y, ext });
+                    }
+                    Some("RECT") => {
+                        self.tokens.next()?;
+                        self.tokens.expect("(")?;
+                        let dx0 = self.tokens.parse()?;
+                        let dy0 = self.tokens.parse()?;
+                        let dx1 = self.tokens.parse()?;
+                        let dy1 = self.tokens.parse()?;
+                        self.tokens.expect(")")?;
+                        wire.points.push(DefRoutePoint::Rect { dx0, dy0, dx1, dy1 });
+                    }
+                    Some("MASK" | "STYLE" | "TAPERRULE") => {
+                        self.tokens.next()?;
+                        self.tokens.next()?;
+                    }
+                    Some("TAPER" | "VIRTUAL") => {
+                        self.tokens.next()?;
+                    }
+                    Some("+")
+                        if special
+                            && matches!(
+                                self.tokens.peek_nth(1),
+                                Some("SHAPE" | "STYLE" | "MASK")
+                            ) =>
+                    {
+                        self.tokens.next()?;
+                        if self.tokens.next()? == "SHAPE" {
+                            wire.shape = Some(self.tokens.next()?.to_string());
+                        } else {
+                            self.tokens.next()?;
+                        }
+                    }
+                    Some("NEW" | "+" | ";") | None => break,
+                    Some(_) => {
+                        let via = self.tokens.next()?.into();
+                        wire.points.push(DefRoutePoint::Via(via));
+                        if self.tokens.peek().and_then(parse_orient).is_some() {
+                            self.tokens.next()?;
+                        }
+                    }
+                }
+            }
+            wires.push(wire);
+            if !self.tokens.eat("NEW") {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Reads a point of a route, which may refer to the previous point's coordinates using `*`.
+    fn read_route_point(
+        &mut self,
+        prev: &[DefRoutePoint],
+    ) -> LefDefResult<(i64, i64, Option<i64>)> {
+        let (px, py) = prev
+            .iter()
+            .rev()
+            .find_map(|p| match *p {
+                DefRoutePoint::Point { x, y, .. } => Some((x, y)),
+                _ => None,
+            })
+            .unwrap_or_default();
+        self.tokens.expect("(")?;
+        let x = if self.tokens.eat("*") {
+            px
+        } else {
+            self.tokens.parse()?
+        };
+        let y = if self.tokens.eat("*") {
+            py
+        } else {
+            self.tokens.parse()?
+        };
+        let ext = if self.tokens.peek_is(")") {
+            None
+        } else {
+            Some(self.tokens.parse()?)
+        };
+        self.tokens.expect(")")?;
+        Ok((x, y, ext))
+    }
+
+    /// Reads a layer name, optional spacing rules, and a rectangle or polygon.
+    fn read_layer_shape(&mut self, polygon: bool) -> LefDefResult<DefLayerShape> {
+        let layer = self.tokens.next()?.into();
+        while matches!(
+            self.tokens.peek(),
+            Some("MASK" | "SPACING" | "DESIGNRULEWIDTH")
+        ) {
+            self.tokens.next()?;
+            self.tokens.next()?;
+        }
+        let shape = if polygon {
+            let mut points = Vec::new();
+            while self.tokens.peek_is("(") {
+                points.push(self.read_point()?);
+            }
+            DefShape::Polygon(points)
+        } else {
+            let p0 = self.read_point()?;
+            let p1 = self.read_point()?;
+            DefShape::Rect(p0, p1)
+        };
+        Ok(DefLayerShape { layer, shape })
+    }
+
+    fn read_placement(&mut self, status: DefPlacementStatus) -> LefDefResult<DefPlacement> {
+        let loc = self.read_point()?;
+        let token = self.tokens.next()?;
+        let orient = match parse_orient(token) {
+            Some(orient) => orient,
+            None => {
+                let msg = format!("invalid orientation `{token}`");
+                return Err(self.tokens.err(msg));
+            }
+        };
+        Ok(DefPlacement {
+            status,
+            loc,
+            orient,
+        })
+    }
+
+    fn read_point(&mut self) -> LefDefResult<DefPoint> {
+        self.tokens.expect("(")?;
+        let x = self.tokens.parse()?;
+        let y = self.tokens.parse()?;
+        self.tokens.expect(")")?;
+        Ok(DefPoint::new(x, y))
+    }
+
+    /// Skips the value of an unsupported `+` option.
+    fn skip_option(&mut self) -> LefDefResult<()> {
+        while !matches!(self.tokens.peek(), Some("+" | ";") | None) {
+            self.tokens.next()?;
+        }
+        Ok(())
+    }
+}
+
+/// Returns the last port of `pin`, creating one if the pin has no ports.
+fn current_port(pin: &mut DefPin) -> &mut DefPinPort {
+    if pin.ports.is_empty() {
+        pin.ports.push(DefPinPort::default());
+    }
+    pin.ports.last_mut().unwrap()
+}
+
+fn placement_status(status: &str) -> DefPlacementStatus {
+    match status {
+        "FIXED" => DefPlacementStatus::Fixed,
+        "COVER" => DefPlacementStatus::Cover,
+        _ => DefPlacementStatus::Placed,
+    }
+}
+
+pub(crate) fn parse_orient(s: &str) -> Option<DefOrient> {
+    Some(match s {
+        "N" => DefOrient::N,
+        "W" => DefOrient::W,
+        "S" => DefOrient::S,
+        "E" => DefOrient::E,
+        "FN" => DefOrient::FN,
+        "FW" => DefOrient::FW,
+        "FS" => DefOrient::FS,
+        "FE" => DefOrient::FE,
+        _ => return None,
+    })
+}
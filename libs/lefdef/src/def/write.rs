@@ -0,0 +1,195 @@
+//! DEF writing.
+
+use std::fmt::{Formatter, Result};
+
+use super::*;
+
+pub(crate) fn write_design(design: &DefDesign, f: &mut Formatter<'_>) -> Result {
+    writeln!(f, "VERSION {DEF_VERSION} ;")?;
+    writeln!(f, "DIVIDERCHAR \"/\" ;")?;
+    writeln!(f, "BUSBITCHARS \"[]\" ;")?;
+    writeln!(f, "DESIGN {} ;", design.name)?;
+    writeln!(f, "UNITS DISTANCE MICRONS {} ;", design.dbu_per_micron)?;
+
+    if !design.die_area.is_empty() {
+        write!(f, "\nDIEAREA")?;
+        for p in design.die_area.iter() {
+            write!(f, " {}", point(*p))?;
+        }
+        writeln!(f, " ;")?;
+    }
+
+    if !design.components.is_empty() {
+        writeln!(f, "\nCOMPONENTS {} ;", design.components.len())?;
+        for c in design.components.iter() {
+            write!(f, "- {} {}", c.name, c.macro_name)?;
+            match c.placement {
+                Some(p) => write!(f, " + {}", placement(p))?,
+                None => write!(f, " + UNPLACED")?,
+            }
+            writeln!(f, " ;")?;
+        }
+        writeln!(f, "END COMPONENTS")?;
+    }
+
+    if !design.pins.is_empty() {
+        writeln!(f, "\nPINS {} ;", design.pins.len())?;
+        for pin in design.pins.iter() {
+            write!(f, "- {} + NET {}", pin.name, pin.net)?;
+            if pin.special {
+                write!(f, " + SPECIAL")?;
+            }
+            if let Some(direction) = pin.direction {
+                let direction = match direction {
+                    DefDirection::Input => "INPUT",
+                    DefDirection::Output => "OUTPUT",
+                    DefDirection::InOut => "INOUT",
+                    DefDirection::Feedthru => "FEEDTHRU",
+                };
+                write!(f, " + DIRECTION {direction}")?;
+            }
+            if let Some(usage) = &pin.usage {
+                write!(f, " + USE {usage}")?;
+            }
+            for port in pin.ports.iter() {
+                write!(f, "\n  + PORT")?;
+                for shape in port.shapes.iter() {
+                    match &shape.shape {
+                        DefShape::Rect(p0, p1) => write!(
+                            f,
+                            "\n    + LAYER {} {} {}",
+                            shape.layer,
+                            point(*p0),
+                            point(*p1)
+                        )?,
+                        DefShape::Polygon(pts) => {
+                            write!(f, "\n    + POLYGON {}", shape.layer)?;
+                            for p in pts.iter() {
+                                write!(f, " {}", point(*p))?;
+                            }
+                        }
+                    }
+                }
+                if let Some(p) = port.placement {
+                    write!(f, "\n    + {}", placement(p))?;
+                }
+            }
+            writeln!(f, " ;")?;
+        }
+        writeln!(f, "END PINS")?;
+    }
+
+    if !design.special_nets.is_empty() {
+        writeln!(f, "\nSPECIALNETS {} ;", design.special_nets.len())?;
+        for net in design.special_nets.iter() {
+            write_net(net, f)?;
+        }
+        writeln!(f, "END SPECIALNETS")?;
+    }
+
+    if !design.nets.is_empty() {
+        writeln!(f, "\nNETS {} ;", design.nets.len())?;
+        for net in design.nets.iter() {
+            write_net(net, f)?;
+        }
+        writeln!(f, "END NETS")?;
+    }
+
+    writeln!(f, "\nEND DESIGN")
+}
+
+fn write_net(net: &DefNet, f: &mut Formatter<'_>) -> Result {
+    write!(f, "- {}", net.name)?;
+    for conn in net.connections.iter() {
+        match conn {
+            DefConnection::Component { component, pin } => write!(f, " ( {component} {pin} )")?,
+            DefConnection::Pin(pin) => write!(f, " ( PIN {pin} )")?,
+        }
+    }
+
+    // Consecutive wires with the same status are joined using `NEW`.
+    let mut prev_status = None;
+    for wire in net.wires.iter() {
+        if prev_status == Some(wire.status) {
+            write!(f, "\n    NEW {}", wire.layer)?;
+        } else {
+            let status = match wire.status {
+                DefWireStatus::Routed => "ROUTED",
+                DefWireStatus::Fixed => "FIXED",
+                DefWireStatus::Cover => "COVER",
+                DefWireStatus::NoShield => "NOSHIELD",
+            };
+            write!(f, "\n  + {status} {}", wire.layer)?;
+        }
+        prev_status = Some(wire.status);
+        if let Some(width) = wire.width {
+            write!(f, " {width}")?;
+        }
+        if let Some(shape) = &wire.shape {
+            write!(f, " + SHAPE {shape}")?;
+        }
+        for p in wire.points.iter() {
+            match p {
+                DefRoutePoint::Point { x, y, ext: None } => write!(f, " ( {x} {y} )")?,
+                DefRoutePoint::Point {
+                    x,
+                    y,
+                    ext: Some(ext),
+                } => write!(f, " ( {x} {y} {ext} )")?,
+                DefRoutePoint::Via(via) => write!(f, " {via}")?,
+                DefRoutePoint::Rect { dx0, dy0, dx1, dy1 } => {
+                    write!(f, " RECT ( {dx0} {dy0} {dx1} {dy1} )")?
+                }
+            }
+        }
+    }
+
+    for shape in net.shapes.iter() {
+        match &shape.shape {
+            DefShape::Rect(p0, p1) => write!(
+                f,
+                "\n  + RECT {} {} {}",
+                shape.layer,
+                point(*p0),
+                point(*p1)
+            )?,
+            DefShape::Polygon(pts) => {
+                write!(f, "\n  + POLYGON {}", shape.layer)?;
+                for p in pts.iter() {
+                    write!(f, " {}", point(*p))?;
+                }
+            }
+        }
+    }
+
+    if let Some(usage) = &net.usage {
+        write!(f, "\n  + USE {usage}")?;
+    }
+    writeln!(f, " ;")
+}
+
+fn point(p: DefPoint) -> String {
+    format!("( {} {} )", p.x, p.y)
+}
+
+fn placement(p: DefPlacement) -> String {
+    let status = match p.status {
+        DefPlacementStatus::Placed => "PLACED",
+        DefPlacementStatus::Fixed => "FIXED",
+        DefPlacementStatus::Cover => "COVER",
+    };
+    format!("{status} {} {}", point(p.loc), orient(p.orient))
+}
+
+fn orient(o: DefOrient) -> &'static str {
+    match o {
+        DefOrient::N => "N",
+        DefOrient::W => "W",
+        DefOrient::S => "S",
+        DefOrient::E => "E",
+        DefOrient::FN => "FN",
+        DefOrient::FW => "FW",
+        DefOrient::FS => "FS",
+        DefOrient::FE => "FE",
+    }
+}
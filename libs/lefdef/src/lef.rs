@@ -0,0 +1,405 @@
+//! The LEF data model and reader.
+
+use arcstr::ArcStr;
+use serde::{Deserialize, Serialize};
+
+use crate::lex::Tokens;
+use crate::LefDefResult;
+
+/// A LEF library, containing technology layers and macros.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LefLibrary {
+    /// The LEF version, if specified.
+    pub version: Option<String>,
+    /// The number of database units per micron, if specified.
+    pub dbu_per_micron: Option<u32>,
+    /// Technology layers, ordered from the bottom of the stack to the top.
+    pub layers: Vec<LefLayer>,
+    /// Macro definitions.
+    pub macros: Vec<LefMacro>,
+}
+
+/// A technology layer.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LefLayer {
+    pub name: ArcStr,
+    pub layer_type: Option<LefLayerType>,
+    /// The preferred routing direction.
+    pub direction: Option<LefDirection>,
+    /// The routing pitch, in microns.
+    pub pitch: Option<f64>,
+    /// The default wire width, in microns.
+    pub width: Option<f64>,
+    /// The minimum spacing, in microns.
+    pub spacing: Option<f64>,
+}
+
+/// The type of a technology layer.
+#[derive(Debug, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub enum LefLayerType {
+    Routing,
+    Cut,
+    Masterslice,
+    Overlap,
+    Implant,
+    Other(String),
+}
+
+/// A preferred routing direction.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub enum LefDirection {
+    Horizontal,
+    Vertical,
+}
+
+/// The abstract view of a placeable cell.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LefMacro {
+    pub name: ArcStr,
+    /// The macro class (e.g. `CORE` or `BLOCK`), if specified.
+    pub class: Option<String>,
+    /// The location of the macro's origin relative to the lower left corner of its
+    /// placement boundary.
+    ///
+    /// Geometry within the macro is specified relative to the origin.
+    pub origin: LefPoint,
+    /// The width and height of the placement boundary, if specified.
+    pub size: Option<(f64, f64)>,
+    pub pins: Vec<LefPin>,
+    /// Obstructions.
+    pub obs: Vec<LefLayerShapes>,
+}
+
+/// A pin of a [`LefMacro`].
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LefPin {
+    pub name: ArcStr,
+    pub direction: Option<LefPinDirection>,
+    /// The pin's usage (e.g. `SIGNAL` or `POWER`), if specified.
+    pub usage: Option<String>,
+    /// The shapes of all of the pin's ports.
+    pub shapes: Vec<LefLayerShapes>,
+}
+
+/// The direction of a [`LefPin`].
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub enum LefPinDirection {
+    Input,
+    Output,
+    InOut,
+    Feedthru,
+}
+
+/// A set of shapes on a single layer.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LefLayerShapes {
+    pub layer: ArcStr,
+    pub shapes: Vec<LefShape>,
+}
+
+/// A LEF shape.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum LefShape {
+    Rect(LefPoint, LefPoint),
+    Polygon(Vec<LefPoint>),
+}
+
+/// A location in microns.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct LefPoint {
+    pub x: f64,
+    pub y: f64,
+}
+
+impl LefPoint {
+    /// Creates a new [`LefPoint`].
+    pub const fn new(x: f64, y: f64) -> Self {
+        Self { x, y }
+    }
+}
+
+impl LefLibrary {
+    /// Parses a [`LefLibrary`] from LEF source text.
+    pub fn parse(src: &str) -> LefDefResult<Self> {
+        LefReader {
+            tokens: Tokens::new(src),
+        }
+        .read_lib()
+    }
+
+    /// Returns the layer named `name`, if it exists.
+    pub fn layer(&self, name: &str) -> Option<&LefLayer> {
+        self.layers.iter().find(|l| l.name == name)
+    }
+
+    /// Returns the macro named `name`, if it exists.
+    pub fn get_macro(&self, name: &str) -> Option<&LefMacro> {
+        self.macros.iter().find(|m| m.name == name)
+    }
+}
+
+struct LefReader {
+    tokens: Tokens,
+}
+
+impl LefReader {
+    fn read_lib(&mut self) -> LefDefResult<LefLibrary> {
+        let mut lib = LefLibrary::default();
+        while !self.tokens.is_empty() {
+            let keyword = self.tokens.next()?.to_string();
+            match keyword.as_str() {
+                "VERSION" => {
+                    lib.version = Some(self.tokens.next()?.to_string());
+                    self.tokens.skip_statement()?;
+                }
+                "UNITS" => lib.dbu_per_micron = self.read_units()?,
+                "LAYER" => lib.layers.push(self.read_layer()?),
+                "MACRO" => lib.macros.push(self.read_macro()?),
+                "END" => {
+                    self.tokens.expect("LIBRARY")?;
+                    break;
+                }
+                "VIA" | "VIARULE" | "SITE" | "NONDEFAULTRULE" | "ARRAY" => {
+                    let name = self.tokens.next()?.to_string();
+                    self.tokens.skip_block(&name)?;
+                }
+                "PROPERTYDEFINITIONS" | "SPACING" => self.tokens.skip_block(&keyword)?,
+                "BEGINEXT" => while self.tokens.next()? != "ENDEXT" {},
+                _ => self.tokens.skip_statement()?,
+            }
+        }
+        Ok(lib)
+    }
+
+    fn read_units(&mut self) -> LefDefResult<Option<u32>> {
+        let mut dbu = None;
+        loop {
+            match self.tokens.next()? {
+                "END" => {
+                    self.tokens.expect("UNITS")?;
+                    return Ok(dbu);
+                }
+                "DATABASE" => {
+                    self.tokens.expect("MICRONS")?;
+                    dbu = Some(self.tokens.parse()?);
+                    self.tokens.skip_statement()?;
+                }
+                _ => self.tokens.skip_statement()?,
+            }
+        }
+    }
+
+    fn read_layer(&mut self) -> LefDefResult<LefLayer> {
+        let name = self.tokens.next()?.to_string();
+        let mut layer = LefLayer {
+            name: name.as_str().into(),
+            ..Default::default()
+        };
+        loop {
+            let keyword = self.tokens.next()?.to_string();
+            match keyword.as_str() {
+                "END" => {
+                    self.tokens.expect(&name)?;
+                    return Ok(layer);
+                }
+                "TYPE" => {
+                    layer.layer_type = Some(match self.tokens.next()? {
+                        "ROUTING" => LefLayerType::Routing,
+                        "CUT" => LefLayerType::Cut,
+                        "MASTERSLICE" => LefLayerType::Masterslice,
+                        "OVERLAP" => LefLayerType::Overlap,
+                        "IMPLANT" => LefLayerType::Implant,
+                        other => LefLayerType::Other(other.to_string()),
+                    });
+                    self.tokens.skip_statement()?;
+                }
+                "DIRECTION" => {
+                    layer.direction = match self.tokens.next()? {
+                        "HORIZONTAL" => Some(LefDirection::Horizontal),
+                        "VERTICAL" => Some(LefDirection::Vertical),
+                        _ => None,
+                    };
+                    self.tokens.skip_statement()?;
+                }
+                // Only the first value of each rule is kept.
+                // For example, `PITCH x y` is read as `PITCH x`.
+                "PITCH" | "WIDTH" | "SPACING" => {
+                    let value: f64 = self.tokens.parse()?;
+                    let field = match keyword.as_str() {
+                        "PITCH" => &mut layer.pitch,
+                        "WIDTH" => &mut layer.width,
+                        _ => &mut layer.spacing,
+                    };
+                    field.get_or_insert(value);
+                    self.tokens.skip_statement()?;
+                }
+                _ => self.tokens.skip_statement()?,
+            }
+        }
+    }
+
+    fn read_macro(&mut self) -> LefDefResult<LefMacro> {
+        let name = self.tokens.next()?.to_string();
+        let mut mac = LefMacro {
+            name: name.as_str().into(),
+            ..Default::default()
+        };
+        loop {
+            let keyword = self.tokens.next()?.to_string();
+            match keyword.as_str() {
+                "END" => {
+                    self.tokens.expect(&name)?;
+                    return Ok(mac);
+                }
+                "CLASS" => {
+                    mac.class = Some(self.tokens.next()?.to_string());
+                    self.tokens.skip_statement()?;
+                }
+                "ORIGIN" => {
+                    mac.origin = self.read_point()?;
+                    self.tokens.expect(";")?;
+                }
+                "SIZE" => {
+                    let w = self.tokens.parse()?;
+                    self.tokens.expect("BY")?;
+                    let h = self.tokens.parse()?;
+                    mac.size = Some((w, h));
+                    self.tokens.expect(";")?;
+                }
+                "PIN" => mac.pins.push(self.read_pin()?),
+                "OBS" => mac.obs = self.read_geometries()?,
+                "BEGINEXT" => while self.tokens.next()? != "ENDEXT" {},
+                _ => self.tokens.skip_statement()?,
+            }
+        }
+    }
+
+    fn read_pin(&mut self) -> LefDefResult<LefPin> {
+        let name = self.tokens.next()?.to_string();
+        let mut pin = LefPin {
+            name: name.as_str().into(),
+            ..Default::default()
+        };
+        loop {
+            match self.tokens.next()? {
+                "END" => {
+                    self.tokens.expect(&name)?;
+                    return Ok(pin);
+                }
+                "DIRECTION" => {
+                    pin.direction = match self.tokens.next()? {
+                        "INPUT" => Some(LefPinDirection::Input),
+                        "OUTPUT" => Some(LefPinDirection::Output),
+                        "INOUT" => Some(LefPinDirection::InOut),
+                        "FEEDTHRU" => Some(LefPinDirection::Feedthru),
+                        _ => None,
+                    };
+                    self.tokens.skip_statement()?;
+                }
+                "USE" => {
+                    pin.usage = Some(self.tokens.next()?.to_string());
+                    self.tokens.skip_statement()?;
+                }
+                "PORT" => {
+                    for shapes in self.read_geometries()? {
+                        match pin.shapes.iter_mut().find(|s| s.layer == shapes.layer) {
+                            Some(existing) => existing.shapes.extend(shapes.shapes),
+                            None => pin.shapes.push(shapes),
+                        }
+                    }
+                }
+                _ => self.tokens.skip_statement()?,
+            }
+        }
+    }
+
+    /// Reads the body of a `PORT` or `OBS` section, up to and including its `END`.
+    fn read_geometries(&mut self) -> LefDefResult<Vec<LefLayerShapes>> {
+        let mut layers: Vec<LefLayerShapes> = Vec::new();
+        let mut width = 0.;
+        loop {
+            match self.tokens.next()? {
+                "END" => return Ok(layers),
+                "LAYER" => {
+                    let layer = self.tokens.next()?.into();
+                    layers.push(LefLayerShapes {
+                        layer,
+                        shapes: Vec::new(),
+                    });
+                    width = 0.;
+                    self.tokens.skip_statement()?;
+                }
+                "WIDTH" => {
+                    width = self.tokens.parse()?;
+                    self.tokens.expect(";")?;
+                }
+                kind @ ("RECT" | "POLYGON" | "PATH") => {
+                    let kind = kind.to_string();
+                    if self.tokens.eat("MASK") {
+                        self.tokens.next()?;
+                    }
+                    if self.tokens.peek_is("ITERATE") {
+                        return Err(self.tokens.err("unsupported LEF feature: ITERATE"));
+                    }
+                    let mut points = Vec::new();
+                    while !self.tokens.eat(";") {
+                        points.push(self.read_point()?);
+                    }
+                    let layer = match layers.last_mut() {
+                        Some(layer) => layer,
+                        None => return Err(self.tokens.err("geometry without a layer")),
+                    };
+                    match kind.as_str() {
+                        "RECT" if points.len() == 2 => {
+                            layer.shapes.push(LefShape::Rect(points[0], points[1]))
+                        }
+                        "POLYGON" if points.len() >= 3 => {
+                            layer.shapes.push(LefShape::Polygon(points))
+                        }
+                        "PATH" => layer.shapes.extend(path_shapes(&points, width)),
+                        _ => return Err(self.tokens.err(format!("malformed {kind}"))),
+                    }
+                }
+                _ => self.tokens.skip_statement()?,
+            }
+        }
+    }
+
+    fn read_point(&mut self) -> LefDefResult<LefPoint> {
+        let x = self.tokens.parse()?;
+        let y = self.tokens.parse()?;
+        Ok(LefPoint::new(x, y))
+    }
+}
+
+/// Converts a LEF path to one shape per segment.
+///
+/// LEF paths are extended by half their width beyond each endpoint.
+fn path_shapes(points: &[LefPoint], width: f64) -> Vec<LefShape> {
+    let half = width / 2.;
+    points
+        .windows(2)
+        .map(|seg| {
+            let (a, b) = (seg[0], seg[1]);
+            if a.x == b.x || a.y == b.y {
+                LefShape::Rect(
+                    LefPoint::new(a.x.min(b.x) - half, a.y.min(b.y) - half),
+                    LefPoint::new(a.x.max(b.x) + half, a.y.max(b.y) + half),
+                )
+            } else {
+                let len = (b.x - a.x).hypot(b.y - a.y);
+                let (dx, dy) = ((b.x - a.x) / len * half, (b.y - a.y) / len * half);
+                let (a, b) = (
+                    LefPoint::new(a.x - dx, a.y - dy),
+                    LefPoint::new(b.x + dx, b.y + dy),
+                );
+                LefShape::Polygon(vec![
+                    LefPoint::new(a.x - dy, a.y + dx),
+                    LefPoint::new(b.x - dy, b.y + dx),
+                    LefPoint::new(b.x + dy, b.y - dx),
+                    LefPoint::new(a.x + dy, a.y - dx),
+                ])
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,171 @@
+//! Tokenization shared by the LEF and DEF readers.
+//!
+//! Both formats consist of whitespace-separated tokens. Statements end with `;`,
+//! `#` starts a comment that runs to the end of the line, and strings may be quoted.
+//! The LEF/DEF specifications require `;`, `(`, and `)` to be surrounded by whitespace,
+//! but they are split into separate tokens regardless, since many tools are lenient.
+
+use std::str::FromStr;
+
+use crate::{LefDefError, LefDefResult};
+
+/// A single token, along with the line on which it appears.
+#[derive(Debug, Clone)]
+struct Token {
+    text: String,
+    line: usize,
+}
+
+/// A stream of tokens.
+pub(crate) struct Tokens {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Tokens {
+    /// Splits `src` into tokens.
+    pub(crate) fn new(src: &str) -> Self {
+        let mut tokens = Vec::new();
+        for (i, line) in src.lines().enumerate() {
+            let line_no = i + 1;
+            let mut chars = line.chars().peekable();
+            let mut curr = String::new();
+            let push = |curr: &mut String, tokens: &mut Vec<Token>| {
+                if !curr.is_empty() {
+                    tokens.push(Token {
+                        text: std::mem::take(curr),
+                        line: line_no,
+                    });
+                }
+            };
+            while let Some(c) = chars.next() {
+                match c {
+                    '#' if curr.is_empty() => break,
+                    '"' if curr.is_empty() => {
+                        for c in chars.by_ref() {
+                            if c == '"' {
+                                break;
+                            }
+                            curr.push(c);
+                        }
+                        tokens.push(Token {
+                            text: std::mem::take(&mut curr),
+                            line: line_no,
+                        });
+                    }
+                    '\\' => {
+                        curr.push(c);
+                        if let Some(c) = chars.next() {
+                            curr.push(c);
+                        }
+                    }
+                    ';' | '(' | ')' => {
+                        push(&mut curr, &mut tokens);
+                        tokens.push(Token {
+                            text: c.to_string(),
+                            line: line_no,
+                        });
+                    }
+                    c if c.is_whitespace() => push(&mut curr, &mut tokens),
+                    c => curr.push(c),
+                }
+            }
+            push(&mut curr, &mut tokens);
+        }
+        Self { tokens, pos: 0 }
+    }
+
+    /// Returns the next token without consuming it.
+    pub(crate) fn peek(&self) -> Option<&str> {
+        self.peek_nth(0)
+    }
+
+    /// Returns the token `n` positions ahead without consuming anything.
+    pub(crate) fn peek_nth(&self, n: usize) -> Option<&str> {
+        self.tokens.get(self.pos + n).map(|t| t.text.as_str())
+    }
+
+    /// Returns `true` if the next token is `s`.
+    pub(crate) fn peek_is(&self, s: &str) -> bool {
+        self.peek() == Some(s)
+    }
+
+    /// Consumes and returns the next token.
+    pub(crate) fn next(&mut self) -> LefDefResult<&str> {
+        match self.tokens.get(self.pos) {
+            Some(token) => {
+                self.pos += 1;
+                Ok(token.text.as_str())
+            }
+            None => Err(self.err("unexpected end of file")),
+        }
+    }
+
+    /// Consumes the next token, which must be `s`.
+    pub(crate) fn expect(&mut self, s: &str) -> LefDefResult<()> {
+        let token = self.next()?;
+        if token != s {
+            let msg = format!("expected `{s}`, found `{token}`");
+            self.pos -= 1;
+            return Err(self.err(msg));
+        }
+        Ok(())
+    }
+
+    /// Consumes the next token if it is `s`, returning whether or not it was consumed.
+    pub(crate) fn eat(&mut self, s: &str) -> bool {
+        if self.peek_is(s) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Consumes the next token and parses it as a `T`.
+    pub(crate) fn parse<T: FromStr>(&mut self) -> LefDefResult<T> {
+        let token = self.next()?;
+        match token.parse() {
+            Ok(val) => Ok(val),
+            Err(_) => {
+                let msg = format!("invalid number `{token}`");
+                self.pos -= 1;
+                Err(self.err(msg))
+            }
+        }
+    }
+
+    /// Returns `true` if all tokens have been consumed.
+    pub(crate) fn is_empty(&self) -> bool {
+        self.pos >= self.tokens.len()
+    }
+
+    /// Skips tokens up to and including the next `;`.
+    pub(crate) fn skip_statement(&mut self) -> LefDefResult<()> {
+        while self.next()? != ";" {}
+        Ok(())
+    }
+
+    /// Skips tokens up to and including `END name`.
+    pub(crate) fn skip_block(&mut self, name: &str) -> LefDefResult<()> {
+        loop {
+            if self.next()? == "END" && self.eat(name) {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Creates a parse error at the current position.
+    pub(crate) fn err(&self, msg: impl Into<String>) -> LefDefError {
+        let line = self
+            .tokens
+            .get(self.pos)
+            .or_else(|| self.tokens.last())
+            .map(|t| t.line)
+            .unwrap_or(0);
+        LefDefError::Parse {
+            msg: msg.into(),
+            line,
+        }
+    }
+}
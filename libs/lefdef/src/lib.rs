@@ -0,0 +1,85 @@
+//! # LEF/DEF Reader & Writer
+//!
+//! LEF (Library Exchange Format) and DEF (Design Exchange Format) are the text formats
+//! used to exchange physical design data with digital place-and-route tools.
+//! LEF describes the technology and the abstract view of each placeable cell ("macro"),
+//! while DEF describes a placed and routed design built out of those macros.
+//!
+//! This crate supports the subset of each format needed to hand blocks to and from a
+//! place-and-route flow:
+//!
+//! * [`LefLibrary`]: technology layers (type, direction, pitch, and width) and macros
+//!   (size, pins, and obstructions). LEF files are read-only.
+//! * [`DefDesign`]: die area, components, pins, and regular and special nets,
+//!   including routed wires. DEF files can be read and written.
+//!
+//! Sections and statements outside this subset are skipped when reading.
+//! LEF coordinates are kept in microns, while DEF coordinates are kept in database units.
+//!
+//! ## Usage
+//!
+//! ```
+//! use lefdef::{DefComponent, DefDesign, DefOrient, DefPlacement, DefPoint};
+//! let mut design = DefDesign::new("top", 1000);
+//! design.components.push(DefComponent {
+//!     name: "inv0".into(),
+//!     macro_name: "inv".into(),
+//!     placement: Some(DefPlacement::placed(DefPoint::new(0, 0), DefOrient::N)),
+//! });
+//!
+//! let text = design.to_string();
+//! let design2 = DefDesign::parse(&text).unwrap();
+//! assert_eq!(design, design2);
+//! ```
+
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+pub use def::*;
+pub use lef::*;
+
+mod def;
+mod lef;
+mod lex;
+
+#[cfg(test)]
+mod tests;
+
+/// A LEF/DEF result type.
+pub type LefDefResult<T> = Result<T, LefDefError>;
+
+/// A LEF/DEF reading or writing error.
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum LefDefError {
+    /// The file contents are malformed.
+    #[error("invalid LEF/DEF syntax on line {line}: {msg}")]
+    Parse { msg: String, line: usize },
+    /// An I/O error.
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+impl LefLibrary {
+    /// Loads a [`LefLibrary`] from the file at `path`.
+    pub fn load(path: impl AsRef<Path>) -> LefDefResult<Self> {
+        Self::parse(&std::fs::read_to_string(path)?)
+    }
+}
+
+impl DefDesign {
+    /// Loads a [`DefDesign`] from the file at `path`.
+    pub fn load(path: impl AsRef<Path>) -> LefDefResult<Self> {
+        Self::parse(&std::fs::read_to_string(path)?)
+    }
+
+    /// Saves the design to the file at `path`.
+    pub fn save(&self, path: impl AsRef<Path>) -> LefDefResult<()> {
+        let file = File::create(path)?;
+        let mut writer = BufWriter::new(file);
+        write!(writer, "{self}")?;
+        writer.flush()?;
+        Ok(())
+    }
+}
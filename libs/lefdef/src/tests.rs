@@ -0,0 +1,234 @@
+use crate::*;
+
+const LEF: &str = r#"
+VERSION 5.8 ;
+BUSBITCHARS "[]" ;
+UNITS
+  DATABASE MICRONS 1000 ;
+END UNITS
+
+LAYER met1
+  TYPE ROUTING ;
+  DIRECTION HORIZONTAL ;
+  PITCH 0.34 ;
+  WIDTH 0.14 ;
+  SPACING 0.14 ;
+  SPACING 0.28 RANGE 3 100 ;
+END met1
+
+LAYER via
+  TYPE CUT ;
+  WIDTH 0.15 ;
+END via
+
+VIA M1M2_PR DEFAULT
+  LAYER met1 ;
+    RECT -0.13 -0.115 0.13 0.115 ;
+END M1M2_PR
+
+MACRO inv
+  CLASS CORE ;
+  ORIGIN 0 0.1 ;
+  SIZE 1.38 BY 2.72 ;
+  SYMMETRY X Y R90 ;
+  PIN A
+    DIRECTION INPUT ;
+    USE SIGNAL ;
+    PORT
+      LAYER li1 ;
+        RECT 0.1 0.2 0.3 0.4 ;
+    END
+    PORT
+      LAYER li1 ;
+        POLYGON 0 0 1 0 1 1 ;
+    END
+  END A
+  PIN Y
+    DIRECTION OUTPUT ;
+    PORT
+      LAYER met1 ;
+        WIDTH 0.2 ;
+        PATH 0 0 1 0 ;
+    END
+  END Y
+  OBS
+    LAYER li1 ;
+      RECT 0 0 1.38 2.72 ; # full obstruction
+  END
+END inv
+
+END LIBRARY
+"#;
+
+const DEF: &str = r#"
+VERSION 5.8 ;
+DIVIDERCHAR "/" ;
+BUSBITCHARS "[]" ;
+DESIGN top ;
+UNITS DISTANCE MICRONS 1000 ;
+DIEAREA ( 0 0 ) ( 10000 8000 ) ;
+ROW ROW_0 unithd 0 0 N DO 10 BY 1 STEP 460 0 ;
+TRACKS X 230 DO 20 STEP 460 LAYER met1 ;
+VIAS 1 ;
+- via_1 + VIARULE M1M2 ;
+END VIAS
+COMPONENTS 2 ;
+- inv0 inv + PLACED ( 100 200 ) N ;
+- inv1 inv + SOURCE NETLIST + FIXED ( 2000 200 ) FS ;
+END COMPONENTS
+PINS 1 ;
+- din + NET din + DIRECTION INPUT + USE SIGNAL
+  + PORT
+    + LAYER met2 ( -70 -70 ) ( 70 70 )
+    + FIXED ( 500 0 ) N ;
+END PINS
+SPECIALNETS 1 ;
+- vdd ( * VPWR )
+  + ROUTED met1 480 + SHAPE STRIPE ( 0 2720 ) ( 10000 * )
+  + RECT met2 ( 0 0 ) ( 100 100 )
+  + USE POWER ;
+END SPECIALNETS
+NETS 1 ;
+- din ( PIN din ) ( inv0 A )
+  + ROUTED met1 ( 500 0 ) ( 500 1000 ) M1M2_PR
+    NEW met2 ( 500 1000 ) ( 1500 * 0 ) ;
+END NETS
+END DESIGN
+"#;
+
+#[test]
+fn parse_lef() {
+    let lib = LefLibrary::parse(LEF).unwrap();
+    assert_eq!(lib.version.as_deref(), Some("5.8"));
+    assert_eq!(lib.dbu_per_micron, Some(1000));
+    assert_eq!(lib.layers.len(), 2);
+
+    let met1 = lib.layer("met1").unwrap();
+    assert_eq!(met1.layer_type, Some(LefLayerType::Routing));
+    assert_eq!(met1.direction, Some(LefDirection::Horizontal));
+    assert_eq!(met1.pitch, Some(0.34));
+    assert_eq!(met1.width, Some(0.14));
+    assert_eq!(met1.spacing, Some(0.14));
+    assert_eq!(
+        lib.layer("via").unwrap().layer_type,
+        Some(LefLayerType::Cut)
+    );
+
+    let inv = lib.get_macro("inv").unwrap();
+    assert_eq!(inv.class.as_deref(), Some("CORE"));
+    assert_eq!(inv.origin, LefPoint::new(0., 0.1));
+    assert_eq!(inv.size, Some((1.38, 2.72)));
+    assert_eq!(inv.pins.len(), 2);
+
+    let a = &inv.pins[0];
+    assert_eq!(a.name, "A");
+    assert_eq!(a.direction, Some(LefPinDirection::Input));
+    assert_eq!(a.usage.as_deref(), Some("SIGNAL"));
+    assert_eq!(a.shapes.len(), 1);
+    assert_eq!(a.shapes[0].layer, "li1");
+    assert_eq!(a.shapes[0].shapes.len(), 2);
+
+    let y = &inv.pins[1];
+    assert_eq!(
+        y.shapes[0].shapes,
+        vec![LefShape::Rect(
+            LefPoint::new(-0.1, -0.1),
+            LefPoint::new(1.1, 0.1)
+        )]
+    );
+
+    assert_eq!(inv.obs.len(), 1);
+    assert_eq!(
+        inv.obs[0].shapes,
+        vec![LefShape::Rect(
+            LefPoint::new(0., 0.),
+            LefPoint::new(1.38, 2.72)
+        )]
+    );
+}
+
+#[test]
+fn parse_def() {
+    let design = DefDesign::parse(DEF).unwrap();
+    assert_eq!(design.name, "top");
+    assert_eq!(design.dbu_per_micron, 1000);
+    assert_eq!(
+        design.die_area,
+        vec![DefPoint::new(0, 0), DefPoint::new(10000, 8000)]
+    );
+
+    assert_eq!(design.components.len(), 2);
+    assert_eq!(
+        design.components[1],
+        DefComponent {
+            name: "inv1".into(),
+            macro_name: "inv".into(),
+            placement: Some(DefPlacement::fixed(DefPoint::new(2000, 200), DefOrient::FS)),
+        }
+    );
+
+    let pin = &design.pins[0];
+    assert_eq!(pin.direction, Some(DefDirection::Input));
+    assert_eq!(pin.ports.len(), 1);
+    assert_eq!(
+        pin.ports[0].shapes,
+        vec![DefLayerShape {
+            layer: "met2".into(),
+            shape: DefShape::Rect(DefPoint::new(-70, -70), DefPoint::new(70, 70)),
+        }]
+    );
+    assert_eq!(
+        pin.ports[0].placement,
+        Some(DefPlacement::fixed(DefPoint::new(500, 0), DefOrient::N))
+    );
+
+    let vdd = &design.special_nets[0];
+    assert_eq!(vdd.usage.as_deref(), Some("POWER"));
+    assert_eq!(vdd.wires.len(), 1);
+    assert_eq!(vdd.wires[0].shape.as_deref(), Some("STRIPE"));
+    assert_eq!(
+        vdd.wires[0].rects(None),
+        vec![(DefPoint::new(0, 2480), DefPoint::new(10000, 2960))]
+    );
+    assert_eq!(vdd.shapes.len(), 1);
+
+    let din = &design.nets[0];
+    assert_eq!(
+        din.connections,
+        vec![
+            DefConnection::Pin("din".into()),
+            DefConnection::Component {
+                component: "inv0".into(),
+                pin: "A".into()
+            }
+        ]
+    );
+    assert_eq!(din.wires.len(), 2);
+    assert_eq!(din.wires[0].points[2], DefRoutePoint::Via("M1M2_PR".into()));
+    assert_eq!(
+        din.wires[0].rects(Some(140)),
+        vec![(DefPoint::new(430, -70), DefPoint::new(570, 1070))]
+    );
+    assert_eq!(
+        din.wires[1].rects(Some(140)),
+        vec![(DefPoint::new(430, 930), DefPoint::new(1500, 1070))]
+    );
+}
+
+#[test]
+fn def_round_trip() {
+    let design = DefDesign::parse(DEF).unwrap();
+    let text = design.to_string();
+    let design2 = DefDesign::parse(&text).unwrap();
+    assert_eq!(design, design2);
+}
+
+#[test]
+fn parse_errors() {
+    let err = DefDesign::parse("DESIGN top ;\nCOMPONENTS 1 ;\n- inv0 inv + PLACED ( 0 0 ) Q ;")
+        .unwrap_err();
+    assert!(matches!(err, LefDefError::Parse { line: 3, .. }));
+
+    let err = LefLibrary::parse("MACRO inv\n  SIZE 1 BY ;\nEND inv").unwrap_err();
+    assert!(matches!(err, LefDefError::Parse { line: 2, .. }));
+}
@@ -0,0 +1,315 @@
+//! # Magic Layout Reader & Writer
+//!
+//! Magic stores each cell in its own plain-text `.mag` file. A cell consists of:
+//!
+//! * Paint: Manhattan rectangles, grouped into one section per Magic layer (tile type).
+//! * Uses: placements of other cells, each with an integer transform.
+//!   A used cell is stored in the `.mag` file with the same name as the cell.
+//! * Labels: text attached to a rectangle on a layer. Labels followed by a `port`
+//!   line define the ports of the cell.
+//! * Properties: string key-value pairs.
+//!
+//! Coordinates are integers in Magic's internal units, whose size is set by the
+//! technology's scale factor and the cell's [`magscale`](MagCell::magscale).
+//! Triangular (non-Manhattan) tiles, arrayed uses, and `parameters` lines are read,
+//! but their contents are not interpreted beyond what is stored in the data model.
+//!
+//! ## Usage
+//!
+//! ```
+//! use magic::{MagCell, MagLabel, MagRect};
+//! let mut cell = MagCell::new("sky130A");
+//! cell.paint("metal1", MagRect::new(0, 0, 100, 200));
+//! cell.labels.push(MagLabel::port("metal1", MagRect::new(0, 0, 100, 20), "a", 1));
+//!
+//! let text = cell.to_string();
+//! let cell2 = MagCell::parse(&text).unwrap();
+//! assert_eq!(cell, cell2);
+//! ```
+
+use std::fmt::{Display, Formatter};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use arcstr::ArcStr;
+use serde::{Deserialize, Serialize};
+
+mod read;
+mod write;
+
+#[cfg(test)]
+mod tests;
+
+/// The file extension of Magic cells.
+pub const MAG_EXTENSION: &str = "mag";
+
+/// A Magic result type.
+pub type MagResult<T> = Result<T, MagError>;
+
+/// A Magic reading or writing error.
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum MagError {
+    /// The file contents are malformed.
+    #[error("invalid Magic syntax on line {line}: {msg}")]
+    Parse { msg: String, line: usize },
+    /// An I/O error.
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+/// A Magic cell, as stored in a single `.mag` file.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct MagCell {
+    /// The name of the technology the cell is drawn in.
+    pub tech: ArcStr,
+    /// The ratio of the technology's lambda to the cell's internal unit, as `(numerator, denominator)`.
+    ///
+    /// Magic writes `magscale 1 2` for technologies drawn on a half-lambda grid, such as sky130.
+    pub magscale: (i64, i64),
+    pub timestamp: i64,
+    /// Paint sections, in the order in which they are written.
+    pub paint: Vec<MagPaint>,
+    pub uses: Vec<MagUse>,
+    pub labels: Vec<MagLabel>,
+    pub properties: Vec<MagProperty>,
+}
+
+/// The rectangles painted on a single Magic layer.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct MagPaint {
+    pub layer: ArcStr,
+    pub rects: Vec<MagRect>,
+}
+
+/// A rectangle in Magic internal units.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub struct MagRect {
+    pub x0: i64,
+    pub y0: i64,
+    pub x1: i64,
+    pub y1: i64,
+}
+
+/// A placement of another cell.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct MagUse {
+    /// The name of the used cell.
+    pub cell: ArcStr,
+    /// The instance name.
+    pub name: ArcStr,
+    pub transform: MagTransform,
+    /// The bounding box of the used cell, in the used cell's coordinates.
+    pub bbox: MagRect,
+    pub array: Option<MagArray>,
+}
+
+/// An integer transform from a used cell's coordinates to its parent's coordinates.
+///
+/// Maps `(x, y)` to `(a * x + b * y + c, d * x + e * y + f)`.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub struct MagTransform {
+    pub a: i64,
+    pub b: i64,
+    pub c: i64,
+    pub d: i64,
+    pub e: i64,
+    pub f: i64,
+}
+
+/// The array parameters of an arrayed use.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub struct MagArray {
+    pub xlo: i64,
+    pub xhi: i64,
+    pub xsep: i64,
+    pub ylo: i64,
+    pub yhi: i64,
+    pub ysep: i64,
+}
+
+/// A label attached to a rectangle on a layer.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct MagLabel {
+    pub layer: ArcStr,
+    /// Whether the label is sticky, i.e. stays attached to `layer` even where it is not painted.
+    pub sticky: bool,
+    pub rect: MagRect,
+    /// The position of the text relative to the rectangle, from 0 (center) to 8 (southwest).
+    pub position: u8,
+    /// The font with which the label is rendered.
+    ///
+    /// Labels with a font are written as `flabel`s; others are written as `rlabel`s.
+    pub font: Option<MagFont>,
+    pub text: ArcStr,
+    /// Port information, if the label defines a port.
+    pub port: Option<MagPort>,
+}
+
+/// The rendering information of an `flabel`.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct MagFont {
+    pub name: ArcStr,
+    pub size: i64,
+    pub rotation: i64,
+    pub xoffset: i64,
+    pub yoffset: i64,
+}
+
+/// Port information attached to a label.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct MagPort {
+    /// The port's index, which orders ports in extracted netlists.
+    pub index: u32,
+    /// The sides of the rectangle on which the port may be connected, e.g. `nsew`.
+    pub sides: ArcStr,
+    /// Any remaining words, such as the port's use and direction.
+    pub extra: Vec<ArcStr>,
+}
+
+/// A string property.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct MagProperty {
+    pub key: ArcStr,
+    pub value: ArcStr,
+}
+
+impl MagCell {
+    /// Creates a new, empty [`MagCell`] in technology `tech`.
+    pub fn new(tech: impl Into<ArcStr>) -> Self {
+        Self {
+            tech: tech.into(),
+            magscale: (1, 1),
+            timestamp: 0,
+            paint: Vec::new(),
+            uses: Vec::new(),
+            labels: Vec::new(),
+            properties: Vec::new(),
+        }
+    }
+
+    /// Paints `rect` on `layer`, adding a paint section for `layer` if needed.
+    pub fn paint(&mut self, layer: impl Into<ArcStr>, rect: MagRect) {
+        let layer = layer.into();
+        match self.paint.iter_mut().find(|p| p.layer == layer) {
+            Some(paint) => paint.rects.push(rect),
+            None => self.paint.push(MagPaint {
+                layer,
+                rects: vec![rect],
+            }),
+        }
+    }
+
+    /// Returns the value of property `key`, if it exists.
+    pub fn property(&self, key: &str) -> Option<&ArcStr> {
+        self.properties
+            .iter()
+            .find(|p| p.key == key)
+            .map(|p| &p.value)
+    }
+
+    /// Parses a [`MagCell`] from the contents of a `.mag` file.
+    pub fn parse(src: &str) -> MagResult<Self> {
+        read::parse_cell(src)
+    }
+
+    /// Loads a [`MagCell`] from the file at `path`.
+    pub fn load(path: impl AsRef<Path>) -> MagResult<Self> {
+        Self::parse(&std::fs::read_to_string(path)?)
+    }
+
+    /// Saves the cell to the file at `path`.
+    pub fn save(&self, path: impl AsRef<Path>) -> MagResult<()> {
+        let file = File::create(path)?;
+        let mut writer = BufWriter::new(file);
+        write!(writer, "{self}")?;
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+impl Display for MagCell {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write::write_cell(self, f)
+    }
+}
+
+impl MagRect {
+    /// Creates a new [`MagRect`] from its lower left and upper right corners.
+    pub const fn new(x0: i64, y0: i64, x1: i64, y1: i64) -> Self {
+        Self { x0, y0, x1, y1 }
+    }
+}
+
+impl MagTransform {
+    /// Returns the identity transform.
+    pub const fn identity() -> Self {
+        Self::new(1, 0, 0, 0, 1, 0)
+    }
+
+    /// Creates a new [`MagTransform`].
+    pub const fn new(a: i64, b: i64, c: i64, d: i64, e: i64, f: i64) -> Self {
+        Self { a, b, c, d, e, f }
+    }
+
+    /// Applies the transform to the point `(x, y)`.
+    pub const fn apply(&self, x: i64, y: i64) -> (i64, i64) {
+        (
+            self.a * x + self.b * y + self.c,
+            self.d * x + self.e * y + self.f,
+        )
+    }
+}
+
+impl Default for MagTransform {
+    fn default() -> Self {
+        Self::identity()
+    }
+}
+
+impl MagLabel {
+    /// Creates a new `rlabel` with text `text` at `rect` on `layer`.
+    pub fn new(layer: impl Into<ArcStr>, rect: MagRect, text: impl Into<ArcStr>) -> Self {
+        Self {
+            layer: layer.into(),
+            sticky: false,
+            rect,
+            position: 0,
+            font: None,
+            text: text.into(),
+            port: None,
+        }
+    }
+
+    /// Creates a new sticky port label with index `index`, connectable from all sides.
+    pub fn port(
+        layer: impl Into<ArcStr>,
+        rect: MagRect,
+        text: impl Into<ArcStr>,
+        index: u32,
+    ) -> Self {
+        Self {
+            sticky: true,
+            font: Some(MagFont::default()),
+            port: Some(MagPort {
+                index,
+                sides: arcstr::literal!("nsew"),
+                extra: Vec::new(),
+            }),
+            ..Self::new(layer, rect, text)
+        }
+    }
+}
+
+impl Default for MagFont {
+    fn default() -> Self {
+        Self {
+            name: arcstr::literal!("FreeSans"),
+            size: 80,
+            rotation: 0,
+            xoffset: 0,
+            yoffset: 0,
+        }
+    }
+}
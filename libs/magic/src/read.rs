@@ -0,0 +1,226 @@
+//! Magic reading.
+//!
+//! `.mag` files are line-oriented: each line is a keyword followed by whitespace-separated words.
+//! Lines of the form `<< name >>` start a new section.
+
+use std::str::FromStr;
+
+use crate::*;
+
+/// The section of a `.mag` file currently being read.
+enum Section {
+    Header,
+    Paint(usize),
+    Labels,
+    Properties,
+    End,
+}
+
+/// A single line of a `.mag` file, split into words.
+struct Line<'a> {
+    words: Vec<&'a str>,
+    line: usize,
+}
+
+impl<'a> Line<'a> {
+    fn err(&self, msg: impl Into<String>) -> MagError {
+        MagError::Parse {
+            msg: msg.into(),
+            line: self.line,
+        }
+    }
+
+    /// Returns the word at index `idx`.
+    fn word(&self, idx: usize) -> MagResult<&'a str> {
+        self.words
+            .get(idx)
+            .copied()
+            .ok_or_else(|| self.err(format!("expected at least {} words", idx + 1)))
+    }
+
+    /// Parses the word at index `idx`.
+    fn parse<T: FromStr>(&self, idx: usize) -> MagResult<T> {
+        let word = self.word(idx)?;
+        word.parse()
+            .map_err(|_| self.err(format!("invalid value `{word}`")))
+    }
+
+    /// Parses a rectangle starting at word `idx`.
+    fn rect(&self, idx: usize) -> MagResult<MagRect> {
+        Ok(MagRect::new(
+            self.parse(idx)?,
+            self.parse(idx + 1)?,
+            self.parse(idx + 2)?,
+            self.parse(idx + 3)?,
+        ))
+    }
+}
+
+pub(crate) fn parse_cell(src: &str) -> MagResult<MagCell> {
+    let mut lines = src
+        .lines()
+        .enumerate()
+        .map(|(i, text)| Line {
+            words: text.split_whitespace().collect(),
+            line: i + 1,
+        })
+        .filter(|line| !line.words.is_empty());
+
+    match lines.next() {
+        Some(line) if line.words == ["magic"] => {}
+        Some(line) => return Err(line.err("expected `magic` header")),
+        None => {
+            return Err(MagError::Parse {
+                msg: "empty file".to_string(),
+                line: 1,
+            })
+        }
+    }
+
+    let mut cell = MagCell::new("");
+    let mut section = Section::Header;
+    // Whether the lines being read belong to the last use.
+    let mut in_use = false;
+    for line in lines {
+        let keyword = line.words[0];
+        if keyword == "<<" {
+            if line.words.len() != 3 || line.words[2] != ">>" {
+                return Err(line.err("malformed section header"));
+            }
+            in_use = false;
+            section = match line.words[1] {
+                "labels" => Section::Labels,
+                "properties" => Section::Properties,
+                "end" => Section::End,
+                layer => {
+                    cell.paint.push(MagPaint {
+                        layer: layer.into(),
+                        rects: Vec::new(),
+                    });
+                    Section::Paint(cell.paint.len() - 1)
+                }
+            };
+            continue;
+        }
+
+        // Uses may appear in any section, and own the `timestamp`, `transform`,
+        // `box`, `array`, and `parameters` lines that follow them.
+        match keyword {
+            "use" => {
+                cell.uses.push(MagUse {
+                    cell: line.word(1)?.into(),
+                    name: line.word(2)?.into(),
+                    transform: MagTransform::identity(),
+                    bbox: MagRect::default(),
+                    array: None,
+                });
+                in_use = true;
+                continue;
+            }
+            "timestamp" | "transform" | "box" | "array" | "parameters" if in_use => {
+                let u = cell.uses.last_mut().unwrap();
+                match keyword {
+                    "transform" => {
+                        u.transform = MagTransform::new(
+                            line.parse(1)?,
+                            line.parse(2)?,
+                            line.parse(3)?,
+                            line.parse(4)?,
+                            line.parse(5)?,
+                            line.parse(6)?,
+                        )
+                    }
+                    "box" => u.bbox = line.rect(1)?,
+                    "array" => {
+                        u.array = Some(MagArray {
+                            xlo: line.parse(1)?,
+                            xhi: line.parse(2)?,
+                            xsep: line.parse(3)?,
+                            ylo: line.parse(4)?,
+                            yhi: line.parse(5)?,
+                            ysep: line.parse(6)?,
+                        })
+                    }
+                    _ => {}
+                }
+                continue;
+            }
+            _ => {}
+        }
+
+        match section {
+            Section::Header => match keyword {
+                "tech" => cell.tech = line.word(1)?.into(),
+                "magscale" => cell.magscale = (line.parse(1)?, line.parse(2)?),
+                "timestamp" => cell.timestamp = line.parse(1)?,
+                _ => {}
+            },
+            Section::Paint(idx) => match keyword {
+                "rect" => cell.paint[idx].rects.push(line.rect(1)?),
+                // Triangles are not supported.
+                "tri" => {}
+                _ => return Err(line.err(format!("unexpected `{keyword}` in paint section"))),
+            },
+            Section::Labels => match keyword {
+                "rlabel" | "flabel" => cell.labels.push(read_label(&line)?),
+                "port" => {
+                    let label = cell
+                        .labels
+                        .last_mut()
+                        .ok_or_else(|| line.err("`port` must follow a label"))?;
+                    label.port = Some(MagPort {
+                        index: line.parse(1)?,
+                        sides: line.word(2)?.into(),
+                        extra: line.words[3..].iter().map(|&w| w.into()).collect(),
+                    });
+                }
+                _ => return Err(line.err(format!("unexpected `{keyword}` in labels section"))),
+            },
+            Section::Properties => match keyword {
+                "string" => cell.properties.push(MagProperty {
+                    key: line.word(1)?.into(),
+                    value: line.words[2..].join(" ").into(),
+                }),
+                _ => return Err(line.err(format!("unexpected `{keyword}` in properties section"))),
+            },
+            Section::End => return Err(line.err("unexpected content after `<< end >>`")),
+        }
+    }
+    Ok(cell)
+}
+
+/// Reads an `rlabel` or `flabel` line.
+///
+/// ```text
+/// rlabel layer [s] x0 y0 x1 y1 position text
+/// flabel layer [s] x0 y0 x1 y1 position font size rotation xoffset yoffset text
+/// ```
+fn read_label(line: &Line<'_>) -> MagResult<MagLabel> {
+    let layer = line.word(1)?;
+    let sticky = line.word(2)? == "s";
+    let i = if sticky { 3 } else { 2 };
+    let rect = line.rect(i)?;
+    let position = line.parse(i + 4)?;
+    let (font, text_idx) = if line.words[0] == "flabel" {
+        let font = MagFont {
+            name: line.word(i + 5)?.into(),
+            size: line.parse(i + 6)?,
+            rotation: line.parse(i + 7)?,
+            xoffset: line.parse(i + 8)?,
+            yoffset: line.parse(i + 9)?,
+        };
+        (Some(font), i + 10)
+    } else {
+        (None, i + 5)
+    };
+    line.word(text_idx)?;
+    Ok(MagLabel {
+        layer: layer.into(),
+        sticky,
+        rect,
+        position,
+        font,
+        text: line.words[text_idx..].join(" ").into(),
+        port: None,
+    })
+}
@@ -0,0 +1,108 @@
+use crate::*;
+
+const MAG: &str = r#"magic
+tech sky130A
+magscale 1 2
+timestamp 1656000000
+<< checkpaint >>
+rect -1260 -1260 1536 1804
+<< metal1 >>
+rect 0 0 276 40
+rect 100 40 140 200
+<< metal2 >>
+rect 0 100 276 140
+use sky130_fd_sc_hd__inv_1  inv_0
+timestamp 1656000000
+transform -1 0 552 0 1 0
+box 0 0 276 544
+use sky130_fd_sc_hd__inv_1 inv_arr
+array 0 3 276 0 0 544
+timestamp 1656000000
+transform 1 0 0 0 1 544
+box 0 0 276 544
+<< labels >>
+flabel metal1 s 0 0 40 40 0 FreeSans 400 0 0 0 A
+port 1 nsew signal input
+rlabel metal2 0 100 20 140 3 net 1
+<< properties >>
+string FIXED_BBOX 0 0 552 544
+<< end >>
+"#;
+
+#[test]
+fn parse_mag() {
+    let cell = MagCell::parse(MAG).unwrap();
+    assert_eq!(cell.tech, "sky130A");
+    assert_eq!(cell.magscale, (1, 2));
+    assert_eq!(cell.timestamp, 1656000000);
+
+    assert_eq!(cell.paint.len(), 3);
+    assert_eq!(cell.paint[0].layer, "checkpaint");
+    assert_eq!(
+        cell.paint[1].rects,
+        vec![MagRect::new(0, 0, 276, 40), MagRect::new(100, 40, 140, 200)]
+    );
+
+    assert_eq!(cell.uses.len(), 2);
+    let inv = &cell.uses[0];
+    assert_eq!(inv.cell, "sky130_fd_sc_hd__inv_1");
+    assert_eq!(inv.name, "inv_0");
+    assert_eq!(inv.transform, MagTransform::new(-1, 0, 552, 0, 1, 0));
+    assert_eq!(inv.transform.apply(276, 544), (276, 544));
+    assert_eq!(inv.bbox, MagRect::new(0, 0, 276, 544));
+    assert_eq!(inv.array, None);
+    assert_eq!(
+        cell.uses[1].array,
+        Some(MagArray {
+            xlo: 0,
+            xhi: 3,
+            xsep: 276,
+            ylo: 0,
+            yhi: 0,
+            ysep: 544,
+        })
+    );
+
+    assert_eq!(cell.labels.len(), 2);
+    let a = &cell.labels[0];
+    assert_eq!(a.layer, "metal1");
+    assert!(a.sticky);
+    assert_eq!(a.rect, MagRect::new(0, 0, 40, 40));
+    assert_eq!(a.font.as_ref().unwrap().size, 400);
+    assert_eq!(a.text, "A");
+    let port = a.port.as_ref().unwrap();
+    assert_eq!(port.index, 1);
+    assert_eq!(port.sides, "nsew");
+    assert_eq!(port.extra, vec!["signal", "input"]);
+
+    let net = &cell.labels[1];
+    assert!(!net.sticky);
+    assert_eq!(net.position, 3);
+    assert_eq!(net.text, "net 1");
+    assert_eq!(net.port, None);
+
+    assert_eq!(
+        cell.property("FIXED_BBOX").map(|v| v.as_str()),
+        Some("0 0 552 544")
+    );
+}
+
+#[test]
+fn mag_round_trip() {
+    let cell = MagCell::parse(MAG).unwrap();
+    let text = cell.to_string();
+    let cell2 = MagCell::parse(&text).unwrap();
+    assert_eq!(cell, cell2);
+}
+
+#[test]
+fn parse_errors() {
+    let err = MagCell::parse("tech sky130A\n").unwrap_err();
+    assert!(matches!(err, MagError::Parse { line: 1, .. }));
+
+    let err = MagCell::parse("magic\ntech sky130A\n<< metal1 >>\nrect 0 0 1\n").unwrap_err();
+    assert!(matches!(err, MagError::Parse { line: 4, .. }));
+
+    let err = MagCell::parse("magic\n<< labels >>\nport 1 nsew\n").unwrap_err();
+    assert!(matches!(err, MagError::Parse { line: 3, .. }));
+}
@@ -0,0 +1,84 @@
+//! Magic writing.
+
+use std::fmt::{Formatter, Result};
+
+use crate::*;
+
+pub(crate) fn write_cell(cell: &MagCell, f: &mut Formatter<'_>) -> Result {
+    writeln!(f, "magic")?;
+    writeln!(f, "tech {}", cell.tech)?;
+    if cell.magscale != (1, 1) {
+        writeln!(f, "magscale {} {}", cell.magscale.0, cell.magscale.1)?;
+    }
+    writeln!(f, "timestamp {}", cell.timestamp)?;
+
+    for paint in cell.paint.iter() {
+        writeln!(f, "<< {} >>", paint.layer)?;
+        for r in paint.rects.iter() {
+            writeln!(f, "rect {}", rect(r))?;
+        }
+    }
+
+    for u in cell.uses.iter() {
+        writeln!(f, "use {} {}", u.cell, u.name)?;
+        if let Some(a) = &u.array {
+            writeln!(
+                f,
+                "array {} {} {} {} {} {}",
+                a.xlo, a.xhi, a.xsep, a.ylo, a.yhi, a.ysep
+            )?;
+        }
+        writeln!(f, "timestamp 0")?;
+        let t = &u.transform;
+        writeln!(
+            f,
+            "transform {} {} {} {} {} {}",
+            t.a, t.b, t.c, t.d, t.e, t.f
+        )?;
+        writeln!(f, "box {}", rect(&u.bbox))?;
+    }
+
+    if !cell.labels.is_empty() {
+        writeln!(f, "<< labels >>")?;
+        for label in cell.labels.iter() {
+            let kind = if label.font.is_some() {
+                "flabel"
+            } else {
+                "rlabel"
+            };
+            write!(f, "{kind} {}", label.layer)?;
+            if label.sticky {
+                write!(f, " s")?;
+            }
+            write!(f, " {} {}", rect(&label.rect), label.position)?;
+            if let Some(font) = &label.font {
+                write!(
+                    f,
+                    " {} {} {} {} {}",
+                    font.name, font.size, font.rotation, font.xoffset, font.yoffset
+                )?;
+            }
+            writeln!(f, " {}", label.text)?;
+            if let Some(port) = &label.port {
+                write!(f, "port {} {}", port.index, port.sides)?;
+                for word in port.extra.iter() {
+                    write!(f, " {word}")?;
+                }
+                writeln!(f)?;
+            }
+        }
+    }
+
+    if !cell.properties.is_empty() {
+        writeln!(f, "<< properties >>")?;
+        for prop in cell.properties.iter() {
+            writeln!(f, "string {} {}", prop.key, prop.value)?;
+        }
+    }
+
+    writeln!(f, "<< end >>")
+}
+
+fn rect(r: &MagRect) -> String {
+    format!("{} {} {} {}", r.x0, r.y0, r.x1, r.y1)
+}
@@ -0,0 +1,501 @@
+//! # OASIS Layout Reader & Writer
+//!
+//! OASIS (Open Artwork System Interchange Standard, SEMI P39) is a compact binary
+//! successor to GDSII. This crate reads and writes the subset of OASIS used for
+//! exchanging hierarchical mask layout, storing data on OASIS's terms in much the
+//! same way as [`gds21`](../gds21/index.html) does for GDSII.
+//!
+//! Layout data is represented as a short tree:
+//!
+//! * An [`OasisLibrary`], which holds the database unit and a set of cells.
+//! * [`OasisCell`]s, each of which holds a list of [`OasisElement`]s.
+//! * [`OasisElement`]s: rectangles, polygons, paths, text, and placements of other cells.
+//!   Every element may carry an [`OasisRepetition`], which replicates it at a set of offsets,
+//!   and a list of [`OasisProperty`]s.
+//!
+//! On disk, OASIS relies heavily on modal variables, name tables, repetitions, and
+//! `DEFLATE`-compressed `CBLOCK` records to save space. The reader resolves all of these,
+//! so the in-memory tree is fully explicit. The writer emits `CELLNAME` and `PROPNAME`
+//! tables, omits fields that match their modal variables, combines copies of an element
+//! that differ only in their position into a single repeated element, and
+//! compresses the elements of each cell into a `CBLOCK` (see [`OasisWriteOpts`]).
+//!
+//! Trapezoids are read as [`OasisPolygon`]s. Circles, compressed trapezoids, and
+//! extension records (`XGEOMETRY`) are not supported. Layer names, and properties
+//! of anything other than an element, are read and discarded.
+//!
+//! ## Usage
+//!
+//! ```
+//! use oasis::{OasisCell, OasisLibrary, OasisRectangle};
+//! let mut lib = OasisLibrary::new(1000.0);
+//! let mut cell = OasisCell::new("mycell");
+//! cell.elems.push(OasisRectangle::new(66, 20, 0, 0, 100, 200).into());
+//! lib.cells.push(cell);
+//!
+//! let mut bytes = Vec::new();
+//! lib.write(&mut bytes).unwrap();
+//! let lib2 = OasisLibrary::read(&mut bytes.as_slice()).unwrap();
+//! assert_eq!(lib, lib2);
+//! ```
+
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+use arcstr::ArcStr;
+use serde::{Deserialize, Serialize};
+
+mod read;
+mod write;
+
+#[cfg(test)]
+mod tests;
+
+/// The magic bytes at the start of every OASIS file.
+pub const MAGIC: &[u8] = b"%SEMI-OASIS\r\n";
+
+/// The OASIS version written and accepted by this crate.
+pub const VERSION: &str = "1.0";
+
+/// An OASIS result type.
+pub type OasisResult<T> = Result<T, OasisError>;
+
+/// An OASIS reading or writing error.
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum OasisError {
+    /// The file does not begin with the OASIS magic bytes.
+    #[error("missing OASIS magic bytes")]
+    BadMagic,
+    /// The file uses an unsupported OASIS version.
+    #[error("unsupported OASIS version {0:?}")]
+    Version(String),
+    /// An unknown record ID was encountered.
+    #[error("invalid record ID {id} at byte {pos}")]
+    InvalidRecord { id: u64, pos: u64 },
+    /// A valid but unsupported OASIS feature was encountered.
+    #[error("unsupported OASIS feature: {0}")]
+    Unsupported(String),
+    /// The file contents are malformed.
+    #[error("invalid OASIS data at byte {pos}: {msg}")]
+    Parse { msg: String, pos: u64 },
+    /// The library cannot be represented in OASIS.
+    #[error("cannot write OASIS: {0}")]
+    Write(String),
+    /// An I/O error.
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+/// Options for writing an [`OasisLibrary`].
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub struct OasisWriteOpts {
+    /// Whether to `DEFLATE`-compress the elements of each cell into a `CBLOCK`.
+    ///
+    /// Defaults to `true`.
+    pub cblocks: bool,
+}
+
+impl Default for OasisWriteOpts {
+    fn default() -> Self {
+        Self { cblocks: true }
+    }
+}
+
+/// A location in database units.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub struct OasisPoint {
+    pub x: i64,
+    pub y: i64,
+}
+
+impl OasisPoint {
+    /// Creates a new [`OasisPoint`].
+    pub const fn new(x: i64, y: i64) -> Self {
+        Self { x, y }
+    }
+
+    /// Returns the point at the origin.
+    pub const fn zero() -> Self {
+        Self { x: 0, y: 0 }
+    }
+}
+
+impl std::ops::Add for OasisPoint {
+    type Output = Self;
+    fn add(self, rhs: Self) -> Self {
+        Self::new(self.x + rhs.x, self.y + rhs.y)
+    }
+}
+
+impl std::ops::Sub for OasisPoint {
+    type Output = Self;
+    fn sub(self, rhs: Self) -> Self {
+        Self::new(self.x - rhs.x, self.y - rhs.y)
+    }
+}
+
+/// An OASIS repetition, which replicates an element at a set of offsets.
+///
+/// Offsets are relative to the position of the repeated element,
+/// and always include the element's own position.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub enum OasisRepetition {
+    /// An `nx` by `ny` grid with horizontal pitch `dx` and vertical pitch `dy`.
+    Matrix { nx: u64, ny: u64, dx: i64, dy: i64 },
+    /// An `n` by `m` grid along arbitrary displacement vectors `a` and `b`.
+    ///
+    /// Lattices whose vectors lie along the axes are read as [`OasisRepetition::Matrix`].
+    Lattice {
+        n: u64,
+        m: u64,
+        a: OasisPoint,
+        b: OasisPoint,
+    },
+    /// An explicit list of offsets.
+    ///
+    /// The first offset must be the origin.
+    Explicit(Vec<OasisPoint>),
+}
+
+impl OasisRepetition {
+    /// Creates an `n` by `m` lattice along vectors `a` and `b`.
+    ///
+    /// Returns a [`OasisRepetition::Matrix`] if the vectors lie along the x and y axes, respectively.
+    pub fn lattice(n: u64, m: u64, a: OasisPoint, b: OasisPoint) -> Self {
+        if a.y == 0 && b.x == 0 {
+            Self::Matrix {
+                nx: n,
+                ny: m,
+                dx: a.x,
+                dy: b.y,
+            }
+        } else {
+            Self::Lattice { n, m, a, b }
+        }
+    }
+
+    /// The number of copies produced by the repetition.
+    pub fn len(&self) -> usize {
+        match self {
+            Self::Matrix { nx, ny, .. } => (nx * ny) as usize,
+            Self::Lattice { n, m, .. } => (n * m) as usize,
+            Self::Explicit(offsets) => offsets.len(),
+        }
+    }
+
+    /// Returns `true` if the repetition produces no copies.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the offset of every copy produced by the repetition.
+    ///
+    /// Grid repetitions are enumerated with the first (x or `n`) index varying fastest.
+    pub fn offsets(&self) -> Vec<OasisPoint> {
+        match *self {
+            Self::Matrix { nx, ny, dx, dy } => (0..ny as i64)
+                .flat_map(|j| (0..nx as i64).map(move |i| OasisPoint::new(i * dx, j * dy)))
+                .collect(),
+            Self::Lattice { n, m, a, b } => (0..m as i64)
+                .flat_map(|j| {
+                    (0..n as i64)
+                        .map(move |i| OasisPoint::new(i * a.x + j * b.x, i * a.y + j * b.y))
+                })
+                .collect(),
+            Self::Explicit(ref offsets) => offsets.clone(),
+        }
+    }
+}
+
+/// A named property attached to an [`OasisElement`].
+///
+/// Property names are written to the `PROPNAME` table.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OasisProperty {
+    pub name: ArcStr,
+    pub values: Vec<OasisPropValue>,
+}
+
+impl OasisProperty {
+    /// Creates a new [`OasisProperty`].
+    pub fn new(name: impl Into<ArcStr>, values: Vec<OasisPropValue>) -> Self {
+        Self {
+            name: name.into(),
+            values,
+        }
+    }
+}
+
+/// A value of an [`OasisProperty`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum OasisPropValue {
+    Unsigned(u64),
+    Signed(i64),
+    Real(f64),
+    /// A string, which is read lossily if it is not valid UTF-8.
+    String(ArcStr),
+}
+
+/// A rectangle with its lower-left corner at (`x`, `y`).
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OasisRectangle {
+    pub layer: u64,
+    pub datatype: u64,
+    pub x: i64,
+    pub y: i64,
+    pub width: u64,
+    pub height: u64,
+    pub repetition: Option<OasisRepetition>,
+    pub properties: Vec<OasisProperty>,
+}
+
+impl OasisRectangle {
+    /// Creates a new non-repeated [`OasisRectangle`].
+    pub fn new(layer: u64, datatype: u64, x: i64, y: i64, width: u64, height: u64) -> Self {
+        Self {
+            layer,
+            datatype,
+            x,
+            y,
+            width,
+            height,
+            repetition: None,
+            properties: Vec::new(),
+        }
+    }
+}
+
+/// A polygon, described by its vertices.
+///
+/// The closing edge from the last vertex back to the first is implicit.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OasisPolygon {
+    pub layer: u64,
+    pub datatype: u64,
+    pub points: Vec<OasisPoint>,
+    pub repetition: Option<OasisRepetition>,
+    pub properties: Vec<OasisProperty>,
+}
+
+/// The treatment of one end of an [`OasisPath`].
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub enum OasisPathExtension {
+    /// The path ends flush with its final point.
+    #[default]
+    Flush,
+    /// The path extends by its half-width beyond its final point.
+    HalfWidth,
+    /// The path extends by the given distance beyond its final point.
+    Explicit(i64),
+}
+
+/// A path of constant width.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OasisPath {
+    pub layer: u64,
+    pub datatype: u64,
+    /// Half of the path's width.
+    pub half_width: u64,
+    pub start_extension: OasisPathExtension,
+    pub end_extension: OasisPathExtension,
+    pub points: Vec<OasisPoint>,
+    pub repetition: Option<OasisRepetition>,
+    pub properties: Vec<OasisProperty>,
+}
+
+/// A text label.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OasisText {
+    pub string: ArcStr,
+    pub layer: u64,
+    pub texttype: u64,
+    pub x: i64,
+    pub y: i64,
+    pub repetition: Option<OasisRepetition>,
+    pub properties: Vec<OasisProperty>,
+}
+
+/// A placement (instance) of another cell.
+///
+/// The placed cell is first reflected about the x-axis if `flip` is set,
+/// then rotated counter-clockwise by `angle` degrees, scaled by `magnification`,
+/// and finally translated to (`x`, `y`).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OasisPlacement {
+    pub cell: ArcStr,
+    pub x: i64,
+    pub y: i64,
+    pub flip: bool,
+    pub angle: f64,
+    pub magnification: f64,
+    pub repetition: Option<OasisRepetition>,
+    pub properties: Vec<OasisProperty>,
+}
+
+impl OasisPlacement {
+    /// Creates a new unrotated, non-repeated [`OasisPlacement`].
+    pub fn new(cell: impl Into<ArcStr>, x: i64, y: i64) -> Self {
+        Self {
+            cell: cell.into(),
+            x,
+            y,
+            flip: false,
+            angle: 0.0,
+            magnification: 1.0,
+            repetition: None,
+            properties: Vec::new(),
+        }
+    }
+}
+
+/// An element of an [`OasisCell`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum OasisElement {
+    Rectangle(OasisRectangle),
+    Polygon(OasisPolygon),
+    Path(OasisPath),
+    Text(OasisText),
+    Placement(OasisPlacement),
+}
+
+impl OasisElement {
+    /// The element's repetition, if any.
+    pub fn repetition(&self) -> Option<&OasisRepetition> {
+        match self {
+            Self::Rectangle(x) => x.repetition.as_ref(),
+            Self::Polygon(x) => x.repetition.as_ref(),
+            Self::Path(x) => x.repetition.as_ref(),
+            Self::Text(x) => x.repetition.as_ref(),
+            Self::Placement(x) => x.repetition.as_ref(),
+        }
+    }
+
+    /// The element's properties.
+    pub fn properties(&self) -> &[OasisProperty] {
+        match self {
+            Self::Rectangle(x) => &x.properties,
+            Self::Polygon(x) => &x.properties,
+            Self::Path(x) => &x.properties,
+            Self::Text(x) => &x.properties,
+            Self::Placement(x) => &x.properties,
+        }
+    }
+
+    /// A mutable reference to the element's properties.
+    pub fn properties_mut(&mut self) -> &mut Vec<OasisProperty> {
+        match self {
+            Self::Rectangle(x) => &mut x.properties,
+            Self::Polygon(x) => &mut x.properties,
+            Self::Path(x) => &mut x.properties,
+            Self::Text(x) => &mut x.properties,
+            Self::Placement(x) => &mut x.properties,
+        }
+    }
+}
+
+impl From<OasisRectangle> for OasisElement {
+    fn from(value: OasisRectangle) -> Self {
+        Self::Rectangle(value)
+    }
+}
+impl From<OasisPolygon> for OasisElement {
+    fn from(value: OasisPolygon) -> Self {
+        Self::Polygon(value)
+    }
+}
+impl From<OasisPath> for OasisElement {
+    fn from(value: OasisPath) -> Self {
+        Self::Path(value)
+    }
+}
+impl From<OasisText> for OasisElement {
+    fn from(value: OasisText) -> Self {
+        Self::Text(value)
+    }
+}
+impl From<OasisPlacement> for OasisElement {
+    fn from(value: OasisPlacement) -> Self {
+        Self::Placement(value)
+    }
+}
+
+/// An OASIS cell definition.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OasisCell {
+    pub name: ArcStr,
+    pub elems: Vec<OasisElement>,
+}
+
+impl OasisCell {
+    /// Creates a new, empty [`OasisCell`].
+    pub fn new(name: impl Into<ArcStr>) -> Self {
+        Self {
+            name: name.into(),
+            elems: Vec::new(),
+        }
+    }
+}
+
+/// An OASIS library: the contents of a single OASIS file.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OasisLibrary {
+    /// The number of database units per micron.
+    pub unit: f64,
+    pub cells: Vec<OasisCell>,
+}
+
+impl OasisLibrary {
+    /// Creates a new, empty [`OasisLibrary`] with `unit` database units per micron.
+    pub fn new(unit: f64) -> Self {
+        Self {
+            unit,
+            cells: Vec::new(),
+        }
+    }
+
+    /// The size of a database unit, in meters.
+    pub fn db_unit(&self) -> f64 {
+        1e-6 / self.unit
+    }
+
+    /// Returns the cell named `name`, if it exists.
+    pub fn cell(&self, name: &str) -> Option<&OasisCell> {
+        self.cells.iter().find(|c| c.name == name)
+    }
+
+    /// Reads an [`OasisLibrary`] from a byte stream.
+    pub fn read(reader: &mut impl Read) -> OasisResult<Self> {
+        read::OasisReader::new(reader).read_lib()
+    }
+
+    /// Loads an [`OasisLibrary`] from the file at `path`.
+    pub fn load(path: impl AsRef<Path>) -> OasisResult<Self> {
+        let file = File::open(path)?;
+        Self::read(&mut BufReader::new(file))
+    }
+
+    /// Writes the library to a byte stream using the default [`OasisWriteOpts`].
+    pub fn write(&self, writer: &mut impl Write) -> OasisResult<()> {
+        self.write_with_opts(writer, OasisWriteOpts::default())
+    }
+
+    /// Writes the library to a byte stream using the given options.
+    pub fn write_with_opts(
+        &self,
+        writer: &mut impl Write,
+        opts: OasisWriteOpts,
+    ) -> OasisResult<()> {
+        write::OasisWriter::new(writer, opts).write_lib(self)
+    }
+
+    /// Saves the library to the file at `path`.
+    pub fn save(&self, path: impl AsRef<Path>) -> OasisResult<()> {
+        let file = File::create(path)?;
+        let mut writer = BufWriter::new(file);
+        self.write(&mut writer)?;
+        writer.flush()?;
+        Ok(())
+    }
+}
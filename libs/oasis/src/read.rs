@@ -0,0 +1,1107 @@
+//! OASIS reading.
+//!
+//! Records are read sequentially, updating the modal variables defined by the spec.
+//! Name references (cell names, text strings, property names, and property strings)
+//! may be defined anywhere in the file, so they are resolved once the `END` record is reached.
+//! `PROPERTY` records are attached to the element that precedes them.
+//!
+//! The records inside a `CBLOCK` are decompressed in full when the `CBLOCK` is reached,
+//! and are then read as if they had appeared in place of it.
+
+use std::collections::HashMap;
+use std::io::{Cursor, Read};
+
+use arcstr::ArcStr;
+use flate2::read::DeflateDecoder;
+
+use crate::{
+    OasisCell, OasisElement, OasisError, OasisLibrary, OasisPath, OasisPathExtension,
+    OasisPlacement, OasisPoint, OasisPolygon, OasisPropValue, OasisProperty, OasisRectangle,
+    OasisRepetition, OasisResult, OasisText, MAGIC, VERSION,
+};
+
+/// A reference to a name, either inline or via a name table.
+#[derive(Debug, Clone)]
+enum NameRef {
+    Name(ArcStr),
+    Ref(u64),
+}
+
+/// A property value, which may reference the `PROPSTRING` table.
+#[derive(Debug, Clone)]
+enum PropValueRef {
+    Value(OasisPropValue),
+    Ref(u64),
+}
+
+/// A name table, such as the `CELLNAME` or `TEXTSTRING` table.
+#[derive(Debug, Default)]
+struct NameTable {
+    names: HashMap<u64, ArcStr>,
+    next_implicit: u64,
+}
+
+impl NameTable {
+    fn insert_implicit(&mut self, name: ArcStr) {
+        self.names.insert(self.next_implicit, name);
+        self.next_implicit += 1;
+    }
+
+    fn insert(&mut self, refnum: u64, name: ArcStr) {
+        self.names.insert(refnum, name);
+    }
+
+    fn resolve(&self, name: &NameRef, kind: &str) -> OasisResult<ArcStr> {
+        match name {
+            NameRef::Name(name) => Ok(name.clone()),
+            NameRef::Ref(refnum) => {
+                self.names
+                    .get(refnum)
+                    .cloned()
+                    .ok_or_else(|| OasisError::Parse {
+                        msg: format!("undefined {kind} reference number {refnum}"),
+                        pos: 0,
+                    })
+            }
+        }
+    }
+}
+
+/// The OASIS modal variables.
+///
+/// All of these are reset at the start of each cell.
+#[derive(Debug, Default)]
+struct Modal {
+    repetition: Option<OasisRepetition>,
+    placement_x: i64,
+    placement_y: i64,
+    placement_cell: Option<NameRef>,
+    layer: Option<u64>,
+    datatype: Option<u64>,
+    textlayer: Option<u64>,
+    texttype: Option<u64>,
+    text_x: i64,
+    text_y: i64,
+    text_string: Option<NameRef>,
+    geometry_x: i64,
+    geometry_y: i64,
+    xy_relative: bool,
+    geometry_w: Option<u64>,
+    geometry_h: Option<u64>,
+    polygon_points: Option<Vec<OasisPoint>>,
+    path_half_width: Option<u64>,
+    path_points: Option<Vec<OasisPoint>>,
+    path_start_extension: Option<OasisPathExtension>,
+    path_end_extension: Option<OasisPathExtension>,
+    last_property_name: Option<NameRef>,
+    last_value_list: Option<Vec<PropValueRef>>,
+}
+
+/// A name that must be filled in once all name tables have been read.
+#[derive(Debug)]
+enum Fixup {
+    CellName {
+        cell: usize,
+        name: NameRef,
+    },
+    Placement {
+        cell: usize,
+        elem: usize,
+        name: NameRef,
+    },
+    Text {
+        cell: usize,
+        elem: usize,
+        name: NameRef,
+    },
+    Property {
+        cell: usize,
+        elem: usize,
+        name: NameRef,
+        values: Vec<PropValueRef>,
+    },
+}
+
+/// An OASIS reader.
+pub(crate) struct OasisReader<'a, R: Read> {
+    reader: &'a mut R,
+    /// The number of bytes read from `reader` so far.
+    pos: u64,
+    /// The decompressed contents of the `CBLOCK` being read, if any.
+    block: Option<Cursor<Vec<u8>>>,
+    /// Whether the table offsets are stored in the `END` record rather than the `START` record.
+    offsets_in_end: bool,
+    modal: Modal,
+    cellnames: NameTable,
+    textstrings: NameTable,
+    propnames: NameTable,
+    propstrings: NameTable,
+    cells: Vec<OasisCell>,
+    /// The index of the element in the last cell that subsequent properties apply to.
+    ///
+    /// Properties of files, cells, and names are discarded.
+    property_target: Option<usize>,
+    fixups: Vec<Fixup>,
+}
+
+impl<'a, R: Read> OasisReader<'a, R> {
+    /// Creates a new [`OasisReader`] reading from `reader`.
+    pub(crate) fn new(reader: &'a mut R) -> Self {
+        Self {
+            reader,
+            pos: 0,
+            block: None,
+            offsets_in_end: false,
+            modal: Modal::default(),
+            cellnames: NameTable::default(),
+            textstrings: NameTable::default(),
+            propnames: NameTable::default(),
+            propstrings: NameTable::default(),
+            cells: Vec::new(),
+            property_target: None,
+            fixups: Vec::new(),
+        }
+    }
+
+    /// Reads an entire [`OasisLibrary`].
+    pub(crate) fn read_lib(mut self) -> OasisResult<OasisLibrary> {
+        let mut magic = [0u8; MAGIC.len()];
+        self.read_bytes(&mut magic)?;
+        if magic != MAGIC {
+            return Err(OasisError::BadMagic);
+        }
+
+        let unit = self.read_start()?;
+        let offsets_in_end = self.offsets_in_end;
+
+        loop {
+            let pos = self.pos;
+            let id = self.read_uint()?;
+            if (3..=12).contains(&id) {
+                self.property_target = None;
+            }
+            match id {
+                // PAD
+                0 => {}
+                1 => return self.fail("unexpected second START record"),
+                2 => {
+                    if offsets_in_end {
+                        self.read_table_offsets()?;
+                    }
+                    self.read_string()?;
+                    match self.read_uint()? {
+                        0 => {}
+                        1 | 2 => {
+                            let mut signature = [0u8; 4];
+                            self.read_bytes(&mut signature)?;
+                        }
+                        scheme => return self.fail(format!("invalid validation scheme {scheme}")),
+                    }
+                    break;
+                }
+                3 => {
+                    let name = self.read_name()?;
+                    self.cellnames.insert_implicit(name);
+                }
+                4 => {
+                    let name = self.read_name()?;
+                    let refnum = self.read_uint()?;
+                    self.cellnames.insert(refnum, name);
+                }
+                5 => {
+                    let name = self.read_name()?;
+                    self.textstrings.insert_implicit(name);
+                }
+                6 => {
+                    let name = self.read_name()?;
+                    let refnum = self.read_uint()?;
+                    self.textstrings.insert(refnum, name);
+                }
+                7 => {
+                    let name = self.read_name()?;
+                    self.propnames.insert_implicit(name);
+                }
+                8 => {
+                    let name = self.read_name()?;
+                    let refnum = self.read_uint()?;
+                    self.propnames.insert(refnum, name);
+                }
+                9 => {
+                    let string = self.read_prop_string()?;
+                    self.propstrings.insert_implicit(string);
+                }
+                10 => {
+                    let string = self.read_prop_string()?;
+                    let refnum = self.read_uint()?;
+                    self.propstrings.insert(refnum, string);
+                }
+                // LAYERNAME
+                11 | 12 => {
+                    self.read_string()?;
+                    self.read_interval()?;
+                    self.read_interval()?;
+                }
+                13 => {
+                    let refnum = self.read_uint()?;
+                    self.begin_cell(NameRef::Ref(refnum));
+                }
+                14 => {
+                    let name = self.read_name()?;
+                    self.begin_cell(NameRef::Name(name));
+                }
+                15 => self.modal.xy_relative = false,
+                16 => self.modal.xy_relative = true,
+                17 | 18 => self.read_placement(id == 18)?,
+                19 => self.read_text()?,
+                20 => self.read_rectangle()?,
+                21 => self.read_polygon()?,
+                22 => self.read_path()?,
+                23..=25 => self.read_trapezoid(id)?,
+                26 => return Err(OasisError::Unsupported("CTRAPEZOID records".to_string())),
+                27 => return Err(OasisError::Unsupported("CIRCLE records".to_string())),
+                28 => self.read_property()?,
+                29 => {
+                    let name = self.modal.last_property_name.clone();
+                    let values = self.modal.last_value_list.clone();
+                    match (name, values) {
+                        (Some(name), Some(values)) => self.add_property(name, values),
+                        _ => return self.fail("undefined modal variable last-property-name"),
+                    }
+                }
+                // XNAME
+                30 => {
+                    self.read_uint()?;
+                    self.read_string()?;
+                }
+                31 => {
+                    self.read_uint()?;
+                    self.read_string()?;
+                    self.read_uint()?;
+                }
+                // XELEMENT
+                32 => {
+                    self.read_uint()?;
+                    self.read_string()?;
+                }
+                33 => return Err(OasisError::Unsupported("XGEOMETRY records".to_string())),
+                34 => self.read_cblock()?,
+                id => return Err(OasisError::InvalidRecord { id, pos }),
+            }
+        }
+
+        self.resolve_names()?;
+        Ok(OasisLibrary {
+            unit,
+            cells: self.cells,
+        })
+    }
+
+    /// Reads the `START` record, returning the number of database units per micron.
+    fn read_start(&mut self) -> OasisResult<f64> {
+        if self.read_uint()? != 1 {
+            return self.fail("expected START record");
+        }
+        let version = self.read_string()?;
+        if version != VERSION.as_bytes() {
+            return Err(OasisError::Version(
+                String::from_utf8_lossy(&version).into_owned(),
+            ));
+        }
+        let unit = self.read_real()?;
+        if unit.is_nan() || unit <= 0.0 {
+            return self.fail(format!("invalid database unit {unit}"));
+        }
+        self.offsets_in_end = self.read_uint()? != 0;
+        if !self.offsets_in_end {
+            self.read_table_offsets()?;
+        }
+        Ok(unit)
+    }
+
+    /// Reads a `CBLOCK` record, so that subsequent records are read from its contents.
+    fn read_cblock(&mut self) -> OasisResult<()> {
+        if self.block.is_some() {
+            return self.fail("CBLOCK records must not be nested");
+        }
+        let scheme = self.read_uint()?;
+        if scheme != 0 {
+            return Err(OasisError::Unsupported(format!(
+                "CBLOCK compression type {scheme}"
+            )));
+        }
+        let uncompressed_len = self.read_uint()? as usize;
+        let compressed_len = self.read_uint()? as usize;
+        let mut compressed = vec![0u8; compressed_len];
+        self.read_bytes(&mut compressed)?;
+
+        let mut data = Vec::with_capacity(uncompressed_len);
+        if DeflateDecoder::new(compressed.as_slice())
+            .read_to_end(&mut data)
+            .is_err()
+        {
+            return self.fail("invalid DEFLATE data in CBLOCK");
+        }
+        if data.len() != uncompressed_len {
+            return self.fail(format!(
+                "CBLOCK decompressed to {} bytes, expected {uncompressed_len}",
+                data.len()
+            ));
+        }
+        if !data.is_empty() {
+            self.block = Some(Cursor::new(data));
+        }
+        Ok(())
+    }
+
+    /// Reads and discards the six table-offset pairs.
+    fn read_table_offsets(&mut self) -> OasisResult<()> {
+        for _ in 0..12 {
+            self.read_uint()?;
+        }
+        Ok(())
+    }
+
+    /// Reads and discards a `LAYERNAME` interval.
+    fn read_interval(&mut self) -> OasisResult<()> {
+        match self.read_uint()? {
+            0 => {}
+            1..=3 => {
+                self.read_uint()?;
+            }
+            4 => {
+                self.read_uint()?;
+                self.read_uint()?;
+            }
+            t => return self.fail(format!("invalid interval type {t}")),
+        }
+        Ok(())
+    }
+
+    /// Reads a `PROPERTY` record, attaching it to the preceding element.
+    fn read_property(&mut self) -> OasisResult<()> {
+        // Info byte: UUUUVCNS
+        let info = self.read_byte()?;
+        let name = if info & 0x04 != 0 {
+            let name = if info & 0x02 != 0 {
+                NameRef::Ref(self.read_uint()?)
+            } else {
+                NameRef::Name(self.read_name()?)
+            };
+            self.modal.last_property_name = Some(name.clone());
+            name
+        } else {
+            match self.modal.last_property_name.clone() {
+                Some(name) => name,
+                None => return self.fail("undefined modal variable last-property-name"),
+            }
+        };
+        let values = if info & 0x08 == 0 {
+            let count = match info >> 4 {
+                15 => self.read_uint()?,
+                count => u64::from(count),
+            };
+            let mut values = Vec::new();
+            for _ in 0..count {
+                values.push(match self.read_uint()? {
+                    t @ 0..=7 => PropValueRef::Value(OasisPropValue::Real(self.read_real_body(t)?)),
+                    8 => PropValueRef::Value(OasisPropValue::Unsigned(self.read_uint()?)),
+                    9 => PropValueRef::Value(OasisPropValue::Signed(self.read_sint()?)),
+                    10..=12 => {
+                        PropValueRef::Value(OasisPropValue::String(self.read_prop_string()?))
+                    }
+                    13..=15 => PropValueRef::Ref(self.read_uint()?),
+                    t => return self.fail(format!("invalid property value type {t}")),
+                });
+            }
+            self.modal.last_value_list = Some(values.clone());
+            values
+        } else {
+            match self.modal.last_value_list.clone() {
+                Some(values) => values,
+                None => return self.fail("undefined modal variable last-value-list"),
+            }
+        };
+        self.add_property(name, values);
+        Ok(())
+    }
+
+    /// Attaches a property to the preceding element, if any.
+    fn add_property(&mut self, name: NameRef, values: Vec<PropValueRef>) {
+        if let Some(elem) = self.property_target {
+            self.fixups.push(Fixup::Property {
+                cell: self.cells.len() - 1,
+                elem,
+                name,
+                values,
+            });
+        }
+    }
+
+    /// Starts a new cell, resetting all modal variables.
+    fn begin_cell(&mut self, name: NameRef) {
+        self.fixups.push(Fixup::CellName {
+            cell: self.cells.len(),
+            name,
+        });
+        self.cells.push(OasisCell::default());
+        self.modal = Modal::default();
+        self.property_target = None;
+    }
+
+    /// Adds an element to the current cell, returning its index.
+    fn push_elem(&mut self, elem: impl Into<OasisElement>) -> OasisResult<usize> {
+        let cell = match self.cells.last_mut() {
+            Some(cell) => cell,
+            None => return self.fail("element outside of a cell"),
+        };
+        cell.elems.push(elem.into());
+        let elem = cell.elems.len() - 1;
+        self.property_target = Some(elem);
+        Ok(elem)
+    }
+
+    fn read_placement(&mut self, explicit_transform: bool) -> OasisResult<()> {
+        // Info byte: CNXYRAAF or CNXYRMAF
+        let info = self.read_byte()?;
+        let name = if info & 0x80 != 0 {
+            let name = if info & 0x40 != 0 {
+                NameRef::Ref(self.read_uint()?)
+            } else {
+                NameRef::Name(self.read_name()?)
+            };
+            self.modal.placement_cell = Some(name.clone());
+            name
+        } else {
+            match self.modal.placement_cell.clone() {
+                Some(name) => name,
+                None => return self.fail("undefined modal variable placement-cell"),
+            }
+        };
+        let (magnification, angle) = if explicit_transform {
+            let magnification = if info & 0x04 != 0 {
+                self.read_real()?
+            } else {
+                1.0
+            };
+            let angle = if info & 0x02 != 0 {
+                self.read_real()?
+            } else {
+                0.0
+            };
+            (magnification, angle)
+        } else {
+            (1.0, f64::from((info >> 1) & 0b11) * 90.0)
+        };
+        let x = self.read_coord(info & 0x20 != 0, |m| &mut m.placement_x)?;
+        let y = self.read_coord(info & 0x10 != 0, |m| &mut m.placement_y)?;
+        let repetition = self.read_opt_repetition(info & 0x08 != 0)?;
+        let elem = self.push_elem(OasisPlacement {
+            cell: ArcStr::new(),
+            x,
+            y,
+            flip: info & 0x01 != 0,
+            angle,
+            magnification,
+            repetition,
+            properties: Vec::new(),
+        })?;
+        self.fixups.push(Fixup::Placement {
+            cell: self.cells.len() - 1,
+            elem,
+            name,
+        });
+        Ok(())
+    }
+
+    fn read_text(&mut self) -> OasisResult<()> {
+        // Info byte: 0CNXYRTL
+        let info = self.read_byte()?;
+        let name = if info & 0x40 != 0 {
+            let name = if info & 0x20 != 0 {
+                NameRef::Ref(self.read_uint()?)
+            } else {
+                NameRef::Name(self.read_name()?)
+            };
+            self.modal.text_string = Some(name.clone());
+            name
+        } else {
+            match self.modal.text_string.clone() {
+                Some(name) => name,
+                None => return self.fail("undefined modal variable text-string"),
+            }
+        };
+        let layer = self.read_modal_uint(info & 0x01 != 0, "textlayer", |m| &mut m.textlayer)?;
+        let texttype = self.read_modal_uint(info & 0x02 != 0, "texttype", |m| &mut m.texttype)?;
+        let x = self.read_coord(info & 0x10 != 0, |m| &mut m.text_x)?;
+        let y = self.read_coord(info & 0x08 != 0, |m| &mut m.text_y)?;
+        let repetition = self.read_opt_repetition(info & 0x04 != 0)?;
+        let elem = self.push_elem(OasisText {
+            string: ArcStr::new(),
+            layer,
+            texttype,
+            x,
+            y,
+            repetition,
+            properties: Vec::new(),
+        })?;
+        self.fixups.push(Fixup::Text {
+            cell: self.cells.len() - 1,
+            elem,
+            name,
+        });
+        Ok(())
+    }
+
+    fn read_rectangle(&mut self) -> OasisResult<()> {
+        // Info byte: SWHXYRDL
+        let info = self.read_byte()?;
+        let square = info & 0x80 != 0;
+        let layer = self.read_modal_uint(info & 0x01 != 0, "layer", |m| &mut m.layer)?;
+        let datatype = self.read_modal_uint(info & 0x02 != 0, "datatype", |m| &mut m.datatype)?;
+        let width = self.read_modal_uint(info & 0x40 != 0, "geometry-w", |m| &mut m.geometry_w)?;
+        let height = if square {
+            if info & 0x20 != 0 {
+                return self.fail("square rectangles must not specify a height");
+            }
+            self.modal.geometry_h = Some(width);
+            width
+        } else {
+            self.read_modal_uint(info & 0x20 != 0, "geometry-h", |m| &mut m.geometry_h)?
+        };
+        let x = self.read_coord(info & 0x10 != 0, |m| &mut m.geometry_x)?;
+        let y = self.read_coord(info & 0x08 != 0, |m| &mut m.geometry_y)?;
+        let repetition = self.read_opt_repetition(info & 0x04 != 0)?;
+        self.push_elem(OasisRectangle {
+            layer,
+            datatype,
+            x,
+            y,
+            width,
+            height,
+            repetition,
+            properties: Vec::new(),
+        })?;
+        Ok(())
+    }
+
+    fn read_polygon(&mut self) -> OasisResult<()> {
+        // Info byte: 00PXYRDL
+        let info = self.read_byte()?;
+        let layer = self.read_modal_uint(info & 0x01 != 0, "layer", |m| &mut m.layer)?;
+        let datatype = self.read_modal_uint(info & 0x02 != 0, "datatype", |m| &mut m.datatype)?;
+        let points = if info & 0x20 != 0 {
+            let points = self.read_point_list(true)?;
+            self.modal.polygon_points = Some(points.clone());
+            points
+        } else {
+            match self.modal.polygon_points.clone() {
+                Some(points) => points,
+                None => return self.fail("undefined modal variable polygon-point-list"),
+            }
+        };
+        let x = self.read_coord(info & 0x10 != 0, |m| &mut m.geometry_x)?;
+        let y = self.read_coord(info & 0x08 != 0, |m| &mut m.geometry_y)?;
+        let repetition = self.read_opt_repetition(info & 0x04 != 0)?;
+        let origin = OasisPoint::new(x, y);
+        self.push_elem(OasisPolygon {
+            layer,
+            datatype,
+            points: points.into_iter().map(|p| p + origin).collect(),
+            repetition,
+            properties: Vec::new(),
+        })?;
+        Ok(())
+    }
+
+    fn read_path(&mut self) -> OasisResult<()> {
+        // Info byte: EWPXYRDL
+        let info = self.read_byte()?;
+        let layer = self.read_modal_uint(info & 0x01 != 0, "layer", |m| &mut m.layer)?;
+        let datatype = self.read_modal_uint(info & 0x02 != 0, "datatype", |m| &mut m.datatype)?;
+        let half_width = self.read_modal_uint(info & 0x40 != 0, "path-halfwidth", |m| {
+            &mut m.path_half_width
+        })?;
+        if info & 0x80 != 0 {
+            // Extension scheme: 0000SSEE
+            let scheme = self.read_uint()?;
+            if let Some(ext) = self.read_extension((scheme >> 2) & 0b11)? {
+                self.modal.path_start_extension = Some(ext);
+            }
+            if let Some(ext) = self.read_extension(scheme & 0b11)? {
+                self.modal.path_end_extension = Some(ext);
+            }
+        }
+        let points = if info & 0x20 != 0 {
+            let points = self.read_point_list(false)?;
+            self.modal.path_points = Some(points.clone());
+            points
+        } else {
+            match self.modal.path_points.clone() {
+                Some(points) => points,
+                None => return self.fail("undefined modal variable path-point-list"),
+            }
+        };
+        let x = self.read_coord(info & 0x10 != 0, |m| &mut m.geometry_x)?;
+        let y = self.read_coord(info & 0x08 != 0, |m| &mut m.geometry_y)?;
+        let repetition = self.read_opt_repetition(info & 0x04 != 0)?;
+        let origin = OasisPoint::new(x, y);
+        self.push_elem(OasisPath {
+            layer,
+            datatype,
+            half_width,
+            start_extension: self.modal.path_start_extension.unwrap_or_default(),
+            end_extension: self.modal.path_end_extension.unwrap_or_default(),
+            points: points.into_iter().map(|p| p + origin).collect(),
+            repetition,
+            properties: Vec::new(),
+        })?;
+        Ok(())
+    }
+
+    /// Reads one half of a path extension scheme.
+    ///
+    /// Returns [`None`] if the existing modal extension should be reused.
+    fn read_extension(&mut self, scheme: u64) -> OasisResult<Option<OasisPathExtension>> {
+        Ok(match scheme {
+            0 => None,
+            1 => Some(OasisPathExtension::Flush),
+            2 => Some(OasisPathExtension::HalfWidth),
+            _ => Some(OasisPathExtension::Explicit(self.read_sint()?)),
+        })
+    }
+
+    /// Reads a `TRAPEZOID` record as a polygon.
+    fn read_trapezoid(&mut self, id: u64) -> OasisResult<()> {
+        // Info byte: OWHXYRDL
+        let info = self.read_byte()?;
+        let layer = self.read_modal_uint(info & 0x01 != 0, "layer", |m| &mut m.layer)?;
+        let datatype = self.read_modal_uint(info & 0x02 != 0, "datatype", |m| &mut m.datatype)?;
+        let w = self.read_modal_uint(info & 0x40 != 0, "geometry-w", |m| &mut m.geometry_w)?;
+        let h = self.read_modal_uint(info & 0x20 != 0, "geometry-h", |m| &mut m.geometry_h)?;
+        let a = if id != 25 { self.read_sint()? } else { 0 };
+        let b = if id != 24 { self.read_sint()? } else { 0 };
+        let x = self.read_coord(info & 0x10 != 0, |m| &mut m.geometry_x)?;
+        let y = self.read_coord(info & 0x08 != 0, |m| &mut m.geometry_y)?;
+        let repetition = self.read_opt_repetition(info & 0x04 != 0)?;
+
+        let (w, h) = (w as i64, h as i64);
+        let corners = if info & 0x80 != 0 {
+            // Vertical orientation: `a` and `b` shift the left and right edges' endpoints.
+            [
+                (0, a.max(0)),
+                (0, h + b.min(0)),
+                (w, h - b.max(0)),
+                (w, -a.min(0)),
+            ]
+        } else {
+            // Horizontal orientation: `a` and `b` shift the top and bottom edges' endpoints.
+            [
+                (a.max(0), h),
+                (w + b.min(0), h),
+                (w - b.max(0), 0),
+                (-a.min(0), 0),
+            ]
+        };
+        let mut points: Vec<OasisPoint> = Vec::with_capacity(4);
+        for (px, py) in corners {
+            let p = OasisPoint::new(x + px, y + py);
+            if points.last() != Some(&p) && (points.len() < 3 || points[0] != p) {
+                points.push(p);
+            }
+        }
+        self.push_elem(OasisPolygon {
+            layer,
+            datatype,
+            points,
+            repetition,
+            properties: Vec::new(),
+        })?;
+        Ok(())
+    }
+
+    /// Reads a point list relative to the origin, with the origin as its first point.
+    ///
+    /// For polygons, Manhattan point lists (types 0 and 1) are completed
+    /// with the implicit point that allows the closing edges to alternate.
+    fn read_point_list(&mut self, polygon: bool) -> OasisResult<Vec<OasisPoint>> {
+        let kind = self.read_uint()?;
+        let count = self.read_uint()?;
+        let mut points = vec![OasisPoint::zero()];
+        let mut cur = OasisPoint::zero();
+        match kind {
+            0 | 1 => {
+                for i in 0..count {
+                    let delta = self.read_sint()?;
+                    if (i % 2 == 0) == (kind == 0) {
+                        cur.x += delta;
+                    } else {
+                        cur.y += delta;
+                    }
+                    points.push(cur);
+                }
+                if polygon {
+                    if (count % 2 == 0) == (kind == 0) {
+                        points.push(OasisPoint::new(0, cur.y));
+                    } else {
+                        points.push(OasisPoint::new(cur.x, 0));
+                    }
+                }
+            }
+            2 | 3 => {
+                for _ in 0..count {
+                    let value = self.read_uint()?;
+                    let delta = if kind == 2 {
+                        octangular(value & 0b11, value >> 2)
+                    } else {
+                        octangular(value & 0b111, value >> 3)
+                    };
+                    cur = cur + delta;
+                    points.push(cur);
+                }
+            }
+            4 => {
+                for _ in 0..count {
+                    cur = cur + self.read_gdelta()?;
+                    points.push(cur);
+                }
+            }
+            5 => {
+                let mut delta = OasisPoint::zero();
+                for _ in 0..count {
+                    delta = delta + self.read_gdelta()?;
+                    cur = cur + delta;
+                    points.push(cur);
+                }
+            }
+            kind => return self.fail(format!("invalid point list type {kind}")),
+        }
+        Ok(points)
+    }
+
+    fn read_opt_repetition(&mut self, present: bool) -> OasisResult<Option<OasisRepetition>> {
+        if present {
+            self.read_repetition().map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn read_repetition(&mut self) -> OasisResult<OasisRepetition> {
+        let kind = self.read_uint()?;
+        let rep = match kind {
+            0 => {
+                return match self.modal.repetition.clone() {
+                    Some(rep) => Ok(rep),
+                    None => self.fail("undefined modal variable repetition"),
+                }
+            }
+            1 => {
+                let nx = self.read_uint()? + 2;
+                let ny = self.read_uint()? + 2;
+                let dx = self.read_uint()? as i64;
+                let dy = self.read_uint()? as i64;
+                OasisRepetition::Matrix { nx, ny, dx, dy }
+            }
+            2 => {
+                let nx = self.read_uint()? + 2;
+                let dx = self.read_uint()? as i64;
+                OasisRepetition::Matrix {
+                    nx,
+                    ny: 1,
+                    dx,
+                    dy: 0,
+                }
+            }
+            3 => {
+                let ny = self.read_uint()? + 2;
+                let dy = self.read_uint()? as i64;
+                OasisRepetition::Matrix {
+                    nx: 1,
+                    ny,
+                    dx: 0,
+                    dy,
+                }
+            }
+            4..=7 => {
+                let n = self.read_uint()? + 2;
+                let grid = if kind % 2 == 1 {
+                    self.read_uint()? as i64
+                } else {
+                    1
+                };
+                let mut offsets = vec![OasisPoint::zero()];
+                let mut cur = 0;
+                for _ in 1..n {
+                    cur += self.read_uint()? as i64 * grid;
+                    offsets.push(if kind < 6 {
+                        OasisPoint::new(cur, 0)
+                    } else {
+                        OasisPoint::new(0, cur)
+                    });
+                }
+                OasisRepetition::Explicit(offsets)
+            }
+            8 => {
+                let n = self.read_uint()? + 2;
+                let m = self.read_uint()? + 2;
+                let a = self.read_gdelta()?;
+                let b = self.read_gdelta()?;
+                OasisRepetition::lattice(n, m, a, b)
+            }
+            9 => {
+                let n = self.read_uint()? + 2;
+                let a = self.read_gdelta()?;
+                OasisRepetition::lattice(n, 1, a, OasisPoint::zero())
+            }
+            10 | 11 => {
+                let n = self.read_uint()? + 2;
+                let grid = if kind == 11 {
+                    self.read_uint()? as i64
+                } else {
+                    1
+                };
+                let mut offsets = vec![OasisPoint::zero()];
+                let mut cur = OasisPoint::zero();
+                for _ in 1..n {
+                    let delta = self.read_gdelta()?;
+                    cur = cur + OasisPoint::new(delta.x * grid, delta.y * grid);
+                    offsets.push(cur);
+                }
+                OasisRepetition::Explicit(offsets)
+            }
+            kind => return self.fail(format!("invalid repetition type {kind}")),
+        };
+        self.modal.repetition = Some(rep.clone());
+        Ok(rep)
+    }
+
+    /// Reads a coordinate if `present`, updating its modal variable.
+    ///
+    /// Respects the current `xy-mode`.
+    fn read_coord(
+        &mut self,
+        present: bool,
+        var: impl Fn(&mut Modal) -> &mut i64,
+    ) -> OasisResult<i64> {
+        if present {
+            let value = self.read_sint()?;
+            let relative = self.modal.xy_relative;
+            let modal = var(&mut self.modal);
+            if relative {
+                *modal += value;
+            } else {
+                *modal = value;
+            }
+        }
+        Ok(*var(&mut self.modal))
+    }
+
+    /// Reads an unsigned integer if `present`, otherwise falling back to its modal variable.
+    fn read_modal_uint(
+        &mut self,
+        present: bool,
+        name: &str,
+        var: impl Fn(&mut Modal) -> &mut Option<u64>,
+    ) -> OasisResult<u64> {
+        if present {
+            let value = self.read_uint()?;
+            *var(&mut self.modal) = Some(value);
+            Ok(value)
+        } else {
+            match *var(&mut self.modal) {
+                Some(value) => Ok(value),
+                None => self.fail(format!("undefined modal variable {name}")),
+            }
+        }
+    }
+
+    /// Fills in all names referenced by number.
+    fn resolve_names(&mut self) -> OasisResult<()> {
+        for fixup in self.fixups.drain(..) {
+            match fixup {
+                Fixup::CellName { cell, name } => {
+                    self.cells[cell].name = self.cellnames.resolve(&name, "cell name")?;
+                }
+                Fixup::Placement { cell, elem, name } => {
+                    if let OasisElement::Placement(ref mut p) = self.cells[cell].elems[elem] {
+                        p.cell = self.cellnames.resolve(&name, "cell name")?;
+                    }
+                }
+                Fixup::Text { cell, elem, name } => {
+                    if let OasisElement::Text(ref mut t) = self.cells[cell].elems[elem] {
+                        t.string = self.textstrings.resolve(&name, "text string")?;
+                    }
+                }
+                Fixup::Property {
+                    cell,
+                    elem,
+                    name,
+                    values,
+                } => {
+                    let name = self.propnames.resolve(&name, "property name")?;
+                    let values = values
+                        .into_iter()
+                        .map(|value| match value {
+                            PropValueRef::Value(value) => Ok(value),
+                            PropValueRef::Ref(refnum) => self
+                                .propstrings
+                                .resolve(&NameRef::Ref(refnum), "property string")
+                                .map(OasisPropValue::String),
+                        })
+                        .collect::<OasisResult<_>>()?;
+                    self.cells[cell].elems[elem]
+                        .properties_mut()
+                        .push(OasisProperty { name, values });
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn read_gdelta(&mut self) -> OasisResult<OasisPoint> {
+        let value = self.read_uint()?;
+        if value & 1 == 0 {
+            Ok(octangular((value >> 1) & 0b111, value >> 4))
+        } else {
+            let magnitude = (value >> 2) as i64;
+            let x = if value & 0b10 != 0 {
+                -magnitude
+            } else {
+                magnitude
+            };
+            let y = self.read_sint()?;
+            Ok(OasisPoint::new(x, y))
+        }
+    }
+
+    fn read_real(&mut self) -> OasisResult<f64> {
+        let kind = self.read_uint()?;
+        self.read_real_body(kind)
+    }
+
+    fn read_real_body(&mut self, kind: u64) -> OasisResult<f64> {
+        Ok(match kind {
+            0 => self.read_uint()? as f64,
+            1 => -(self.read_uint()? as f64),
+            2 => 1.0 / self.read_uint()? as f64,
+            3 => -1.0 / self.read_uint()? as f64,
+            4 | 5 => {
+                let num = self.read_uint()? as f64;
+                let den = self.read_uint()? as f64;
+                if kind == 4 {
+                    num / den
+                } else {
+                    -num / den
+                }
+            }
+            6 => {
+                let mut bytes = [0u8; 4];
+                self.read_bytes(&mut bytes)?;
+                f64::from(f32::from_le_bytes(bytes))
+            }
+            7 => {
+                let mut bytes = [0u8; 8];
+                self.read_bytes(&mut bytes)?;
+                f64::from_le_bytes(bytes)
+            }
+            kind => return self.fail(format!("invalid real type {kind}")),
+        })
+    }
+
+    /// Reads a string as a name, which must be valid UTF-8.
+    fn read_name(&mut self) -> OasisResult<ArcStr> {
+        let bytes = self.read_string()?;
+        match String::from_utf8(bytes) {
+            Ok(s) => Ok(ArcStr::from(s)),
+            Err(_) => self.fail("invalid UTF-8 in name"),
+        }
+    }
+
+    /// Reads a property string, replacing any invalid UTF-8.
+    fn read_prop_string(&mut self) -> OasisResult<ArcStr> {
+        let bytes = self.read_string()?;
+        Ok(ArcStr::from(String::from_utf8_lossy(&bytes)))
+    }
+
+    fn read_string(&mut self) -> OasisResult<Vec<u8>> {
+        let len = self.read_uint()? as usize;
+        let mut bytes = vec![0u8; len];
+        self.read_bytes(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    fn read_sint(&mut self) -> OasisResult<i64> {
+        let value = self.read_uint()?;
+        let magnitude = (value >> 1) as i64;
+        Ok(if value & 1 != 0 {
+            -magnitude
+        } else {
+            magnitude
+        })
+    }
+
+    fn read_uint(&mut self) -> OasisResult<u64> {
+        let mut value = 0u64;
+        let mut shift = 0;
+        loop {
+            let byte = self.read_byte()?;
+            if shift >= 64 {
+                return self.fail("unsigned integer overflow");
+            }
+            value |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+            shift += 7;
+        }
+    }
+
+    fn read_byte(&mut self) -> OasisResult<u8> {
+        let mut byte = [0u8];
+        self.read_bytes(&mut byte)?;
+        Ok(byte[0])
+    }
+
+    fn read_bytes(&mut self, buf: &mut [u8]) -> OasisResult<()> {
+        // Exhausted blocks are dropped immediately, so any remaining block is non-empty.
+        if let Some(block) = self.block.as_mut() {
+            let remaining = block.get_ref().len() - block.position() as usize;
+            if remaining < buf.len() {
+                return self.fail("record extends past the end of its CBLOCK");
+            }
+            block.read_exact(buf)?;
+            if remaining == buf.len() {
+                self.block = None;
+            }
+            return Ok(());
+        }
+        self.reader.read_exact(buf)?;
+        self.pos += buf.len() as u64;
+        Ok(())
+    }
+
+    fn fail<T>(&self, msg: impl Into<String>) -> OasisResult<T> {
+        Err(OasisError::Parse {
+            msg: msg.into(),
+            pos: self.pos,
+        })
+    }
+}
+
+/// Converts an octangular direction and magnitude into a displacement.
+///
+/// Directions are numbered counter-clockwise from east,
+/// followed by the diagonals starting from northeast.
+fn octangular(dir: u64, magnitude: u64) -> OasisPoint {
+    let m = magnitude as i64;
+    match dir {
+        0 => OasisPoint::new(m, 0),
+        1 => OasisPoint::new(0, m),
+        2 => OasisPoint::new(-m, 0),
+        3 => OasisPoint::new(0, -m),
+        4 => OasisPoint::new(m, m),
+        5 => OasisPoint::new(-m, m),
+        6 => OasisPoint::new(-m, -m),
+        _ => OasisPoint::new(m, -m),
+    }
+}
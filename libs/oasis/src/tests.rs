@@ -0,0 +1,708 @@
+use super::*;
+
+/// Creates a library exercising every element type and repetition kind.
+fn sample_lib() -> OasisLibrary {
+    let mut leaf = OasisCell::new("leaf");
+    leaf.elems
+        .push(OasisRectangle::new(66, 20, -10, 0, 100, 200).into());
+    leaf.elems
+        .push(OasisRectangle::new(67, 20, 0, 0, 50, 50).into());
+    leaf.elems.push(
+        OasisPolygon {
+            layer: 68,
+            datatype: 20,
+            points: vec![
+                OasisPoint::new(0, 0),
+                OasisPoint::new(300, 0),
+                OasisPoint::new(300, 100),
+                OasisPoint::new(150, 250),
+                OasisPoint::new(0, 100),
+            ],
+            repetition: None,
+            properties: Vec::new(),
+        }
+        .into(),
+    );
+    leaf.elems.push(
+        OasisPath {
+            layer: 69,
+            datatype: 20,
+            half_width: 25,
+            start_extension: OasisPathExtension::HalfWidth,
+            end_extension: OasisPathExtension::Explicit(-5),
+            points: vec![
+                OasisPoint::new(0, 0),
+                OasisPoint::new(0, 500),
+                OasisPoint::new(-700, 500),
+            ],
+            repetition: Some(OasisRepetition::Explicit(vec![
+                OasisPoint::zero(),
+                OasisPoint::new(13, -7),
+                OasisPoint::new(100, 100),
+            ])),
+            properties: Vec::new(),
+        }
+        .into(),
+    );
+    leaf.elems.push(
+        OasisText {
+            string: arcstr::literal!("vdd"),
+            layer: 68,
+            texttype: 5,
+            x: 20,
+            y: -30,
+            repetition: None,
+            properties: Vec::new(),
+        }
+        .into(),
+    );
+
+    let mut top = OasisCell::new("top");
+    top.elems.push(OasisPlacement::new("leaf", 0, 0).into());
+    top.elems.push(
+        OasisPlacement {
+            flip: true,
+            angle: 270.0,
+            repetition: Some(OasisRepetition::Matrix {
+                nx: 3,
+                ny: 2,
+                dx: 1000,
+                dy: 2000,
+            }),
+            ..OasisPlacement::new("leaf", 5000, -5000)
+        }
+        .into(),
+    );
+    top.elems.push(
+        OasisPlacement {
+            angle: 45.0,
+            magnification: 0.5,
+            repetition: Some(OasisRepetition::Matrix {
+                nx: 4,
+                ny: 1,
+                dx: -300,
+                dy: 0,
+            }),
+            ..OasisPlacement::new("leaf", 10, 20)
+        }
+        .into(),
+    );
+    let mut rect = OasisRectangle::new(66, 20, 0, 0, 10, 20);
+    rect.repetition = Some(OasisRepetition::Lattice {
+        n: 2,
+        m: 3,
+        a: OasisPoint::new(40, 40),
+        b: OasisPoint::new(-15, 60),
+    });
+    top.elems.push(rect.into());
+
+    OasisLibrary {
+        unit: 1000.0,
+        cells: vec![leaf, top],
+    }
+}
+
+/// Encodes an unsigned integer.
+fn uint(mut value: u64) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            bytes.push(byte);
+            return bytes;
+        }
+        bytes.push(byte | 0x80);
+    }
+}
+
+/// Encodes a signed integer.
+fn sint(value: i64) -> Vec<u8> {
+    uint(value.unsigned_abs() << 1 | u64::from(value < 0))
+}
+
+/// Encodes a string.
+fn string(s: &str) -> Vec<u8> {
+    let mut bytes = uint(s.len() as u64);
+    bytes.extend(s.as_bytes());
+    bytes
+}
+
+#[test]
+fn roundtrip() {
+    let lib = sample_lib();
+    let mut bytes = Vec::new();
+    lib.write(&mut bytes).unwrap();
+    assert!(bytes.starts_with(MAGIC));
+    let lib2 = OasisLibrary::read(&mut bytes.as_slice()).unwrap();
+    assert_eq!(lib, lib2);
+}
+
+#[test]
+fn end_record_length() {
+    let mut bytes = Vec::new();
+    OasisLibrary::new(1000.0).write(&mut bytes).unwrap();
+    // Magic, START record ("1.0", real 1000, offset flag, 12 table offsets).
+    let start_len = 1 + 4 + 3 + 1 + 12;
+    assert_eq!(bytes.len(), MAGIC.len() + start_len + 256);
+    assert_eq!(bytes[MAGIC.len() + start_len], 2);
+}
+
+#[test]
+fn repetition_offsets() {
+    let rep = OasisRepetition::Matrix {
+        nx: 2,
+        ny: 2,
+        dx: 10,
+        dy: 20,
+    };
+    assert_eq!(
+        rep.offsets(),
+        vec![
+            OasisPoint::new(0, 0),
+            OasisPoint::new(10, 0),
+            OasisPoint::new(0, 20),
+            OasisPoint::new(10, 20),
+        ]
+    );
+    let rep = OasisRepetition::Lattice {
+        n: 2,
+        m: 2,
+        a: OasisPoint::new(10, 10),
+        b: OasisPoint::new(-5, 5),
+    };
+    assert_eq!(rep.len(), 4);
+    assert_eq!(rep.offsets()[3], OasisPoint::new(5, 15));
+}
+
+#[test]
+fn invalid_explicit_repetition() {
+    let mut lib = OasisLibrary::new(1000.0);
+    let mut cell = OasisCell::new("cell");
+    let mut rect = OasisRectangle::new(1, 0, 0, 0, 10, 10);
+    rect.repetition = Some(OasisRepetition::Explicit(vec![
+        OasisPoint::new(5, 5),
+        OasisPoint::new(10, 10),
+    ]));
+    cell.elems.push(rect.into());
+    lib.cells.push(cell);
+    let mut bytes = Vec::new();
+    assert!(matches!(lib.write(&mut bytes), Err(OasisError::Write(_))));
+}
+
+#[test]
+fn bad_magic() {
+    let bytes = b"%SEMI-OASIS\r\r".to_vec();
+    assert!(matches!(
+        OasisLibrary::read(&mut bytes.as_slice()),
+        Err(OasisError::BadMagic)
+    ));
+}
+
+/// Reads a hand-encoded file relying on modal variables, trailing name tables,
+/// table offsets in the `END` record, and compact point list and repetition types.
+#[test]
+fn read_modal() {
+    let mut b = MAGIC.to_vec();
+    // START: version, unit as a ratio (2000 / 2), offsets stored in END.
+    b.extend(uint(1));
+    b.extend(string("1.0"));
+    b.extend(uint(4));
+    b.extend(uint(2000));
+    b.extend(uint(2));
+    b.extend(uint(1));
+
+    // CELL "top", by reference number 7.
+    b.extend(uint(13));
+    b.extend(uint(7));
+
+    // PROPERTY with an inline name and two values, which is discarded.
+    b.extend(uint(28));
+    b.push(0b0010_0100);
+    b.extend(string("prop"));
+    b.extend(uint(8));
+    b.extend(uint(42));
+    b.extend(uint(10));
+    b.extend(string("value"));
+
+    // RECTANGLE: square, layer 1, datatype 2, width 10, at (5, 6), repeated 3 times along x.
+    b.extend(uint(20));
+    b.push(0b1101_1111);
+    b.extend(uint(1));
+    b.extend(uint(2));
+    b.extend(uint(10));
+    b.extend(sint(5));
+    b.extend(sint(6));
+    b.extend(uint(4));
+    b.extend(uint(1));
+    b.extend(uint(3));
+    b.extend(uint(4));
+
+    // XYRELATIVE, then RECTANGLE reusing layer, datatype, width, height and repetition,
+    // offset by (100, 0) from the previous rectangle.
+    b.extend(uint(16));
+    b.extend(uint(20));
+    b.push(0b0001_0100);
+    b.extend(sint(100));
+    b.extend(uint(0));
+
+    // XYABSOLUTE, then POLYGON: a 20x30 rectangle as a horizontal-first Manhattan list,
+    // relying on the implicit final point.
+    b.extend(uint(15));
+    b.extend(uint(21));
+    b.push(0b0011_1000);
+    b.extend(uint(0));
+    b.extend(uint(2));
+    b.extend(sint(20));
+    b.extend(sint(30));
+    b.extend(sint(-1));
+    b.extend(sint(-2));
+
+    // PATH: half-width 5, flush start, half-width end, 2-delta point list (north 10, west 4).
+    b.extend(uint(22));
+    b.push(0b1111_1000);
+    b.extend(uint(5));
+    b.extend(uint(0b0110));
+    b.extend(uint(2));
+    b.extend(uint(2));
+    b.extend(uint(10 << 2 | 1));
+    b.extend(uint(4 << 2 | 2));
+    b.extend(sint(0));
+    b.extend(sint(0));
+
+    // TEXT: string by reference number 3 on textlayer 9, texttype 1.
+    b.extend(uint(19));
+    b.push(0b0111_1011);
+    b.extend(uint(3));
+    b.extend(uint(9));
+    b.extend(uint(1));
+    b.extend(sint(1));
+    b.extend(sint(2));
+
+    // PLACEMENT of cell 8 rotated by 90 degrees, repeated on a 2x2 grid.
+    b.extend(uint(17));
+    b.push(0b1111_1010);
+    b.extend(uint(8));
+    b.extend(sint(-100));
+    b.extend(sint(200));
+    b.extend(uint(1));
+    b.extend(uint(0));
+    b.extend(uint(0));
+    b.extend(uint(50));
+    b.extend(uint(60));
+
+    // PLACEMENT reusing the cell with a magnification and an explicit g-delta repetition.
+    b.extend(uint(18));
+    b.push(0b0011_1100);
+    b.extend(uint(4));
+    b.extend(uint(3));
+    b.extend(uint(2));
+    b.extend(sint(0));
+    b.extend(sint(0));
+    b.extend(uint(10));
+    b.extend(uint(0));
+    b.extend(uint(7 << 2 | 0b11));
+    b.extend(sint(9));
+
+    // TRAPEZOID (both deltas), horizontal, 100 x 50, delta-a 10, delta-b -20.
+    b.extend(uint(23));
+    b.push(0b0111_1000);
+    b.extend(uint(100));
+    b.extend(uint(50));
+    b.extend(sint(10));
+    b.extend(sint(-20));
+    b.extend(sint(0));
+    b.extend(sint(0));
+
+    // CELL "leaf", by reference number 8, empty.
+    b.extend(uint(13));
+    b.extend(uint(8));
+
+    // Name tables, after the cells that use them.
+    b.extend(uint(4));
+    b.extend(string("top"));
+    b.extend(uint(7));
+    b.extend(uint(4));
+    b.extend(string("leaf"));
+    b.extend(uint(8));
+    b.extend(uint(6));
+    b.extend(string("label"));
+    b.extend(uint(3));
+
+    // END: table offsets, padding, no validation.
+    b.extend(uint(2));
+    for _ in 0..12 {
+        b.extend(uint(0));
+    }
+    b.extend(string(""));
+    b.extend(uint(0));
+
+    let lib = OasisLibrary::read(&mut b.as_slice()).unwrap();
+    assert_eq!(lib.unit, 1000.0);
+    assert_eq!(lib.cells.len(), 2);
+    assert_eq!(lib.cells[1], OasisCell::new("leaf"));
+
+    let top = lib.cell("top").unwrap();
+    let rep = Some(OasisRepetition::Explicit(vec![
+        OasisPoint::new(0, 0),
+        OasisPoint::new(3, 0),
+        OasisPoint::new(7, 0),
+    ]));
+    let expected: Vec<OasisElement> = vec![
+        OasisRectangle {
+            repetition: rep.clone(),
+            ..OasisRectangle::new(1, 2, 5, 6, 10, 10)
+        }
+        .into(),
+        OasisRectangle {
+            repetition: rep,
+            ..OasisRectangle::new(1, 2, 105, 6, 10, 10)
+        }
+        .into(),
+        OasisPolygon {
+            layer: 1,
+            datatype: 2,
+            points: vec![
+                OasisPoint::new(-1, -2),
+                OasisPoint::new(19, -2),
+                OasisPoint::new(19, 28),
+                OasisPoint::new(-1, 28),
+            ],
+            repetition: None,
+            properties: Vec::new(),
+        }
+        .into(),
+        OasisPath {
+            layer: 1,
+            datatype: 2,
+            half_width: 5,
+            start_extension: OasisPathExtension::Flush,
+            end_extension: OasisPathExtension::HalfWidth,
+            points: vec![
+                OasisPoint::new(0, 0),
+                OasisPoint::new(0, 10),
+                OasisPoint::new(-4, 10),
+            ],
+            repetition: None,
+            properties: Vec::new(),
+        }
+        .into(),
+        OasisText {
+            string: arcstr::literal!("label"),
+            layer: 9,
+            texttype: 1,
+            x: 1,
+            y: 2,
+            repetition: None,
+            properties: Vec::new(),
+        }
+        .into(),
+        OasisPlacement {
+            angle: 90.0,
+            repetition: Some(OasisRepetition::Matrix {
+                nx: 2,
+                ny: 2,
+                dx: 50,
+                dy: 60,
+            }),
+            ..OasisPlacement::new("leaf", -100, 200)
+        }
+        .into(),
+        OasisPlacement {
+            magnification: 1.5,
+            repetition: Some(OasisRepetition::Explicit(vec![
+                OasisPoint::new(0, 0),
+                OasisPoint::new(-7, 9),
+            ])),
+            ..OasisPlacement::new("leaf", 0, 0)
+        }
+        .into(),
+        OasisPolygon {
+            layer: 1,
+            datatype: 2,
+            points: vec![
+                OasisPoint::new(10, 50),
+                OasisPoint::new(80, 50),
+                OasisPoint::new(100, 0),
+                OasisPoint::new(0, 0),
+            ],
+            repetition: None,
+            properties: Vec::new(),
+        }
+        .into(),
+    ];
+    assert_eq!(top.elems, expected);
+}
+
+#[test]
+fn compress_repetitions() {
+    let mut cell = OasisCell::new("cell");
+    for j in 0..2 {
+        for i in 0..3 {
+            cell.elems
+                .push(OasisRectangle::new(1, 0, 100 * i, 50 * j, 10, 20).into());
+        }
+    }
+    for (x, y) in [(0, 0), (7, 3), (-5, 11)] {
+        cell.elems.push(OasisPlacement::new("leaf", x, y).into());
+    }
+    cell.elems
+        .push(OasisRectangle::new(2, 0, 0, 0, 10, 20).into());
+    let mut lib = OasisLibrary::new(1000.0);
+    lib.cells.push(cell);
+
+    let mut bytes = Vec::new();
+    lib.write(&mut bytes).unwrap();
+    let lib2 = OasisLibrary::read(&mut bytes.as_slice()).unwrap();
+    let expected: Vec<OasisElement> = vec![
+        OasisRectangle {
+            repetition: Some(OasisRepetition::Matrix {
+                nx: 3,
+                ny: 2,
+                dx: 100,
+                dy: 50,
+            }),
+            ..OasisRectangle::new(1, 0, 0, 0, 10, 20)
+        }
+        .into(),
+        OasisPlacement {
+            repetition: Some(OasisRepetition::Explicit(vec![
+                OasisPoint::new(0, 0),
+                OasisPoint::new(7, 3),
+                OasisPoint::new(-5, 11),
+            ])),
+            ..OasisPlacement::new("leaf", 0, 0)
+        }
+        .into(),
+        OasisRectangle::new(2, 0, 0, 0, 10, 20).into(),
+    ];
+    assert_eq!(lib2.cells[0].elems, expected);
+}
+
+#[test]
+fn compress_modal() {
+    // Rectangles with distinct widths, which cannot be combined using repetitions.
+    let mut cell = OasisCell::new("cell");
+    for i in 1..=100 {
+        cell.elems
+            .push(OasisRectangle::new(1, 0, 37 * i * i, 11 * i, 10 + i as u64, 200).into());
+    }
+    let mut lib = OasisLibrary::new(1000.0);
+    lib.cells.push(cell);
+
+    let mut bytes = Vec::new();
+    lib.write_with_opts(&mut bytes, OasisWriteOpts { cblocks: false })
+        .unwrap();
+    // Each rectangle specifies its width and coordinates,
+    // and only the first specifies its layer, datatype, and height.
+    let empty_len = MAGIC.len() + 21 + 256;
+    let names_len = string("cell").len() + 3;
+    let rect_len = |i: i64| 3 + sint(37 * i * i).len() + sint(11 * i).len();
+    let rects_len = 4 + (1..=100).map(rect_len).sum::<usize>();
+    assert_eq!(bytes.len(), empty_len + names_len + rects_len);
+    assert_eq!(OasisLibrary::read(&mut bytes.as_slice()).unwrap(), lib);
+}
+
+#[test]
+fn write_cblock() {
+    // Rectangles with distinct widths, whose records share most of their bytes.
+    let mut cell = OasisCell::new("cell");
+    for i in 1..=100 {
+        cell.elems
+            .push(OasisRectangle::new(1, 0, 1000 * i, 0, 10 + i as u64, 200).into());
+    }
+    let mut lib = sample_lib();
+    lib.cells.push(cell);
+
+    let mut uncompressed = Vec::new();
+    lib.write_with_opts(&mut uncompressed, OasisWriteOpts { cblocks: false })
+        .unwrap();
+    let mut compressed = Vec::new();
+    lib.write(&mut compressed).unwrap();
+    assert!(compressed.len() < uncompressed.len());
+    assert_eq!(OasisLibrary::read(&mut compressed.as_slice()).unwrap(), lib);
+
+    // Cells whose records do not compress are written without a `CBLOCK`.
+    let mut lib = OasisLibrary::new(1000.0);
+    let mut cell = OasisCell::new("cell");
+    cell.elems
+        .push(OasisRectangle::new(1, 0, 0, 0, 10, 20).into());
+    lib.cells.push(cell);
+    let mut bytes = Vec::new();
+    lib.write(&mut bytes).unwrap();
+    let mut expected = Vec::new();
+    lib.write_with_opts(&mut expected, OasisWriteOpts { cblocks: false })
+        .unwrap();
+    assert_eq!(bytes, expected);
+}
+
+#[test]
+fn read_cblock() {
+    use std::io::Write;
+
+    let mut records = Vec::new();
+    // CELLNAME "top", then CELL "top" containing one rectangle.
+    records.extend(uint(3));
+    records.extend(string("top"));
+    records.extend(uint(13));
+    records.extend(uint(0));
+    records.extend(uint(20));
+    records.push(0b0111_1011);
+    records.extend(uint(1));
+    records.extend(uint(2));
+    records.extend(uint(10));
+    records.extend(uint(20));
+    records.extend(sint(-5));
+    records.extend(sint(5));
+
+    let mut encoder =
+        flate2::write::DeflateEncoder::new(Vec::new(), flate2::Compression::default());
+    encoder.write_all(&records).unwrap();
+    let compressed = encoder.finish().unwrap();
+
+    let mut b = MAGIC.to_vec();
+    b.extend(uint(1));
+    b.extend(string("1.0"));
+    b.extend(uint(0));
+    b.extend(uint(1000));
+    b.extend(uint(0));
+    for _ in 0..12 {
+        b.extend(uint(0));
+    }
+    b.extend(uint(34));
+    b.extend(uint(0));
+    b.extend(uint(records.len() as u64));
+    b.extend(uint(compressed.len() as u64));
+    b.extend(compressed);
+    // A record following the CBLOCK, read from the uncompressed stream.
+    b.extend(uint(20));
+    b.push(0b0001_1000);
+    b.extend(sint(100));
+    b.extend(sint(200));
+    b.extend(uint(2));
+    b.extend(string(""));
+    b.extend(uint(0));
+
+    let lib = OasisLibrary::read(&mut b.as_slice()).unwrap();
+    let top = lib.cell("top").unwrap();
+    assert_eq!(
+        top.elems,
+        vec![
+            OasisRectangle::new(1, 2, -5, 5, 10, 20).into(),
+            OasisRectangle::new(1, 2, 100, 200, 10, 20).into(),
+        ]
+    );
+}
+
+#[test]
+fn roundtrip_properties() {
+    let net = |name: &str| OasisProperty::new("NET", vec![OasisPropValue::String(name.into())]);
+    let mut cell = OasisCell::new("cell");
+    // Copies with properties are not combined into a repetition.
+    for (x, name) in [(0, "a"), (100, "a"), (200, "b")] {
+        let mut rect = OasisRectangle::new(1, 0, x, 0, 10, 20);
+        rect.properties.push(net(name));
+        cell.elems.push(rect.into());
+    }
+    let mut text = OasisText {
+        string: arcstr::literal!("a"),
+        layer: 1,
+        texttype: 1,
+        ..Default::default()
+    };
+    text.properties.push(net("a"));
+    text.properties.push(OasisProperty::new(
+        "VALUES",
+        (0..20)
+            .map(|i| match i % 3 {
+                0 => OasisPropValue::Unsigned(i),
+                1 => OasisPropValue::Signed(-(i as i64)),
+                _ => OasisPropValue::Real(i as f64 + 0.5),
+            })
+            .collect(),
+    ));
+    cell.elems.push(text.into());
+    let mut lib = OasisLibrary::new(1000.0);
+    lib.cells.push(cell);
+
+    for cblocks in [false, true] {
+        let mut bytes = Vec::new();
+        lib.write_with_opts(&mut bytes, OasisWriteOpts { cblocks })
+            .unwrap();
+        let lib2 = OasisLibrary::read(&mut bytes.as_slice()).unwrap();
+        assert_eq!(lib, lib2);
+    }
+}
+
+#[test]
+fn read_properties() {
+    let mut b = MAGIC.to_vec();
+    b.extend(uint(1));
+    b.extend(string("1.0"));
+    b.extend(uint(0));
+    b.extend(uint(1000));
+    b.extend(uint(0));
+    for _ in 0..12 {
+        b.extend(uint(0));
+    }
+    b.extend(uint(3));
+    b.extend(string("top"));
+    b.extend(uint(14));
+    b.extend(string("top"));
+
+    // RECTANGLE with a property naming PROPNAME 5 and referencing PROPSTRING 2.
+    b.extend(uint(20));
+    b.push(0b0111_1011);
+    b.extend(uint(1));
+    b.extend(uint(2));
+    b.extend(uint(10));
+    b.extend(uint(20));
+    b.extend(sint(0));
+    b.extend(sint(0));
+    b.extend(uint(28));
+    b.push(0b0001_0110);
+    b.extend(uint(5));
+    b.extend(uint(13));
+    b.extend(uint(2));
+
+    // RECTANGLE with a repeated PROPERTY record.
+    b.extend(uint(20));
+    b.push(0b0001_0000);
+    b.extend(sint(50));
+    b.extend(uint(29));
+
+    // Name tables, after the properties that use them.
+    // The PROPSTRING table record itself carries a property, which is discarded.
+    b.extend(uint(8));
+    b.extend(string("NET"));
+    b.extend(uint(5));
+    b.extend(uint(10));
+    b.extend(string("vdd"));
+    b.extend(uint(2));
+    b.extend(uint(28));
+    b.push(0b0000_1100);
+    b.extend(string("ignored"));
+
+    b.extend(uint(2));
+    b.extend(string(""));
+    b.extend(uint(0));
+
+    let lib = OasisLibrary::read(&mut b.as_slice()).unwrap();
+    let net = OasisProperty::new("NET", vec![OasisPropValue::String("vdd".into())]);
+    let top = lib.cell("top").unwrap();
+    assert_eq!(
+        top.elems,
+        vec![
+            OasisRectangle {
+                properties: vec![net.clone()],
+                ..OasisRectangle::new(1, 2, 0, 0, 10, 20)
+            }
+            .into(),
+            OasisRectangle {
+                properties: vec![net],
+                ..OasisRectangle::new(1, 2, 50, 0, 10, 20)
+            }
+            .into(),
+        ]
+    );
+}
@@ -0,0 +1,912 @@
+//! OASIS writing.
+//!
+//! Three kinds of compression are applied:
+//!
+//! * Copies of an element that differ only in their position are combined into
+//!   a single element with an [`OasisRepetition`], preferring regular grids
+//!   to explicit lists of offsets. Elements that already have a repetition
+//!   or carry properties are left as-is.
+//! * Fields that match the current value of their modal variable are omitted.
+//!   Coordinates are always written in absolute (`XYABSOLUTE`) mode.
+//! * If [`OasisWriteOpts::cblocks`] is set, the element records of each cell are
+//!   `DEFLATE`-compressed into a `CBLOCK`, unless doing so would not save space.
+
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::io::Write;
+
+use arcstr::ArcStr;
+use flate2::write::DeflateEncoder;
+use flate2::Compression;
+
+use crate::{
+    OasisCell, OasisElement, OasisError, OasisLibrary, OasisPath, OasisPathExtension,
+    OasisPlacement, OasisPoint, OasisPolygon, OasisPropValue, OasisProperty, OasisRectangle,
+    OasisRepetition, OasisResult, OasisText, OasisWriteOpts, MAGIC, VERSION,
+};
+
+/// The length of the `END` record, as required by the OASIS spec.
+const END_RECORD_LEN: usize = 256;
+
+/// The OASIS modal variables, as last written.
+///
+/// All of these are reset at the start of each cell.
+#[derive(Debug, Default)]
+struct Modal {
+    repetition: Option<OasisRepetition>,
+    placement_x: i64,
+    placement_y: i64,
+    placement_cell: Option<u64>,
+    layer: Option<u64>,
+    datatype: Option<u64>,
+    textlayer: Option<u64>,
+    texttype: Option<u64>,
+    text_x: i64,
+    text_y: i64,
+    text_string: Option<ArcStr>,
+    geometry_x: i64,
+    geometry_y: i64,
+    geometry_w: Option<u64>,
+    geometry_h: Option<u64>,
+    polygon_points: Option<Vec<OasisPoint>>,
+    path_half_width: Option<u64>,
+    path_points: Option<Vec<OasisPoint>>,
+    path_start_extension: Option<OasisPathExtension>,
+    path_end_extension: Option<OasisPathExtension>,
+    last_property_name: Option<u64>,
+}
+
+/// An element with its position removed.
+///
+/// Elements with equal shapes differ only in their position,
+/// and can be combined using a repetition.
+#[derive(Debug, Eq, PartialEq, Hash)]
+enum Shape {
+    Rectangle {
+        layer: u64,
+        datatype: u64,
+        width: u64,
+        height: u64,
+    },
+    Polygon {
+        layer: u64,
+        datatype: u64,
+        points: Vec<OasisPoint>,
+    },
+    Path {
+        layer: u64,
+        datatype: u64,
+        half_width: u64,
+        start_extension: OasisPathExtension,
+        end_extension: OasisPathExtension,
+        points: Vec<OasisPoint>,
+    },
+    Text {
+        string: ArcStr,
+        layer: u64,
+        texttype: u64,
+    },
+    Placement {
+        cell: ArcStr,
+        flip: bool,
+        /// The bits of the placement's angle.
+        angle: u64,
+        /// The bits of the placement's magnification.
+        magnification: u64,
+    },
+}
+
+/// An OASIS writer.
+pub(crate) struct OasisWriter<'a, W: Write> {
+    writer: &'a mut W,
+    opts: OasisWriteOpts,
+    /// Reference numbers of each cell name.
+    cellnames: HashMap<ArcStr, u64>,
+    /// Reference numbers of each property name.
+    propnames: HashMap<ArcStr, u64>,
+    modal: Modal,
+    /// The uncompressed contents of the `CBLOCK` being written, if any.
+    block: Option<Vec<u8>>,
+}
+
+impl<'a, W: Write> OasisWriter<'a, W> {
+    /// Creates a new [`OasisWriter`] writing to `writer`.
+    pub(crate) fn new(writer: &'a mut W, opts: OasisWriteOpts) -> Self {
+        Self {
+            writer,
+            opts,
+            cellnames: HashMap::new(),
+            propnames: HashMap::new(),
+            modal: Modal::default(),
+            block: None,
+        }
+    }
+
+    /// Writes an entire [`OasisLibrary`].
+    pub(crate) fn write_lib(&mut self, lib: &OasisLibrary) -> OasisResult<()> {
+        if lib.unit.is_nan() || lib.unit <= 0.0 {
+            return Err(OasisError::Write(format!(
+                "database units per micron must be positive, got {}",
+                lib.unit
+            )));
+        }
+        self.write_bytes(MAGIC)?;
+
+        // START record. Table offsets are stored here, and are all zero,
+        // indicating that no table locations are provided.
+        self.write_uint(1)?;
+        self.write_string(VERSION)?;
+        self.write_real(lib.unit)?;
+        self.write_uint(0)?;
+        for _ in 0..12 {
+            self.write_uint(0)?;
+        }
+
+        // CELLNAME records, with implicitly assigned reference numbers.
+        // Cells that are placed but not defined are included as well.
+        let referenced = lib.cells.iter().flat_map(|cell| {
+            cell.elems.iter().filter_map(|elem| match elem {
+                OasisElement::Placement(p) => Some(&p.cell),
+                _ => None,
+            })
+        });
+        for name in lib.cells.iter().map(|c| &c.name).chain(referenced) {
+            if self.cellnames.contains_key(name) {
+                continue;
+            }
+            self.cellnames
+                .insert(name.clone(), self.cellnames.len() as u64);
+            self.write_uint(3)?;
+            self.write_string(name)?;
+        }
+
+        // PROPNAME records, with implicitly assigned reference numbers.
+        let propnames = lib
+            .cells
+            .iter()
+            .flat_map(|cell| cell.elems.iter().flat_map(|elem| elem.properties()))
+            .map(|prop| &prop.name);
+        for name in propnames {
+            if self.propnames.contains_key(name) {
+                continue;
+            }
+            self.propnames
+                .insert(name.clone(), self.propnames.len() as u64);
+            self.write_uint(7)?;
+            self.write_string(name)?;
+        }
+
+        for cell in lib.cells.iter() {
+            self.write_cell(cell)?;
+        }
+
+        // END record, padded to its required length.
+        // Validation scheme 0 indicates that no checksum is provided.
+        let mut padding = END_RECORD_LEN - 2;
+        while padding + uint_len(padding as u64) > END_RECORD_LEN - 2 {
+            padding -= 1;
+        }
+        self.write_uint(2)?;
+        self.write_uint(padding as u64)?;
+        self.write_bytes(&vec![0u8; padding])?;
+        self.write_uint(0)?;
+        Ok(())
+    }
+
+    /// Writes a `CELL` record and all of the cell's elements.
+    fn write_cell(&mut self, cell: &OasisCell) -> OasisResult<()> {
+        self.write_uint(13)?;
+        self.write_uint(self.cellnames[&cell.name])?;
+        self.modal = Modal::default();
+        if self.opts.cblocks {
+            self.block = Some(Vec::new());
+        }
+        for elem in compress_repetitions(&cell.elems).iter() {
+            match elem {
+                OasisElement::Rectangle(x) => self.write_rectangle(x)?,
+                OasisElement::Polygon(x) => self.write_polygon(x)?,
+                OasisElement::Path(x) => self.write_path(x)?,
+                OasisElement::Text(x) => self.write_text(x)?,
+                OasisElement::Placement(x) => self.write_placement(x)?,
+            }
+            for prop in elem.properties() {
+                self.write_property(prop)?;
+            }
+        }
+        match self.block.take() {
+            Some(records) => self.write_cblock(&records),
+            None => Ok(()),
+        }
+    }
+
+    /// Writes `records` as a `DEFLATE`-compressed `CBLOCK`.
+    ///
+    /// The records are written uncompressed if compression does not make them smaller.
+    fn write_cblock(&mut self, records: &[u8]) -> OasisResult<()> {
+        if records.is_empty() {
+            return Ok(());
+        }
+        let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(records)?;
+        let compressed = encoder.finish()?;
+        let header_len = uint_len(34)
+            + uint_len(0)
+            + uint_len(records.len() as u64)
+            + uint_len(compressed.len() as u64);
+        if header_len + compressed.len() >= records.len() {
+            return self.write_bytes(records);
+        }
+        self.write_uint(34)?;
+        self.write_uint(0)?;
+        self.write_uint(records.len() as u64)?;
+        self.write_uint(compressed.len() as u64)?;
+        self.write_bytes(&compressed)
+    }
+
+    fn write_rectangle(&mut self, rect: &OasisRectangle) -> OasisResult<()> {
+        let rep = effective_repetition(&rect.repetition)?;
+        // Info byte: SWHXYRDL
+        let mut info = 0;
+        let square = rect.width == rect.height;
+        if square {
+            info |= 0b1000_0000;
+        }
+        if self.modal.geometry_w != Some(rect.width) {
+            info |= 0b0100_0000;
+        }
+        if !square && self.modal.geometry_h != Some(rect.height) {
+            info |= 0b0010_0000;
+        }
+        info |= self.geometry_info(rect.layer, rect.datatype, rect.x, rect.y, rep);
+
+        self.write_uint(20)?;
+        self.write_byte(info)?;
+        self.write_layer(info, rect.layer, rect.datatype)?;
+        if info & 0b0100_0000 != 0 {
+            self.write_uint(rect.width)?;
+        }
+        if info & 0b0010_0000 != 0 {
+            self.write_uint(rect.height)?;
+        }
+        self.modal.geometry_w = Some(rect.width);
+        self.modal.geometry_h = Some(rect.height);
+        self.write_geometry_xy(info, rect.x, rect.y)?;
+        self.write_opt_repetition(rep)
+    }
+
+    fn write_polygon(&mut self, poly: &OasisPolygon) -> OasisResult<()> {
+        if poly.points.len() < 3 {
+            return Err(OasisError::Write(format!(
+                "polygons must have at least 3 points, got {}",
+                poly.points.len()
+            )));
+        }
+        let rep = effective_repetition(&poly.repetition)?;
+        let origin = poly.points[0];
+        let points = relative_points(&poly.points);
+        // Info byte: 00PXYRDL
+        let mut info = 0;
+        if self.modal.polygon_points.as_ref() != Some(&points) {
+            info |= 0b0010_0000;
+        }
+        info |= self.geometry_info(poly.layer, poly.datatype, origin.x, origin.y, rep);
+
+        self.write_uint(21)?;
+        self.write_byte(info)?;
+        self.write_layer(info, poly.layer, poly.datatype)?;
+        if info & 0b0010_0000 != 0 {
+            self.write_point_list(&points)?;
+            self.modal.polygon_points = Some(points);
+        }
+        self.write_geometry_xy(info, origin.x, origin.y)?;
+        self.write_opt_repetition(rep)
+    }
+
+    fn write_path(&mut self, path: &OasisPath) -> OasisResult<()> {
+        if path.points.len() < 2 {
+            return Err(OasisError::Write(format!(
+                "paths must have at least 2 points, got {}",
+                path.points.len()
+            )));
+        }
+        let rep = effective_repetition(&path.repetition)?;
+        let origin = path.points[0];
+        let points = relative_points(&path.points);
+        // Each half of the extension scheme is zero if the modal extension is reused.
+        let scheme = |ext: OasisPathExtension, modal: Option<OasisPathExtension>| {
+            if modal == Some(ext) {
+                return 0;
+            }
+            match ext {
+                OasisPathExtension::Flush => 1,
+                OasisPathExtension::HalfWidth => 2,
+                OasisPathExtension::Explicit(_) => 3,
+            }
+        };
+        let scheme = scheme(path.start_extension, self.modal.path_start_extension) << 2
+            | scheme(path.end_extension, self.modal.path_end_extension);
+        // Info byte: EWPXYRDL
+        let mut info = 0;
+        if scheme != 0 {
+            info |= 0b1000_0000;
+        }
+        if self.modal.path_half_width != Some(path.half_width) {
+            info |= 0b0100_0000;
+        }
+        if self.modal.path_points.as_ref() != Some(&points) {
+            info |= 0b0010_0000;
+        }
+        info |= self.geometry_info(path.layer, path.datatype, origin.x, origin.y, rep);
+
+        self.write_uint(22)?;
+        self.write_byte(info)?;
+        self.write_layer(info, path.layer, path.datatype)?;
+        if info & 0b0100_0000 != 0 {
+            self.write_uint(path.half_width)?;
+            self.modal.path_half_width = Some(path.half_width);
+        }
+        if info & 0b1000_0000 != 0 {
+            self.write_uint(scheme)?;
+            for (ext, half) in [
+                (path.start_extension, scheme >> 2),
+                (path.end_extension, scheme & 0b11),
+            ] {
+                if let (3, OasisPathExtension::Explicit(value)) = (half, ext) {
+                    self.write_sint(value)?;
+                }
+            }
+            self.modal.path_start_extension = Some(path.start_extension);
+            self.modal.path_end_extension = Some(path.end_extension);
+        }
+        if info & 0b0010_0000 != 0 {
+            self.write_point_list(&points)?;
+            self.modal.path_points = Some(points);
+        }
+        self.write_geometry_xy(info, origin.x, origin.y)?;
+        self.write_opt_repetition(rep)
+    }
+
+    fn write_text(&mut self, text: &OasisText) -> OasisResult<()> {
+        let rep = effective_repetition(&text.repetition)?;
+        // Info byte: 0CNXYRTL
+        let mut info = 0;
+        if self.modal.text_string.as_ref() != Some(&text.string) {
+            info |= 0b0100_0000;
+        }
+        if self.modal.text_x != text.x {
+            info |= 0b0001_0000;
+        }
+        if self.modal.text_y != text.y {
+            info |= 0b0000_1000;
+        }
+        if self.modal.textlayer != Some(text.layer) {
+            info |= 0b0000_0001;
+        }
+        if self.modal.texttype != Some(text.texttype) {
+            info |= 0b0000_0010;
+        }
+        info |= self.repetition_info(rep);
+
+        self.write_uint(19)?;
+        self.write_byte(info)?;
+        if info & 0b0100_0000 != 0 {
+            self.write_string(&text.string)?;
+            self.modal.text_string = Some(text.string.clone());
+        }
+        if info & 0b0000_0001 != 0 {
+            self.write_uint(text.layer)?;
+            self.modal.textlayer = Some(text.layer);
+        }
+        if info & 0b0000_0010 != 0 {
+            self.write_uint(text.texttype)?;
+            self.modal.texttype = Some(text.texttype);
+        }
+        if info & 0b0001_0000 != 0 {
+            self.write_sint(text.x)?;
+            self.modal.text_x = text.x;
+        }
+        if info & 0b0000_1000 != 0 {
+            self.write_sint(text.y)?;
+            self.modal.text_y = text.y;
+        }
+        self.write_opt_repetition(rep)
+    }
+
+    fn write_placement(&mut self, placement: &OasisPlacement) -> OasisResult<()> {
+        let rep = effective_repetition(&placement.repetition)?;
+        let refnum = self.cellnames[&placement.cell];
+        // Info byte: CNXYRAAF or CNXYRMAF
+        let mut info = 0;
+        if self.modal.placement_cell != Some(refnum) {
+            info |= 0b1100_0000;
+        }
+        if self.modal.placement_x != placement.x {
+            info |= 0b0010_0000;
+        }
+        if self.modal.placement_y != placement.y {
+            info |= 0b0001_0000;
+        }
+        if rep.is_some() {
+            info |= 0b0000_1000;
+        }
+        if placement.flip {
+            info |= 0b0000_0001;
+        }
+        let quarter_turns = placement.angle / 90.0;
+        let simple = placement.magnification == 1.0 && quarter_turns.fract() == 0.0;
+        if simple {
+            info |= (quarter_turns.rem_euclid(4.0) as u8) << 1;
+            self.write_uint(17)?;
+        } else {
+            if placement.magnification != 1.0 {
+                info |= 0b0000_0100;
+            }
+            if placement.angle != 0.0 {
+                info |= 0b0000_0010;
+            }
+            self.write_uint(18)?;
+        }
+        self.write_byte(info)?;
+        if info & 0b1000_0000 != 0 {
+            self.write_uint(refnum)?;
+            self.modal.placement_cell = Some(refnum);
+        }
+        if !simple {
+            if info & 0b0000_0100 != 0 {
+                self.write_real(placement.magnification)?;
+            }
+            if info & 0b0000_0010 != 0 {
+                self.write_real(placement.angle)?;
+            }
+        }
+        if info & 0b0010_0000 != 0 {
+            self.write_sint(placement.x)?;
+            self.modal.placement_x = placement.x;
+        }
+        if info & 0b0001_0000 != 0 {
+            self.write_sint(placement.y)?;
+            self.modal.placement_y = placement.y;
+        }
+        self.write_opt_repetition(rep)
+    }
+
+    /// Writes a `PROPERTY` record, referencing the property name by number.
+    fn write_property(&mut self, prop: &OasisProperty) -> OasisResult<()> {
+        let refnum = self.propnames[&prop.name];
+        let count = prop.values.len() as u64;
+        // Info byte: UUUUVCNS
+        let mut info = (count.min(15) as u8) << 4;
+        if self.modal.last_property_name != Some(refnum) {
+            info |= 0b0000_0110;
+        }
+
+        self.write_uint(28)?;
+        self.write_byte(info)?;
+        if info & 0b0000_0100 != 0 {
+            self.write_uint(refnum)?;
+            self.modal.last_property_name = Some(refnum);
+        }
+        if count >= 15 {
+            self.write_uint(count)?;
+        }
+        for value in prop.values.iter() {
+            match *value {
+                OasisPropValue::Unsigned(x) => {
+                    self.write_uint(8)?;
+                    self.write_uint(x)?;
+                }
+                OasisPropValue::Signed(x) => {
+                    self.write_uint(9)?;
+                    self.write_sint(x)?;
+                }
+                OasisPropValue::Real(x) => self.write_real(x)?,
+                // Binary strings (type 11) place no restrictions on their contents.
+                OasisPropValue::String(ref x) => {
+                    self.write_uint(11)?;
+                    self.write_string(x)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Computes the `XYRDL` bits of a geometry record's info byte.
+    fn geometry_info(
+        &self,
+        layer: u64,
+        datatype: u64,
+        x: i64,
+        y: i64,
+        rep: Option<&OasisRepetition>,
+    ) -> u8 {
+        let mut info = self.repetition_info(rep);
+        if self.modal.geometry_x != x {
+            info |= 0b0001_0000;
+        }
+        if self.modal.geometry_y != y {
+            info |= 0b0000_1000;
+        }
+        if self.modal.datatype != Some(datatype) {
+            info |= 0b0000_0010;
+        }
+        if self.modal.layer != Some(layer) {
+            info |= 0b0000_0001;
+        }
+        info
+    }
+
+    /// Computes the `R` bit of an info byte, which is in the same position for all
+    /// records other than placements.
+    fn repetition_info(&self, rep: Option<&OasisRepetition>) -> u8 {
+        if rep.is_some() {
+            0b0000_0100
+        } else {
+            0
+        }
+    }
+
+    /// Writes the layer and datatype of a geometry record, if indicated by `info`.
+    fn write_layer(&mut self, info: u8, layer: u64, datatype: u64) -> OasisResult<()> {
+        if info & 0b0000_0001 != 0 {
+            self.write_uint(layer)?;
+            self.modal.layer = Some(layer);
+        }
+        if info & 0b0000_0010 != 0 {
+            self.write_uint(datatype)?;
+            self.modal.datatype = Some(datatype);
+        }
+        Ok(())
+    }
+
+    /// Writes the coordinates of a geometry record, if indicated by `info`.
+    fn write_geometry_xy(&mut self, info: u8, x: i64, y: i64) -> OasisResult<()> {
+        if info & 0b0001_0000 != 0 {
+            self.write_sint(x)?;
+            self.modal.geometry_x = x;
+        }
+        if info & 0b0000_1000 != 0 {
+            self.write_sint(y)?;
+            self.modal.geometry_y = y;
+        }
+        Ok(())
+    }
+
+    /// Writes a point list whose first point is the origin, using g-deltas (type 4).
+    fn write_point_list(&mut self, points: &[OasisPoint]) -> OasisResult<()> {
+        self.write_uint(4)?;
+        self.write_uint(points.len() as u64 - 1)?;
+        for pair in points.windows(2) {
+            self.write_gdelta(pair[1] - pair[0])?;
+        }
+        Ok(())
+    }
+
+    /// Writes a repetition if present, reusing the modal repetition if possible.
+    fn write_opt_repetition(&mut self, rep: Option<&OasisRepetition>) -> OasisResult<()> {
+        match rep {
+            Some(rep) if self.modal.repetition.as_ref() == Some(rep) => self.write_uint(0),
+            Some(rep) => {
+                self.write_repetition(rep)?;
+                self.modal.repetition = Some(rep.clone());
+                Ok(())
+            }
+            None => Ok(()),
+        }
+    }
+
+    fn write_repetition(&mut self, rep: &OasisRepetition) -> OasisResult<()> {
+        match *rep {
+            OasisRepetition::Matrix { nx, ny, dx, dy } if dx >= 0 && dy >= 0 => {
+                if nx > 1 && ny > 1 {
+                    self.write_uint(1)?;
+                    self.write_uint(nx - 2)?;
+                    self.write_uint(ny - 2)?;
+                    self.write_uint(dx as u64)?;
+                    self.write_uint(dy as u64)?;
+                } else if nx > 1 {
+                    self.write_uint(2)?;
+                    self.write_uint(nx - 2)?;
+                    self.write_uint(dx as u64)?;
+                } else {
+                    self.write_uint(3)?;
+                    self.write_uint(ny - 2)?;
+                    self.write_uint(dy as u64)?;
+                }
+            }
+            OasisRepetition::Matrix { nx, ny, dx, dy } => {
+                // Negative pitches are only representable as lattices.
+                self.write_lattice(nx, ny, OasisPoint::new(dx, 0), OasisPoint::new(0, dy))?;
+            }
+            OasisRepetition::Lattice { n, m, a, b } => self.write_lattice(n, m, a, b)?,
+            OasisRepetition::Explicit(ref offsets) => {
+                if offsets[0] != OasisPoint::zero() {
+                    return Err(OasisError::Write(format!(
+                        "the first offset of an explicit repetition must be the origin, got {:?}",
+                        offsets[0]
+                    )));
+                }
+                self.write_uint(10)?;
+                self.write_uint(offsets.len() as u64 - 2)?;
+                for pair in offsets.windows(2) {
+                    self.write_gdelta(pair[1] - pair[0])?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn write_lattice(&mut self, n: u64, m: u64, a: OasisPoint, b: OasisPoint) -> OasisResult<()> {
+        if n > 1 && m > 1 {
+            self.write_uint(8)?;
+            self.write_uint(n - 2)?;
+            self.write_uint(m - 2)?;
+            self.write_gdelta(a)?;
+            self.write_gdelta(b)
+        } else if n > 1 {
+            self.write_uint(9)?;
+            self.write_uint(n - 2)?;
+            self.write_gdelta(a)
+        } else {
+            self.write_uint(9)?;
+            self.write_uint(m - 2)?;
+            self.write_gdelta(b)
+        }
+    }
+
+    /// Writes a g-delta, using the compact octangular form where possible.
+    fn write_gdelta(&mut self, delta: OasisPoint) -> OasisResult<()> {
+        let OasisPoint { x, y } = delta;
+        let dir = match (x.signum(), y.signum()) {
+            (_, 0) if x >= 0 => Some(0),
+            (0, 1) => Some(1),
+            (-1, 0) => Some(2),
+            (0, -1) => Some(3),
+            (1, 1) if x == y => Some(4),
+            (-1, 1) if -x == y => Some(5),
+            (-1, -1) if x == y => Some(6),
+            (1, -1) if x == -y => Some(7),
+            _ => None,
+        };
+        match dir {
+            Some(dir) => {
+                let magnitude = x.unsigned_abs().max(y.unsigned_abs());
+                self.write_uint(magnitude << 4 | dir << 1)
+            }
+            None => {
+                let sign = u64::from(x < 0);
+                self.write_uint(x.unsigned_abs() << 2 | sign << 1 | 1)?;
+                self.write_sint(y)
+            }
+        }
+    }
+
+    /// Writes an OASIS real, exactly for integers and as a 64-bit float otherwise.
+    fn write_real(&mut self, value: f64) -> OasisResult<()> {
+        if value.fract() == 0.0 && value.abs() < (1u64 << 53) as f64 {
+            self.write_uint(if value < 0.0 { 1 } else { 0 })?;
+            self.write_uint(value.abs() as u64)
+        } else {
+            self.write_uint(7)?;
+            self.write_bytes(&value.to_le_bytes())
+        }
+    }
+
+    /// Writes a length-prefixed string.
+    fn write_string(&mut self, s: &str) -> OasisResult<()> {
+        self.write_uint(s.len() as u64)?;
+        self.write_bytes(s.as_bytes())
+    }
+
+    /// Writes a signed integer, storing its sign in the least significant bit.
+    fn write_sint(&mut self, value: i64) -> OasisResult<()> {
+        self.write_uint(value.unsigned_abs() << 1 | u64::from(value < 0))
+    }
+
+    /// Writes an unsigned integer in groups of 7 bits, least significant first.
+    fn write_uint(&mut self, mut value: u64) -> OasisResult<()> {
+        loop {
+            let byte = (value & 0x7f) as u8;
+            value >>= 7;
+            if value == 0 {
+                return self.write_byte(byte);
+            }
+            self.write_byte(byte | 0x80)?;
+        }
+    }
+
+    fn write_byte(&mut self, byte: u8) -> OasisResult<()> {
+        self.write_bytes(&[byte])
+    }
+
+    fn write_bytes(&mut self, bytes: &[u8]) -> OasisResult<()> {
+        match self.block {
+            Some(ref mut block) => block.extend_from_slice(bytes),
+            None => self.writer.write_all(bytes)?,
+        }
+        Ok(())
+    }
+}
+
+/// Combines copies of elements that differ only in their position using repetitions.
+///
+/// Combined elements are placed at the position of their first copy in the output.
+fn compress_repetitions(elems: &[OasisElement]) -> Vec<OasisElement> {
+    let mut out: Vec<(OasisElement, Vec<OasisPoint>)> = Vec::with_capacity(elems.len());
+    let mut shapes: HashMap<Shape, usize> = HashMap::new();
+    for elem in elems {
+        match shape(elem) {
+            Some((shape, at)) => match shapes.entry(shape) {
+                Entry::Occupied(entry) => out[*entry.get()].1.push(at),
+                Entry::Vacant(entry) => {
+                    entry.insert(out.len());
+                    out.push((elem.clone(), vec![at]));
+                }
+            },
+            None => out.push((elem.clone(), Vec::new())),
+        }
+    }
+
+    out.into_iter()
+        .map(|(mut elem, positions)| {
+            if positions.len() > 1 {
+                let (origin, rep) = repetition(positions);
+                move_to(&mut elem, origin, rep);
+            }
+            elem
+        })
+        .collect()
+}
+
+/// Splits a non-repeated element into its shape and position.
+///
+/// Returns [`None`] if the element is repeated, has properties, or cannot be written.
+fn shape(elem: &OasisElement) -> Option<(Shape, OasisPoint)> {
+    if elem.repetition().is_some() || !elem.properties().is_empty() {
+        return None;
+    }
+    Some(match elem {
+        OasisElement::Rectangle(x) => (
+            Shape::Rectangle {
+                layer: x.layer,
+                datatype: x.datatype,
+                width: x.width,
+                height: x.height,
+            },
+            OasisPoint::new(x.x, x.y),
+        ),
+        OasisElement::Polygon(x) => (
+            Shape::Polygon {
+                layer: x.layer,
+                datatype: x.datatype,
+                points: relative_points(&x.points),
+            },
+            *x.points.first()?,
+        ),
+        OasisElement::Path(x) => (
+            Shape::Path {
+                layer: x.layer,
+                datatype: x.datatype,
+                half_width: x.half_width,
+                start_extension: x.start_extension,
+                end_extension: x.end_extension,
+                points: relative_points(&x.points),
+            },
+            *x.points.first()?,
+        ),
+        OasisElement::Text(x) => (
+            Shape::Text {
+                string: x.string.clone(),
+                layer: x.layer,
+                texttype: x.texttype,
+            },
+            OasisPoint::new(x.x, x.y),
+        ),
+        OasisElement::Placement(x) => (
+            Shape::Placement {
+                cell: x.cell.clone(),
+                flip: x.flip,
+                angle: x.angle.to_bits(),
+                magnification: x.magnification.to_bits(),
+            },
+            OasisPoint::new(x.x, x.y),
+        ),
+    })
+}
+
+/// Moves an element from its current position to `origin`, and repeats it using `rep`.
+fn move_to(elem: &mut OasisElement, origin: OasisPoint, rep: OasisRepetition) {
+    match elem {
+        OasisElement::Rectangle(x) => {
+            (x.x, x.y) = (origin.x, origin.y);
+            x.repetition = Some(rep);
+        }
+        OasisElement::Polygon(x) => {
+            let delta = origin - x.points[0];
+            x.points.iter_mut().for_each(|p| *p = *p + delta);
+            x.repetition = Some(rep);
+        }
+        OasisElement::Path(x) => {
+            let delta = origin - x.points[0];
+            x.points.iter_mut().for_each(|p| *p = *p + delta);
+            x.repetition = Some(rep);
+        }
+        OasisElement::Text(x) => {
+            (x.x, x.y) = (origin.x, origin.y);
+            x.repetition = Some(rep);
+        }
+        OasisElement::Placement(x) => {
+            (x.x, x.y) = (origin.x, origin.y);
+            x.repetition = Some(rep);
+        }
+    }
+}
+
+/// Finds a repetition producing all of the given positions.
+///
+/// Returns the origin of the repetition, along with the repetition itself.
+/// Positions that form a uniformly spaced grid are described by a [`OasisRepetition::Matrix`],
+/// and all others by an [`OasisRepetition::Explicit`] list of offsets.
+fn repetition(mut positions: Vec<OasisPoint>) -> (OasisPoint, OasisRepetition) {
+    positions.sort_by_key(|p| (p.y, p.x));
+    let mut xs = positions.iter().map(|p| p.x).collect::<Vec<_>>();
+    xs.sort_unstable();
+    xs.dedup();
+    let mut ys = positions.iter().map(|p| p.y).collect::<Vec<_>>();
+    ys.dedup();
+
+    let origin = positions[0];
+    let pitch = |values: &[i64]| match values {
+        [a, b, ..] if values.windows(2).all(|w| w[1] - w[0] == b - a) => Some(b - a),
+        [_] => Some(0),
+        _ => None,
+    };
+    if let (Some(dx), Some(dy)) = (pitch(&xs), pitch(&ys)) {
+        let rep = OasisRepetition::Matrix {
+            nx: xs.len() as u64,
+            ny: ys.len() as u64,
+            dx,
+            dy,
+        };
+        if rep
+            .offsets()
+            .into_iter()
+            .map(|p| p + origin)
+            .eq(positions.iter().copied())
+        {
+            return (origin, rep);
+        }
+    }
+    let offsets = positions.into_iter().map(|p| p - origin).collect();
+    (origin, OasisRepetition::Explicit(offsets))
+}
+
+/// Returns `points` relative to the first point.
+fn relative_points(points: &[OasisPoint]) -> Vec<OasisPoint> {
+    points.iter().map(|&p| p - points[0]).collect()
+}
+
+/// Returns the repetition to write, omitting repetitions that produce only the original element.
+fn effective_repetition(rep: &Option<OasisRepetition>) -> OasisResult<Option<&OasisRepetition>> {
+    match rep {
+        None => Ok(None),
+        Some(rep) if rep.is_empty() => Err(OasisError::Write(
+            "repetitions must produce at least one copy".to_string(),
+        )),
+        Some(rep) if rep.len() == 1 && rep.offsets()[0] == OasisPoint::zero() => Ok(None),
+        Some(rep) if rep.len() == 1 => Err(OasisError::Write(format!(
+            "single-copy repetitions must not be offset, got {:?}",
+            rep.offsets()[0]
+        ))),
+        Some(rep) => Ok(Some(rep)),
+    }
+}
+
+/// The number of bytes used to encode `value` as an OASIS unsigned integer.
+fn uint_len(mut value: u64) -> usize {
+    let mut len = 1;
+    while value >= 0x80 {
+        value >>= 7;
+        len += 1;
+    }
+    len
+}
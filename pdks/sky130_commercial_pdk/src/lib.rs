@@ -4,8 +4,10 @@ use arcstr::ArcStr;
 use sky130_common_pdk::Sky130Pdk;
 use substrate::error::Result;
 use substrate::layout::context::LayoutCtx;
+use substrate::layout::elements::guard_ring::GuardRingParams;
 use substrate::layout::elements::via::ViaParams;
-use substrate::layout::layers::Layers;
+use substrate::layout::fill::FillRules;
+use substrate::layout::layers::{LayerInfo, Layers};
 use substrate::pdk::mos::spec::MosSpec;
 use substrate::pdk::{Pdk, PdkParams, Units};
 use substrate::schematic::context::SchematicCtx;
@@ -79,10 +81,26 @@ impl Pdk for Sky130CommercialPdk {
         Sky130Pdk::via_layout(ctx, params)
     }
 
+    fn guard_ring_layout(&self, ctx: &mut LayoutCtx, params: &GuardRingParams) -> Result<()> {
+        Sky130Pdk::guard_ring_layout(ctx, params)
+    }
+
     fn layout_grid(&self) -> i64 {
         self.inner.layout_grid()
     }
 
+    fn sheet_resistance(&self, layer: &LayerInfo) -> Option<f64> {
+        self.inner.sheet_resistance(&layer.name)
+    }
+
+    fn via_resistance(&self, layer: &LayerInfo) -> Option<f64> {
+        self.inner.via_resistance(&layer.name)
+    }
+
+    fn fill_rules(&self, layer: &LayerInfo) -> Option<FillRules> {
+        self.inner.fill_rules(&layer.name)
+    }
+
     fn includes(
         &self,
         purpose: substrate::schematic::netlist::NetlistPurpose,
@@ -11,6 +11,15 @@ pub const DIFF_PSDM_ENCLOSURE: i64 = 125;
 pub const DIFF_NSDM_ENCLOSURE: i64 = 125;
 pub const POLY_DIFF_EXTENSION: i64 = 130;
 pub const NPC_LICON_POLY_ENCLOSURE: i64 = 100;
+pub const LICON_SPACE: i64 = 170;
+pub const LICON_TAP_ENCLOSURE: i64 = 60;
+pub const LICON_TAP_END_ENCLOSURE: i64 = 120;
+pub const MCON_WIDTH: i64 = 170;
+pub const MCON_SPACE: i64 = 190;
+pub const MCON_MET1_ENCLOSURE: i64 = 60;
+pub const TAP_NWELL_ENCLOSURE: i64 = 180;
+pub const DNWELL_NWELL_ENCLOSURE: i64 = 400;
+pub const DNWELL_NWELL_OVERLAP: i64 = 1030;
 
 const fn max(a: i64, b: i64) -> i64 {
     [a, b][(a < b) as usize]
@@ -22,3 +31,4 @@ pub const DIFF_EDGE_TO_GATE: i64 = max(
 );
 pub const FINGER_SPACE: i64 = max(2 * GATE_LICON_SPACE + LI1_WIDTH, POLY_SPACE);
 pub const DIFF_TO_OPPOSITE_DIFF: i64 = DIFF_NWELL_SPACE + DIFF_NWELL_ENCLOSURE;
+pub const GUARD_RING_WIDTH: i64 = LICON_WIDTH + 2 * LICON_TAP_ENCLOSURE;
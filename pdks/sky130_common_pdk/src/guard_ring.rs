@@ -0,0 +1,170 @@
+use subgeom::bbox::BoundBox;
+use subgeom::ring::Ring;
+use subgeom::{snap_to_grid, Dir, Rect, Side, Span};
+use substrate::error::{ErrorSource, Result};
+use substrate::layout::cell::CellPort;
+use substrate::layout::context::LayoutCtx;
+use substrate::layout::elements::guard_ring::{GuardRingKind, GuardRingParams, GUARD_RING_PORT};
+use substrate::layout::layers::selector::Selector;
+use substrate::layout::layers::LayerKey;
+
+use crate::constants::{
+    DIFF_NSDM_ENCLOSURE, DIFF_PSDM_ENCLOSURE, DIFF_SPACE, DNWELL_NWELL_ENCLOSURE,
+    DNWELL_NWELL_OVERLAP, GUARD_RING_WIDTH, LICON_SPACE, LICON_TAP_END_ENCLOSURE, LICON_WIDTH,
+    MCON_MET1_ENCLOSURE, MCON_SPACE, MCON_WIDTH, TAP_NWELL_ENCLOSURE,
+};
+use crate::Sky130Pdk;
+
+impl Sky130Pdk {
+    pub fn guard_ring_layout(ctx: &mut LayoutCtx, params: &GuardRingParams) -> Result<()> {
+        let width = params.width.unwrap_or(GUARD_RING_WIDTH);
+        if width < GUARD_RING_WIDTH {
+            return Err(ErrorSource::InvalidArgs(format!(
+                "guard ring width must be at least {GUARD_RING_WIDTH}, got {width}"
+            ))
+            .into());
+        }
+        if params.deep_nwell && params.kind != GuardRingKind::NWell {
+            return Err(ErrorSource::InvalidArgs(
+                "deep N-well is only supported for N-well guard rings".to_string(),
+            )
+            .into());
+        }
+
+        let layers = ctx.layers();
+        let tap = layers.get(Selector::Name("tap"))?;
+        let licon = layers.get(Selector::Name("licon1"))?;
+        let li1 = layers.get(Selector::Metal(0))?;
+        let mcon = layers.get(Selector::Name("mcon"))?;
+        let met1 = layers.get(Selector::Metal(1))?;
+
+        let ring = Ring::builder()
+            .inner(params.enclosed.expand(DIFF_SPACE))
+            .uniform_width(width)
+            .build();
+
+        for rect in ring.rects() {
+            ctx.draw_rect(tap, rect);
+            ctx.draw_rect(li1, rect);
+            ctx.draw_rect(met1, rect);
+        }
+
+        draw_cuts(
+            ctx,
+            &ring,
+            licon,
+            LICON_WIDTH,
+            LICON_SPACE,
+            LICON_TAP_END_ENCLOSURE,
+        );
+        draw_cuts(
+            ctx,
+            &ring,
+            mcon,
+            MCON_WIDTH,
+            MCON_SPACE,
+            MCON_MET1_ENCLOSURE,
+        );
+
+        match params.kind {
+            GuardRingKind::PSub => {
+                let psdm = layers.get(Selector::Name("psdm"))?;
+                draw_expanded(ctx, &ring, psdm, DIFF_PSDM_ENCLOSURE);
+            }
+            GuardRingKind::NWell => {
+                let nsdm = layers.get(Selector::Name("nsdm"))?;
+                let nwell = layers.get(Selector::Name("nwell"))?;
+                draw_expanded(ctx, &ring, nsdm, DIFF_NSDM_ENCLOSURE);
+
+                if params.deep_nwell {
+                    let dnwell = layers.get(Selector::Name("dnwell"))?;
+                    let dnwell_rect = ring.outer().shrink(width / 2);
+                    ctx.draw_rect(dnwell, dnwell_rect);
+
+                    // The N-well ring straddles the edge of the deep N-well.
+                    let outer = ring
+                        .outer()
+                        .expand(TAP_NWELL_ENCLOSURE)
+                        .union(dnwell_rect.expand(DNWELL_NWELL_ENCLOSURE).bbox())
+                        .into_rect();
+                    let inner = ring
+                        .inner()
+                        .shrink(TAP_NWELL_ENCLOSURE)
+                        .intersection(dnwell_rect.shrink(DNWELL_NWELL_OVERLAP).bbox())
+                        .into_rect();
+                    let nwell_ring = Ring::builder()
+                        .outer(outer)
+                        .left_width(inner.left() - outer.left())
+                        .right_width(outer.right() - inner.right())
+                        .bot_height(inner.bottom() - outer.bottom())
+                        .top_height(outer.top() - inner.top())
+                        .build();
+                    for rect in nwell_ring.rects() {
+                        ctx.draw_rect(nwell, rect);
+                    }
+                } else {
+                    ctx.draw_rect(nwell, ring.outer().expand(TAP_NWELL_ENCLOSURE));
+                }
+            }
+        }
+
+        let mut port = CellPort::new(GUARD_RING_PORT);
+        for rect in ring.rects() {
+            port.add(li1, rect.into());
+            port.add(met1, rect.into());
+        }
+        ctx.add_port(port)?;
+
+        Ok(())
+    }
+}
+
+/// Draws a ring on `layer` that extends `enclosure` beyond `ring` on all sides.
+fn draw_expanded(ctx: &mut LayoutCtx, ring: &Ring, layer: LayerKey, enclosure: i64) {
+    let expanded = Ring::builder()
+        .inner(ring.inner().shrink(enclosure))
+        .uniform_width(ring.left().width() + 2 * enclosure)
+        .build();
+    for rect in expanded.rects() {
+        ctx.draw_rect(layer, rect);
+    }
+}
+
+/// Draws a single row of square cuts centered along each side of `ring`.
+///
+/// Cuts on the left and right sides avoid the corners, which are
+/// covered by the cuts on the top and bottom sides.
+fn draw_cuts(
+    ctx: &mut LayoutCtx,
+    ring: &Ring,
+    layer: LayerKey,
+    size: i64,
+    space: i64,
+    end_enclosure: i64,
+) {
+    let grid = ctx.pdk().layout_grid();
+    for side in [Side::Top, Side::Bot, Side::Left, Side::Right] {
+        let rect = match side {
+            Side::Top | Side::Bot => ring.rect(side),
+            Side::Left | Side::Right => ring.inner_rect(side).expand_dir(Dir::Vert, -space),
+        };
+        let dir = side.edge_dir();
+        let span = rect.span(dir);
+        let avail = span.length() - 2 * end_enclosure;
+        if avail < size {
+            continue;
+        }
+        let n = (avail + space) / (size + space);
+        let used = n * size + (n - 1) * space;
+        let start = span.start() + snap_to_grid((span.length() - used) / 2, grid);
+        let center = rect.span(!dir).center();
+        for i in 0..n {
+            let lo = start + i * (size + space);
+            let cut = Rect::span_builder()
+                .with(dir, Span::new(lo, lo + size))
+                .with(!dir, Span::from_center_span(center, size))
+                .build();
+            ctx.draw_rect(layer, cut);
+        }
+    }
+}
@@ -14,10 +14,16 @@ impl Sky130Pdk {
                     } else {
                         name[3..].parse::<usize>().unwrap()
                     };
+                    let magic_name = if num == 0 {
+                        "locali".to_string()
+                    } else {
+                        format!("metal{num}")
+                    };
                     LayerInfo::builder()
                         .route_idx(num)
                         .metal_idx(num)
                         .layer_type(LayerType::Metal)
+                        .magic_name(magic_name)
                         .build()
                         .unwrap()
                 } else if name.starts_with("via") || name == "mcon" {
@@ -28,9 +34,14 @@ impl Sky130Pdk {
                     } else {
                         name[3..].parse::<usize>().unwrap()
                     };
+                    let magic_name = match num {
+                        0 => "viali".to_string(),
+                        n => format!("via{n}"),
+                    };
                     LayerInfo::builder()
                         .via_idx(num)
                         .layer_type(LayerType::Via)
+                        .magic_name(magic_name)
                         .build()
                         .unwrap()
                 } else {
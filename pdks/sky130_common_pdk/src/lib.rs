@@ -1,11 +1,13 @@
 use std::collections::HashMap;
 use std::path::PathBuf;
 
+use substrate::layout::fill::FillRules;
 use substrate::pdk::corner::{CornerData, CornerDb, CornerSkew};
 use substrate::pdk::{Supplies, Supply, SupplyId, Units};
 use substrate::units::SiPrefix;
 
 pub mod constants;
+pub mod guard_ring;
 pub mod layers;
 pub mod mos;
 pub mod stdcells;
@@ -50,6 +52,52 @@ impl Sky130Pdk {
         5
     }
 
+    /// Typical sheet resistances of SKY 130 interconnect layers, in ohms per square.
+    pub fn sheet_resistance(&self, name: &str) -> Option<f64> {
+        match name {
+            "li1" => Some(12.8),
+            "met1" | "met2" => Some(0.125),
+            "met3" | "met4" => Some(0.047),
+            "met5" => Some(0.029),
+            _ => None,
+        }
+    }
+
+    /// Typical resistances of a single SKY 130 via cut, in ohms.
+    pub fn via_resistance(&self, name: &str) -> Option<f64> {
+        match name {
+            "mcon" => Some(9.3),
+            "via" => Some(4.5),
+            "via2" | "via3" => Some(3.41),
+            "via4" => Some(0.38),
+            _ => None,
+        }
+    }
+
+    /// Approximate density and fill rules for SKY 130 poly and metal layers.
+    ///
+    /// Densities are checked in 700um windows stepped by 70um.
+    /// Poly fill is only kept away from drawn poly; it is the caller's
+    /// responsibility to block fill over diffusion.
+    pub fn fill_rules(&self, name: &str) -> Option<FillRules> {
+        let (min_density, max_density, fill, fill_spacing, space_to_drawn) = match name {
+            "poly" => (0.15, 0.56, 1_000, 500, 1_000),
+            "met1" | "met2" => (0.3, 0.8, 3_000, 300, 500),
+            "met3" | "met4" => (0.3, 0.8, 4_000, 400, 800),
+            _ => return None,
+        };
+        Some(FillRules {
+            min_density,
+            max_density,
+            window: 700_000,
+            window_step: 70_000,
+            fill_width: fill,
+            fill_height: fill,
+            fill_spacing,
+            space_to_drawn,
+        })
+    }
+
     pub fn corners(&self) -> CornerDb {
         let mut db = CornerDb::new();
         let tt = CornerData::builder()
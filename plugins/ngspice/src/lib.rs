@@ -5,6 +5,7 @@ use std::process::Command;
 use anyhow::{bail, Result};
 use spice_rawfile::Rawfile;
 use substrate::error::ErrorSource;
+use substrate::verification::simulation::oppoint::{MosOpParam, MosOpPoint, MosOpTarget};
 use substrate::verification::simulation::{
     AcAnalysis, AcData, Analysis, AnalysisData, AnalysisType, DcAnalysis, DcData, OpAnalysis,
     OpData, Quantity, RealSignal, ScalarSignal, SimInput, SimOutput, Simulator, SimulatorOpts,
@@ -76,6 +77,28 @@ impl Simulator for Ngspice {
     }
 }
 
+/// Formats the name of a MOSFET operating point vector, such as `@m.x1.m0[gm]`.
+///
+/// Elements beginning with `X` are subcircuits wrapping a single MOSFET
+/// named `M` followed by the model name.
+fn mos_op_string(target: &MosOpTarget, param: MosOpParam) -> String {
+    let mut s = String::from("@m");
+    for inst in target.path.iter() {
+        s.push_str(".x");
+        s.push_str(inst);
+    }
+    s.push('.');
+    s.push_str(&target.element);
+    if target.element.starts_with(['x', 'X']) {
+        s.push_str(".m");
+        s.push_str(&target.model);
+    }
+    s.push('[');
+    s.push_str(param.name());
+    s.push(']');
+    s.to_lowercase()
+}
+
 fn get_analyses(input: &[Analysis]) -> Result<Vec<String>> {
     input.iter().map(analysis_line).collect()
 }
@@ -88,6 +111,16 @@ fn get_directives(input: &SimInput) -> Vec<String> {
     if let Some(t) = input.opts.tnom {
         directives.push(format!(".options tnom={t}"));
     }
+    if !input.mos_op.is_empty() {
+        // Device parameters are only saved when requested explicitly,
+        // and any explicit save disables the default of saving all nodes.
+        directives.push(String::from(".save all"));
+        for target in input.mos_op.iter() {
+            for param in MosOpParam::ALL {
+                directives.push(format!(".save {}", mos_op_string(target, param)));
+            }
+        }
+    }
     directives
 }
 
@@ -137,7 +170,7 @@ fn arrange_rawfile(input: &SimInput, raw: Rawfile) -> Result<Vec<AnalysisData>>
             .enumerate()
             .find(|(_, a)| a.analysis_type() == t)
             .unwrap();
-        out[idx] = parse_analysis(ian, an)?;
+        out[idx] = parse_analysis(input, ian, an)?;
     }
     Ok(out)
 }
@@ -159,11 +192,11 @@ fn atype(raw: &spice_rawfile::parser::Analysis) -> AnalysisType {
 
 use spice_rawfile::parser::Analysis as RawAnalysis;
 
-fn parse_analysis(input: &Analysis, output: RawAnalysis) -> Result<AnalysisData> {
+fn parse_analysis(sim: &SimInput, input: &Analysis, output: RawAnalysis) -> Result<AnalysisData> {
     Ok(match input {
         Analysis::Ac(ac) => AnalysisData::Ac(parse_ac(ac, output)),
         Analysis::Tran(tran) => AnalysisData::Tran(parse_tran(tran, output)),
-        Analysis::Op(op) => AnalysisData::Op(parse_op(op, output)),
+        Analysis::Op(op) => AnalysisData::Op(parse_op(op, &sim.mos_op, output)?),
         Analysis::Dc(dc) => AnalysisData::Dc(parse_dc(dc, output)),
         Analysis::MonteCarlo(_) => bail!("ngspice plugin does not support Monte Carlo analyses"),
     })
@@ -237,7 +270,7 @@ fn parse_dc(_input: &DcAnalysis, output: RawAnalysis) -> DcData {
     DcData { data: map }
 }
 
-fn parse_op(_input: &OpAnalysis, output: RawAnalysis) -> OpData {
+fn parse_op(_input: &OpAnalysis, mos_op: &[MosOpTarget], output: RawAnalysis) -> Result<OpData> {
     let data = output.data.unwrap_real();
     let mut map = HashMap::with_capacity(output.variables.len());
     for (sig, var) in data.into_iter().zip(output.variables.iter()) {
@@ -249,7 +282,27 @@ fn parse_op(_input: &OpAnalysis, output: RawAnalysis) -> OpData {
         map.insert(var.name.trim().to_string(), sig);
     }
 
-    OpData { data: map }
+    let values = map
+        .iter()
+        .map(|(name, sig)| (name.to_lowercase(), sig.value))
+        .collect::<HashMap<_, _>>();
+    let mut mos = HashMap::with_capacity(mos_op.len());
+    for target in mos_op {
+        let op = MosOpPoint::from_params(target.kind, None, |param| {
+            values.get(&mos_op_string(target, param)).copied()
+        });
+        match op {
+            Some(op) => {
+                mos.insert(target.instance(), op);
+            }
+            None => bail!(
+                "missing operating point of MOSFET {}",
+                mos_op_string(target, MosOpParam::Id)
+            ),
+        }
+    }
+
+    Ok(OpData { data: map, mos })
 }
 
 fn parse_qty(name: &str) -> Quantity {
@@ -259,6 +312,8 @@ fn parse_qty(name: &str) -> Quantity {
         "frequency" => Quantity::Frequency,
         "time" => Quantity::Time,
         "temp" | "temp-sweep" | "temperature" => Quantity::Temperature,
+        // Device parameters, such as MOSFET transconductance, have no quantity.
+        "notype" => Quantity::Unknown,
         _ => panic!("unknown quantity"),
     }
 }
@@ -1,5 +1,8 @@
 use std::path::PathBuf;
 
+use substrate::deps::arcstr::ArcStr;
+use substrate::pdk::mos::spec::MosKind;
+use substrate::verification::simulation::oppoint::{MosOpParam, MosOpTarget};
 use substrate::verification::simulation::{
     AcAnalysis, Analysis, AnalysisType, DcAnalysis, OpAnalysis, SimInput, Simulator, SimulatorOpts,
     SweepMode, TranAnalysis,
@@ -61,3 +64,27 @@ fn vdivider_test() {
     assert_eq!(out.data[2].analysis_type(), AnalysisType::Ac);
     assert_eq!(out.data[3].analysis_type(), AnalysisType::Dc);
 }
+
+#[test]
+fn mos_op_string_test() {
+    let target = MosOpTarget {
+        path: vec![ArcStr::from("dut"), ArcStr::from("n1")],
+        element: ArcStr::from("X0"),
+        model: ArcStr::from("sky130_fd_pr__nfet_01v8"),
+        kind: MosKind::Nmos,
+    };
+    assert_eq!(
+        crate::mos_op_string(&target, MosOpParam::Gm),
+        "@m.xdut.xn1.x0.msky130_fd_pr__nfet_01v8[gm]"
+    );
+
+    let target = MosOpTarget {
+        path: vec![ArcStr::from("1")],
+        element: ArcStr::from("M0"),
+        ..target
+    };
+    assert_eq!(
+        crate::mos_op_string(&target, MosOpParam::Vdsat),
+        "@m.x1.m0[vdsat]"
+    );
+}
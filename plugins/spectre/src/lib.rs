@@ -10,6 +10,9 @@ use psfparser::analysis::ac::AcData as PsfAcData;
 use psfparser::analysis::dc::DcData as PsfDcData;
 use psfparser::analysis::transient::TransientData;
 use serde::Serialize;
+use substrate::error::ErrorSource;
+use substrate::schematic::netlist::interface::NetlistFormat;
+use substrate::verification::simulation::oppoint::{MosOpPoint, MosOpTarget, MosRegion};
 use substrate::verification::simulation::{
     AcData, Analysis, AnalysisData, AnalysisType, ComplexSignal, DcData, MonteCarloData, OpData,
     OutputFormat, Quantity, RealSignal, Save, ScalarSignal, SimInput, SimOutput, Simulator,
@@ -21,6 +24,13 @@ use tera::{Context, Tera};
 pub const TOP_NETLIST_NAME: &str = "sim.top.spice";
 pub const BASE_ANALYSIS_PREFIX: &str = "analysis";
 
+/// The [`SimulatorOpts`] key selecting the format of the netlists passed to [`Spectre`].
+///
+/// Set to `spectre` if the netlists are written by a
+/// [`SpectreNetlister`](substrate::schematic::netlist::impls::spectre::SpectreNetlister),
+/// or to `spectre-spice` (the default) if they are written in SPICE syntax.
+pub const NETLIST_FORMAT_OPT: &str = "netlist_format";
+
 lazy_static! {
     pub static ref TEMPLATES: Tera =
         match Tera::new(concat!(env!("CARGO_MANIFEST_DIR"), "/templates/*")) {
@@ -86,21 +96,76 @@ fn dc_conv(parsed_data: PsfDcData) -> DcData {
     }
 }
 
-fn op_conv(parsed_data: PsfDcData) -> OpData {
-    OpData {
-        data: match parsed_data {
-            PsfDcData::Op(data) => HashMap::from_iter(data.signals.into_iter().map(|(k, v)| {
-                (
-                    k,
-                    ScalarSignal {
-                        value: v,
-                        quantity: Quantity::Unknown,
-                    },
-                )
-            })),
-            PsfDcData::Sweep(_) => panic!("expected op analysis, found a dc sweep"),
-        },
+fn op_conv(
+    parsed_data: PsfDcData,
+    mos_op: &[MosOpTarget],
+    netlist_format: &NetlistFormat,
+) -> Result<OpData> {
+    let data: HashMap<String, ScalarSignal> = match parsed_data {
+        PsfDcData::Op(data) => HashMap::from_iter(data.signals.into_iter().map(|(k, v)| {
+            (
+                k,
+                ScalarSignal {
+                    value: v,
+                    quantity: Quantity::Unknown,
+                },
+            )
+        })),
+        PsfDcData::Sweep(_) => panic!("expected op analysis, found a dc sweep"),
+    };
+
+    let values = data
+        .iter()
+        .map(|(name, sig)| (name.to_lowercase(), sig.value))
+        .collect::<HashMap<_, _>>();
+    let mut mos = HashMap::with_capacity(mos_op.len());
+    for target in mos_op {
+        let name = mos_instance_name(target, netlist_format).to_lowercase();
+        let region = values
+            .get(&format!("{name}:region"))
+            .and_then(|&region| MosRegion::from_spectre(region));
+        let op = MosOpPoint::from_params(target.kind, region, |param| {
+            values.get(&format!("{name}:{}", param.name())).copied()
+        });
+        match op {
+            Some(op) => {
+                mos.insert(target.instance(), op);
+            }
+            None => bail!("missing operating point of MOSFET {name}"),
+        }
+    }
+
+    Ok(OpData { data, mos })
+}
+
+/// Returns the prefix of subcircuit instance names in netlists of the given format.
+///
+/// SPICE subcircuit instances begin with `X`, while native Spectre instances are
+/// named as-is.
+fn instance_prefix(netlist_format: &NetlistFormat) -> &'static str {
+    match netlist_format {
+        NetlistFormat::Spectre => "",
+        _ => "X",
+    }
+}
+
+/// Formats the hierarchical name of a MOSFET, such as `X1.M0`.
+///
+/// Elements beginning with `X` are subcircuits wrapping a single MOSFET
+/// named `M` followed by the model name.
+fn mos_instance_name(target: &MosOpTarget, netlist_format: &NetlistFormat) -> String {
+    let mut s = String::new();
+    for inst in target.path.iter() {
+        s.push_str(instance_prefix(netlist_format));
+        s.push_str(inst);
+        s.push('.');
+    }
+    s.push_str(&target.element);
+    if target.element.starts_with(['x', 'X']) {
+        s.push_str(".m");
+        s.push_str(&target.model);
     }
+    s
 }
 
 fn analysis_name(prefix: &str, num: usize) -> String {
@@ -111,15 +176,27 @@ pub(crate) mod templates;
 #[cfg(test)]
 mod tests;
 
-pub struct Spectre {}
+pub struct Spectre {
+    netlist_format: NetlistFormat,
+}
 
 struct SpectreOutputParser<'a> {
     raw_output_dir: &'a Path,
+    mos_op: &'a [MosOpTarget],
+    netlist_format: &'a NetlistFormat,
 }
 
 impl<'a> SpectreOutputParser<'a> {
-    fn new(raw_output_dir: &'a Path) -> Self {
-        Self { raw_output_dir }
+    fn new(
+        raw_output_dir: &'a Path,
+        mos_op: &'a [MosOpTarget],
+        netlist_format: &'a NetlistFormat,
+    ) -> Self {
+        Self {
+            raw_output_dir,
+            mos_op,
+            netlist_format,
+        }
     }
 
     fn parse_analysis(
@@ -178,7 +255,9 @@ impl<'a> SpectreOutputParser<'a> {
                     AnalysisType::Ac => ac_conv(PsfAcData::from_ast(&ast)).into(),
                     AnalysisType::Tran => tran_conv(TransientData::from_ascii(&ast)).into(),
                     AnalysisType::Dc => dc_conv(PsfDcData::from_ast(&ast)).into(),
-                    AnalysisType::Op => op_conv(PsfDcData::from_ast(&ast)).into(),
+                    AnalysisType::Op => {
+                        op_conv(PsfDcData::from_ast(&ast), self.mos_op, self.netlist_format)?.into()
+                    }
                     _ => bail!("spectre plugin only supports transient, ac, and dc simulations"),
                 })
             }
@@ -224,7 +303,7 @@ fn generate_paths(work_dir: impl AsRef<Path>) -> Paths {
     }
 }
 
-fn save_directives(input: &SimInput, directives: &mut Vec<String>) {
+fn save_directives(input: &SimInput, netlist_format: &NetlistFormat, directives: &mut Vec<String>) {
     match &input.save {
         Save::Signals(s) => {
             directives.reserve(s.len());
@@ -235,6 +314,12 @@ fn save_directives(input: &SimInput, directives: &mut Vec<String>) {
         Save::All => directives.push("opsaveall options save=allpub".to_string()),
         Save::None => directives.push("opsavenone options save=none".to_string()),
     }
+    for target in input.mos_op.iter() {
+        directives.push(format!(
+            "save {}:oppoint",
+            mos_instance_name(target, netlist_format)
+        ));
+    }
 }
 
 fn temp_directives(input: &SimInput, directives: &mut Vec<String>) {
@@ -261,7 +346,7 @@ fn ic_directives(input: &SimInput, directives: &mut Vec<String>) {
     directives.push(ic);
 }
 
-pub fn run_spectre(input: &SimInput) -> Result<Vec<AnalysisData>> {
+pub fn run_spectre(input: &SimInput, netlist_format: &NetlistFormat) -> Result<Vec<AnalysisData>> {
     let work_dir = &input.work_dir;
     let paths = generate_paths(work_dir);
 
@@ -269,7 +354,7 @@ pub fn run_spectre(input: &SimInput) -> Result<Vec<AnalysisData>> {
     let analyses = get_analyses(&input.analyses)?;
 
     let mut spectre_directives = vec!["oppreserveall options preserve_inst=all".to_string()];
-    save_directives(input, &mut spectre_directives);
+    save_directives(input, netlist_format, &mut spectre_directives);
     temp_directives(input, &mut spectre_directives);
 
     let mut spice_directives = Vec::new();
@@ -303,7 +388,8 @@ pub fn run_spectre(input: &SimInput) -> Result<Vec<AnalysisData>> {
         bail!("Spectre exited unsuccessfully");
     }
 
-    SpectreOutputParser::new(&paths.raw_output_dir).parse_analyses(input)
+    SpectreOutputParser::new(&paths.raw_output_dir, &input.mos_op, netlist_format)
+        .parse_analyses(input)
 }
 
 fn output_format_name<'a>(input: &SimInput, format: &'a OutputFormat) -> &'a str {
@@ -362,18 +448,28 @@ fn write_run_script(paths: &Paths, input: &SimInput) -> Result<()> {
 }
 
 impl Simulator for Spectre {
-    fn new(_opts: SimulatorOpts) -> substrate::error::Result<Self>
+    fn new(opts: SimulatorOpts) -> substrate::error::Result<Self>
     where
         Self: Sized,
     {
-        Ok(Self {})
+        let netlist_format = match opts.opts.get(NETLIST_FORMAT_OPT).map(String::as_str) {
+            None | Some("spectre-spice") => NetlistFormat::SpectreSpice,
+            Some("spectre") => NetlistFormat::Spectre,
+            Some(format) => {
+                return Err(ErrorSource::InvalidArgs(format!(
+                    "unsupported Spectre netlist format: {format}"
+                ))
+                .into())
+            }
+        };
+        Ok(Self { netlist_format })
     }
 
     fn simulate(&self, input: SimInput) -> substrate::error::Result<SimOutput> {
         if input.analyses.is_empty() {
             return Ok(SimOutput { data: Vec::new() });
         }
-        let data = run_spectre(&input)?;
+        let data = run_spectre(&input, &self.netlist_format)?;
         Ok(SimOutput { data })
     }
 
@@ -385,7 +481,7 @@ impl Simulator for Spectre {
 
         let mut s = String::new();
         for inst in path.insts.iter() {
-            s.push('X');
+            s.push_str(instance_prefix(&self.netlist_format));
             s.push_str(inst);
             s.push('.');
         }
@@ -2,12 +2,13 @@ use std::path::PathBuf;
 
 use approx::abs_diff_eq;
 use statrs::statistics::Statistics;
+use substrate::schematic::signal::NamedSignalPathBuf;
 use substrate::verification::simulation::{
     AcAnalysis, Analysis, AnalysisType, MonteCarloAnalysis, OpAnalysis, SimInput, Simulator,
     SimulatorOpts, SweepMode, TranAnalysis, Variations,
 };
 
-use crate::Spectre;
+use crate::{Spectre, NETLIST_FORMAT_OPT};
 
 pub(crate) const TEST_BUILD_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/build");
 pub(crate) const EXAMPLES_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/examples");
@@ -127,3 +128,26 @@ fn vdivider_test() {
     assert!(abs_diff_eq!(vout_avg, 0.6, epsilon = 0.004));
     assert!(abs_diff_eq!(vout_stddev, 0.08, epsilon = 0.002));
 }
+
+#[test]
+fn node_voltage_string_netlist_format() {
+    let path = NamedSignalPathBuf {
+        insts: vec!["dut".into(), "inv0".into()],
+        signal: "din".into(),
+        idx: Some(3),
+    };
+
+    let spice = Spectre::new(SimulatorOpts::default()).unwrap();
+    assert_eq!(spice.node_voltage_string(&path), "Xdut.Xinv0.din[3]");
+
+    let opts = SimulatorOpts {
+        opts: [(NETLIST_FORMAT_OPT.to_string(), "spectre".to_string())].into(),
+    };
+    let native = Spectre::new(opts).unwrap();
+    assert_eq!(native.node_voltage_string(&path), "dut.inv0.din[3]");
+
+    let opts = SimulatorOpts {
+        opts: [(NETLIST_FORMAT_OPT.to_string(), "verilog".to_string())].into(),
+    };
+    assert!(Spectre::new(opts).is_err());
+}
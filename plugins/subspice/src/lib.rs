@@ -1,5 +1,5 @@
 use error::{Error, Result};
-use parser::{SpiceLine, SubcktLine};
+use parser::{InstanceLine, SpiceLine, SubcktLine};
 use serde::Serialize;
 
 pub mod error;
@@ -44,4 +44,33 @@ impl<'a> ParsedSpice<'a> {
         let name = name.as_ref();
         self.subcircuits().find(|ckt| ckt.name == name)
     }
+
+    /// Return the lines between the definition of the subcircuit
+    /// with the given name and the next `.ends` statement.
+    ///
+    /// Like [`subcircuit_named`](ParsedSpice::subcircuit_named),
+    /// this operation takes `O(N)` time.
+    pub fn subcircuit_body(&self, name: impl AsRef<str>) -> Option<&[SpiceLine<'a>]> {
+        let name = name.as_ref();
+        let start = self
+            .lines
+            .iter()
+            .position(|line| matches!(line, SpiceLine::Subckt(ckt) if ckt.name == name))?
+            + 1;
+        let len = self.lines[start..]
+            .iter()
+            .position(|line| matches!(line, SpiceLine::Ends(_)))
+            .unwrap_or(self.lines.len() - start);
+        Some(&self.lines[start..start + len])
+    }
+
+    /// Return an iterator over the instances in the body of the subcircuit with the given name.
+    ///
+    /// The iterator is empty if no such subcircuit exists.
+    pub fn instances_of(&self, name: impl AsRef<str>) -> impl Iterator<Item = &InstanceLine> {
+        self.subcircuit_body(name)
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|line| line.instance())
+    }
 }
@@ -2,9 +2,9 @@ use std::str;
 
 use nom::branch::alt;
 use nom::bytes::complete::{tag_no_case, take_till, take_till1};
-use nom::character::complete::{line_ending, multispace0, space0, space1};
-use nom::character::streaming::char;
-use nom::combinator::opt;
+use nom::character::complete::{char, line_ending, multispace0, space0, space1};
+use nom::combinator::{map, opt, recognize};
+use nom::error::{Error, ErrorKind};
 use nom::multi::{many0, many1};
 use nom::sequence::{delimited, pair, preceded, tuple};
 use nom::IResult;
@@ -16,8 +16,17 @@ mod tests;
 #[derive(Debug, Clone, PartialEq, Serialize)]
 pub enum SpiceLine<'a> {
     Subckt(SubcktLine<'a>),
+    /// The end of a subcircuit definition, optionally followed by the subcircuit name.
+    Ends(Option<&'a str>),
+    Instance(InstanceLine<'a>),
+    /// A `.param` statement.
+    Param(Vec<Param<'a>>),
+    /// An `.include` statement, with any quotes removed from the path.
+    Include(&'a str),
+    Lib(LibLine<'a>),
     Comment(&'a str),
-    Other,
+    /// Any other line, such as an unsupported element or control statement, as raw text.
+    Other(&'a str),
 }
 
 impl<'a> SpiceLine<'a> {
@@ -27,12 +36,80 @@ impl<'a> SpiceLine<'a> {
             _ => None,
         }
     }
+
+    pub fn instance(&self) -> Option<&InstanceLine> {
+        match self {
+            SpiceLine::Instance(line) => Some(line),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Eq, PartialEq, Serialize)]
 pub struct SubcktLine<'a> {
     pub name: &'a str,
     pub ports: Vec<&'a str>,
+    /// Parameters declared with their default values.
+    pub params: Vec<Param<'a>>,
+}
+
+/// A `name=value` parameter assignment.
+///
+/// Values are stored verbatim, so expressions keep their enclosing braces or quotes.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize)]
+pub struct Param<'a> {
+    pub name: &'a str,
+    pub value: &'a str,
+}
+
+/// The kind of device described by an [`InstanceLine`],
+/// as determined by the first letter of its name.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Serialize)]
+pub enum InstanceKind {
+    Mos,
+    Resistor,
+    Capacitor,
+    Inductor,
+    Diode,
+    Vsource,
+    Isource,
+    Subckt,
+}
+
+/// A device or subcircuit instance.
+///
+/// | Kind | Nodes | Model |
+/// | ---- | ----- | ----- |
+/// | `M` | `d g s b` | Required |
+/// | `R`, `C`, `L` | `p n` | Optional |
+/// | `D` | `a c` | Required |
+/// | `V`, `I` | `p n` | None |
+/// | `X` | Any number | The subcircuit name |
+///
+/// Any positional arguments after the model (or after the nodes, if there is no model)
+/// are stored in `values`, such as the resistance of a resistor or the `DC 1.8`
+/// specification of a voltage source.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize)]
+pub struct InstanceLine<'a> {
+    pub kind: InstanceKind,
+    pub name: &'a str,
+    pub nodes: Vec<&'a str>,
+    pub model: Option<&'a str>,
+    pub values: Vec<&'a str>,
+    pub params: Vec<Param<'a>>,
+}
+
+/// A `.lib path section` statement, with any quotes removed from the path.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize)]
+pub struct LibLine<'a> {
+    pub path: &'a str,
+    pub section: &'a str,
+}
+
+/// A positional value or parameter assignment.
+enum Arg<'a> {
+    Value(&'a str),
+    Param(Param<'a>),
 }
 
 fn is_newline(c: char) -> bool {
@@ -43,6 +120,10 @@ fn is_space_or_line(c: char) -> bool {
     c == '\n' || c == '\r' || c == ' ' || c == '\t'
 }
 
+fn is_word_end(c: char) -> bool {
+    is_space_or_line(c) || c == '=' || c == '(' || c == ';'
+}
+
 fn within_line_space1(input: &str) -> IResult<&str, ()> {
     let (input, _) = space1(input)?;
     Ok((input, ()))
@@ -79,19 +160,160 @@ fn ident(input: &str) -> IResult<&str, &str> {
     take_till1(is_space_or_line)(input)
 }
 
-fn subckt_ports(input: &str) -> IResult<&str, Vec<&str>> {
-    many0(preceded(spice_space1, ident))(input)
+fn delimited_group(open: char, close: char) -> impl FnMut(&str) -> IResult<&str, &str> {
+    move |input| recognize(tuple((char(open), take_till(|c| c == close), char(close))))(input)
+}
+
+fn word(input: &str) -> IResult<&str, &str> {
+    recognize(pair(
+        take_till1(is_word_end),
+        opt(delimited_group('(', ')')),
+    ))(input)
+}
+
+fn value(input: &str) -> IResult<&str, &str> {
+    alt((
+        delimited_group('{', '}'),
+        delimited_group('\'', '\''),
+        delimited_group('(', ')'),
+        word,
+    ))(input)
+}
+
+fn param(input: &str) -> IResult<&str, Param> {
+    let (input, (name, _, _, _, value)) =
+        tuple((take_till1(is_word_end), space0, char('='), space0, value))(input)?;
+    Ok((input, Param { name, value }))
 }
 
-fn subckt_name(input: &str) -> IResult<&str, &str> {
-    preceded(spice_space1, ident)(input)
+fn arg(input: &str) -> IResult<&str, Arg> {
+    alt((map(param, Arg::Param), map(value, Arg::Value)))(input)
+}
+
+fn args(input: &str) -> IResult<&str, (Vec<&str>, Vec<Param>)> {
+    let (input, args) = many0(preceded(spice_space1, arg))(input)?;
+    let (input, _) = opt(line_comment)(input)?;
+
+    let mut values = Vec::new();
+    let mut params = Vec::new();
+    for arg in args {
+        match arg {
+            Arg::Value(value) => values.push(value),
+            Arg::Param(param) => params.push(param),
+        }
+    }
+    Ok((input, (values, params)))
+}
+
+fn path(input: &str) -> IResult<&str, &str> {
+    alt((
+        delimited(char('"'), take_till(|c| c == '"'), char('"')),
+        delimited(char('\''), take_till(|c| c == '\''), char('\'')),
+        ident,
+    ))(input)
 }
 
 fn subckt_line(input: &str) -> IResult<&str, SpiceLine> {
-    let (input, (_, name, ports)) =
-        tuple((tag_no_case(".subckt"), subckt_name, subckt_ports))(input)?;
+    let (input, (_, _, name, (values, params))) =
+        tuple((tag_no_case(".subckt"), spice_space1, ident, args))(input)?;
+
+    let ports = values
+        .into_iter()
+        .filter(|value| !value.eq_ignore_ascii_case("params:"))
+        .collect();
 
-    Ok((input, SpiceLine::Subckt(SubcktLine { name, ports })))
+    Ok((
+        input,
+        SpiceLine::Subckt(SubcktLine {
+            name,
+            ports,
+            params,
+        }),
+    ))
+}
+
+fn ends_line(input: &str) -> IResult<&str, SpiceLine> {
+    let (input, (_, name)) = pair(
+        tag_no_case(".ends"),
+        opt(preceded(within_line_space1, ident)),
+    )(input)?;
+    Ok((input, SpiceLine::Ends(name)))
+}
+
+fn param_line(input: &str) -> IResult<&str, SpiceLine> {
+    let (input, (_, params)) =
+        pair(tag_no_case(".param"), many0(preceded(spice_space1, param)))(input)?;
+    Ok((input, SpiceLine::Param(params)))
+}
+
+fn include_line(input: &str) -> IResult<&str, SpiceLine> {
+    let (input, (_, _, path)) = tuple((
+        alt((tag_no_case(".include"), tag_no_case(".inc"))),
+        spice_space1,
+        path,
+    ))(input)?;
+    Ok((input, SpiceLine::Include(path)))
+}
+
+fn lib_line(input: &str) -> IResult<&str, SpiceLine> {
+    let (input, (_, _, path, _, section)) = tuple((
+        tag_no_case(".lib"),
+        spice_space1,
+        path,
+        within_line_space1,
+        ident,
+    ))(input)?;
+    Ok((input, SpiceLine::Lib(LibLine { path, section })))
+}
+
+fn instance_line(input: &str) -> IResult<&str, SpiceLine> {
+    let start = input;
+    let (input, name) = take_till1(is_word_end)(input)?;
+    let kind = match name.chars().next().map(|c| c.to_ascii_uppercase()) {
+        Some('M') => InstanceKind::Mos,
+        Some('R') => InstanceKind::Resistor,
+        Some('C') => InstanceKind::Capacitor,
+        Some('L') => InstanceKind::Inductor,
+        Some('D') => InstanceKind::Diode,
+        Some('V') => InstanceKind::Vsource,
+        Some('I') => InstanceKind::Isource,
+        Some('X') => InstanceKind::Subckt,
+        _ => return Err(nom::Err::Error(Error::new(start, ErrorKind::Char))),
+    };
+    let (input, (mut values, params)) = args(input)?;
+
+    let num_nodes = match kind {
+        InstanceKind::Mos => 4,
+        InstanceKind::Subckt => values.len().saturating_sub(1),
+        _ => 2,
+    };
+    let has_model = match kind {
+        InstanceKind::Mos | InstanceKind::Diode | InstanceKind::Subckt => true,
+        InstanceKind::Resistor | InstanceKind::Capacitor | InstanceKind::Inductor => values
+            .get(num_nodes)
+            .map(|value| parse_value(value).is_none() && !is_expr(value))
+            .unwrap_or(false),
+        InstanceKind::Vsource | InstanceKind::Isource => false,
+    };
+    let num_required = num_nodes + usize::from(has_model);
+    if values.len() < num_required {
+        return Err(nom::Err::Error(Error::new(start, ErrorKind::Count)));
+    }
+
+    let rest = values.split_off(num_required);
+    let model = if has_model { values.pop() } else { None };
+
+    Ok((
+        input,
+        SpiceLine::Instance(InstanceLine {
+            kind,
+            name,
+            nodes: values,
+            model,
+            values: rest,
+            params,
+        }),
+    ))
 }
 
 fn comment_line(input: &str) -> IResult<&str, SpiceLine> {
@@ -101,14 +323,104 @@ fn comment_line(input: &str) -> IResult<&str, SpiceLine> {
 }
 
 fn other_line(input: &str) -> IResult<&str, SpiceLine> {
-    let (input, _) = pair(ident, many0(preceded(ident, spice_space1)))(input)?;
-    Ok((input, SpiceLine::Other))
+    let (input, line) = recognize(pair(ident, many0(preceded(spice_space1, ident))))(input)?;
+    Ok((input, SpiceLine::Other(line)))
 }
 
 fn spice_line(input: &str) -> IResult<&str, SpiceLine> {
-    alt((subckt_line, comment_line, other_line))(input)
+    alt((
+        subckt_line,
+        ends_line,
+        param_line,
+        include_line,
+        lib_line,
+        comment_line,
+        instance_line,
+        other_line,
+    ))(input)
 }
 
 pub(crate) fn parse_spice(input: &str) -> IResult<&str, Vec<SpiceLine>> {
     many0(delimited(multispace0, spice_line, multispace0))(input)
 }
+
+/// Returns `true` if the given value is an expression enclosed in braces or single quotes.
+pub fn is_expr(value: &str) -> bool {
+    strip_delimiters(value).is_some()
+}
+
+/// Strips the braces or single quotes enclosing an expression.
+///
+/// Returns `None` if the value is not enclosed in braces or single quotes.
+pub fn strip_delimiters(value: &str) -> Option<&str> {
+    value
+        .strip_prefix('{')
+        .and_then(|value| value.strip_suffix('}'))
+        .or_else(|| {
+            value
+                .strip_prefix('\'')
+                .and_then(|value| value.strip_suffix('\''))
+        })
+}
+
+/// Parses a SPICE number, such as `1.5`, `10k`, `2e-15` or `1meg`.
+///
+/// Numbers enclosed in braces or single quotes are also accepted.
+/// Any letters following the scale factor, such as the `F` in `10pF`, are ignored.
+/// Returns `None` if the value is not a number.
+pub fn parse_value(value: &str) -> Option<f64> {
+    let value = strip_delimiters(value).unwrap_or(value).trim();
+    let bytes = value.as_bytes();
+
+    let mut end = 0;
+    if matches!(bytes.first(), Some(b'+' | b'-')) {
+        end += 1;
+    }
+    let mantissa_start = end;
+    while end < bytes.len() && (bytes[end].is_ascii_digit() || bytes[end] == b'.') {
+        end += 1;
+    }
+    if end == mantissa_start {
+        return None;
+    }
+    if matches!(bytes.get(end), Some(b'e' | b'E')) {
+        let mut exp_end = end + 1;
+        if matches!(bytes.get(exp_end), Some(b'+' | b'-')) {
+            exp_end += 1;
+        }
+        let digits_start = exp_end;
+        while exp_end < bytes.len() && bytes[exp_end].is_ascii_digit() {
+            exp_end += 1;
+        }
+        if exp_end > digits_start {
+            end = exp_end;
+        }
+    }
+
+    let number = value[..end].parse::<f64>().ok()?;
+    let suffix = value[end..].to_ascii_lowercase();
+    if !suffix.chars().all(|c| c.is_ascii_alphabetic()) {
+        return None;
+    }
+
+    let multiplier = if suffix.starts_with("meg") {
+        1e6
+    } else if suffix.starts_with("mil") {
+        25.4e-6
+    } else {
+        match suffix.chars().next() {
+            Some('t') => 1e12,
+            Some('g') => 1e9,
+            Some('k') => 1e3,
+            Some('m') => 1e-3,
+            Some('u') => 1e-6,
+            Some('n') => 1e-9,
+            Some('p') => 1e-12,
+            Some('f') => 1e-15,
+            Some('a') => 1e-18,
+            _ => 1.0,
+        }
+    };
+
+    Some(number * multiplier)
+}
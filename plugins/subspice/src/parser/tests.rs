@@ -1,7 +1,9 @@
 use std::path::PathBuf;
 
 use crate::parse;
-use crate::parser::SubcktLine;
+use crate::parser::{
+    parse_value, InstanceKind, InstanceLine, LibLine, Param, SpiceLine, SubcktLine,
+};
 
 pub(crate) const EXAMPLES_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/examples");
 
@@ -20,6 +22,7 @@ fn test_spice_resistor() {
         &SubcktLine {
             name: "my_resistor",
             ports: vec!["p", "n"],
+            params: vec![],
         }
     );
 }
@@ -35,6 +38,7 @@ fn test_dff() {
         &SubcktLine {
             name: "openram_dff",
             ports: vec!["VDD", "GND", "CLK", "D", "Q", "Q_N"],
+            params: vec![],
         }
     );
 }
@@ -50,6 +54,7 @@ fn test_sense_amp() {
         &SubcktLine {
             name: "AAA_Comp_SA_sense",
             ports: vec!["clk", "inn", "inp", "midn", "midp", "outn", "outp", "VDD", "VSS"],
+            params: vec![],
         }
     );
 }
@@ -65,6 +70,7 @@ fn test_tmc() {
         &SubcktLine {
             name: "dbdr_delay_unit_3",
             ports: vec!["clk_in", "clk_out", "sae_in", "sae_out", "clk_rev", "vdd", "vss"],
+            params: vec![],
         }
     );
     assert_eq!(
@@ -72,6 +78,7 @@ fn test_tmc() {
         &SubcktLine {
             name: "timing_multiplier_3",
             ports: vec!["clk", "sae_in", "sae_out", "vdd", "vss"],
+            params: vec![],
         }
     );
 }
@@ -89,6 +96,7 @@ fn test_sram() {
         &SubcktLine {
             name: "hierarchical_decoder_inv_3",
             ports: vec!["gnd", "vdd", "din", "din_b"],
+            params: vec![],
         }
     );
     assert_eq!(
@@ -119,6 +127,222 @@ fn test_sram() {
                 "dout[2]", "dout[1]", "dout[0]", "we", "addr[6]", "addr[5]", "addr[4]", "addr[3]",
                 "addr[2]", "addr[1]", "addr[0]",
             ],
+            params: vec![],
         }
     );
 }
+
+const SPICE_PARAMETERIZED: &str = r#"
+.include "models/sky130.spice"
+.lib '/path/to/models.lib' tt
+.param vdd=1.8 ratio={2*3}
+
+.subckt inv din dout vdd vss params: wn=1 wp='2*wn'
+M0 dout din vss vss nmos w=wn l=0.15 ; pull-down
+mp dout din vdd vdd pmos
++ w={wp} l=0.15
+R1 din dout 10k
+Rpoly din dout rpoly w=1
+C1 dout vss c=2f
+D1 vss dout dmodel 2
+V1 vdd vss DC 1.8
+I1 vdd vss PULSE(0 1 0 1n 1n 5n 10n)
+X0 din dout vdd vss buf mult=2
+.ends inv
+"#;
+
+#[test]
+fn test_spice_parameterized() {
+    let parsed = parse(&SPICE_PARAMETERIZED).unwrap();
+    assert_eq!(
+        parsed.lines().next().unwrap(),
+        &SpiceLine::Include("models/sky130.spice")
+    );
+    assert_eq!(
+        parsed.lines().nth(1).unwrap(),
+        &SpiceLine::Lib(LibLine {
+            path: "/path/to/models.lib",
+            section: "tt",
+        })
+    );
+    assert_eq!(
+        parsed.lines().nth(2).unwrap(),
+        &SpiceLine::Param(vec![
+            Param {
+                name: "vdd",
+                value: "1.8"
+            },
+            Param {
+                name: "ratio",
+                value: "{2*3}"
+            },
+        ])
+    );
+    assert_eq!(
+        parsed.subcircuit_named("inv").unwrap(),
+        &SubcktLine {
+            name: "inv",
+            ports: vec!["din", "dout", "vdd", "vss"],
+            params: vec![
+                Param {
+                    name: "wn",
+                    value: "1"
+                },
+                Param {
+                    name: "wp",
+                    value: "'2*wn'"
+                },
+            ],
+        }
+    );
+
+    let body = parsed.subcircuit_body("inv").unwrap();
+    assert_eq!(body.len(), 9);
+    assert_eq!(
+        parsed.lines().last().unwrap(),
+        &SpiceLine::Ends(Some("inv"))
+    );
+
+    let instances = parsed.instances_of("inv").collect::<Vec<_>>();
+    assert_eq!(
+        instances[0],
+        &InstanceLine {
+            kind: InstanceKind::Mos,
+            name: "M0",
+            nodes: vec!["dout", "din", "vss", "vss"],
+            model: Some("nmos"),
+            values: vec![],
+            params: vec![
+                Param {
+                    name: "w",
+                    value: "wn"
+                },
+                Param {
+                    name: "l",
+                    value: "0.15"
+                },
+            ],
+        }
+    );
+    assert_eq!(instances[1].nodes, vec!["dout", "din", "vdd", "vdd"]);
+    assert_eq!(instances[1].params[0].value, "{wp}");
+    assert_eq!(instances[2].kind, InstanceKind::Resistor);
+    assert_eq!(instances[2].model, None);
+    assert_eq!(instances[2].values, vec!["10k"]);
+    assert_eq!(instances[3].model, Some("rpoly"));
+    assert_eq!(instances[4].kind, InstanceKind::Capacitor);
+    assert!(instances[4].values.is_empty());
+    assert_eq!(instances[5].model, Some("dmodel"));
+    assert_eq!(instances[5].values, vec!["2"]);
+    assert_eq!(instances[6].values, vec!["DC", "1.8"]);
+    assert_eq!(instances[7].values, vec!["PULSE(0 1 0 1n 1n 5n 10n)"]);
+    assert_eq!(
+        instances[8],
+        &InstanceLine {
+            kind: InstanceKind::Subckt,
+            name: "X0",
+            nodes: vec!["din", "dout", "vdd", "vss"],
+            model: Some("buf"),
+            values: vec![],
+            params: vec![Param {
+                name: "mult",
+                value: "2"
+            }],
+        }
+    );
+}
+
+#[test]
+fn test_parse_value() {
+    assert_eq!(parse_value("100"), Some(100.0));
+    assert_eq!(parse_value("10k"), Some(10e3));
+    assert_eq!(parse_value("1MEG"), Some(1e6));
+    assert_eq!(parse_value("2.5e-3"), Some(2.5e-3));
+    assert_eq!(parse_value("10pF"), Some(10e-12));
+    assert_eq!(parse_value("'3.2'"), Some(3.2));
+    assert_eq!(parse_value("-1m"), Some(-1e-3));
+    assert_eq!(parse_value("wn"), None);
+    assert_eq!(parse_value("{2*wn}"), None);
+}
+
+#[test]
+fn test_instances() {
+    let path = PathBuf::from(EXAMPLES_PATH).join("sram.spice");
+    let data = std::fs::read_to_string(path).unwrap();
+    let parsed = parse(&data).unwrap();
+    let instances = parsed
+        .instances_of("hierarchical_decoder_nand_2")
+        .collect::<Vec<_>>();
+    assert_eq!(instances.len(), 6);
+    assert_eq!(
+        instances[0],
+        &InstanceLine {
+            kind: InstanceKind::Subckt,
+            name: "xn1",
+            nodes: vec!["x1", "a", "gnd", "gnd"],
+            model: Some("sky130_fd_pr__nfet_01v8"),
+            values: vec![],
+            params: vec![
+                Param {
+                    name: "w",
+                    value: "'3.2'"
+                },
+                Param {
+                    name: "l",
+                    value: "'0.15'"
+                },
+            ],
+        }
+    );
+
+    let path = PathBuf::from(EXAMPLES_PATH).join("sense_amp.spice");
+    let data = std::fs::read_to_string(path).unwrap();
+    let parsed = parse(&data).unwrap();
+    let instances = parsed.instances_of("AAA_Comp_SA_sense").collect::<Vec<_>>();
+    assert_eq!(instances.len(), 11);
+    assert!(instances
+        .iter()
+        .all(|inst| inst.kind == InstanceKind::Mos && inst.params.len() == 10));
+}
+
+#[test]
+fn test_parse_without_trailing_newline() {
+    let parsed = parse(&"X0 d g s b nfet w=1.000 l=0.150").unwrap();
+    let lines = parsed.lines().collect::<Vec<_>>();
+    assert_eq!(lines.len(), 1);
+    assert_eq!(
+        lines[0],
+        &SpiceLine::Instance(InstanceLine {
+            kind: InstanceKind::Subckt,
+            name: "X0",
+            nodes: vec!["d", "g", "s", "b"],
+            model: Some("nfet"),
+            values: vec![],
+            params: vec![
+                Param {
+                    name: "w",
+                    value: "1.000"
+                },
+                Param {
+                    name: "l",
+                    value: "0.150"
+                },
+            ],
+        })
+    );
+}
+
+#[test]
+fn test_parse_other_line() {
+    let parsed = parse(&".subckt amp in out\nE1 out 0 in 0\n+ 10\n.ends\n").unwrap();
+    let body = parsed.subcircuit_body("amp").unwrap();
+    assert_eq!(body, &[SpiceLine::Other("E1 out 0 in 0\n+ 10")]);
+}
+
+#[test]
+fn test_parse_subckt_without_trailing_newline() {
+    let parsed = parse(&".subckt res p n\nR1 p n 100\n.ends res").unwrap();
+    assert_eq!(parsed.lines().count(), 3);
+    assert_eq!(parsed.instances_of("res").count(), 1);
+    assert_eq!(parsed.lines().last().unwrap(), &SpiceLine::Ends(Some("res")));
+}
@@ -17,7 +17,9 @@ use crate::generation::GeneratedCheck;
 use crate::io::create_dir_all;
 use crate::layout::cell::{Cell, CellKey, Instance as LayoutInstance};
 use crate::layout::context::{LayoutCtx, LayoutData};
+use crate::layout::convert::gds::GdsExportOpts;
 use crate::layout::layers::{Layers, LayersRef};
+use crate::layout::render::{ImageFormat, LayerStyles, RenderOpts, Renderer};
 use crate::layout::LayoutFormat;
 use crate::log::{self, Log};
 use crate::pdk::corner::error::ProcessCornerError;
@@ -27,12 +29,18 @@ use crate::pdk::stdcell::StdCellDb;
 use crate::pdk::Pdk;
 use crate::schematic::circuit::{Instance as SchematicInstance, Reference};
 use crate::schematic::context::{ModuleKey, SchematicCtx, SchematicData};
+use crate::schematic::flatten::{flatten, flatten_to_depth, FlatSchematic};
 use crate::schematic::module::{AbstractModule, ExternalModule, Module, RawSource};
-use crate::schematic::netlist::interface::{InstanceInfo, Netlister, SubcircuitInfo};
+use crate::schematic::netlist::interface::{
+    InstanceInfo, Netlister, PrimitiveInfo, SubcircuitInfo,
+};
 use crate::schematic::netlist::preprocess::{preprocess_netlist, PreprocessedNetlist};
 use crate::schematic::netlist::NetlistPurpose;
+use crate::schematic::primitive::PrimitiveDevice;
+use crate::schematic::render::{SchematicFormat, SchematicRenderOpts, SchematicRenderer};
 use crate::schematic::validation::connectivity::validate_connectivity;
 use crate::schematic::validation::drivers::validate_drivers;
+use crate::schematic::validation::erc::{validate_erc, ErcConfig, ErcValidatorOutput};
 use crate::schematic::validation::naming::validate_naming;
 use crate::script::map::ScriptMap;
 use crate::script::Script;
@@ -40,6 +48,7 @@ use crate::verification::drc::{DrcInput, DrcOutput, DrcTool};
 use crate::verification::lvs::{LvsInput, LvsOutput, LvsTool};
 use crate::verification::pex::{PexInput, PexOutput, PexTool};
 use crate::verification::simulation::context::{PostSimCtx, PreSimCtx};
+use crate::verification::simulation::oppoint::{MosOpSelect, MosOpTarget};
 use crate::verification::simulation::testbench::Testbench;
 use crate::verification::simulation::{SimInput, SimOpts, Simulator};
 use crate::verification::timing::context::TimingCtx;
@@ -114,6 +123,8 @@ pub(crate) struct InnerWriteSchematicArgs<W> {
     flatten_top: FlattenTop,
     purpose: NetlistPurpose,
     out: W,
+    /// Overrides the configured netlister.
+    netlister: Option<Arc<dyn Netlister>>,
 }
 
 /// Whether or not to verify timing constraints for transient simulations.
@@ -373,6 +384,17 @@ impl SubstrateCtx {
         &self,
         args: WriteSchematicArgs<T::Params, W>,
     ) -> Result<()>
+    where
+        T: Component,
+    {
+        self.write_schematic_with_netlister::<T, W>(args, None)
+    }
+
+    fn write_schematic_with_netlister<T, W: Write>(
+        &self,
+        args: WriteSchematicArgs<T::Params, W>,
+        netlister: Option<Arc<dyn Netlister>>,
+    ) -> Result<()>
     where
         T: Component,
     {
@@ -386,6 +408,7 @@ impl SubstrateCtx {
             flatten_top: args.flatten_top,
             purpose: args.purpose,
             out: args.out,
+            netlister,
         };
         let mut inner = self.write();
         inner.write_schematic(args)?;
@@ -409,6 +432,7 @@ impl SubstrateCtx {
             flatten_top: args.flatten_top,
             purpose: args.purpose,
             out: args.out,
+            netlister: None,
         };
         let mut inner = self.write();
         let netlist = inner.write_schematic(args)?;
@@ -435,6 +459,19 @@ impl SubstrateCtx {
         out: impl AsRef<Path>,
         purpose: NetlistPurpose,
     ) -> Result<()>
+    where
+        T: Component,
+    {
+        self.write_schematic_to_file_with_netlister::<T>(params, out, purpose, None)
+    }
+
+    fn write_schematic_to_file_with_netlister<T>(
+        &self,
+        params: &T::Params,
+        out: impl AsRef<Path>,
+        purpose: NetlistPurpose,
+        netlister: Option<Arc<dyn Netlister>>,
+    ) -> Result<()>
     where
         T: Component,
     {
@@ -451,7 +488,7 @@ impl SubstrateCtx {
                 purpose,
                 flatten_top: FlattenTop::No,
             };
-            self.write_schematic_for_purpose::<T, _>(args)
+            self.write_schematic_with_netlister::<T, _>(args, netlister)
         };
 
         with_err_context(inner(), || {
@@ -475,6 +512,80 @@ impl SubstrateCtx {
         self.write_schematic_to_file_for_purpose::<T>(params, out, NetlistPurpose::default())
     }
 
+    /// Generates the schematic of the given component and flattens it
+    /// into a device-level [`FlatSchematic`].
+    pub fn flatten_schematic<T>(&self, params: &T::Params) -> Result<FlatSchematic>
+    where
+        T: Component,
+    {
+        self.flatten_schematic_to_depth::<T>(params, None)
+    }
+
+    /// Generates the schematic of the given component and flattens
+    /// at most `max_depth` levels of its hierarchy.
+    ///
+    /// Instances below `max_depth` are kept as black-box devices.
+    /// If `max_depth` is [`None`], this is equivalent to [`SubstrateCtx::flatten_schematic`].
+    pub fn flatten_schematic_to_depth<T>(
+        &self,
+        params: &T::Params,
+        max_depth: Option<usize>,
+    ) -> Result<FlatSchematic>
+    where
+        T: Component,
+    {
+        let inst = self.instantiate_schematic::<T>(params)?;
+        let top = inst
+            .module()
+            .local_id()
+            .ok_or(ErrorSource::NetlistExternalModule)?;
+        let inner = self.read();
+        let netlist = preprocess_netlist(&inner.schematics, top)?;
+        flatten_to_depth(&netlist, &inner.schematics.external_modules, max_depth)
+    }
+
+    /// Renders the schematic of component `T` to a file at `path`.
+    ///
+    /// The format is inferred from the file extension of `path`
+    /// (`.svg` for SVG), defaulting to a Graphviz DOT graph.
+    pub fn write_schematic_image<T>(
+        &self,
+        params: &T::Params,
+        path: impl AsRef<Path>,
+        opts: SchematicRenderOpts,
+    ) -> Result<()>
+    where
+        T: Component,
+    {
+        let path = path.as_ref();
+        let format = SchematicFormat::from_path(path).unwrap_or_default();
+
+        let inner = || -> Result<()> {
+            let flat = self.flatten_schematic_to_depth::<T>(params, opts.max_depth)?;
+            if let Some(parent) = path.parent() {
+                create_dir_all(parent)?;
+            }
+            SchematicRenderer::new(opts).write(&flat, path, format)
+        };
+
+        with_err_context(inner(), || {
+            ErrorContext::Task(arcstr::format!("rendering schematic to file {:?}", path))
+        })
+    }
+
+    /// Runs electrical rule checks on the flattened schematic of the given component.
+    ///
+    /// Uses the PDK's MOSFET database and supply ratings.
+    pub fn run_erc<T>(&self, params: &T::Params, config: &ErcConfig) -> Result<ErcValidatorOutput>
+    where
+        T: Component,
+    {
+        let flat = self.flatten_schematic::<T>(params)?;
+        let output = validate_erc(&flat, &self.mos_db(), &self.pdk().supplies(), config);
+        output.log();
+        Ok(output)
+    }
+
     pub fn instantiate_layout<T>(&self, params: &T::Params) -> Result<LayoutInstance>
     where
         T: Component,
@@ -493,19 +604,72 @@ impl SubstrateCtx {
         })
     }
 
+    /// Writes the layout of component `T` to `path`.
+    ///
+    /// The layout format is inferred from the file extension of `path`
+    /// (`.oas` or `.oasis` for OASIS), defaulting to GDSII.
+    /// Use [`SubstrateCtx::write_layout_with_format`] to choose the format explicitly.
     pub fn write_layout<T>(&self, params: &T::Params, path: impl AsRef<Path>) -> Result<()>
     where
         T: Component,
     {
         let path = path.as_ref();
+        let format = LayoutFormat::from_path(path).unwrap_or_default();
+        self.write_layout_with_format::<T>(params, path, format)
+    }
 
+    /// Writes the layout of component `T` to `path` in the given format.
+    pub fn write_layout_with_format<T>(
+        &self,
+        params: &T::Params,
+        path: impl AsRef<Path>,
+        format: LayoutFormat,
+    ) -> Result<()>
+    where
+        T: Component,
+    {
+        self.write_layout_inner::<T>(params, path.as_ref(), format, &GdsExportOpts::default())
+    }
+
+    /// Writes the layout of component `T` to `path` using the given export options.
+    ///
+    /// The layout format is inferred as in [`SubstrateCtx::write_layout`].
+    /// The options apply to both formats. In OASIS, net names are stored in properties
+    /// named [`NET_NAME_PROPNAME`](crate::layout::convert::oasis::NET_NAME_PROPNAME).
+    pub fn write_layout_with_opts<T>(
+        &self,
+        params: &T::Params,
+        path: impl AsRef<Path>,
+        opts: &GdsExportOpts,
+    ) -> Result<()>
+    where
+        T: Component,
+    {
+        let path = path.as_ref();
+        let format = LayoutFormat::from_path(path).unwrap_or_default();
+        self.write_layout_inner::<T>(params, path, format, opts)
+    }
+
+    fn write_layout_inner<T>(
+        &self,
+        params: &T::Params,
+        path: &Path,
+        format: LayoutFormat,
+        opts: &GdsExportOpts,
+    ) -> Result<()>
+    where
+        T: Component,
+    {
         let inner = || -> Result<()> {
             let inst = self.instantiate_layout::<T>(params)?;
             let top = inst.cell().clone();
             if let Some(parent) = path.parent() {
                 create_dir_all(parent)?;
             }
-            self.to_gds_with_top(top, path)?;
+            match format {
+                LayoutFormat::Gds => self.to_gds_with_top(top, path, opts)?,
+                LayoutFormat::Oasis => self.to_oasis_with_top(top, path, opts)?,
+            }
             Ok(())
         };
 
@@ -514,6 +678,36 @@ impl SubstrateCtx {
         })
     }
 
+    /// Renders the layout of component `T` to an image at `path`.
+    ///
+    /// The image format is inferred from the file extension of `path`
+    /// (`.png` for PNG), defaulting to SVG.
+    pub fn write_layout_image<T>(
+        &self,
+        params: &T::Params,
+        path: impl AsRef<Path>,
+        styles: &LayerStyles,
+        opts: RenderOpts,
+    ) -> Result<()>
+    where
+        T: Component,
+    {
+        let path = path.as_ref();
+        let format = ImageFormat::from_path(path).unwrap_or_default();
+
+        let inner = || -> Result<()> {
+            let inst = self.instantiate_layout::<T>(params)?;
+            if let Some(parent) = path.parent() {
+                create_dir_all(parent)?;
+            }
+            Renderer::new(styles, opts).write(inst.cell(), path, format)
+        };
+
+        with_err_context(inner(), || {
+            ErrorContext::Task(arcstr::format!("rendering layout to file {:?}", path))
+        })
+    }
+
     #[inline]
     pub fn instantiate_digital<T>(&self, params: &T::Params) -> Result<DigitalInstance>
     where
@@ -620,12 +814,25 @@ impl SubstrateCtx {
         create_dir_all(work_dir)?;
         let layout_path = PathBuf::from(&work_dir).join("layout.gds");
         self.write_layout::<T>(params, &layout_path)?;
-        let schematic_path = PathBuf::from(&work_dir).join("netlist.spice");
-        self.write_schematic_to_file_for_purpose::<T>(
-            params,
-            &schematic_path,
-            NetlistPurpose::Lvs,
-        )?;
+        let lvs_tool = self.lvs_tool().ok_or(ErrorSource::ToolNotSpecified)?;
+        let schematic_path = if let Some(netlister) = lvs_tool.cdl_netlister(&self.mos_db()) {
+            let schematic_path = PathBuf::from(&work_dir).join("netlist.cdl");
+            self.write_schematic_to_file_with_netlister::<T>(
+                params,
+                &schematic_path,
+                NetlistPurpose::Lvs,
+                Some(Arc::new(netlister)),
+            )?;
+            schematic_path
+        } else {
+            let schematic_path = PathBuf::from(&work_dir).join("netlist.spice");
+            self.write_schematic_to_file_for_purpose::<T>(
+                params,
+                &schematic_path,
+                NetlistPurpose::Lvs,
+            )?;
+            schematic_path
+        };
         let cell_name = T::new(params, self)?.name();
         self.run_lvs(LvsInput {
             work_dir: PathBuf::from(&work_dir),
@@ -808,6 +1015,9 @@ impl SubstrateCtx {
 
         tb.setup(&mut ctx)?;
         self.pdk().pre_sim(&mut ctx)?;
+        if ctx.mos_op != MosOpSelect::None {
+            ctx.input.mos_op = self.mos_op_targets(&netlist, &ctx.mos_op)?;
+        }
         let simulator = self.simulator().ok_or(ErrorSource::ToolNotSpecified)?;
 
         let output = if let VerifyTiming::Yes(ref pvt) = verify_timing {
@@ -852,6 +1062,50 @@ impl SubstrateCtx {
         Ok(output)
     }
 
+    /// Finds the MOSFETs selected for operating point back-annotation
+    /// in a preprocessed testbench netlist.
+    fn mos_op_targets(
+        &self,
+        netlist: &PreprocessedNetlist,
+        select: &MosOpSelect,
+    ) -> Result<Vec<MosOpTarget>> {
+        let flat = {
+            let inner = self.read();
+            flatten(netlist, &inner.schematics.external_modules)?
+        };
+        let mos_db = self.mos_db();
+        let mut skipped = Vec::new();
+        let targets = flat
+            .find_mos(&mos_db, &mut skipped)
+            .into_iter()
+            .filter_map(|mos| {
+                let path = flat.device(mos.device).path();
+                let selected = match select {
+                    MosOpSelect::None => false,
+                    MosOpSelect::All => true,
+                    MosOpSelect::Instances(instances) => instances
+                        .iter()
+                        .any(|inst| inst.path.as_slice() == path && inst.element == mos.element),
+                };
+                selected.then(|| MosOpTarget {
+                    path: path.to_vec(),
+                    element: mos.element,
+                    model: mos.spec.name.as_str().into(),
+                    kind: mos.spec.kind,
+                })
+            })
+            .collect::<Vec<_>>();
+
+        if let MosOpSelect::Instances(instances) = select {
+            for inst in instances {
+                if !targets.iter().any(|target| target.instance() == *inst) {
+                    return Err(ErrorSource::MosNotFound(inst.to_string()).into());
+                }
+            }
+        }
+        Ok(targets)
+    }
+
     pub fn simulate<T>(&self, params: &T::Params) -> Result<T::Output>
     where
         T: Testbench,
@@ -933,7 +1187,7 @@ impl SubstrateCtx {
             view: View::Layout,
         })?;
         ctx.cell.freeze();
-        ctx.cell.validate()?;
+        ctx.cell.validate_with_grid(self.pdk().layout_grid())?;
 
         // Now that the cell is frozen, mark `ctx` as immutable
         // so we don't accidentally modify the cell in any way.
@@ -983,7 +1237,6 @@ impl SubstrateCtx {
         Ok((module, intf))
     }
 
-    #[allow(dead_code)]
     pub(crate) fn get_external_module<Q>(&self, name: &Q) -> Result<Arc<ExternalModule>>
     where
         Q: AsRef<str>,
@@ -1070,13 +1323,22 @@ impl SubstrateData {
 
     pub(crate) fn write_schematic<W>(
         &mut self,
-        args: InnerWriteSchematicArgs<W>,
+        mut args: InnerWriteSchematicArgs<W>,
     ) -> Result<PreprocessedNetlist>
     where
         W: Write,
     {
         let purp = args.purpose.clone();
-        with_err_context(self._write_schematic(args), || {
+        // Temporarily replace the configured netlister, if requested.
+        let prev = args
+            .netlister
+            .take()
+            .map(|netlister| std::mem::replace(&mut self.netlister, Some(netlister)));
+        let result = self._write_schematic(args);
+        if let Some(prev) = prev {
+            self.netlister = prev;
+        }
+        with_err_context(result, || {
             ErrorContext::Task(arcstr::format!("writing schematic for {}", purp))
         })
     }
@@ -1200,15 +1462,18 @@ impl SubstrateData {
         let conns = inst.connections();
 
         let mut ordered_conns = Vec::with_capacity(submodule.raw_ports().len());
+        let mut port_names = Vec::with_capacity(submodule.raw_ports().len());
 
         for port in submodule.ports() {
             let name = submodule.signals()[port.signal].name();
             ordered_conns.push(&conns[name]);
+            port_names.push(name);
         }
 
         let info = InstanceInfo {
             name: inst.name(),
             ports: &ordered_conns,
+            port_names: &port_names,
             params: inst.params(),
             signals: module.signals(),
             subcircuit_name: submodule.name(),
@@ -1232,15 +1497,18 @@ impl SubstrateData {
         let conns = inst.connections();
 
         let mut ordered_conns = Vec::with_capacity(submodule.raw_ports().len());
+        let mut port_names = Vec::with_capacity(submodule.raw_ports().len());
 
         for port in submodule.raw_ports() {
             let name = submodule.signals()[port.signal].name();
             ordered_conns.push(&conns[name]);
+            port_names.push(name);
         }
 
         let info = InstanceInfo {
             name: inst.name(),
             ports: &ordered_conns,
+            port_names: &port_names,
             params: inst.params(),
             signals: module.signals(),
             subcircuit_name: submodule.name(),
@@ -1250,6 +1518,23 @@ impl SubstrateData {
         Ok(())
     }
 
+    fn emit_primitive<W: Write>(
+        &mut self,
+        module: &Module,
+        device: &PrimitiveDevice,
+        out: &mut Box<W>,
+    ) -> Result<()> {
+        let info = PrimitiveInfo {
+            name: device.name(),
+            kind: device.kind(),
+            ports: device.connections(),
+            signals: module.signals(),
+        };
+
+        self.try_netlister()?.emit_primitive(out, info)?;
+        Ok(())
+    }
+
     fn emit_module<W: Write>(
         &mut self,
         key: ModuleKey,
@@ -1274,6 +1559,10 @@ impl SubstrateData {
             };
         }
 
+        for device in module.primitives() {
+            self.emit_primitive(module, device, out)?;
+        }
+
         if let Some(spice) = module.raw_spice() {
             netlister.emit_raw_spice(out, spice)?;
         }
@@ -1317,6 +1606,10 @@ impl SubstrateData {
             };
         }
 
+        for device in module.primitives() {
+            self.emit_primitive(module, device, out)?;
+        }
+
         if let Some(spice) = module.raw_spice() {
             log::warn!("Raw spice in flattened top level modules is unsupported. This may result in floating ground nets. If you need raw spice in a testbench, consider importing it as a hard macro or in a submodule.");
             netlister.emit_raw_spice(out, spice)?;
@@ -6,8 +6,13 @@ use thiserror::Error;
 use crate::component::{self, View};
 use crate::deps::arcstr::ArcStr;
 use crate::layout::cell::PortError;
+use crate::layout::diff::DiffError;
 use crate::layout::error::LayoutError;
+use crate::layout::fill::FillError;
+use crate::layout::placement::solver::PlacementError;
+use crate::layout::render::RenderError;
 use crate::layout::routing;
+use crate::layout::straps::ir::IrDropError;
 use crate::layout::straps::PowerStrapError;
 use crate::pdk::corner::error::ProcessCornerError;
 use crate::pdk::mos::error::MosError;
@@ -174,6 +179,9 @@ pub enum ErrorSource {
     #[error("no such device")]
     DeviceNotFound,
 
+    #[error("no MOSFET found: {0}")]
+    MosNotFound(String),
+
     #[error("error while generating MOSFET device: {0}")]
     Mos(#[from] MosError),
 
@@ -189,6 +197,18 @@ pub enum ErrorSource {
     #[error("power strap error: {0}")]
     PowerStrapError(#[from] PowerStrapError),
 
+    #[error("error performing IR drop analysis: {0}")]
+    IrDrop(#[from] IrDropError),
+
+    #[error("error generating fill: {0}")]
+    Fill(#[from] FillError),
+
+    #[error("error rendering layout: {0}")]
+    Render(#[from] RenderError),
+
+    #[error("error comparing layouts: {0}")]
+    Diff(#[from] DiffError),
+
     #[error("no such layer: {0}")]
     LayerNotFound(String),
 
@@ -216,6 +236,9 @@ pub enum ErrorSource {
     #[error("error parsing SPICE: {0}")]
     SpiceParsing(#[from] subspice::error::Error),
 
+    #[error("error importing SPICE netlist: {0}")]
+    SpiceImport(String),
+
     #[error("error parsing TOML: {0}")]
     TomlParsing(#[from] toml::de::Error),
 
@@ -234,6 +257,9 @@ pub enum ErrorSource {
     #[error("error accessing schematic port: {0}")]
     SchematicPort(#[from] SchematicPortError),
 
+    #[error("error solving placement constraints: {0}")]
+    Placement(#[from] PlacementError),
+
     #[error("error performing automatic routing: {0}")]
     AutoRouting(#[from] routing::auto::error::Error),
 
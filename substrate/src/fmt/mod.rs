@@ -1 +1,2 @@
 pub mod signal;
+pub(crate) mod svg;
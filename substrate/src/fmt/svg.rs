@@ -0,0 +1,31 @@
+//! Helpers for writing SVG documents.
+
+use std::fmt::Write;
+
+/// Writes a black, monospace text element anchored at the given point.
+pub(crate) fn write_text(out: &mut String, at: [f64; 2], text: &str, anchor: &str, size: f64) {
+    writeln!(
+        out,
+        r#"<text x="{:.2}" y="{:.2}" font-family="monospace" font-size="{size}" text-anchor="{anchor}" fill="black">{}</text>"#,
+        at[0],
+        at[1],
+        escape(text)
+    )
+    .unwrap();
+}
+
+/// Escapes text for inclusion in XML content or attributes.
+pub(crate) fn escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
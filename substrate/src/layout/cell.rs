@@ -38,6 +38,8 @@ pub struct Cell {
     name: ArcStr,
     /// A list of instances contained in the cell.
     insts: Vec<Instance>,
+    /// A list of instance arrays contained in the cell.
+    arrays: Vec<InstanceArray>,
     /// A list of primitive/geometric elements.
     elems: Vec<Element>,
     /// A list of text annotations.
@@ -96,6 +98,49 @@ impl Draw for Instance {
     }
 }
 
+/// A regular two-dimensional array of instances of a single cell.
+///
+/// The instance in row `row` and column `col` is located at
+/// `loc + col * col_pitch + row * row_pitch`, and all instances share the same orientation.
+/// Arrays are exported to GDS as a single array reference, and are only expanded
+/// into individual [`Instance`]s when requested.
+#[derive(Debug, Clone, Builder)]
+pub struct InstanceArray {
+    /// The array name.
+    #[builder(default)]
+    pub(crate) name: ArcStr,
+    /// A pointer to the reference cell.
+    pub(crate) cell: Arc<Cell>,
+    /// The location of the instance in row 0, column 0.
+    #[builder(default)]
+    pub(crate) loc: Point,
+    /// The orientation of each instance.
+    #[builder(default)]
+    pub(crate) orientation: Orientation,
+    /// The number of rows.
+    pub(crate) rows: usize,
+    /// The number of columns.
+    pub(crate) cols: usize,
+    /// The offset between adjacent rows.
+    #[builder(default)]
+    pub(crate) row_pitch: Point,
+    /// The offset between adjacent columns.
+    #[builder(default)]
+    pub(crate) col_pitch: Point,
+}
+
+impl DrawRef for InstanceArray {
+    fn draw_ref(&self) -> crate::error::Result<Group> {
+        Ok(self.clone().into())
+    }
+}
+
+impl Draw for InstanceArray {
+    fn draw(self) -> crate::error::Result<Group> {
+        Ok(self.into())
+    }
+}
+
 /// A primitive geometric element.
 ///
 /// Combines a geometric [`Shape`] with a [`LayerSpec`],
@@ -593,6 +638,35 @@ impl Cell {
         }
     }
 
+    /// Returns an iterator over the instance arrays in the cell.
+    #[inline]
+    pub fn arrays(&self) -> impl Iterator<Item = &InstanceArray> {
+        self.arrays.iter()
+    }
+
+    /// Adds an instance array to the cell.
+    pub fn add_array(&mut self, array: impl Into<InstanceArray>) {
+        debug_assert!(!self.is_frozen());
+        self.arrays.push(array.into());
+    }
+
+    /// Adds all instance arrays from the given iterator to this cell.
+    pub fn add_arrays(&mut self, arrays: impl IntoIterator<Item = InstanceArray>) {
+        debug_assert!(!self.is_frozen());
+        self.arrays.extend(arrays);
+    }
+
+    /// Replaces each [`InstanceArray`] in the cell with the [`Instance`]s it contains.
+    ///
+    /// Unlike [`Flatten::flatten`], this only removes one level of array hierarchy.
+    pub fn flatten_arrays(&mut self) {
+        debug_assert!(!self.is_frozen());
+        let arrays = std::mem::take(&mut self.arrays);
+        for array in arrays {
+            self.insts.extend(array.insts());
+        }
+    }
+
     /// Returns an iterator over the elements in the cell.
     #[inline]
     pub fn elems(&self) -> impl Iterator<Item = &Element> {
@@ -769,6 +843,9 @@ impl Cell {
                 bbox = s.union(bbox);
             }
         }
+        for array in &self.arrays {
+            bbox = array.bbox().union(bbox);
+        }
         bbox
     }
 
@@ -777,6 +854,7 @@ impl Cell {
         debug_assert!(!self.is_frozen());
         self.add_elements(cell.elems().cloned());
         self.add_instances(cell.insts().cloned());
+        self.add_arrays(cell.arrays().cloned());
         self.add_annotations(cell.annotations().cloned());
         self.add_ports(cell.ports().cloned())?;
         self.add_blockages(cell.blockages().map(|(k, v)| (k, v.clone())));
@@ -793,6 +871,7 @@ impl Cell {
         debug_assert!(!self.is_frozen());
         self.add_elements(cell.elems().cloned());
         self.add_instances(cell.insts().cloned());
+        self.add_arrays(cell.arrays().cloned());
         self.add_annotations(cell.annotations().cloned());
         self.add_ports_with_strategy(cell.ports().cloned(), port_conflict_strategy)?;
         self.add_blockages(cell.blockages().map(|(k, v)| (k, v.clone())));
@@ -820,7 +899,10 @@ impl Cell {
         debug_assert!(!self.is_frozen());
 
         // Instances cannot be trimmed
-        assert!(self.insts.is_empty(), "must flatten Cell before trimming");
+        assert!(
+            self.insts.is_empty() && self.arrays.is_empty(),
+            "must flatten Cell before trimming"
+        );
 
         // Trim elements
         let elems = std::mem::take(&mut self.elems);
@@ -875,8 +957,22 @@ impl Cell {
         self.cache = Some(cache);
     }
 
+    /// Validates the cell, logging any warnings or errors that are found.
+    ///
+    /// Returns an error if validation fails.
     pub fn validate(&self) -> crate::error::Result<()> {
-        let validation = validate_cell(self);
+        self.validate_inner(None)
+    }
+
+    /// Validates the cell, additionally checking that geometry lies on the layout grid `grid`.
+    ///
+    /// Returns an error if validation fails.
+    pub fn validate_with_grid(&self, grid: i64) -> crate::error::Result<()> {
+        self.validate_inner(Some(grid))
+    }
+
+    fn validate_inner(&self, grid: Option<i64>) -> crate::error::Result<()> {
+        let validation = validate_cell(self, grid);
         validation.log();
         if validation.has_errors() {
             return Err(ErrorSource::InvalidLayout(validation.first_error()).into());
@@ -891,6 +987,13 @@ impl Cell {
         &self.insts
     }
 
+    /// The instance arrays of the cell, as a slice.
+    ///
+    /// Prefer to use the [`Cell::arrays`] function where possible.
+    pub(crate) fn _arrays(&self) -> &[InstanceArray] {
+        &self.arrays
+    }
+
     pub fn set_metadata<T: Send + Sync + 'static>(&mut self, data: T) -> bool {
         self.metadata.set(data)
     }
@@ -899,14 +1002,69 @@ impl Cell {
         self.metadata.get::<T>()
     }
 
+    pub fn try_get_metadata<T: Send + Sync + 'static>(&self) -> Option<&T> {
+        self.metadata.try_get::<T>()
+    }
+
     pub fn shapes_on(&self, layer: LayerKey) -> Box<dyn Iterator<Item = Shape> + '_> {
         let recur = self.insts().flat_map(move |inst| inst.shapes_on(layer));
+        let arrays = self.arrays().flat_map(move |array| array.shapes_on(layer));
         let curr = self
             .elems()
             .filter(move |&elem| elem.layer.layer() == layer)
             .map(|elem| elem.inner.clone());
-        Box::new(curr.chain(recur))
+        Box::new(curr.chain(recur).chain(arrays))
     }
+
+    /// Returns the elements on net `net`, including elements within instantiated cells.
+    ///
+    /// Nets within instances are named hierarchically: net `b` of instance `x`
+    /// is referred to as `x/b`, and net `b` of the instance in row 0, column 1
+    /// of array `arr` is referred to as `arr[0][1]/b`.
+    /// Returned elements are transformed to this cell's coordinates,
+    /// and have their net set to `net`.
+    pub fn shapes_on_net(&self, net: &str) -> Vec<Element> {
+        let mut out: Vec<Element> = self
+            .elems()
+            .filter(|elem| elem.net.as_deref() == Some(net))
+            .cloned()
+            .collect();
+
+        if let Some((name, child_net)) = net.split_once('/') {
+            let mut insts = self
+                .insts()
+                .filter(|inst| inst.name() == name)
+                .cloned()
+                .collect::<Vec<_>>();
+            if let Some((array, row, col)) = split_array_index(name) {
+                insts.extend(
+                    self.arrays()
+                        .filter(|x| x.name() == array && row < x.rows() && col < x.cols())
+                        .map(|x| x.inst(row, col)),
+                );
+            }
+            for inst in insts {
+                let tf = inst.transformation();
+                out.extend(
+                    inst.cell()
+                        .shapes_on_net(child_net)
+                        .into_iter()
+                        .map(|elem| Element {
+                            net: Some(net.into()),
+                            ..elem.transform(tf)
+                        }),
+                );
+            }
+        }
+        out
+    }
+}
+
+/// Splits the name of an array element, `arr[r][c]`, into the array name, row, and column.
+fn split_array_index(name: &str) -> Option<(&str, usize, usize)> {
+    let (rest, col) = name.strip_suffix(']')?.rsplit_once('[')?;
+    let (array, row) = rest.strip_suffix(']')?.rsplit_once('[')?;
+    Some((array, row.parse().ok()?, col.parse().ok()?))
 }
 
 impl Translate for Cell {
@@ -916,6 +1074,9 @@ impl Translate for Cell {
         for inst in self.insts.iter_mut() {
             inst.translate(p);
         }
+        for array in self.arrays.iter_mut() {
+            array.translate(p);
+        }
         for elem in self.elems.iter_mut() {
             elem.translate(p);
         }
@@ -934,15 +1095,18 @@ impl Translate for Cell {
 }
 
 impl Flatten for Cell {
-    /// Flattens this cell, recursively replacing any [`Instance`]s with their contents.
+    /// Flattens this cell, recursively replacing any [`Instance`]s
+    /// and [`InstanceArray`]s with their contents.
     fn flatten(&mut self) {
         flatten_recur(
             &mut self.elems,
             &mut self.annotations,
             Transformation::identity(),
             &self.insts,
+            &self.arrays,
         );
         self.insts.clear();
+        self.arrays.clear();
     }
 }
 
@@ -959,6 +1123,9 @@ impl BoundBox for Cell {
                 bbox = r.union(bbox);
             }
         }
+        for array in &self.arrays {
+            bbox = array.bbox().union(bbox);
+        }
         bbox
     }
 }
@@ -978,6 +1145,9 @@ impl LayerBoundBox for Cell {
                 bbox = r.union(bbox);
             }
         }
+        for array in &self.arrays {
+            bbox = array.layer_bbox(layer).union(bbox);
+        }
         bbox
     }
 }
@@ -1539,6 +1709,323 @@ impl Transform for Instance {
 
 impl AlignRect for Instance {}
 
+impl InstanceArray {
+    /// Creates a new [`InstanceArray`] with `rows` rows spaced by `row_pitch` vertically
+    /// and `cols` columns spaced by `col_pitch` horizontally.
+    pub fn new(
+        cell: impl Into<Arc<Cell>>,
+        rows: usize,
+        cols: usize,
+        row_pitch: i64,
+        col_pitch: i64,
+    ) -> Self {
+        let cell = cell.into();
+        Self {
+            name: cell.name.clone(),
+            cell,
+            loc: Point::zero(),
+            orientation: Orientation::default(),
+            rows,
+            cols,
+            row_pitch: Point::new(0, row_pitch),
+            col_pitch: Point::new(col_pitch, 0),
+        }
+    }
+
+    /// Creates a new [`InstanceArrayBuilder`].
+    #[inline]
+    pub fn builder() -> InstanceArrayBuilder {
+        InstanceArrayBuilder::default()
+    }
+
+    /// Creates an [`InstanceArray`] from a grid of instances.
+    ///
+    /// `insts[row][col]` becomes the instance in the given row and column of the array.
+    /// Returns [`None`] if the grid is empty or ragged, or if the instances are not
+    /// uniformly spaced instances of the same cell with the same orientation.
+    pub fn from_insts(insts: &[Vec<Instance>]) -> Option<Self> {
+        let first = insts.first()?.first()?;
+        let rows = insts.len();
+        let cols = insts[0].len();
+        if insts.iter().any(|row| row.len() != cols) {
+            return None;
+        }
+
+        let row_pitch = if rows > 1 {
+            insts[1][0].loc - first.loc
+        } else {
+            Point::zero()
+        };
+        let col_pitch = if cols > 1 {
+            insts[0][1].loc - first.loc
+        } else {
+            Point::zero()
+        };
+        let array = Self {
+            name: first.name.clone(),
+            cell: first.cell.clone(),
+            loc: first.loc,
+            orientation: first.orientation,
+            rows,
+            cols,
+            row_pitch,
+            col_pitch,
+        };
+
+        for (i, row) in insts.iter().enumerate() {
+            for (j, inst) in row.iter().enumerate() {
+                if inst.cell.id() != array.cell.id()
+                    || inst.orientation != array.orientation
+                    || inst.loc != array.inst_loc(i, j)
+                {
+                    return None;
+                }
+            }
+        }
+
+        Some(array)
+    }
+
+    /// Returns the name of the array.
+    #[inline]
+    pub fn name(&self) -> &ArcStr {
+        &self.name
+    }
+
+    /// Sets the name of the array.
+    #[inline]
+    pub fn set_name(&mut self, name: impl Into<ArcStr>) {
+        self.name = name.into();
+    }
+
+    /// Returns a pointer to the array's reference cell.
+    #[inline]
+    pub fn cell(&self) -> &Arc<Cell> {
+        &self.cell
+    }
+
+    /// Returns the number of rows in the array.
+    #[inline]
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    /// Returns the number of columns in the array.
+    #[inline]
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    /// Returns the number of instances in the array.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.rows * self.cols
+    }
+
+    /// Returns true if the array contains no instances.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the offset between adjacent rows.
+    #[inline]
+    pub fn row_pitch(&self) -> Point {
+        self.row_pitch
+    }
+
+    /// Returns the offset between adjacent columns.
+    #[inline]
+    pub fn col_pitch(&self) -> Point {
+        self.col_pitch
+    }
+
+    /// Returns the location of the instance in row 0, column 0.
+    #[inline]
+    pub fn loc(&self) -> Point {
+        self.loc
+    }
+
+    /// Sets the location of the instance in row 0, column 0.
+    #[inline]
+    pub fn set_loc(&mut self, p: impl Into<Point>) {
+        self.loc = p.into();
+    }
+
+    /// Returns the orientation of each instance in the array.
+    #[inline]
+    pub fn orientation(&self) -> Orientation {
+        self.orientation
+    }
+
+    /// Sets the orientation of each instance in the array.
+    ///
+    /// The row and column pitches are not modified.
+    #[inline]
+    pub fn set_orientation(&mut self, o: impl Into<Orientation>) {
+        self.orientation = o.into();
+    }
+
+    /// Returns the location of the instance at the given row and column.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `row` or `col` are out of bounds.
+    pub fn inst_loc(&self, row: usize, col: usize) -> Point {
+        assert!(
+            row < self.rows && col < self.cols,
+            "array index ({row}, {col}) out of bounds for {}x{} array",
+            self.rows,
+            self.cols
+        );
+        let (row, col) = (row as i64, col as i64);
+        Point::new(
+            self.loc.x + col * self.col_pitch.x + row * self.row_pitch.x,
+            self.loc.y + col * self.col_pitch.y + row * self.row_pitch.y,
+        )
+    }
+
+    /// Returns the transformation of the instance at the given row and column.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `row` or `col` are out of bounds.
+    #[inline]
+    pub fn transformation(&self, row: usize, col: usize) -> Transformation {
+        Transformation::with_loc_and_orientation(self.inst_loc(row, col), self.orientation)
+    }
+
+    /// Returns the [`Instance`] at the given row and column.
+    ///
+    /// The instance is named `{name}[{row}][{col}]`.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `row` or `col` are out of bounds.
+    pub fn inst(&self, row: usize, col: usize) -> Instance {
+        Instance {
+            name: arcstr::format!("{}[{}][{}]", self.name, row, col),
+            cell: self.cell.clone(),
+            loc: self.inst_loc(row, col),
+            orientation: self.orientation,
+        }
+    }
+
+    /// Returns an iterator over the [`Instance`]s in the array, in row-major order.
+    ///
+    /// Instances are created lazily as the iterator is advanced.
+    pub fn insts(&self) -> impl Iterator<Item = Instance> + '_ {
+        (0..self.rows).flat_map(move |row| (0..self.cols).map(move |col| self.inst(row, col)))
+    }
+
+    /// Returns the port with id `id` of the instance at the given row and column.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `row` or `col` are out of bounds.
+    pub fn port(
+        &self,
+        row: usize,
+        col: usize,
+        id: impl Into<PortId>,
+    ) -> std::result::Result<TransformedPort<CellPort>, PortError> {
+        let port = self.cell.port(id)?;
+        Ok(TransformedPort {
+            transformation: self.transformation(row, col),
+            inner: port,
+        })
+    }
+
+    /// Returns the [`CellPort`]s of the instance at the given row and column.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `row` or `col` are out of bounds.
+    pub fn ports(&self, row: usize, col: usize) -> impl Iterator<Item = CellPort> + '_ {
+        let transformation = self.transformation(row, col);
+        self.cell.ports().map(move |port| {
+            TransformedPort {
+                transformation,
+                inner: port,
+            }
+            .into_cell_port()
+        })
+    }
+
+    pub fn shapes_on(&self, layer: LayerKey) -> impl Iterator<Item = Shape> + '_ {
+        self.insts()
+            .flat_map(move |inst| inst.shapes_on(layer).collect::<Vec<_>>())
+    }
+
+    /// Returns the bounding box of the array given the bounding box of its reference cell.
+    ///
+    /// Since the instance locations form a lattice, only the corner instances
+    /// need to be considered.
+    fn lattice_bbox(&self, bbox: Bbox) -> Bbox {
+        if bbox.is_empty() || self.is_empty() {
+            return Bbox::empty();
+        }
+        let rect = bbox.into_rect();
+        let mut out = Bbox::empty();
+        for row in [0, self.rows - 1] {
+            for col in [0, self.cols - 1] {
+                out = rect.transform(self.transformation(row, col)).union(out);
+            }
+        }
+        out
+    }
+
+    #[inline]
+    pub fn add_to(self, ctx: &mut LayoutCtx) -> crate::error::Result<()> {
+        ctx.draw(self)
+    }
+
+    #[inline]
+    pub fn add_to_ref(&self, ctx: &mut LayoutCtx) -> crate::error::Result<()> {
+        ctx.draw_ref(self)
+    }
+}
+
+impl BoundBox for InstanceArray {
+    fn bbox(&self) -> Bbox {
+        self.lattice_bbox(self.cell.bbox())
+    }
+}
+
+impl LayerBoundBox for InstanceArray {
+    fn layer_bbox(&self, layer: LayerKey) -> Bbox {
+        self.lattice_bbox(self.cell.layer_bbox(layer))
+    }
+}
+
+impl Translate for InstanceArray {
+    fn translate(&mut self, p: Point) {
+        self.loc.translate(p);
+    }
+}
+
+impl Transform for InstanceArray {
+    fn transform(&self, trans: Transformation) -> Self {
+        let mut value = self.clone();
+        let inst = Transformation::cascade(
+            trans,
+            Transformation::with_loc_and_orientation(self.loc, self.orientation),
+        );
+        value.orientation = inst.orientation();
+        value.loc = inst.offset_point();
+        // Pitches are displacements, so only the linear part of the transformation applies.
+        let linear = Transformation {
+            a: trans.a,
+            b: [0., 0.],
+        };
+        value.row_pitch = self.row_pitch.transform(linear);
+        value.col_pitch = self.col_pitch.transform(linear);
+        value
+    }
+}
+
+impl AlignRect for InstanceArray {}
+
 impl<'a> CellPort {
     /// Returns the shapes associated with layer `layer` in the port.
     fn _shapes(&'a self, layer: LayerKey) -> std::iter::Cloned<std::slice::Iter<'a, Shape>> {
@@ -1577,31 +2064,60 @@ pub trait Flatten {
 /// This transformation is applied to each element in the instance,
 /// and the resulting [`Element`] is added to `out`.
 ///
+/// Each [`InstanceArray`] is treated as the list of [`Instance`]s it contains.
+///
 /// Finally, this recurses on any [`Instance`]s contained within each [`Instance`].
 pub(crate) fn flatten_recur(
     elts: &mut Vec<Element>,
     annotations: &mut Vec<TextElement>,
     tx: Transformation,
     insts: &[Instance],
+    arrays: &[InstanceArray],
 ) {
     for inst in insts {
-        let tx = Transformation::cascade(tx, inst.transformation());
-        for elem in inst.cell.elems() {
-            elts.push(elem.transform(tx));
-        }
-        for elem in inst.cell.annotations() {
-            annotations.push(elem.transform(tx));
+        flatten_inst(elts, annotations, tx, inst);
+    }
+    for array in arrays {
+        for inst in array.insts() {
+            flatten_inst(elts, annotations, tx, &inst);
         }
-        flatten_recur(elts, annotations, tx, inst.cell._insts());
     }
 }
 
+fn flatten_inst(
+    elts: &mut Vec<Element>,
+    annotations: &mut Vec<TextElement>,
+    tx: Transformation,
+    inst: &Instance,
+) {
+    let tx = Transformation::cascade(tx, inst.transformation());
+    for elem in inst.cell.elems() {
+        elts.push(elem.transform(tx));
+    }
+    for elem in inst.cell.annotations() {
+        annotations.push(elem.transform(tx));
+    }
+    flatten_recur(
+        elts,
+        annotations,
+        tx,
+        inst.cell._insts(),
+        inst.cell._arrays(),
+    );
+}
+
 impl From<&Instance> for Instance {
     fn from(value: &Instance) -> Self {
         value.to_owned()
     }
 }
 
+impl From<&InstanceArray> for InstanceArray {
+    fn from(value: &InstanceArray) -> Self {
+        value.to_owned()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use slotmap::SlotMap;
@@ -1628,4 +2144,44 @@ mod tests {
             })
         );
     }
+
+    #[test]
+    fn test_shapes_on_net_in_array() {
+        let mut cells: SlotMap<CellKey, ()> = SlotMap::with_key();
+        let mut layers: SlotMap<LayerKey, ()> = SlotMap::with_key();
+        let layer = LayerSpec::drawing(layers.insert(()));
+
+        let mut leaf = Cell::new(cells.insert(()));
+        leaf.add_elements([Element::with_net_name(
+            "x",
+            layer,
+            Rect::new(Point::new(0, 0), Point::new(10, 10)),
+        )]);
+        let leaf = Arc::new(leaf);
+
+        let mut top = Cell::new(cells.insert(()));
+        for name in ["arr", "ar"] {
+            top.add_array(
+                InstanceArray::builder()
+                    .name(ArcStr::from(name))
+                    .cell(leaf.clone())
+                    .rows(2)
+                    .cols(3)
+                    .row_pitch(Point::new(0, 100))
+                    .col_pitch(Point::new(100, 0))
+                    .build()
+                    .unwrap(),
+            );
+        }
+
+        let shapes = top.shapes_on_net("arr[1][2]/x");
+        assert_eq!(shapes.len(), 1);
+        assert_eq!(shapes[0].net.as_deref(), Some("arr[1][2]/x"));
+        assert_eq!(
+            shapes[0].inner,
+            Shape::Rect(Rect::new(Point::new(200, 100), Point::new(210, 110)))
+        );
+        assert!(top.shapes_on_net("arr[2][0]/x").is_empty());
+        assert!(top.shapes_on_net("arr/x").is_empty());
+    }
 }
@@ -7,9 +7,10 @@ use subgeom::trim::Trim;
 use subgeom::{Point, Rect, Shape};
 
 use super::cell::{
-    Cell, CellKey, CellPort, Element, Flatten, Instance, PortConflictStrategy, PortError,
-    TextElement,
+    Cell, CellKey, CellPort, Element, Flatten, Instance, InstanceArray, PortConflictStrategy,
+    PortError, TextElement,
 };
+use super::fill::{DensityReport, Fill, FillCell};
 use super::group::Group;
 use super::layers::{LayerPurpose, LayersRef, UserLayer};
 use super::{Draw, DrawRef};
@@ -198,6 +199,7 @@ impl LayoutCtx {
     pub(crate) fn add_group(&mut self, group: Group) {
         self.add_elements(group.elements());
         self.add_instances(group.instances());
+        self.add_arrays(group.arrays());
         self.add_annotations(group.annotations());
     }
 
@@ -222,6 +224,28 @@ impl LayoutCtx {
         self.cell.trim::<T>(bounds);
     }
 
+    /// Fills the current cell to meet the PDK's density rules.
+    ///
+    /// Fill shapes are placed in a new child cell, which is instantiated
+    /// in the current cell as an instance named `fill`. No instance is added
+    /// if no fill is needed. Returns the density of each window before and after fill.
+    ///
+    /// See the [`fill`](super::fill) module for more information.
+    pub fn fill(&mut self) -> SubResult<DensityReport> {
+        let fill = Fill::compute(&self.inner, &self.cell)?;
+        if !fill.is_empty() {
+            let params = fill.cell_params(arcstr::format!("{}_fill", self.cell.name()));
+            let cell = self.instantiate::<FillCell>(&params)?.cell().clone();
+            let inst = Instance::builder()
+                .name(arcstr::literal!("fill"))
+                .cell(cell)
+                .build()
+                .unwrap();
+            self.cell.add_inst(inst);
+        }
+        Ok(fill.into_report())
+    }
+
     /// Sets the origin of the current cell to the given [`Point`].
     ///
     /// This is implemented by translating the contents of the cell appropriately.
@@ -240,6 +264,11 @@ impl LayoutCtx {
         self.cell.add_instances(instances);
     }
 
+    /// Adds all instance arrays from the given iterator to this cell.
+    pub(crate) fn add_arrays(&mut self, arrays: impl IntoIterator<Item = InstanceArray>) {
+        self.cell.add_arrays(arrays);
+    }
+
     /// Adds all annotations from the given iterator to this cell.
     pub(crate) fn add_annotations(&mut self, annotations: impl IntoIterator<Item = TextElement>) {
         self.cell.add_annotations(annotations);
@@ -0,0 +1,815 @@
+//! Utilities for DEF conversion.
+//!
+//! Converts between placed-and-routed Substrate cells and [`lefdef::DefDesign`]s.
+//!
+//! When exporting, the top cell's bounding box becomes the die area,
+//! its instances become components, its ports become pins,
+//! and its top-level elements annotated with net names become routed nets.
+//! Elements without a net name are not exported.
+//! Connections between nets and component pins are found by looking for overlaps
+//! between each net's shapes and the ports of each instance on the same layer.
+//!
+//! When importing, components are placed using existing cells as macros,
+//! typically the abstracts imported from LEF (see [`super::lef`]).
+//! Routed wires become elements annotated with their net names. Vias are not imported.
+//!
+//! Components are placed by the lower left corner of their macro's placement boundary,
+//! which is the [`PlacementBoundary`] stored in the macro's metadata if present,
+//! and its bounding box otherwise.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::Path;
+use std::sync::Arc;
+
+use derive_builder::Builder;
+use lefdef::{
+    DefComponent, DefConnection, DefDesign, DefLayerShape, DefNet, DefOrient, DefPin, DefPinPort,
+    DefPlacement, DefPoint, DefRoutePoint, DefShape, DefWire, DefWireStatus, LefLayerType,
+    LefLibrary,
+};
+use subgeom::bbox::{Bbox, BoundBox};
+use subgeom::orientation::{Named, Orientation};
+use subgeom::transform::{Transform, Transformation};
+use subgeom::{Point, Polygon, Rect, Shape, ShapeTrait};
+
+use super::error::{ErrorContext, ErrorHelper};
+use super::lef::{dbu_per_micron, parse_port_id, PlacementBoundary};
+use crate::component::Component;
+use crate::data::SubstrateCtx;
+use crate::deps::arcstr::ArcStr;
+use crate::error::{
+    with_err_context, ErrorContext as SubErrorContext, ErrorSource, Result as SubResult,
+};
+use crate::fmt::signal::BusFmt;
+use crate::layout::cell::{Cell, CellPort, Element, Instance};
+use crate::layout::diff::polygons;
+use crate::layout::error::{LayoutError, LayoutResult};
+use crate::layout::layers::selector::Selector;
+use crate::layout::layers::{LayerKey, LayerSpec, LayersRef};
+
+/// Options for DEF export.
+#[derive(Debug, Clone, Default, Builder)]
+pub struct DefExportOpts {
+    /// The nets to write as special nets, such as power and ground.
+    ///
+    /// Pins connected to these nets are marked as special.
+    #[builder(default, setter(into))]
+    pub special_nets: HashSet<ArcStr>,
+}
+
+/// Options for DEF import.
+#[derive(Debug, Clone, Default, Builder)]
+pub struct DefImportOpts {
+    /// The default wire width of each routing layer, in database units, keyed by layer name.
+    ///
+    /// Regular nets do not specify the width of their wires,
+    /// so a width must be provided for every layer on which regular nets are routed.
+    #[builder(default, setter(into))]
+    pub wire_widths: HashMap<ArcStr, i64>,
+}
+
+/// A DEF exporter.
+struct DefExporter<'a> {
+    opts: &'a DefExportOpts,
+    layers: LayersRef,
+    backtrace: Vec<ErrorContext>,
+}
+
+/// A DEF importer.
+struct DefImporter<'a> {
+    macros: &'a HashMap<ArcStr, Arc<Cell>>,
+    opts: &'a DefImportOpts,
+    layers: LayersRef,
+    /// The number of Substrate database units per DEF database unit.
+    scale: i64,
+    backtrace: Vec<ErrorContext>,
+}
+
+/// A placed component and its name in the exported design.
+struct PlacedComponent<'a> {
+    name: ArcStr,
+    inst: &'a Instance,
+}
+
+/// Additional [`SubstrateCtx`] methods for DEF conversion.
+impl SubstrateCtx {
+    /// Converts the cell `top` to a DEF design.
+    pub fn to_def_design(&self, top: &Cell, opts: &DefExportOpts) -> SubResult<DefDesign> {
+        let inner = || -> SubResult<DefDesign> {
+            let units = self.read().layouts().units();
+            let dbu = dbu_per_micron(units).map_err(ErrorSource::Layout)?;
+            let mut exporter = DefExporter {
+                opts,
+                layers: self.layers(),
+                backtrace: Vec::new(),
+            };
+            Ok(exporter
+                .export_design(top, dbu)
+                .map_err(ErrorSource::Layout)?)
+        };
+        with_err_context(inner(), || {
+            SubErrorContext::Task(arcstr::format!("converting cell {} to DEF", top.name()))
+        })
+    }
+
+    /// Saves the cell `top` to a DEF file.
+    pub fn to_def(
+        &self,
+        top: &Cell,
+        path: impl AsRef<Path>,
+        opts: &DefExportOpts,
+    ) -> SubResult<()> {
+        self.to_def_design(top, opts)?
+            .save(path)
+            .map_err(LayoutError::from)
+            .map_err(ErrorSource::Layout)?;
+        Ok(())
+    }
+
+    /// Writes the layout of component `T` to a DEF file.
+    pub fn write_def<T>(
+        &self,
+        params: &T::Params,
+        path: impl AsRef<Path>,
+        opts: &DefExportOpts,
+    ) -> SubResult<()>
+    where
+        T: Component,
+    {
+        let path = path.as_ref();
+        let inner = || -> SubResult<()> {
+            let inst = self.instantiate_layout::<T>(params)?;
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            self.to_def(inst.cell(), path, opts)
+        };
+        with_err_context(inner(), || {
+            SubErrorContext::Task(arcstr::format!("writing DEF to file {:?}", path))
+        })
+    }
+
+    /// Adds a cell built from a DEF design to the context.
+    ///
+    /// Components are instantiated from `macros`, a map from macro names to cells,
+    /// such as the map returned by [`SubstrateCtx::from_lef`].
+    pub fn from_def_design(
+        &self,
+        design: &DefDesign,
+        macros: &HashMap<ArcStr, Arc<Cell>>,
+        opts: &DefImportOpts,
+    ) -> SubResult<Arc<Cell>> {
+        let inner = || -> SubResult<Arc<Cell>> {
+            let units = self.read().layouts().units();
+            let dbu = dbu_per_micron(units).map_err(ErrorSource::Layout)?;
+            let mut importer = DefImporter {
+                macros,
+                opts,
+                layers: self.layers(),
+                scale: 1,
+                backtrace: Vec::new(),
+            };
+            importer.scale = importer
+                .import_units(design.dbu_per_micron, dbu)
+                .map_err(ErrorSource::Layout)?;
+
+            let mut inner = self.write();
+            let name = inner.layouts().alloc_name(design.name.clone());
+            let id = inner.layouts_mut().gen_id();
+            let mut cell = Cell::new(id);
+            cell.set_name(name);
+            importer
+                .import_design(design, &mut cell)
+                .map_err(ErrorSource::Layout)?;
+            cell.freeze();
+            Ok(inner.layouts_mut().set_cell(cell))
+        };
+        with_err_context(inner(), || {
+            SubErrorContext::Task(arcstr::format!("importing DEF design {}", design.name))
+        })
+    }
+
+    /// Adds a cell built from a DEF file to the context.
+    ///
+    /// See [`SubstrateCtx::from_def_design`].
+    pub fn from_def(
+        &self,
+        path: impl AsRef<Path>,
+        macros: &HashMap<ArcStr, Arc<Cell>>,
+        opts: &DefImportOpts,
+    ) -> SubResult<Arc<Cell>> {
+        let design = DefDesign::load(path)
+            .map_err(LayoutError::from)
+            .map_err(ErrorSource::Layout)?;
+        self.from_def_design(&design, macros, opts)
+    }
+
+    /// Adds a cell built from a DEF file to the context,
+    /// using abstracts of the macros in the LEF file at `lef`.
+    ///
+    /// The default wire width of each routing layer is taken from the LEF file.
+    pub fn from_def_with_lef(
+        &self,
+        def: impl AsRef<Path>,
+        lef: impl AsRef<Path>,
+    ) -> SubResult<Arc<Cell>> {
+        let lef = LefLibrary::load(lef)
+            .map_err(LayoutError::from)
+            .map_err(ErrorSource::Layout)?;
+        let macros = self.from_lef_lib(&lef)?;
+        let dbu = dbu_per_micron(self.read().layouts().units()).map_err(ErrorSource::Layout)?;
+        let wire_widths = lef
+            .layers
+            .iter()
+            .filter(|layer| layer.layer_type == Some(LefLayerType::Routing))
+            .filter_map(|layer| {
+                let width = layer.width?;
+                Some((layer.name.clone(), (width * dbu as f64).round() as i64))
+            })
+            .collect();
+        self.from_def(def, &macros, &DefImportOpts { wire_widths })
+    }
+}
+
+impl<'a> DefExporter<'a> {
+    fn export_design(&mut self, top: &Cell, dbu: u32) -> LayoutResult<DefDesign> {
+        self.backtrace.push(ErrorContext::Cell(top.name().clone()));
+        let mut design = DefDesign::new(top.name().clone(), dbu);
+        if let Some(boundary) = placement_boundary(top) {
+            design.die_area = vec![export_point(boundary.p0), export_point(boundary.p1)];
+        }
+
+        // Expand instance arrays, and give each component a unique name.
+        // Unnamed instances are named after their cell.
+        let arrays: Vec<Instance> = top.arrays().flat_map(|a| a.insts()).collect();
+        let mut names = HashSet::new();
+        let mut components = Vec::new();
+        for inst in top.insts().chain(arrays.iter()) {
+            let base = if inst.name().is_empty() {
+                inst.cell().name()
+            } else {
+                inst.name()
+            };
+            let mut name = base.clone();
+            let mut i = 1;
+            while names.contains(&name) {
+                name = arcstr::format!("{}_{}", base, i);
+                i += 1;
+            }
+            names.insert(name.clone());
+            components.push(PlacedComponent { name, inst });
+        }
+
+        self.backtrace.push(ErrorContext::Impl);
+        for c in components.iter() {
+            design.components.push(self.export_component(c)?);
+        }
+        self.backtrace.pop();
+
+        self.backtrace.push(ErrorContext::Ports);
+        let mut buses: Vec<_> = top.bus_ports().collect();
+        buses.sort_by_key(|(name, _)| *name);
+        for (_, bus) in buses {
+            let mut ports: Vec<&CellPort> = bus.values().collect();
+            ports.sort_by_key(|port| port.id.index());
+            for port in ports {
+                design.pins.push(self.export_pin(port, bus.len())?);
+            }
+        }
+        self.backtrace.pop();
+
+        self.backtrace.push(ErrorContext::Geometry);
+        let mut nets: BTreeMap<ArcStr, Vec<&Element>> = BTreeMap::new();
+        for elem in top.elems() {
+            if let Some(net) = &elem.net {
+                nets.entry(net.clone()).or_default().push(elem);
+            }
+        }
+        for (name, elems) in nets {
+            let net = self.export_net(&design.pins, &components, name, elems)?;
+            if self.opts.special_nets.contains(&net.name) {
+                design.special_nets.push(net);
+            } else {
+                design.nets.push(net);
+            }
+        }
+        self.backtrace.pop();
+
+        self.backtrace.pop();
+        Ok(design)
+    }
+
+    fn export_component(&mut self, c: &PlacedComponent) -> LayoutResult<DefComponent> {
+        self.backtrace.push(ErrorContext::Instance(c.name.clone()));
+        let cell = c.inst.cell();
+        let boundary = self.unwrap(
+            placement_boundary(cell),
+            format!("cannot place instance of empty cell {}", cell.name()),
+        )?;
+        let orient = self.export_orientation(c.inst.orientation())?;
+        let loc = boundary.transform(c.inst.transformation()).p0;
+        self.backtrace.pop();
+        Ok(DefComponent {
+            name: c.name.clone(),
+            macro_name: cell.name().clone(),
+            placement: Some(DefPlacement::placed(export_point(loc), orient)),
+        })
+    }
+
+    /// Exports a port of the top cell as a pin with one DEF port per shape.
+    ///
+    /// Each DEF port is placed at the center of its shape's bounding box.
+    fn export_pin(&mut self, port: &CellPort, width: usize) -> LayoutResult<DefPin> {
+        let name = port
+            .id
+            .format_signal(width, BusFmt::DoubleDelimiter('[', ']'));
+        let mut pin = DefPin {
+            name: name.clone(),
+            net: name.clone(),
+            special: self.opts.special_nets.contains(&name),
+            ..Default::default()
+        };
+        let mut layers: Vec<(&LayerKey, &Vec<Shape>)> = port.shapes.iter().collect();
+        layers.sort_by_key(|(layer, _)| **layer);
+        for (layer, shapes) in layers {
+            let layer = self.export_layer(*layer)?;
+            for shape in shapes.iter() {
+                let bbox = shape.bbox();
+                if bbox.is_empty() {
+                    continue;
+                }
+                let center = bbox.center();
+                let offset = Point::new(-center.x, -center.y);
+                for shape in self.export_shape(shape, offset, true)? {
+                    pin.ports.push(DefPinPort {
+                        shapes: vec![DefLayerShape {
+                            layer: layer.clone(),
+                            shape,
+                        }],
+                        placement: Some(DefPlacement::fixed(export_point(center), DefOrient::N)),
+                    });
+                }
+            }
+        }
+        Ok(pin)
+    }
+
+    /// Exports the elements tagged with net name `name`.
+    ///
+    /// Shapes of special nets are written as `RECT` and `POLYGON` statements.
+    /// Shapes of regular nets are written as wires consisting of a single `RECT`.
+    fn export_net(
+        &mut self,
+        pins: &[DefPin],
+        components: &[PlacedComponent],
+        name: ArcStr,
+        elems: Vec<&Element>,
+    ) -> LayoutResult<DefNet> {
+        let special = self.opts.special_nets.contains(&name);
+        let mut net = DefNet {
+            name: name.clone(),
+            ..Default::default()
+        };
+        if pins.iter().any(|pin| pin.name == name) {
+            net.connections.push(DefConnection::Pin(name.clone()));
+        }
+
+        for c in components.iter() {
+            let cell = c.inst.cell();
+            for port in c.inst.ports() {
+                let connected = port.shapes.iter().any(|(layer, shapes)| {
+                    elems
+                        .iter()
+                        .filter(|elem| elem.layer.layer() == *layer)
+                        .any(|elem| shapes.iter().any(|s| overlaps(&elem.inner, s)))
+                });
+                if connected {
+                    let width = cell.bus_port(&port.id.name()).map(|b| b.len()).unwrap_or(1);
+                    net.connections.push(DefConnection::Component {
+                        component: c.name.clone(),
+                        pin: port
+                            .id
+                            .format_signal(width, BusFmt::DoubleDelimiter('[', ']')),
+                    });
+                }
+            }
+        }
+
+        for elem in elems {
+            let layer = self.export_layer(elem.layer.layer())?;
+            if special {
+                for shape in self.export_shape(&elem.inner, Point::zero(), true)? {
+                    net.shapes.push(DefLayerShape {
+                        layer: layer.clone(),
+                        shape,
+                    });
+                }
+            } else {
+                for shape in self.export_shape(&elem.inner, Point::zero(), false)? {
+                    if let DefShape::Rect(p0, p1) = shape {
+                        net.wires.push(DefWire {
+                            status: DefWireStatus::Routed,
+                            layer: layer.clone(),
+                            points: vec![
+                                DefRoutePoint::Point {
+                                    x: p0.x,
+                                    y: p0.y,
+                                    ext: None,
+                                },
+                                DefRoutePoint::Rect {
+                                    dx0: 0,
+                                    dy0: 0,
+                                    dx1: p1.x - p0.x,
+                                    dy1: p1.y - p0.y,
+                                },
+                            ],
+                            ..Default::default()
+                        });
+                    }
+                }
+            }
+        }
+        Ok(net)
+    }
+
+    /// Converts `shape`, translated by `offset`, to DEF shapes.
+    ///
+    /// Paths are split into one rectangle per segment. Polygons are only allowed
+    /// if `polygons` is true.
+    fn export_shape(
+        &self,
+        shape: &Shape,
+        offset: Point,
+        polygons: bool,
+    ) -> LayoutResult<Vec<DefShape>> {
+        let tf = |p: Point| export_point(Point::new(p.x + offset.x, p.y + offset.y));
+        Ok(match shape {
+            Shape::Rect(r) => vec![DefShape::Rect(tf(r.p0), tf(r.p1))],
+            Shape::Polygon(p) => {
+                if !polygons {
+                    return self.fail("polygons are not supported in regular nets");
+                }
+                vec![DefShape::Polygon(
+                    p.points.iter().copied().map(tf).collect(),
+                )]
+            }
+            Shape::Path(p) => {
+                let half = p.width as i64 / 2;
+                let mut rects = Vec::new();
+                for seg in p.points.windows(2) {
+                    let (a, b) = (seg[0], seg[1]);
+                    let r = if a.x == b.x {
+                        Rect::new(Point::new(a.x - half, a.y), Point::new(a.x + half, b.y))
+                    } else if a.y == b.y {
+                        Rect::new(Point::new(a.x, a.y - half), Point::new(b.x, a.y + half))
+                    } else {
+                        return self.fail("non-Manhattan paths are not supported");
+                    };
+                    rects.push(DefShape::Rect(tf(r.p0), tf(r.p1)));
+                }
+                rects
+            }
+            Shape::Point(_) => Vec::new(),
+        })
+    }
+
+    fn export_layer(&self, layer: LayerKey) -> LayoutResult<ArcStr> {
+        self.layers
+            .name(layer)
+            .map_err(|_| self.err(format!("layer {layer:?} not found")))
+    }
+
+    fn export_orientation(&self, o: Orientation) -> LayoutResult<DefOrient> {
+        self.unwrap(
+            export_orientation(o),
+            format!("unsupported orientation {o:?}"),
+        )
+    }
+}
+
+impl ErrorHelper for DefExporter<'_> {
+    type Error = LayoutError;
+    fn err(&self, msg: impl Into<String>) -> LayoutError {
+        LayoutError::Export {
+            message: msg.into(),
+            stack: self.backtrace.clone(),
+        }
+    }
+}
+
+impl<'a> DefImporter<'a> {
+    /// Returns the number of Substrate database units per DEF database unit.
+    fn import_units(&mut self, def_dbu: u32, dbu: u32) -> LayoutResult<i64> {
+        self.backtrace.push(ErrorContext::Units);
+        self.assert(
+            def_dbu > 0 && dbu % def_dbu == 0,
+            format!("DEF units ({def_dbu} per micron) are not compatible with PDK units ({dbu} per micron)"),
+        )?;
+        self.backtrace.pop();
+        Ok((dbu / def_dbu) as i64)
+    }
+
+    fn import_design(&mut self, design: &DefDesign, cell: &mut Cell) -> LayoutResult<()> {
+        self.backtrace.push(ErrorContext::Cell(design.name.clone()));
+        if design.die_area.len() >= 2 {
+            let pts: Vec<Point> = design
+                .die_area
+                .iter()
+                .map(|p| self.import_point(*p))
+                .collect();
+            let bbox = Polygon { points: pts }.bbox();
+            cell.set_metadata(PlacementBoundary(bbox.into_rect()));
+        }
+
+        self.backtrace.push(ErrorContext::Impl);
+        for c in design.components.iter() {
+            cell.add_inst(self.import_component(c)?);
+        }
+        self.backtrace.pop();
+
+        self.backtrace.push(ErrorContext::Ports);
+        for pin in design.pins.iter() {
+            cell.merge_port(self.import_pin(pin)?);
+        }
+        self.backtrace.pop();
+
+        self.backtrace.push(ErrorContext::Geometry);
+        for net in design.special_nets.iter().chain(design.nets.iter()) {
+            cell.add_elements(self.import_net(net)?);
+        }
+        self.backtrace.pop();
+
+        self.backtrace.pop();
+        Ok(())
+    }
+
+    fn import_component(&mut self, c: &DefComponent) -> LayoutResult<Instance> {
+        self.backtrace.push(ErrorContext::Instance(c.name.clone()));
+        let cell = self.unwrap(
+            self.macros.get(&c.macro_name),
+            format!("macro {} not found", c.macro_name),
+        )?;
+        let placement = c.placement.unwrap_or_default();
+        let orientation = import_orientation(placement.orient);
+
+        // Shift the instance so that the lower left corner of its
+        // oriented placement boundary lies at the placement location.
+        let loc = self.import_point(placement.loc);
+        let boundary = placement_boundary(cell).unwrap_or_default();
+        let ll = boundary
+            .transform(Transformation::with_loc_and_orientation(
+                Point::zero(),
+                orientation,
+            ))
+            .p0;
+        let inst = Instance::builder()
+            .name(c.name.clone())
+            .cell(cell.clone())
+            .loc(Point::new(loc.x - ll.x, loc.y - ll.y))
+            .orientation(orientation)
+            .build()
+            .unwrap();
+        self.backtrace.pop();
+        Ok(inst)
+    }
+
+    fn import_pin(&mut self, pin: &DefPin) -> LayoutResult<CellPort> {
+        let mut port = CellPort::new(parse_port_id(&pin.name));
+        for p in pin.ports.iter() {
+            let placement = p.placement.unwrap_or_default();
+            let tf = Transformation::with_loc_and_orientation(
+                self.import_point(placement.loc),
+                import_orientation(placement.orient),
+            );
+            for shape in p.shapes.iter() {
+                let layer = self.import_layer(&shape.layer)?;
+                port.add(layer, self.import_shape(&shape.shape).transform(tf));
+            }
+        }
+        Ok(port)
+    }
+
+    fn import_net(&mut self, net: &DefNet) -> LayoutResult<Vec<Element>> {
+        let mut elems = Vec::new();
+        for wire in net.wires.iter() {
+            let layer = self.import_layer(&wire.layer)?;
+            let width = self.opts.wire_widths.get(&wire.layer).copied();
+            let segments = wire
+                .points
+                .iter()
+                .filter(|p| matches!(p, DefRoutePoint::Point { .. }))
+                .count();
+            self.assert(
+                wire.width.is_some() || width.is_some() || segments < 2,
+                format!("no wire width specified for layer {}", wire.layer),
+            )?;
+            // Default widths are specified in Substrate units.
+            let width = width.map(|w| w / self.scale);
+            for (p0, p1) in wire.rects(width) {
+                elems.push(Element::with_net_name(
+                    net.name.clone(),
+                    LayerSpec::drawing(layer),
+                    Rect::new(self.import_point(p0), self.import_point(p1)),
+                ));
+            }
+        }
+        for shape in net.shapes.iter() {
+            let layer = self.import_layer(&shape.layer)?;
+            elems.push(Element::with_net_name(
+                net.name.clone(),
+                LayerSpec::drawing(layer),
+                self.import_shape(&shape.shape),
+            ));
+        }
+        Ok(elems)
+    }
+
+    fn import_shape(&self, shape: &DefShape) -> Shape {
+        match shape {
+            DefShape::Rect(p0, p1) => {
+                Shape::Rect(Rect::new(self.import_point(*p0), self.import_point(*p1)))
+            }
+            DefShape::Polygon(pts) => Shape::Polygon(Polygon {
+                points: pts.iter().map(|p| self.import_point(*p)).collect(),
+            }),
+        }
+    }
+
+    fn import_layer(&self, name: &str) -> LayoutResult<LayerKey> {
+        self.layers
+            .get(Selector::Name(name))
+            .map_err(|_| self.err(format!("layer {name} not found")))
+    }
+
+    fn import_point(&self, p: DefPoint) -> Point {
+        Point::new(p.x * self.scale, p.y * self.scale)
+    }
+}
+
+impl ErrorHelper for DefImporter<'_> {
+    type Error = LayoutError;
+    fn err(&self, msg: impl Into<String>) -> LayoutError {
+        LayoutError::Import {
+            message: msg.into(),
+            stack: self.backtrace.clone(),
+        }
+    }
+}
+
+/// Returns the placement boundary of `cell`, or [`None`] if the cell is empty.
+fn placement_boundary(cell: &Cell) -> Option<Rect> {
+    if let Some(PlacementBoundary(rect)) = cell.try_get_metadata::<PlacementBoundary>() {
+        return Some(*rect);
+    }
+    let bbox: Bbox = cell.bbox();
+    (!bbox.is_empty()).then(|| bbox.into_rect())
+}
+
+/// Returns `true` if shapes `a` and `b` overlap or touch.
+///
+/// Paths are treated as the union of the polygons covering each of their segments.
+fn overlaps(a: &Shape, b: &Shape) -> bool {
+    if a.bbox().intersection(b.bbox()).is_empty() {
+        return false;
+    }
+    let (a, b) = (polygons(a), polygons(b));
+    a.iter().any(|pa| b.iter().any(|pb| polygons_touch(pa, pb)))
+}
+
+/// Returns `true` if the closed polygons `a` and `b` share at least one point.
+///
+/// Either the boundaries of the polygons cross, or one polygon lies entirely inside the other.
+fn polygons_touch(a: &[Point], b: &[Point]) -> bool {
+    if a.is_empty() || b.is_empty() {
+        return false;
+    }
+    let edges = |poly: &[Point]| {
+        (0..poly.len())
+            .map(|i| (poly[i], poly[(i + 1) % poly.len()]))
+            .collect::<Vec<_>>()
+    };
+    let (edges_a, edges_b) = (edges(a), edges(b));
+    edges_a.iter().any(|&(p0, p1)| {
+        edges_b
+            .iter()
+            .any(|&(q0, q1)| segments_touch(p0, p1, q0, q1))
+    }) || Polygon { points: a.to_vec() }.contains(b[0])
+        || Polygon { points: b.to_vec() }.contains(a[0])
+}
+
+/// Returns `true` if the closed segments `p0`-`p1` and `q0`-`q1` share at least one point.
+fn segments_touch(p0: Point, p1: Point, q0: Point, q1: Point) -> bool {
+    let d0 = cross(q0, q1, p0);
+    let d1 = cross(q0, q1, p1);
+    let d2 = cross(p0, p1, q0);
+    let d3 = cross(p0, p1, q1);
+    if d0.signum() * d1.signum() < 0 && d2.signum() * d3.signum() < 0 {
+        return true;
+    }
+    (d0 == 0 && in_box(q0, q1, p0))
+        || (d1 == 0 && in_box(q0, q1, p1))
+        || (d2 == 0 && in_box(p0, p1, q0))
+        || (d3 == 0 && in_box(p0, p1, q1))
+}
+
+/// The cross product of `a - o` and `b - o`.
+fn cross(o: Point, a: Point, b: Point) -> i128 {
+    (a.x - o.x) as i128 * (b.y - o.y) as i128 - (a.y - o.y) as i128 * (b.x - o.x) as i128
+}
+
+/// Returns `true` if `p` lies within the bounding box of `a` and `b`.
+fn in_box(a: Point, b: Point, p: Point) -> bool {
+    a.x.min(b.x) <= p.x && p.x <= a.x.max(b.x) && a.y.min(b.y) <= p.y && p.y <= a.y.max(b.y)
+}
+
+fn export_point(p: Point) -> DefPoint {
+    DefPoint::new(p.x, p.y)
+}
+
+/// Converts a rectangular [`Orientation`] to a [`DefOrient`].
+fn export_orientation(o: Orientation) -> Option<DefOrient> {
+    let named = Named::all_rectangular()
+        .into_iter()
+        .find(|named| Orientation::from(*named) == o)?;
+    Some(match named {
+        Named::Default => DefOrient::N,
+        Named::R90 => DefOrient::W,
+        Named::R180 => DefOrient::S,
+        Named::R270 => DefOrient::E,
+        Named::ReflectHoriz => DefOrient::FN,
+        Named::ReflectVert => DefOrient::FS,
+        Named::FlipYx => DefOrient::FW,
+        Named::FlipMinusYx => DefOrient::FE,
+        _ => unreachable!(),
+    })
+}
+
+fn import_orientation(o: DefOrient) -> Orientation {
+    match o {
+        DefOrient::N => Named::Default,
+        DefOrient::W => Named::R90,
+        DefOrient::S => Named::R180,
+        DefOrient::E => Named::R270,
+        DefOrient::FN => Named::ReflectHoriz,
+        DefOrient::FS => Named::ReflectVert,
+        DefOrient::FW => Named::FlipYx,
+        DefOrient::FE => Named::FlipMinusYx,
+    }
+    .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layout::cell::PortId;
+
+    #[test]
+    fn test_orientation_round_trip() {
+        for o in [
+            DefOrient::N,
+            DefOrient::W,
+            DefOrient::S,
+            DefOrient::E,
+            DefOrient::FN,
+            DefOrient::FW,
+            DefOrient::FS,
+            DefOrient::FE,
+        ] {
+            assert_eq!(export_orientation(import_orientation(o)), Some(o));
+        }
+    }
+
+    #[test]
+    fn test_overlaps() {
+        // An L-shaped polygon whose bounding box contains `notch`.
+        let l = Shape::Polygon(Polygon {
+            points: vec![
+                Point::new(0, 0),
+                Point::new(100, 0),
+                Point::new(100, 20),
+                Point::new(20, 20),
+                Point::new(20, 100),
+                Point::new(0, 100),
+            ],
+        });
+        let notch = Shape::Rect(Rect::new(Point::new(50, 50), Point::new(90, 90)));
+        let abutting = Shape::Rect(Rect::new(Point::new(20, 40), Point::new(60, 60)));
+        let inside = Shape::Rect(Rect::new(Point::new(5, 5), Point::new(10, 10)));
+        let crossing = Shape::Rect(Rect::new(Point::new(50, -10), Point::new(60, 200)));
+
+        assert!(!overlaps(&l, &notch));
+        assert!(!overlaps(&notch, &l));
+        assert!(overlaps(&l, &abutting));
+        assert!(overlaps(&l, &inside));
+        assert!(overlaps(&inside, &l));
+        assert!(overlaps(&l, &crossing));
+        assert!(!overlaps(&notch, &inside));
+    }
+
+    #[test]
+    fn test_parse_port_id() {
+        assert_eq!(parse_port_id("din[3]"), PortId::new("din", 3));
+        assert_eq!(parse_port_id("vdd"), PortId::new("vdd", 0));
+        assert_eq!(parse_port_id("x[a]"), PortId::new("x[a]", 0));
+    }
+}
@@ -8,6 +8,7 @@ use std::hash::Hash;
 use std::sync::{Arc, RwLock};
 
 use derivative::Derivative;
+use derive_builder::Builder;
 use slotmap::{new_key_type, SecondaryMap, SlotMap};
 use subgeom::bbox::BoundBox;
 use subgeom::orientation::Orientation;
@@ -20,8 +21,10 @@ use crate::error::{
     with_err_context, ErrorContext as SubErrorContext, ErrorSource, Result as SubResult,
 };
 use crate::fmt::signal::BusFmt;
-use crate::layout::cell::{BusPort, Cell, CellKey, CellPort, Element, Instance, TextElement};
-use crate::layout::context::LayoutCtx;
+use crate::layout::cell::{
+    BusPort, Cell, CellKey, CellPort, Element, Instance, InstanceArray, TextElement,
+};
+use crate::layout::context::{LayoutCtx, LayoutData};
 use crate::layout::error::{LayoutError, LayoutResult};
 use crate::layout::layers::{GdsLayerSpec, LayerInfo, LayerKey, LayerPurpose, LayerSpec, Layers};
 use crate::units::SiPrefix;
@@ -31,8 +34,27 @@ new_key_type! {
     pub struct ElementKey;
 }
 
+/// The GDS property attribute (`PROPATTR`) under which element net names are stored.
+///
+/// Net names are written as the `PROPVALUE` of a property with this attribute
+/// on each exported boundary, path, and net label.
+pub const NET_NAME_PROPATTR: i16 = 1;
+
+/// Options for GDSII export.
+#[derive(Debug, Clone, Default, Builder)]
+pub struct GdsExportOpts {
+    /// Whether to add a text label for each [`Element`] with a net name.
+    ///
+    /// Labels are placed on the label purpose of the element's layer.
+    /// Net names are always stored as element properties,
+    /// so labels are only needed by tools that do not read GDS properties.
+    #[builder(default)]
+    pub net_labels: bool,
+}
+
+/// The set of cells to export.
 #[derive(Debug, Clone, Default)]
-enum ExportSet {
+pub(super) enum ExportSet {
     #[default]
     All,
     Set(HashSet<CellKey>),
@@ -48,13 +70,9 @@ pub struct GdsExporter<'a> {
     data: &'a SubstrateData,
     layers: Arc<RwLock<Layers>>,
     backtrace: Vec<ErrorContext>,
-    names_used: HashSet<ArcStr>,
-    /// The top level cell.
-    ///
-    /// The name of this cell will be preserved.
-    top: Option<Arc<Cell>>,
     export_set: ExportSet,
     names: SecondaryMap<CellKey, ArcStr>,
+    opts: GdsExportOpts,
 }
 
 /// A GDSII importer.
@@ -75,16 +93,19 @@ pub struct GdsImporter<'a> {
 impl SubstrateCtx {
     /// Converts the context to a GDSII library.
     pub fn to_gds_lib(&self) -> SubResult<gds21::GdsLibrary> {
+        self.to_gds_lib_with_opts(&GdsExportOpts::default())
+    }
+    /// Converts the context to a GDSII library using the given export options.
+    pub fn to_gds_lib_with_opts(&self, opts: &GdsExportOpts) -> SubResult<gds21::GdsLibrary> {
         let data = self.read();
         let inner = || -> SubResult<gds21::GdsLibrary> {
             let data = GdsExporter {
                 data: &data,
                 layers: data.layers(),
                 backtrace: Vec::new(),
-                names_used: HashSet::with_capacity(data.layouts().cells().count()),
-                top: None,
                 export_set: ExportSet::All,
-                names: SecondaryMap::new(),
+                names: unique_cell_names(data.layouts(), None),
+                opts: opts.clone(),
             }
             .export_lib()
             .map_err(ErrorSource::Layout)?;
@@ -96,18 +117,22 @@ impl SubstrateCtx {
             ))
         })
     }
-    /// Converts the context to a GDSII library.
-    pub(crate) fn to_gds_lib_with_top(&self, top: Arc<Cell>) -> SubResult<gds21::GdsLibrary> {
+    /// Converts `top` and the cells it instantiates to a GDSII library
+    /// using the given export options.
+    pub(crate) fn to_gds_lib_with_top(
+        &self,
+        top: Arc<Cell>,
+        opts: &GdsExportOpts,
+    ) -> SubResult<gds21::GdsLibrary> {
         let data = self.read();
         let inner = || -> SubResult<gds21::GdsLibrary> {
             Ok(GdsExporter {
                 data: &data,
                 layers: data.layers(),
                 backtrace: Vec::new(),
-                names_used: HashSet::with_capacity(data.layouts().cells().count()),
                 export_set: ExportSet::for_top(&top),
-                top: Some(top),
-                names: SecondaryMap::new(),
+                names: unique_cell_names(data.layouts(), Some(&top)),
+                opts: opts.clone(),
             }
             .export_lib()
             .map_err(ErrorSource::Layout)?)
@@ -118,8 +143,16 @@ impl SubstrateCtx {
     }
     /// Saves the context to a GDS file.
     pub fn to_gds(&self, path: impl AsRef<std::path::Path>) -> SubResult<()> {
+        self.to_gds_with_opts(path, &GdsExportOpts::default())
+    }
+    /// Saves the context to a GDS file using the given export options.
+    pub fn to_gds_with_opts(
+        &self,
+        path: impl AsRef<std::path::Path>,
+        opts: &GdsExportOpts,
+    ) -> SubResult<()> {
         let inner = || -> SubResult<()> {
-            self.to_gds_lib()?
+            self.to_gds_lib_with_opts(opts)?
                 .save(path)
                 .map_err(LayoutError::from)
                 .map_err(ErrorSource::Layout)?;
@@ -129,14 +162,16 @@ impl SubstrateCtx {
             SubErrorContext::Task(arcstr::literal!("converting cells in context to GDS"))
         })
     }
-    /// Saves the context to a GDS file.
+    /// Saves `top` and the cells it instantiates to a GDS file
+    /// using the given export options.
     pub(crate) fn to_gds_with_top(
         &self,
         top: Arc<Cell>,
         path: impl AsRef<std::path::Path>,
+        opts: &GdsExportOpts,
     ) -> SubResult<()> {
         let inner = || -> SubResult<()> {
-            self.to_gds_lib_with_top(top)?
+            self.to_gds_lib_with_top(top, opts)?
                 .save(path)
                 .map_err(LayoutError::from)
                 .map_err(ErrorSource::Layout)?;
@@ -245,21 +280,8 @@ impl LayoutCtx {
 }
 
 impl<'a> GdsExporter<'a> {
-    /// Runs basic preprocessing before export.
-    fn prepare(&mut self) {
-        if let Some(ref top) = self.top {
-            self.names_used.insert(top.name().to_owned());
-        }
-        let layouts = self.data.layouts();
-        for cell in layouts.cells() {
-            let name = self.get_cell_name(cell);
-            self.names.insert(cell.id(), name);
-        }
-    }
     /// Exports to a [`gds21::GdsLibrary`].
     fn export_lib(&mut self) -> LayoutResult<gds21::GdsLibrary> {
-        self.prepare();
-
         self.backtrace.push(ErrorContext::Library);
         // Create a new GDS library.
         let mut gdslib = gds21::GdsLibrary::new("TOP".to_string());
@@ -299,6 +321,11 @@ impl<'a> GdsExporter<'a> {
             elems.push(self.export_instance(inst)?.into());
         }
 
+        // Convert each [`InstanceArray`]
+        for array in cell.arrays().filter(|array| !array.is_empty()) {
+            elems.push(self.export_instance_array(array)?.into());
+        }
+
         // Convert each [`Element`]
         // Note each can produce more than one [GdsElement]
         self.backtrace.push(ErrorContext::Geometry);
@@ -343,6 +370,42 @@ impl<'a> GdsExporter<'a> {
         self.backtrace.pop();
         Ok(gdsinst)
     }
+    /// Converts an [`InstanceArray`] to a GDS array reference ([`gds21::GdsArrayRef`]).
+    ///
+    /// The three reference points are the location of the first instance,
+    /// and that location displaced by `cols` column pitches and `rows` row pitches.
+    fn export_instance_array(&mut self, array: &InstanceArray) -> LayoutResult<gds21::GdsArrayRef> {
+        self.backtrace
+            .push(ErrorContext::Array(array.name().clone()));
+        let cell = array.cell();
+        let cols = i16::try_from(array.cols())?;
+        let rows = i16::try_from(array.rows())?;
+        let p0 = array.loc();
+        let col_pitch = array.col_pitch();
+        let row_pitch = array.row_pitch();
+        let p1 = Point::new(
+            p0.x + i64::from(cols) * col_pitch.x,
+            p0.y + i64::from(cols) * col_pitch.y,
+        );
+        let p2 = Point::new(
+            p0.x + i64::from(rows) * row_pitch.x,
+            p0.y + i64::from(rows) * row_pitch.y,
+        );
+        let gdsarray = gds21::GdsArrayRef {
+            name: self.names[cell.id()].clone(),
+            xy: [
+                self.export_point(&p0)?,
+                self.export_point(&p1)?,
+                self.export_point(&p2)?,
+            ],
+            cols,
+            rows,
+            strans: array.orientation().into(),
+            ..Default::default()
+        };
+        self.backtrace.pop();
+        Ok(gdsarray)
+    }
     /// Converts a [`LayerSpec`] combination to a [`gds21::GdsLayerSpec`].
     pub fn export_layerspec(&mut self, spec: &LayerSpec) -> LayoutResult<gds21::GdsLayerSpec> {
         let layers = self.layers.read().unwrap();
@@ -365,15 +428,37 @@ impl<'a> GdsExporter<'a> {
     }
     /// Converts an [`Element`] into one or more [`gds21::GdsElement`]s.
     ///
+    /// The element's net name, if any, is stored as a property with attribute [`NET_NAME_PROPATTR`].
+    /// If net labels are enabled, a text label carrying the same property is also added.
     pub fn export_element(&mut self, elem: &Element) -> LayoutResult<Vec<gds21::GdsElement>> {
         // Get the element's layer-numbers pair
         let layerspec = self.export_layerspec(&elem.layer)?;
         // Convert its core inner [Shape]
-        let gds_elems = match self.export_shape(&elem.inner, &layerspec)? {
+        let mut gds_elems = match self.export_shape(&elem.inner, &layerspec)? {
             Some(x) => vec![x],
             None => Vec::new(),
         };
-        // FIXME: Find a way to store net labels in GDS
+        if let Some(net) = &elem.net {
+            let prop = gds21::GdsProperty {
+                attr: NET_NAME_PROPATTR,
+                value: net.clone(),
+            };
+            for gds_elem in gds_elems.iter_mut() {
+                match gds_elem {
+                    gds21::GdsElement::GdsBoundary(x) => x.properties.push(prop.clone()),
+                    gds21::GdsElement::GdsPath(x) => x.properties.push(prop.clone()),
+                    _ => unreachable!("shapes are exported as boundaries or paths"),
+                }
+            }
+            if self.opts.net_labels && !gds_elems.is_empty() {
+                let label_spec = self.export_label_layerspec(elem.layer.layer())?;
+                let mut label = self.export_shape_label(net.clone(), &elem.inner, &label_spec)?;
+                if let gds21::GdsElement::GdsTextElem(ref mut x) = label {
+                    x.properties.push(prop);
+                }
+                gds_elems.push(label);
+            }
+        }
         Ok(gds_elems)
     }
     /// Converts a [`Shape`] to a [`gds21::GdsElement`].
@@ -527,39 +612,6 @@ impl<'a> GdsExporter<'a> {
         let y = pt.y.try_into()?;
         Ok(gds21::GdsPoint::new(x, y))
     }
-
-    /// Renames the cell with the given name to avoid duplicate cell names.
-    ///
-    /// Does not rename the top cell. However, to ensure no other cell takes
-    /// the top cell's name, the top cell name must be inserted into the
-    /// `names_used` map prior to calling this function.
-    fn get_cell_name(&mut self, cell: &Arc<Cell>) -> ArcStr {
-        let name = cell.name();
-        let name = if self.names_used.contains(name) && !self.is_top(cell) {
-            let mut i = 1;
-            loop {
-                let newname = arcstr::format!("{}_{}", name, i);
-                if !self.names_used.contains(&newname) {
-                    break newname;
-                }
-                i += 1;
-            }
-        } else {
-            name.clone()
-        };
-
-        self.names_used.insert(name.clone());
-        name
-    }
-
-    /// Checks if `cell`'s ID matches the top cell's ID.
-    fn is_top(&self, cell: &Arc<Cell>) -> bool {
-        if let Some(ref top) = self.top {
-            top.id() == cell.id()
-        } else {
-            false
-        }
-    }
 }
 
 impl ErrorHelper for GdsExporter<'_> {
@@ -578,7 +630,7 @@ impl ErrorHelper for GdsExporter<'_> {
 ///
 /// While Layout21 formats do not include "placed text", GDSII relies on it for connectivity annotations.
 /// How to place these labels varies by shape type.
-trait PlaceLabels {
+pub(super) trait PlaceLabels {
     fn label_location(&self) -> Point;
 }
 impl PlaceLabels for Shape {
@@ -701,7 +753,7 @@ impl<'a> GdsDepOrder<'a> {
 
 impl<'a> GdsImporter<'a> {
     /// Creates a new [`GdsImporter`].
-    fn new(data: &'a mut SubstrateData, layers: &'a mut Layers) -> Self {
+    pub(super) fn new(data: &'a mut SubstrateData, layers: &'a mut Layers) -> Self {
         GdsImporter {
             data,
             layers,
@@ -710,6 +762,14 @@ impl<'a> GdsImporter<'a> {
             cell_map: HashMap::new(),
         }
     }
+    /// Returns the imported cell originally named `name`, if it exists.
+    pub(super) fn cell(&self, name: &str) -> Option<&Arc<Cell>> {
+        self.cell_map.get(name)
+    }
+    /// Consumes the importer, returning a map from original cell names to imported cells.
+    pub(super) fn into_cell_map(self) -> HashMap<ArcStr, Arc<Cell>> {
+        self.cell_map
+    }
     /// Imports a [gds21::GdsLibrary].
     fn import_all(&mut self, gdslib: &gds21::GdsLibrary) -> LayoutResult<()> {
         self.backtrace.push(ErrorContext::Library);
@@ -764,7 +824,7 @@ impl<'a> GdsImporter<'a> {
         self.check_units(&gdslib.units)
     }
     /// Checks that the database units match up with the units specified by the PDK.
-    fn check_units(&mut self, units: &gds21::GdsUnits) -> LayoutResult<()> {
+    pub(super) fn check_units(&mut self, units: &gds21::GdsUnits) -> LayoutResult<()> {
         self.backtrace.push(ErrorContext::Units);
         // Peel out the GDS "database unit", the one of its numbers that really matters
         let gdsunit = units.db_unit();
@@ -787,6 +847,16 @@ impl<'a> GdsImporter<'a> {
     }
     /// Imports and adds a cell if not already defined
     fn import_and_add(&mut self, strukt: &gds21::GdsStruct) -> LayoutResult<()> {
+        self.import_and_add_with_insts(strukt, Vec::new(), Vec::new())
+    }
+    /// Imports and adds a cell if not already defined,
+    /// including the given [`Instance`]s and [`InstanceArray`]s alongside those defined in `strukt`.
+    pub(super) fn import_and_add_with_insts(
+        &mut self,
+        strukt: &gds21::GdsStruct,
+        insts: Vec<Instance>,
+        arrays: Vec<InstanceArray>,
+    ) -> LayoutResult<()> {
         let name = &strukt.name;
         // Check whether we're already defined, and bail if so
         if self.cell_map.get(name).is_some() {
@@ -800,6 +870,8 @@ impl<'a> GdsImporter<'a> {
         let mut cell = Cell::new(id);
         cell.set_name(new_name);
         self.import_cell(strukt, &mut cell)?;
+        cell.add_insts(insts);
+        cell.add_arrays(arrays);
         self.data.layouts_mut().set_cell(cell);
         let cell = self.data.layouts().get_by_id(id).unwrap();
         // And add the cell to our name-map
@@ -857,6 +929,11 @@ impl<'a> GdsImporter<'a> {
         // Text elements which do not overlap a geometric element on the same layer
         // are converted to annotations.
         for textelem in &texts {
+            // Net labels written alongside named elements carry the net name as a property.
+            // The net name is already imported from the element itself.
+            if import_net_name(&textelem.properties).is_some() {
+                continue;
+            }
             // Import the GDS text element into a Substrate text element, creating missing layers
             // as necessary.
             let text_elem = self.import_text_elem(textelem)?;
@@ -961,7 +1038,7 @@ impl<'a> GdsImporter<'a> {
         let layer = self.import_element_layer(x)?;
         // Create the Element, and insert it in our slotmap
         let e = Element {
-            net: None,
+            net: import_net_name(&x.properties),
             layer,
             inner,
         };
@@ -985,7 +1062,7 @@ impl<'a> GdsImporter<'a> {
         let layer = self.import_element_layer(x)?;
         // Create the Element, and insert it in our slotmap
         let e = Element {
-            net: None,
+            net: import_net_name(&x.properties),
             layer,
             inner,
         };
@@ -1009,7 +1086,7 @@ impl<'a> GdsImporter<'a> {
         let layer = self.import_element_layer(x)?;
         // Create the Element, and insert it in our slotmap
         let e = Element {
-            net: None,
+            net: import_net_name(&x.properties),
             layer,
             inner,
         };
@@ -1056,11 +1133,8 @@ impl<'a> GdsImporter<'a> {
     /// In principle these need not be the same as "x" and "y" spacing,
     /// i.e. there might be "diamond-shaped" array specifications.
     ///
-    /// Here, arrays are supported if they are "specified rectangular",
-    /// i.e. that (a) the first two points align in `y`, and (b) the second two points align in `x`.
-    ///
-    /// Further support for such "non-rectangular-specified" arrays may (or may not) become a future addition,
-    /// based on observed GDSII usage.
+    /// All such arrays are supported. The points are interpreted in the parent cell's
+    /// coordinate frame, so the array's orientation applies only to each instance.
     fn import_instance_array(&mut self, aref: &gds21::GdsArrayRef) -> LayoutResult<Vec<Instance>> {
         let cname = aref.name.clone();
         self.backtrace.push(ErrorContext::Array(cname.clone()));
@@ -1076,35 +1150,29 @@ impl<'a> GdsImporter<'a> {
         let p0 = self.import_point(&aref.xy[0])?;
         let p1 = self.import_point(&aref.xy[1])?;
         let p2 = self.import_point(&aref.xy[2])?;
-        // Check for (thus far) unsupported non-rectangular arrays
-        if p0.y != p1.y || p0.x != p2.x {
-            self.fail("Unsupported Non-Rectangular GDS Array")?;
+        if aref.cols <= 0 || aref.rows <= 0 {
+            self.fail("Invalid GDS Array dimensions")?;
         }
-        // Sort out the inter-element spacing
-        let mut xstep = (p1.x - p0.x) / i64::from(aref.cols);
-        let mut ystep = (p2.y - p0.y) / i64::from(aref.rows);
+        // Sort out the inter-element spacing.
+        // The reference points are given in the parent's coordinate frame,
+        // so the lattice vectors are not affected by the array's orientation.
+        let cols = i64::from(aref.cols);
+        let rows = i64::from(aref.rows);
+        let colstep = Point::new((p1.x - p0.x) / cols, (p1.y - p0.y) / cols);
+        let rowstep = Point::new((p2.x - p0.x) / rows, (p2.y - p0.y) / rows);
 
         // Incorporate the reflection/ rotation settings
         let mut orientation = Orientation::default();
         if let Some(strans) = &aref.strans {
             orientation = self.import_orientation(strans)?;
         }
-        // The angle-setting rotates the *entire* array lattice together.
-        // Update the (x,y) steps via a rotation-matrix multiplication:
-        // x = x * cos(a) - y * sin(a)
-        // y = x * sin(a) + y * cos(a)
-        let prev_xy = (i32::try_from(xstep)?, i32::try_from(ystep)?);
-        let prev_xy = (f64::from(prev_xy.0), f64::from(prev_xy.1));
-        let a = orientation.angle().to_radians(); // Rust `sin` and `cos` take radians, convert first
-        xstep = (prev_xy.0 * a.cos() - prev_xy.1 * a.sin()) as i64;
-        ystep = (prev_xy.0 * a.sin() + prev_xy.1 * a.cos()) as i64;
 
         // Create the Instances
-        let mut insts = Vec::with_capacity((aref.rows * aref.cols) as usize);
-        for ix in 0..i64::from(aref.cols) {
-            let x = p0.x + ix * xstep;
-            for iy in 0..i64::from(aref.rows) {
-                let y = p0.y + iy * ystep;
+        let mut insts = Vec::with_capacity((rows * cols) as usize);
+        for ix in 0..cols {
+            for iy in 0..rows {
+                let x = p0.x + ix * colstep.x + iy * rowstep.x;
+                let y = p0.y + ix * colstep.y + iy * rowstep.y;
                 insts.push(
                     Instance::builder()
                         .name(ArcStr::from(format!("{cname}[{ix}][{iy}]")))
@@ -1132,7 +1200,10 @@ impl<'a> GdsImporter<'a> {
             .collect::<Result<Vec<_>, _>>()
     }
     /// Imports an orientation.
-    fn import_orientation(&mut self, strans: &gds21::GdsStrans) -> LayoutResult<Orientation> {
+    pub(super) fn import_orientation(
+        &mut self,
+        strans: &gds21::GdsStrans,
+    ) -> LayoutResult<Orientation> {
         if strans.abs_mag || strans.abs_angle {
             return self.fail("Unsupported GDSII Instance Feature: Absolute Magnitude/ Angle");
         }
@@ -1172,6 +1243,40 @@ impl<'a> ErrorHelper for GdsImporter<'a> {
     }
 }
 
+/// Assigns a unique export name to each cell in `layouts`.
+///
+/// Cells whose names are already taken are renamed by appending `_{i}`.
+/// The name of `top`, if given, is always preserved.
+pub(super) fn unique_cell_names(
+    layouts: &LayoutData,
+    top: Option<&Arc<Cell>>,
+) -> SecondaryMap<CellKey, ArcStr> {
+    let mut names_used = HashSet::with_capacity(layouts.cells().count());
+    if let Some(top) = top {
+        names_used.insert(top.name().clone());
+    }
+    let mut names = SecondaryMap::new();
+    for cell in layouts.cells() {
+        let name = cell.name();
+        let is_top = top.is_some_and(|top| top.id() == cell.id());
+        let name = if names_used.contains(name) && !is_top {
+            let mut i = 1;
+            loop {
+                let newname = arcstr::format!("{}_{}", name, i);
+                if !names_used.contains(&newname) {
+                    break newname;
+                }
+                i += 1;
+            }
+        } else {
+            name.clone()
+        };
+        names_used.insert(name.clone());
+        names.insert(cell.id(), name);
+    }
+    names
+}
+
 impl ExportSet {
     #[inline]
     pub fn contains(&self, key: &CellKey) -> bool {
@@ -1188,8 +1293,11 @@ impl ExportSet {
         set.insert(top.id());
 
         while let Some(cell) = stack.pop_front() {
-            for inst in cell.insts() {
-                let cell = inst.cell();
+            let cells = cell
+                .insts()
+                .map(|inst| inst.cell())
+                .chain(cell.arrays().map(|array| array.cell()));
+            for cell in cells {
                 let id = cell.id();
                 if !set.contains(&id) {
                     set.insert(id);
@@ -1201,3 +1309,13 @@ impl ExportSet {
         Self::Set(set)
     }
 }
+
+/// Returns the net name stored in a set of GDS element properties, if any.
+///
+/// See [`NET_NAME_PROPATTR`].
+fn import_net_name(properties: &[gds21::GdsProperty]) -> Option<ArcStr> {
+    properties
+        .iter()
+        .find(|prop| prop.attr == NET_NAME_PROPATTR)
+        .map(|prop| prop.value.clone())
+}
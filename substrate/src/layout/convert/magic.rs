@@ -0,0 +1,652 @@
+//! Utilities for Magic conversion.
+//!
+//! Converts between Substrate cells and Magic [`MagCell`]s, stored one cell per `.mag` file.
+//!
+//! When exporting, each drawing and pin element is painted as one or more rectangles
+//! on the Magic layer named by its [`LayerInfo::magic_name`](crate::layout::layers::LayerInfo::magic_name).
+//! Elements on other purposes, as well as blockages, are not exported.
+//! Ports are painted and marked with sticky port labels,
+//! elements with net names are marked with plain labels,
+//! and instances become `use`s of the cells they instantiate.
+//! Instance arrays are expanded into one `use` per instance.
+//! Magic only supports Manhattan geometry, so polygons that are not rectangles are an error.
+//!
+//! When importing, paint becomes drawing elements, port labels become ports,
+//! and other labels assign net names to the paint beneath them,
+//! or become annotations if there is none.
+//! Used cells are read from `.mag` files in the same directory as the cell that uses them.
+//!
+//! A cell's [`PlacementBoundary`] is stored as its `FIXED_BBOX` property.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use derive_builder::Builder;
+use magic::{MagCell, MagLabel, MagProperty, MagRect, MagTransform, MagUse, MAG_EXTENSION};
+use subgeom::bbox::BoundBox;
+use subgeom::orientation::{Named, Orientation};
+use subgeom::transform::Transformation;
+use subgeom::{Point, Rect, Shape};
+
+use super::error::{ErrorContext, ErrorHelper};
+use super::lef::{parse_port_id, PlacementBoundary};
+use crate::component::Component;
+use crate::data::SubstrateCtx;
+use crate::deps::arcstr::ArcStr;
+use crate::error::{
+    with_err_context, ErrorContext as SubErrorContext, ErrorSource, Result as SubResult,
+};
+use crate::fmt::signal::BusFmt;
+use crate::layout::cell::{Cell, CellKey, CellPort, Element, Instance, TextElement};
+use crate::layout::error::{LayoutError, LayoutResult};
+use crate::layout::layers::{LayerKey, LayerPurpose, LayerSpec, LayersRef};
+
+/// The name of the Magic property storing a cell's placement boundary.
+const FIXED_BBOX: &str = "FIXED_BBOX";
+
+/// Magic layers that hold no design data, and are skipped when importing.
+const IGNORED_LAYERS: [&str; 4] = ["checkpaint", "error_p", "error_s", "error_ps"];
+
+/// Options for Magic conversion.
+///
+/// For sky130, Magic cells use technology `sky130A` with a `magscale` of `1 2`,
+/// which corresponds to an internal unit of 5 nm.
+#[derive(Debug, Clone, Builder)]
+pub struct MagicOpts {
+    /// The name of the Magic technology.
+    #[builder(setter(into))]
+    pub tech: ArcStr,
+    /// The `magscale` written to exported cells.
+    #[builder(default = "(1, 1)")]
+    pub magscale: (i64, i64),
+    /// The number of Substrate database units per Magic internal unit at [`magscale`](Self::magscale).
+    ///
+    /// Cells read with a different `magscale` are rescaled accordingly.
+    #[builder(default = "1")]
+    pub dbu_per_unit: i64,
+}
+
+/// A Magic exporter.
+struct MagicExporter<'a> {
+    opts: &'a MagicOpts,
+    layers: LayersRef,
+    backtrace: Vec<ErrorContext>,
+}
+
+/// A Magic importer.
+///
+/// Imports a cell and, recursively, the cells it uses.
+struct MagicImporter<'a> {
+    ctx: &'a SubstrateCtx,
+    opts: &'a MagicOpts,
+    /// A map from Magic layer names to Substrate layers.
+    layers: HashMap<ArcStr, LayerKey>,
+    /// The directory in which used cells are found.
+    dir: PathBuf,
+    /// The cells imported so far, keyed by Magic cell name.
+    cells: HashMap<ArcStr, Arc<Cell>>,
+    /// The cells currently being imported, used to detect recursive uses.
+    stack: HashSet<ArcStr>,
+    backtrace: Vec<ErrorContext>,
+}
+
+/// Additional [`SubstrateCtx`] methods for Magic conversion.
+impl SubstrateCtx {
+    /// Converts the cell `top` and all cells it instantiates to Magic cells.
+    ///
+    /// Returns the name and contents of each cell, starting with `top`.
+    pub fn to_magic_cells(
+        &self,
+        top: &Arc<Cell>,
+        opts: &MagicOpts,
+    ) -> SubResult<Vec<(ArcStr, MagCell)>> {
+        let inner = || -> SubResult<Vec<(ArcStr, MagCell)>> {
+            let mut exporter = MagicExporter {
+                opts,
+                layers: self.layers(),
+                backtrace: vec![ErrorContext::Library],
+            };
+            let mut cells = Vec::new();
+            for cell in hierarchy(top) {
+                let mag = exporter.export_cell(&cell).map_err(ErrorSource::Layout)?;
+                cells.push((cell.name().clone(), mag));
+            }
+            Ok(cells)
+        };
+        with_err_context(inner(), || {
+            SubErrorContext::Task(arcstr::format!("converting cell {} to Magic", top.name()))
+        })
+    }
+
+    /// Saves the cell `top` and all cells it instantiates as `.mag` files in directory `dir`.
+    pub fn to_magic(
+        &self,
+        top: &Arc<Cell>,
+        dir: impl AsRef<Path>,
+        opts: &MagicOpts,
+    ) -> SubResult<()> {
+        let dir = dir.as_ref();
+        std::fs::create_dir_all(dir)?;
+        for (name, mag) in self.to_magic_cells(top, opts)? {
+            mag.save(mag_path(dir, &name))
+                .map_err(LayoutError::from)
+                .map_err(ErrorSource::Layout)?;
+        }
+        Ok(())
+    }
+
+    /// Writes the layout of component `T` and all cells it instantiates
+    /// as `.mag` files in directory `dir`.
+    pub fn write_magic<T>(
+        &self,
+        params: &T::Params,
+        dir: impl AsRef<Path>,
+        opts: &MagicOpts,
+    ) -> SubResult<()>
+    where
+        T: Component,
+    {
+        let dir = dir.as_ref();
+        let inner = || -> SubResult<()> {
+            let inst = self.instantiate_layout::<T>(params)?;
+            self.to_magic(inst.cell(), dir, opts)
+        };
+        with_err_context(inner(), || {
+            SubErrorContext::Task(arcstr::format!("writing Magic cells to {:?}", dir))
+        })
+    }
+
+    /// Adds the cell in the `.mag` file at `path`, and all cells it uses, to the context.
+    ///
+    /// Used cells are read from the directory containing `path`.
+    pub fn from_magic(&self, path: impl AsRef<Path>, opts: &MagicOpts) -> SubResult<Arc<Cell>> {
+        let path = path.as_ref();
+        let inner = || -> SubResult<Arc<Cell>> {
+            let layers = self.layers();
+            let mut magic_layers = HashMap::new();
+            for key in layers.keys() {
+                magic_layers.insert(layers.info(key)?.magic_name().clone(), key);
+            }
+            let name = path
+                .file_stem()
+                .and_then(|name| name.to_str())
+                .ok_or_else(|| LayoutError::Str(format!("invalid Magic cell file name: {path:?}")))
+                .map_err(ErrorSource::Layout)?;
+            let mut importer = MagicImporter {
+                ctx: self,
+                opts,
+                layers: magic_layers,
+                dir: path.parent().map(Path::to_path_buf).unwrap_or_default(),
+                cells: HashMap::new(),
+                stack: HashSet::new(),
+                backtrace: vec![ErrorContext::Library],
+            };
+            Ok(importer
+                .import_cell(&ArcStr::from(name))
+                .map_err(ErrorSource::Layout)?)
+        };
+        with_err_context(inner(), || {
+            SubErrorContext::Task(arcstr::format!("importing Magic cell {:?}", path))
+        })
+    }
+}
+
+impl MagicExporter<'_> {
+    fn export_cell(&mut self, cell: &Cell) -> LayoutResult<MagCell> {
+        self.backtrace.push(ErrorContext::Cell(cell.name().clone()));
+        let mut mag = MagCell::new(self.opts.tech.clone());
+        mag.magscale = self.opts.magscale;
+
+        self.backtrace.push(ErrorContext::Geometry);
+        for elem in cell.elems() {
+            if !matches!(
+                elem.layer.purpose(),
+                LayerPurpose::Drawing | LayerPurpose::Pin
+            ) {
+                continue;
+            }
+            let layer = self.export_layer(elem.layer.layer())?;
+            let rects = self.export_shape(&elem.inner)?;
+            if let (Some(net), Some(rect)) = (&elem.net, rects.first()) {
+                mag.labels
+                    .push(MagLabel::new(layer.clone(), *rect, net.clone()));
+            }
+            for rect in rects {
+                mag.paint(layer.clone(), rect);
+            }
+        }
+        self.backtrace.pop();
+
+        self.backtrace.push(ErrorContext::Ports);
+        let mut buses: Vec<_> = cell.bus_ports().collect();
+        buses.sort_by_key(|(name, _)| *name);
+        let mut index = 0;
+        for (_, bus) in buses {
+            let mut ports: Vec<_> = bus.values().collect();
+            ports.sort_by_key(|port| port.id().index());
+            for port in ports {
+                index += 1;
+                let name = port
+                    .id()
+                    .format_signal(bus.len(), BusFmt::DoubleDelimiter('[', ']'));
+                let mut shapes = Vec::new();
+                for (key, layer_shapes) in port.shapes.iter() {
+                    let layer = self.export_layer(*key)?;
+                    for shape in layer_shapes {
+                        for rect in self.export_shape(shape)? {
+                            shapes.push((layer.clone(), rect));
+                        }
+                    }
+                }
+                shapes.sort_by(|a, b| a.0.cmp(&b.0));
+                for (layer, rect) in shapes {
+                    mag.paint(layer.clone(), rect);
+                    mag.labels
+                        .push(MagLabel::port(layer, rect, name.clone(), index));
+                }
+            }
+        }
+        self.backtrace.pop();
+
+        self.backtrace.push(ErrorContext::Annotations);
+        for text in cell.annotations() {
+            let layer = self.export_layer(text.layer.layer())?;
+            let (x, y) = (
+                self.export_coord(text.loc.x)?,
+                self.export_coord(text.loc.y)?,
+            );
+            mag.labels.push(MagLabel::new(
+                layer,
+                MagRect::new(x, y, x, y),
+                text.string.clone(),
+            ));
+        }
+        self.backtrace.pop();
+
+        self.backtrace.push(ErrorContext::Impl);
+        let mut names = HashSet::new();
+        for inst in cell.insts() {
+            let base = if inst.name().is_empty() {
+                inst.cell().name().as_str()
+            } else {
+                inst.name().as_str()
+            };
+            let name = unique_name(&mut names, base);
+            mag.uses.push(self.export_use(inst, name)?);
+        }
+        for array in cell.arrays() {
+            for row in 0..array.rows() {
+                for col in 0..array.cols() {
+                    let name =
+                        unique_name(&mut names, &format!("{}_{}_{}", array.name(), row, col));
+                    mag.uses.push(self.export_use(&array.inst(row, col), name)?);
+                }
+            }
+        }
+        self.backtrace.pop();
+
+        if let Some(PlacementBoundary(rect)) = cell.try_get_metadata::<PlacementBoundary>() {
+            let r = self.export_rect(*rect)?;
+            mag.properties.push(MagProperty {
+                key: ArcStr::from(FIXED_BBOX),
+                value: arcstr::format!("{} {} {} {}", r.x0, r.y0, r.x1, r.y1),
+            });
+        }
+
+        self.backtrace.pop();
+        Ok(mag)
+    }
+
+    fn export_use(&mut self, inst: &Instance, name: ArcStr) -> LayoutResult<MagUse> {
+        self.backtrace.push(ErrorContext::Instance(name.clone()));
+        let named = Named::all_rectangular()
+            .into_iter()
+            .find(|named| Orientation::from(*named) == inst.orientation());
+        let named = self.unwrap(
+            named,
+            format!("unsupported orientation {:?}", inst.orientation()),
+        )?;
+        let m = orientation_matrix(named);
+        let loc = inst.loc();
+        let transform = MagTransform::new(
+            m[0],
+            m[1],
+            self.export_coord(loc.x)?,
+            m[2],
+            m[3],
+            self.export_coord(loc.y)?,
+        );
+
+        // The bounding box of a cell need not lie on the Magic grid,
+        // so it is rounded outwards.
+        let bbox = inst.cell().bbox();
+        let bbox = if bbox.is_empty() {
+            MagRect::default()
+        } else {
+            let unit = self.opts.dbu_per_unit;
+            let r = bbox.into_rect();
+            MagRect::new(
+                r.left().div_euclid(unit),
+                r.bottom().div_euclid(unit),
+                -(-r.right()).div_euclid(unit),
+                -(-r.top()).div_euclid(unit),
+            )
+        };
+        self.backtrace.pop();
+        Ok(MagUse {
+            cell: inst.cell().name().clone(),
+            name,
+            transform,
+            bbox,
+            array: None,
+        })
+    }
+
+    /// Converts a shape to Magic rectangles.
+    ///
+    /// Paths are split into one rectangle per segment.
+    fn export_shape(&self, shape: &Shape) -> LayoutResult<Vec<MagRect>> {
+        Ok(match shape {
+            Shape::Rect(r) => vec![self.export_rect(*r)?],
+            Shape::Polygon(p) => {
+                let bbox = p.bbox().into_rect();
+                let is_rect = p.points.len() == 4
+                    && p.points.iter().all(|pt| {
+                        (pt.x == bbox.left() || pt.x == bbox.right())
+                            && (pt.y == bbox.bottom() || pt.y == bbox.top())
+                    });
+                if !is_rect {
+                    return self.fail("non-rectangular polygons are not supported in Magic");
+                }
+                vec![self.export_rect(bbox)?]
+            }
+            Shape::Path(p) => {
+                let half = p.width as i64 / 2;
+                let mut rects = Vec::new();
+                for seg in p.points.windows(2) {
+                    let (a, b) = (seg[0], seg[1]);
+                    let r = if a.x == b.x {
+                        Rect::new(Point::new(a.x - half, a.y), Point::new(a.x + half, b.y))
+                    } else if a.y == b.y {
+                        Rect::new(Point::new(a.x, a.y - half), Point::new(b.x, a.y + half))
+                    } else {
+                        return self.fail("non-Manhattan paths are not supported in Magic");
+                    };
+                    rects.push(self.export_rect(r)?);
+                }
+                rects
+            }
+            Shape::Point(_) => Vec::new(),
+        })
+    }
+
+    fn export_rect(&self, r: Rect) -> LayoutResult<MagRect> {
+        Ok(MagRect::new(
+            self.export_coord(r.left())?,
+            self.export_coord(r.bottom())?,
+            self.export_coord(r.right())?,
+            self.export_coord(r.top())?,
+        ))
+    }
+
+    fn export_coord(&self, v: i64) -> LayoutResult<i64> {
+        let unit = self.opts.dbu_per_unit;
+        self.assert(
+            v % unit == 0,
+            format!("coordinate {v} is not on the Magic grid of {unit} database units"),
+        )?;
+        Ok(v / unit)
+    }
+
+    fn export_layer(&self, layer: LayerKey) -> LayoutResult<ArcStr> {
+        self.layers
+            .info(layer)
+            .map(|info| info.magic_name().clone())
+            .map_err(|_| self.err(format!("layer {layer:?} not found")))
+    }
+}
+
+impl ErrorHelper for MagicExporter<'_> {
+    type Error = LayoutError;
+    fn err(&self, msg: impl Into<String>) -> LayoutError {
+        LayoutError::Export {
+            message: msg.into(),
+            stack: self.backtrace.clone(),
+        }
+    }
+}
+
+impl MagicImporter<'_> {
+    /// Imports the cell named `name`, reading it from the importer's directory if needed.
+    fn import_cell(&mut self, name: &ArcStr) -> LayoutResult<Arc<Cell>> {
+        if let Some(cell) = self.cells.get(name) {
+            return Ok(cell.clone());
+        }
+        self.backtrace.push(ErrorContext::Cell(name.clone()));
+        if !self.stack.insert(name.clone()) {
+            return self.fail("cell uses itself");
+        }
+        let mag = MagCell::load(mag_path(&self.dir, name))?;
+        let cell = self.import_mag_cell(name, &mag)?;
+        self.stack.remove(name);
+        self.cells.insert(name.clone(), cell.clone());
+        self.backtrace.pop();
+        Ok(cell)
+    }
+
+    fn import_mag_cell(&mut self, name: &ArcStr, mag: &MagCell) -> LayoutResult<Arc<Cell>> {
+        let scale = self.import_scale(mag.magscale)?;
+
+        // Import used cells first, so that they exist before this cell is created.
+        self.backtrace.push(ErrorContext::Impl);
+        let mut insts = Vec::with_capacity(mag.uses.len());
+        for u in mag.uses.iter() {
+            let child = self.import_cell(&u.cell)?;
+            self.backtrace.push(ErrorContext::Instance(u.name.clone()));
+            self.assert(u.array.is_none(), "arrayed uses are not supported")?;
+            let t = &u.transform;
+            let orientation = self.unwrap(
+                import_orientation([t.a, t.b, t.d, t.e]),
+                format!("unsupported transform {t:?}"),
+            )?;
+            insts.push(
+                Instance::builder()
+                    .name(u.name.clone())
+                    .cell(child)
+                    .loc(Point::new(t.c * scale, t.f * scale))
+                    .orientation(orientation)
+                    .build()
+                    .unwrap(),
+            );
+            self.backtrace.pop();
+        }
+        self.backtrace.pop();
+
+        let ctx = self.ctx;
+        let mut inner = ctx.write();
+        let id = inner.layouts_mut().gen_id();
+        let mut cell = Cell::new(id);
+        cell.set_name(inner.layouts().alloc_name(name));
+        cell.add_insts(insts);
+
+        self.backtrace.push(ErrorContext::Geometry);
+        let mut elems = Vec::new();
+        for paint in mag.paint.iter() {
+            if IGNORED_LAYERS.contains(&paint.layer.as_str()) {
+                continue;
+            }
+            let layer = self.import_layer(&paint.layer)?;
+            for rect in paint.rects.iter() {
+                elems.push(Element::new(
+                    LayerSpec::drawing(layer),
+                    import_rect(rect, scale),
+                ));
+            }
+        }
+        self.backtrace.pop();
+
+        self.backtrace.push(ErrorContext::Annotations);
+        for label in mag.labels.iter() {
+            let layer = self.import_layer(&label.layer)?;
+            let rect = import_rect(&label.rect, scale);
+            // The paint on `layer` that contains the label.
+            let under = elems.iter().position(|elem| {
+                let r = elem.inner.bbox().into_rect();
+                elem.layer.layer() == layer
+                    && r.hspan().contains(rect.hspan())
+                    && r.vspan().contains(rect.vspan())
+            });
+            if label.port.is_some() {
+                let shape = if rect.width() > 0 && rect.height() > 0 {
+                    Shape::Rect(rect)
+                } else {
+                    let under = self.unwrap(
+                        under,
+                        format!("port label {} is not attached to any paint", label.text),
+                    )?;
+                    elems[under].inner.clone()
+                };
+                let mut port = CellPort::new(parse_port_id(&label.text));
+                port.add(layer, shape);
+                cell.merge_port(port);
+            } else if let Some(under) = under.filter(|&i| elems[i].net.is_none()) {
+                elems[under].net = Some(label.text.clone());
+            } else {
+                cell.add_annotation(TextElement {
+                    string: label.text.clone(),
+                    loc: rect.center(),
+                    layer: LayerSpec::new(layer, LayerPurpose::Label),
+                });
+            }
+        }
+        self.backtrace.pop();
+        cell.set_elems(elems);
+
+        if let Some(value) = mag.property(FIXED_BBOX) {
+            let coords = value
+                .split_whitespace()
+                .map(|v| v.parse::<i64>())
+                .collect::<Result<Vec<_>, _>>();
+            let coords = self.unwrap(
+                coords.ok().filter(|coords| coords.len() == 4),
+                format!("invalid {FIXED_BBOX} property: {value}"),
+            )?;
+            let rect = MagRect::new(coords[0], coords[1], coords[2], coords[3]);
+            cell.set_metadata(PlacementBoundary(import_rect(&rect, scale)));
+        }
+
+        cell.freeze();
+        Ok(inner.layouts_mut().set_cell(cell))
+    }
+
+    /// Returns the number of Substrate database units per internal unit of a cell
+    /// with the given `magscale`.
+    fn import_scale(&mut self, magscale: (i64, i64)) -> LayoutResult<i64> {
+        self.backtrace.push(ErrorContext::Units);
+        let (n, d) = self.opts.magscale;
+        let num = self.opts.dbu_per_unit * magscale.1 * n;
+        let den = magscale.0 * d;
+        self.assert(
+            den > 0 && num > 0 && num % den == 0,
+            format!(
+                "magscale {} {} is not compatible with magscale {n} {d}",
+                magscale.0, magscale.1
+            ),
+        )?;
+        self.backtrace.pop();
+        Ok(num / den)
+    }
+
+    fn import_layer(&self, name: &str) -> LayoutResult<LayerKey> {
+        self.unwrap(
+            self.layers.get(name).copied(),
+            format!("no layer corresponds to Magic layer {name}"),
+        )
+    }
+}
+
+impl ErrorHelper for MagicImporter<'_> {
+    type Error = LayoutError;
+    fn err(&self, msg: impl Into<String>) -> LayoutError {
+        LayoutError::Import {
+            message: msg.into(),
+            stack: self.backtrace.clone(),
+        }
+    }
+}
+
+/// Returns `top` and the cells it instantiates, directly or indirectly, each exactly once.
+fn hierarchy(top: &Arc<Cell>) -> Vec<Arc<Cell>> {
+    let mut seen: HashSet<CellKey> = HashSet::from([top.id()]);
+    let mut queue = VecDeque::from([top.clone()]);
+    let mut cells = Vec::new();
+    while let Some(cell) = queue.pop_front() {
+        let children = cell
+            .insts()
+            .map(|inst| inst.cell())
+            .chain(cell.arrays().map(|array| array.cell()));
+        for child in children {
+            if seen.insert(child.id()) {
+                queue.push_back(child.clone());
+            }
+        }
+        cells.push(cell);
+    }
+    cells
+}
+
+/// Returns `base` if it is not in `names`, or `base` with a numeric suffix otherwise,
+/// and adds the returned name to `names`.
+fn unique_name(names: &mut HashSet<ArcStr>, base: &str) -> ArcStr {
+    let mut name = ArcStr::from(base);
+    let mut i = 1;
+    while names.contains(&name) {
+        name = arcstr::format!("{base}_{i}");
+        i += 1;
+    }
+    names.insert(name.clone());
+    name
+}
+
+fn mag_path(dir: &Path, name: &str) -> PathBuf {
+    dir.join(format!("{name}.{MAG_EXTENSION}"))
+}
+
+fn import_rect(r: &MagRect, scale: i64) -> Rect {
+    Rect::new(
+        Point::new(r.x0 * scale, r.y0 * scale),
+        Point::new(r.x1 * scale, r.y1 * scale),
+    )
+}
+
+/// Returns the transformation matrix of `named`, in row-major order.
+fn orientation_matrix(named: Named) -> [i64; 4] {
+    let a = Transformation::from(named).a;
+    [a[0][0], a[0][1], a[1][0], a[1][1]].map(|v| v.round() as i64)
+}
+
+/// Returns the rectangular orientation whose transformation matrix,
+/// in row-major order, is `m`.
+fn import_orientation(m: [i64; 4]) -> Option<Orientation> {
+    Named::all_rectangular()
+        .into_iter()
+        .find(|&named| orientation_matrix(named) == m)
+        .map(Orientation::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_orientation_round_trip() {
+        for named in Named::all_rectangular() {
+            assert_eq!(
+                import_orientation(orientation_matrix(named)),
+                Some(Orientation::from(named))
+            );
+        }
+        assert_eq!(import_orientation([1, 1, 0, 1]), None);
+    }
+}
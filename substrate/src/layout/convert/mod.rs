@@ -1,5 +1,8 @@
 //! File type conversion utilities.
 
+pub mod def;
 pub mod error;
 pub mod gds;
-// pub mod lef;
+pub mod lef;
+pub mod magic;
+pub mod oasis;
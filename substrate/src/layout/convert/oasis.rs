@@ -0,0 +1,925 @@
+//! Utilities for OASIS conversion.
+//!
+//! Converts between Substrate's layout data-model and [`oasis`] structures.
+//!
+//! Cells are exported directly to [`oasis::OasisCell`]s,
+//! with the same cell names, layers, port shapes, and labels as GDSII export.
+//! Net names are stored in properties named [`NET_NAME_PROPNAME`].
+//! [`InstanceArray`]s are exported as placements repeated on a lattice.
+//!
+//! Import goes through the GDSII conversion in [`super::gds`].
+//! Placements repeated on a grid or lattice are imported as [`InstanceArray`]s.
+//! Placements repeated at explicit offsets are imported as individual [`Instance`]s,
+//! and repeated shapes and text are expanded into individual elements.
+
+use std::collections::{HashMap, HashSet};
+use std::convert::TryFrom;
+use std::sync::{Arc, RwLock};
+
+use derivative::Derivative;
+use slotmap::SecondaryMap;
+use subgeom::{Point, Shape};
+
+use super::error::{ErrorContext, ErrorHelper};
+use super::gds::{
+    unique_cell_names, ExportSet, GdsExportOpts, GdsImporter, PlaceLabels, NET_NAME_PROPATTR,
+};
+use crate::data::{SubstrateCtx, SubstrateData};
+use crate::deps::arcstr::ArcStr;
+use crate::error::{
+    with_err_context, ErrorContext as SubErrorContext, ErrorSource, Result as SubResult,
+};
+use crate::fmt::signal::BusFmt;
+use crate::layout::cell::{BusPort, Cell, CellKey, Element, Instance, InstanceArray, TextElement};
+use crate::layout::context::LayoutCtx;
+use crate::layout::error::{LayoutError, LayoutResult};
+use crate::layout::layers::{GdsLayerSpec, LayerKey, LayerPurpose, LayerSpec, Layers};
+use crate::units::SiPrefix;
+
+/// The name of the OASIS property under which element net names are stored.
+///
+/// Net names are written as the only value of a property with this name
+/// on each exported shape and net label. On import, they are treated
+/// like GDSII properties with attribute [`NET_NAME_PROPATTR`].
+pub const NET_NAME_PROPNAME: &str = "NET_NAME";
+
+/// An OASIS exporter.
+///
+/// Converts Substrate layout data to an OASIS library ([`oasis::OasisLibrary`]).
+#[derive(Derivative)]
+#[derivative(Debug)]
+pub struct OasisExporter<'a> {
+    #[derivative(Debug = "ignore")]
+    data: &'a SubstrateData,
+    layers: Arc<RwLock<Layers>>,
+    backtrace: Vec<ErrorContext>,
+    export_set: ExportSet,
+    names: SecondaryMap<CellKey, ArcStr>,
+    opts: GdsExportOpts,
+}
+
+/// An OASIS importer.
+///
+/// Imports cells from an [`oasis::OasisLibrary`] into Substrate.
+#[derive(Debug)]
+pub struct OasisImporter<'a> {
+    gds: GdsImporter<'a>,
+    backtrace: Vec<ErrorContext>,
+}
+
+/// A repeated placement.
+struct PlacementArray {
+    cell: ArcStr,
+    loc: Point,
+    strans: gds21::GdsStrans,
+    layout: PlacementArrayLayout,
+}
+
+/// The arrangement of the elements of a [`PlacementArray`].
+enum PlacementArrayLayout {
+    /// A grid of `rows` by `cols` elements, imported as an [`InstanceArray`].
+    Grid {
+        rows: usize,
+        cols: usize,
+        row_pitch: Point,
+        col_pitch: Point,
+    },
+    /// Elements at arbitrary offsets, imported as individual [`Instance`]s.
+    Explicit(Vec<Point>),
+}
+
+/// Additional [`SubstrateCtx`] methods for OASIS conversion.
+impl SubstrateCtx {
+    /// Converts the context to an OASIS library.
+    pub fn to_oasis_lib(&self) -> SubResult<oasis::OasisLibrary> {
+        let data = self.read();
+        let inner = || -> SubResult<oasis::OasisLibrary> {
+            Ok(OasisExporter {
+                data: &data,
+                layers: data.layers(),
+                backtrace: Vec::new(),
+                export_set: ExportSet::All,
+                names: unique_cell_names(data.layouts(), None),
+                opts: GdsExportOpts::default(),
+            }
+            .export_lib()
+            .map_err(ErrorSource::Layout)?)
+        };
+        with_err_context(inner(), || {
+            SubErrorContext::Task(arcstr::literal!(
+                "converting cells in context to OASIS library"
+            ))
+        })
+    }
+    /// Converts `top` and the cells it instantiates to an OASIS library
+    /// using the given export options.
+    pub(crate) fn to_oasis_lib_with_top(
+        &self,
+        top: Arc<Cell>,
+        opts: &GdsExportOpts,
+    ) -> SubResult<oasis::OasisLibrary> {
+        let data = self.read();
+        let inner = || -> SubResult<oasis::OasisLibrary> {
+            Ok(OasisExporter {
+                data: &data,
+                layers: data.layers(),
+                backtrace: Vec::new(),
+                export_set: ExportSet::for_top(&top),
+                names: unique_cell_names(data.layouts(), Some(&top)),
+                opts: opts.clone(),
+            }
+            .export_lib()
+            .map_err(ErrorSource::Layout)?)
+        };
+        with_err_context(inner(), || {
+            SubErrorContext::Task(arcstr::literal!("converting top cell to OASIS library"))
+        })
+    }
+    /// Saves the context to an OASIS file.
+    pub fn to_oasis(&self, path: impl AsRef<std::path::Path>) -> SubResult<()> {
+        let inner = || -> SubResult<()> {
+            self.to_oasis_lib()?
+                .save(path)
+                .map_err(LayoutError::from)
+                .map_err(ErrorSource::Layout)?;
+            Ok(())
+        };
+        with_err_context(inner(), || {
+            SubErrorContext::Task(arcstr::literal!("converting cells in context to OASIS"))
+        })
+    }
+    /// Saves `top` and the cells it instantiates to an OASIS file
+    /// using the given export options.
+    pub(crate) fn to_oasis_with_top(
+        &self,
+        top: Arc<Cell>,
+        path: impl AsRef<std::path::Path>,
+        opts: &GdsExportOpts,
+    ) -> SubResult<()> {
+        let inner = || -> SubResult<()> {
+            self.to_oasis_lib_with_top(top, opts)?
+                .save(path)
+                .map_err(LayoutError::from)
+                .map_err(ErrorSource::Layout)?;
+            Ok(())
+        };
+        with_err_context(inner(), || {
+            SubErrorContext::Task(arcstr::literal!("converting top cell to OASIS"))
+        })
+    }
+    /// Adds cells from an OASIS library to the context.
+    pub fn from_oasis_lib(
+        &self,
+        lib: &oasis::OasisLibrary,
+    ) -> SubResult<HashMap<ArcStr, Arc<Cell>>> {
+        let mut data = self.write();
+        let layers = data.layers();
+        let mut layers_guard = layers.write().unwrap();
+        let mut importer = OasisImporter {
+            gds: GdsImporter::new(&mut data, &mut layers_guard),
+            backtrace: Vec::new(),
+        };
+        importer.import_all(lib)?;
+        Ok(importer.gds.into_cell_map())
+    }
+    /// Adds cells from an OASIS file to the context.
+    pub fn from_oasis(
+        &self,
+        path: impl AsRef<std::path::Path>,
+    ) -> SubResult<HashMap<ArcStr, Arc<Cell>>> {
+        let library = oasis::OasisLibrary::load(path)
+            .map_err(LayoutError::from)
+            .map_err(ErrorSource::Layout)?;
+        self.from_oasis_lib(&library)
+    }
+}
+
+/// Additional [`LayoutCtx`] methods for OASIS conversion.
+impl LayoutCtx {
+    /// Adds cells from an OASIS file to the context.
+    pub fn from_oasis(
+        &mut self,
+        path: impl AsRef<std::path::Path>,
+    ) -> SubResult<HashMap<ArcStr, Arc<Cell>>> {
+        self.inner.from_oasis(path)
+    }
+}
+
+impl<'a> OasisExporter<'a> {
+    /// Exports to an [`oasis::OasisLibrary`].
+    fn export_lib(&mut self) -> LayoutResult<oasis::OasisLibrary> {
+        self.backtrace.push(ErrorContext::Library);
+        let layouts = self.data.layouts();
+
+        // OASIS stores the number of database units per micron.
+        let units = layouts.units();
+        let unit = match units {
+            SiPrefix::Micro => 1.0,
+            SiPrefix::Nano => 1e3,
+            SiPrefix::Pico => 1e6,
+            _ => {
+                return self.fail(format!("Invalid unit prefix for library: {units:?}"));
+            }
+        };
+
+        let mut lib = oasis::OasisLibrary::new(unit);
+        for cell in layouts.cells() {
+            if !self.export_set.contains(&cell.id()) {
+                continue;
+            }
+            let cell = self.export_cell(cell)?;
+            lib.cells.push(cell);
+        }
+        self.backtrace.pop();
+        Ok(lib)
+    }
+    /// Converts a [`Cell`] to an [`oasis::OasisCell`].
+    fn export_cell(&mut self, cell: &Arc<Cell>) -> LayoutResult<oasis::OasisCell> {
+        self.backtrace.push(ErrorContext::Cell(cell.name().clone()));
+        let mut elems = Vec::new();
+
+        for inst in cell.insts() {
+            elems.push(self.export_instance(inst)?.into());
+        }
+        for array in cell.arrays().filter(|array| !array.is_empty()) {
+            elems.push(self.export_instance_array(array)?.into());
+        }
+
+        self.backtrace.push(ErrorContext::Geometry);
+        for elem in cell.elems() {
+            elems.extend(self.export_element(elem)?);
+        }
+        self.backtrace.pop();
+
+        self.backtrace.push(ErrorContext::Annotations);
+        for annotation in cell.annotations() {
+            elems.push(self.export_annotation(annotation)?);
+        }
+        self.backtrace.pop();
+
+        self.backtrace.push(ErrorContext::Ports);
+        for (_, bus) in cell.bus_ports() {
+            elems.extend(self.export_bus(bus)?);
+        }
+        self.backtrace.pop();
+
+        let mut oasis_cell = oasis::OasisCell::new(self.names[cell.id()].clone());
+        oasis_cell.elems = elems;
+        self.backtrace.pop();
+        Ok(oasis_cell)
+    }
+    /// Converts an [`Instance`] to an [`oasis::OasisPlacement`].
+    fn export_instance(&mut self, inst: &Instance) -> LayoutResult<oasis::OasisPlacement> {
+        self.backtrace
+            .push(ErrorContext::Instance(inst.name().clone()));
+        let loc = inst.loc();
+        let mut placement =
+            oasis::OasisPlacement::new(self.names[inst.cell().id()].clone(), loc.x, loc.y);
+        self.export_strans(&inst.orientation().into(), &mut placement)?;
+        self.backtrace.pop();
+        Ok(placement)
+    }
+    /// Converts an [`InstanceArray`] to an [`oasis::OasisPlacement`]
+    /// repeated on a lattice of `cols` column pitches by `rows` row pitches.
+    fn export_instance_array(
+        &mut self,
+        array: &InstanceArray,
+    ) -> LayoutResult<oasis::OasisPlacement> {
+        self.backtrace
+            .push(ErrorContext::Array(array.name().clone()));
+        let loc = array.loc();
+        let (col, row) = (array.col_pitch(), array.row_pitch());
+        let mut placement =
+            oasis::OasisPlacement::new(self.names[array.cell().id()].clone(), loc.x, loc.y);
+        self.export_strans(&array.orientation().into(), &mut placement)?;
+        placement.repetition = Some(oasis::OasisRepetition::lattice(
+            u64::try_from(array.cols())?,
+            u64::try_from(array.rows())?,
+            oasis::OasisPoint::new(col.x, col.y),
+            oasis::OasisPoint::new(row.x, row.y),
+        ));
+        self.backtrace.pop();
+        Ok(placement)
+    }
+    /// Converts a [`LayerSpec`] to an OASIS layer and datatype.
+    fn export_layerspec(&mut self, spec: &LayerSpec) -> LayoutResult<(u64, u64)> {
+        let GdsLayerSpec(layer, datatype) = {
+            let layers = self.layers.read().unwrap();
+            self.unwrap(
+                layers.to_gds_spec(spec),
+                format!("No GDS spec found for layer spec {spec:?}"),
+            )?
+        };
+        self.export_layer(layer, datatype)
+    }
+    /// Converts a [`LayerKey`] to the OASIS layer and texttype of labels for that layer.
+    fn export_label_layerspec(&mut self, key: LayerKey) -> LayoutResult<(u64, u64)> {
+        let GdsLayerSpec(layer, texttype) = {
+            let layers = self.layers.read().unwrap();
+            self.unwrap(
+                layers.to_label_gds_spec(key),
+                format!("No GDS spec found for layer spec {key:?}"),
+            )?
+        };
+        self.export_layer(layer, texttype)
+    }
+    /// Converts an [`Element`] into one or more [`oasis::OasisElement`]s.
+    ///
+    /// The element's net name, if any, is stored as a property named [`NET_NAME_PROPNAME`].
+    /// If net labels are enabled, a text label carrying the same property is also added.
+    fn export_element(&mut self, elem: &Element) -> LayoutResult<Vec<oasis::OasisElement>> {
+        let layer = self.export_layerspec(&elem.layer)?;
+        let mut elems = match self.export_shape(&elem.inner, layer)? {
+            Some(x) => vec![x],
+            None => Vec::new(),
+        };
+        if let Some(net) = &elem.net {
+            let prop = oasis::OasisProperty::new(
+                NET_NAME_PROPNAME,
+                vec![oasis::OasisPropValue::String(net.clone())],
+            );
+            for elem in elems.iter_mut() {
+                elem.properties_mut().push(prop.clone());
+            }
+            if self.opts.net_labels && !elems.is_empty() {
+                let label_layer = self.export_label_layerspec(elem.layer.layer())?;
+                let mut label = export_shape_label(net.clone(), &elem.inner, label_layer);
+                label.properties_mut().push(prop);
+                elems.push(label);
+            }
+        }
+        Ok(elems)
+    }
+    /// Converts a [`Shape`] to an [`oasis::OasisElement`] on the given layer and datatype.
+    ///
+    /// Points have no OASIS equivalent, and are skipped.
+    fn export_shape(
+        &mut self,
+        shape: &Shape,
+        layer: (u64, u64),
+    ) -> LayoutResult<Option<oasis::OasisElement>> {
+        let elem = match shape {
+            Shape::Rect(r) => {
+                let (x0, y0) = (r.p0.x.min(r.p1.x), r.p0.y.min(r.p1.y));
+                let (x1, y1) = (r.p0.x.max(r.p1.x), r.p0.y.max(r.p1.y));
+                oasis::OasisRectangle::new(
+                    layer.0,
+                    layer.1,
+                    x0,
+                    y0,
+                    (x1 - x0) as u64,
+                    (y1 - y0) as u64,
+                )
+                .into()
+            }
+            Shape::Polygon(poly) => export_polygon(layer, export_points(&poly.points)),
+            Shape::Path(path) => {
+                if path.width % 2 != 0 {
+                    return self.fail(format!(
+                        "OASIS paths must have an even width, got {}",
+                        path.width
+                    ));
+                }
+                oasis::OasisPath {
+                    layer: layer.0,
+                    datatype: layer.1,
+                    half_width: (path.width / 2) as u64,
+                    start_extension: oasis::OasisPathExtension::Flush,
+                    end_extension: oasis::OasisPathExtension::Flush,
+                    points: export_points(&path.points),
+                    repetition: None,
+                    properties: Vec::new(),
+                }
+                .into()
+            }
+            Shape::Point(_) => return Ok(None),
+        };
+        Ok(Some(elem))
+    }
+    /// Converts a [`TextElement`] to an [`oasis::OasisText`].
+    fn export_annotation(&mut self, text_elem: &TextElement) -> LayoutResult<oasis::OasisElement> {
+        let (layer, texttype) = self.export_layerspec(&text_elem.layer)?;
+        Ok(oasis::OasisText {
+            string: text_elem.string.clone(),
+            layer,
+            texttype,
+            x: text_elem.loc.x,
+            y: text_elem.loc.y,
+            repetition: None,
+            properties: Vec::new(),
+        }
+        .into())
+    }
+    /// Converts a [`BusPort`] to its constituent [`oasis::OasisElement`]s.
+    ///
+    /// Each port shape is written on the drawing and pin purposes of its layer,
+    /// and labeled on the label purpose.
+    fn export_bus(&mut self, bus: &BusPort) -> LayoutResult<Vec<oasis::OasisElement>> {
+        let width = bus.len();
+        let mut elems = Vec::new();
+
+        for port in bus.values() {
+            for (key, shapes) in port.shapes.iter() {
+                let drawing =
+                    self.export_layerspec(&LayerSpec::new(*key, LayerPurpose::Drawing))?;
+                let pin = self.export_layerspec(&LayerSpec::new(*key, LayerPurpose::Pin))?;
+                let label = self.export_label_layerspec(*key)?;
+                for shape in shapes {
+                    elems.extend(self.export_shape(shape, drawing)?);
+                    elems.extend(self.export_shape(shape, pin)?);
+                    elems.push(export_shape_label(
+                        port.id
+                            .format_signal(width, BusFmt::DoubleDelimiter('[', ']')),
+                        shape,
+                        label,
+                    ));
+                }
+            }
+        }
+        Ok(elems)
+    }
+    /// Applies a GDS transformation to an [`oasis::OasisPlacement`].
+    fn export_strans(
+        &mut self,
+        strans: &Option<gds21::GdsStrans>,
+        placement: &mut oasis::OasisPlacement,
+    ) -> LayoutResult<()> {
+        if let Some(strans) = strans {
+            if strans.abs_mag || strans.abs_angle {
+                return self.fail("Unsupported GDSII Instance Feature: Absolute Magnitude/ Angle");
+            }
+            placement.flip = strans.reflected;
+            placement.angle = strans.angle.unwrap_or_default();
+            placement.magnification = strans.mag.unwrap_or(1.0);
+        }
+        Ok(())
+    }
+    /// Converts a GDS layer and datatype to their OASIS equivalents.
+    fn export_layer(&self, layer: i16, datatype: i16) -> LayoutResult<(u64, u64)> {
+        match (u64::try_from(layer), u64::try_from(datatype)) {
+            (Ok(layer), Ok(datatype)) => Ok((layer, datatype)),
+            _ => self.fail(format!(
+                "OASIS layers must be non-negative, got ({layer}, {datatype})"
+            )),
+        }
+    }
+}
+
+impl ErrorHelper for OasisExporter<'_> {
+    type Error = LayoutError;
+    fn err(&self, msg: impl Into<String>) -> LayoutError {
+        LayoutError::Export {
+            message: msg.into(),
+            stack: self.backtrace.clone(),
+        }
+    }
+}
+
+/// Converts Substrate points to OASIS points.
+fn export_points(pts: &[Point]) -> Vec<oasis::OasisPoint> {
+    pts.iter()
+        .map(|p| oasis::OasisPoint::new(p.x, p.y))
+        .collect()
+}
+
+/// Creates an [`oasis::OasisText`] labeling [`Shape`] `shape`.
+///
+/// Labels are placed as in GDSII export, but are never rotated.
+fn export_shape_label(
+    net: ArcStr,
+    shape: &Shape,
+    (layer, texttype): (u64, u64),
+) -> oasis::OasisElement {
+    let loc = shape.label_location();
+    oasis::OasisText {
+        string: net,
+        layer,
+        texttype,
+        x: loc.x,
+        y: loc.y,
+        repetition: None,
+        properties: Vec::new(),
+    }
+    .into()
+}
+
+/// Creates an [`oasis::OasisRectangle`] if `points` describe an axis-aligned rectangle,
+/// and an [`oasis::OasisPolygon`] otherwise.
+fn export_polygon(
+    (layer, datatype): (u64, u64),
+    points: Vec<oasis::OasisPoint>,
+) -> oasis::OasisElement {
+    if points.len() == 4 {
+        let xs: HashSet<i64> = points.iter().map(|p| p.x).collect();
+        let ys: HashSet<i64> = points.iter().map(|p| p.y).collect();
+        let manhattan = (0..4).all(|i| {
+            let (a, b) = (points[i], points[(i + 1) % 4]);
+            (a.x == b.x) != (a.y == b.y)
+        });
+        if manhattan && xs.len() == 2 && ys.len() == 2 {
+            let x0 = *xs.iter().min().unwrap();
+            let x1 = *xs.iter().max().unwrap();
+            let y0 = *ys.iter().min().unwrap();
+            let y1 = *ys.iter().max().unwrap();
+            return oasis::OasisRectangle::new(
+                layer,
+                datatype,
+                x0,
+                y0,
+                (x1 - x0) as u64,
+                (y1 - y0) as u64,
+            )
+            .into();
+        }
+    }
+    oasis::OasisPolygon {
+        layer,
+        datatype,
+        points,
+        repetition: None,
+        properties: Vec::new(),
+    }
+    .into()
+}
+
+impl<'a> OasisImporter<'a> {
+    /// Imports an [`oasis::OasisLibrary`].
+    fn import_all(&mut self, lib: &oasis::OasisLibrary) -> LayoutResult<()> {
+        self.backtrace.push(ErrorContext::Library);
+        // GDSII units are expressed relative to a user unit of 1µm.
+        let units = gds21::GdsUnits::new(1.0 / lib.unit, 1e-6 / lib.unit);
+        self.gds.check_units(&units)?;
+
+        for cell in OasisDepOrder::new(lib).total_order() {
+            self.import_and_add(cell)?;
+        }
+        self.backtrace.pop();
+        Ok(())
+    }
+    /// Imports and adds a cell.
+    ///
+    /// All cells placed by `cell` must already be imported.
+    fn import_and_add(&mut self, cell: &oasis::OasisCell) -> LayoutResult<()> {
+        self.backtrace.push(ErrorContext::Cell(cell.name.clone()));
+        let mut strukt = gds21::GdsStruct::new(cell.name.clone());
+        let mut arrays = Vec::new();
+        for elem in cell.elems.iter() {
+            use oasis::OasisElement::*;
+            match elem {
+                Placement(x) => match x.repetition {
+                    Some(ref rep) => arrays.push(self.import_placement_array(x, rep)?),
+                    None => strukt.elems.push(
+                        gds21::GdsStructRef {
+                            name: x.cell.clone(),
+                            xy: self.import_point(x.x, x.y)?,
+                            strans: Some(self.import_strans(x)),
+                            ..Default::default()
+                        }
+                        .into(),
+                    ),
+                },
+                _ => {
+                    let offsets = match elem.repetition() {
+                        Some(rep) => rep.offsets(),
+                        None => vec![oasis::OasisPoint::zero()],
+                    };
+                    for offset in offsets {
+                        strukt.elems.push(self.import_element(elem, offset)?);
+                    }
+                }
+            }
+        }
+
+        let mut insts = Vec::new();
+        let mut inst_arrays = Vec::new();
+        for array in arrays {
+            match self.import_instance_array(array)? {
+                ImportedArray::Array(array) => inst_arrays.push(array),
+                ImportedArray::Insts(x) => insts.extend(x),
+            }
+        }
+        self.gds
+            .import_and_add_with_insts(&strukt, insts, inst_arrays)?;
+        self.backtrace.pop();
+        Ok(())
+    }
+    /// Converts a non-placement [`oasis::OasisElement`], displaced by `offset`,
+    /// to a [`gds21::GdsElement`].
+    fn import_element(
+        &mut self,
+        elem: &oasis::OasisElement,
+        offset: oasis::OasisPoint,
+    ) -> LayoutResult<gds21::GdsElement> {
+        use oasis::OasisElement::*;
+        self.backtrace.push(ErrorContext::Geometry);
+        let elem = match elem {
+            Rectangle(x) => {
+                let (layer, datatype) = self.import_layer(x.layer, x.datatype)?;
+                let (x0, y0) = (x.x + offset.x, x.y + offset.y);
+                let (x1, y1) = (x0 + x.width as i64, y0 + x.height as i64);
+                gds21::GdsBoundary {
+                    layer,
+                    datatype,
+                    properties: import_properties(&x.properties),
+                    xy: vec![
+                        self.import_point(x0, y0)?,
+                        self.import_point(x1, y0)?,
+                        self.import_point(x1, y1)?,
+                        self.import_point(x0, y1)?,
+                        self.import_point(x0, y0)?,
+                    ],
+                    ..Default::default()
+                }
+                .into()
+            }
+            Polygon(x) => {
+                let (layer, datatype) = self.import_layer(x.layer, x.datatype)?;
+                let mut xy = self.import_points(&x.points, offset)?;
+                // GDS boundaries repeat their first point.
+                xy.push(xy[0].clone());
+                gds21::GdsBoundary {
+                    layer,
+                    datatype,
+                    xy,
+                    properties: import_properties(&x.properties),
+                    ..Default::default()
+                }
+                .into()
+            }
+            Path(x) => {
+                let (layer, datatype) = self.import_layer(x.layer, x.datatype)?;
+                let mut path = import_path_extensions(x)?;
+                path.layer = layer;
+                path.datatype = datatype;
+                path.xy = self.import_points(&x.points, offset)?;
+                path.properties = import_properties(&x.properties);
+                path.into()
+            }
+            Text(x) => {
+                let (layer, texttype) = self.import_layer(x.layer, x.texttype)?;
+                gds21::GdsTextElem {
+                    string: x.string.clone(),
+                    layer,
+                    texttype,
+                    xy: self.import_point(x.x + offset.x, x.y + offset.y)?,
+                    properties: import_properties(&x.properties),
+                    ..Default::default()
+                }
+                .into()
+            }
+            Placement(_) => unreachable!("placements are imported as instances"),
+        };
+        self.backtrace.pop();
+        Ok(elem)
+    }
+    /// Collects the elements of a repeated [`oasis::OasisPlacement`].
+    fn import_placement_array(
+        &mut self,
+        placement: &oasis::OasisPlacement,
+        rep: &oasis::OasisRepetition,
+    ) -> LayoutResult<PlacementArray> {
+        let layout = match *rep {
+            oasis::OasisRepetition::Matrix { nx, ny, dx, dy } => PlacementArrayLayout::Grid {
+                rows: usize::try_from(ny)?,
+                cols: usize::try_from(nx)?,
+                row_pitch: Point::new(0, dy),
+                col_pitch: Point::new(dx, 0),
+            },
+            oasis::OasisRepetition::Lattice { n, m, a, b } => PlacementArrayLayout::Grid {
+                rows: usize::try_from(m)?,
+                cols: usize::try_from(n)?,
+                row_pitch: Point::new(b.x, b.y),
+                col_pitch: Point::new(a.x, a.y),
+            },
+            oasis::OasisRepetition::Explicit(ref offsets) => PlacementArrayLayout::Explicit(
+                offsets.iter().map(|p| Point::new(p.x, p.y)).collect(),
+            ),
+        };
+        Ok(PlacementArray {
+            cell: placement.cell.clone(),
+            loc: Point::new(placement.x, placement.y),
+            strans: self.import_strans(placement),
+            layout,
+        })
+    }
+    /// Imports a [`PlacementArray`].
+    ///
+    /// Grids are imported as an [`InstanceArray`] named after the placed cell.
+    /// Explicit repetitions are imported as [`Instance`]s named `{cell}[{index}]`.
+    fn import_instance_array(&mut self, array: PlacementArray) -> LayoutResult<ImportedArray> {
+        let cname = array.cell;
+        self.backtrace.push(ErrorContext::Array(cname.clone()));
+        let cell = self.unwrap(
+            self.gds.cell(&cname),
+            format!("Instance Array of invalid cell {cname}"),
+        )?;
+        let cell = Arc::clone(cell);
+        let orientation = self.gds.import_orientation(&array.strans)?;
+
+        let imported = match array.layout {
+            PlacementArrayLayout::Grid {
+                rows,
+                cols,
+                row_pitch,
+                col_pitch,
+            } => ImportedArray::Array(
+                InstanceArray::builder()
+                    .name(cname.clone())
+                    .cell(cell)
+                    .loc(array.loc)
+                    .orientation(orientation)
+                    .rows(rows)
+                    .cols(cols)
+                    .row_pitch(row_pitch)
+                    .col_pitch(col_pitch)
+                    .build()
+                    .unwrap(),
+            ),
+            PlacementArrayLayout::Explicit(offsets) => ImportedArray::Insts(
+                offsets
+                    .into_iter()
+                    .enumerate()
+                    .map(|(i, offset)| {
+                        Instance::builder()
+                            .name(ArcStr::from(format!("{cname}[{i}]")))
+                            .cell(cell.clone())
+                            .loc(array.loc + offset)
+                            .orientation(orientation)
+                            .build()
+                            .unwrap()
+                    })
+                    .collect(),
+            ),
+        };
+        self.backtrace.pop();
+        Ok(imported)
+    }
+    /// Converts the transformation of an [`oasis::OasisPlacement`] to a [`gds21::GdsStrans`].
+    fn import_strans(&self, placement: &oasis::OasisPlacement) -> gds21::GdsStrans {
+        gds21::GdsStrans {
+            reflected: placement.flip,
+            angle: Some(placement.angle),
+            mag: (placement.magnification != 1.0).then_some(placement.magnification),
+            ..Default::default()
+        }
+    }
+    /// Converts OASIS layer and datatype numbers to their GDS equivalents.
+    fn import_layer(&mut self, layer: u64, datatype: u64) -> LayoutResult<(i16, i16)> {
+        match (i16::try_from(layer), i16::try_from(datatype)) {
+            (Ok(layer), Ok(datatype)) => Ok((layer, datatype)),
+            _ => self.fail(format!(
+                "OASIS layer ({layer}, {datatype}) exceeds the range of GDS layers"
+            )),
+        }
+    }
+    /// Converts OASIS points, displaced by `offset`, to GDS points.
+    fn import_points(
+        &mut self,
+        pts: &[oasis::OasisPoint],
+        offset: oasis::OasisPoint,
+    ) -> LayoutResult<Vec<gds21::GdsPoint>> {
+        pts.iter()
+            .map(|p| self.import_point(p.x + offset.x, p.y + offset.y))
+            .collect()
+    }
+    /// Converts a coordinate pair to a [`gds21::GdsPoint`].
+    fn import_point(&self, x: i64, y: i64) -> LayoutResult<gds21::GdsPoint> {
+        Ok(gds21::GdsPoint::new(i32::try_from(x)?, i32::try_from(y)?))
+    }
+}
+
+/// An imported [`PlacementArray`].
+enum ImportedArray {
+    Array(InstanceArray),
+    Insts(Vec<Instance>),
+}
+
+/// Converts the net name property of an OASIS element, if any, to GDS properties.
+///
+/// Other properties have no GDS equivalent, and are dropped.
+fn import_properties(properties: &[oasis::OasisProperty]) -> Vec<gds21::GdsProperty> {
+    properties
+        .iter()
+        .filter(|prop| prop.name == NET_NAME_PROPNAME)
+        .find_map(|prop| match prop.values.as_slice() {
+            [oasis::OasisPropValue::String(value)] => Some(gds21::GdsProperty {
+                attr: NET_NAME_PROPATTR,
+                value: value.clone(),
+            }),
+            _ => None,
+        })
+        .into_iter()
+        .collect()
+}
+
+/// Converts the width and extensions of an [`oasis::OasisPath`] to a [`gds21::GdsPath`].
+///
+/// Paths with half-width extensions at both ends are imported as GDS path type 2.
+/// All other extended paths are imported as GDS path type 4, with explicit extensions.
+fn import_path_extensions(x: &oasis::OasisPath) -> LayoutResult<gds21::GdsPath> {
+    use oasis::OasisPathExtension::*;
+    let width = i32::try_from(2 * x.half_width)?;
+    let extension = |ext: oasis::OasisPathExtension| -> LayoutResult<i32> {
+        Ok(match ext {
+            Flush => 0,
+            HalfWidth => width / 2,
+            Explicit(value) => i32::try_from(value)?,
+        })
+    };
+    let (path_type, begin_extn, end_extn) = match (x.start_extension, x.end_extension) {
+        (Flush, Flush) => (None, None, None),
+        (HalfWidth, HalfWidth) => (Some(2), None, None),
+        (start, end) => (Some(4), Some(extension(start)?), Some(extension(end)?)),
+    };
+    Ok(gds21::GdsPath {
+        width: Some(width),
+        path_type,
+        begin_extn,
+        end_extn,
+        ..Default::default()
+    })
+}
+
+impl ErrorHelper for OasisImporter<'_> {
+    type Error = LayoutError;
+    fn err(&self, msg: impl Into<String>) -> LayoutError {
+        LayoutError::Import {
+            message: msg.into(),
+            stack: self.backtrace.clone(),
+        }
+    }
+}
+
+/// A helper for retrieving OASIS dependencies in reverse topological order.
+///
+/// Each cell in the ordered return value is guaranteed *not* to place any cell which comes later.
+struct OasisDepOrder<'a> {
+    cells: HashMap<&'a str, &'a oasis::OasisCell>,
+    stack: Vec<&'a oasis::OasisCell>,
+    seen: HashSet<&'a str>,
+}
+
+impl<'a> OasisDepOrder<'a> {
+    /// Creates a new [`OasisDepOrder`] for an [`oasis::OasisLibrary`].
+    fn new(lib: &'a oasis::OasisLibrary) -> Self {
+        Self {
+            cells: lib.cells.iter().map(|c| (c.name.as_str(), c)).collect(),
+            stack: Vec::new(),
+            seen: HashSet::new(),
+        }
+    }
+    /// Returns a reverse topological sort of all cells in the library.
+    ///
+    /// Placements of cells that are not defined in the library are ignored here,
+    /// and reported when the placing cell is imported.
+    fn total_order(mut self) -> Vec<&'a oasis::OasisCell> {
+        let mut cells = self.cells.values().copied().collect::<Vec<_>>();
+        cells.sort_by(|a, b| a.name.cmp(&b.name));
+        for cell in cells {
+            self.push(cell);
+        }
+        self.stack
+    }
+    /// Adds all of `cell`'s dependencies, and then `cell` itself, to the stack.
+    fn push(&mut self, cell: &'a oasis::OasisCell) {
+        if self.seen.insert(cell.name.as_str()) {
+            for elem in cell.elems.iter() {
+                if let oasis::OasisElement::Placement(ref x) = elem {
+                    if let Some(&dep) = self.cells.get(x.cell.as_str()) {
+                        self.push(dep);
+                    }
+                }
+            }
+            self.stack.push(cell);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_import_path_extensions() {
+        use oasis::OasisPathExtension::*;
+        for (start_extension, end_extension, path_type, begin_extn, end_extn) in [
+            (Flush, Flush, None, None, None),
+            (HalfWidth, HalfWidth, Some(2), None, None),
+            (Explicit(50), Explicit(-20), Some(4), Some(50), Some(-20)),
+            // Mixed extensions are imported as explicit extensions.
+            (Flush, HalfWidth, Some(4), Some(0), Some(50)),
+        ] {
+            let path = oasis::OasisPath {
+                half_width: 50,
+                start_extension,
+                end_extension,
+                ..Default::default()
+            };
+            let imported = import_path_extensions(&path).unwrap();
+            assert_eq!(imported.width, Some(100));
+            assert_eq!(imported.path_type, path_type);
+            assert_eq!(imported.begin_extn, begin_extn);
+            assert_eq!(imported.end_extn, end_extn);
+        }
+    }
+}
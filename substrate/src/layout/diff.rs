@@ -0,0 +1,767 @@
+//! Geometric comparison of layouts.
+//!
+//! A [`LayoutDiff`] compares two cells after flattening them, computing the
+//! XOR of their geometry on each layer. Two layouts are considered identical if they
+//! cover the same area on every layer, regardless of how that area is divided into
+//! shapes or organized into hierarchy.
+//!
+//! Differences are reported as disjoint rectangles, each of which is covered by one
+//! layout but not the other. The XOR geometry can be written to a GDS file for
+//! inspection using [`LayoutDiff::write_gds`].
+//!
+//! Port shapes are compared on both the drawing and pin purposes of their layer,
+//! so that a cell compares equal to itself after a round trip through GDS.
+//! Text annotations are not compared.
+//!
+//! Geometry is exact for Manhattan shapes. Non-Manhattan polygons and paths are
+//! approximated by vertical slabs spanning consecutive vertex x-coordinates,
+//! sampled at the center of each slab.
+//!
+//! # Examples
+//!
+//! ```ignore
+//! // Fails with a description of every differing region.
+//! ctx.assert_layout_matches_gds::<MyComponent>(&params, "golden/my_component.gds");
+//! ```
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::{self, Display};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use arcstr::ArcStr;
+use subgeom::transform::{Transform, Transformation};
+use subgeom::{Point, Rect, Shape};
+use thiserror::Error;
+
+use super::cell::{Cell, Element};
+use super::convert::gds::GdsExportOpts;
+use super::layers::{LayerPurpose, LayerSpec};
+use crate::component::Component;
+use crate::data::SubstrateCtx;
+use crate::error::{with_err_context, ErrorContext, Result};
+
+/// The environment variable that, if set, causes
+/// [`SubstrateCtx::assert_layout_matches_gds`] to overwrite golden files
+/// instead of comparing against them.
+pub const UPDATE_GOLDEN_ENV: &str = "SUBSTRATE_UPDATE_GOLDEN";
+
+/// The maximum number of regions per layer listed when displaying a [`LayoutDiff`].
+const MAX_DISPLAYED_REGIONS: usize = 10;
+
+/// The differences between two layouts on a single layer.
+#[derive(Clone, Debug)]
+pub struct LayerDiff {
+    layer: LayerSpec,
+    name: ArcStr,
+    only_in_a: Vec<Rect>,
+    only_in_b: Vec<Rect>,
+}
+
+/// The geometric differences between two layouts.
+///
+/// Created by [`LayoutDiff::compute`]. Holds one [`LayerDiff`] for each layer
+/// on which the two layouts differ.
+#[derive(Clone, Debug)]
+pub struct LayoutDiff {
+    a: ArcStr,
+    b: ArcStr,
+    layers: Vec<LayerDiff>,
+}
+
+/// An error encountered while comparing layouts.
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum DiffError {
+    #[error("no cell named {cell} in {path:?}")]
+    CellNotFound { cell: ArcStr, path: PathBuf },
+}
+
+impl LayerDiff {
+    /// The layer on which the differences were found.
+    #[inline]
+    pub fn layer(&self) -> &LayerSpec {
+        &self.layer
+    }
+
+    /// The name of the layer, as declared by the PDK.
+    #[inline]
+    pub fn name(&self) -> &ArcStr {
+        &self.name
+    }
+
+    /// Regions covered by the first layout but not the second.
+    #[inline]
+    pub fn only_in_a(&self) -> &[Rect] {
+        &self.only_in_a
+    }
+
+    /// Regions covered by the second layout but not the first.
+    #[inline]
+    pub fn only_in_b(&self) -> &[Rect] {
+        &self.only_in_b
+    }
+
+    /// Returns an iterator over all differing regions.
+    pub fn regions(&self) -> impl Iterator<Item = Rect> + '_ {
+        self.only_in_a.iter().chain(self.only_in_b.iter()).copied()
+    }
+
+    /// The total area of the differing regions.
+    pub fn area(&self) -> i64 {
+        self.regions().map(|r| r.area()).sum()
+    }
+}
+
+impl LayoutDiff {
+    /// Computes the differences between cells `a` and `b`.
+    ///
+    /// Both cells are flattened before being compared.
+    pub fn compute(ctx: &SubstrateCtx, a: &Cell, b: &Cell) -> Result<Self> {
+        let layers = ctx.layers();
+        let shapes_a = flat_shapes(a);
+        let mut shapes_b = flat_shapes(b);
+
+        let mut specs = shapes_a.keys().cloned().collect::<Vec<_>>();
+        specs.extend(
+            shapes_b
+                .keys()
+                .filter(|spec| !shapes_a.contains_key(spec))
+                .cloned(),
+        );
+        specs.sort();
+
+        let mut diff = Self {
+            a: a.name().clone(),
+            b: b.name().clone(),
+            layers: Vec::new(),
+        };
+        for spec in specs {
+            let polys_a = shapes_a.get(&spec).map(Vec::as_slice).unwrap_or(&[]);
+            let polys_b = shapes_b.remove(&spec).unwrap_or_default();
+            let (only_in_a, only_in_b) = xor(polys_a, &polys_b);
+            if only_in_a.is_empty() && only_in_b.is_empty() {
+                continue;
+            }
+            diff.layers.push(LayerDiff {
+                name: layers.info(spec.layer())?.name,
+                layer: spec,
+                only_in_a,
+                only_in_b,
+            });
+        }
+        Ok(diff)
+    }
+
+    /// The name of the first cell.
+    #[inline]
+    pub fn a(&self) -> &ArcStr {
+        &self.a
+    }
+
+    /// The name of the second cell.
+    #[inline]
+    pub fn b(&self) -> &ArcStr {
+        &self.b
+    }
+
+    /// The differences on each layer.
+    ///
+    /// Layers on which the two layouts are identical are omitted.
+    #[inline]
+    pub fn layers(&self) -> &[LayerDiff] {
+        &self.layers
+    }
+
+    /// The differences on the given layer, if any.
+    pub fn layer(&self, layer: &LayerSpec) -> Option<&LayerDiff> {
+        self.layers.iter().find(|l| &l.layer == layer)
+    }
+
+    /// Returns `true` if the two layouts cover the same area on every layer.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.layers.is_empty()
+    }
+
+    /// Panics with a description of the differences if the layouts are not identical.
+    #[track_caller]
+    pub fn assert_empty(&self) {
+        if !self.is_empty() {
+            panic!("{self}");
+        }
+    }
+
+    /// Creates a new cell containing the XOR geometry.
+    ///
+    /// Each differing region is drawn on the layer on which it was found.
+    /// The cell's name is derived from `name`, modified if needed to avoid conflicts
+    /// with existing cells.
+    pub fn to_cell(&self, ctx: &SubstrateCtx, name: impl Into<ArcStr>) -> Arc<Cell> {
+        let mut inner = ctx.write();
+        let name = inner.layouts().alloc_name(name);
+        let id = inner.layouts_mut().gen_id();
+        let mut cell = Cell::new(id);
+        cell.set_name(name);
+        cell.add_elements(self.layers.iter().flat_map(|layer| {
+            layer.regions().map(|rect| Element {
+                net: None,
+                layer: layer.layer.clone(),
+                inner: rect.into(),
+            })
+        }));
+        cell.freeze();
+        inner.layouts_mut().set_cell(cell)
+    }
+
+    /// Writes the XOR geometry to a GDS file at `path`.
+    pub fn write_gds(&self, ctx: &SubstrateCtx, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        let cell = self.to_cell(ctx, arcstr::format!("{}_xor_{}", self.a, self.b));
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        ctx.to_gds_with_top(cell, path, &GdsExportOpts::default())
+    }
+}
+
+impl Display for LayoutDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return write!(f, "layouts of {} and {} are identical", self.a, self.b);
+        }
+        writeln!(f, "layouts of {} and {} differ:", self.a, self.b)?;
+        for layer in self.layers.iter() {
+            writeln!(
+                f,
+                "  {} ({:?}): {} region(s) only in {}, {} region(s) only in {}",
+                layer.name,
+                layer.layer.purpose(),
+                layer.only_in_a.len(),
+                self.a,
+                layer.only_in_b.len(),
+                self.b,
+            )?;
+            for (cell, rects) in [(&self.a, &layer.only_in_a), (&self.b, &layer.only_in_b)] {
+                for r in rects.iter().take(MAX_DISPLAYED_REGIONS) {
+                    writeln!(
+                        f,
+                        "    only in {cell}: ({}, {}) to ({}, {})",
+                        r.left(),
+                        r.bottom(),
+                        r.right(),
+                        r.top()
+                    )?;
+                }
+                if rects.len() > MAX_DISPLAYED_REGIONS {
+                    writeln!(
+                        f,
+                        "    ... and {} more only in {cell}",
+                        rects.len() - MAX_DISPLAYED_REGIONS
+                    )?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Additional [`SubstrateCtx`] methods for comparing layouts.
+impl SubstrateCtx {
+    /// Computes the differences between cells `a` and `b`.
+    ///
+    /// See [`LayoutDiff::compute`].
+    #[inline]
+    pub fn diff_layouts(&self, a: &Cell, b: &Cell) -> Result<LayoutDiff> {
+        LayoutDiff::compute(self, a, b)
+    }
+
+    /// Computes the differences between the cells named `cell` in two GDS files.
+    pub fn diff_gds(
+        &self,
+        a: impl AsRef<Path>,
+        b: impl AsRef<Path>,
+        cell: &str,
+    ) -> Result<LayoutDiff> {
+        let (a, b) = (a.as_ref(), b.as_ref());
+        let inner = || -> Result<LayoutDiff> {
+            let a = self.import_gds_cell(a, cell)?;
+            let b = self.import_gds_cell(b, cell)?;
+            LayoutDiff::compute(self, &a, &b)
+        };
+        with_err_context(inner(), || {
+            ErrorContext::Task(arcstr::format!(
+                "comparing cell {} in {:?} and {:?}",
+                cell,
+                a,
+                b
+            ))
+        })
+    }
+
+    /// Computes the differences between the layout of component `T` and
+    /// the cell of the same name in the GDS file at `golden`.
+    pub fn diff_layout_with_gds<T>(
+        &self,
+        params: &T::Params,
+        golden: impl AsRef<Path>,
+    ) -> Result<LayoutDiff>
+    where
+        T: Component,
+    {
+        let golden = golden.as_ref();
+        let inner = || -> Result<LayoutDiff> {
+            let inst = self.instantiate_layout::<T>(params)?;
+            let expected = self.import_gds_cell(golden, inst.cell().name())?;
+            LayoutDiff::compute(self, inst.cell(), &expected)
+        };
+        with_err_context(inner(), || {
+            ErrorContext::Task(arcstr::format!(
+                "comparing layout against golden file {:?}",
+                golden
+            ))
+        })
+    }
+
+    /// Asserts that the layout of component `T` matches the golden GDS file at `golden`.
+    ///
+    /// If the [`UPDATE_GOLDEN_ENV`] environment variable is set, the golden file is
+    /// (re)generated instead. On mismatch, the XOR geometry is written next to the
+    /// golden file, with the extension `.xor.gds`, before panicking.
+    #[track_caller]
+    pub fn assert_layout_matches_gds<T>(&self, params: &T::Params, golden: impl AsRef<Path>)
+    where
+        T: Component,
+    {
+        let golden = golden.as_ref();
+        if std::env::var_os(UPDATE_GOLDEN_ENV).is_some() {
+            self.write_layout::<T>(params, golden)
+                .expect("failed to write golden layout");
+            return;
+        }
+        let diff = self
+            .diff_layout_with_gds::<T>(params, golden)
+            .expect("failed to compare layout against golden file");
+        if !diff.is_empty() {
+            let xor = golden.with_extension("xor.gds");
+            diff.write_gds(self, &xor)
+                .expect("failed to write layout XOR");
+            panic!("{diff}\nXOR geometry written to {xor:?}");
+        }
+    }
+
+    fn import_gds_cell(&self, path: &Path, cell: &str) -> Result<Arc<Cell>> {
+        let mut cells = self.from_gds(path)?;
+        Ok(cells.remove(cell).ok_or_else(|| DiffError::CellNotFound {
+            cell: cell.into(),
+            path: path.to_path_buf(),
+        })?)
+    }
+}
+
+/// Flattens `cell`, returning its shapes as polygons grouped by layer.
+///
+/// Port shapes are included on both the drawing and pin purposes of their layer,
+/// matching the geometry written by the GDS exporter.
+fn flat_shapes(cell: &Cell) -> BTreeMap<LayerSpec, Vec<Vec<Point>>> {
+    let mut shapes = BTreeMap::new();
+    flatten_into(&mut shapes, cell, Transformation::identity());
+    shapes
+}
+
+fn flatten_into(
+    shapes: &mut BTreeMap<LayerSpec, Vec<Vec<Point>>>,
+    cell: &Cell,
+    tf: Transformation,
+) {
+    let mut add = |spec: LayerSpec, shape: &Shape| {
+        let polys = polygons(&shape.transform(tf));
+        if !polys.is_empty() {
+            shapes.entry(spec).or_default().extend(polys);
+        }
+    };
+    for elem in cell.elems() {
+        add(elem.layer.clone(), &elem.inner);
+    }
+    for port in cell.ports() {
+        for (&layer, port_shapes) in port.shapes.iter() {
+            for shape in port_shapes {
+                add(LayerSpec::drawing(layer), shape);
+                add(LayerSpec::new(layer, LayerPurpose::Pin), shape);
+            }
+        }
+    }
+
+    let insts = cell
+        .insts()
+        .cloned()
+        .chain(cell.arrays().flat_map(|array| array.insts()));
+    for inst in insts {
+        let tf = Transformation::cascade(tf, inst.transformation());
+        flatten_into(shapes, inst.cell(), tf);
+    }
+}
+
+/// Converts a shape to polygons.
+///
+/// Paths are converted to one polygon per segment.
+pub(crate) fn polygons(shape: &Shape) -> Vec<Vec<Point>> {
+    match shape {
+        Shape::Rect(r) => vec![rect_polygon(*r)],
+        Shape::Polygon(p) => vec![p.points.clone()],
+        Shape::Path(p) => {
+            let half = p.width as f64 / 2.;
+            p.points
+                .windows(2)
+                .filter_map(|seg| {
+                    let (a, b) = (seg[0], seg[1]);
+                    if a == b {
+                        return None;
+                    }
+                    if a.x == b.x || a.y == b.y {
+                        let half = p.width as i64 / 2;
+                        let r = Rect::new(a, b);
+                        return Some(rect_polygon(if a.x == b.x {
+                            Rect::new(
+                                Point::new(r.left() - half, r.bottom()),
+                                Point::new(r.right() + half, r.top()),
+                            )
+                        } else {
+                            Rect::new(
+                                Point::new(r.left(), r.bottom() - half),
+                                Point::new(r.right(), r.top() + half),
+                            )
+                        }));
+                    }
+                    let (dx, dy) = ((b.x - a.x) as f64, (b.y - a.y) as f64);
+                    let len = dx.hypot(dy);
+                    let nx = (-dy / len * half).round() as i64;
+                    let ny = (dx / len * half).round() as i64;
+                    Some(vec![
+                        Point::new(a.x + nx, a.y + ny),
+                        Point::new(b.x + nx, b.y + ny),
+                        Point::new(b.x - nx, b.y - ny),
+                        Point::new(a.x - nx, a.y - ny),
+                    ])
+                })
+                .collect()
+        }
+        Shape::Point(_) => Vec::new(),
+    }
+}
+
+fn rect_polygon(r: Rect) -> Vec<Point> {
+    vec![
+        Point::new(r.left(), r.bottom()),
+        Point::new(r.right(), r.bottom()),
+        Point::new(r.right(), r.top()),
+        Point::new(r.left(), r.top()),
+    ]
+}
+
+/// A half-open interval of y-coordinates.
+type Span = (i64, i64);
+
+/// Computes the XOR of two sets of polygons.
+///
+/// Returns the regions covered only by `a` and the regions covered only by `b`.
+///
+/// Sweeps a line from left to right, stopping at every distinct x-coordinate.
+/// Only the polygons that span the slab between consecutive stops are sampled.
+fn xor(a: &[Vec<Point>], b: &[Vec<Point>]) -> (Vec<Rect>, Vec<Rect>) {
+    let mut xs = a
+        .iter()
+        .chain(b.iter())
+        .flatten()
+        .map(|p| p.x)
+        .collect::<Vec<_>>();
+    xs.sort_unstable();
+    xs.dedup();
+
+    let mut active_a = ActiveSet::new(a);
+    let mut active_b = ActiveSet::new(b);
+    let mut only_a = SlabMerger::default();
+    let mut only_b = SlabMerger::default();
+    for slab in xs.windows(2) {
+        let (x0, x1) = (slab[0], slab[1]);
+        active_a.advance(x0);
+        active_b.advance(x0);
+        let spans_a = active_a.coverage(x0, x1);
+        let spans_b = active_b.coverage(x0, x1);
+        only_a.push(x0, x1, subtract(&spans_a, &spans_b));
+        only_b.push(x0, x1, subtract(&spans_b, &spans_a));
+    }
+    (only_a.finish(), only_b.finish())
+}
+
+/// The polygons intersecting the current slab of a left-to-right sweep.
+struct ActiveSet<'a> {
+    polys: &'a [Vec<Point>],
+    /// The minimum and maximum x-coordinate of each polygon.
+    extents: Vec<(i64, i64)>,
+    /// Indices of non-empty polygons, sorted by their minimum x-coordinate.
+    pending: Vec<usize>,
+    /// The number of polygons in `pending` that have been added to the active set.
+    added: usize,
+    /// The maximum x-coordinate and index of each active polygon.
+    active: BTreeSet<(i64, usize)>,
+}
+
+impl<'a> ActiveSet<'a> {
+    fn new(polys: &'a [Vec<Point>]) -> Self {
+        let extents = polys
+            .iter()
+            .map(|poly| {
+                poly.iter().fold((i64::MAX, i64::MIN), |(lo, hi), p| {
+                    (lo.min(p.x), hi.max(p.x))
+                })
+            })
+            .collect::<Vec<_>>();
+        let mut pending = (0..polys.len())
+            .filter(|&i| !polys[i].is_empty())
+            .collect::<Vec<_>>();
+        pending.sort_unstable_by_key(|&i| extents[i].0);
+        Self {
+            polys,
+            extents,
+            pending,
+            added: 0,
+            active: BTreeSet::new(),
+        }
+    }
+
+    /// Moves the sweep line to `x0`.
+    ///
+    /// Adds polygons that begin at or before `x0`, and removes polygons that end there.
+    /// Since `x0` increases monotonically, each polygon is added and removed once.
+    fn advance(&mut self, x0: i64) {
+        while let Some(&i) = self.pending.get(self.added) {
+            if self.extents[i].0 > x0 {
+                break;
+            }
+            self.active.insert((self.extents[i].1, i));
+            self.added += 1;
+        }
+        while self.active.first().is_some_and(|&(x1, _)| x1 <= x0) {
+            self.active.pop_first();
+        }
+    }
+
+    /// Returns the union of the y-spans covered by the active polygons
+    /// within the slab from `x0` to `x1`.
+    ///
+    /// Polygons are sampled at the center of the slab using the even-odd rule.
+    fn coverage(&self, x0: i64, x1: i64) -> Vec<Span> {
+        let xm = (x0 as f64 + x1 as f64) / 2.;
+        let mut spans = Vec::new();
+        let mut crossings = Vec::new();
+        for &(_, i) in self.active.iter() {
+            let poly = &self.polys[i];
+            crossings.clear();
+            for (i, p) in poly.iter().enumerate() {
+                let q = poly[(i + 1) % poly.len()];
+                if (p.x as f64 <= xm) != (q.x as f64 <= xm) {
+                    let y =
+                        p.y as f64 + (xm - p.x as f64) / (q.x - p.x) as f64 * (q.y - p.y) as f64;
+                    crossings.push(y.round() as i64);
+                }
+            }
+            crossings.sort_unstable();
+            spans.extend(
+                crossings
+                    .chunks_exact(2)
+                    .map(|c| (c[0], c[1]))
+                    .filter(|(y0, y1)| y0 < y1),
+            );
+        }
+        union(spans)
+    }
+}
+
+/// Merges overlapping and abutting spans, returning sorted, disjoint spans.
+fn union(mut spans: Vec<Span>) -> Vec<Span> {
+    spans.sort_unstable();
+    let mut out: Vec<Span> = Vec::with_capacity(spans.len());
+    for (y0, y1) in spans {
+        match out.last_mut() {
+            Some(last) if y0 <= last.1 => last.1 = std::cmp::max(last.1, y1),
+            _ => out.push((y0, y1)),
+        }
+    }
+    out
+}
+
+/// Returns the parts of `a` not covered by `b`.
+///
+/// Both inputs must be sorted and disjoint.
+fn subtract(a: &[Span], b: &[Span]) -> Vec<Span> {
+    let mut out = Vec::new();
+    let mut j = 0;
+    for &(mut y0, y1) in a {
+        while j < b.len() && b[j].1 <= y0 {
+            j += 1;
+        }
+        let mut k = j;
+        while k < b.len() && b[k].0 < y1 {
+            if b[k].0 > y0 {
+                out.push((y0, b[k].0));
+            }
+            y0 = std::cmp::max(y0, b[k].1);
+            k += 1;
+        }
+        if y0 < y1 {
+            out.push((y0, y1));
+        }
+    }
+    out
+}
+
+/// Combines spans from consecutive slabs into rectangles.
+///
+/// Spans that are identical in adjacent slabs are merged into a single rectangle.
+#[derive(Default)]
+struct SlabMerger {
+    /// The spans of the previous slab, along with the x-coordinate at which each began.
+    open: Vec<(Span, i64)>,
+    /// The right edge of the previous slab.
+    x: i64,
+    rects: Vec<Rect>,
+}
+
+impl SlabMerger {
+    fn push(&mut self, x0: i64, x1: i64, spans: Vec<Span>) {
+        let open = std::mem::take(&mut self.open);
+        let contiguous = open.is_empty() || self.x == x0;
+        for (span, start) in open {
+            if contiguous && spans.binary_search(&span).is_ok() {
+                self.open.push((span, start));
+            } else {
+                self.close(span, start);
+            }
+        }
+        for span in spans {
+            if !self.open.iter().any(|(s, _)| *s == span) {
+                self.open.push((span, x0));
+            }
+        }
+        self.open.sort_unstable();
+        self.x = x1;
+    }
+
+    fn close(&mut self, (y0, y1): Span, start: i64) {
+        self.rects
+            .push(Rect::new(Point::new(start, y0), Point::new(self.x, y1)));
+    }
+
+    fn finish(mut self) -> Vec<Rect> {
+        for (span, start) in std::mem::take(&mut self.open) {
+            self.close(span, start);
+        }
+        self.rects
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rect(x0: i64, y0: i64, x1: i64, y1: i64) -> Vec<Point> {
+        rect_polygon(Rect::new(Point::new(x0, y0), Point::new(x1, y1)))
+    }
+
+    fn r(x0: i64, y0: i64, x1: i64, y1: i64) -> Rect {
+        Rect::new(Point::new(x0, y0), Point::new(x1, y1))
+    }
+
+    fn area(rects: &[Rect]) -> i64 {
+        rects.iter().map(|r| r.area()).sum()
+    }
+
+    #[test]
+    fn test_xor_identical_decompositions() {
+        let a = vec![rect(0, 0, 100, 100)];
+        let b = vec![
+            rect(0, 0, 50, 100),
+            rect(50, 0, 100, 100),
+            rect(20, 20, 30, 30),
+        ];
+        let (only_a, only_b) = xor(&a, &b);
+        assert!(only_a.is_empty());
+        assert!(only_b.is_empty());
+    }
+
+    #[test]
+    fn test_xor_shifted() {
+        let a = vec![rect(0, 0, 100, 100)];
+        let b = vec![rect(10, 0, 110, 100)];
+        let (only_a, only_b) = xor(&a, &b);
+        assert_eq!(only_a, vec![r(0, 0, 10, 100)]);
+        assert_eq!(only_b, vec![r(100, 0, 110, 100)]);
+    }
+
+    #[test]
+    fn test_xor_hole() {
+        let a = vec![rect(0, 0, 100, 100)];
+        let b = vec![
+            rect(0, 0, 100, 40),
+            rect(0, 60, 100, 100),
+            rect(0, 40, 40, 60),
+            rect(60, 40, 100, 60),
+        ];
+        let (only_a, only_b) = xor(&a, &b);
+        assert_eq!(only_a, vec![r(40, 40, 60, 60)]);
+        assert!(only_b.is_empty());
+    }
+
+    #[test]
+    fn test_xor_disjoint_slabs() {
+        let a = vec![rect(0, 0, 10, 10), rect(20, 0, 30, 10)];
+        let (only_a, only_b) = xor(&a, &[]);
+        assert_eq!(only_a, vec![r(0, 0, 10, 10), r(20, 0, 30, 10)]);
+        assert!(only_b.is_empty());
+    }
+
+    #[test]
+    fn test_xor_triangle() {
+        let a = vec![vec![
+            Point::new(0, 0),
+            Point::new(100, 0),
+            Point::new(0, 100),
+        ]];
+        let b = vec![vec![
+            Point::new(0, 0),
+            Point::new(100, 0),
+            Point::new(0, 100),
+        ]];
+        let (only_a, only_b) = xor(&a, &b);
+        assert!(only_a.is_empty());
+        assert!(only_b.is_empty());
+
+        let (only_a, _) = xor(&a, &[rect(0, 0, 100, 10)]);
+        assert_eq!(area(&only_a), 100 * 50 - 100 * 10);
+    }
+
+    #[test]
+    fn test_xor_staggered() {
+        // Polygons enter and leave the sweep at different x-coordinates.
+        let a = (0..100)
+            .map(|i| rect(10 * i, 0, 10 * i + 15, 10))
+            .collect::<Vec<_>>();
+        let b = vec![rect(0, 0, 500, 10), rect(600, 0, 1005, 10)];
+        let (only_a, only_b) = xor(&a, &b);
+        assert_eq!(only_a, vec![r(500, 0, 600, 10)]);
+        assert!(only_b.is_empty());
+    }
+
+    #[test]
+    fn test_subtract() {
+        assert_eq!(
+            subtract(&[(0, 100), (200, 300)], &[(10, 20), (50, 250)]),
+            vec![(0, 10), (20, 50), (250, 300)]
+        );
+        assert_eq!(subtract(&[(0, 10)], &[(0, 10)]), vec![]);
+        assert_eq!(
+            union(vec![(5, 10), (0, 5), (20, 30), (25, 27)]),
+            vec![(0, 10), (20, 30)]
+        );
+    }
+}
@@ -0,0 +1,81 @@
+//! A PDK-provided guard ring layout `Component`.
+
+use serde::{Deserialize, Serialize};
+use subgeom::Rect;
+
+use crate::component::Component;
+use crate::deps::arcstr::ArcStr;
+use crate::layout::context::LayoutCtx;
+
+/// The name of the port containing the ring's contact metal.
+pub const GUARD_RING_PORT: &str = "ring";
+
+/// The type of guard ring to draw.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub enum GuardRingKind {
+    /// A P+ substrate tap ring, typically surrounding NMOS devices
+    /// and connected to ground.
+    #[default]
+    PSub,
+    /// An N+ tap ring in an N-well, typically surrounding PMOS devices
+    /// and connected to the positive supply.
+    NWell,
+}
+
+/// Guard ring drawing parameters.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct GuardRingParams {
+    /// The type of guard ring.
+    pub kind: GuardRingKind,
+    /// The region the guard ring must enclose.
+    ///
+    /// The PDK is responsible for spacing the ring far enough
+    /// from any device diffusion inside this region.
+    pub enclosed: Rect,
+    /// The width of the ring's tap diffusion.
+    ///
+    /// If [`None`], the PDK chooses the minimum width
+    /// that fits a single row of contacts.
+    pub width: Option<i64>,
+    /// Whether or not to isolate the enclosed region with a deep N-well.
+    ///
+    /// Only supported for [`GuardRingKind::NWell`] rings, in which case
+    /// the N-well is drawn as a ring over the edge of the deep N-well.
+    pub deep_nwell: bool,
+}
+
+impl GuardRingParams {
+    /// Creates guard ring parameters with the default width and no deep N-well.
+    pub fn new(kind: GuardRingKind, enclosed: Rect) -> Self {
+        Self {
+            kind,
+            enclosed,
+            width: None,
+            deep_nwell: false,
+        }
+    }
+}
+
+/// A guard ring parametrized by [`GuardRingParams`].
+///
+/// The ring's topmost contact layers are exposed as a port named [`GUARD_RING_PORT`].
+pub struct GuardRing(GuardRingParams);
+
+impl Component for GuardRing {
+    type Params = GuardRingParams;
+
+    fn new(params: &Self::Params, _ctx: &crate::data::SubstrateCtx) -> crate::error::Result<Self> {
+        Ok(Self(params.to_owned()))
+    }
+
+    fn name(&self) -> ArcStr {
+        match self.0.kind {
+            GuardRingKind::PSub => arcstr::literal!("guard_ring_psub"),
+            GuardRingKind::NWell => arcstr::literal!("guard_ring_nwell"),
+        }
+    }
+
+    fn layout(&self, ctx: &mut LayoutCtx) -> crate::error::Result<()> {
+        ctx.pdk().guard_ring_layout(ctx, &self.0)
+    }
+}
@@ -1,4 +1,5 @@
 //! Generic layout elements.
 
+pub mod guard_ring;
 pub mod mos;
 pub mod via;
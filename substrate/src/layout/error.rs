@@ -97,6 +97,24 @@ impl From<gds21::GdsError> for LayoutError {
     }
 }
 
+impl From<oasis::OasisError> for LayoutError {
+    fn from(e: oasis::OasisError) -> Self {
+        Self::Boxed(Box::new(e))
+    }
+}
+
+impl From<lefdef::LefDefError> for LayoutError {
+    fn from(e: lefdef::LefDefError) -> Self {
+        Self::Boxed(Box::new(e))
+    }
+}
+
+impl From<magic::MagError> for LayoutError {
+    fn from(e: magic::MagError) -> Self {
+        Self::Boxed(Box::new(e))
+    }
+}
+
 impl<T: std::error::Error + Send + Sync + 'static> From<Box<T>> for LayoutError {
     fn from(e: Box<T>) -> Self {
         Self::Boxed(e)
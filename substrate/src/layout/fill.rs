@@ -0,0 +1,676 @@
+//! Dummy fill generation for density balancing.
+//!
+//! Foundries require the density of each metal and poly layer to lie within a
+//! given range inside every density window. Windows are squares of a fixed size,
+//! stepped across the layout by a fixed pitch (so neighboring windows may overlap).
+//! Windows that extend past the boundary of the layout are clipped to it.
+//!
+//! A [`Fill`] is computed on a flattened view of a [`Cell`]. For each layer for which
+//! the PDK declares [`FillRules`] via [`Pdk::fill_rules`](crate::pdk::Pdk::fill_rules),
+//! candidate fill rectangles are laid out on a regular lattice spanning the cell.
+//! Candidates too close to drawn geometry are discarded. Then, for every window below
+//! the minimum density, candidates are added (spread out evenly across the window)
+//! until the window reaches the minimum density. A candidate is never added if it would
+//! push any window above the maximum density.
+//!
+//! Fill shapes are placed in a separate child cell, a [`FillCell`], so that fill can be
+//! easily toggled or removed. See [`LayoutCtx::fill`](crate::layout::context::LayoutCtx::fill).
+//!
+//! Non-rectangular drawn shapes are approximated by their bounding boxes.
+use std::ops::Range;
+
+use arcstr::ArcStr;
+use serde::{Deserialize, Serialize};
+use subgeom::bbox::BoundBox;
+use subgeom::{Point, Rect};
+use thiserror::Error;
+
+use super::cell::Cell;
+use super::context::LayoutCtx;
+use super::layers::LayerKey;
+use crate::component::Component;
+use crate::data::SubstrateCtx;
+use crate::error::{Result, SubstrateError};
+
+/// Density and fill rules for a single layer.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct FillRules {
+    /// The minimum density allowed in any window, from 0 to 1.
+    pub min_density: f64,
+    /// The maximum density allowed in any window, from 0 to 1.
+    pub max_density: f64,
+    /// The side length of a density window.
+    pub window: i64,
+    /// The distance by which consecutive density windows are offset.
+    pub window_step: i64,
+    /// The width of a fill shape.
+    pub fill_width: i64,
+    /// The height of a fill shape.
+    pub fill_height: i64,
+    /// The spacing between adjacent fill shapes.
+    pub fill_spacing: i64,
+    /// The minimum spacing between fill shapes and drawn shapes.
+    pub space_to_drawn: i64,
+}
+
+/// The density of a single window, before and after fill.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct WindowDensity {
+    /// The window, clipped to the boundary of the layout.
+    pub window: Rect,
+    /// The density of drawn shapes in the window.
+    pub before: f64,
+    /// The density of drawn and fill shapes in the window.
+    pub after: f64,
+}
+
+/// The per-window densities of a single layer.
+#[derive(Clone, Debug)]
+pub struct LayerDensity {
+    layer: LayerKey,
+    rules: FillRules,
+    windows: Vec<WindowDensity>,
+}
+
+/// A report of per-window densities for every filled layer.
+#[derive(Clone, Debug, Default)]
+pub struct DensityReport {
+    layers: Vec<LayerDensity>,
+}
+
+/// Fill shapes for a [`Cell`], along with the resulting [`DensityReport`].
+///
+/// Created by [`Fill::compute`]. Use [`Fill::cell_params`] to place the fill
+/// shapes in a [`FillCell`].
+#[derive(Clone, Debug, Default)]
+pub struct Fill {
+    rects: Vec<(LayerKey, Rect)>,
+    report: DensityReport,
+}
+
+/// Parameters for a [`FillCell`].
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FillCellParams {
+    /// The name of the fill cell.
+    pub name: ArcStr,
+    /// The fill shapes, along with the layers they are drawn on.
+    pub rects: Vec<(LayerKey, Rect)>,
+}
+
+/// A cell containing the shapes of a [`Fill`].
+pub struct FillCell(FillCellParams);
+
+/// An error encountered while generating fill.
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum FillError {
+    #[error("invalid fill rules for layer {layer}: {reason}")]
+    InvalidRules { layer: ArcStr, reason: &'static str },
+}
+
+impl FillRules {
+    fn check(&self) -> std::result::Result<(), &'static str> {
+        if !(0. ..=1.).contains(&self.min_density) || !(0. ..=1.).contains(&self.max_density) {
+            return Err("densities must be between 0 and 1");
+        }
+        if self.min_density > self.max_density {
+            return Err("minimum density exceeds maximum density");
+        }
+        if self.window <= 0 || self.window_step <= 0 {
+            return Err("window size and step must be positive");
+        }
+        if self.fill_width <= 0 || self.fill_height <= 0 {
+            return Err("fill dimensions must be positive");
+        }
+        if self.fill_spacing < 0 || self.space_to_drawn < 0 {
+            return Err("fill spacings must be non-negative");
+        }
+        Ok(())
+    }
+}
+
+impl LayerDensity {
+    /// The layer whose density was analyzed.
+    #[inline]
+    pub fn layer(&self) -> LayerKey {
+        self.layer
+    }
+
+    /// The rules the layer was filled according to.
+    #[inline]
+    pub fn rules(&self) -> &FillRules {
+        &self.rules
+    }
+
+    /// The densities of each window, in row-major order starting from the lower left.
+    #[inline]
+    pub fn windows(&self) -> &[WindowDensity] {
+        &self.windows
+    }
+
+    /// Returns an iterator over the windows whose density after fill is out of range.
+    pub fn violations(&self) -> impl Iterator<Item = &WindowDensity> {
+        self.windows.iter().filter(|w| {
+            w.after < self.rules.min_density - DENSITY_TOLERANCE
+                || w.after > self.rules.max_density + DENSITY_TOLERANCE
+        })
+    }
+}
+
+impl DensityReport {
+    /// The densities of each filled layer.
+    #[inline]
+    pub fn layers(&self) -> &[LayerDensity] {
+        &self.layers
+    }
+
+    /// The densities of the given layer, if it was filled.
+    pub fn layer(&self, layer: LayerKey) -> Option<&LayerDensity> {
+        self.layers.iter().find(|l| l.layer == layer)
+    }
+
+    /// Returns `true` if every window of every layer is within the allowed density range.
+    pub fn is_clean(&self) -> bool {
+        self.layers.iter().all(|l| l.violations().next().is_none())
+    }
+}
+
+/// Slack allowed when comparing densities, to account for floating point error.
+const DENSITY_TOLERANCE: f64 = 1e-9;
+
+impl Fill {
+    /// Computes fill for every layer of `cell` for which the PDK declares [`FillRules`].
+    ///
+    /// Density windows span the bounding box of `cell`. Shapes in child instances
+    /// are included in the density calculation.
+    pub fn compute(ctx: &SubstrateCtx, cell: &Cell) -> Result<Self> {
+        let bbox = cell.bbox();
+        if bbox.is_empty() || bbox.into_rect().area() == 0 {
+            return Ok(Self::default());
+        }
+        let region = bbox.into_rect();
+
+        let pdk = ctx.pdk();
+        let grid = pdk.layout_grid();
+        let layers = ctx.layers();
+
+        let mut fill = Self::default();
+        for layer in layers.keys() {
+            let info = layers.info(layer)?;
+            let rules = match pdk.fill_rules(&info) {
+                Some(rules) => rules,
+                None => continue,
+            };
+            rules.check().map_err(|reason| {
+                SubstrateError::new(FillError::InvalidRules {
+                    layer: info.name.clone(),
+                    reason,
+                })
+            })?;
+
+            let drawn = cell
+                .shapes_on(layer)
+                .map(|s| s.bbox())
+                .filter(|b| !b.is_empty())
+                .map(|b| b.into_rect())
+                .collect::<Vec<_>>();
+            let (rects, windows) = fill_layer(region, &drawn, &rules, grid);
+            fill.rects.extend(rects.into_iter().map(|r| (layer, r)));
+            fill.report.layers.push(LayerDensity {
+                layer,
+                rules,
+                windows,
+            });
+        }
+        Ok(fill)
+    }
+
+    /// The fill shapes, along with the layers they are drawn on.
+    #[inline]
+    pub fn rects(&self) -> &[(LayerKey, Rect)] {
+        &self.rects
+    }
+
+    /// Returns `true` if no fill shapes are needed.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.rects.is_empty()
+    }
+
+    /// The per-window densities before and after fill.
+    #[inline]
+    pub fn report(&self) -> &DensityReport {
+        &self.report
+    }
+
+    /// Consumes the [`Fill`], returning its [`DensityReport`].
+    #[inline]
+    pub fn into_report(self) -> DensityReport {
+        self.report
+    }
+
+    /// Returns the parameters of a [`FillCell`] containing the fill shapes.
+    ///
+    /// The cell can be instantiated using [`LayoutCtx::instantiate`].
+    /// Its name is derived from `name`, modified if needed to avoid conflicts
+    /// with existing cells.
+    pub fn cell_params(&self, name: impl Into<ArcStr>) -> FillCellParams {
+        FillCellParams {
+            name: name.into(),
+            rects: self.rects.clone(),
+        }
+    }
+}
+
+impl Component for FillCell {
+    type Params = FillCellParams;
+
+    fn new(params: &Self::Params, _ctx: &SubstrateCtx) -> Result<Self> {
+        Ok(Self(params.clone()))
+    }
+
+    fn name(&self) -> ArcStr {
+        self.0.name.clone()
+    }
+
+    fn layout(&self, ctx: &mut LayoutCtx) -> Result<()> {
+        for &(layer, rect) in self.0.rects.iter() {
+            ctx.draw_rect(layer, rect);
+        }
+        Ok(())
+    }
+}
+
+/// Computes fill for a single layer within `region`.
+///
+/// Returns the fill shapes and the per-window densities.
+fn fill_layer(
+    region: Rect,
+    drawn: &[Rect],
+    rules: &FillRules,
+    grid: i64,
+) -> (Vec<Rect>, Vec<WindowDensity>) {
+    let xwins = window_spans(region.left(), region.right(), rules);
+    let ywins = window_spans(region.bottom(), region.top(), rules);
+    let windows = ywins
+        .iter()
+        .flat_map(|&(y0, y1)| {
+            xwins
+                .iter()
+                .map(move |&(x0, x1)| Rect::new(Point::new(x0, y0), Point::new(x1, y1)))
+        })
+        .collect::<Vec<_>>();
+
+    let mut areas = windows
+        .iter()
+        .map(|w| union_area(drawn, w))
+        .collect::<Vec<_>>();
+    let before = areas.clone();
+
+    // Lay out candidate fill shapes on a lattice aligned to the layout grid.
+    let xpitch = rules.fill_width + rules.fill_spacing;
+    let ypitch = rules.fill_height + rules.fill_spacing;
+    let x0 = ceil_div(region.left(), grid) * grid;
+    let y0 = ceil_div(region.bottom(), grid) * grid;
+    let nx = lattice_len(x0, region.right(), rules.fill_width, xpitch);
+    let ny = lattice_len(y0, region.top(), rules.fill_height, ypitch);
+    let candidate = |i: usize, j: usize| {
+        let p0 = Point::new(x0 + i as i64 * xpitch, y0 + j as i64 * ypitch);
+        Rect::new(
+            p0,
+            Point::new(p0.x + rules.fill_width, p0.y + rules.fill_height),
+        )
+    };
+
+    // Candidates that are closer to drawn shapes than the required spacing are unavailable.
+    let mut used = vec![false; nx * ny];
+    for rect in drawn {
+        let (imin, imax) = overlap_range(
+            rect.left() - rules.space_to_drawn,
+            rect.right() + rules.space_to_drawn,
+            x0,
+            rules.fill_width,
+            xpitch,
+            nx,
+        );
+        let (jmin, jmax) = overlap_range(
+            rect.bottom() - rules.space_to_drawn,
+            rect.top() + rules.space_to_drawn,
+            y0,
+            rules.fill_height,
+            ypitch,
+            ny,
+        );
+        for j in jmin..jmax {
+            for i in imin..imax {
+                used[j * nx + i] = true;
+            }
+        }
+    }
+
+    let fill_area = rules.fill_width * rules.fill_height;
+    let mut rects = Vec::new();
+    for (w, window) in windows.iter().enumerate() {
+        let target = (rules.min_density * window.area() as f64).ceil() as i64;
+        if areas[w] >= target {
+            continue;
+        }
+
+        // Candidates that overlap this window.
+        let (imin, imax) = overlap_range(
+            window.left(),
+            window.right(),
+            x0,
+            rules.fill_width,
+            xpitch,
+            nx,
+        );
+        let (jmin, jmax) = overlap_range(
+            window.bottom(),
+            window.top(),
+            y0,
+            rules.fill_height,
+            ypitch,
+            ny,
+        );
+        let free = (jmin..jmax)
+            .flat_map(|j| (imin..imax).map(move |i| (i, j)))
+            .filter(|&(i, j)| !used[j * nx + i])
+            .collect::<Vec<_>>();
+        if free.is_empty() {
+            continue;
+        }
+
+        // Visit an evenly spaced subset of candidates first, so that fill is spread across
+        // the window rather than concentrated at one edge.
+        let needed = ceil_div(target - areas[w], fill_area) as usize;
+        let order = if needed < free.len() {
+            let mut spread = vec![false; free.len()];
+            for k in 0..needed {
+                spread[k * free.len() / needed] = true;
+            }
+            (0..free.len())
+                .filter(|&k| spread[k])
+                .chain((0..free.len()).filter(|&k| !spread[k]))
+                .collect::<Vec<_>>()
+        } else {
+            (0..free.len()).collect()
+        };
+
+        for k in order {
+            if areas[w] >= target {
+                break;
+            }
+            let (i, j) = free[k];
+            let rect = candidate(i, j);
+            let xrange = window_range(&xwins, rect.left(), rect.right());
+            let overlaps = window_range(&ywins, rect.bottom(), rect.top())
+                .flat_map(|wy| xrange.clone().map(move |wx| wy * xwins.len() + wx))
+                .map(|v| (v, overlap_area(&rect, &windows[v])))
+                .filter(|&(_, area)| area > 0)
+                .collect::<Vec<_>>();
+            let fits = overlaps.iter().all(|&(v, area)| {
+                (areas[v] + area) as f64
+                    <= rules.max_density * windows[v].area() as f64 + DENSITY_TOLERANCE
+            });
+            if !fits {
+                continue;
+            }
+            for (v, area) in overlaps {
+                areas[v] += area;
+            }
+            used[j * nx + i] = true;
+            rects.push(rect);
+        }
+    }
+
+    let densities = windows
+        .into_iter()
+        .zip(before)
+        .zip(areas)
+        .map(|((window, before), after)| {
+            let area = window.area() as f64;
+            WindowDensity {
+                window,
+                before: before as f64 / area,
+                after: after as f64 / area,
+            }
+        })
+        .collect();
+
+    (rects, densities)
+}
+
+/// Returns the spans of the density windows along one axis of the interval `[lo, hi]`.
+fn window_spans(lo: i64, hi: i64, rules: &FillRules) -> Vec<(i64, i64)> {
+    let mut spans = Vec::new();
+    let mut start = lo;
+    loop {
+        let stop = std::cmp::min(start + rules.window, hi);
+        spans.push((start, stop));
+        if stop >= hi {
+            break;
+        }
+        start += rules.window_step;
+    }
+    spans
+}
+
+/// The range of indices of the window spans that overlap the open interval `(lo, hi)`.
+///
+/// Window spans are sorted by both their start and stop coordinates.
+fn window_range(spans: &[(i64, i64)], lo: i64, hi: i64) -> Range<usize> {
+    let min = spans.partition_point(|&(_, stop)| stop <= lo);
+    let max = spans.partition_point(|&(start, _)| start < hi);
+    min..std::cmp::max(min, max)
+}
+
+/// The number of lattice positions starting at `origin` with the given pitch
+/// at which a shape of length `len` fits below `hi`.
+fn lattice_len(origin: i64, hi: i64, len: i64, pitch: i64) -> usize {
+    if origin + len > hi {
+        0
+    } else {
+        ((hi - origin - len) / pitch + 1) as usize
+    }
+}
+
+/// The range of lattice indices whose shapes overlap the open interval `(lo, hi)`.
+fn overlap_range(lo: i64, hi: i64, origin: i64, len: i64, pitch: i64, n: usize) -> (usize, usize) {
+    // A shape at index `i` overlaps if `origin + i * pitch < hi` and
+    // `origin + i * pitch + len > lo`.
+    let min = floor_div(lo - len - origin, pitch) + 1;
+    let max = ceil_div(hi - origin, pitch);
+    clamp_range(min, max, n)
+}
+
+fn clamp_range(min: i64, max: i64, n: usize) -> (usize, usize) {
+    let min = min.clamp(0, n as i64) as usize;
+    let max = max.clamp(0, n as i64) as usize;
+    (min, std::cmp::max(min, max))
+}
+
+fn floor_div(a: i64, b: i64) -> i64 {
+    a.div_euclid(b)
+}
+
+fn ceil_div(a: i64, b: i64) -> i64 {
+    -(-a).div_euclid(b)
+}
+
+/// The area of the intersection of two rectangles.
+fn overlap_area(a: &Rect, b: &Rect) -> i64 {
+    let w = std::cmp::min(a.right(), b.right()) - std::cmp::max(a.left(), b.left());
+    let h = std::cmp::min(a.top(), b.top()) - std::cmp::max(a.bottom(), b.bottom());
+    if w > 0 && h > 0 {
+        w * h
+    } else {
+        0
+    }
+}
+
+/// The area of the union of `rects` within `window`.
+fn union_area(rects: &[Rect], window: &Rect) -> i64 {
+    let clipped = rects
+        .iter()
+        .filter(|r| overlap_area(r, window) > 0)
+        .map(|r| {
+            (
+                std::cmp::max(r.left(), window.left()),
+                std::cmp::min(r.right(), window.right()),
+                std::cmp::max(r.bottom(), window.bottom()),
+                std::cmp::min(r.top(), window.top()),
+            )
+        })
+        .collect::<Vec<_>>();
+
+    let mut xs = clipped
+        .iter()
+        .flat_map(|&(x0, x1, _, _)| [x0, x1])
+        .collect::<Vec<_>>();
+    xs.sort_unstable();
+    xs.dedup();
+
+    let mut area = 0;
+    let mut spans = Vec::new();
+    for slab in xs.windows(2) {
+        let (left, right) = (slab[0], slab[1]);
+        spans.clear();
+        spans.extend(
+            clipped
+                .iter()
+                .filter(|&&(x0, x1, _, _)| x0 <= left && x1 >= right)
+                .map(|&(_, _, y0, y1)| (y0, y1)),
+        );
+        spans.sort_unstable();
+
+        let mut covered = 0;
+        let mut reach = i64::MIN;
+        for &(y0, y1) in spans.iter() {
+            if y1 <= reach {
+                continue;
+            }
+            covered += y1 - std::cmp::max(y0, reach);
+            reach = y1;
+        }
+        area += covered * (right - left);
+    }
+    area
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rect(x0: i64, y0: i64, x1: i64, y1: i64) -> Rect {
+        Rect::new(Point::new(x0, y0), Point::new(x1, y1))
+    }
+
+    fn rules() -> FillRules {
+        FillRules {
+            min_density: 0.3,
+            max_density: 0.8,
+            window: 100,
+            window_step: 50,
+            fill_width: 10,
+            fill_height: 10,
+            fill_spacing: 5,
+            space_to_drawn: 5,
+        }
+    }
+
+    #[test]
+    fn test_union_area() {
+        let window = rect(0, 0, 100, 100);
+        let rects = [
+            rect(0, 0, 20, 20),
+            rect(10, 10, 30, 30),
+            rect(90, 90, 200, 200),
+            rect(-50, 40, 10, 50),
+        ];
+        assert_eq!(union_area(&rects, &window), 400 + 400 - 100 + 100 + 100);
+        assert_eq!(union_area(&[], &window), 0);
+    }
+
+    #[test]
+    fn test_windows() {
+        let spans = window_spans(0, 220, &rules());
+        assert_eq!(spans, vec![(0, 100), (50, 150), (100, 200), (150, 220)]);
+        assert_eq!(window_spans(0, 60, &rules()), vec![(0, 60)]);
+    }
+
+    #[test]
+    fn test_window_range() {
+        let spans = window_spans(0, 220, &rules());
+        assert_eq!(window_range(&spans, 0, 10), 0..1);
+        assert_eq!(window_range(&spans, 95, 105), 0..3);
+        assert_eq!(window_range(&spans, 100, 110), 1..3);
+        assert_eq!(window_range(&spans, 200, 220), 3..4);
+    }
+
+    #[test]
+    fn test_fill_layer() {
+        let rules = rules();
+        let region = rect(0, 0, 200, 200);
+        let drawn = [rect(0, 0, 200, 20), rect(95, 95, 105, 105)];
+        let (fill, windows) = fill_layer(region, &drawn, &rules, 5);
+
+        assert_eq!(windows.len(), 9);
+        assert!(windows.iter().any(|w| w.before < rules.min_density));
+        for w in windows.iter() {
+            assert!(w.after >= w.before);
+            assert!(w.after >= rules.min_density, "window {:?} under-filled", w);
+            assert!(w.after <= rules.max_density, "window {:?} over-filled", w);
+        }
+
+        for (k, a) in fill.iter().enumerate() {
+            assert_eq!(a.width(), rules.fill_width);
+            assert_eq!(a.height(), rules.fill_height);
+            assert!(overlap_area(a, &region) == a.area());
+            for d in drawn.iter() {
+                let keepout = d.expand(rules.space_to_drawn);
+                assert_eq!(
+                    overlap_area(a, &keepout),
+                    0,
+                    "fill {:?} too close to {:?}",
+                    a,
+                    d
+                );
+            }
+            for b in fill[k + 1..].iter() {
+                assert_eq!(overlap_area(&a.expand(rules.fill_spacing), b), 0);
+            }
+        }
+    }
+
+    #[test]
+    fn test_fill_respects_max_density() {
+        let rules = FillRules {
+            min_density: 0.2,
+            max_density: 0.55,
+            ..rules()
+        };
+        let region = rect(0, 0, 150, 100);
+        let (_, windows) = fill_layer(region, &[rect(0, 0, 50, 100)], &rules, 5);
+        assert_eq!(windows.len(), 2);
+        assert_eq!(windows[0].before, 0.5);
+        assert_eq!(windows[1].before, 0.);
+        assert!(windows[0].after <= rules.max_density);
+        assert!(windows[1].after >= rules.min_density);
+    }
+
+    #[test]
+    fn test_fill_unreachable_density() {
+        let rules = FillRules {
+            min_density: 0.9,
+            max_density: 0.95,
+            ..rules()
+        };
+        let region = rect(0, 0, 100, 100);
+        let (fill, windows) = fill_layer(region, &[], &rules, 5);
+        assert_eq!(fill.len(), 49);
+        assert_eq!(windows.len(), 1);
+        assert_eq!(windows[0].before, 0.);
+        assert_eq!(windows[0].after, 0.49);
+    }
+}
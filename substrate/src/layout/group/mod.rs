@@ -8,8 +8,8 @@ use subgeom::transform::{Transform, Transformation, Translate};
 use subgeom::{Point, Rect, Shape};
 
 use super::cell::{
-    BusPort, CellPort, Instance, PortConflictStrategy, PortError, PortId, PortMap, PortMapFn,
-    TextElement, TransformedPort,
+    BusPort, CellPort, Instance, InstanceArray, PortConflictStrategy, PortError, PortId, PortMap,
+    PortMapFn, TextElement, TransformedPort,
 };
 use super::layers::{LayerBoundBox, LayerKey, LayerPurpose, UserLayer};
 use super::{Draw, DrawRef};
@@ -19,7 +19,7 @@ use crate::layout::placement::align::AlignRect;
 
 pub mod elements;
 
-/// A group of layout [`Element`]s, [`Instance`]s, and/or [`TextElement`]s.
+/// A group of layout [`Element`]s, [`Instance`]s, [`InstanceArray`]s, and/or [`TextElement`]s.
 ///
 /// Cannot contain ports or blockages. If you need those features, create a
 /// [`Component`](crate::component::Component).
@@ -38,6 +38,8 @@ pub struct Group {
     elems: Vec<Element>,
     /// The list of [`Instance`]s in this group.
     insts: Vec<Instance>,
+    /// The list of [`InstanceArray`]s in this group.
+    arrays: Vec<InstanceArray>,
     /// The list of [`TextElement`]s in this group.
     annotations: Vec<TextElement>,
     /// A map of ports.
@@ -105,6 +107,7 @@ impl Group {
         match item {
             GroupItem::Element(elt) => self.elems.push(elt),
             GroupItem::Instance(inst) => self.insts.push(inst),
+            GroupItem::InstanceArray(array) => self.arrays.push(array),
             GroupItem::TextElement(text) => self.annotations.push(text),
         }
     }
@@ -159,6 +162,18 @@ impl Group {
         self.insts.iter().map(move |i| i.transform(tf))
     }
 
+    /// Adds a single [`InstanceArray`] to this group.
+    #[inline]
+    pub fn add_array(&mut self, array: impl Into<InstanceArray>) {
+        self.arrays.push(array.into());
+    }
+
+    /// Returns an iterator over the instance arrays in this group **after transformation**.
+    pub fn arrays(&self) -> impl Iterator<Item = InstanceArray> + '_ {
+        let tf = self.transformation();
+        self.arrays.iter().map(move |a| a.transform(tf))
+    }
+
     /// Returns an iterator over the text annotations in this group **after transformation**.
     pub fn annotations(&self) -> impl Iterator<Item = TextElement> + '_ {
         let tf = self.transformation();
@@ -166,12 +181,17 @@ impl Group {
     }
 
     /// Exposes ports from [`Instance`]s within this group.
+    ///
+    /// Each [`InstanceArray`] is treated as the list of [`Instance`]s it contains.
     pub fn expose_ports(
         &mut self,
         mut port_map_fn: impl GroupPortMapFn,
         port_conflict_strategy: PortConflictStrategy,
     ) -> Result<(), PortError> {
-        let insts: Vec<Instance> = self.instances().collect();
+        let insts: Vec<Instance> = self
+            .instances()
+            .chain(self.arrays().flat_map(|a| a.insts().collect::<Vec<_>>()))
+            .collect();
         for inst in insts {
             for port in inst.ports() {
                 if let Some(port) = port_map_fn.map(port, inst.clone()) {
@@ -254,6 +274,7 @@ impl Group {
     pub fn add_group(&mut self, other: Group) {
         self.elems.extend(other.elements());
         self.insts.extend(other.instances());
+        self.arrays.extend(other.arrays());
         self.annotations.extend(other.annotations());
     }
 
@@ -299,11 +320,14 @@ impl Group {
                 .map(|shape| shape.transform(tf))
                 .collect::<Vec<Shape>>()
         });
+        let arrays = self
+            .arrays()
+            .flat_map(move |array| array.shapes_on(layer).collect::<Vec<Shape>>());
         let curr = self
             .elements()
             .filter(move |elem| elem.layer.layer() == layer)
             .map(|elem| elem.inner);
-        Box::new(curr.chain(recur))
+        Box::new(curr.chain(recur).chain(arrays))
     }
 }
 
@@ -316,6 +340,9 @@ impl BoundBox for Group {
         for inst in self.instances() {
             bbox = inst.bbox().union(bbox);
         }
+        for array in self.arrays() {
+            bbox = array.bbox().union(bbox);
+        }
         bbox
     }
 }
@@ -329,6 +356,9 @@ impl LayerBoundBox for Group {
         for inst in self.instances() {
             bbox = inst.layer_bbox(layer).union(bbox);
         }
+        for array in self.arrays() {
+            bbox = array.layer_bbox(layer).union(bbox);
+        }
         bbox
     }
 }
@@ -347,6 +377,7 @@ impl AlignRect for Group {}
 pub enum GroupItem {
     Element(Element),
     Instance(Instance),
+    InstanceArray(InstanceArray),
     TextElement(TextElement),
 }
 
@@ -362,6 +393,12 @@ impl From<Instance> for GroupItem {
     }
 }
 
+impl From<InstanceArray> for GroupItem {
+    fn from(value: InstanceArray) -> Self {
+        Self::InstanceArray(value)
+    }
+}
+
 impl From<TextElement> for GroupItem {
     fn from(value: TextElement) -> Self {
         Self::TextElement(value)
@@ -390,6 +427,15 @@ impl From<Instance> for Group {
     }
 }
 
+impl From<InstanceArray> for Group {
+    fn from(value: InstanceArray) -> Self {
+        Self {
+            arrays: vec![value],
+            ..Default::default()
+        }
+    }
+}
+
 impl From<Element> for Group {
     fn from(value: Element) -> Self {
         Self {
@@ -406,7 +452,9 @@ impl Flatten for Group {
             &mut self.annotations,
             Transformation::identity(),
             &self.insts,
+            &self.arrays,
         );
         self.insts.clear();
+        self.arrays.clear();
     }
 }
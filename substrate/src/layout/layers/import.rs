@@ -0,0 +1,348 @@
+//! Builds layer tables from KLayout layer properties and LEF technology files.
+//!
+//! A KLayout layer properties (`.lyp`) file supplies layer names and GDS layer/datatype
+//! pairs. A LEF technology section supplies the ordering of the metal and via stack,
+//! along with preferred routing directions, pitches, and widths.
+//! The two can be combined: load the `.lyp` file with [`Layers::from_lyp`], then
+//! annotate the result with [`Layers::apply_lef_tech`].
+
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+use lefdef::{LefDirection, LefLayerType, LefLibrary};
+use subgeom::Dir;
+use thiserror::Error;
+
+use super::{GdsLayerSpec, LayerInfo, LayerPurpose, LayerType, Layers};
+use crate::deps::arcstr::ArcStr;
+use crate::layout::convert::lef::dbu_per_micron;
+use crate::layout::render::style::parse_source;
+use crate::units::SiPrefix;
+
+/// An error encountered while importing a layer table.
+#[derive(Debug, Error)]
+pub enum LayerImportError {
+    #[error("error parsing layer properties: {0}")]
+    LayerProperties(String),
+
+    #[error("unsupported units for LEF: {0:?}")]
+    Units(SiPrefix),
+
+    #[error("error reading LEF: {0}")]
+    Lef(#[from] lefdef::LefDefError),
+
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+impl Layers {
+    /// Loads a [`Layers`] from a KLayout layer properties (`.lyp`) file.
+    ///
+    /// See [`Layers::from_lyp_str`].
+    pub fn from_lyp(path: impl AsRef<Path>) -> Result<Self, LayerImportError> {
+        let text = std::fs::read_to_string(path)?;
+        Self::from_lyp_str(&text)
+    }
+
+    /// Parses a [`Layers`] from the contents of a KLayout layer properties (`.lyp`) file.
+    ///
+    /// Entry names of the form `layer.purpose`, optionally followed by ` - ` and a comment,
+    /// are grouped into a single layer with one GDS layer/datatype pair per purpose.
+    /// Entries without a name or with a wildcard source are ignored.
+    ///
+    /// Rendering colors are not stored in the layer table;
+    /// load them with [`LayerStyles::from_lyp`](crate::layout::render::style::LayerStyles::from_lyp).
+    pub fn from_lyp_str(text: &str) -> Result<Self, LayerImportError> {
+        let doc = roxmltree::Document::parse(text)
+            .map_err(|e| LayerImportError::LayerProperties(e.to_string()))?;
+
+        let mut layer_infos: Vec<LayerInfo> = Vec::new();
+        let mut idxs: HashMap<ArcStr, usize> = HashMap::new();
+        let mut seen = HashSet::new();
+
+        for props in doc.descendants().filter(|n| n.has_tag_name("properties")) {
+            let child = |tag: &str| {
+                props
+                    .children()
+                    .find(|c| c.has_tag_name(tag))
+                    .and_then(|c| c.text())
+                    .map(str::trim)
+            };
+
+            let (spec, name) = match (child("source").and_then(parse_source), child("name")) {
+                (Some(spec), Some(name)) if !name.is_empty() => (spec, name),
+                _ => continue,
+            };
+            if !seen.insert(spec) {
+                continue;
+            }
+
+            let (name, purpose) = parse_lyp_name(name);
+            let idx = *idxs.entry(name.clone()).or_insert_with(|| {
+                layer_infos.push(LayerInfo {
+                    name,
+                    ..Default::default()
+                });
+                layer_infos.len() - 1
+            });
+            let info = &mut layer_infos[idx];
+            let purpose = if info.purps.contains_key(&purpose) {
+                LayerPurpose::Other(spec.1)
+            } else {
+                purpose
+            };
+            info.add_purpose(purpose, spec);
+        }
+
+        Ok(Self::from_layer_infos(layer_infos))
+    }
+
+    /// Annotates layers with the technology information in a LEF file.
+    ///
+    /// See [`Layers::apply_lef_tech`].
+    pub fn apply_lef(
+        &mut self,
+        path: impl AsRef<Path>,
+        units: SiPrefix,
+    ) -> Result<(), LayerImportError> {
+        let lib = LefLibrary::load(path)?;
+        self.apply_lef_tech(&lib, units)
+    }
+
+    /// Annotates layers with the technology layers of a LEF library.
+    ///
+    /// LEF layers are matched to existing layers by name; LEF layers without a
+    /// match are added without any GDS layer/datatype pairs.
+    ///
+    /// Routing layers become metal and routing layers, numbered from 0 in stack order.
+    /// Each cut layer becomes the via between the routing layers below and above it,
+    /// so that via `n` connects metals `n` and `n + 1`. Preferred routing directions,
+    /// pitches, and widths are converted to layout units given by `units`.
+    pub fn apply_lef_tech(
+        &mut self,
+        lib: &LefLibrary,
+        units: SiPrefix,
+    ) -> Result<(), LayerImportError> {
+        let dbu = dbu_per_micron(units).map_err(|_| LayerImportError::Units(units))? as f64;
+        let to_dbu = |x: Option<f64>| x.map(|x| (x * dbu).round() as i64);
+
+        let mut metals = 0;
+        for lef_layer in lib.layers.iter() {
+            let key = match self.get_key(&lef_layer.name) {
+                Some(key) => key,
+                None => self.add(LayerInfo {
+                    name: lef_layer.name.clone(),
+                    ..Default::default()
+                }),
+            };
+
+            let info = &mut self.slots[key].info;
+            info.dir = lef_layer.direction.map(|dir| match dir {
+                LefDirection::Horizontal => Dir::Horiz,
+                LefDirection::Vertical => Dir::Vert,
+            });
+            info.pitch = to_dbu(lef_layer.pitch);
+            info.width = to_dbu(lef_layer.width);
+
+            match lef_layer.layer_type {
+                Some(LefLayerType::Routing) => {
+                    info.layer_type = LayerType::Metal;
+                    info.metal_idx = Some(metals);
+                    info.route_idx = Some(metals);
+                    self.metal_idxs.insert(metals, key);
+                    self.route_idxs.insert(metals, key);
+                    metals += 1;
+                }
+                Some(LefLayerType::Cut) if metals > 0 => {
+                    info.layer_type = LayerType::Via;
+                    info.via_idx = Some(metals - 1);
+                    self.via_idxs.insert(metals - 1, key);
+                }
+                Some(LefLayerType::Implant) if info.layer_type == LayerType::Other => {
+                    info.layer_type = LayerType::Implant;
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Splits a KLayout entry name such as `met1.drawing - 68/20` into a layer name and purpose.
+fn parse_lyp_name(name: &str) -> (ArcStr, LayerPurpose) {
+    let name = name.split(" - ").next().unwrap_or(name).trim();
+    match name.rsplit_once('.') {
+        Some((layer, purpose)) => {
+            let purpose = match purpose.to_lowercase().as_str() {
+                "drawing" | "drw" => LayerPurpose::Drawing,
+                "pin" => LayerPurpose::Pin,
+                "label" | "lbl" | "text" | "txt" => LayerPurpose::Label,
+                "obstruction" | "blockage" | "block" => LayerPurpose::Obstruction,
+                "outline" | "boundary" => LayerPurpose::Outline,
+                _ => purpose.parse().unwrap(),
+            };
+            (ArcStr::from(layer), purpose)
+        }
+        None => (ArcStr::from(name), LayerPurpose::Drawing),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layout::layers::LayerSpec;
+
+    const LYP: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<layer-properties>
+ <properties>
+  <fill-color>#cc00cc</fill-color>
+  <name>li1.drawing - 67/20</name>
+  <source>67/20@1</source>
+ </properties>
+ <group-members>
+  <properties>
+   <name>met1.drawing - 68/20</name>
+   <source>68/20@1</source>
+  </properties>
+  <properties>
+   <name>met1.pin - 68/16</name>
+   <source>68/16@1</source>
+  </properties>
+ </group-members>
+ <properties>
+  <name>met1.pin - duplicate</name>
+  <source>68/16@1</source>
+ </properties>
+ <properties>
+  <name>mcon.drawing</name>
+  <source>67/44@1</source>
+ </properties>
+ <properties>
+  <name>met2.drawing</name>
+  <source>69/20@1</source>
+ </properties>
+ <properties>
+  <name>all</name>
+  <source>*/*@*</source>
+ </properties>
+ <properties>
+  <source>81/4@1</source>
+ </properties>
+</layer-properties>
+"#;
+
+    const LEF: &str = r#"
+VERSION 5.7 ;
+LAYER nwell
+  TYPE MASTERSLICE ;
+END nwell
+LAYER li1
+  TYPE ROUTING ;
+  DIRECTION VERTICAL ;
+  PITCH 0.46 ;
+  WIDTH 0.17 ;
+END li1
+LAYER mcon
+  TYPE CUT ;
+  WIDTH 0.17 ;
+END mcon
+LAYER met1
+  TYPE ROUTING ;
+  DIRECTION HORIZONTAL ;
+  PITCH 0.34 ;
+  WIDTH 0.14 ;
+END met1
+LAYER via
+  TYPE CUT ;
+END via
+LAYER met2
+  TYPE ROUTING ;
+  DIRECTION VERTICAL ;
+  PITCH 0.46 ;
+  WIDTH 0.14 ;
+END met2
+END LIBRARY
+"#;
+
+    #[test]
+    fn test_parse_lyp_name() {
+        assert_eq!(
+            parse_lyp_name("met1.drawing - 68/20"),
+            (ArcStr::from("met1"), LayerPurpose::Drawing)
+        );
+        assert_eq!(
+            parse_lyp_name("met1.lbl"),
+            (ArcStr::from("met1"), LayerPurpose::Label)
+        );
+        assert_eq!(
+            parse_lyp_name("nwell"),
+            (ArcStr::from("nwell"), LayerPurpose::Drawing)
+        );
+        assert_eq!(
+            parse_lyp_name("pwell.res"),
+            (ArcStr::from("pwell"), LayerPurpose::Named("res".into()))
+        );
+    }
+
+    #[test]
+    fn test_layers_from_lyp() {
+        let layers = Layers::from_lyp_str(LYP).unwrap();
+        let mut names = layers.get_layer_names();
+        names.sort();
+        assert_eq!(names, vec!["li1", "mcon", "met1", "met2"]);
+
+        let met1 = layers.get_layer("met1").unwrap();
+        assert_eq!(
+            met1.spec(&LayerPurpose::Drawing),
+            Some(GdsLayerSpec(68, 20))
+        );
+        assert_eq!(met1.spec(&LayerPurpose::Pin), Some(GdsLayerSpec(68, 16)));
+        assert_eq!(met1.purps().len(), 2);
+        assert_eq!(
+            layers.get_from_spec(GdsLayerSpec(68, 16)),
+            Some(&LayerSpec::pin(met1.id))
+        );
+        assert!(layers.get_from_spec(GdsLayerSpec(81, 4)).is_none());
+    }
+
+    #[test]
+    fn test_apply_lef_tech() {
+        let mut layers = Layers::from_lyp_str(LYP).unwrap();
+        let lib = LefLibrary::parse(LEF).unwrap();
+        layers.apply_lef_tech(&lib, SiPrefix::Nano).unwrap();
+
+        let met1 = &layers.get_layer("met1").unwrap().info;
+        assert_eq!(met1.layer_type, LayerType::Metal);
+        assert_eq!(met1.metal_idx, Some(1));
+        assert_eq!(met1.route_idx, Some(1));
+        assert_eq!(met1.dir, Some(Dir::Horiz));
+        assert_eq!(met1.pitch, Some(340));
+        assert_eq!(met1.width, Some(140));
+        assert_eq!(
+            met1.spec(&LayerPurpose::Drawing),
+            Some(GdsLayerSpec(68, 20))
+        );
+
+        let mcon = &layers.get_layer("mcon").unwrap().info;
+        assert_eq!(mcon.layer_type, LayerType::Via);
+        assert_eq!(mcon.via_idx, Some(0));
+        assert_eq!(mcon.pitch, None);
+
+        // LEF layers missing from the layer properties are added without GDS specs.
+        let via = &layers.get_layer("via").unwrap().info;
+        assert_eq!(via.via_idx, Some(1));
+        assert!(via.purps.is_empty());
+        let nwell = &layers.get_layer("nwell").unwrap().info;
+        assert_eq!(nwell.layer_type, LayerType::Other);
+        assert_eq!(nwell.metal_idx, None);
+
+        assert_eq!(layers.metal_idxs.get(&0).copied(), layers.get_key("li1"));
+        assert_eq!(layers.route_idxs.get(&2).copied(), layers.get_key("met2"));
+        assert_eq!(layers.via_idxs.get(&1).copied(), layers.get_key("via"));
+
+        assert!(matches!(
+            layers.apply_lef_tech(&lib, SiPrefix::Milli),
+            Err(LayerImportError::Units(SiPrefix::Milli))
+        ));
+    }
+}
@@ -11,12 +11,14 @@ use derive_builder::Builder;
 use serde::{Deserialize, Serialize};
 use slotmap::{new_key_type, SlotMap};
 use subgeom::bbox::{Bbox, BoundBox};
+use subgeom::Dir;
 use thiserror::Error;
 
 use self::selector::Selector;
 use crate::deps::arcstr::ArcStr;
 use crate::error::{ErrorSource, Result as SubResult};
 
+pub mod import;
 pub mod selector;
 
 new_key_type! {
@@ -308,6 +310,22 @@ pub struct LayerInfo {
     /// The purpose with which labels should be emitted.
     #[builder(default = "LayerPurpose::Label")]
     pub label_purpose: LayerPurpose,
+    /// The name of the layer in Magic, if it differs from the layer name.
+    #[builder(setter(into, strip_option), default)]
+    #[serde(default)]
+    pub magic_name: Option<ArcStr>,
+    /// The preferred routing direction.
+    #[builder(setter(strip_option), default)]
+    #[serde(default)]
+    pub dir: Option<Dir>,
+    /// The default routing pitch, in layout units.
+    #[builder(setter(strip_option), default)]
+    #[serde(default)]
+    pub pitch: Option<i64>,
+    /// The default wire width, in layout units.
+    #[builder(setter(strip_option), default)]
+    #[serde(default)]
+    pub width: Option<i64>,
 }
 
 impl Default for LayerInfo {
@@ -320,6 +338,10 @@ impl Default for LayerInfo {
             via_idx: Default::default(),
             layer_type: Default::default(),
             label_purpose: LayerPurpose::Label,
+            magic_name: None,
+            dir: None,
+            pitch: None,
+            width: None,
         }
     }
 }
@@ -408,6 +430,11 @@ impl LayerInfo {
         self.purps.get(&self.label_purpose).copied()
     }
 
+    /// Returns the name of the layer in Magic.
+    pub fn magic_name(&self) -> &ArcStr {
+        self.magic_name.as_ref().unwrap_or(&self.name)
+    }
+
     /// Retrieves a list of [`LayerPurpose`]-[`GdsLayerSpec`] tuples.
     pub fn purps(&self) -> Vec<(&LayerPurpose, &GdsLayerSpec)> {
         self.purps.iter().collect()
@@ -439,6 +466,12 @@ impl LayersRef {
         key.ok_or(ErrorSource::LayerNotFound(format!("{sel:?}")).into())
     }
 
+    /// Returns the keys of all layers.
+    pub fn keys(&self) -> Vec<LayerKey> {
+        let inner = self.inner.read().unwrap();
+        inner.slots.keys().collect()
+    }
+
     /// Gets the [`LayerInfo`] associated with [`LayerKey`] `layer`.
     pub fn info(&self, layer: LayerKey) -> SubResult<LayerInfo> {
         let inner = self.inner.read().unwrap();
@@ -8,11 +8,14 @@ use self::group::Group;
 pub mod cell;
 pub mod context;
 pub mod convert;
+pub mod diff;
 pub mod elements;
 pub mod error;
+pub mod fill;
 pub mod group;
 pub mod layers;
 pub mod placement;
+pub mod render;
 pub mod routing;
 pub mod straps;
 pub mod validation;
@@ -22,6 +25,29 @@ pub mod validation;
 pub enum LayoutFormat {
     #[default]
     Gds,
+    Oasis,
+}
+
+impl LayoutFormat {
+    /// Infers the layout format from the extension of `path`.
+    ///
+    /// Returns [`None`] if the extension is not recognized.
+    pub fn from_path(path: impl AsRef<std::path::Path>) -> Option<Self> {
+        let ext = path.as_ref().extension()?.to_str()?.to_ascii_lowercase();
+        match ext.as_str() {
+            "gds" | "gdsii" | "gds2" => Some(Self::Gds),
+            "oas" | "oasis" => Some(Self::Oasis),
+            _ => None,
+        }
+    }
+
+    /// The conventional file extension for the layout format.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Gds => "gds",
+            Self::Oasis => "oas",
+        }
+    }
 }
 
 /// A trait implemented by objects that can be drawn
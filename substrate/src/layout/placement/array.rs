@@ -5,7 +5,7 @@ use subgeom::{Point, Rect};
 
 use super::align::{AlignMode, AlignRect};
 use super::tile::Tile;
-use crate::layout::cell::{CellPort, PortConflictStrategy, PortMap, PortMapFn};
+use crate::layout::cell::{CellPort, InstanceArray, PortConflictStrategy, PortMap, PortMapFn};
 use crate::layout::group::Group;
 use crate::layout::{Draw, DrawRef};
 
@@ -16,6 +16,7 @@ pub struct ArrayTiler<'a> {
     mode: AlignMode,
     alt_mode: Option<AlignMode>,
     cells: Vec<Rect>,
+    use_instance_array: bool,
 }
 
 pub trait ArrayPortMapFn: PortMapFn<usize> {}
@@ -28,6 +29,7 @@ pub struct ArrayTilerBuilder<'a> {
     alt_mode: Option<AlignMode>,
     space: i64,
     alt_space: i64,
+    use_instance_array: bool,
 }
 
 impl<'a> ArrayTilerBuilder<'a> {
@@ -48,6 +50,15 @@ impl<'a> ArrayTilerBuilder<'a> {
         self
     }
 
+    /// Sets whether uniform arrays of instances should be drawn as an [`InstanceArray`].
+    ///
+    /// See [`ArrayTiler::to_instance_array`] for more information.
+    #[inline]
+    pub fn use_instance_array(&mut self, use_instance_array: bool) -> &mut Self {
+        self.use_instance_array = use_instance_array;
+        self
+    }
+
     #[inline]
     pub fn push<'b>(&mut self, tile: impl Into<Tile<'b>>) -> &mut Self
     where
@@ -118,6 +129,7 @@ impl<'a> ArrayTiler<'a> {
             mode,
             alt_mode: builder.alt_mode,
             cells,
+            use_instance_array: builder.use_instance_array,
         }
     }
 
@@ -158,13 +170,42 @@ impl<'a> ArrayTiler<'a> {
         self.alt_mode
     }
 
+    /// Returns the tiles as a single-row [`InstanceArray`].
+    ///
+    /// Returns [`None`] unless every tile is an [`Instance`](crate::layout::cell::Instance)
+    /// of the same cell with the same orientation, and the tiles are uniformly spaced.
+    /// Tile `i` becomes column `i` of the array.
+    pub fn to_instance_array(&self) -> Option<InstanceArray> {
+        let insts = self
+            .tiles
+            .iter()
+            .zip(self.cells.iter())
+            .map(|(tile, cell)| {
+                let mut inst = tile.as_instance()?.clone();
+                inst.translate(translation(tile, cell));
+                Some(inst)
+            })
+            .collect::<Option<Vec<_>>>()?;
+        InstanceArray::from_insts(&[insts])
+    }
+
     pub fn generate(&self) -> crate::error::Result<Group> {
         let mut group = Group::new();
 
-        for (tile, cell) in self.tiles.iter().zip(self.cells.iter()) {
-            let mut tgroup = tile.draw_ref()?;
-            tgroup.translate(translation(tile, cell));
-            group.add_group(tgroup);
+        let array = if self.use_instance_array {
+            self.to_instance_array()
+        } else {
+            None
+        };
+
+        if let Some(array) = array {
+            group.add_array(array);
+        } else {
+            for (tile, cell) in self.tiles.iter().zip(self.cells.iter()) {
+                let mut tgroup = tile.draw_ref()?;
+                tgroup.translate(translation(tile, cell));
+                group.add_group(tgroup);
+            }
         }
 
         group.add_ports(self.ports().cloned()).unwrap();
@@ -5,7 +5,7 @@ use subgeom::{Dims, Point, Rect};
 
 use super::nine_patch::NpTiler;
 use super::tile::{OptionTile, Tile};
-use crate::layout::cell::{CellPort, PortConflictStrategy, PortMap, PortMapFn};
+use crate::layout::cell::{CellPort, InstanceArray, PortConflictStrategy, PortMap, PortMapFn};
 use crate::layout::group::Group;
 use crate::layout::{Draw, DrawRef};
 
@@ -31,6 +31,9 @@ pub struct GridTiler<'a> {
     ///
     /// `col_widths[0]` represents the width of the **left-most** column.
     col_widths: Vec<i64>,
+
+    /// Whether uniform grids of instances should be drawn as an [`InstanceArray`].
+    use_instance_array: bool,
 }
 
 pub trait GridPortMapFn: PortMapFn<(usize, usize)> {}
@@ -89,6 +92,7 @@ impl<'a> GridTiler<'a> {
                 .into_iter()
                 .map(|x| x.unwrap_or_default())
                 .collect(),
+            use_instance_array: false,
         }
     }
 
@@ -132,6 +136,37 @@ impl<'a> GridTiler<'a> {
         Ok(())
     }
 
+    /// Sets whether uniform grids of instances should be drawn as an [`InstanceArray`].
+    ///
+    /// See [`GridTiler::to_instance_array`] for more information.
+    pub fn use_instance_array(&mut self, use_instance_array: bool) -> &mut Self {
+        self.use_instance_array = use_instance_array;
+        self
+    }
+
+    /// Returns the tiles as an [`InstanceArray`].
+    ///
+    /// Returns [`None`] unless every position in the grid holds an
+    /// [`Instance`](crate::layout::cell::Instance) of the same cell with the same orientation,
+    /// and the tiles are uniformly spaced.
+    ///
+    /// The tile in row `i`, column `j` becomes the instance in row `i`, column `j` of the array.
+    /// Since rows are ordered from top to bottom, the array's row pitch points downwards.
+    pub fn to_instance_array(&self) -> Option<InstanceArray> {
+        let insts = (0..self.tiles.rows())
+            .map(|i| {
+                (0..self.tiles.cols())
+                    .map(|j| {
+                        let mut inst = self.tiles[i][j].as_ref()?.as_instance()?.clone();
+                        inst.translate(self.translation(i, j));
+                        Some(inst)
+                    })
+                    .collect::<Option<Vec<_>>>()
+            })
+            .collect::<Option<Vec<_>>>()?;
+        InstanceArray::from_insts(&insts)
+    }
+
     pub fn port_map(&self) -> &PortMap {
         &self.ports
     }
@@ -143,6 +178,13 @@ impl<'a> GridTiler<'a> {
     pub(crate) fn generate(&self) -> crate::error::Result<Group> {
         let mut group = Group::new();
 
+        if self.use_instance_array {
+            if let Some(array) = self.to_instance_array() {
+                group.add_array(array);
+                return Ok(group);
+            }
+        }
+
         let rows = self.tiles.rows();
         let cols = self.tiles.cols();
 
@@ -0,0 +1,409 @@
+//! Placement of matched device arrays.
+//!
+//! Matched analog devices (such as differential pair transistors or
+//! capacitor DAC units) are commonly split into identical unit devices,
+//! which are then arranged in a pattern that cancels process gradients.
+//!
+//! A [`MatchedArray`] computes such an arrangement for a set of device
+//! groups, optionally surrounds it with dummy devices, and produces a
+//! tile grid suitable for use with a [`GridTiler`].
+//!
+//! Rows are indexed from top to bottom and columns from left to right,
+//! consistent with [`GridTiler`].
+use arcstr::ArcStr;
+use grid::Grid;
+use subgeom::orientation::Named;
+
+use super::grid::GridTiler;
+use super::tile::OptionTile;
+use crate::error::{ErrorSource, Result};
+use crate::layout::cell::{CellPort, Instance, PortConflictStrategy, PortId};
+
+/// A placement pattern for matched devices.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub enum MatchPattern {
+    /// A point-symmetric arrangement, such as `ABBA`.
+    ///
+    /// Every group has its centroid at the center of the array.
+    /// At most one group may have an odd number of units, and only
+    /// if the array has an odd number of slots.
+    #[default]
+    CommonCentroid,
+    /// Units of each group are interleaved as evenly as possible, such as `ABAB`.
+    Interdigitated,
+    /// Like [`MatchPattern::Interdigitated`], but each row is shifted by one column
+    /// relative to the row above it, so that `A` and `B` units form a checkerboard.
+    ///
+    /// The unit in row `r` and column `c` belongs to group `(r + c) % n`, where `n` is
+    /// the number of groups. Once a group has placed all of its units, its remaining
+    /// slots are filled as in [`MatchPattern::Interdigitated`].
+    Checkerboard,
+}
+
+/// The contents of a single slot in a [`MatchedArray`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum MatchedSlot {
+    /// The `index`-th unit of the group at position `group`.
+    Unit { group: usize, index: usize },
+    /// The `index`-th dummy device.
+    Dummy { index: usize },
+}
+
+#[derive(Clone, Debug)]
+struct MatchedGroup {
+    name: ArcStr,
+    unit: Instance,
+    count: usize,
+}
+
+/// An arrangement of matched devices.
+///
+/// Created using a [`MatchedArrayBuilder`]. Each slot of the array holds either a unit
+/// device of one group or a dummy device; see [`MatchedSlot`].
+#[derive(Clone, Debug)]
+pub struct MatchedArray {
+    groups: Vec<MatchedGroup>,
+    dummy: Option<Instance>,
+    slots: Grid<MatchedSlot>,
+    orientations: Grid<Named>,
+}
+
+/// A builder for a [`MatchedArray`].
+#[derive(Clone, Debug, Default)]
+pub struct MatchedArrayBuilder {
+    groups: Vec<MatchedGroup>,
+    pattern: MatchPattern,
+    rows: usize,
+    dummy: Option<Instance>,
+    dummy_rows: usize,
+    dummy_cols: usize,
+    mirror_alternate: bool,
+}
+
+impl MatchedArrayBuilder {
+    #[inline]
+    pub fn new() -> Self {
+        Self {
+            rows: 1,
+            ..Default::default()
+        }
+    }
+
+    /// Adds a group of `count` identical units.
+    ///
+    /// All units and dummies must have the same dimensions.
+    pub fn group(&mut self, name: impl Into<ArcStr>, unit: Instance, count: usize) -> &mut Self {
+        self.groups.push(MatchedGroup {
+            name: name.into(),
+            unit,
+            count,
+        });
+        self
+    }
+
+    /// Sets the placement pattern. Defaults to [`MatchPattern::CommonCentroid`].
+    #[inline]
+    pub fn pattern(&mut self, pattern: MatchPattern) -> &mut Self {
+        self.pattern = pattern;
+        self
+    }
+
+    /// Sets the number of rows of units, excluding dummies. Defaults to 1.
+    ///
+    /// The total number of units must be divisible by the number of rows.
+    #[inline]
+    pub fn rows(&mut self, rows: usize) -> &mut Self {
+        self.rows = rows;
+        self
+    }
+
+    /// Sets the device used for dummies.
+    #[inline]
+    pub fn dummy(&mut self, dummy: Instance) -> &mut Self {
+        self.dummy = Some(dummy);
+        self
+    }
+
+    /// Sets the number of dummy rows added to both the top and bottom of the array.
+    #[inline]
+    pub fn dummy_rows(&mut self, dummy_rows: usize) -> &mut Self {
+        self.dummy_rows = dummy_rows;
+        self
+    }
+
+    /// Sets the number of dummy columns added to both the left and right of the array.
+    #[inline]
+    pub fn dummy_cols(&mut self, dummy_cols: usize) -> &mut Self {
+        self.dummy_cols = dummy_cols;
+        self
+    }
+
+    /// If `true`, every other column is reflected horizontally.
+    ///
+    /// This allows adjacent units to share source/drain diffusions or
+    /// other edge structures. Defaults to `false`.
+    #[inline]
+    pub fn mirror_alternate(&mut self, mirror_alternate: bool) -> &mut Self {
+        self.mirror_alternate = mirror_alternate;
+        self
+    }
+
+    pub fn build(&mut self) -> Result<MatchedArray> {
+        if self.groups.is_empty() {
+            return Err(invalid("matched array must contain at least one group"));
+        }
+        if self.rows == 0 {
+            return Err(invalid("matched array must contain at least one row"));
+        }
+        if (self.dummy_rows > 0 || self.dummy_cols > 0) && self.dummy.is_none() {
+            return Err(invalid(
+                "dummy rows or columns requested without a dummy device",
+            ));
+        }
+
+        let counts = self.groups.iter().map(|g| g.count).collect::<Vec<_>>();
+        let total = counts.iter().sum::<usize>();
+        if total == 0 || total % self.rows != 0 {
+            return Err(invalid(format!(
+                "{total} matched units cannot be evenly divided into {} rows",
+                self.rows
+            )));
+        }
+        let cols = total / self.rows;
+
+        let units = match self.pattern {
+            MatchPattern::CommonCentroid => common_centroid(&counts)?,
+            MatchPattern::Interdigitated => interleave(&counts),
+            MatchPattern::Checkerboard => checkerboard(&counts, cols),
+        };
+
+        let rows = self.rows + 2 * self.dummy_rows;
+        let all_cols = cols + 2 * self.dummy_cols;
+        let mut slots = Grid::init(rows, all_cols, MatchedSlot::Dummy { index: 0 });
+        let mut orientations = Grid::init(rows, all_cols, Named::Default);
+
+        let mut indices = vec![0; self.groups.len()];
+        let mut dummies = 0;
+        for i in 0..rows {
+            for j in 0..all_cols {
+                let inner = i >= self.dummy_rows
+                    && i < self.dummy_rows + self.rows
+                    && j >= self.dummy_cols
+                    && j < self.dummy_cols + cols;
+                slots[i][j] = if inner {
+                    let group = units[(i - self.dummy_rows) * cols + j - self.dummy_cols];
+                    let index = indices[group];
+                    indices[group] += 1;
+                    MatchedSlot::Unit { group, index }
+                } else {
+                    let index = dummies;
+                    dummies += 1;
+                    MatchedSlot::Dummy { index }
+                };
+                if self.mirror_alternate && j % 2 == 1 {
+                    orientations[i][j] = Named::ReflectHoriz;
+                }
+            }
+        }
+
+        Ok(MatchedArray {
+            groups: self.groups.clone(),
+            dummy: self.dummy.clone(),
+            slots,
+            orientations,
+        })
+    }
+}
+
+impl MatchedArray {
+    #[inline]
+    pub fn builder() -> MatchedArrayBuilder {
+        MatchedArrayBuilder::new()
+    }
+
+    /// The number of rows in the array, including dummies.
+    #[inline]
+    pub fn rows(&self) -> usize {
+        self.slots.rows()
+    }
+
+    /// The number of columns in the array, including dummies.
+    #[inline]
+    pub fn cols(&self) -> usize {
+        self.slots.cols()
+    }
+
+    /// The contents of the slot in row `i`, column `j`.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `i` or `j` are out of bounds.
+    #[inline]
+    pub fn slot(&self, i: usize, j: usize) -> MatchedSlot {
+        self.slots[i][j]
+    }
+
+    /// The name of the group occupying row `i`, column `j`,
+    /// or [`None`] if the slot contains a dummy.
+    pub fn group_name(&self, i: usize, j: usize) -> Option<&ArcStr> {
+        match self.slot(i, j) {
+            MatchedSlot::Unit { group, .. } => Some(&self.groups[group].name),
+            MatchedSlot::Dummy { .. } => None,
+        }
+    }
+
+    /// The orientation of the device in row `i`, column `j`.
+    #[inline]
+    pub fn orientation(&self, i: usize, j: usize) -> Named {
+        self.orientations[i][j]
+    }
+
+    /// Returns the tiles of the array, with each unit and dummy in its computed orientation.
+    pub fn tiles(&self) -> Grid<OptionTile<'static>> {
+        let mut tiles = Grid::new(self.rows(), self.cols());
+        for i in 0..self.rows() {
+            for j in 0..self.cols() {
+                let mut inst = match self.slot(i, j) {
+                    MatchedSlot::Unit { group, .. } => self.groups[group].unit.clone(),
+                    MatchedSlot::Dummy { .. } => self.dummy.clone().unwrap(),
+                };
+                inst.set_orientation(self.orientation(i, j));
+                tiles[i][j] = inst.into();
+            }
+        }
+        tiles
+    }
+
+    /// Maps a port of the device in row `i`, column `j` to a port of the array.
+    ///
+    /// A port named `port` on the `k`-th unit of group `group` becomes
+    /// bit `k` of the bus `{group}_{port}`. Ports of the `k`-th dummy
+    /// become bit `k` of the bus `dummy_{port}`.
+    pub fn map_port(&self, port: CellPort, (i, j): (usize, usize)) -> Option<CellPort> {
+        let (prefix, index) = match self.slot(i, j) {
+            MatchedSlot::Unit { group, index } => (self.groups[group].name.clone(), index),
+            MatchedSlot::Dummy { index } => (arcstr::literal!("dummy"), index),
+        };
+        let name = arcstr::format!("{}_{}", prefix, port.name());
+        Some(port.with_id(PortId::new(name, index)))
+    }
+
+    /// Creates a [`GridTiler`] containing the array,
+    /// with ports exposed according to [`MatchedArray::map_port`].
+    pub fn tiler(&self) -> Result<GridTiler<'static>> {
+        GridTiler::new_with_ports(
+            self.tiles(),
+            |port: CellPort, loc: (usize, usize)| self.map_port(port, loc),
+            PortConflictStrategy::Error,
+        )
+    }
+}
+
+fn invalid(msg: impl Into<String>) -> crate::error::SubstrateError {
+    ErrorSource::InvalidArgs(msg.into()).into()
+}
+
+/// Interleaves units of each group as evenly as possible.
+///
+/// At each step, selects the group that has placed the smallest fraction of its units,
+/// breaking ties in favor of earlier groups.
+fn interleave(counts: &[usize]) -> Vec<usize> {
+    let total = counts.iter().sum::<usize>();
+    let mut placed = vec![0usize; counts.len()];
+    let mut out = Vec::with_capacity(total);
+    for _ in 0..total {
+        let group = (0..counts.len())
+            .filter(|&g| placed[g] < counts[g])
+            .min_by(|&a, &b| {
+                // Compare placed[a] / counts[a] with placed[b] / counts[b].
+                (placed[a] * counts[b]).cmp(&(placed[b] * counts[a]))
+            })
+            .unwrap();
+        placed[group] += 1;
+        out.push(group);
+    }
+    out
+}
+
+/// Arranges units in a checkerboard with `cols` columns, in row-major order.
+///
+/// Each slot prefers group `(row + col) % n`. If that group has no units left,
+/// falls back to the group that has placed the smallest fraction of its units.
+fn checkerboard(counts: &[usize], cols: usize) -> Vec<usize> {
+    let n = counts.len();
+    let total = counts.iter().sum::<usize>();
+    let mut placed = vec![0usize; n];
+    let mut out = Vec::with_capacity(total);
+    for k in 0..total {
+        let preferred = (k / cols + k % cols) % n;
+        let group = if placed[preferred] < counts[preferred] {
+            preferred
+        } else {
+            (0..n)
+                .filter(|&g| placed[g] < counts[g])
+                .min_by(|&a, &b| (placed[a] * counts[b]).cmp(&(placed[b] * counts[a])))
+                .unwrap()
+        };
+        placed[group] += 1;
+        out.push(group);
+    }
+    out
+}
+
+/// Produces a point-symmetric sequence of groups.
+///
+/// The first half of the sequence is interleaved; the second half mirrors the first.
+fn common_centroid(counts: &[usize]) -> Result<Vec<usize>> {
+    let total = counts.iter().sum::<usize>();
+    let odd = counts
+        .iter()
+        .enumerate()
+        .filter(|(_, &c)| c % 2 == 1)
+        .map(|(g, _)| g)
+        .collect::<Vec<_>>();
+    if odd.len() > 1 {
+        return Err(invalid(
+            "common centroid placement allows at most one group with an odd number of units",
+        ));
+    }
+
+    let half = counts.iter().map(|c| c / 2).collect::<Vec<_>>();
+    let first = interleave(&half);
+    let mut out = Vec::with_capacity(total);
+    out.extend(first.iter().copied());
+    if let Some(&g) = odd.first() {
+        out.push(g);
+    }
+    out.extend(first.iter().rev().copied());
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn interleave_sequences() {
+        assert_eq!(interleave(&[2, 2]), vec![0, 1, 0, 1]);
+        assert_eq!(interleave(&[4, 2]), vec![0, 1, 0, 0, 1, 0]);
+    }
+
+    #[test]
+    fn checkerboard_sequences() {
+        assert_eq!(checkerboard(&[2, 2], 2), vec![0, 1, 1, 0]);
+        assert_eq!(checkerboard(&[3, 3], 3), vec![0, 1, 0, 1, 0, 1]);
+        assert_eq!(checkerboard(&[3, 3, 3], 3), vec![0, 1, 2, 1, 2, 0, 2, 0, 1]);
+        assert_eq!(checkerboard(&[2, 4], 3), vec![0, 1, 0, 1, 1, 1]);
+    }
+
+    #[test]
+    fn common_centroid_sequences() {
+        assert_eq!(common_centroid(&[2, 2]).unwrap(), vec![0, 1, 1, 0]);
+        assert_eq!(common_centroid(&[2, 1]).unwrap(), vec![0, 1, 0]);
+        assert_eq!(
+            common_centroid(&[4, 4]).unwrap(),
+            vec![0, 1, 0, 1, 1, 0, 1, 0]
+        );
+        assert!(common_centroid(&[1, 1]).is_err());
+    }
+}
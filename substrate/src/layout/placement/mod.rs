@@ -1,8 +1,10 @@
 pub mod align;
 pub mod array;
 pub mod grid;
+pub mod matching;
 pub mod nine_patch;
 pub mod place_bbox;
+pub mod solver;
 pub mod tile;
 
 pub enum OriginX {
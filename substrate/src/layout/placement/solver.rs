@@ -0,0 +1,698 @@
+//! Constraint-based relative placement.
+//!
+//! [`AlignRect`](super::align::AlignRect) moves one object relative to another.
+//! When many objects depend on each other, it is often easier to declare
+//! all relationships up front and let a [`PlacementSolver`] compute a
+//! translation for every object at once.
+//!
+//! Each axis is solved independently. Equality constraints (alignment,
+//! abutment, symmetry, and fixed positions) are resolved first; the
+//! remaining freedom is then used to satisfy spacing constraints while
+//! moving objects as little as possible to the right and upward.
+//! Objects that are not constrained along an axis are not moved along that axis.
+//!
+//! # Example
+//!
+//! ```ignore
+//! let mut solver = PlacementSolver::from_ctx(ctx)?;
+//! let a = solver.add("a", &inst_a)?;
+//! let b = solver.add("b", &inst_b)?;
+//! solver.fix(a, Point::zero());
+//! solver.left_of(a, b, 200);
+//! solver.align_centers_vertically(a, b);
+//!
+//! let placement = solver.solve()?;
+//! placement.apply(a, &mut inst_a);
+//! placement.apply(b, &mut inst_b);
+//! ```
+use subgeom::bbox::BoundBox;
+use subgeom::transform::Translate;
+use subgeom::{Dir, Point, Rect, Side, Span};
+use thiserror::Error;
+
+use crate::error::{Result, SubstrateError};
+use crate::layout::context::LayoutCtx;
+
+/// An identifier for an object added to a [`PlacementSolver`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Ord, PartialOrd)]
+pub struct PlaceId(usize);
+
+/// A placement constraint.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum Constraint {
+    /// The right edge of `a` is at least `spacing` to the left of the left edge of `b`.
+    LeftOf {
+        a: PlaceId,
+        b: PlaceId,
+        spacing: i64,
+    },
+    /// The top edge of `a` is at least `spacing` below the bottom edge of `b`.
+    Below {
+        a: PlaceId,
+        b: PlaceId,
+        spacing: i64,
+    },
+    /// The given sides of `a` and `b` are at the same coordinate.
+    AlignSides { a: PlaceId, b: PlaceId, side: Side },
+    /// The centers of `a` and `b` have the same coordinate in the given direction.
+    ///
+    /// [`Dir::Horiz`] aligns x-coordinates; [`Dir::Vert`] aligns y-coordinates.
+    AlignCenters { a: PlaceId, b: PlaceId, dir: Dir },
+    /// The edge `side` of `a` touches the opposite edge of `b`.
+    Abut { a: PlaceId, b: PlaceId, side: Side },
+    /// `a` and `b` are mirror images of each other about an axis.
+    ///
+    /// If `dir` is [`Dir::Vert`], the axis is the vertical line `x = coord`;
+    /// if `dir` is [`Dir::Horiz`], the axis is the horizontal line `y = coord`.
+    Symmetric {
+        a: PlaceId,
+        b: PlaceId,
+        dir: Dir,
+        coord: i64,
+    },
+    /// The lower left corner of `a` is at the given point.
+    Fixed { a: PlaceId, point: Point },
+}
+
+/// An error encountered while solving placement constraints.
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum PlacementError {
+    #[error("conflicting placement constraints: {}", .0.join("; "))]
+    Conflict(Vec<String>),
+
+    #[error("placement constraints require `{0}` to be placed at a half-integer coordinate")]
+    HalfUnit(String),
+
+    #[error("placement constraints require `{instance}` to be placed off of the {grid} unit grid")]
+    OffGrid { instance: String, grid: i64 },
+
+    #[error("placement grid must be positive, but was {0}")]
+    InvalidGrid(i64),
+
+    #[error("cannot place `{0}`, since its bounding box is empty")]
+    EmptyBbox(String),
+}
+
+/// A solver for relative placement constraints.
+///
+/// Objects are registered with [`PlacementSolver::add`] and related by constraints.
+/// [`PlacementSolver::solve`] returns a [`Placement`] with the translation of each object.
+#[derive(Clone, Debug, Default)]
+pub struct PlacementSolver {
+    names: Vec<String>,
+    bboxes: Vec<Rect>,
+    constraints: Vec<Constraint>,
+    grid: Option<i64>,
+}
+
+/// The translations computed by a [`PlacementSolver`].
+#[derive(Clone, Debug)]
+pub struct Placement {
+    translations: Vec<Point>,
+}
+
+impl PlacementSolver {
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a new solver that snaps translations to the PDK's layout grid.
+    ///
+    /// See [`Pdk::layout_grid`](crate::pdk::Pdk::layout_grid).
+    pub fn from_ctx(ctx: &LayoutCtx) -> Result<Self> {
+        let mut solver = Self::new();
+        solver.grid(ctx.pdk().layout_grid())?;
+        Ok(solver)
+    }
+
+    /// Requires every translation to be a multiple of `grid`.
+    ///
+    /// Returns [`PlacementError::InvalidGrid`] if `grid` is not positive.
+    /// Solving fails with [`PlacementError::OffGrid`] if the constraints
+    /// cannot be satisfied on the grid.
+    pub fn grid(&mut self, grid: i64) -> Result<&mut Self> {
+        if grid <= 0 {
+            return Err(SubstrateError::new(PlacementError::InvalidGrid(grid)));
+        }
+        self.grid = Some(grid);
+        Ok(self)
+    }
+
+    /// Adds an object with the given name to the solver.
+    ///
+    /// The name is used in error messages. The object is not modified;
+    /// use [`Placement::apply`] to move it once the constraints are solved.
+    ///
+    /// Returns [`PlacementError::EmptyBbox`] if the object has an empty bounding box.
+    pub fn add(&mut self, name: impl Into<String>, object: &impl BoundBox) -> Result<PlaceId> {
+        let name = name.into();
+        let bbox = object.bbox();
+        if bbox.is_empty() {
+            return Err(SubstrateError::new(PlacementError::EmptyBbox(name)));
+        }
+        Ok(self.add_rect(name, bbox.into_rect()))
+    }
+
+    /// Adds an object with the given name and bounding box to the solver.
+    pub fn add_rect(&mut self, name: impl Into<String>, bbox: Rect) -> PlaceId {
+        self.names.push(name.into());
+        self.bboxes.push(bbox);
+        PlaceId(self.names.len() - 1)
+    }
+
+    /// Adds an arbitrary constraint.
+    pub fn constrain(&mut self, constraint: Constraint) -> &mut Self {
+        self.constraints.push(constraint);
+        self
+    }
+
+    /// Places `a` to the left of `b`, with at least `spacing` between them.
+    pub fn left_of(&mut self, a: PlaceId, b: PlaceId, spacing: i64) -> &mut Self {
+        self.constrain(Constraint::LeftOf { a, b, spacing })
+    }
+
+    /// Places `a` below `b`, with at least `spacing` between them.
+    pub fn below(&mut self, a: PlaceId, b: PlaceId, spacing: i64) -> &mut Self {
+        self.constrain(Constraint::Below { a, b, spacing })
+    }
+
+    /// Aligns the given sides of `a` and `b`.
+    pub fn align_sides(&mut self, a: PlaceId, b: PlaceId, side: Side) -> &mut Self {
+        self.constrain(Constraint::AlignSides { a, b, side })
+    }
+
+    /// Aligns the x-coordinates of the centers of `a` and `b`.
+    pub fn align_centers_horizontally(&mut self, a: PlaceId, b: PlaceId) -> &mut Self {
+        self.constrain(Constraint::AlignCenters {
+            a,
+            b,
+            dir: Dir::Horiz,
+        })
+    }
+
+    /// Aligns the y-coordinates of the centers of `a` and `b`.
+    pub fn align_centers_vertically(&mut self, a: PlaceId, b: PlaceId) -> &mut Self {
+        self.constrain(Constraint::AlignCenters {
+            a,
+            b,
+            dir: Dir::Vert,
+        })
+    }
+
+    /// Places `b` so that it abuts the edge `side` of `a`.
+    ///
+    /// Does not constrain the position of `b` along the shared edge.
+    pub fn abut(&mut self, a: PlaceId, b: PlaceId, side: Side) -> &mut Self {
+        self.constrain(Constraint::Abut { a, b, side })
+    }
+
+    /// Places `a` and `b` symmetrically about the vertical line `x = coord`.
+    pub fn symmetric_x(&mut self, a: PlaceId, b: PlaceId, coord: i64) -> &mut Self {
+        self.constrain(Constraint::Symmetric {
+            a,
+            b,
+            dir: Dir::Vert,
+            coord,
+        })
+    }
+
+    /// Places `a` and `b` symmetrically about the horizontal line `y = coord`.
+    pub fn symmetric_y(&mut self, a: PlaceId, b: PlaceId, coord: i64) -> &mut Self {
+        self.constrain(Constraint::Symmetric {
+            a,
+            b,
+            dir: Dir::Horiz,
+            coord,
+        })
+    }
+
+    /// Places the lower left corner of `a` at `point`.
+    pub fn fix(&mut self, a: PlaceId, point: impl Into<Point>) -> &mut Self {
+        self.constrain(Constraint::Fixed {
+            a,
+            point: point.into(),
+        })
+    }
+
+    /// Computes translations satisfying all constraints.
+    pub fn solve(&self) -> Result<Placement> {
+        let x = self.solve_axis(Dir::Horiz)?;
+        let y = self.solve_axis(Dir::Vert)?;
+
+        Ok(Placement {
+            translations: x
+                .into_iter()
+                .zip(y)
+                .map(|(x, y)| Point::new(x, y))
+                .collect(),
+        })
+    }
+
+    /// Solves for translations along the given axis.
+    ///
+    /// All quantities are measured in half units, so that centers are always integers.
+    fn solve_axis(&self, axis: Dir) -> Result<Vec<i64>> {
+        let n = self.names.len();
+        // Node `n` is the origin, whose translation is always 0.
+        let origin = n;
+        let mut system = AxisSystem::new(n + 1);
+        system.fixed[origin] = Some(0);
+
+        let span = |id: PlaceId| -> Span { self.bboxes[id.0].span(axis) };
+        let center2 = |id: PlaceId| -> i64 {
+            let s = span(id);
+            s.start() + s.stop()
+        };
+
+        let mut inequalities = Vec::new();
+        for (idx, constraint) in self.constraints.iter().enumerate() {
+            match *constraint {
+                Constraint::LeftOf { a, b, spacing } if axis == Dir::Horiz => {
+                    inequalities.push((
+                        idx,
+                        a.0,
+                        b.0,
+                        2 * (span(a).stop() + spacing - span(b).start()),
+                    ));
+                }
+                Constraint::Below { a, b, spacing } if axis == Dir::Vert => {
+                    inequalities.push((
+                        idx,
+                        a.0,
+                        b.0,
+                        2 * (span(a).stop() + spacing - span(b).start()),
+                    ));
+                }
+                Constraint::AlignSides { a, b, side } if side.coord_dir() == axis => {
+                    let (ea, eb) = (self.bboxes[a.0].side(side), self.bboxes[b.0].side(side));
+                    system
+                        .relate(idx, a.0, b.0, 1, 2 * (ea - eb))
+                        .map_err(|c| self.conflict(c))?;
+                }
+                Constraint::AlignCenters { a, b, dir } if dir == axis => {
+                    system
+                        .relate(idx, a.0, b.0, 1, center2(a) - center2(b))
+                        .map_err(|c| self.conflict(c))?;
+                }
+                Constraint::Abut { a, b, side } if side.coord_dir() == axis => {
+                    let ea = self.bboxes[a.0].side(side);
+                    let eb = self.bboxes[b.0].side(side.other());
+                    system
+                        .relate(idx, a.0, b.0, 1, 2 * (ea - eb))
+                        .map_err(|c| self.conflict(c))?;
+                }
+                Constraint::Symmetric { a, b, dir, coord } => {
+                    if dir == !axis {
+                        // Mirrored about an axis perpendicular to this one.
+                        system
+                            .relate(idx, a.0, b.0, -1, 4 * coord - center2(a) - center2(b))
+                            .map_err(|c| self.conflict(c))?;
+                    } else {
+                        system
+                            .relate(idx, a.0, b.0, 1, center2(a) - center2(b))
+                            .map_err(|c| self.conflict(c))?;
+                    }
+                }
+                Constraint::Fixed { a, point } => {
+                    let k = 2 * (point.coord(axis) - span(a).start());
+                    system
+                        .relate(idx, origin, a.0, 1, k)
+                        .map_err(|c| self.conflict(c))?;
+                }
+                _ => {}
+            }
+        }
+
+        let step = 2 * self.grid.unwrap_or(1);
+        system
+            .relax(&inequalities, step)
+            .map_err(|c| self.conflict(c))?;
+
+        let mut out = Vec::with_capacity(n);
+        for i in 0..n {
+            let t2 = system.value(i);
+            if t2 % 2 != 0 {
+                return Err(SubstrateError::new(PlacementError::HalfUnit(
+                    self.names[i].clone(),
+                )));
+            }
+            let t = t2 / 2;
+            if let Some(grid) = self.grid {
+                if t % grid != 0 {
+                    return Err(SubstrateError::new(PlacementError::OffGrid {
+                        instance: self.names[i].clone(),
+                        grid,
+                    }));
+                }
+            }
+            out.push(t);
+        }
+        Ok(out)
+    }
+
+    fn conflict(&self, constraints: Vec<usize>) -> SubstrateError {
+        let mut constraints = constraints;
+        constraints.sort_unstable();
+        constraints.dedup();
+        SubstrateError::new(PlacementError::Conflict(
+            constraints
+                .into_iter()
+                .map(|c| self.describe(&self.constraints[c]))
+                .collect(),
+        ))
+    }
+
+    fn describe(&self, constraint: &Constraint) -> String {
+        let name = |id: &PlaceId| &self.names[id.0];
+        match constraint {
+            Constraint::LeftOf { a, b, spacing } => {
+                format!("`{}` left of `{}` (spacing {spacing})", name(a), name(b))
+            }
+            Constraint::Below { a, b, spacing } => {
+                format!("`{}` below `{}` (spacing {spacing})", name(a), name(b))
+            }
+            Constraint::AlignSides { a, b, side } => {
+                format!("`{}` and `{}` aligned on side {side:?}", name(a), name(b))
+            }
+            Constraint::AlignCenters { a, b, dir } => {
+                format!("`{}` and `{}` centers aligned ({dir})", name(a), name(b))
+            }
+            Constraint::Abut { a, b, side } => {
+                format!("`{}` abuts side {side:?} of `{}`", name(b), name(a))
+            }
+            Constraint::Symmetric { a, b, dir, coord } => {
+                let axis = match dir {
+                    Dir::Vert => "x",
+                    Dir::Horiz => "y",
+                };
+                format!(
+                    "`{}` and `{}` symmetric about {axis} = {coord}",
+                    name(a),
+                    name(b)
+                )
+            }
+            Constraint::Fixed { a, point } => {
+                format!("`{}` fixed at ({}, {})", name(a), point.x, point.y)
+            }
+        }
+    }
+}
+
+impl Placement {
+    /// The translation computed for the given object.
+    #[inline]
+    pub fn translation(&self, id: PlaceId) -> Point {
+        self.translations[id.0]
+    }
+
+    /// Translates `object` by the amount computed for `id`.
+    pub fn apply<T: Translate>(&self, id: PlaceId, object: &mut T) {
+        object.translate(self.translation(id));
+    }
+}
+
+/// Equality and inequality constraints along a single axis.
+///
+/// Equalities are maintained in a union-find structure in which each node
+/// is related to its parent by `x = sign * x_parent + offset`.
+struct AxisSystem {
+    parent: Vec<usize>,
+    sign: Vec<i64>,
+    offset: Vec<i64>,
+    /// The constraint that linked each node to its parent.
+    reason: Vec<Option<usize>>,
+    /// Values of roots that are pinned by equality constraints.
+    fixed: Vec<Option<i64>>,
+    /// The constraints responsible for pinning each root.
+    fixed_by: Vec<Vec<usize>>,
+    /// Current values of free roots.
+    values: Vec<i64>,
+}
+
+impl AxisSystem {
+    fn new(n: usize) -> Self {
+        Self {
+            parent: (0..n).collect(),
+            sign: vec![1; n],
+            offset: vec![0; n],
+            reason: vec![None; n],
+            fixed: vec![None; n],
+            fixed_by: vec![Vec::new(); n],
+            values: vec![0; n],
+        }
+    }
+
+    /// Returns `(root, sign, offset)` such that `x = sign * x_root + offset`.
+    fn find(&self, mut node: usize) -> (usize, i64, i64) {
+        let (mut sign, mut offset) = (1, 0);
+        while self.parent[node] != node {
+            // x = s * x_node + o, and x_node = s' * x_parent + o'.
+            offset += sign * self.offset[node];
+            sign *= self.sign[node];
+            node = self.parent[node];
+        }
+        (node, sign, offset)
+    }
+
+    /// The constraints relating `node` to its root.
+    fn path(&self, mut node: usize) -> Vec<usize> {
+        let mut out = Vec::new();
+        while self.parent[node] != node {
+            out.extend(self.reason[node]);
+            node = self.parent[node];
+        }
+        out.extend(self.fixed_by[node].iter().copied());
+        out
+    }
+
+    fn root_value(&self, root: usize) -> i64 {
+        self.fixed[root].unwrap_or(self.values[root])
+    }
+
+    fn value(&self, node: usize) -> i64 {
+        let (root, sign, offset) = self.find(node);
+        sign * self.root_value(root) + offset
+    }
+
+    /// Adds the constraint `x_b = sigma * x_a + k`.
+    ///
+    /// On conflict, returns the indices of the conflicting constraints.
+    fn relate(
+        &mut self,
+        idx: usize,
+        a: usize,
+        b: usize,
+        sigma: i64,
+        k: i64,
+    ) -> std::result::Result<(), Vec<usize>> {
+        let (ra, sa, ca) = self.find(a);
+        let (rb, sb, cb) = self.find(b);
+        let conflict = |this: &Self| {
+            let mut out = this.path(a);
+            out.extend(this.path(b));
+            out.push(idx);
+            out
+        };
+
+        if ra == rb {
+            // sb * r + cb = sigma * (sa * r + ca) + k
+            let coeff = sb - sigma * sa;
+            let rhs = sigma * ca + k - cb;
+            if coeff == 0 {
+                return if rhs == 0 {
+                    Ok(())
+                } else {
+                    Err(conflict(self))
+                };
+            }
+            // coeff is +2 or -2.
+            if rhs % coeff != 0 {
+                return Err(conflict(self));
+            }
+            let value = rhs / coeff;
+            match self.fixed[ra] {
+                Some(v) if v != value => Err(conflict(self)),
+                Some(_) => Ok(()),
+                None => {
+                    self.fixed[ra] = Some(value);
+                    self.fixed_by[ra] = conflict(self);
+                    Ok(())
+                }
+            }
+        } else {
+            // x_rb = sb * (sigma * (sa * x_ra + ca) + k - cb)
+            let sign = sb * sigma * sa;
+            let offset = sb * (sigma * ca + k - cb);
+            if let Some(vb) = self.fixed[rb] {
+                let va = sign * (vb - offset);
+                match self.fixed[ra] {
+                    Some(v) if v != va => return Err(conflict(self)),
+                    Some(_) => {}
+                    None => {
+                        self.fixed[ra] = Some(va);
+                        let by = std::mem::take(&mut self.fixed_by[rb]);
+                        self.fixed_by[ra].extend(by);
+                        self.fixed_by[ra].push(idx);
+                    }
+                }
+            }
+            self.parent[rb] = ra;
+            self.sign[rb] = sign;
+            self.offset[rb] = offset;
+            self.reason[rb] = Some(idx);
+            self.fixed[rb] = None;
+            Ok(())
+        }
+    }
+
+    /// Adjusts free roots until all constraints `x_b - x_a >= k` are satisfied.
+    ///
+    /// Roots only move by multiples of `step`.
+    fn relax(
+        &mut self,
+        inequalities: &[(usize, usize, usize, i64)],
+        step: i64,
+    ) -> std::result::Result<(), Vec<usize>> {
+        let round = |delta: i64| (delta + step - 1) / step * step;
+        let conflict = |this: &Self, idx: usize, a: usize, b: usize| {
+            let mut out = this.path(a);
+            out.extend(this.path(b));
+            out.push(idx);
+            out
+        };
+
+        let max_passes = self.parent.len() + 1;
+        for _ in 0..max_passes {
+            let mut changed = false;
+            for &(idx, a, b, k) in inequalities {
+                let diff = self.value(b) - self.value(a);
+                if diff >= k {
+                    continue;
+                }
+                let delta = k - diff;
+                let (ra, sa, _) = self.find(a);
+                let (rb, sb, _) = self.find(b);
+                if ra == rb {
+                    if sa == sb || self.fixed[ra].is_some() {
+                        return Err(conflict(self, idx, a, b));
+                    }
+                    // Moving the shared root by `d` changes the difference by `2d`.
+                    self.values[rb] += sb * round((delta + 1) / 2);
+                } else if self.fixed[rb].is_none() {
+                    self.values[rb] += sb * round(delta);
+                } else if self.fixed[ra].is_none() {
+                    self.values[ra] -= sa * round(delta);
+                } else {
+                    return Err(conflict(self, idx, a, b));
+                }
+                changed = true;
+            }
+            if !changed {
+                return Ok(());
+            }
+        }
+
+        // Constraints that are still violated form a cycle that cannot be satisfied.
+        let mut out = Vec::new();
+        for &(idx, a, b, k) in inequalities {
+            if self.value(b) - self.value(a) < k {
+                out.extend(conflict(self, idx, a, b));
+            }
+        }
+        Err(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use subgeom::bbox::Bbox;
+
+    use super::*;
+
+    fn square(x: i64, y: i64, size: i64) -> Rect {
+        Rect::new(Point::new(x, y), Point::new(x + size, y + size))
+    }
+
+    #[test]
+    fn left_of_and_align() {
+        let mut solver = PlacementSolver::new();
+        let a = solver.add_rect("a", square(0, 0, 100));
+        let b = solver.add_rect("b", square(0, 0, 50));
+        solver.fix(a, Point::zero()).left_of(a, b, 20);
+        solver.align_centers_vertically(a, b);
+        let placement = solver.solve().unwrap();
+        assert_eq!(placement.translation(a), Point::zero());
+        assert_eq!(placement.translation(b), Point::new(120, 25));
+    }
+
+    #[test]
+    fn symmetric_about_axis() {
+        let mut solver = PlacementSolver::new();
+        let a = solver.add_rect("a", square(0, 0, 100));
+        let b = solver.add_rect("b", square(0, 0, 100));
+        solver.fix(a, Point::new(0, 40)).symmetric_x(a, b, 300);
+        let placement = solver.solve().unwrap();
+        assert_eq!(placement.translation(b), Point::new(500, 40));
+    }
+
+    #[test]
+    fn abut_chain() {
+        let mut solver = PlacementSolver::new();
+        let a = solver.add_rect("a", square(0, 0, 100));
+        let b = solver.add_rect("b", square(0, 0, 100));
+        let c = solver.add_rect("c", square(0, 0, 100));
+        solver.abut(a, b, Side::Right).abut(b, c, Side::Top);
+        let placement = solver.solve().unwrap();
+        assert_eq!(placement.translation(b), Point::new(100, 0));
+        assert_eq!(placement.translation(c), Point::new(0, 100));
+    }
+
+    #[test]
+    fn conflicting_constraints() {
+        let mut solver = PlacementSolver::new();
+        let a = solver.add_rect("a", square(0, 0, 100));
+        let b = solver.add_rect("b", square(0, 0, 100));
+        solver.fix(a, Point::zero()).fix(b, Point::zero());
+        solver.left_of(a, b, 0);
+        let err = solver.solve().unwrap_err();
+        match err.source() {
+            crate::error::ErrorSource::Placement(PlacementError::Conflict(constraints)) => {
+                assert_eq!(constraints.len(), 3);
+            }
+            _ => panic!("unexpected error"),
+        }
+    }
+
+    #[test]
+    fn grid_snapping() {
+        let mut solver = PlacementSolver::new();
+        let a = solver.add_rect("a", square(0, 0, 100));
+        let b = solver.add_rect("b", square(0, 0, 100));
+        solver
+            .grid(5)
+            .unwrap()
+            .fix(a, Point::zero())
+            .left_of(a, b, 12);
+        let placement = solver.solve().unwrap();
+        assert_eq!(placement.translation(b), Point::new(115, 0));
+    }
+
+    #[test]
+    fn invalid_inputs() {
+        let mut solver = PlacementSolver::new();
+        let err = solver.grid(0).unwrap_err();
+        assert!(matches!(
+            err.source(),
+            crate::error::ErrorSource::Placement(PlacementError::InvalidGrid(0))
+        ));
+
+        let err = solver.add("empty", &Bbox::empty()).unwrap_err();
+        assert!(matches!(
+            err.source(),
+            crate::error::ErrorSource::Placement(PlacementError::EmptyBbox(name)) if name == "empty"
+        ));
+    }
+}
@@ -173,6 +173,14 @@ impl<'a> Tile<'a> {
         self.bbox().into_rect().dims()
     }
 
+    /// Returns the underlying [`Instance`], if this tile is an instance.
+    pub fn as_instance(&self) -> Option<&Instance> {
+        match self {
+            Self::Instance(v) => Some(v.deref()),
+            _ => None,
+        }
+    }
+
     pub fn borrowed(&'a self) -> Self {
         use Tile::*;
         match self {
@@ -0,0 +1,372 @@
+//! Rendering of layout cells to SVG and PNG images.
+//!
+//! Renders are intended for documentation and debugging, not for sign-off.
+//! Shapes are drawn using per-layer [`LayerStyles`], which can be loaded from
+//! a KLayout layer properties file. Layers without a style are drawn using a
+//! default palette.
+//!
+//! Instances are flattened up to [`RenderOpts::max_depth`] levels of hierarchy.
+//! Instances below that depth are drawn as labeled bounding boxes.
+//! Port shapes and names of the top-level cell, as well as the bounding boxes of
+//! all expanded instances, can optionally be overlaid on the image.
+//!
+//! # Examples
+//!
+//! ```ignore
+//! let inst = ctx.instantiate_layout::<MyComponent>(&params)?;
+//! let opts = RenderOpts::builder().ports(true).build().unwrap();
+//! let svg = Renderer::new(&LayerStyles::new(), opts).svg(inst.cell())?;
+//! ```
+use std::collections::HashMap;
+use std::path::Path;
+
+use arcstr::ArcStr;
+use derive_builder::Builder;
+use subgeom::bbox::BoundBox;
+use subgeom::transform::{Transform, Transformation};
+use subgeom::{Point, Rect, Shape};
+use thiserror::Error;
+
+pub use self::style::{Color, LayerStyle, LayerStyles, Stipple};
+use super::cell::Cell;
+use super::layers::LayerKey;
+use crate::error::Result;
+
+mod font;
+mod raster;
+pub mod style;
+mod svg;
+
+/// The fraction of the cell's larger dimension left as a margin around the image.
+const MARGIN: f64 = 0.02;
+
+/// Options for rendering a layout cell.
+#[derive(Debug, Clone, Builder)]
+pub struct RenderOpts {
+    /// The maximum depth of hierarchy to draw.
+    ///
+    /// The top cell is at depth 0. Instances deeper than this are drawn
+    /// as labeled bounding boxes. If [`None`], the entire hierarchy is drawn.
+    #[builder(default, setter(strip_option))]
+    pub max_depth: Option<usize>,
+    /// Whether or not to overlay the shapes and names of the top cell's ports.
+    #[builder(default)]
+    pub ports: bool,
+    /// Whether or not to outline the bounding boxes of the top cell and its instances.
+    #[builder(default)]
+    pub bboxes: bool,
+    /// The width of the image, in pixels.
+    ///
+    /// The height is chosen to preserve the aspect ratio of the cell.
+    #[builder(default = "1024")]
+    pub width: u32,
+    /// The background color.
+    #[builder(default = "Color::WHITE")]
+    pub background: Color,
+}
+
+/// An error encountered while rendering a layout.
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum RenderError {
+    #[error("cannot render empty cell {0}")]
+    EmptyCell(ArcStr),
+
+    #[error("invalid image width: {0}")]
+    InvalidWidth(u32),
+
+    #[error("error parsing layer properties: {0}")]
+    LayerProperties(String),
+
+    #[error("error encoding PNG: {0}")]
+    Png(#[from] png::EncodingError),
+}
+
+/// An image format supported by [`Renderer`].
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub enum ImageFormat {
+    #[default]
+    Svg,
+    Png,
+}
+
+/// Renders layout cells to images.
+///
+/// A renderer draws shapes using its [`LayerStyles`] and the options in its [`RenderOpts`].
+#[derive(Debug, Clone)]
+pub struct Renderer<'a> {
+    styles: &'a LayerStyles,
+    opts: RenderOpts,
+}
+
+/// A polygon in image coordinates.
+pub(crate) type Polygon = Vec<[f64; 2]>;
+
+/// The shapes on a single layer, in image coordinates.
+pub(crate) struct SceneLayer {
+    pub(crate) style: LayerStyle,
+    pub(crate) polys: Vec<Polygon>,
+}
+
+/// A rectangular outline, in image coordinates.
+pub(crate) struct Outline {
+    pub(crate) rect: [f64; 4],
+    pub(crate) label: Option<ArcStr>,
+}
+
+/// A port of the top cell, in image coordinates.
+pub(crate) struct PortOverlay {
+    pub(crate) name: ArcStr,
+    pub(crate) center: [f64; 2],
+    pub(crate) polys: Vec<Polygon>,
+}
+
+/// A cell, converted to image coordinates and ready to be drawn.
+pub(crate) struct Scene {
+    pub(crate) width: u32,
+    pub(crate) height: u32,
+    pub(crate) background: Color,
+    pub(crate) layers: Vec<SceneLayer>,
+    pub(crate) outlines: Vec<Outline>,
+    pub(crate) ports: Vec<PortOverlay>,
+}
+
+impl Default for RenderOpts {
+    fn default() -> Self {
+        Self::builder().build().unwrap()
+    }
+}
+
+impl RenderOpts {
+    #[inline]
+    pub fn builder() -> RenderOptsBuilder {
+        RenderOptsBuilder::default()
+    }
+}
+
+impl ImageFormat {
+    /// Infers the image format from the extension of `path`.
+    ///
+    /// Returns [`None`] if the extension is not recognized.
+    pub fn from_path(path: impl AsRef<Path>) -> Option<Self> {
+        let ext = path.as_ref().extension()?.to_str()?.to_ascii_lowercase();
+        match ext.as_str() {
+            "svg" => Some(Self::Svg),
+            "png" => Some(Self::Png),
+            _ => None,
+        }
+    }
+}
+
+impl<'a> Renderer<'a> {
+    /// Creates a new [`Renderer`].
+    pub fn new(styles: &'a LayerStyles, opts: RenderOpts) -> Self {
+        Self { styles, opts }
+    }
+
+    /// Renders `cell` to an SVG document.
+    pub fn svg(&self, cell: &Cell) -> Result<String> {
+        let scene = Scene::new(cell, self.styles, &self.opts)?;
+        Ok(svg::render(&scene))
+    }
+
+    /// Renders `cell` to PNG-encoded bytes.
+    pub fn png(&self, cell: &Cell) -> Result<Vec<u8>> {
+        let scene = Scene::new(cell, self.styles, &self.opts)?;
+        raster::render(&scene)
+    }
+
+    /// Renders `cell` to the given file in the given format.
+    pub fn write(&self, cell: &Cell, path: impl AsRef<Path>, format: ImageFormat) -> Result<()> {
+        let data = match format {
+            ImageFormat::Svg => self.svg(cell)?.into_bytes(),
+            ImageFormat::Png => self.png(cell)?,
+        };
+        std::fs::write(path, data)?;
+        Ok(())
+    }
+}
+
+/// Accumulates the contents of a [`Scene`] in layout coordinates.
+struct SceneBuilder<'a> {
+    opts: &'a RenderOpts,
+    shapes: HashMap<LayerKey, Vec<Shape>>,
+    outlines: Vec<(Rect, Option<ArcStr>)>,
+}
+
+impl<'a> SceneBuilder<'a> {
+    fn add_cell(&mut self, cell: &Cell, tf: Transformation, depth: usize) {
+        for elem in cell.elems() {
+            self.shapes
+                .entry(elem.layer.layer())
+                .or_default()
+                .push(elem.inner.transform(tf));
+        }
+
+        let insts = cell
+            .insts()
+            .cloned()
+            .chain(cell.arrays().flat_map(|array| array.insts()));
+        for inst in insts {
+            let bbox = inst.cell().bbox();
+            if bbox.is_empty() {
+                continue;
+            }
+            let child_tf = Transformation::cascade(tf, inst.transformation());
+            let rect = bbox.into_rect().transform(child_tf);
+            if self.opts.max_depth.map(|max| depth < max).unwrap_or(true) {
+                self.add_cell(inst.cell(), child_tf, depth + 1);
+                if self.opts.bboxes {
+                    self.outlines.push((rect, None));
+                }
+            } else {
+                self.outlines.push((rect, Some(inst.cell().name().clone())));
+            }
+        }
+    }
+}
+
+impl Scene {
+    fn new(cell: &Cell, styles: &LayerStyles, opts: &RenderOpts) -> Result<Self> {
+        if opts.width == 0 {
+            return Err(RenderError::InvalidWidth(opts.width).into());
+        }
+        let bbox = cell.bbox();
+        if bbox.is_empty() {
+            return Err(RenderError::EmptyCell(cell.name().clone()).into());
+        }
+        let bbox = bbox.into_rect();
+
+        let margin = MARGIN * std::cmp::max(bbox.width(), bbox.height()) as f64;
+        let margin = if margin > 0. { margin } else { 1. };
+        let x0 = bbox.left() as f64 - margin;
+        let y1 = bbox.top() as f64 + margin;
+        let scale = opts.width as f64 / (bbox.width() as f64 + 2. * margin);
+        let height = ((bbox.height() as f64 + 2. * margin) * scale)
+            .round()
+            .max(1.) as u32;
+        let to_image = |x: f64, y: f64| [(x - x0) * scale, (y1 - y) * scale];
+        let rect_to_image = |r: Rect| {
+            let [xa, ya] = to_image(r.left() as f64, r.top() as f64);
+            let [xb, yb] = to_image(r.right() as f64, r.bottom() as f64);
+            [xa, ya, xb, yb]
+        };
+
+        let mut builder = SceneBuilder {
+            opts,
+            shapes: HashMap::new(),
+            outlines: Vec::new(),
+        };
+        builder.add_cell(cell, Transformation::identity(), 0);
+
+        let mut keys = builder.shapes.keys().copied().collect::<Vec<_>>();
+        keys.sort();
+        let mut unstyled = 0;
+        let mut layers = Vec::new();
+        for key in keys {
+            let style = match styles.get(key) {
+                Some(style) => style.clone(),
+                None => {
+                    unstyled += 1;
+                    LayerStyle::palette(unstyled - 1)
+                }
+            };
+            if !style.visible {
+                continue;
+            }
+            let polys = builder.shapes[&key]
+                .iter()
+                .flat_map(polygons)
+                .map(|poly| poly.into_iter().map(|[x, y]| to_image(x, y)).collect())
+                .collect();
+            layers.push(SceneLayer { style, polys });
+        }
+
+        let mut outlines = Vec::new();
+        if opts.bboxes {
+            outlines.push(Outline {
+                rect: rect_to_image(bbox),
+                label: None,
+            });
+        }
+        outlines.extend(builder.outlines.into_iter().map(|(rect, label)| Outline {
+            rect: rect_to_image(rect),
+            label,
+        }));
+
+        let mut ports = Vec::new();
+        if opts.ports {
+            for port in cell.ports() {
+                let shapes = port.shapes.values().flatten().collect::<Vec<_>>();
+                let bbox = shapes
+                    .iter()
+                    .fold(subgeom::bbox::Bbox::empty(), |acc, s| s.union(acc));
+                if bbox.is_empty() {
+                    continue;
+                }
+                let center = bbox.into_rect().center();
+                let name = if port.id().index == 0 {
+                    port.name().clone()
+                } else {
+                    arcstr::format!("{}", port.id())
+                };
+                ports.push(PortOverlay {
+                    name,
+                    center: to_image(center.x as f64, center.y as f64),
+                    polys: shapes
+                        .into_iter()
+                        .flat_map(polygons)
+                        .map(|poly| poly.into_iter().map(|[x, y]| to_image(x, y)).collect())
+                        .collect(),
+                });
+            }
+        }
+
+        Ok(Self {
+            width: opts.width,
+            height,
+            background: opts.background,
+            layers,
+            outlines,
+            ports,
+        })
+    }
+}
+
+/// Converts a shape to polygons in layout coordinates.
+///
+/// Paths are converted to one polygon per segment.
+fn polygons(shape: &Shape) -> Vec<Polygon> {
+    let point = |p: &Point| [p.x as f64, p.y as f64];
+    match shape {
+        Shape::Rect(r) => vec![vec![
+            [r.left() as f64, r.bottom() as f64],
+            [r.right() as f64, r.bottom() as f64],
+            [r.right() as f64, r.top() as f64],
+            [r.left() as f64, r.top() as f64],
+        ]],
+        Shape::Polygon(p) => vec![p.points.iter().map(point).collect()],
+        Shape::Path(p) => {
+            let half = p.width as f64 / 2.;
+            p.points
+                .windows(2)
+                .filter_map(|seg| {
+                    let [ax, ay] = point(&seg[0]);
+                    let [bx, by] = point(&seg[1]);
+                    let len = (bx - ax).hypot(by - ay);
+                    if len == 0. {
+                        return None;
+                    }
+                    let (nx, ny) = (-(by - ay) / len * half, (bx - ax) / len * half);
+                    Some(vec![
+                        [ax + nx, ay + ny],
+                        [bx + nx, by + ny],
+                        [bx - nx, by - ny],
+                        [ax - nx, ay - ny],
+                    ])
+                })
+                .collect()
+        }
+        Shape::Point(_) => Vec::new(),
+    }
+}
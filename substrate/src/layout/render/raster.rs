@@ -0,0 +1,258 @@
+//! PNG output.
+
+use super::font::{self, ADVANCE, GLYPH_HEIGHT, GLYPH_WIDTH};
+use super::style::{Color, Stipple};
+use super::{RenderError, Scene};
+use crate::error::Result;
+
+/// The opacity of solid fills.
+const SOLID_OPACITY: f64 = 0.5;
+
+/// An RGB pixel buffer.
+struct Canvas {
+    width: usize,
+    height: usize,
+    pixels: Vec<Color>,
+}
+
+pub(crate) fn render(scene: &Scene) -> Result<Vec<u8>> {
+    let canvas = draw(scene);
+
+    let mut buf = Vec::new();
+    {
+        let mut encoder = png::Encoder::new(&mut buf, scene.width, scene.height);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header().map_err(RenderError::from)?;
+        writer
+            .write_image_data(&canvas.to_rgb())
+            .map_err(RenderError::from)?;
+    }
+    Ok(buf)
+}
+
+fn draw(scene: &Scene) -> Canvas {
+    let mut canvas = Canvas::new(
+        scene.width as usize,
+        scene.height as usize,
+        scene.background,
+    );
+
+    for layer in scene.layers.iter() {
+        let style = &layer.style;
+        let alpha = match style.stipple {
+            Stipple::Solid => SOLID_OPACITY,
+            _ => 1.,
+        };
+        for poly in layer.polys.iter() {
+            canvas.fill_polygon(poly, style.fill, style.stipple, alpha);
+            canvas.stroke_polygon(poly, style.frame, false);
+        }
+    }
+
+    // Scale labels up for large images so they remain legible.
+    let text_scale = std::cmp::max(1, scene.width as usize / 800);
+
+    for outline in scene.outlines.iter() {
+        let [x0, y0, x1, y1] = outline.rect;
+        canvas.stroke_polygon(
+            &[[x0, y0], [x1, y0], [x1, y1], [x0, y1]],
+            Color::BLACK,
+            false,
+        );
+        if let Some(label) = &outline.label {
+            canvas.draw_text(x0 + 2., y0 + 2., label, text_scale, Color::BLACK);
+        }
+    }
+
+    for port in scene.ports.iter() {
+        for poly in port.polys.iter() {
+            canvas.stroke_polygon(poly, Color::BLACK, true);
+        }
+        let width = (port.name.chars().count() * ADVANCE * text_scale) as f64;
+        let height = (GLYPH_HEIGHT * text_scale) as f64;
+        canvas.draw_text(
+            port.center[0] - width / 2.,
+            port.center[1] - height / 2.,
+            &port.name,
+            text_scale,
+            Color::BLACK,
+        );
+    }
+
+    canvas
+}
+
+impl Canvas {
+    fn new(width: usize, height: usize, background: Color) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![background; width * height],
+        }
+    }
+
+    /// Returns the color of the pixel at `(x, y)`.
+    #[cfg(test)]
+    fn get(&self, x: usize, y: usize) -> Color {
+        self.pixels[y * self.width + x]
+    }
+
+    fn to_rgb(&self) -> Vec<u8> {
+        self.pixels.iter().flat_map(|c| [c.r, c.g, c.b]).collect()
+    }
+
+    fn blend(&mut self, x: i64, y: i64, color: Color, alpha: f64) {
+        if x < 0 || y < 0 || x as usize >= self.width || y as usize >= self.height {
+            return;
+        }
+        let idx = y as usize * self.width + x as usize;
+        self.pixels[idx] = self.pixels[idx].blend(color, alpha);
+    }
+
+    /// Fills the pixels whose centers lie inside `poly`, using the even-odd rule.
+    fn fill_polygon(&mut self, poly: &[[f64; 2]], color: Color, stipple: Stipple, alpha: f64) {
+        if stipple == Stipple::Hollow || poly.len() < 3 {
+            return;
+        }
+        let ymin = poly.iter().map(|p| p[1]).fold(f64::INFINITY, f64::min);
+        let ymax = poly.iter().map(|p| p[1]).fold(f64::NEG_INFINITY, f64::max);
+        let row_start = std::cmp::max(0, (ymin - 0.5).ceil() as i64);
+        let row_stop = std::cmp::min(self.height as i64, (ymax - 0.5).ceil() as i64);
+
+        let mut crossings = Vec::new();
+        for y in row_start..row_stop {
+            let yc = y as f64 + 0.5;
+            crossings.clear();
+            for (i, a) in poly.iter().enumerate() {
+                let b = poly[(i + 1) % poly.len()];
+                if (a[1] <= yc) != (b[1] <= yc) {
+                    crossings.push(a[0] + (yc - a[1]) / (b[1] - a[1]) * (b[0] - a[0]));
+                }
+            }
+            crossings.sort_by(f64::total_cmp);
+            for span in crossings.chunks_exact(2) {
+                let start = std::cmp::max(0, (span[0] - 0.5).ceil() as i64);
+                let stop = std::cmp::min(self.width as i64, (span[1] - 0.5).ceil() as i64);
+                for x in start..stop {
+                    if stipple.covers(x, y) {
+                        self.blend(x, y, color, alpha);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Draws the outline of `poly`, optionally dashed.
+    fn stroke_polygon(&mut self, poly: &[[f64; 2]], color: Color, dashed: bool) {
+        for (i, a) in poly.iter().enumerate() {
+            let b = poly[(i + 1) % poly.len()];
+            self.line(*a, b, color, dashed);
+        }
+    }
+
+    /// Draws a one pixel wide line using Bresenham's algorithm.
+    fn line(&mut self, a: [f64; 2], b: [f64; 2], color: Color, dashed: bool) {
+        // Clamp far-away endpoints so that huge shapes don't take forever to draw.
+        let limit = (self.width + self.height) as f64 * 4.;
+        let clamp = |v: f64| v.clamp(-limit, limit).floor() as i64;
+        let (mut x0, mut y0) = (clamp(a[0]), clamp(a[1]));
+        let (x1, y1) = (clamp(b[0]), clamp(b[1]));
+        let dx = (x1 - x0).abs();
+        let dy = -(y1 - y0).abs();
+        let sx = if x0 < x1 { 1 } else { -1 };
+        let sy = if y0 < y1 { 1 } else { -1 };
+        let mut err = dx + dy;
+        let mut step = 0;
+        loop {
+            if !dashed || step % 6 < 4 {
+                self.blend(x0, y0, color, 1.);
+            }
+            if x0 == x1 && y0 == y1 {
+                break;
+            }
+            let e2 = 2 * err;
+            if e2 >= dy {
+                err += dy;
+                x0 += sx;
+            }
+            if e2 <= dx {
+                err += dx;
+                y0 += sy;
+            }
+            step += 1;
+        }
+    }
+
+    /// Draws `text` with its upper left corner at `(x, y)`.
+    fn draw_text(&mut self, x: f64, y: f64, text: &str, scale: usize, color: Color) {
+        let (x, y) = (x.round() as i64, y.round() as i64);
+        let scale = scale as i64;
+        for (i, c) in text.chars().enumerate() {
+            let glyph = font::glyph(c);
+            let gx = x + (i * ADVANCE) as i64 * scale;
+            for (row, bits) in glyph.iter().enumerate() {
+                for col in 0..GLYPH_WIDTH {
+                    if bits & (1 << (GLYPH_WIDTH - 1 - col)) == 0 {
+                        continue;
+                    }
+                    for dy in 0..scale {
+                        for dx in 0..scale {
+                            self.blend(
+                                gx + col as i64 * scale + dx,
+                                y + row as i64 * scale + dy,
+                                color,
+                                1.,
+                            );
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layout::render::style::LayerStyle;
+    use crate::layout::render::{Outline, SceneLayer};
+
+    fn scene() -> Scene {
+        Scene {
+            width: 20,
+            height: 10,
+            background: Color::WHITE,
+            layers: vec![SceneLayer {
+                style: LayerStyle::new(Color::new(0xff, 0, 0), Stipple::Solid),
+                polys: vec![vec![[2., 2.], [8., 2.], [8., 8.], [2., 8.]]],
+            }],
+            outlines: vec![Outline {
+                rect: [10., 0., 19., 9.],
+                label: None,
+            }],
+            ports: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_draw() {
+        let canvas = draw(&scene());
+        let red = Color::new(0xff, 0, 0);
+        // Interior pixels are blended with the background.
+        assert_eq!(canvas.get(4, 4), Color::WHITE.blend(red, SOLID_OPACITY));
+        // Edges are drawn in the frame color.
+        assert_eq!(canvas.get(2, 5), red);
+        assert_eq!(canvas.get(0, 0), Color::WHITE);
+        assert_eq!(canvas.get(9, 5), Color::WHITE);
+        assert_eq!(canvas.get(10, 5), Color::BLACK);
+        assert_eq!(canvas.get(14, 0), Color::BLACK);
+        assert_eq!(canvas.get(14, 5), Color::WHITE);
+    }
+
+    #[test]
+    fn test_render_png() {
+        let png = render(&scene()).unwrap();
+        assert_eq!(&png[1..4], b"PNG");
+    }
+}
@@ -0,0 +1,315 @@
+//! Per-layer rendering styles.
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use arcstr::ArcStr;
+
+use super::RenderError;
+use crate::error::Result;
+use crate::layout::layers::selector::Selector;
+use crate::layout::layers::{GdsLayerSpec, LayerKey, LayerPurpose, LayersRef};
+
+/// An opaque RGB color.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+/// A fill pattern.
+///
+/// All patterns repeat every 4 pixels in each direction.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub enum Stipple {
+    /// A solid, semi-transparent fill.
+    #[default]
+    Solid,
+    /// No fill; only the outline of each shape is drawn.
+    Hollow,
+    /// A sparse grid of dots.
+    Dotted,
+    /// Diagonal lines running from the upper left to the lower right.
+    LeftHatch,
+    /// Diagonal lines running from the lower left to the upper right.
+    RightHatch,
+    /// Diagonal lines in both directions.
+    CrossHatch,
+}
+
+/// The style with which shapes on a layer are drawn.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct LayerStyle {
+    /// The layer's display name, if any.
+    pub name: Option<ArcStr>,
+    /// The color used to fill shapes.
+    pub fill: Color,
+    /// The color used to outline shapes.
+    pub frame: Color,
+    /// The pattern used to fill shapes.
+    pub stipple: Stipple,
+    /// Whether or not shapes on the layer are drawn.
+    pub visible: bool,
+}
+
+/// A collection of [`LayerStyle`]s, indexed by [`LayerKey`].
+///
+/// Layers without a style are drawn using a default palette.
+#[derive(Clone, Debug, Default)]
+pub struct LayerStyles {
+    styles: HashMap<LayerKey, LayerStyle>,
+}
+
+/// Colors used for layers that do not have a style.
+const PALETTE: [Color; 10] = [
+    Color::new(0x1f, 0x77, 0xb4),
+    Color::new(0xff, 0x7f, 0x0e),
+    Color::new(0x2c, 0xa0, 0x2c),
+    Color::new(0xd6, 0x27, 0x28),
+    Color::new(0x94, 0x67, 0xbd),
+    Color::new(0x8c, 0x56, 0x4b),
+    Color::new(0xe3, 0x77, 0xc2),
+    Color::new(0x7f, 0x7f, 0x7f),
+    Color::new(0xbc, 0xbd, 0x22),
+    Color::new(0x17, 0xbe, 0xcf),
+];
+
+const PALETTE_STIPPLES: [Stipple; 4] = [
+    Stipple::Solid,
+    Stipple::LeftHatch,
+    Stipple::RightHatch,
+    Stipple::CrossHatch,
+];
+
+impl Color {
+    pub const BLACK: Self = Self::new(0, 0, 0);
+    pub const WHITE: Self = Self::new(0xff, 0xff, 0xff);
+
+    /// Creates a new [`Color`] from its red, green, and blue components.
+    #[inline]
+    pub const fn new(r: u8, g: u8, b: u8) -> Self {
+        Self { r, g, b }
+    }
+
+    /// Parses a color of the form `#rrggbb`.
+    pub fn from_hex(s: &str) -> Option<Self> {
+        let s = s.trim().strip_prefix('#')?;
+        if s.len() != 6 || !s.is_ascii() {
+            return None;
+        }
+        let component = |i: usize| u8::from_str_radix(&s[i..i + 2], 16).ok();
+        Some(Self::new(component(0)?, component(2)?, component(4)?))
+    }
+
+    /// Formats the color as `#rrggbb`.
+    pub fn to_hex(&self) -> String {
+        format!("#{:02x}{:02x}{:02x}", self.r, self.g, self.b)
+    }
+
+    /// Blends `other` over `self`, weighting `other` by `alpha`.
+    pub(crate) fn blend(&self, other: Color, alpha: f64) -> Self {
+        let mix = |a: u8, b: u8| (a as f64 * (1. - alpha) + b as f64 * alpha).round() as u8;
+        Self::new(
+            mix(self.r, other.r),
+            mix(self.g, other.g),
+            mix(self.b, other.b),
+        )
+    }
+}
+
+impl Stipple {
+    /// Approximates a KLayout dither pattern such as `I5`.
+    ///
+    /// Unrecognized or custom patterns are treated as [`Stipple::Solid`].
+    pub fn from_dither(s: &str) -> Self {
+        let idx = s
+            .trim()
+            .strip_prefix('I')
+            .and_then(|s| s.parse::<u32>().ok());
+        match idx {
+            Some(1) => Self::Hollow,
+            Some(2..=3) => Self::Dotted,
+            Some(4..=7) => Self::LeftHatch,
+            Some(8..=11) => Self::RightHatch,
+            Some(12..=15) => Self::CrossHatch,
+            _ => Self::Solid,
+        }
+    }
+
+    /// Returns `true` if the pixel at `(x, y)` should be filled.
+    ///
+    /// Pixel coordinates increase to the right and downward.
+    pub fn covers(&self, x: i64, y: i64) -> bool {
+        let left = (x - y).rem_euclid(4) == 0;
+        let right = (x + y).rem_euclid(4) == 0;
+        match self {
+            Self::Solid => true,
+            Self::Hollow => false,
+            Self::Dotted => {
+                (x.rem_euclid(4) == 0 && y.rem_euclid(4) == 0)
+                    || (x.rem_euclid(4) == 2 && y.rem_euclid(4) == 2)
+            }
+            Self::LeftHatch => left,
+            Self::RightHatch => right,
+            Self::CrossHatch => left || right,
+        }
+    }
+}
+
+impl LayerStyle {
+    /// Creates a visible, unnamed style with the same fill and frame color.
+    pub fn new(color: Color, stipple: Stipple) -> Self {
+        Self {
+            name: None,
+            fill: color,
+            frame: color,
+            stipple,
+            visible: true,
+        }
+    }
+
+    /// The default style for the `n`-th unstyled layer.
+    pub(crate) fn palette(n: usize) -> Self {
+        Self::new(
+            PALETTE[n % PALETTE.len()],
+            PALETTE_STIPPLES[(n / PALETTE.len()) % PALETTE_STIPPLES.len()],
+        )
+    }
+}
+
+impl LayerStyles {
+    /// Creates an empty set of styles.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Loads styles from a KLayout layer properties (`.lyp`) file.
+    ///
+    /// See [`LayerStyles::from_lyp_str`].
+    pub fn from_lyp(path: impl AsRef<Path>, layers: &LayersRef) -> Result<Self> {
+        let text = std::fs::read_to_string(path)?;
+        Self::from_lyp_str(&text, layers)
+    }
+
+    /// Parses styles from the contents of a KLayout layer properties (`.lyp`) file.
+    ///
+    /// Each entry's `source` is matched against the GDS layer specifications in `layers`.
+    /// Entries that do not correspond to a known layer are ignored. If several entries map
+    /// to the same layer, the entry for the layer's drawing purpose takes precedence.
+    pub fn from_lyp_str(text: &str, layers: &LayersRef) -> Result<Self> {
+        let doc = roxmltree::Document::parse(text)
+            .map_err(|e| RenderError::LayerProperties(e.to_string()))?;
+
+        let mut styles = Self::new();
+        for props in doc.descendants().filter(|n| n.has_tag_name("properties")) {
+            let child = |tag: &str| {
+                props
+                    .children()
+                    .find(|c| c.has_tag_name(tag))
+                    .and_then(|c| c.text())
+                    .map(str::trim)
+            };
+
+            let spec = match child("source").and_then(parse_source) {
+                Some(spec) => spec,
+                None => continue,
+            };
+            let layer = match layers.get(Selector::Gds(spec)) {
+                Ok(layer) => layer,
+                Err(_) => continue,
+            };
+            let is_drawing = layers.info(layer)?.spec(&LayerPurpose::Drawing) == Some(spec);
+            if !is_drawing && styles.styles.contains_key(&layer) {
+                continue;
+            }
+
+            let fill = child("fill-color")
+                .and_then(Color::from_hex)
+                .unwrap_or_default();
+            let frame = child("frame-color")
+                .and_then(Color::from_hex)
+                .unwrap_or(fill);
+            styles.set(
+                layer,
+                LayerStyle {
+                    name: child("name").map(ArcStr::from),
+                    fill,
+                    frame,
+                    stipple: child("dither-pattern")
+                        .map(Stipple::from_dither)
+                        .unwrap_or_default(),
+                    visible: child("visible") != Some("false"),
+                },
+            );
+        }
+        Ok(styles)
+    }
+
+    /// Sets the style of the given layer.
+    pub fn set(&mut self, layer: LayerKey, style: LayerStyle) -> &mut Self {
+        self.styles.insert(layer, style);
+        self
+    }
+
+    /// Gets the style of the given layer, if one has been set.
+    #[inline]
+    pub fn get(&self, layer: LayerKey) -> Option<&LayerStyle> {
+        self.styles.get(&layer)
+    }
+}
+
+/// Parses a KLayout layer source of the form `layer/datatype@cellview`.
+///
+/// Wildcard sources return [`None`].
+pub(crate) fn parse_source(source: &str) -> Option<GdsLayerSpec> {
+    let spec = source.split('@').next()?;
+    let (layer, datatype) = spec.split_once('/')?;
+    Some(GdsLayerSpec(
+        layer.trim().parse().ok()?,
+        datatype.trim().parse().ok()?,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_colors() {
+        assert_eq!(Color::from_hex("#ff8000"), Some(Color::new(0xff, 0x80, 0)));
+        assert_eq!(Color::from_hex(" #0000FF "), Some(Color::new(0, 0, 0xff)));
+        assert_eq!(Color::from_hex("ff8000"), None);
+        assert_eq!(Color::from_hex("#ff80"), None);
+        assert_eq!(Color::new(0x12, 0xab, 0).to_hex(), "#12ab00");
+    }
+
+    #[test]
+    fn test_parse_source() {
+        assert_eq!(parse_source("68/20@1"), Some(GdsLayerSpec(68, 20)));
+        assert_eq!(parse_source("235/4"), Some(GdsLayerSpec(235, 4)));
+        assert_eq!(parse_source("*/*@*"), None);
+    }
+
+    #[test]
+    fn test_stipples() {
+        assert_eq!(Stipple::from_dither("I0"), Stipple::Solid);
+        assert_eq!(Stipple::from_dither("I1"), Stipple::Hollow);
+        assert_eq!(Stipple::from_dither("I9"), Stipple::RightHatch);
+        assert_eq!(Stipple::from_dither("C3"), Stipple::Solid);
+
+        let count = |s: Stipple| {
+            (0..4)
+                .flat_map(|x| (0..4).map(move |y| (x, y)))
+                .filter(|&(x, y)| s.covers(x, y))
+                .count()
+        };
+        assert_eq!(count(Stipple::Solid), 16);
+        assert_eq!(count(Stipple::Hollow), 0);
+        assert_eq!(count(Stipple::Dotted), 2);
+        assert_eq!(count(Stipple::LeftHatch), 4);
+        assert_eq!(count(Stipple::CrossHatch), 6);
+        assert!(Stipple::LeftHatch.covers(5, 1) && Stipple::LeftHatch.covers(-3, 1));
+    }
+}
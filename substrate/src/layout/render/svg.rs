@@ -0,0 +1,141 @@
+//! SVG output.
+
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use super::style::{Color, Stipple};
+use super::Scene;
+use crate::fmt::svg::{escape, write_text};
+
+/// The opacity of solid fills.
+const SOLID_OPACITY: f64 = 0.5;
+
+/// The font size of labels, in pixels.
+const FONT_SIZE: f64 = 10.;
+
+pub(crate) fn render(scene: &Scene) -> String {
+    let mut out = String::new();
+    writeln!(
+        out,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{0}" height="{1}" viewBox="0 0 {0} {1}">"#,
+        scene.width, scene.height
+    )
+    .unwrap();
+
+    // Each combination of stipple and color gets its own pattern.
+    let mut patterns = HashMap::new();
+    let mut defs = String::new();
+    for layer in scene.layers.iter() {
+        let style = &layer.style;
+        if matches!(style.stipple, Stipple::Solid | Stipple::Hollow) {
+            continue;
+        }
+        let id = patterns.len();
+        patterns
+            .entry((style.stipple, style.fill))
+            .or_insert_with(|| {
+                writeln!(
+                    defs,
+                    r#"<pattern id="p{id}" width="4" height="4" patternUnits="userSpaceOnUse">"#
+                )
+                .unwrap();
+                for y in 0..4 {
+                    for x in 0..4 {
+                        if style.stipple.covers(x, y) {
+                            writeln!(
+                                defs,
+                                r#"<rect x="{x}" y="{y}" width="1" height="1" fill="{}"/>"#,
+                                style.fill.to_hex()
+                            )
+                            .unwrap();
+                        }
+                    }
+                }
+                writeln!(defs, "</pattern>").unwrap();
+                id
+            });
+    }
+    if !defs.is_empty() {
+        write!(out, "<defs>\n{defs}</defs>\n").unwrap();
+    }
+
+    writeln!(
+        out,
+        r#"<rect width="100%" height="100%" fill="{}"/>"#,
+        scene.background.to_hex()
+    )
+    .unwrap();
+
+    for layer in scene.layers.iter() {
+        let style = &layer.style;
+        let fill = match style.stipple {
+            Stipple::Solid => format!(
+                r#"fill="{}" fill-opacity="{SOLID_OPACITY}""#,
+                style.fill.to_hex()
+            ),
+            Stipple::Hollow => r#"fill="none""#.to_string(),
+            stipple => format!(r#"fill="url(#p{})""#, patterns[&(stipple, style.fill)]),
+        };
+        match &style.name {
+            Some(name) => writeln!(out, r#"<g data-layer="{}">"#, escape(name)).unwrap(),
+            None => writeln!(out, "<g>").unwrap(),
+        }
+        for poly in layer.polys.iter() {
+            writeln!(
+                out,
+                r#"<path d="{}" {fill} stroke="{}" stroke-width="1"/>"#,
+                path_data(poly),
+                style.frame.to_hex()
+            )
+            .unwrap();
+        }
+        writeln!(out, "</g>").unwrap();
+    }
+
+    for outline in scene.outlines.iter() {
+        let [x0, y0, x1, y1] = outline.rect;
+        writeln!(
+            out,
+            r#"<rect x="{x0:.2}" y="{y0:.2}" width="{:.2}" height="{:.2}" fill="none" stroke="{}" stroke-width="1"/>"#,
+            x1 - x0,
+            y1 - y0,
+            Color::BLACK.to_hex()
+        )
+        .unwrap();
+        if let Some(label) = &outline.label {
+            write_text(
+                &mut out,
+                [x0 + 2., y0 + FONT_SIZE + 2.],
+                label,
+                "start",
+                FONT_SIZE,
+            );
+        }
+    }
+
+    for port in scene.ports.iter() {
+        for poly in port.polys.iter() {
+            writeln!(
+                out,
+                r#"<path d="{}" fill="none" stroke="{}" stroke-width="1" stroke-dasharray="4 2"/>"#,
+                path_data(poly),
+                Color::BLACK.to_hex()
+            )
+            .unwrap();
+        }
+        write_text(&mut out, port.center, &port.name, "middle", FONT_SIZE);
+    }
+
+    writeln!(out, "</svg>").unwrap();
+    out
+}
+
+fn path_data(poly: &[[f64; 2]]) -> String {
+    let mut d = String::new();
+    for (i, [x, y]) in poly.iter().enumerate() {
+        let cmd = if i == 0 { 'M' } else { 'L' };
+        write!(d, "{cmd}{x:.2} {y:.2} ").unwrap();
+    }
+    d.push('Z');
+    d
+}
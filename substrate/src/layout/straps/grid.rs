@@ -0,0 +1,494 @@
+//! Automatic power grid generation.
+//!
+//! A [`PowerGrid`] draws power straps on a stack of metal layers within a
+//! rectangular region. Straps on adjacent layers are stitched together with
+//! via arrays wherever two straps of the same net cross, and the lowest strap
+//! layers are connected to any target rails (such as standard cell power rails)
+//! on the metal layer immediately below them.
+//!
+//! The generated straps are returned as a [`PlacedGrid`], which exposes one
+//! [`CellPort`] per net. Like the rest of this module, the grid is generic
+//! over the net type `N`, so custom net enumerations can be used in place of
+//! [`SingleSupplyNet`].
+use std::collections::HashMap;
+use std::fmt::Display;
+
+use subgeom::bbox::BoundBox;
+use subgeom::transform::Translate;
+use subgeom::{Dims, Rect, Span};
+
+use super::{LayerStraps, PowerStrapError, SingleSupplyNet, StrapConfig};
+use crate::error::{Result, SubstrateError};
+use crate::layout::cell::{CellPort, Instance};
+use crate::layout::context::LayoutCtx;
+use crate::layout::elements::via::{Via, ViaParams};
+use crate::layout::layers::LayerKey;
+
+/// A rail that should be connected to a [`PowerGrid`].
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct GridTarget<N = SingleSupplyNet> {
+    layer: LayerKey,
+    rect: Rect,
+    net: N,
+}
+
+/// A strap segment drawn by a [`PowerGrid`].
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct GridStrap<N = SingleSupplyNet> {
+    /// The layer on which the strap is drawn.
+    pub layer: LayerKey,
+    /// The geometry of the strap.
+    pub rect: Rect,
+    /// The net to which the strap belongs.
+    pub net: N,
+}
+
+/// A via array drawn by a [`PowerGrid`].
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct GridVia<N = SingleSupplyNet> {
+    /// The lower metal layer.
+    pub bot: LayerKey,
+    /// The upper metal layer.
+    pub top: LayerKey,
+    /// The overlap of the connected shapes.
+    pub rect: Rect,
+    /// The net to which the via belongs.
+    pub net: N,
+    /// The layer containing the via cuts, if any cuts were drawn.
+    pub cut_layer: Option<LayerKey>,
+    /// The number of cuts in the via array.
+    pub cuts: usize,
+}
+
+/// A power grid generator.
+///
+/// Layers of straps are added with [`PowerGrid::add_layer`], and are drawn in order
+/// of their layer index by [`PowerGrid::draw`].
+#[derive(Clone, Debug)]
+pub struct PowerGrid<N = SingleSupplyNet> {
+    region: Rect,
+    layers: Vec<LayerStraps<N>>,
+    blockages: HashMap<LayerKey, Vec<Rect>>,
+    targets: Vec<GridTarget<N>>,
+}
+
+/// The result of drawing a [`PowerGrid`].
+#[derive(Clone, Debug)]
+pub struct PlacedGrid<N = SingleSupplyNet> {
+    straps: Vec<GridStrap<N>>,
+    vias: Vec<GridVia<N>>,
+    ports: Vec<(N, CellPort)>,
+    unconnected_targets: Vec<GridTarget<N>>,
+}
+
+impl<N> GridTarget<N> {
+    /// Creates a new [`GridTarget`] on the given layer.
+    pub fn new(layer: LayerKey, net: N, rect: impl Into<Rect>) -> Self {
+        Self {
+            layer,
+            rect: rect.into(),
+            net,
+        }
+    }
+
+    #[inline]
+    pub fn layer(&self) -> LayerKey {
+        self.layer
+    }
+
+    #[inline]
+    pub fn rect(&self) -> Rect {
+        self.rect
+    }
+
+    #[inline]
+    pub fn net(&self) -> &N {
+        &self.net
+    }
+}
+
+impl<N> PowerGrid<N>
+where
+    N: Clone + Eq + Display,
+{
+    /// Creates a new, empty power grid covering `region`.
+    ///
+    /// Strap offsets are measured from the lower left corner of `region`.
+    pub fn new(region: Rect) -> Self {
+        Self {
+            region,
+            layers: Vec::new(),
+            blockages: HashMap::new(),
+            targets: Vec::new(),
+        }
+    }
+
+    /// Creates a power grid covering `region` using the layers of a macro's [`StrapConfig`].
+    pub fn from_strap_config(region: Rect, config: &StrapConfig<N>) -> Self {
+        let mut grid = Self::new(region);
+        grid.add_layer(config.top.clone());
+        if let Some(ref above_top) = config.above_top {
+            grid.add_layer(above_top.clone());
+        }
+        grid
+    }
+
+    /// Adds a layer of straps to the grid.
+    ///
+    /// Layers may be added in any order.
+    pub fn add_layer(&mut self, straps: LayerStraps<N>) -> &mut Self {
+        self.layers.push(straps);
+        self
+    }
+
+    /// Prevents straps from being drawn over the given rectangle on `layer`.
+    ///
+    /// Straps that cross a blockage are split into segments on either side of it.
+    pub fn add_blockage(&mut self, layer: LayerKey, rect: impl Into<Rect>) -> &mut Self {
+        self.blockages
+            .entry(layer)
+            .or_insert_with(|| Vec::with_capacity(1))
+            .push(rect.into());
+        self
+    }
+
+    /// Adds a rail that should be connected to straps of the same net.
+    ///
+    /// The target must either lie on one of the grid's strap layers,
+    /// or on the metal layer immediately below one of the grid's strap layers.
+    pub fn add_target(&mut self, target: GridTarget<N>) -> &mut Self {
+        self.targets.push(target);
+        self
+    }
+
+    /// Draws the power grid in the given layout context.
+    ///
+    /// Does not add any ports to the context; use [`PlacedGrid::ports`]
+    /// to obtain ports for the generated nets.
+    pub fn draw(&self, ctx: &mut LayoutCtx) -> Result<PlacedGrid<N>> {
+        if self.layers.is_empty() {
+            return Err(SubstrateError::new(PowerStrapError::NoStrapLayers));
+        }
+
+        let mut layers = self.layers.iter().collect::<Vec<_>>();
+        layers.sort_by_key(|l| l.index);
+        for pair in layers.windows(2) {
+            if pair[0].index == pair[1].index {
+                return Err(SubstrateError::new(PowerStrapError::DuplicateMetalLayers(
+                    pair[0].index,
+                )));
+            }
+        }
+
+        let segments = layers
+            .iter()
+            .map(|layer| self.segments(layer))
+            .collect::<Vec<_>>();
+
+        let mut vias = ViaCache::new();
+
+        for i in 0..layers.len().saturating_sub(1) {
+            let (bot, top) = (layers[i], layers[i + 1]);
+            if bot.dir == top.dir || top.index != bot.index + 1 {
+                continue;
+            }
+            for b in segments[i].iter() {
+                for t in segments[i + 1].iter().filter(|t| t.net == b.net) {
+                    if let Some(overlap) = overlap(b.rect, t.rect) {
+                        vias.draw(ctx, bot.layer, top.layer, overlap, &b.net)?;
+                    }
+                }
+            }
+        }
+
+        let mut unconnected_targets = Vec::new();
+        let metal_layers = ctx.layers();
+        for target in self.targets.iter() {
+            if let Some(i) = layers.iter().position(|l| l.layer == target.layer) {
+                // Targets on a strap layer connect to the straps they overlap.
+                let hit = segments[i]
+                    .iter()
+                    .any(|s| s.net == target.net && overlap(target.rect, s.rect).is_some());
+                if !hit {
+                    unconnected_targets.push(target.clone());
+                }
+                continue;
+            }
+            let target_idx = metal_layers.which_metal(target.layer)?;
+            let (i, layer) = layers
+                .iter()
+                .enumerate()
+                .find(|(_, l)| l.index == target_idx + 1)
+                .ok_or_else(|| {
+                    PowerStrapError::UnreachableTarget(
+                        metal_layers
+                            .name(target.layer)
+                            .map(|n| n.to_string())
+                            .unwrap_or_default(),
+                    )
+                })?;
+
+            let mut hit = false;
+            for s in segments[i].iter().filter(|s| s.net == target.net) {
+                if let Some(overlap) = overlap(target.rect, s.rect) {
+                    vias.draw(ctx, target.layer, layer.layer, overlap, &target.net)?;
+                    hit = true;
+                }
+            }
+            if !hit {
+                unconnected_targets.push(target.clone());
+            }
+        }
+
+        let mut ports: Vec<(N, CellPort)> = Vec::new();
+        let mut straps = Vec::new();
+        for (layer, segments) in layers.iter().zip(segments) {
+            for segment in segments {
+                ctx.draw_rect(layer.layer, segment.rect);
+
+                let idx = match ports.iter().position(|(n, _)| *n == segment.net) {
+                    Some(idx) => idx,
+                    None => {
+                        ports.push((segment.net.clone(), CellPort::new(segment.net.to_string())));
+                        ports.len() - 1
+                    }
+                };
+                ports[idx].1.add(layer.layer, segment.rect.into());
+
+                straps.push(GridStrap {
+                    layer: layer.layer,
+                    rect: segment.rect,
+                    net: segment.net,
+                });
+            }
+        }
+
+        Ok(PlacedGrid {
+            straps,
+            vias: vias.placed,
+            ports,
+            unconnected_targets,
+        })
+    }
+
+    /// Computes the unblocked strap segments on the given layer.
+    fn segments(&self, layer: &LayerStraps<N>) -> Vec<Segment<N>> {
+        let dir = layer.dir;
+        let base = self.region.span(!dir).start();
+        let length = self.region.length(!dir);
+        let blockages = self
+            .blockages
+            .get(&layer.layer)
+            .map(|b| b.as_slice())
+            .unwrap_or_default();
+
+        let mut out = Vec::new();
+        for strap in layer.straps_until(length) {
+            if strap.span.stop() > length {
+                continue;
+            }
+            let track = strap.span.translate(base);
+
+            let mut spans = vec![self.region.span(dir)];
+            for blockage in blockages
+                .iter()
+                .filter(|b| strictly_intersects(b.span(!dir), track))
+            {
+                spans = spans
+                    .into_iter()
+                    .flat_map(|s| subtract(s, blockage.span(dir)))
+                    .collect();
+            }
+
+            out.extend(spans.into_iter().map(|span| {
+                Segment {
+                    rect: Rect::span_builder()
+                        .with(dir, span)
+                        .with(!dir, track)
+                        .build(),
+                    net: strap.net.clone(),
+                }
+            }));
+        }
+        out
+    }
+}
+
+impl<N> PlacedGrid<N> {
+    /// All strap segments drawn by the grid.
+    #[inline]
+    pub fn straps(&self) -> &[GridStrap<N>] {
+        &self.straps
+    }
+
+    /// Iterates over the strap segments drawn on the given layer.
+    pub fn straps_on(&self, layer: LayerKey) -> impl Iterator<Item = &GridStrap<N>> {
+        self.straps.iter().filter(move |s| s.layer == layer)
+    }
+
+    /// One port per net, named according to the net's [`Display`] implementation.
+    ///
+    /// Each port contains all strap segments on its net.
+    pub fn ports(&self) -> impl Iterator<Item = &CellPort> {
+        self.ports.iter().map(|(_, port)| port)
+    }
+
+    /// Consumes the grid, returning its ports.
+    pub fn into_ports(self) -> impl Iterator<Item = CellPort> {
+        self.ports.into_iter().map(|(_, port)| port)
+    }
+
+    /// Targets that did not overlap any strap of the same net.
+    #[inline]
+    pub fn unconnected_targets(&self) -> &[GridTarget<N>] {
+        &self.unconnected_targets
+    }
+
+    /// All via arrays drawn by the grid, including vias to targets.
+    #[inline]
+    pub fn vias(&self) -> &[GridVia<N>] {
+        &self.vias
+    }
+
+    /// The number of via arrays drawn, including vias to targets.
+    #[inline]
+    pub fn num_vias(&self) -> usize {
+        self.vias.len()
+    }
+}
+
+impl<N: PartialEq> PlacedGrid<N> {
+    /// Returns the port corresponding to the given net, if the grid contains straps on that net.
+    pub fn port(&self, net: &N) -> Option<&CellPort> {
+        self.ports
+            .iter()
+            .find_map(|(n, port)| if n == net { Some(port) } else { None })
+    }
+}
+
+struct Segment<N> {
+    rect: Rect,
+    net: N,
+}
+
+/// Caches vias by overlap dimensions, since identical vias are very common in power grids.
+struct ViaCache<N> {
+    vias: HashMap<(LayerKey, LayerKey, Dims), CachedVia>,
+    placed: Vec<GridVia<N>>,
+}
+
+struct CachedVia {
+    inst: Instance,
+    base: Rect,
+    cut_layer: Option<LayerKey>,
+    cuts: usize,
+}
+
+impl<N: Clone> ViaCache<N> {
+    fn new() -> Self {
+        Self {
+            vias: HashMap::new(),
+            placed: Vec::new(),
+        }
+    }
+
+    fn draw(
+        &mut self,
+        ctx: &mut LayoutCtx,
+        bot: LayerKey,
+        top: LayerKey,
+        overlap: Rect,
+        net: &N,
+    ) -> Result<()> {
+        let key = (bot, top, overlap.dims());
+        if !self.vias.contains_key(&key) {
+            let viap = ViaParams::builder()
+                .layers(bot, top)
+                .geometry(overlap, overlap)
+                .build();
+            let inst = ctx.instantiate::<Via>(&viap)?;
+            let cuts = inst
+                .cell()
+                .elems()
+                .filter(|e| e.layer.layer() != bot && e.layer.layer() != top)
+                .map(|e| e.layer.layer())
+                .collect::<Vec<_>>();
+            self.vias.insert(
+                key,
+                CachedVia {
+                    inst,
+                    base: overlap,
+                    cut_layer: cuts.first().copied(),
+                    cuts: cuts.len(),
+                },
+            );
+        }
+
+        let cached = &self.vias[&key];
+        let mut via = cached.inst.clone();
+        via.translate(overlap.p0 - cached.base.p0);
+        ctx.draw(via)?;
+
+        self.placed.push(GridVia {
+            bot,
+            top,
+            rect: overlap,
+            net: net.clone(),
+            cut_layer: cached.cut_layer,
+            cuts: cached.cuts,
+        });
+        Ok(())
+    }
+}
+
+/// Returns the overlap of two rectangles, if it has non-zero area.
+fn overlap(a: Rect, b: Rect) -> Option<Rect> {
+    let bbox = a.intersection(b.bbox());
+    if bbox.is_empty() {
+        return None;
+    }
+    let rect = bbox.into_rect();
+    if rect.width() > 0 && rect.height() > 0 {
+        Some(rect)
+    } else {
+        None
+    }
+}
+
+/// Returns `true` if the spans share more than a single point.
+#[inline]
+fn strictly_intersects(a: Span, b: Span) -> bool {
+    a.start() < b.stop() && b.start() < a.stop()
+}
+
+/// Removes `cut` from `span`, returning the non-empty remaining pieces.
+fn subtract(span: Span, cut: Span) -> Vec<Span> {
+    if !strictly_intersects(span, cut) {
+        return vec![span];
+    }
+    let mut out = Vec::with_capacity(2);
+    if span.start() < cut.start() {
+        out.push(Span::new(span.start(), cut.start()));
+    }
+    if cut.stop() < span.stop() {
+        out.push(Span::new(cut.stop(), span.stop()));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn subtract_spans() {
+        let span = Span::new(0, 100);
+        assert_eq!(
+            subtract(span, Span::new(20, 40)),
+            vec![Span::new(0, 20), Span::new(40, 100)]
+        );
+        assert_eq!(subtract(span, Span::new(-10, 40)), vec![Span::new(40, 100)]);
+        assert_eq!(subtract(span, Span::new(100, 140)), vec![span]);
+        assert!(subtract(span, Span::new(-10, 140)).is_empty());
+    }
+}
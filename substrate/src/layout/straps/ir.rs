@@ -0,0 +1,800 @@
+//! Static IR drop estimation for power grids.
+//!
+//! An [`IrDropAnalysis`] models a single supply net as a resistor network.
+//! Each metal shape is divided into segments along its longer dimension,
+//! with nodes wherever a via, supply, or current tap attaches to it. The
+//! resistance of each segment is computed from the sheet resistance of its
+//! layer, as reported by [`Pdk::sheet_resistance`](crate::pdk::Pdk::sheet_resistance).
+//! Via arrays contribute one resistor per array, whose resistance is the
+//! single-cut resistance from [`Pdk::via_resistance`](crate::pdk::Pdk::via_resistance)
+//! divided by the number of cuts.
+//!
+//! Supplies are ideal: the voltage at each supply point is held fixed. Current
+//! taps draw a constant DC current from the network. The resulting sparse
+//! linear system is solved iteratively, producing the voltage drop relative to
+//! the supply at every node in the network.
+//!
+//! Drops are reported as positive values, regardless of whether the analyzed net
+//! is a power or a ground net.
+use std::collections::{HashMap, VecDeque};
+
+use arcstr::ArcStr;
+use subgeom::{Dir, Point, Rect};
+use thiserror::Error;
+
+use super::grid::PlacedGrid;
+use crate::data::SubstrateCtx;
+use crate::error::{Result, SubstrateError};
+use crate::layout::cell::TextElement;
+use crate::layout::group::Group;
+use crate::layout::layers::{LayerKey, LayerSpec};
+
+/// The default relative tolerance of the iterative solver.
+pub const DEFAULT_TOLERANCE: f64 = 1e-9;
+
+/// The default maximum number of solver iterations.
+pub const DEFAULT_MAX_ITERS: usize = 100_000;
+
+/// A static IR drop analysis of a single supply net.
+///
+/// Metal shapes, vias, supplies and current taps are added individually,
+/// or extracted from a drawn grid using [`IrDropAnalysis::from_grid`].
+#[derive(Clone, Debug)]
+pub struct IrDropAnalysis {
+    metals: Vec<(LayerKey, Rect)>,
+    vias: Vec<ViaConn>,
+    supplies: Vec<(LayerKey, Point)>,
+    taps: Vec<CurrentTap>,
+    tolerance: f64,
+    max_iters: usize,
+}
+
+#[derive(Copy, Clone, Debug)]
+struct ViaConn {
+    bot: LayerKey,
+    top: LayerKey,
+    rect: Rect,
+    cut_layer: LayerKey,
+    cuts: usize,
+}
+
+#[derive(Copy, Clone, Debug)]
+struct CurrentTap {
+    layer: LayerKey,
+    point: Point,
+    current: f64,
+}
+
+/// The voltage drop along a single metal shape.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct StrapDrop {
+    /// The layer on which the shape is drawn.
+    pub layer: LayerKey,
+    /// The geometry of the shape.
+    pub rect: Rect,
+    /// The largest voltage drop anywhere along the shape, in volts.
+    pub max_drop: f64,
+    /// The maximum drop, normalized to the worst drop in the network.
+    ///
+    /// Ranges from 0 (no drop) to 1 (the worst drop in the network).
+    pub heat: f64,
+}
+
+/// The results of an [`IrDropAnalysis`].
+#[derive(Clone, Debug)]
+pub struct IrDropReport {
+    straps: Vec<StrapDrop>,
+    worst_drop: f64,
+    worst_location: Option<(LayerKey, Point)>,
+}
+
+/// An error encountered while performing IR drop analysis.
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum IrDropError {
+    #[error("the PDK does not specify a sheet resistance for layer {0}")]
+    MissingSheetResistance(ArcStr),
+
+    #[error("the PDK does not specify a via resistance for layer {0}")]
+    MissingViaResistance(ArcStr),
+
+    #[error("point {point:?} on layer {layer} does not lie on any metal shape")]
+    PointNotOnNet { layer: ArcStr, point: Point },
+
+    #[error("IR drop analysis requires at least one supply")]
+    NoSupplies,
+
+    #[error("current tap at {0:?} is not connected to any supply")]
+    FloatingTap(Point),
+
+    #[error("IR drop solver did not converge after {0} iterations")]
+    DidNotConverge(usize),
+}
+
+impl Default for IrDropAnalysis {
+    fn default() -> Self {
+        Self {
+            metals: Vec::new(),
+            vias: Vec::new(),
+            supplies: Vec::new(),
+            taps: Vec::new(),
+            tolerance: DEFAULT_TOLERANCE,
+            max_iters: DEFAULT_MAX_ITERS,
+        }
+    }
+}
+
+impl IrDropAnalysis {
+    /// Creates a new, empty IR drop analysis.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates an IR drop analysis containing the straps and vias of `net` in a [`PlacedGrid`].
+    ///
+    /// Vias to grid targets are included, but the targets themselves are not;
+    /// target rails should be added using [`IrDropAnalysis::add_metal`].
+    pub fn from_grid<N: PartialEq>(grid: &PlacedGrid<N>, net: &N) -> Self {
+        let mut analysis = Self::new();
+        for strap in grid.straps().iter().filter(|s| s.net == *net) {
+            analysis.add_metal(strap.layer, strap.rect);
+        }
+        for via in grid.vias().iter().filter(|v| v.net == *net) {
+            if let Some(cut_layer) = via.cut_layer {
+                analysis.add_via(via.bot, via.top, via.rect, cut_layer, via.cuts);
+            }
+        }
+        analysis
+    }
+
+    /// Adds a metal shape to the network.
+    ///
+    /// Overlapping shapes on the same layer are treated as electrically connected.
+    pub fn add_metal(&mut self, layer: LayerKey, rect: impl Into<Rect>) -> &mut Self {
+        self.metals.push((layer, rect.into()));
+        self
+    }
+
+    /// Adds a via array connecting layers `bot` and `top` over the given rectangle.
+    pub fn add_via(
+        &mut self,
+        bot: LayerKey,
+        top: LayerKey,
+        rect: impl Into<Rect>,
+        cut_layer: LayerKey,
+        cuts: usize,
+    ) -> &mut Self {
+        self.vias.push(ViaConn {
+            bot,
+            top,
+            rect: rect.into(),
+            cut_layer,
+            cuts,
+        });
+        self
+    }
+
+    /// Adds an ideal supply at the given point.
+    pub fn add_supply(&mut self, layer: LayerKey, point: impl Into<Point>) -> &mut Self {
+        self.supplies.push((layer, point.into()));
+        self
+    }
+
+    /// Adds a tap drawing `current` amps from the network at the given point.
+    pub fn add_tap(&mut self, layer: LayerKey, point: impl Into<Point>, current: f64) -> &mut Self {
+        self.taps.push(CurrentTap {
+            layer,
+            point: point.into(),
+            current,
+        });
+        self
+    }
+
+    /// Sets the relative tolerance of the iterative solver.
+    ///
+    /// Defaults to [`DEFAULT_TOLERANCE`].
+    pub fn tolerance(&mut self, tolerance: f64) -> &mut Self {
+        self.tolerance = tolerance;
+        self
+    }
+
+    /// Sets the maximum number of solver iterations.
+    ///
+    /// Defaults to [`DEFAULT_MAX_ITERS`].
+    pub fn max_iters(&mut self, max_iters: usize) -> &mut Self {
+        self.max_iters = max_iters;
+        self
+    }
+
+    /// Solves for the voltage drop at every node in the network.
+    pub fn solve(&self, ctx: &SubstrateCtx) -> Result<IrDropReport> {
+        if self.supplies.is_empty() {
+            return Err(SubstrateError::new(IrDropError::NoSupplies));
+        }
+
+        let layers = ctx.layers();
+        let pdk = ctx.pdk();
+
+        let mut sheet_res = HashMap::new();
+        for &(layer, _) in self.metals.iter() {
+            if sheet_res.contains_key(&layer) {
+                continue;
+            }
+            let info = layers.info(layer)?;
+            let res = pdk
+                .sheet_resistance(&info)
+                .ok_or_else(|| IrDropError::MissingSheetResistance(info.name.clone()))?;
+            sheet_res.insert(layer, res);
+        }
+
+        // Nodes are identified by (shape index, position along the shape's longer dimension).
+        let mut positions = self
+            .metals
+            .iter()
+            .map(|(_, rect)| {
+                let span = rect.span(rect.longer_dir());
+                vec![span.start(), span.stop()]
+            })
+            .collect::<Vec<_>>();
+
+        let index = ShapeIndex::new(&self.metals);
+        let locate = |layer: LayerKey, point: Point| -> Result<Vec<usize>> {
+            let shapes = index.containing(layer, point);
+            if shapes.is_empty() {
+                return Err(SubstrateError::new(IrDropError::PointNotOnNet {
+                    layer: layers.name(layer)?,
+                    point,
+                }));
+            }
+            Ok(shapes)
+        };
+
+        let mut via_shapes = Vec::with_capacity(self.vias.len());
+        for via in self.vias.iter() {
+            let center = via.rect.center();
+            let bot = locate(via.bot, center)?;
+            let top = locate(via.top, center)?;
+            for &i in bot.iter().chain(top.iter()) {
+                positions[i].push(along(self.metals[i].1, center));
+            }
+            via_shapes.push((bot, top));
+        }
+
+        let mut supply_shapes = Vec::with_capacity(self.supplies.len());
+        for &(layer, point) in self.supplies.iter() {
+            let shapes = locate(layer, point)?;
+            for &i in shapes.iter() {
+                positions[i].push(along(self.metals[i].1, point));
+            }
+            supply_shapes.push(shapes);
+        }
+
+        let mut tap_shapes = Vec::with_capacity(self.taps.len());
+        for tap in self.taps.iter() {
+            let shapes = locate(tap.layer, tap.point)?;
+            for &i in shapes.iter() {
+                positions[i].push(along(self.metals[i].1, tap.point));
+            }
+            tap_shapes.push(shapes);
+        }
+
+        // Overlapping shapes on the same layer are shorted at the center of their overlap.
+        let shorts = index.overlaps();
+        for &(i, j, center) in shorts.iter() {
+            positions[i].push(along(self.metals[i].1, center));
+            positions[j].push(along(self.metals[j].1, center));
+        }
+
+        let mut offsets = Vec::with_capacity(positions.len());
+        let mut num_nodes = 0;
+        for pos in positions.iter_mut() {
+            pos.sort_unstable();
+            pos.dedup();
+            offsets.push(num_nodes);
+            num_nodes += pos.len();
+        }
+        let node = |shape: usize, point: Point| -> usize {
+            let pos = along(self.metals[shape].1, point);
+            offsets[shape] + positions[shape].binary_search(&pos).unwrap()
+        };
+
+        let mut uf = UnionFind::new(num_nodes);
+        for &(i, j, center) in shorts.iter() {
+            uf.union(node(i, center), node(j, center));
+        }
+        for (shapes, &(_, point)) in supply_shapes.iter().zip(self.supplies.iter()) {
+            for &i in shapes.iter().skip(1) {
+                uf.union(node(shapes[0], point), node(i, point));
+            }
+        }
+        for (shapes, tap) in tap_shapes.iter().zip(self.taps.iter()) {
+            for &i in shapes.iter().skip(1) {
+                uf.union(node(shapes[0], tap.point), node(i, tap.point));
+            }
+        }
+
+        let mut conductances: Vec<(usize, usize, f64)> = Vec::new();
+        for (i, &(layer, rect)) in self.metals.iter().enumerate() {
+            let dir = rect.longer_dir();
+            let width = rect.length(!dir) as f64;
+            let rs = sheet_res[&layer];
+            for (k, pair) in positions[i].windows(2).enumerate() {
+                let length = (pair[1] - pair[0]) as f64;
+                let (a, b) = (offsets[i] + k, offsets[i] + k + 1);
+                if length == 0.0 {
+                    uf.union(a, b);
+                } else {
+                    conductances.push((a, b, width / (rs * length)));
+                }
+            }
+        }
+
+        let mut via_res = HashMap::new();
+        for (via, (bot, top)) in self.vias.iter().zip(via_shapes.iter()) {
+            if via.cuts == 0 {
+                continue;
+            }
+            let res = match via_res.get(&via.cut_layer) {
+                Some(&res) => res,
+                None => {
+                    let info = layers.info(via.cut_layer)?;
+                    let res = pdk
+                        .via_resistance(&info)
+                        .ok_or_else(|| IrDropError::MissingViaResistance(info.name.clone()))?;
+                    via_res.insert(via.cut_layer, res);
+                    res
+                }
+            };
+            let center = via.rect.center();
+            let g = via.cuts as f64 / res;
+            // Parallel shapes on the same layer are already shorted together.
+            conductances.push((node(bot[0], center), node(top[0], center), g));
+            for &i in bot.iter().skip(1) {
+                uf.union(node(bot[0], center), node(i, center));
+            }
+            for &i in top.iter().skip(1) {
+                uf.union(node(top[0], center), node(i, center));
+            }
+        }
+
+        // Compact merged nodes.
+        let mut compact = vec![usize::MAX; num_nodes];
+        let mut roots = HashMap::new();
+        for (n, slot) in compact.iter_mut().enumerate() {
+            let root = uf.find(n);
+            let len = roots.len();
+            *slot = *roots.entry(root).or_insert(len);
+        }
+        let num_nets = roots.len();
+
+        let mut adj: Vec<Vec<(usize, f64)>> = vec![Vec::new(); num_nets];
+        for &(a, b, g) in conductances.iter() {
+            let (a, b) = (compact[a], compact[b]);
+            if a == b {
+                continue;
+            }
+            adj[a].push((b, g));
+            adj[b].push((a, g));
+        }
+
+        let mut fixed = vec![false; num_nets];
+        for (shapes, &(_, point)) in supply_shapes.iter().zip(self.supplies.iter()) {
+            fixed[compact[node(shapes[0], point)]] = true;
+        }
+
+        // Only nodes connected to a supply participate in the solve.
+        let mut reachable = fixed.clone();
+        let mut queue = (0..num_nets).filter(|&n| fixed[n]).collect::<VecDeque<_>>();
+        while let Some(n) = queue.pop_front() {
+            for &(m, _) in adj[n].iter() {
+                if !reachable[m] {
+                    reachable[m] = true;
+                    queue.push_back(m);
+                }
+            }
+        }
+
+        let mut current = vec![0f64; num_nets];
+        for (shapes, tap) in tap_shapes.iter().zip(self.taps.iter()) {
+            let n = compact[node(shapes[0], tap.point)];
+            if !reachable[n] {
+                return Err(SubstrateError::new(IrDropError::FloatingTap(tap.point)));
+            }
+            current[n] += tap.current;
+        }
+
+        let mut unknowns = vec![usize::MAX; num_nets];
+        let mut num_unknowns = 0;
+        for n in 0..num_nets {
+            if reachable[n] && !fixed[n] {
+                unknowns[n] = num_unknowns;
+                num_unknowns += 1;
+            }
+        }
+
+        let mut matrix = SparseMatrix::new(num_unknowns);
+        let mut rhs = vec![0f64; num_unknowns];
+        for n in 0..num_nets {
+            let row = unknowns[n];
+            if row == usize::MAX {
+                continue;
+            }
+            rhs[row] = current[n];
+            for &(m, g) in adj[n].iter() {
+                matrix.diag[row] += g;
+                // Fixed nodes have zero drop, so they do not contribute to the right hand side.
+                if unknowns[m] != usize::MAX {
+                    matrix.rows[row].push((unknowns[m], -g));
+                }
+            }
+        }
+
+        let solution = matrix.solve_cg(&rhs, self.tolerance, self.max_iters)?;
+        let drop_at = |n: usize| -> Option<f64> {
+            let n = compact[n];
+            if fixed[n] {
+                Some(0.)
+            } else if unknowns[n] != usize::MAX {
+                Some(solution[unknowns[n]])
+            } else {
+                None
+            }
+        };
+
+        let mut straps = Vec::with_capacity(self.metals.len());
+        let mut worst_drop = 0f64;
+        let mut worst_location = None;
+        for (i, &(layer, rect)) in self.metals.iter().enumerate() {
+            let dir = rect.longer_dir();
+            let mut max_drop = None;
+            for (k, &pos) in positions[i].iter().enumerate() {
+                let drop = match drop_at(offsets[i] + k) {
+                    Some(drop) => drop,
+                    None => continue,
+                };
+                if max_drop.map(|d| drop > d).unwrap_or(true) {
+                    max_drop = Some(drop);
+                }
+                if drop > worst_drop || worst_location.is_none() {
+                    worst_drop = worst_drop.max(drop);
+                    let point = Point::from_dir_coords(dir, pos, rect.span(!dir).center());
+                    worst_location = Some((layer, point));
+                }
+            }
+            if let Some(max_drop) = max_drop {
+                straps.push(StrapDrop {
+                    layer,
+                    rect,
+                    max_drop,
+                    heat: 0.,
+                });
+            }
+        }
+
+        if worst_drop > 0. {
+            for strap in straps.iter_mut() {
+                strap.heat = strap.max_drop / worst_drop;
+            }
+        }
+
+        Ok(IrDropReport {
+            straps,
+            worst_drop,
+            worst_location,
+        })
+    }
+}
+
+impl IrDropReport {
+    /// The largest voltage drop anywhere in the network, in volts.
+    #[inline]
+    pub fn worst_drop(&self) -> f64 {
+        self.worst_drop
+    }
+
+    /// The layer and location of the largest voltage drop in the network.
+    #[inline]
+    pub fn worst_location(&self) -> Option<(LayerKey, Point)> {
+        self.worst_location
+    }
+
+    /// The maximum voltage drop along each metal shape connected to a supply.
+    #[inline]
+    pub fn straps(&self) -> &[StrapDrop] {
+        &self.straps
+    }
+
+    /// Creates a heat map of the network as a group of text annotations.
+    ///
+    /// Each shape is labeled at its center with its maximum drop in millivolts,
+    /// using the label purpose of the shape's layer.
+    pub fn annotations(&self) -> Group {
+        let mut group = Group::new();
+        for strap in self.straps.iter() {
+            group.add(TextElement {
+                string: arcstr::format!("{:.2}mV", strap.max_drop * 1e3),
+                loc: strap.rect.center(),
+                layer: LayerSpec::label(strap.layer),
+            });
+        }
+        group
+    }
+}
+
+/// Returns the coordinate of `point` along the longer dimension of `rect`.
+#[inline]
+fn along(rect: Rect, point: Point) -> i64 {
+    let dir = rect.longer_dir();
+    let span = rect.span(dir);
+    point.coord(dir).clamp(span.start(), span.stop())
+}
+
+#[inline]
+fn contains(rect: Rect, point: Point) -> bool {
+    rect.p0.x <= point.x && point.x <= rect.p1.x && rect.p0.y <= point.y && point.y <= rect.p1.y
+}
+
+/// Returns the center of the overlap of two rectangles, if they overlap or abut.
+fn overlap_center(a: Rect, b: Rect) -> Option<Point> {
+    let xs = (a.p0.x.max(b.p0.x), a.p1.x.min(b.p1.x));
+    let ys = (a.p0.y.max(b.p0.y), a.p1.y.min(b.p1.y));
+    if xs.0 > xs.1 || ys.0 > ys.1 {
+        return None;
+    }
+    Some(Point::new((xs.0 + xs.1) / 2, (ys.0 + ys.1) / 2))
+}
+
+/// The metal shapes on each layer, sorted along one axis.
+///
+/// Power grid layers mostly contain straps running in a single direction,
+/// so each layer is sorted along the axis in which its shapes are narrowest.
+/// Only shapes whose sorted spans come close to a query need to be checked.
+struct ShapeIndex<'a> {
+    metals: &'a [(LayerKey, Rect)],
+    layers: HashMap<LayerKey, SortedShapes>,
+}
+
+struct SortedShapes {
+    dir: Dir,
+    /// The widest span of any shape on the layer along `dir`.
+    max_len: i64,
+    /// Shape indices, sorted by the start of their span along `dir`.
+    shapes: Vec<usize>,
+}
+
+impl<'a> ShapeIndex<'a> {
+    fn new(metals: &'a [(LayerKey, Rect)]) -> Self {
+        let mut buckets: HashMap<LayerKey, Vec<usize>> = HashMap::new();
+        for (i, &(layer, _)) in metals.iter().enumerate() {
+            buckets.entry(layer).or_default().push(i);
+        }
+
+        let layers = buckets
+            .into_iter()
+            .map(|(layer, mut shapes)| {
+                let max_len = |dir: Dir| {
+                    shapes
+                        .iter()
+                        .map(|&i| metals[i].1.length(dir))
+                        .max()
+                        .unwrap_or_default()
+                };
+                let dir = if max_len(Dir::Horiz) <= max_len(Dir::Vert) {
+                    Dir::Horiz
+                } else {
+                    Dir::Vert
+                };
+                let max_len = max_len(dir);
+                shapes.sort_by_key(|&i| metals[i].1.span(dir).start());
+                (
+                    layer,
+                    SortedShapes {
+                        dir,
+                        max_len,
+                        shapes,
+                    },
+                )
+            })
+            .collect();
+
+        Self { metals, layers }
+    }
+
+    /// Returns the indices of the shapes on `layer` that contain `point`, in increasing order.
+    fn containing(&self, layer: LayerKey, point: Point) -> Vec<usize> {
+        let sorted = match self.layers.get(&layer) {
+            Some(sorted) => sorted,
+            None => return Vec::new(),
+        };
+        let coord = point.coord(sorted.dir);
+        let start = sorted
+            .shapes
+            .partition_point(|&i| self.start(sorted.dir, i) < coord - sorted.max_len);
+        let mut shapes = sorted.shapes[start..]
+            .iter()
+            .copied()
+            .take_while(|&i| self.start(sorted.dir, i) <= coord)
+            .filter(|&i| contains(self.metals[i].1, point))
+            .collect::<Vec<_>>();
+        shapes.sort_unstable();
+        shapes
+    }
+
+    /// Returns every pair of overlapping or abutting shapes on the same layer,
+    /// along with the center of their overlap.
+    fn overlaps(&self) -> Vec<(usize, usize, Point)> {
+        let mut overlaps = Vec::new();
+        for sorted in self.layers.values() {
+            for (k, &i) in sorted.shapes.iter().enumerate() {
+                let ri = self.metals[i].1;
+                let stop = ri.span(sorted.dir).stop();
+                for &j in sorted.shapes[k + 1..].iter() {
+                    if self.start(sorted.dir, j) > stop {
+                        break;
+                    }
+                    if let Some(center) = overlap_center(ri, self.metals[j].1) {
+                        overlaps.push((i.min(j), i.max(j), center));
+                    }
+                }
+            }
+        }
+        overlaps
+    }
+
+    #[inline]
+    fn start(&self, dir: Dir, shape: usize) -> i64 {
+        self.metals[shape].1.span(dir).start()
+    }
+}
+
+struct UnionFind {
+    parents: Vec<usize>,
+}
+
+impl UnionFind {
+    fn new(n: usize) -> Self {
+        Self {
+            parents: (0..n).collect(),
+        }
+    }
+
+    fn find(&mut self, mut n: usize) -> usize {
+        while self.parents[n] != n {
+            self.parents[n] = self.parents[self.parents[n]];
+            n = self.parents[n];
+        }
+        n
+    }
+
+    fn union(&mut self, a: usize, b: usize) {
+        let (a, b) = (self.find(a), self.find(b));
+        if a != b {
+            self.parents[a] = b;
+        }
+    }
+}
+
+/// A symmetric sparse matrix with separately stored diagonal entries.
+struct SparseMatrix {
+    diag: Vec<f64>,
+    rows: Vec<Vec<(usize, f64)>>,
+}
+
+impl SparseMatrix {
+    fn new(n: usize) -> Self {
+        Self {
+            diag: vec![0.; n],
+            rows: vec![Vec::new(); n],
+        }
+    }
+
+    fn mul(&self, x: &[f64], out: &mut [f64]) {
+        for (i, row) in self.rows.iter().enumerate() {
+            out[i] = self.diag[i] * x[i] + row.iter().map(|&(j, v)| v * x[j]).sum::<f64>();
+        }
+    }
+
+    /// Solves `Ax = b` using the Jacobi-preconditioned conjugate gradient method.
+    fn solve_cg(&self, b: &[f64], tolerance: f64, max_iters: usize) -> Result<Vec<f64>> {
+        let n = b.len();
+        let mut x = vec![0.; n];
+        let norm_b = dot(b, b).sqrt();
+        if norm_b == 0. {
+            return Ok(x);
+        }
+
+        let mut r = b.to_vec();
+        let mut z = r
+            .iter()
+            .zip(self.diag.iter())
+            .map(|(r, d)| r / d)
+            .collect::<Vec<_>>();
+        let mut p = z.clone();
+        let mut ap = vec![0.; n];
+        let mut rz = dot(&r, &z);
+
+        for _ in 0..max_iters {
+            self.mul(&p, &mut ap);
+            let alpha = rz / dot(&p, &ap);
+            for i in 0..n {
+                x[i] += alpha * p[i];
+                r[i] -= alpha * ap[i];
+            }
+            if dot(&r, &r).sqrt() <= tolerance * norm_b {
+                return Ok(x);
+            }
+            for i in 0..n {
+                z[i] = r[i] / self.diag[i];
+            }
+            let rz_next = dot(&r, &z);
+            let beta = rz_next / rz;
+            rz = rz_next;
+            for i in 0..n {
+                p[i] = z[i] + beta * p[i];
+            }
+        }
+
+        Err(SubstrateError::new(IrDropError::DidNotConverge(max_iters)))
+    }
+}
+
+#[inline]
+fn dot(a: &[f64], b: &[f64]) -> f64 {
+    a.iter().zip(b.iter()).map(|(a, b)| a * b).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cg_solves_resistor_ladder() {
+        // Two 1 ohm resistors in series from a fixed node, with 1 A drawn at the end.
+        let mut matrix = SparseMatrix::new(2);
+        matrix.diag = vec![2., 1.];
+        matrix.rows[0].push((1, -1.));
+        matrix.rows[1].push((0, -1.));
+        let x = matrix.solve_cg(&[0., 1.], 1e-12, 100).unwrap();
+        assert!((x[0] - 1.).abs() < 1e-9);
+        assert!((x[1] - 2.).abs() < 1e-9);
+    }
+
+    #[test]
+    fn shape_index_matches_exhaustive_search() {
+        let mut keys = slotmap::SlotMap::<LayerKey, ()>::with_key();
+        let (m1, m2) = (keys.insert(()), keys.insert(()));
+        let metals = vec![
+            (m1, Rect::new(Point::new(0, 0), Point::new(1_000, 100))),
+            (m1, Rect::new(Point::new(0, 400), Point::new(1_000, 500))),
+            (m1, Rect::new(Point::new(900, 0), Point::new(1_000, 500))),
+            (
+                m1,
+                Rect::new(Point::new(1_000, 450), Point::new(2_000, 550)),
+            ),
+            (m2, Rect::new(Point::new(0, 0), Point::new(100, 1_000))),
+            (m2, Rect::new(Point::new(500, 0), Point::new(600, 1_000))),
+        ];
+        let index = ShapeIndex::new(&metals);
+
+        for layer in [m1, m2] {
+            for x in (-50..2_100).step_by(50) {
+                for y in (-50..1_100).step_by(50) {
+                    let point = Point::new(x, y);
+                    let expected = (0..metals.len())
+                        .filter(|&i| metals[i].0 == layer && contains(metals[i].1, point))
+                        .collect::<Vec<_>>();
+                    assert_eq!(index.containing(layer, point), expected);
+                }
+            }
+        }
+
+        let mut overlaps = index
+            .overlaps()
+            .into_iter()
+            .map(|(i, j, _)| (i, j))
+            .collect::<Vec<_>>();
+        overlaps.sort_unstable();
+        assert_eq!(overlaps, vec![(0, 2), (1, 2), (1, 3), (2, 3)]);
+    }
+}
@@ -53,11 +53,22 @@
 //! Custom net types should implement [`FromStr`] so that they can be parsed from strings
 //! (such as the strings specified in Hammer-generated JSON).
 //!
+//! # Power Grids
+//!
+//! Straps on several layers can be drawn and stitched together automatically
+//! using a [`PowerGrid`](grid::PowerGrid). Net types used in power grids should also
+//! implement [`Display`](std::fmt::Display); the displayed value is used as the name
+//! of the port exposed for each net.
+//!
+//! The static IR drop of a placed grid can be estimated using an
+//! [`IrDropAnalysis`](ir::IrDropAnalysis).
+//!
 use std::cmp::Ordering;
 use std::error::Error;
 use std::path::Path;
 use std::str::FromStr;
 
+use derive_builder::{Builder, UninitializedFieldError};
 use serde::{Deserialize, Serialize};
 use subgeom::{Dir, DirParseError, Rect, Span};
 use thiserror::Error;
@@ -69,7 +80,9 @@ use super::layers::{LayerKey, LayerSpec};
 use crate::data::SubstrateCtx;
 use crate::error::{Result, SubstrateError};
 
+pub mod grid;
 mod hammer;
+pub mod ir;
 mod parse;
 
 /// Net type enumeration for power straps with a single supply.
@@ -96,17 +109,47 @@ impl FromStr for SingleSupplyNet {
     }
 }
 
+impl std::fmt::Display for SingleSupplyNet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Vdd => write!(f, "vdd"),
+            Self::Vss => write!(f, "vss"),
+        }
+    }
+}
+
 /// Layer strap configuration for a single layer.
-#[derive(Clone, Eq, PartialEq, Debug, Serialize, Deserialize)]
+#[derive(Clone, Eq, PartialEq, Debug, Builder, Serialize, Deserialize)]
+#[allow(clippy::needless_borrow)]
+#[builder(
+    derive(Eq, PartialEq, Debug),
+    build_fn(
+        private,
+        name = "build_inner",
+        error = "PowerStrapError",
+        validate = "Self::validate"
+    )
+)]
 pub struct LayerStraps<N = SingleSupplyNet> {
+    /// The layer on which straps are drawn.
+    #[builder(setter(custom))]
     layer: LayerKey,
     /// Index of the metal layer.
+    #[builder(setter(custom))]
     index: usize,
+    /// The direction in which straps run.
     dir: Dir,
+    /// The nets in each group, in left-to-right or bottom-to-top order.
+    #[builder(setter(custom))]
     nets: Vec<N>,
+    /// The width of each strap.
     width: i64,
+    /// The spacing between adjacent straps within a group.
     spacing: i64,
+    /// The distance between the start of one group and the start of the next group.
     group_pitch: i64,
+    /// The offset of the first strap. Defaults to 0.
+    #[builder(default)]
     offset: i64,
 }
 
@@ -173,6 +216,37 @@ where
     }
 }
 
+impl<N: Clone> LayerStrapsBuilder<N> {
+    /// Sets the layer on which straps are drawn, along with its metal index.
+    ///
+    /// The metal index can be obtained using [`LayersRef::which_metal`](crate::layout::layers::LayersRef::which_metal).
+    pub fn layer(&mut self, layer: LayerKey, index: usize) -> &mut Self {
+        self.layer = Some(layer);
+        self.index = Some(index);
+        self
+    }
+
+    /// Sets the nets in each group, in left-to-right or bottom-to-top order.
+    pub fn nets(&mut self, nets: impl IntoIterator<Item = N>) -> &mut Self {
+        self.nets = Some(nets.into_iter().collect());
+        self
+    }
+
+    /// Builds a [`LayerStraps`] struct.
+    ///
+    /// Returns an error if any required field was not set or if no nets were specified.
+    pub fn build(&self) -> Result<LayerStraps<N>> {
+        self.build_inner().map_err(SubstrateError::new)
+    }
+
+    fn validate(&self) -> std::result::Result<(), PowerStrapError> {
+        match self.nets {
+            Some(ref nets) if !nets.is_empty() => Ok(()),
+            _ => Err(PowerStrapError::NoNets),
+        }
+    }
+}
+
 fn usize_as_i64(x: usize) -> i64 {
     i64::try_from(x).unwrap()
 }
@@ -194,6 +268,11 @@ impl<N> Strap<N> {
 }
 
 impl<N: Clone> LayerStraps<N> {
+    #[inline]
+    pub fn builder() -> LayerStrapsBuilder<N> {
+        LayerStrapsBuilder::default()
+    }
+
     /// The i-th power strap.
     pub fn strap(&self, i: usize) -> Strap<N> {
         let (group_idx, net_idx) = self.to_group_indices(i);
@@ -258,6 +337,12 @@ impl<N> LayerStraps<N> {
         self.layer
     }
 
+    /// The metal index of the layer on which straps are drawn.
+    #[inline]
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
     #[inline]
     pub fn dir(&self) -> Dir {
         self.dir
@@ -409,4 +494,22 @@ pub enum PowerStrapError {
 
     #[error("found the same metal index `{0}` multiple times in power strap configuration")]
     DuplicateMetalLayers(usize),
+
+    #[error("power grid must contain at least one layer of straps")]
+    NoStrapLayers,
+
+    #[error("target on layer {0} is not adjacent to any power grid layer")]
+    UnreachableTarget(String),
+
+    #[error("layer strap configuration is missing required field `{0}`")]
+    MissingField(&'static str),
+
+    #[error("layer strap configuration must specify at least one net")]
+    NoNets,
+}
+
+impl From<UninitializedFieldError> for PowerStrapError {
+    fn from(value: UninitializedFieldError) -> Self {
+        Self::MissingField(value.field_name())
+    }
 }
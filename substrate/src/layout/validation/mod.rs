@@ -1,48 +1,157 @@
+use std::collections::HashMap;
 use std::fmt::Display;
 
+use subgeom::{Point, Rect, Shape};
+
 use super::cell::{BusPort, Cell, CellKey, PortId};
+use super::layers::LayerKey;
 use crate::deps::arcstr::ArcStr;
 use crate::log::Log;
 use crate::validation::{Empty, ValidatorOutput};
 
 /// Validates a layout cell.
-pub fn validate_cell(cell: &Cell) -> LayoutValidatorOutput {
-    LayoutValidator { cell }.validate()
+///
+/// Geometry is checked against the layout grid `grid`, if one is provided.
+pub fn validate_cell(cell: &Cell, grid: Option<i64>) -> LayoutValidatorOutput {
+    LayoutValidator { cell, grid }.validate()
 }
 
+/// Validates the contents of a single layout cell.
+///
+/// Hierarchy is not traversed, since instantiated cells are validated
+/// when they are generated.
 pub struct LayoutValidator<'a> {
     cell: &'a Cell,
+    grid: Option<i64>,
 }
 
-pub type LayoutValidatorOutput = ValidatorOutput<Empty, Empty, Error, Empty>;
+pub type LayoutValidatorOutput = ValidatorOutput<Empty, Warning, Error, Empty>;
+
+/// The tolerance, in degrees, used when checking whether an orientation is Manhattan.
+const ANGLE_TOLERANCE: f64 = 1e-6;
 
 /// An error location or net.
 #[derive(Debug, Clone, Eq, PartialEq, Hash)]
 pub struct Location {
     cell: CellKey,
     cell_name: ArcStr,
-    port_id: PortId,
+    item: Item,
+}
+
+/// An item within a layout cell.
+#[non_exhaustive]
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum Item {
+    /// A port, identified by its ID.
+    Port(PortId),
+    /// An element, identified by its index in the cell.
+    Element(usize),
+    /// An instance, identified by its name.
+    Instance(ArcStr),
+    /// An instance array, identified by its name.
+    Array(ArcStr),
 }
 
 impl Location {
-    /// Creates a new [`Location`].
+    /// Creates a new [`Location`] referring to a port.
     pub fn new(cell: CellKey, cell_name: impl Into<ArcStr>, port_id: impl Into<PortId>) -> Self {
+        Self::with_item(cell, cell_name, Item::Port(port_id.into()))
+    }
+
+    /// Creates a new [`Location`] referring to the given [`Item`].
+    pub fn with_item(cell: CellKey, cell_name: impl Into<ArcStr>, item: Item) -> Self {
         Self {
             cell,
             cell_name: cell_name.into(),
-            port_id: port_id.into(),
+            item,
         }
     }
 }
 
 impl Display for Location {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "cell {}, port {}", self.cell_name, self.port_id)
+        match &self.item {
+            Item::Port(id) => write!(f, "cell {}, port {}", self.cell_name, id),
+            Item::Element(idx) => write!(f, "cell {}, element {}", self.cell_name, idx),
+            Item::Instance(name) => write!(f, "cell {}, instance {}", self.cell_name, name),
+            Item::Array(name) => write!(f, "cell {}, instance array {}", self.cell_name, name),
+        }
+    }
+}
+
+/// Data for a warning.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Warning {
+    loc: Location,
+    cause: WarningCause,
+}
+
+/// An enumeration of causes for a warning.
+#[non_exhaustive]
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum WarningCause {
+    /// A shape or instance origin that does not lie on the layout grid.
+    OffGrid { point: Point, grid: i64 },
+    /// A rectangle with zero width or zero height.
+    ZeroArea(Rect),
+    /// An instance that is rotated by an angle that is not a multiple of 90 degrees.
+    NonManhattan,
+    /// Port shapes on the same layer overlap a shape of a different port.
+    ///
+    /// This is a warning rather than an error, since distinct ports
+    /// are sometimes intentionally connected (such as a `vdd` port and a
+    /// `vdd_b` port that share a rail).
+    Short {
+        /// The other port.
+        other: PortId,
+        /// The layer on which the short occurs.
+        layer: LayerKey,
+        /// The overlapping region.
+        overlap: Rect,
+    },
+}
+
+impl Log for Warning {
+    fn log(&self) {
+        use crate::log::warn;
+        warn!("{self}");
+    }
+}
+
+impl Warning {
+    /// Creates a new [`Warning`].
+    pub fn new(loc: Location, cause: WarningCause) -> Self {
+        Self { loc, cause }
+    }
+}
+
+impl Display for Warning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.cause {
+            WarningCause::OffGrid { point, grid } => write!(
+                f,
+                "point ({}, {}) is not on the {} unit layout grid: {}",
+                point.x, point.y, grid, self.loc
+            ),
+            WarningCause::ZeroArea(rect) => {
+                write!(f, "rectangle {rect:?} has zero area: {}", self.loc)
+            }
+            WarningCause::NonManhattan => {
+                write!(f, "instance has a non-Manhattan orientation: {}", self.loc)
+            }
+            WarningCause::Short { other, overlap, .. } => {
+                write!(
+                    f,
+                    "port is shorted to port {other} at {overlap:?}: {}",
+                    self.loc
+                )
+            }
+        }
     }
 }
 
 /// Data for an error.
-#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+#[derive(Debug, Clone, Eq, PartialEq)]
 pub struct Error {
     loc: Location,
     cause: ErrorCause,
@@ -57,18 +166,23 @@ impl Log for Error {
 
 /// An enumeration of causes for an error.
 #[non_exhaustive]
-#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+#[derive(Debug, Clone, Eq, PartialEq)]
 pub enum ErrorCause {
     /// Missing port in bus.
     MissingPort,
+    /// A rectangle whose lower left corner is above or to the right of its upper right corner.
+    InvertedRect(Rect),
 }
 
 impl Display for Error {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self.cause {
+        match &self.cause {
             ErrorCause::MissingPort => {
                 write!(f, "bus is missing a port: {}", self.loc)
             }
+            ErrorCause::InvertedRect(rect) => {
+                write!(f, "rectangle {rect:?} is inverted: {}", self.loc)
+            }
         }
     }
 }
@@ -84,9 +198,16 @@ impl<'a> LayoutValidator<'a> {
     fn validate(&self) -> LayoutValidatorOutput {
         let mut output = LayoutValidatorOutput::default();
         self.validate_bus_ports(&mut output);
+        self.validate_port_shorts(&mut output);
+        self.validate_shapes(&mut output);
+        self.validate_instances(&mut output);
         output
     }
 
+    fn loc(&self, item: Item) -> Location {
+        Location::with_item(self.cell.id(), self.cell.name(), item)
+    }
+
     /// Validates all bus ports at the top level of the cell.
     fn validate_bus_ports(&self, output: &mut LayoutValidatorOutput) {
         for (name, bus_port) in self.cell.bus_ports() {
@@ -112,4 +233,272 @@ impl<'a> LayoutValidator<'a> {
             }
         }
     }
+
+    /// Warns if rectangles belonging to different ports touch or overlap.
+    ///
+    /// Each pair of shorted ports is reported once per layer.
+    /// Non-rectangular port shapes are not checked.
+    fn validate_port_shorts(&self, output: &mut LayoutValidatorOutput) {
+        let mut layers: HashMap<LayerKey, Vec<(Rect, &PortId)>> = HashMap::new();
+        for port in self.cell.ports() {
+            for (layer, shapes) in port.shapes.iter() {
+                let rects = layers.entry(*layer).or_default();
+                rects.extend(
+                    shapes
+                        .iter()
+                        .filter_map(|shape| shape.as_rect())
+                        .map(|rect| (rect, &port.id)),
+                );
+            }
+        }
+
+        let mut layers = layers.into_iter().collect::<Vec<_>>();
+        layers.sort_by_key(|(layer, _)| *layer);
+
+        for (layer, mut rects) in layers {
+            // Sweep from left to right, keeping only rectangles that
+            // may still touch the current rectangle.
+            rects.sort_by_key(|(rect, _)| (rect.p0.x, rect.p0.y));
+            let mut reported: Vec<(&PortId, &PortId)> = Vec::new();
+            let mut active: Vec<(Rect, &PortId)> = Vec::new();
+            for (rect, id) in rects {
+                active.retain(|(other, _)| other.p1.x >= rect.p0.x);
+                for (other, other_id) in active.iter() {
+                    if other_id == &id || other.p1.y < rect.p0.y || rect.p1.y < other.p0.y {
+                        continue;
+                    }
+                    let pair = if (&other_id.name, other_id.index) < (&id.name, id.index) {
+                        (*other_id, id)
+                    } else {
+                        (id, *other_id)
+                    };
+                    if reported.contains(&pair) {
+                        continue;
+                    }
+                    reported.push(pair);
+                    let overlap = Rect::new(
+                        Point::new(rect.p0.x.max(other.p0.x), rect.p0.y.max(other.p0.y)),
+                        Point::new(rect.p1.x.min(other.p1.x), rect.p1.y.min(other.p1.y)),
+                    );
+                    output.warnings.push(Warning::new(
+                        self.loc(Item::Port(pair.0.clone())),
+                        WarningCause::Short {
+                            other: pair.1.clone(),
+                            layer,
+                            overlap,
+                        },
+                    ));
+                }
+                active.push((rect, id));
+            }
+        }
+    }
+
+    /// Validates the geometry of all elements and ports at the top level of the cell.
+    fn validate_shapes(&self, output: &mut LayoutValidatorOutput) {
+        for (i, elem) in self.cell.elems().enumerate() {
+            self.validate_shape(&elem.inner, Item::Element(i), output);
+        }
+        for port in self.cell.ports() {
+            for shape in port.shapes.values().flatten() {
+                self.validate_shape(shape, Item::Port(port.id.clone()), output);
+            }
+        }
+    }
+
+    /// Validates that a shape is well-formed and lies on the layout grid.
+    fn validate_shape(&self, shape: &Shape, item: Item, output: &mut LayoutValidatorOutput) {
+        if let Shape::Rect(rect) = shape {
+            if rect.p0.x > rect.p1.x || rect.p0.y > rect.p1.y {
+                output
+                    .errors
+                    .push(Error::new(self.loc(item), ErrorCause::InvertedRect(*rect)));
+                return;
+            }
+            if rect.p0.x == rect.p1.x || rect.p0.y == rect.p1.y {
+                output.warnings.push(Warning::new(
+                    self.loc(item.clone()),
+                    WarningCause::ZeroArea(*rect),
+                ));
+            }
+        }
+
+        let off_grid = match shape {
+            Shape::Rect(rect) => [rect.p0, rect.p1].into_iter().find(|p| !self.on_grid(*p)),
+            Shape::Polygon(poly) => poly.points.iter().copied().find(|p| !self.on_grid(*p)),
+            Shape::Path(path) => path.points.iter().copied().find(|p| !self.on_grid(*p)),
+            Shape::Point(p) => Some(*p).filter(|p| !self.on_grid(*p)),
+        };
+        if let Some(point) = off_grid {
+            self.warn_off_grid(point, item, output);
+        }
+    }
+
+    /// Validates the origins and orientations of all instances
+    /// and instance arrays at the top level of the cell.
+    fn validate_instances(&self, output: &mut LayoutValidatorOutput) {
+        let insts = self.cell.insts().map(|inst| {
+            (
+                Item::Instance(inst.name().clone()),
+                inst.loc(),
+                inst.orientation(),
+            )
+        });
+        let arrays = self.cell.arrays().flat_map(|array| {
+            let item = Item::Array(array.name().clone());
+            [
+                (item.clone(), array.loc(), array.orientation()),
+                (item.clone(), array.row_pitch(), Default::default()),
+                (item, array.col_pitch(), Default::default()),
+            ]
+        });
+
+        for (item, point, orientation) in insts.chain(arrays) {
+            let rem = orientation.angle().rem_euclid(90.);
+            if rem > ANGLE_TOLERANCE && 90. - rem > ANGLE_TOLERANCE {
+                output.warnings.push(Warning::new(
+                    self.loc(item.clone()),
+                    WarningCause::NonManhattan,
+                ));
+            }
+            if !self.on_grid(point) {
+                self.warn_off_grid(point, item, output);
+            }
+        }
+    }
+
+    #[inline]
+    fn on_grid(&self, p: Point) -> bool {
+        match self.grid {
+            Some(grid) if grid > 1 => p.x % grid == 0 && p.y % grid == 0,
+            _ => true,
+        }
+    }
+
+    fn warn_off_grid(&self, point: Point, item: Item, output: &mut LayoutValidatorOutput) {
+        output.warnings.push(Warning::new(
+            self.loc(item),
+            WarningCause::OffGrid {
+                point,
+                grid: self.grid.unwrap_or(1),
+            },
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use slotmap::SlotMap;
+    use subgeom::orientation::Orientation;
+    use subgeom::transform::Transformation;
+
+    use super::*;
+    use crate::layout::cell::{CellPort, Element, Instance};
+    use crate::layout::layers::LayerSpec;
+
+    fn keys() -> (CellKey, LayerKey, LayerKey) {
+        let mut cells: SlotMap<CellKey, ()> = SlotMap::with_key();
+        let mut layers: SlotMap<LayerKey, ()> = SlotMap::with_key();
+        (cells.insert(()), layers.insert(()), layers.insert(()))
+    }
+
+    #[test]
+    fn test_port_shorts() {
+        let (cell_key, m1, m2) = keys();
+        let mut cell = Cell::new(cell_key);
+        let a = Rect::new(Point::new(0, 0), Point::new(100, 100));
+        let b = Rect::new(Point::new(100, 50), Point::new(200, 150));
+        cell.add_port(CellPort::with_shape("a", m1, a)).unwrap();
+        cell.add_port(CellPort::with_shape("b", m1, b)).unwrap();
+        // Same location as port `b`, but on a different layer.
+        cell.add_port(CellPort::with_shape("c", m2, b)).unwrap();
+        // Same net as port `a`.
+        cell.merge_port(CellPort::with_shape("a", m1, a));
+
+        let output = validate_cell(&cell, None);
+        assert!(!output.has_errors());
+        assert_eq!(output.warnings.len(), 1);
+        assert_eq!(
+            output.warnings[0].cause,
+            WarningCause::Short {
+                other: PortId::new("b", 0),
+                layer: m1,
+                overlap: Rect::new(Point::new(100, 50), Point::new(100, 100)),
+            }
+        );
+        assert_eq!(output.warnings[0].loc.item, Item::Port(PortId::new("a", 0)));
+    }
+
+    #[test]
+    fn test_invalid_geometry() {
+        let (cell_key, m1, _) = keys();
+        let mut cell = Cell::new(cell_key);
+        let layer = LayerSpec::drawing(m1);
+        cell.add(Element::new(
+            layer.clone(),
+            Rect::new(Point::new(0, 0), Point::new(100, 100)),
+        ));
+        cell.add(Element::new(
+            layer.clone(),
+            Rect::new(Point::new(0, 0), Point::new(0, 100)),
+        ));
+        cell.add(Element::new(
+            layer.clone(),
+            Rect {
+                p0: Point::new(100, 0),
+                p1: Point::new(0, 100),
+            },
+        ));
+        cell.add(Element::new(
+            layer,
+            Rect::new(Point::new(5, 0), Point::new(100, 100)),
+        ));
+
+        let output = validate_cell(&cell, Some(10));
+        assert_eq!(output.errors.len(), 1);
+        assert_eq!(output.errors[0].loc.item, Item::Element(2));
+        assert!(matches!(
+            output.errors[0].cause,
+            ErrorCause::InvertedRect(_)
+        ));
+        assert_eq!(output.warnings.len(), 2);
+        assert_eq!(output.warnings[0].loc.item, Item::Element(1));
+        assert!(matches!(
+            output.warnings[0].cause,
+            WarningCause::ZeroArea(_)
+        ));
+        assert_eq!(output.warnings[1].loc.item, Item::Element(3));
+        assert_eq!(
+            output.warnings[1].cause,
+            WarningCause::OffGrid {
+                point: Point::new(5, 0),
+                grid: 10
+            }
+        );
+    }
+
+    #[test]
+    fn test_instance_transformations() {
+        let (cell_key, _, _) = keys();
+        let child = Cell::new(cell_key);
+        let mut cell = Cell::new(cell_key);
+        let mut inst = Instance::new(child);
+        inst.set_loc(Point::new(10, 25));
+        inst.set_orientation(Orientation::from_transformation(Transformation::rotate(
+            45.,
+        )));
+        cell.add_inst(inst);
+
+        let output = validate_cell(&cell, Some(5));
+        assert!(!output.has_errors());
+        assert_eq!(output.warnings.len(), 1);
+        assert_eq!(output.warnings[0].cause, WarningCause::NonManhattan);
+
+        let output = validate_cell(&cell, Some(10));
+        assert_eq!(output.warnings.len(), 2);
+        assert!(matches!(
+            output.warnings[1].cause,
+            WarningCause::OffGrid { .. }
+        ));
+    }
 }
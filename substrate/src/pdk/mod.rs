@@ -6,10 +6,13 @@ use self::corner::CornerDb;
 use self::mos::spec::MosSpec;
 use self::mos::{LayoutMosParams, MosParams};
 use self::stdcell::StdCellDb;
+use crate::component::View;
 use crate::error::Result;
 use crate::layout::context::LayoutCtx;
+use crate::layout::elements::guard_ring::GuardRingParams;
 use crate::layout::elements::via::ViaParams;
-use crate::layout::layers::Layers;
+use crate::layout::fill::FillRules;
+use crate::layout::layers::{LayerInfo, Layers};
 use crate::schematic::context::SchematicCtx;
 use crate::schematic::netlist::{IncludeBundle, NetlistPurpose};
 use crate::units::SiPrefix;
@@ -107,9 +110,39 @@ pub trait Pdk {
     /// Draws a via with the given params in the given context.
     fn via_layout(&self, ctx: &mut LayoutCtx, params: &ViaParams) -> Result<()>;
 
+    /// Draws a guard ring with the given params in the given context.
+    ///
+    /// The ring's contact metal should be exposed as a port named
+    /// [`GUARD_RING_PORT`](crate::layout::elements::guard_ring::GUARD_RING_PORT).
+    /// PDKs that do not support guard rings return an error.
+    fn guard_ring_layout(&self, _ctx: &mut LayoutCtx, _params: &GuardRingParams) -> Result<()> {
+        Err(crate::component::error::Error::ViewUnsupported(View::Layout).into())
+    }
+
     /// The grid on which all layout geometry must lie.
     fn layout_grid(&self) -> i64;
 
+    /// The sheet resistance of the given layer, in ohms per square.
+    ///
+    /// Used for IR drop analysis. Returns [`None`] if the resistance of the layer is unknown.
+    fn sheet_resistance(&self, _layer: &LayerInfo) -> Option<f64> {
+        None
+    }
+
+    /// The resistance of a single cut on the given via layer, in ohms.
+    ///
+    /// Used for IR drop analysis. Returns [`None`] if the resistance of the layer is unknown.
+    fn via_resistance(&self, _layer: &LayerInfo) -> Option<f64> {
+        None
+    }
+
+    /// The density and fill rules for the given layer.
+    ///
+    /// Used for fill generation. Returns [`None`] if the layer should not be filled.
+    fn fill_rules(&self, _layer: &LayerInfo) -> Option<FillRules> {
+        None
+    }
+
     /// Called before running simulations.
     ///
     /// Allows the PDK to include model libraries, configure simulation
@@ -42,6 +42,11 @@ impl MosDb {
             .ok_or(ErrorSource::DeviceNotFound.into())
     }
 
+    /// Returns an iterator over the specifications of all MOSFETs in the database.
+    pub fn specs(&self) -> impl Iterator<Item = &MosSpec> {
+        self.devices.values()
+    }
+
     pub fn default_nmos(&self) -> Result<QueryResult> {
         self.query(Query::builder().kind(MosKind::Nmos).build().unwrap())
     }
@@ -38,7 +38,7 @@ pub struct MosSpec {
     pub supply: SupplyId,
 }
 
-#[derive(Debug, Default, Copy, Clone, Eq, PartialEq, Hash)]
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
 pub enum MosKind {
     #[default]
     Nmos,
@@ -128,6 +128,21 @@ impl Instance {
         &self.connections
     }
 
+    /// Overrides the value of the parameter `name` of the instance's module.
+    ///
+    /// Use [`Value::Expr`] to set the parameter to an expression of the
+    /// parameters of the enclosing module.
+    #[inline]
+    pub fn set_param(&mut self, name: impl Into<ArcStr>, value: impl Into<Value>) {
+        self.params.insert(name.into(), value.into());
+    }
+
+    /// A consuming method to override the value of a parameter.
+    pub fn with_param(mut self, name: impl Into<ArcStr>, value: impl Into<Value>) -> Self {
+        self.set_param(name, value);
+        self
+    }
+
     /// Sets the name of the instance.
     #[inline]
     pub fn set_name(&mut self, name: impl Into<ArcStr>) {
@@ -247,7 +262,6 @@ impl Reference {
 
 /// A general-purpose parameter type for schematic objects.
 #[derive(Clone, Debug)]
-#[allow(dead_code)]
 pub struct Param {
     name: ArcStr,
     desc: Option<ArcStr>,
@@ -261,9 +275,74 @@ pub enum Value {
     Int(i64),
     Float(f64),
     String(String),
+    /// An expression, which may refer to the parameters of the enclosing module.
+    ///
+    /// Expressions are written to netlists verbatim, so they must use syntax
+    /// that is understood by the target simulator.
     Expr(String),
 }
 
+impl Param {
+    /// Creates a new [`Param`] with the given name and default value.
+    pub fn new(name: impl Into<ArcStr>, value: impl Into<Value>) -> Self {
+        Self {
+            name: name.into(),
+            desc: None,
+            value: value.into(),
+        }
+    }
+
+    /// A consuming method to set the description of the parameter.
+    pub fn with_desc(mut self, desc: impl Into<ArcStr>) -> Self {
+        self.desc = Some(desc.into());
+        self
+    }
+
+    /// Returns the name of the parameter.
+    #[inline]
+    pub fn name(&self) -> &ArcStr {
+        &self.name
+    }
+
+    /// Returns the description of the parameter, if any.
+    #[inline]
+    pub fn desc(&self) -> Option<&ArcStr> {
+        self.desc.as_ref()
+    }
+
+    /// Returns the default value of the parameter.
+    #[inline]
+    pub fn value(&self) -> &Value {
+        &self.value
+    }
+}
+
+impl Value {
+    /// Creates a new [`Value::Expr`].
+    #[inline]
+    pub fn expr(expr: impl Into<String>) -> Self {
+        Self::Expr(expr.into())
+    }
+}
+
+impl From<i64> for Value {
+    fn from(value: i64) -> Self {
+        Self::Int(value)
+    }
+}
+
+impl From<f64> for Value {
+    fn from(value: f64) -> Self {
+        Self::Float(value)
+    }
+}
+
+impl From<String> for Value {
+    fn from(value: String) -> Self {
+        Self::String(value)
+    }
+}
+
 /// An enumeration of port directions.
 #[derive(
     Clone, Copy, Eq, PartialEq, Hash, Default, Debug, Ord, PartialOrd, Serialize, Deserialize,
@@ -9,8 +9,9 @@ use std::sync::Arc;
 use itertools::Itertools;
 use slotmap::new_key_type;
 
-use super::circuit::{Direction, Instance, PortInfo};
+use super::circuit::{Direction, Instance, Param, PortInfo, Value};
 use super::module::{ExternalModule, Module};
+use super::primitive::PrimitiveDevice;
 use super::signal::Slice;
 use crate::component::Component;
 use crate::data::SubstrateCtx;
@@ -149,6 +150,11 @@ impl SchematicCtx {
         self.module.add_instance(inst);
     }
 
+    /// Adds a primitive device, such as a resistor or voltage source, to the schematic.
+    pub fn add_primitive(&mut self, device: PrimitiveDevice) {
+        self.module.add_primitive(device);
+    }
+
     pub fn port(&mut self, name: impl Into<ArcStr>, direction: Direction) -> Slice {
         self.module.add_port(name, 1, direction)
     }
@@ -193,6 +199,18 @@ impl SchematicCtx {
         names.map(|name| self.bus(name, width))
     }
 
+    /// Declares a parameter of the schematic with the given default value.
+    ///
+    /// Instances of the schematic may override the default using [`Instance::set_param`].
+    /// Returns a [`Value::Expr`] referring to the parameter, which can be used to pass the
+    /// parameter's value on to the parameters of child instances.
+    pub fn param(&mut self, name: impl Into<ArcStr>, default: impl Into<Value>) -> Value {
+        let param = Param::new(name, default);
+        let value = Value::expr(param.name().as_str());
+        self.module.add_param(param);
+        value
+    }
+
     pub fn set_spice(&mut self, spice: impl Into<ArcStr>) {
         self.module.set_raw_spice(spice)
     }
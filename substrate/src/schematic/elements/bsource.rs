@@ -0,0 +1,54 @@
+//! A behavioral source.
+
+use serde::{Deserialize, Serialize};
+
+use crate::component::Component;
+use crate::deps::arcstr::ArcStr;
+use crate::schematic::circuit::Direction;
+use crate::schematic::context::SchematicCtx;
+use crate::schematic::primitive::{PrimitiveDevice, PrimitiveKind};
+
+/// A behavioral source, whose output is an arbitrary expression.
+///
+/// The expression is written verbatim to the netlist, so it may refer to node voltages
+/// (e.g. `v(a)`) and branch currents using the syntax of the target simulator.
+#[derive(Clone, Eq, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Bsource {
+    /// The quantity driven by the source.
+    pub output: BsourceOutput,
+    /// The expression for the source's output.
+    pub expr: ArcStr,
+}
+
+/// The quantity driven by a [`Bsource`].
+#[derive(Copy, Clone, Eq, Debug, PartialEq, Hash, Serialize, Deserialize)]
+pub enum BsourceOutput {
+    /// The voltage from `p` to `n`.
+    Voltage,
+    /// The current flowing from `p` to `n` through the source.
+    Current,
+}
+
+impl Component for Bsource {
+    type Params = Bsource;
+
+    fn new(params: &Self::Params, _ctx: &crate::data::SubstrateCtx) -> crate::error::Result<Self> {
+        Ok(params.clone())
+    }
+
+    fn name(&self) -> ArcStr {
+        arcstr::literal!("bsource")
+    }
+
+    fn schematic(&self, ctx: &mut SchematicCtx) -> crate::error::Result<()> {
+        let p = ctx.port("p", Direction::InOut);
+        let n = ctx.port("n", Direction::InOut);
+
+        ctx.add_primitive(PrimitiveDevice::new(
+            "src",
+            PrimitiveKind::Bsource(self.clone()),
+            [p, n],
+        ));
+        Ok(())
+    }
+}
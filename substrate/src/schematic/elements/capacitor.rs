@@ -4,6 +4,7 @@ use crate::component::Component;
 use crate::deps::arcstr::ArcStr;
 use crate::schematic::circuit::Direction;
 use crate::schematic::context::SchematicCtx;
+use crate::schematic::primitive::{PrimitiveDevice, PrimitiveKind};
 use crate::units::SiValue;
 
 /// A primitive capacitor parametrized by capacitance.
@@ -21,10 +22,14 @@ impl Component for Capacitor {
     }
 
     fn schematic(&self, ctx: &mut SchematicCtx) -> crate::error::Result<()> {
-        let _p = ctx.port("p", Direction::InOut);
-        let _n = ctx.port("n", Direction::InOut);
+        let p = ctx.port("p", Direction::InOut);
+        let n = ctx.port("n", Direction::InOut);
 
-        ctx.set_spice(format!("C1 p n {}", self.0));
+        ctx.add_primitive(PrimitiveDevice::new(
+            "cap",
+            PrimitiveKind::Capacitor(self.0),
+            [p, n],
+        ));
         Ok(())
     }
 }
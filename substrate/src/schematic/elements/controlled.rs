@@ -0,0 +1,73 @@
+//! Primitive controlled source `Component`s.
+//!
+//! Each source drives `p` and `n`, and is controlled by the voltage across
+//! or the current flowing from `cp` to `cn`.
+
+use crate::component::Component;
+use crate::deps::arcstr::ArcStr;
+use crate::schematic::circuit::Direction;
+use crate::schematic::context::SchematicCtx;
+use crate::schematic::primitive::{PrimitiveDevice, PrimitiveKind};
+use crate::units::SiValue;
+
+macro_rules! controlled_source {
+    ($(#[$doc:meta])* $name:ident, $kind:ident, $prefix:literal) => {
+        $(#[$doc])*
+        pub struct $name(SiValue);
+
+        impl Component for $name {
+            type Params = SiValue;
+
+            fn new(
+                params: &Self::Params,
+                _ctx: &crate::data::SubstrateCtx,
+            ) -> crate::error::Result<Self> {
+                Ok(Self(*params))
+            }
+
+            fn name(&self) -> ArcStr {
+                arcstr::format!(concat!($prefix, "_{}"), self.0)
+            }
+
+            fn schematic(&self, ctx: &mut SchematicCtx) -> crate::error::Result<()> {
+                let [p, n, cp, cn] = ctx.ports(["p", "n", "cp", "cn"], Direction::InOut);
+
+                ctx.add_primitive(PrimitiveDevice::new(
+                    "src",
+                    PrimitiveKind::$kind(self.0),
+                    [p, n, cp, cn],
+                ));
+                Ok(())
+            }
+        }
+    };
+}
+
+controlled_source!(
+    /// A voltage-controlled voltage source parametrized by voltage gain.
+    Vcvs,
+    Vcvs,
+    "vcvs"
+);
+controlled_source!(
+    /// A voltage-controlled current source parametrized by transconductance.
+    Vccs,
+    Vccs,
+    "vccs"
+);
+controlled_source!(
+    /// A current-controlled voltage source parametrized by transresistance.
+    ///
+    /// The controlling current flows through a short circuit from `cp` to `cn`.
+    Ccvs,
+    Ccvs,
+    "ccvs"
+);
+controlled_source!(
+    /// A current-controlled current source parametrized by current gain.
+    ///
+    /// The controlling current flows through a short circuit from `cp` to `cn`.
+    Cccs,
+    Cccs,
+    "cccs"
+);
@@ -0,0 +1,43 @@
+//! A primitive diode `Component`.
+
+use serde::{Deserialize, Serialize};
+
+use crate::component::Component;
+use crate::deps::arcstr::ArcStr;
+use crate::schematic::circuit::Direction;
+use crate::schematic::context::SchematicCtx;
+use crate::schematic::primitive::{PrimitiveDevice, PrimitiveKind};
+use crate::units::SiValue;
+
+/// A diode with anode `a` and cathode `c`.
+#[derive(Clone, Eq, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Diode {
+    /// The name of the diode model.
+    pub model: ArcStr,
+    /// The area scale factor, if any.
+    pub area: Option<SiValue>,
+}
+
+impl Component for Diode {
+    type Params = Diode;
+
+    fn new(params: &Self::Params, _ctx: &crate::data::SubstrateCtx) -> crate::error::Result<Self> {
+        Ok(params.clone())
+    }
+
+    fn name(&self) -> ArcStr {
+        arcstr::format!("diode_{}", self.model)
+    }
+
+    fn schematic(&self, ctx: &mut SchematicCtx) -> crate::error::Result<()> {
+        let a = ctx.port("a", Direction::InOut);
+        let c = ctx.port("c", Direction::InOut);
+
+        ctx.add_primitive(PrimitiveDevice::new(
+            "diode",
+            PrimitiveKind::Diode(self.clone()),
+            [a, c],
+        ));
+        Ok(())
+    }
+}
@@ -3,6 +3,7 @@
 use crate::component::Component;
 use crate::deps::arcstr::ArcStr;
 use crate::schematic::circuit::Direction;
+use crate::schematic::primitive::{PrimitiveDevice, PrimitiveKind};
 use crate::units::SiValue;
 
 /// A primitive DC current supply parametrized by DC current.
@@ -23,10 +24,14 @@ impl Component for Idc {
         &self,
         ctx: &mut crate::schematic::context::SchematicCtx,
     ) -> crate::error::Result<()> {
-        let _p = ctx.port("p", Direction::InOut);
-        let _n = ctx.port("n", Direction::InOut);
+        let p = ctx.port("p", Direction::InOut);
+        let n = ctx.port("n", Direction::InOut);
 
-        ctx.set_spice(format!("I1 p n {}", self.0));
+        ctx.add_primitive(PrimitiveDevice::new(
+            "src",
+            PrimitiveKind::Idc(self.0),
+            [p, n],
+        ));
         Ok(())
     }
 }
@@ -0,0 +1,35 @@
+//! A primitive inductor `Component`.
+
+use crate::component::Component;
+use crate::deps::arcstr::ArcStr;
+use crate::schematic::circuit::Direction;
+use crate::schematic::context::SchematicCtx;
+use crate::schematic::primitive::{PrimitiveDevice, PrimitiveKind};
+use crate::units::SiValue;
+
+/// A primitive inductor parametrized by inductance.
+pub struct Inductor(SiValue);
+
+impl Component for Inductor {
+    type Params = SiValue;
+
+    fn new(params: &Self::Params, _ctx: &crate::data::SubstrateCtx) -> crate::error::Result<Self> {
+        Ok(Self(*params))
+    }
+
+    fn name(&self) -> ArcStr {
+        arcstr::format!("inductor_{}", self.0)
+    }
+
+    fn schematic(&self, ctx: &mut SchematicCtx) -> crate::error::Result<()> {
+        let p = ctx.port("p", Direction::InOut);
+        let n = ctx.port("n", Direction::InOut);
+
+        ctx.add_primitive(PrimitiveDevice::new(
+            "ind",
+            PrimitiveKind::Inductor(self.0),
+            [p, n],
+        ));
+        Ok(())
+    }
+}
@@ -1,11 +1,18 @@
 //! Primitive schematic elements.
 
+pub mod bsource;
 pub mod capacitor;
+pub mod controlled;
+pub mod diode;
 pub mod iac;
 pub mod idc;
+pub mod inductor;
 pub mod mos;
 pub mod resistor;
+pub mod switch;
 pub mod vac;
 pub mod vdc;
+pub mod vexp;
 pub mod vpulse;
 pub mod vpwl;
+pub mod vsin;
@@ -0,0 +1,46 @@
+//! An ideal voltage-controlled switch.
+
+use serde::{Deserialize, Serialize};
+
+use crate::component::Component;
+use crate::deps::arcstr::ArcStr;
+use crate::schematic::circuit::Direction;
+use crate::schematic::context::SchematicCtx;
+use crate::schematic::primitive::{PrimitiveDevice, PrimitiveKind};
+use crate::units::SiValue;
+
+/// An ideal switch between `p` and `n`.
+///
+/// The switch is closed when the voltage from `cp` to `cn` exceeds `vt`.
+#[derive(Copy, Clone, Eq, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Switch {
+    /// On resistance (ohms).
+    pub ron: SiValue,
+    /// Off resistance (ohms).
+    pub roff: SiValue,
+    /// Threshold voltage (volts).
+    pub vt: SiValue,
+}
+
+impl Component for Switch {
+    type Params = Switch;
+
+    fn new(params: &Self::Params, _ctx: &crate::data::SubstrateCtx) -> crate::error::Result<Self> {
+        Ok(*params)
+    }
+
+    fn name(&self) -> ArcStr {
+        arcstr::literal!("switch")
+    }
+
+    fn schematic(&self, ctx: &mut SchematicCtx) -> crate::error::Result<()> {
+        let [p, n, cp, cn] = ctx.ports(["p", "n", "cp", "cn"], Direction::InOut);
+
+        ctx.add_primitive(PrimitiveDevice::new(
+            "sw",
+            PrimitiveKind::Switch(*self),
+            [p, n, cp, cn],
+        ));
+        Ok(())
+    }
+}
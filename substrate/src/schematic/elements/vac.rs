@@ -3,6 +3,7 @@
 use crate::component::Component;
 use crate::deps::arcstr::ArcStr;
 use crate::schematic::circuit::Direction;
+use crate::schematic::primitive::{PrimitiveDevice, PrimitiveKind};
 use crate::units::SiValue;
 
 /// A primitive AC supply parametrized by AC amplitude.
@@ -23,10 +24,14 @@ impl Component for Vac {
         &self,
         ctx: &mut crate::schematic::context::SchematicCtx,
     ) -> crate::error::Result<()> {
-        let _p = ctx.port("p", Direction::InOut);
-        let _n = ctx.port("n", Direction::InOut);
+        let p = ctx.port("p", Direction::InOut);
+        let n = ctx.port("n", Direction::InOut);
 
-        ctx.set_spice(format!("V1 p n ac {}", self.0));
+        ctx.add_primitive(PrimitiveDevice::new(
+            "src",
+            PrimitiveKind::Vac(self.0),
+            [p, n],
+        ));
         Ok(())
     }
 }
@@ -0,0 +1,56 @@
+//! An exponential voltage source.
+
+use serde::{Deserialize, Serialize};
+
+use crate::component::Component;
+use crate::deps::arcstr::ArcStr;
+use crate::schematic::circuit::Direction;
+use crate::schematic::primitive::{PrimitiveDevice, PrimitiveKind};
+use crate::units::SiValue;
+
+/// An exponential voltage source.
+///
+/// The output rises exponentially from `v1` toward `v2` starting at `td1`,
+/// then decays back toward `v1` starting at `td2`.
+#[derive(Copy, Clone, Eq, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Vexp {
+    /// Initial value (volts).
+    pub v1: SiValue,
+    /// Pulsed value (volts).
+    pub v2: SiValue,
+    /// Rise delay time (seconds).
+    pub td1: SiValue,
+    /// Rise time constant (seconds).
+    pub tau1: SiValue,
+    /// Fall delay time (seconds).
+    pub td2: SiValue,
+    /// Fall time constant (seconds).
+    pub tau2: SiValue,
+}
+
+impl Component for Vexp {
+    type Params = Vexp;
+
+    fn new(params: &Self::Params, _ctx: &crate::data::SubstrateCtx) -> crate::error::Result<Self> {
+        Ok(*params)
+    }
+
+    fn name(&self) -> ArcStr {
+        arcstr::literal!("vexp")
+    }
+
+    fn schematic(
+        &self,
+        ctx: &mut crate::schematic::context::SchematicCtx,
+    ) -> crate::error::Result<()> {
+        let p = ctx.port("p", Direction::InOut);
+        let n = ctx.port("n", Direction::InOut);
+
+        ctx.add_primitive(PrimitiveDevice::new(
+            "src",
+            PrimitiveKind::Vexp(*self),
+            [p, n],
+        ));
+        Ok(())
+    }
+}
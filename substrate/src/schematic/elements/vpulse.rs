@@ -5,6 +5,7 @@ use serde::{Deserialize, Serialize};
 use crate::component::Component;
 use crate::deps::arcstr::ArcStr;
 use crate::schematic::circuit::Direction;
+use crate::schematic::primitive::{PrimitiveDevice, PrimitiveKind};
 use crate::units::SiValue;
 
 /// A pulse voltage source.
@@ -41,12 +42,13 @@ impl Component for Vpulse {
         &self,
         ctx: &mut crate::schematic::context::SchematicCtx,
     ) -> crate::error::Result<()> {
-        let _p = ctx.port("p", Direction::InOut);
-        let _n = ctx.port("n", Direction::InOut);
+        let p = ctx.port("p", Direction::InOut);
+        let n = ctx.port("n", Direction::InOut);
 
-        ctx.set_spice(format!(
-            "V1 p n PULSE({} {} {} {} {} {} {})",
-            self.v1, self.v2, self.td, self.tr, self.tf, self.pw, self.period
+        ctx.add_primitive(PrimitiveDevice::new(
+            "src",
+            PrimitiveKind::Vpulse(*self),
+            [p, n],
         ));
         Ok(())
     }
@@ -1,11 +1,11 @@
 //! A piece-wise linear voltage source.
 
-use std::fmt::Write;
 use std::sync::Arc;
 
 use crate::component::Component;
 use crate::deps::arcstr::ArcStr;
 use crate::schematic::circuit::Direction;
+use crate::schematic::primitive::{PrimitiveDevice, PrimitiveKind};
 use crate::verification::simulation::waveform::{TimeWaveform, Waveform};
 
 /// A piece-wise linear voltage source.
@@ -27,15 +27,14 @@ impl Component for Vpwl {
         &self,
         ctx: &mut crate::schematic::context::SchematicCtx,
     ) -> crate::error::Result<()> {
-        let _p = ctx.port("p", Direction::InOut);
-        let _n = ctx.port("n", Direction::InOut);
-
-        let mut spice = String::from("V1 p n PWL(");
-        for pt in self.0.values() {
-            write!(&mut spice, " {} {}", pt.t(), pt.x()).unwrap();
-        }
-        write!(&mut spice, " )").unwrap();
-        ctx.set_spice(spice);
+        let p = ctx.port("p", Direction::InOut);
+        let n = ctx.port("n", Direction::InOut);
+
+        ctx.add_primitive(PrimitiveDevice::new(
+            "src",
+            PrimitiveKind::Vpwl(self.0.clone()),
+            [p, n],
+        ));
         Ok(())
     }
 }
@@ -0,0 +1,53 @@
+//! A sinusoidal voltage source.
+
+use serde::{Deserialize, Serialize};
+
+use crate::component::Component;
+use crate::deps::arcstr::ArcStr;
+use crate::schematic::circuit::Direction;
+use crate::schematic::primitive::{PrimitiveDevice, PrimitiveKind};
+use crate::units::SiValue;
+
+/// A sinusoidal voltage source.
+#[derive(Copy, Clone, Eq, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Vsin {
+    /// Offset (volts).
+    pub vo: SiValue,
+    /// Amplitude (volts).
+    pub va: SiValue,
+    /// Frequency (hertz).
+    pub freq: SiValue,
+    /// Delay time (seconds).
+    pub td: SiValue,
+    /// Damping factor (1/seconds).
+    pub theta: SiValue,
+    /// Phase (degrees).
+    pub phase: SiValue,
+}
+
+impl Component for Vsin {
+    type Params = Vsin;
+
+    fn new(params: &Self::Params, _ctx: &crate::data::SubstrateCtx) -> crate::error::Result<Self> {
+        Ok(*params)
+    }
+
+    fn name(&self) -> ArcStr {
+        arcstr::literal!("vsin")
+    }
+
+    fn schematic(
+        &self,
+        ctx: &mut crate::schematic::context::SchematicCtx,
+    ) -> crate::error::Result<()> {
+        let p = ctx.port("p", Direction::InOut);
+        let n = ctx.port("n", Direction::InOut);
+
+        ctx.add_primitive(PrimitiveDevice::new(
+            "src",
+            PrimitiveKind::Vsin(*self),
+            [p, n],
+        ));
+        Ok(())
+    }
+}
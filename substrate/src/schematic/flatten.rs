@@ -0,0 +1,654 @@
+//! Hierarchical schematic flattening and net tracing.
+//!
+//! A [`FlatSchematic`] is a device-level view of a generated schematic.
+//! Every bit of every hierarchical net is mapped to a canonical flat net,
+//! which is named by its least nested reference.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::Arc;
+
+use slotmap::SecondaryMap;
+use subspice::parser::{InstanceKind, SpiceLine};
+
+use super::circuit::{Direction, Reference, Value};
+use super::context::ModuleKey;
+use super::module::{AbstractModule, ExternalModule, Module};
+use super::netlist::preprocess::PreprocessedNetlist;
+use super::primitive::PrimitiveKind;
+use super::signal::{NamedSignalPathBuf, SignalKey};
+use crate::deps::arcstr::ArcStr;
+use crate::error::{with_err_context, ErrorContext, ErrorSource, Result};
+use crate::fmt::signal::{format_signal, BusFmt};
+use crate::index::IndexOwned;
+use crate::pdk::mos::db::MosDb;
+use crate::pdk::mos::spec::MosSpec;
+
+/// An identifier for a net in a [`FlatSchematic`].
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Ord, PartialOrd)]
+pub struct NetId(usize);
+
+/// An identifier for a device in a [`FlatSchematic`].
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Ord, PartialOrd)]
+pub struct DeviceId(usize);
+
+/// A fully flattened schematic.
+#[derive(Debug, Clone)]
+pub struct FlatSchematic {
+    name: ArcStr,
+    nets: Vec<FlatNet>,
+    devices: Vec<FlatDevice>,
+    ports: Vec<FlatPort>,
+    net_map: HashMap<NamedSignalPathBuf, NetId>,
+}
+
+/// A net in a [`FlatSchematic`].
+#[derive(Debug, Clone)]
+pub struct FlatNet {
+    name: NamedSignalPathBuf,
+    terminals: Vec<FlatTerminal>,
+}
+
+/// A connection from a device terminal to a net.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct FlatTerminal {
+    /// The device.
+    pub device: DeviceId,
+    /// The name of the device terminal.
+    pub terminal: ArcStr,
+}
+
+/// A leaf device in a [`FlatSchematic`].
+#[derive(Debug, Clone)]
+pub struct FlatDevice {
+    path: Vec<ArcStr>,
+    kind: FlatDeviceKind,
+    terminals: Vec<(ArcStr, NetId)>,
+    directions: Vec<Direction>,
+}
+
+/// An enumeration of leaf device kinds.
+#[derive(Debug, Clone)]
+pub enum FlatDeviceKind {
+    /// A primitive device.
+    Primitive(PrimitiveKind),
+    /// An instance of an [`ExternalModule`], treated as a black box.
+    External {
+        /// The name of the external module.
+        module: ArcStr,
+        /// The parameters of the instance.
+        params: HashMap<ArcStr, Value>,
+    },
+    /// A module containing a raw SPICE literal, such as a PDK MOSFET.
+    ///
+    /// The terminals of the device are the ports of the module.
+    RawSpice {
+        /// The name of the module.
+        module: ArcStr,
+        /// The raw SPICE literal.
+        spice: ArcStr,
+    },
+    /// An instance of a local module that was not expanded
+    /// because it lies below the maximum flattening depth.
+    ///
+    /// The terminals of the device are the ports of the module.
+    Instance {
+        /// The name of the module.
+        module: ArcStr,
+    },
+}
+
+/// A bit of a port of the top-level module of a [`FlatSchematic`].
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct FlatPort {
+    /// The name of the port.
+    pub name: ArcStr,
+    /// The bit index of the port, if the port is a bus.
+    pub idx: Option<usize>,
+    /// The direction of the port.
+    pub direction: Direction,
+    /// The net connected to the port.
+    pub net: NetId,
+}
+
+/// A MOSFET found in the raw SPICE of a leaf device.
+pub(crate) struct FlatMos<'a> {
+    /// The leaf device containing the MOSFET.
+    pub(crate) device: DeviceId,
+    /// The name of the MOSFET's SPICE element, such as `M0` or `X0`.
+    pub(crate) element: ArcStr,
+    pub(crate) spec: &'a MosSpec,
+    /// The nets connected to the drain, gate, source, and bulk.
+    pub(crate) nets: [NetId; 4],
+}
+
+/// A path between two nets through a chain of devices.
+///
+/// Device `devices[i]` connects net `nets[i]` to net `nets[i + 1]`.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct NetPath {
+    /// The nets along the path, including both endpoints.
+    pub nets: Vec<NetId>,
+    /// The devices along the path.
+    pub devices: Vec<DeviceId>,
+}
+
+impl NetId {
+    /// The index of the net within its [`FlatSchematic`].
+    #[inline]
+    pub(crate) fn index(self) -> usize {
+        self.0
+    }
+}
+
+impl DeviceId {
+    /// The index of the device within its [`FlatSchematic`].
+    #[inline]
+    pub(crate) fn index(self) -> usize {
+        self.0
+    }
+}
+
+impl FlatNet {
+    /// The canonical name of the net.
+    ///
+    /// This is the least nested hierarchical reference to the net.
+    #[inline]
+    pub fn name(&self) -> &NamedSignalPathBuf {
+        &self.name
+    }
+
+    /// The device terminals connected to the net.
+    #[inline]
+    pub fn terminals(&self) -> &[FlatTerminal] {
+        &self.terminals
+    }
+}
+
+impl FlatDevice {
+    /// The hierarchical path to the device, ending with the device name.
+    #[inline]
+    pub fn path(&self) -> &[ArcStr] {
+        &self.path
+    }
+
+    /// The kind of the device.
+    #[inline]
+    pub fn kind(&self) -> &FlatDeviceKind {
+        &self.kind
+    }
+
+    /// The terminals of the device and the nets to which they are connected.
+    #[inline]
+    pub fn terminals(&self) -> &[(ArcStr, NetId)] {
+        &self.terminals
+    }
+
+    /// Returns the net connected to the terminal with the given name.
+    pub fn terminal(&self, name: &str) -> Option<NetId> {
+        self.terminals
+            .iter()
+            .find(|(terminal, _)| terminal == name)
+            .map(|(_, net)| *net)
+    }
+
+    /// Returns the direction of the terminal with the given name.
+    ///
+    /// Terminals of primitive devices are always [`Direction::InOut`].
+    pub fn terminal_direction(&self, name: &str) -> Option<Direction> {
+        self.terminals
+            .iter()
+            .position(|(terminal, _)| terminal == name)
+            .map(|i| self.directions[i])
+    }
+}
+
+impl FlatSchematic {
+    /// The name of the top-level module.
+    #[inline]
+    pub fn name(&self) -> &ArcStr {
+        &self.name
+    }
+
+    /// Iterates over all nets.
+    pub fn nets(&self) -> impl Iterator<Item = (NetId, &FlatNet)> {
+        self.nets.iter().enumerate().map(|(i, net)| (NetId(i), net))
+    }
+
+    /// Iterates over all leaf devices.
+    pub fn devices(&self) -> impl Iterator<Item = (DeviceId, &FlatDevice)> {
+        self.devices
+            .iter()
+            .enumerate()
+            .map(|(i, device)| (DeviceId(i), device))
+    }
+
+    /// Returns the net with the given ID.
+    #[inline]
+    pub fn net(&self, id: NetId) -> &FlatNet {
+        &self.nets[id.0]
+    }
+
+    /// Returns the device with the given ID.
+    #[inline]
+    pub fn device(&self, id: DeviceId) -> &FlatDevice {
+        &self.devices[id.0]
+    }
+
+    /// The bits of the ports of the top-level module.
+    #[inline]
+    pub fn ports(&self) -> &[FlatPort] {
+        &self.ports
+    }
+
+    /// Returns the net connected to the given bit of a top-level port.
+    ///
+    /// The index should be `None` for single-bit ports.
+    pub fn port(&self, name: &str, idx: Option<usize>) -> Option<NetId> {
+        self.ports
+            .iter()
+            .find(|port| port.name.as_str() == name && port.idx == idx)
+            .map(|port| port.net)
+    }
+
+    /// Returns the canonical net for a hierarchical net.
+    pub fn find_net(&self, path: &NamedSignalPathBuf) -> Option<NetId> {
+        self.net_map.get(path).copied()
+    }
+
+    /// Iterates over every hierarchical net and the canonical net to which it resolves.
+    pub fn hierarchical_nets(&self) -> impl Iterator<Item = (&NamedSignalPathBuf, NetId)> {
+        self.net_map.iter().map(|(path, id)| (path, *id))
+    }
+
+    /// Iterates over all hierarchical references to the given net.
+    pub fn aliases(&self, net: NetId) -> impl Iterator<Item = &NamedSignalPathBuf> {
+        self.net_map
+            .iter()
+            .filter(move |(_, id)| **id == net)
+            .map(|(path, _)| path)
+    }
+
+    /// Returns the distinct devices connected to the given net.
+    pub fn devices_on(&self, net: NetId) -> Vec<DeviceId> {
+        let mut seen = HashSet::new();
+        self.net(net)
+            .terminals
+            .iter()
+            .map(|terminal| terminal.device)
+            .filter(|&device| seen.insert(device))
+            .collect()
+    }
+
+    /// Returns the number of distinct devices connected to the given top-level port.
+    ///
+    /// Returns `None` if no such port exists.
+    pub fn fanout(&self, port: &str, idx: Option<usize>) -> Option<usize> {
+        self.port(port, idx).map(|net| self.devices_on(net).len())
+    }
+
+    /// Finds the MOSFETs in the raw SPICE of all leaf devices.
+    ///
+    /// MOSFETs are identified by looking up their models in the given [`MosDb`].
+    /// Devices whose raw SPICE cannot be parsed are added to `skipped`.
+    pub(crate) fn find_mos<'a>(
+        &self,
+        mos_db: &'a MosDb,
+        skipped: &mut Vec<DeviceId>,
+    ) -> Vec<FlatMos<'a>> {
+        let mut mos = Vec::new();
+        for (id, device) in self.devices() {
+            let spice = match device.kind() {
+                FlatDeviceKind::RawSpice { spice, .. } => spice,
+                _ => continue,
+            };
+            let parsed = match subspice::parse(spice) {
+                Ok(parsed) => parsed,
+                Err(_) => {
+                    skipped.push(id);
+                    continue;
+                }
+            };
+            for line in parsed.lines() {
+                let inst = match line {
+                    SpiceLine::Instance(inst)
+                        if matches!(inst.kind, InstanceKind::Mos | InstanceKind::Subckt)
+                            && inst.nodes.len() == 4 =>
+                    {
+                        inst
+                    }
+                    _ => continue,
+                };
+                let spec = inst
+                    .model
+                    .and_then(|model| mos_db.get_spec_from_name(model).ok());
+                let nets = inst
+                    .nodes
+                    .iter()
+                    .map(|node| device.terminal(node))
+                    .collect::<Option<Vec<_>>>();
+                if let (Some(spec), Some(nets)) = (spec, nets) {
+                    mos.push(FlatMos {
+                        device: id,
+                        element: inst.name.into(),
+                        spec,
+                        nets: [nets[0], nets[1], nets[2], nets[3]],
+                    });
+                }
+            }
+        }
+        mos
+    }
+
+    /// Finds a shortest path of devices connecting two nets.
+    ///
+    /// Returns `None` if the nets are not connected.
+    pub fn path(&self, from: NetId, to: NetId) -> Option<NetPath> {
+        self.path_excluding(from, to, &[])
+    }
+
+    /// Finds a shortest path of devices connecting two nets,
+    /// without passing through any of the nets in `exclude`.
+    ///
+    /// Excluding supply nets is useful to avoid trivial paths through power and ground.
+    pub fn path_excluding(&self, from: NetId, to: NetId, exclude: &[NetId]) -> Option<NetPath> {
+        let mut prev: HashMap<NetId, (NetId, DeviceId)> = HashMap::new();
+        let mut visited = HashSet::from([from]);
+        let mut queue = VecDeque::from([from]);
+
+        while let Some(net) = queue.pop_front() {
+            if net == to {
+                let mut nets = vec![to];
+                let mut devices = Vec::new();
+                let mut curr = to;
+                while let Some(&(p, device)) = prev.get(&curr) {
+                    nets.push(p);
+                    devices.push(device);
+                    curr = p;
+                }
+                nets.reverse();
+                devices.reverse();
+                return Some(NetPath { nets, devices });
+            }
+            if net != from && exclude.contains(&net) {
+                continue;
+            }
+            for device in self.devices_on(net) {
+                for &(_, next) in self.device(device).terminals.iter() {
+                    if visited.insert(next) {
+                        prev.insert(next, (net, device));
+                        queue.push_back(next);
+                    }
+                }
+            }
+        }
+        None
+    }
+}
+
+/// Flattens the top-level module of a preprocessed netlist.
+pub(crate) fn flatten(
+    netlist: &PreprocessedNetlist,
+    ext_modules: &HashMap<ArcStr, Arc<ExternalModule>>,
+) -> Result<FlatSchematic> {
+    flatten_to_depth(netlist, ext_modules, None)
+}
+
+/// Flattens the top-level module of a preprocessed netlist,
+/// expanding at most `max_depth` levels of hierarchy.
+///
+/// The top-level module is at depth 0. Instances of local modules deeper than
+/// `max_depth` become [`FlatDeviceKind::Instance`] devices. If `max_depth` is
+/// [`None`], the entire hierarchy is expanded.
+pub(crate) fn flatten_to_depth(
+    netlist: &PreprocessedNetlist,
+    ext_modules: &HashMap<ArcStr, Arc<ExternalModule>>,
+    max_depth: Option<usize>,
+) -> Result<FlatSchematic> {
+    let top = &netlist.modules[netlist.top];
+    let mut flattener = Flattener {
+        netlist,
+        ext_modules,
+        max_depth,
+        out: FlatSchematic {
+            name: top.name().clone(),
+            nets: Vec::new(),
+            devices: Vec::new(),
+            ports: Vec::new(),
+            net_map: HashMap::new(),
+        },
+        path: Vec::new(),
+    };
+
+    let mut bits = SecondaryMap::new();
+    for port in top.raw_ports() {
+        let info = top.port_info(port);
+        let nets = (0..info.width())
+            .map(|i| flattener.new_net(info.name(), i, info.width()))
+            .collect::<Vec<_>>();
+        for (i, &net) in nets.iter().enumerate() {
+            flattener.out.ports.push(FlatPort {
+                name: info.name().clone(),
+                idx: (info.width() > 1).then_some(i),
+                direction: port.direction(),
+                net,
+            });
+        }
+        bits.insert(port.signal, nets);
+    }
+
+    with_err_context(flattener.flatten_module(netlist.top, bits), || {
+        ErrorContext::Task(arcstr::format!("flattening schematic {}", top.name()))
+    })?;
+    Ok(flattener.out)
+}
+
+/// The state of a schematic flattener.
+struct Flattener<'a> {
+    netlist: &'a PreprocessedNetlist,
+    ext_modules: &'a HashMap<ArcStr, Arc<ExternalModule>>,
+    max_depth: Option<usize>,
+    out: FlatSchematic,
+    /// The instance names leading to the module currently being flattened.
+    path: Vec<ArcStr>,
+}
+
+impl<'a> Flattener<'a> {
+    fn named_path(&self, name: &ArcStr, idx: usize, width: usize) -> NamedSignalPathBuf {
+        NamedSignalPathBuf {
+            insts: self.path.clone(),
+            signal: name.clone(),
+            idx: (width > 1).then_some(idx),
+        }
+    }
+
+    fn new_net(&mut self, name: &ArcStr, idx: usize, width: usize) -> NetId {
+        let id = NetId(self.out.nets.len());
+        let name = self.named_path(name, idx, width);
+        self.out.net_map.insert(name.clone(), id);
+        self.out.nets.push(FlatNet {
+            name,
+            terminals: Vec::new(),
+        });
+        id
+    }
+
+    fn add_device(
+        &mut self,
+        name: Option<&ArcStr>,
+        kind: FlatDeviceKind,
+        terminals: Vec<(ArcStr, NetId, Direction)>,
+    ) {
+        let id = DeviceId(self.out.devices.len());
+        for (terminal, net, _) in terminals.iter() {
+            self.out.nets[net.0].terminals.push(FlatTerminal {
+                device: id,
+                terminal: terminal.clone(),
+            });
+        }
+        let mut path = self.path.clone();
+        path.extend(name.cloned());
+        let (terminals, directions) = terminals
+            .into_iter()
+            .map(|(terminal, net, direction)| ((terminal, net), direction))
+            .unzip();
+        self.out.devices.push(FlatDevice {
+            path,
+            kind,
+            terminals,
+            directions,
+        });
+    }
+
+    /// Flattens a module, given the nets connected to each of its ports.
+    fn flatten_module(
+        &mut self,
+        key: ModuleKey,
+        mut bits: SecondaryMap<SignalKey, Vec<NetId>>,
+    ) -> Result<()> {
+        let netlist = self.netlist;
+        let module = &netlist.modules[key];
+
+        for (signal, info) in module.signals().iter() {
+            if let Some(nets) = bits.get(signal) {
+                // Record the hierarchical names of nets that pass through ports.
+                for (i, &net) in nets.iter().enumerate() {
+                    let name = self.named_path(info.name(), i, info.width());
+                    self.out.net_map.entry(name).or_insert(net);
+                }
+            } else {
+                let nets = (0..info.width())
+                    .map(|i| self.new_net(info.name(), i, info.width()))
+                    .collect();
+                bits.insert(signal, nets);
+            }
+        }
+
+        if let Some(spice) = module.raw_spice() {
+            let terminals = port_terminals(module, &bits);
+            self.add_device(
+                None,
+                FlatDeviceKind::RawSpice {
+                    module: module.name().clone(),
+                    spice: spice.into(),
+                },
+                terminals,
+            );
+        }
+
+        for device in module.primitives() {
+            let terminals = device
+                .kind()
+                .terminals()
+                .iter()
+                .zip(device.connections())
+                .map(|(&terminal, slice)| {
+                    (
+                        ArcStr::from(terminal),
+                        bits[slice.signal][slice.idx],
+                        Direction::InOut,
+                    )
+                })
+                .collect();
+            self.add_device(
+                Some(device.name()),
+                FlatDeviceKind::Primitive(device.kind().clone()),
+                terminals,
+            );
+        }
+
+        for inst in module.instances() {
+            match inst.module() {
+                Reference::Local(submodule) => {
+                    let submodule = &netlist.modules[submodule.id()];
+                    let mut child_bits = SecondaryMap::new();
+                    for port in submodule.raw_ports() {
+                        let info = submodule.port_info(port);
+                        if let Some(conn) = inst.connections().get(info.name()) {
+                            let nets = (0..info.width())
+                                .map(|i| {
+                                    let slice = conn.index(i).into_single();
+                                    bits[slice.signal][slice.idx]
+                                })
+                                .collect();
+                            child_bits.insert(port.signal, nets);
+                        }
+                    }
+                    if self
+                        .max_depth
+                        .map(|max| self.path.len() < max)
+                        .unwrap_or(true)
+                    {
+                        self.path.push(inst.name().clone());
+                        self.flatten_module(submodule.id(), child_bits)?;
+                        self.path.pop();
+                    } else {
+                        self.add_device(
+                            Some(inst.name()),
+                            FlatDeviceKind::Instance {
+                                module: submodule.name().clone(),
+                            },
+                            port_terminals(submodule, &child_bits),
+                        );
+                    }
+                }
+                Reference::External(name) => {
+                    let ext = self
+                        .ext_modules
+                        .get(&name)
+                        .ok_or_else(|| ErrorSource::ModuleNotFound(name.to_string()))?;
+                    let mut terminals = Vec::new();
+                    for port in ext.raw_ports() {
+                        let info = ext.port_info(port);
+                        let conn = match inst.connections().get(info.name()) {
+                            Some(conn) => conn,
+                            None => continue,
+                        };
+                        for i in 0..info.width() {
+                            let slice = conn.index(i).into_single();
+                            terminals.push((
+                                format_signal(info.name(), i, info.width(), BusFmt::default()),
+                                bits[slice.signal][slice.idx],
+                                port.direction(),
+                            ));
+                        }
+                    }
+                    self.add_device(
+                        Some(inst.name()),
+                        FlatDeviceKind::External {
+                            module: name,
+                            params: inst.params().clone(),
+                        },
+                        terminals,
+                    );
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Lists the nets connected to each bit of the ports of a module.
+///
+/// Ports without an entry in `bits` are skipped.
+fn port_terminals(
+    module: &Module,
+    bits: &SecondaryMap<SignalKey, Vec<NetId>>,
+) -> Vec<(ArcStr, NetId, Direction)> {
+    let mut terminals = Vec::new();
+    for port in module.raw_ports() {
+        let info = module.port_info(port);
+        let nets = match bits.get(port.signal) {
+            Some(nets) => nets,
+            None => continue,
+        };
+        for (i, &net) in nets.iter().enumerate() {
+            terminals.push((
+                format_signal(info.name(), i, info.width(), BusFmt::default()),
+                net,
+                port.direction(),
+            ));
+        }
+    }
+    terminals
+}
@@ -0,0 +1,498 @@
+//! Importing SPICE netlists as Substrate modules.
+//!
+//! Unlike [`SchematicCtx::import_spice`], which wraps a subcircuit in an opaque
+//! [`ExternalModule`], the [`SpiceSubckt`] component converts the contents of a
+//! subcircuit into instances, primitive devices and signals. Imported modules can
+//! therefore be validated and re-netlisted like any other schematic.
+
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+use subspice::parser::{
+    is_expr, parse_value, strip_delimiters, InstanceKind, InstanceLine, Param, SpiceLine,
+};
+
+use super::circuit::{Direction, Instance, Value};
+use super::context::SchematicCtx;
+use super::elements::diode::Diode;
+use super::elements::mos::SchematicMos;
+use super::module::{ExternalModule, RawSource};
+use super::primitive::{PrimitiveDevice, PrimitiveKind};
+use super::signal::Slice;
+use crate::component::Component;
+use crate::data::SubstrateCtx;
+use crate::deps::arcstr::ArcStr;
+use crate::error::{with_err_context, ErrorContext, ErrorSource, Result};
+use crate::pdk::mos::MosParams;
+use crate::units::{SiPrefix, SiValue};
+
+/// The parameters of a [`SpiceSubckt`].
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct SpiceSubcktParams {
+    /// The path to the SPICE netlist.
+    pub path: PathBuf,
+    /// The name of the subcircuit to import.
+    pub subckt: ArcStr,
+    /// The unit in which MOSFET widths and lengths are given.
+    ///
+    /// Netlists extracted from many PDKs, including Sky130, specify
+    /// widths and lengths in microns rather than meters.
+    pub length_unit: SiPrefix,
+}
+
+impl SpiceSubcktParams {
+    /// Creates a new [`SpiceSubcktParams`], with MOSFET dimensions given in meters.
+    pub fn new(path: impl Into<PathBuf>, subckt: impl Into<ArcStr>) -> Self {
+        Self {
+            path: path.into(),
+            subckt: subckt.into(),
+            length_unit: SiPrefix::None,
+        }
+    }
+
+    /// A consuming method to set the unit of MOSFET widths and lengths.
+    pub fn with_length_unit(mut self, length_unit: SiPrefix) -> Self {
+        self.length_unit = length_unit;
+        self
+    }
+}
+
+/// A subcircuit imported from a SPICE netlist.
+///
+/// Devices are imported as follows:
+/// * `R`, `C` and `L` lines with a numeric value become primitive devices.
+///   Primitive devices only store numbers, so values given as parameter
+///   expressions, such as `C1 p n {c}`, are rejected.
+/// * `D` lines become primitive [`Diode`]s.
+/// * `V` and `I` lines with a DC or AC value become primitive sources.
+/// * `M` lines, and `X` lines with four nodes, whose model is a MOSFET
+///   in the PDK's [`MosDb`](crate::pdk::mos::db::MosDb) become [`SchematicMos`] instances.
+/// * `X` lines referring to a subcircuit in the same netlist
+///   become instances of another [`SpiceSubckt`].
+///
+/// All other devices, including `R` and `C` lines with a model name,
+/// become instances of [`ExternalModule`]s with the model's name.
+/// If no such external module has been added to the context,
+/// one is created without a source, so the file that defines the model must be
+/// included separately.
+///
+/// Subcircuit parameters and `.param` statements within the subcircuit become module
+/// parameters, and instance parameters of subcircuits and external modules are
+/// preserved as parameter overrides. `.include` and `.lib` statements are not followed.
+///
+/// Any other line in the subcircuit body, such as a controlled source (`E`, `F`, `G`, `H`),
+/// behavioral source (`B`), or bipolar transistor (`Q`), causes the import to fail
+/// with [`ErrorSource::SpiceImport`].
+pub struct SpiceSubckt {
+    params: SpiceSubcktParams,
+    spice: String,
+}
+
+impl Component for SpiceSubckt {
+    type Params = SpiceSubcktParams;
+
+    fn new(params: &Self::Params, _ctx: &SubstrateCtx) -> Result<Self> {
+        Ok(Self {
+            params: params.clone(),
+            spice: crate::io::read_to_string(&params.path)?,
+        })
+    }
+
+    fn name(&self) -> ArcStr {
+        self.params.subckt.clone()
+    }
+
+    fn schematic(&self, ctx: &mut SchematicCtx) -> Result<()> {
+        with_err_context(self.import(ctx), || {
+            ErrorContext::Task(arcstr::format!(
+                "importing SPICE subcircuit `{}` from {:?}",
+                self.params.subckt,
+                self.params.path
+            ))
+        })
+    }
+}
+
+impl SpiceSubckt {
+    fn import(&self, ctx: &mut SchematicCtx) -> Result<()> {
+        let parsed = subspice::parse(&self.spice)?;
+        let name = &self.params.subckt;
+        let subckt = parsed
+            .subcircuit_named(name)
+            .ok_or_else(|| ErrorSource::ModuleNotFound(name.to_string()))?;
+        let subckts = parsed.subcircuits().map(|ckt| ckt.name).collect();
+
+        let mut nodes = HashMap::new();
+        for &port in subckt.ports.iter() {
+            nodes
+                .entry(port)
+                .or_insert_with(|| ctx.port(port, Direction::InOut));
+        }
+        for param in subckt.params.iter() {
+            ctx.param(param.name, to_value(param.value));
+        }
+
+        let mut importer = Importer {
+            params: &self.params,
+            subckts,
+            nodes,
+        };
+
+        for line in parsed.subcircuit_body(name).unwrap_or_default() {
+            match line {
+                SpiceLine::Param(params) => {
+                    for param in params.iter() {
+                        ctx.param(param.name, to_value(param.value));
+                    }
+                }
+                SpiceLine::Instance(inst) => importer.import_instance(ctx, inst)?,
+                SpiceLine::Other(line) => {
+                    return Err(ErrorSource::SpiceImport(format!(
+                        "cannot import unsupported line `{line}`"
+                    ))
+                    .into());
+                }
+                _ => (),
+            }
+        }
+
+        Ok(())
+    }
+}
+
+struct Importer<'a> {
+    params: &'a SpiceSubcktParams,
+    subckts: HashSet<&'a str>,
+    nodes: HashMap<&'a str, Slice>,
+}
+
+impl<'a> Importer<'a> {
+    fn node(&mut self, ctx: &mut SchematicCtx, name: &'a str) -> Slice {
+        *self.nodes.entry(name).or_insert_with(|| ctx.signal(name))
+    }
+
+    fn import_instance(&mut self, ctx: &mut SchematicCtx, inst: &InstanceLine<'a>) -> Result<()> {
+        let conns = inst
+            .nodes
+            .iter()
+            .map(|&node| self.node(ctx, node))
+            .collect::<Vec<_>>();
+
+        match (inst.kind, inst.model) {
+            (InstanceKind::Resistor | InstanceKind::Capacitor | InstanceKind::Inductor, None) => {
+                let key = match inst.kind {
+                    InstanceKind::Resistor => "r",
+                    InstanceKind::Capacitor => "c",
+                    _ => "l",
+                };
+                let value = inst
+                    .values
+                    .first()
+                    .copied()
+                    .or_else(|| find_param(&inst.params, key))
+                    .ok_or_else(|| unsupported(inst, "missing value"))?;
+                if is_expr(value) && parse_value(value).is_none() {
+                    return Err(unsupported(
+                        inst,
+                        &format!("parameter expression `{value}` is not supported as a value"),
+                    ));
+                }
+                let value = to_si_value(parse_number(inst, value)?);
+                let kind = match inst.kind {
+                    InstanceKind::Resistor => PrimitiveKind::Resistor(value),
+                    InstanceKind::Capacitor => PrimitiveKind::Capacitor(value),
+                    _ => PrimitiveKind::Inductor(value),
+                };
+                add_primitive(ctx, inst, kind, conns);
+            }
+            (InstanceKind::Diode, Some(model)) => {
+                let area = inst
+                    .values
+                    .first()
+                    .copied()
+                    .or_else(|| find_param(&inst.params, "area"))
+                    .map(|area| parse_number(inst, area).map(to_si_value))
+                    .transpose()?;
+                let kind = PrimitiveKind::Diode(Diode {
+                    model: model.into(),
+                    area,
+                });
+                add_primitive(ctx, inst, kind, conns);
+            }
+            (InstanceKind::Vsource | InstanceKind::Isource, _) => {
+                let kind = source_kind(inst)?;
+                add_primitive(ctx, inst, kind, conns);
+            }
+            (InstanceKind::Subckt, Some(model)) if self.subckts.contains(model) => {
+                let params = SpiceSubcktParams {
+                    subckt: model.into(),
+                    ..self.params.clone()
+                };
+                let mut child = ctx.instantiate::<SpiceSubckt>(&params)?;
+                let ports = child.ports()?.map(|port| port.name).collect::<Vec<_>>();
+                if ports.len() != conns.len() {
+                    return Err(unsupported(inst, "wrong number of connections"));
+                }
+                child.connect_all(ports.into_iter().zip(conns));
+                add_instance(ctx, inst, child);
+            }
+            (InstanceKind::Mos | InstanceKind::Subckt, Some(model))
+                if conns.len() == 4 && ctx.mos_db().get_spec_from_name(model).is_ok() =>
+            {
+                let params = self.mos_params(ctx, inst, model)?;
+                let mos = ctx
+                    .instantiate::<SchematicMos>(&params)?
+                    .with_connections(["d", "g", "s", "b"].into_iter().zip(conns));
+                add_instance(ctx, inst, mos);
+            }
+            (kind, Some(model)) => {
+                let ports = match kind {
+                    InstanceKind::Mos => vec!["d".into(), "g".into(), "s".into(), "b".into()],
+                    InstanceKind::Diode => vec!["a".into(), "c".into()],
+                    InstanceKind::Subckt => {
+                        (0..conns.len()).map(|i| arcstr::format!("p{i}")).collect()
+                    }
+                    _ => vec!["p".into(), "n".into()],
+                };
+                let mut ext = external_instance(ctx, model, ports, conns.len())?;
+                for param in inst.params.iter() {
+                    ext.set_param(param.name, to_value(param.value));
+                }
+                add_instance(ctx, inst, ext);
+            }
+            (_, None) => return Err(unsupported(inst, "missing model")),
+        }
+
+        Ok(())
+    }
+
+    fn mos_params(
+        &self,
+        ctx: &SchematicCtx,
+        inst: &InstanceLine,
+        model: &str,
+    ) -> Result<MosParams> {
+        let id = ctx.mos_db().get_spec_from_name(model)?.id;
+        let scale =
+            self.params.length_unit.multiplier() / ctx.pdk().lengths().schematic.multiplier();
+        let number = |key: &str| {
+            find_param(&inst.params, key)
+                .map(|value| parse_number(inst, value))
+                .transpose()
+        };
+
+        let w = number("w")?.ok_or_else(|| unsupported(inst, "missing width"))?;
+        let l = number("l")?.ok_or_else(|| unsupported(inst, "missing length"))?;
+        let nf = number("nf")?.unwrap_or(1.).round().max(1.) as u64;
+        let m = number("m")?.unwrap_or(1.).round().max(1.) as u64;
+
+        Ok(MosParams {
+            w: (w * scale / nf as f64).round() as i64,
+            l: (l * scale).round() as i64,
+            m,
+            nf,
+            id,
+        })
+    }
+}
+
+fn add_primitive(
+    ctx: &mut SchematicCtx,
+    inst: &InstanceLine,
+    kind: PrimitiveKind,
+    conns: Vec<Slice>,
+) {
+    ctx.add_primitive(PrimitiveDevice::new(device_name(inst.name), kind, conns));
+}
+
+fn add_instance(ctx: &mut SchematicCtx, inst: &InstanceLine, instance: Instance) {
+    let name = if inst.kind == InstanceKind::Subckt {
+        device_name(inst.name)
+    } else {
+        inst.name
+    };
+    ctx.add_instance(instance.named(name));
+}
+
+/// Returns an instance of the external module named `model`,
+/// creating the module with the given ports if it does not exist.
+fn external_instance(
+    ctx: &mut SchematicCtx,
+    model: &str,
+    ports: Vec<ArcStr>,
+    num_conns: usize,
+) -> Result<Instance> {
+    let ports = match ctx.inner().get_external_module(&model) {
+        Ok(module) => module
+            .ports
+            .iter()
+            .map(|port| module.signals()[port.signal].name().clone())
+            .collect(),
+        Err(_) => {
+            let mut builder = ExternalModule::builder()
+                .name(model)
+                .source(RawSource::ManualInclude);
+            for port in ports.iter() {
+                builder = builder.add_port(port.clone(), 1, Direction::InOut);
+            }
+            ctx.inner().add_external_module(builder.build())?;
+            ports
+        }
+    };
+    if ports.len() != num_conns {
+        return Err(ErrorSource::SpiceImport(format!(
+            "external module `{model}` has {} ports, but {num_conns} connections were given",
+            ports.len()
+        ))
+        .into());
+    }
+    ctx.instantiate_external(&model)
+}
+
+/// Determines the primitive kind of a `V` or `I` line from its DC or AC value.
+fn source_kind(inst: &InstanceLine) -> Result<PrimitiveKind> {
+    let mut dc = None;
+    let mut ac = None;
+    let mut values = inst.values.iter();
+    while let Some(value) = values.next() {
+        if value.eq_ignore_ascii_case("dc") || value.eq_ignore_ascii_case("ac") {
+            let number = values
+                .next()
+                .ok_or_else(|| unsupported(inst, "missing source value"))?;
+            let number = parse_number(inst, number)?;
+            if value.eq_ignore_ascii_case("dc") {
+                dc = Some(number);
+            } else {
+                ac = Some(number);
+                // Skip the AC phase, if any.
+                values.next();
+            }
+        } else if dc.is_none() && ac.is_none() {
+            dc = Some(parse_number(inst, value)?);
+        } else {
+            return Err(unsupported(inst, "unsupported source specification"));
+        }
+    }
+
+    let is_voltage = inst.kind == InstanceKind::Vsource;
+    Ok(match (dc, ac) {
+        (dc, None) => {
+            let dc = to_si_value(dc.unwrap_or_default());
+            if is_voltage {
+                PrimitiveKind::Vdc(dc)
+            } else {
+                PrimitiveKind::Idc(dc)
+            }
+        }
+        (None, Some(ac)) => {
+            let ac = to_si_value(ac);
+            if is_voltage {
+                PrimitiveKind::Vac(ac)
+            } else {
+                PrimitiveKind::Iac(ac)
+            }
+        }
+        (Some(dc), Some(ac)) if dc == 0. => {
+            let ac = to_si_value(ac);
+            if is_voltage {
+                PrimitiveKind::Vac(ac)
+            } else {
+                PrimitiveKind::Iac(ac)
+            }
+        }
+        _ => return Err(unsupported(inst, "sources with both DC and AC values")),
+    })
+}
+
+/// Strips the leading type letter from a device name.
+fn device_name(name: &str) -> &str {
+    match name.char_indices().nth(1) {
+        Some((idx, _)) => &name[idx..],
+        None => name,
+    }
+}
+
+fn find_param<'a>(params: &[Param<'a>], key: &str) -> Option<&'a str> {
+    params
+        .iter()
+        .find(|param| param.name.eq_ignore_ascii_case(key))
+        .map(|param| param.value)
+}
+
+fn parse_number(inst: &InstanceLine, value: &str) -> Result<f64> {
+    parse_value(value).ok_or_else(|| unsupported(inst, &format!("non-numeric value `{value}`")))
+}
+
+fn unsupported(inst: &InstanceLine, reason: &str) -> crate::error::SubstrateError {
+    ErrorSource::SpiceImport(format!("cannot import instance `{}`: {reason}", inst.name)).into()
+}
+
+/// Converts a SPICE parameter value to a [`Value`].
+///
+/// Values that are not numbers are treated as expressions.
+fn to_value(value: &str) -> Value {
+    if let Ok(value) = value.parse::<i64>() {
+        Value::Int(value)
+    } else if let Some(value) = parse_value(value) {
+        Value::Float(value)
+    } else {
+        Value::expr(strip_delimiters(value).unwrap_or(value))
+    }
+}
+
+/// Converts a number to an [`SiValue`], using the largest prefix
+/// that represents the number exactly.
+fn to_si_value(value: f64) -> SiValue {
+    if value == 0. {
+        return SiValue::zero();
+    }
+    for prefix in [
+        SiPrefix::Tera,
+        SiPrefix::Giga,
+        SiPrefix::Mega,
+        SiPrefix::Kilo,
+        SiPrefix::None,
+        SiPrefix::Milli,
+        SiPrefix::Micro,
+        SiPrefix::Nano,
+        SiPrefix::Pico,
+    ] {
+        let scaled = value / prefix.multiplier();
+        let rounded = scaled.round();
+        if rounded != 0. && (scaled - rounded).abs() <= 1e-6 * rounded.abs() {
+            return SiValue::new(rounded as i64, prefix);
+        }
+    }
+    SiValue::with_precision(value, SiPrefix::Femto)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_si_value() {
+        assert_eq!(to_si_value(10e3), SiValue::new(10, SiPrefix::Kilo));
+        assert_eq!(to_si_value(1.5e-6), SiValue::new(1500, SiPrefix::Nano));
+        assert_eq!(to_si_value(2e-15), SiValue::new(2, SiPrefix::Femto));
+        assert_eq!(to_si_value(100.), SiValue::new(100, SiPrefix::None));
+        assert_eq!(to_si_value(0.), SiValue::zero());
+    }
+
+    #[test]
+    fn test_to_value() {
+        assert!(matches!(to_value("2"), Value::Int(2)));
+        assert!(matches!(to_value("1.8"), Value::Float(x) if x == 1.8));
+        assert!(matches!(to_value("{2*w}"), Value::Expr(expr) if expr == "2*w"));
+        assert!(matches!(to_value("'wn'"), Value::Expr(expr) if expr == "wn"));
+        assert!(matches!(to_value("wn"), Value::Expr(expr) if expr == "wn"));
+    }
+
+    #[test]
+    fn test_device_name() {
+        assert_eq!(device_name("R1"), "1");
+        assert_eq!(device_name("xinv"), "inv");
+        assert_eq!(device_name("X"), "X");
+    }
+}
@@ -9,7 +9,11 @@
 pub mod circuit;
 pub mod context;
 pub mod elements;
+pub mod flatten;
+pub mod import;
 pub mod module;
 pub mod netlist;
+pub mod primitive;
+pub mod render;
 pub mod signal;
 pub mod validation;
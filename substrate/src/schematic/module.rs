@@ -6,6 +6,7 @@ use subspice::parser::SubcktLine;
 
 use super::circuit::{Direction, Instance, InstanceKey, Param, Port, PortError, PortInfo};
 use super::context::ModuleKey;
+use super::primitive::PrimitiveDevice;
 use super::signal::{SignalInfo, SignalKey, Slice};
 use crate::deps::arcstr::ArcStr;
 use crate::error::{ErrorSource, Result};
@@ -17,6 +18,7 @@ pub struct Module {
     name: ArcStr,
     ports: Vec<Port>,
     instances: SlotMap<InstanceKey, Instance>,
+    primitives: Vec<PrimitiveDevice>,
     parameters: HashMap<ArcStr, Param>,
     signals: SlotMap<SignalKey, SignalInfo>,
     raw_spice: Option<ArcStr>,
@@ -30,6 +32,7 @@ impl Module {
             name: arcstr::literal!("unnamed"),
             ports: Vec::new(),
             instances: SlotMap::with_key(),
+            primitives: Vec::new(),
             parameters: HashMap::new(),
             signals: SlotMap::with_key(),
             raw_spice: None,
@@ -61,11 +64,31 @@ impl Module {
         self.raw_spice = Some(s.into())
     }
 
+    #[inline]
+    pub(crate) fn add_param(&mut self, param: Param) {
+        self.parameters.insert(param.name().clone(), param);
+    }
+
     #[inline]
     pub(crate) fn add_instance(&mut self, inst: Instance) {
         self.instances.insert(inst);
     }
 
+    #[inline]
+    pub(crate) fn add_primitive(&mut self, device: PrimitiveDevice) {
+        self.primitives.push(device);
+    }
+
+    #[inline]
+    pub fn primitives(&self) -> impl Iterator<Item = &PrimitiveDevice> {
+        self.primitives.iter()
+    }
+
+    #[inline]
+    pub(crate) fn primitives_mut(&mut self) -> impl Iterator<Item = &mut PrimitiveDevice> {
+        self.primitives.iter_mut()
+    }
+
     #[inline]
     pub fn instances_iter(&self) -> impl Iterator<Item = (InstanceKey, &Instance)> {
         self.instances.iter()
@@ -0,0 +1,390 @@
+//! A built-in CDL netlister implementation, for use with LVS tools.
+
+use std::collections::HashSet;
+use std::path::Path;
+
+use super::spice::format_value;
+use crate::deps::arcstr::ArcStr;
+use crate::fmt::signal::format_signal;
+use crate::pdk::mos::db::MosDb;
+use crate::schematic::circuit::Direction;
+use crate::schematic::netlist::interface::{
+    InstanceInfo, NetlistError, NetlistFormat, NetlistOpts, Netlister, PrimitiveInfo, Result,
+    SubcircuitInfo,
+};
+use crate::schematic::primitive::PrimitiveKind;
+
+/// A CDL netlister.
+///
+/// Port directions are written as `*.PININFO` comments.
+/// MOSFETs in raw SPICE whose model is a known MOSFET device are written as `M` devices,
+/// even if the PDK instantiates them as subcircuits.
+/// Subcircuit parameters are not written, since LVS only compares device parameters.
+#[derive(Clone, Debug, Default)]
+pub struct CdlNetlister {
+    mos_devices: HashSet<String>,
+    globals: Vec<ArcStr>,
+    substrate: Option<ArcStr>,
+}
+
+impl CdlNetlister {
+    /// Creates a new [`CdlNetlister`].
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a new [`CdlNetlister`] that recognizes the MOSFETs in the given [`MosDb`].
+    pub fn from_mos_db(mos_db: &MosDb) -> Self {
+        Self {
+            mos_devices: mos_db.specs().map(|spec| spec.name.clone()).collect(),
+            ..Default::default()
+        }
+    }
+
+    /// A consuming method to recognize an additional MOSFET model name.
+    pub fn with_mos_device(mut self, name: impl Into<String>) -> Self {
+        self.mos_devices.insert(name.into());
+        self
+    }
+
+    /// A consuming method to declare a net as global using a `.GLOBAL` statement.
+    pub fn with_global(mut self, net: impl Into<ArcStr>) -> Self {
+        self.globals.push(net.into());
+        self
+    }
+
+    /// A consuming method to set the substrate net of resistors, capacitors and diodes.
+    ///
+    /// The net is written using the `$SUB=` syntax.
+    pub fn with_substrate(mut self, net: impl Into<ArcStr>) -> Self {
+        self.substrate = Some(net.into());
+        self
+    }
+
+    /// Rewrites a MOSFET instantiated as a subcircuit into an `M` device.
+    ///
+    /// Returns `None` if the line does not describe a known MOSFET.
+    fn map_mos_line(&self, line: &str) -> Option<String> {
+        let mut tokens = line.split_whitespace();
+        let name = tokens.next()?;
+        if !matches!(name.chars().next(), Some('X' | 'x' | 'M' | 'm')) {
+            return None;
+        }
+        let tokens = tokens.collect::<Vec<_>>();
+        if tokens.len() < 5
+            || tokens[..4].iter().any(|token| token.contains('='))
+            || !self.mos_devices.contains(tokens[4])
+        {
+            return None;
+        }
+        Some(format!("M{} {}", &name[1..], tokens.join(" ")))
+    }
+
+    fn write_substrate(&self, out: &mut dyn std::io::Write) -> Result<()> {
+        if let Some(ref substrate) = self.substrate {
+            write!(out, " $SUB={substrate}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Returns the CDL pin direction corresponding to the given [`Direction`].
+fn pin_direction(direction: Direction) -> char {
+    match direction {
+        Direction::Input => 'I',
+        Direction::Output => 'O',
+        Direction::InOut => 'B',
+    }
+}
+
+impl Netlister for CdlNetlister {
+    /// Returns configuration options for this netlister.
+    ///
+    /// The global ground net is named `0` by default.
+    fn opts(&self) -> NetlistOpts {
+        NetlistOpts {
+            netlist_format: NetlistFormat::Cdl,
+            global_ground_net: arcstr::literal!("0"),
+            ..Default::default()
+        }
+    }
+
+    fn emit_comment(&self, out: &mut dyn std::io::Write, comment: &str) -> Result<()> {
+        writeln!(out, "* {comment}")?;
+        Ok(())
+    }
+
+    fn emit_begin_subcircuit(
+        &self,
+        out: &mut dyn std::io::Write,
+        info: SubcircuitInfo,
+    ) -> Result<()> {
+        let bus_format = self.opts().bus_format;
+        let mut pins = Vec::new();
+        writeln!(out, "\n.SUBCKT {}", info.name)?;
+        for &port in info.ports {
+            let sig = &info.signals[port.signal];
+            for i in 0..sig.width() {
+                let name = format_signal(sig.name(), i, sig.width(), bus_format);
+                writeln!(out, "+ {name}")?;
+                pins.push(format!("{name}:{}", pin_direction(port.direction())));
+            }
+        }
+        writeln!(out, "*.PININFO {}", pins.join(" "))?;
+        Ok(())
+    }
+
+    fn emit_end_subcircuit(&self, out: &mut dyn std::io::Write, name: &str) -> Result<()> {
+        writeln!(out, ".ENDS {name}\n")?;
+        Ok(())
+    }
+
+    fn emit_raw_spice(&self, out: &mut dyn std::io::Write, spice: &str) -> Result<()> {
+        let mut lines = spice.lines().peekable();
+        while let Some(first) = lines.next() {
+            // Join `+` continuation lines so that multi-line MOSFETs are mapped too.
+            let mut statement = vec![first];
+            while let Some(line) = lines.next_if(|line| line.trim_start().starts_with('+')) {
+                statement.push(line);
+            }
+            let joined = statement
+                .iter()
+                .map(|line| line.trim_start().trim_start_matches('+'))
+                .collect::<Vec<_>>()
+                .join(" ");
+            match self.map_mos_line(&joined) {
+                Some(mapped) => writeln!(out, "{mapped}")?,
+                None => {
+                    for line in statement {
+                        writeln!(out, "{line}")?;
+                    }
+                }
+            }
+        }
+        if spice.is_empty() || spice.ends_with('\n') {
+            writeln!(out)?;
+        }
+        Ok(())
+    }
+
+    fn emit_instance(&self, out: &mut dyn std::io::Write, instance: InstanceInfo) -> Result<()> {
+        let bus_format = self.opts().bus_format;
+        writeln!(out, "X{}", instance.name)?;
+        for &signal in instance.ports {
+            for part in signal.parts() {
+                let info = &instance.signals[part.signal()];
+                for i in part.range() {
+                    let name = format_signal(info.name(), i, info.width(), bus_format);
+                    writeln!(out, "+ {name}")?;
+                }
+            }
+        }
+        writeln!(out, "+ / {}", instance.subcircuit_name)?;
+
+        let mut params = instance.params.iter().collect::<Vec<_>>();
+        params.sort_by(|a, b| a.0.cmp(b.0));
+        for (name, value) in params {
+            writeln!(out, "+ {}={}", name, format_value(value))?;
+        }
+        Ok(())
+    }
+
+    fn emit_primitive(&self, out: &mut dyn std::io::Write, primitive: PrimitiveInfo) -> Result<()> {
+        let name = primitive.name;
+        let ports = primitive
+            .ports
+            .iter()
+            .map(|port| {
+                let info = &primitive.signals[port.signal];
+                format_signal(info.name(), port.idx, info.width(), self.opts().bus_format)
+            })
+            .collect::<Vec<_>>();
+        let nodes = ports.join(" ");
+
+        match primitive.kind {
+            PrimitiveKind::Resistor(r) => write!(out, "R{name} {nodes} {r}")?,
+            PrimitiveKind::Capacitor(c) => write!(out, "C{name} {nodes} {c}")?,
+            PrimitiveKind::Inductor(l) => {
+                writeln!(out, "L{name} {nodes} {l}")?;
+                return Ok(());
+            }
+            PrimitiveKind::Diode(d) => {
+                write!(out, "D{name} {nodes} $[{}]", d.model)?;
+                if let Some(area) = d.area {
+                    write!(out, " {area}")?;
+                }
+            }
+            kind => {
+                return Err(NetlistError::UnsupportedPrimitive {
+                    name: name.to_string(),
+                    kind: kind.clone(),
+                })
+            }
+        }
+        self.write_substrate(out)?;
+        writeln!(out)?;
+        Ok(())
+    }
+
+    fn emit_include(&self, out: &mut dyn std::io::Write, include: &Path) -> Result<()> {
+        writeln!(out, ".INCLUDE {include:?}")?;
+        Ok(())
+    }
+
+    fn emit_lib_include(
+        &self,
+        out: &mut dyn std::io::Write,
+        lib: &Path,
+        section: &str,
+    ) -> Result<()> {
+        writeln!(out, ".LIB {lib:?} {section}")?;
+        Ok(())
+    }
+
+    fn emit_begin(&self, out: &mut dyn std::io::Write) -> Result<()> {
+        writeln!(out, "*.BUSDELIMITER [")?;
+        if !self.globals.is_empty() {
+            writeln!(out, ".GLOBAL {}", self.globals.join(" "))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use slotmap::SlotMap;
+
+    use super::*;
+    use crate::schematic::circuit::{Port, Value};
+    use crate::schematic::elements::diode::Diode;
+    use crate::schematic::signal::{Signal, SignalInfo, Slice, SliceOne};
+    use crate::units::{SiPrefix, SiValue};
+
+    #[test]
+    fn test_map_mos_line() {
+        let netlister = CdlNetlister::new().with_mos_device("nfet");
+        assert_eq!(
+            netlister.map_mos_line("X0 d g s b nfet w=1.000 l=0.150"),
+            Some("M0 d g s b nfet w=1.000 l=0.150".to_string())
+        );
+        assert_eq!(
+            netlister.map_mos_line("M1 d g s b nfet"),
+            Some("M1 d g s b nfet".to_string())
+        );
+        assert_eq!(netlister.map_mos_line("X0 d g s b pfet w=1"), None);
+        assert_eq!(netlister.map_mos_line("R1 a b 1k"), None);
+        assert_eq!(netlister.map_mos_line("X0 d g s w=1 nfet"), None);
+    }
+
+    #[test]
+    fn test_raw_spice_continuation() {
+        let netlister = CdlNetlister::new().with_mos_device("nfet");
+        let mut out = Vec::new();
+        netlister
+            .emit_raw_spice(
+                &mut out,
+                "X0 d g s b\n+ nfet w=1.000\n+ l=0.150\nR1 a b\n+ 1k\n",
+            )
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "M0 d g s b nfet w=1.000 l=0.150\nR1 a b\n+ 1k\n\n"
+        );
+    }
+
+    #[test]
+    fn test_cdl_netlist() {
+        let mut signals = SlotMap::with_key();
+        let vdd = signals.insert(SignalInfo::new("vdd", 1, true));
+        let din = signals.insert(SignalInfo::new("din", 2, true));
+        let dout = signals.insert(SignalInfo::new("dout", 1, true));
+        let ports = [
+            Port::new(vdd, Direction::InOut),
+            Port::new(din, Direction::Input),
+            Port::new(dout, Direction::Output),
+        ];
+
+        let netlister = CdlNetlister::new()
+            .with_mos_device("nfet")
+            .with_global("vdd")
+            .with_substrate("vss");
+        let mut out = Vec::new();
+        netlister.emit_begin(&mut out).unwrap();
+        netlister
+            .emit_begin_subcircuit(
+                &mut out,
+                SubcircuitInfo {
+                    name: "buf",
+                    ports: &ports,
+                    params: &HashMap::new(),
+                    signals: &signals,
+                },
+            )
+            .unwrap();
+
+        let vdd_sig = Signal::from(Slice::with_width(vdd, 1));
+        let din_sig = Signal::from(Slice::with_width(din, 2));
+        let params = HashMap::from([("w".into(), Value::Int(2))]);
+        netlister
+            .emit_instance(
+                &mut out,
+                InstanceInfo {
+                    name: "inv",
+                    ports: &[&vdd_sig, &din_sig],
+                    port_names: &[&arcstr::literal!("vdd"), &arcstr::literal!("din")],
+                    params: &params,
+                    signals: &signals,
+                    subcircuit_name: "inv",
+                },
+            )
+            .unwrap();
+        netlister
+            .emit_raw_spice(&mut out, "X0 dout din[0] vdd vdd nfet w=1.000 l=0.150")
+            .unwrap();
+        netlister
+            .emit_primitive(
+                &mut out,
+                PrimitiveInfo {
+                    name: "esd",
+                    kind: &PrimitiveKind::Diode(Diode {
+                        model: "dnw".into(),
+                        area: Some(SiValue::new(2, SiPrefix::None)),
+                    }),
+                    ports: &[SliceOne::new(dout, 0), SliceOne::new(vdd, 0)],
+                    signals: &signals,
+                },
+            )
+            .unwrap();
+        netlister.emit_end_subcircuit(&mut out, "buf").unwrap();
+
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "*.BUSDELIMITER [\n.GLOBAL vdd\n\n\
+            .SUBCKT buf\n+ vdd\n+ din[0]\n+ din[1]\n+ dout\n\
+            *.PININFO vdd:B din[0]:I din[1]:I dout:O\n\
+            Xinv\n+ vdd\n+ din[0]\n+ din[1]\n+ / inv\n+ w=2\n\
+            M0 dout din[0] vdd vdd nfet w=1.000 l=0.150\n\
+            Desd dout vdd $[dnw] 2 $SUB=vss\n\
+            .ENDS buf\n\n"
+        );
+
+        let err = netlister
+            .emit_primitive(
+                &mut Vec::new(),
+                PrimitiveInfo {
+                    name: "src",
+                    kind: &PrimitiveKind::Vdc(SiValue::new(1, SiPrefix::None)),
+                    ports: &[SliceOne::new(vdd, 0), SliceOne::new(dout, 0)],
+                    signals: &signals,
+                },
+            )
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            NetlistError::UnsupportedPrimitive { ref name, .. } if name == "src"
+        ));
+    }
+}
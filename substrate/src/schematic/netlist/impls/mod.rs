@@ -1,3 +1,6 @@
 //! Built-in netlister implementations.
 
+pub mod cdl;
+pub mod spectre;
 pub mod spice;
+pub mod verilog;
@@ -0,0 +1,400 @@
+//! A built-in Spectre netlister implementation.
+
+use std::io::Write;
+use std::path::Path;
+
+use crate::fmt::signal::format_signal;
+use crate::schematic::circuit::Value;
+use crate::schematic::elements::bsource::BsourceOutput;
+use crate::schematic::netlist::interface::{
+    InstanceInfo, NetlistFormat, NetlistOpts, Netlister, PrimitiveInfo, Result, SubcircuitInfo,
+};
+use crate::schematic::primitive::PrimitiveKind;
+use crate::units::{SiPrefix, SiValue};
+use crate::verification::simulation::waveform::TimeWaveform;
+
+/// A netlister that emits native Spectre syntax.
+///
+/// Raw SPICE, such as the contents of external modules, is wrapped in
+/// `simulator lang=spice` blocks.
+///
+/// When simulating with the Spectre plugin, set its `netlist_format` simulator
+/// option to `spectre` so that hierarchical names match this netlister's output.
+#[derive(Clone, Debug, Default)]
+pub struct SpectreNetlister;
+
+impl SpectreNetlister {
+    /// Creates a new [`SpectreNetlister`].
+    #[inline]
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Netlister for SpectreNetlister {
+    /// Returns configuration options for this netlister.
+    ///
+    /// The global ground net is named `0` by default.
+    fn opts(&self) -> NetlistOpts {
+        NetlistOpts {
+            netlist_format: NetlistFormat::Spectre,
+            global_ground_net: arcstr::literal!("0"),
+            ..Default::default()
+        }
+    }
+
+    fn emit_comment(&self, out: &mut dyn Write, comment: &str) -> Result<()> {
+        writeln!(out, "// {comment}")?;
+        Ok(())
+    }
+
+    fn emit_begin_subcircuit(&self, out: &mut dyn Write, info: SubcircuitInfo) -> Result<()> {
+        write!(out, "\nsubckt {}", escape(info.name))?;
+        for &port in info.ports {
+            let sig = &info.signals[port.signal];
+            for i in 0..sig.width() {
+                let name = format_signal(sig.name(), i, sig.width(), self.opts().bus_format);
+                write!(out, " {}", escape(&name))?;
+            }
+        }
+        writeln!(out)?;
+        if !info.params.is_empty() {
+            let mut params = info.params.values().collect::<Vec<_>>();
+            params.sort_by(|a, b| a.name().cmp(b.name()));
+            write!(out, "parameters")?;
+            for param in params {
+                write!(
+                    out,
+                    " {}={}",
+                    escape(param.name()),
+                    format_value(param.value())
+                )?;
+            }
+            writeln!(out)?;
+        }
+        writeln!(out)?;
+        Ok(())
+    }
+
+    fn emit_end_subcircuit(&self, out: &mut dyn Write, name: &str) -> Result<()> {
+        writeln!(out, "ends {}\n", escape(name))?;
+        Ok(())
+    }
+
+    fn emit_raw_spice(&self, out: &mut dyn Write, spice: &str) -> Result<()> {
+        if spice.trim().is_empty() {
+            writeln!(out, "{spice}")?;
+        } else {
+            writeln!(out, "simulator lang=spice\n{spice}\nsimulator lang=spectre")?;
+        }
+        Ok(())
+    }
+
+    fn emit_instance(&self, out: &mut dyn Write, instance: InstanceInfo) -> Result<()> {
+        write!(out, "{} (", escape(instance.name))?;
+        let mut first = true;
+        for &signal in instance.ports {
+            for part in signal.parts() {
+                let info = &instance.signals[part.signal()];
+                for i in part.range() {
+                    let name = format_signal(info.name(), i, info.width(), self.opts().bus_format);
+                    if !first {
+                        write!(out, " ")?;
+                    }
+                    write!(out, "{}", escape(&name))?;
+                    first = false;
+                }
+            }
+        }
+        write!(out, ") {}", escape(instance.subcircuit_name))?;
+
+        let mut params = instance.params.iter().collect::<Vec<_>>();
+        params.sort_by(|a, b| a.0.cmp(b.0));
+        for (name, value) in params {
+            write!(out, " {}={}", escape(name), format_value(value))?;
+        }
+        writeln!(out)?;
+        Ok(())
+    }
+
+    fn emit_primitive(&self, out: &mut dyn Write, primitive: PrimitiveInfo) -> Result<()> {
+        let name = escape(primitive.name);
+        let ports = primitive
+            .ports
+            .iter()
+            .map(|port| {
+                let info = &primitive.signals[port.signal];
+                escape(&format_signal(
+                    info.name(),
+                    port.idx,
+                    info.width(),
+                    self.opts().bus_format,
+                ))
+            })
+            .collect::<Vec<_>>();
+        let nodes = ports.join(" ");
+
+        match primitive.kind {
+            PrimitiveKind::Resistor(r) => writeln!(out, "{name} ({nodes}) resistor r={}", si(r))?,
+            PrimitiveKind::Capacitor(c) => {
+                writeln!(out, "{name} ({nodes}) capacitor c={}", si(c))?
+            }
+            PrimitiveKind::Inductor(l) => writeln!(out, "{name} ({nodes}) inductor l={}", si(l))?,
+            PrimitiveKind::Diode(d) => {
+                write!(out, "{name} ({nodes}) {}", escape(&d.model))?;
+                if let Some(area) = d.area {
+                    write!(out, " area={}", si(area))?;
+                }
+                writeln!(out)?;
+            }
+            PrimitiveKind::Vdc(v) => writeln!(out, "{name} ({nodes}) vsource dc={}", si(v))?,
+            PrimitiveKind::Idc(i) => writeln!(out, "{name} ({nodes}) isource dc={}", si(i))?,
+            PrimitiveKind::Vac(v) => writeln!(out, "{name} ({nodes}) vsource mag={}", si(v))?,
+            PrimitiveKind::Iac(i) => writeln!(out, "{name} ({nodes}) isource mag={}", si(i))?,
+            PrimitiveKind::Vpulse(p) => writeln!(
+                out,
+                "{name} ({nodes}) vsource type=pulse val0={} val1={} delay={} rise={} fall={} width={} period={}",
+                si(p.v1),
+                si(p.v2),
+                si(p.td),
+                si(p.tr),
+                si(p.tf),
+                si(p.pw),
+                si(p.period)
+            )?,
+            PrimitiveKind::Vpwl(waveform) => {
+                write!(out, "{name} ({nodes}) vsource type=pwl wave=[")?;
+                for pt in waveform.values() {
+                    write!(out, " {} {}", pt.t(), pt.x())?;
+                }
+                writeln!(out, " ]")?;
+            }
+            PrimitiveKind::Vexp(e) => writeln!(
+                out,
+                "{name} ({nodes}) vsource type=exp val0={} val1={} td1={} tau1={} td2={} tau2={}",
+                si(e.v1),
+                si(e.v2),
+                si(e.td1),
+                si(e.tau1),
+                si(e.td2),
+                si(e.tau2)
+            )?,
+            PrimitiveKind::Vsin(s) => writeln!(
+                out,
+                "{name} ({nodes}) vsource type=sine sinedc={} ampl={} freq={} delay={} damp={} sinephase={}",
+                si(s.vo),
+                si(s.va),
+                si(s.freq),
+                si(s.td),
+                si(s.theta),
+                si(s.phase)
+            )?,
+            PrimitiveKind::Vcvs(gain) => writeln!(out, "{name} ({nodes}) vcvs gain={}", si(gain))?,
+            PrimitiveKind::Vccs(gm) => writeln!(out, "{name} ({nodes}) vccs gm={}", si(gm))?,
+            // The controlling current is sensed by a zero-volt source from `cp` to `cn`.
+            PrimitiveKind::Ccvs(rm) => {
+                writeln!(out, "{name}_sense ({} {}) vsource dc=0", ports[2], ports[3])?;
+                writeln!(
+                    out,
+                    "{name} ({} {}) ccvs rm={} probe={name}_sense",
+                    ports[0],
+                    ports[1],
+                    si(rm)
+                )?;
+            }
+            PrimitiveKind::Cccs(gain) => {
+                writeln!(out, "{name}_sense ({} {}) vsource dc=0", ports[2], ports[3])?;
+                writeln!(
+                    out,
+                    "{name} ({} {}) cccs gain={} probe={name}_sense",
+                    ports[0],
+                    ports[1],
+                    si(gain)
+                )?;
+            }
+            PrimitiveKind::Switch(sw) => writeln!(
+                out,
+                "{name} ({nodes}) relay vt1={} vt2={} rclosed={} ropen={}",
+                si(sw.vt),
+                si(sw.vt),
+                si(sw.ron),
+                si(sw.roff)
+            )?,
+            PrimitiveKind::Bsource(b) => {
+                let quantity = match b.output {
+                    BsourceOutput::Voltage => "v",
+                    BsourceOutput::Current => "i",
+                };
+                writeln!(out, "{name} ({nodes}) bsource {quantity}={}", b.expr)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn emit_include(&self, out: &mut dyn Write, include: &Path) -> Result<()> {
+        writeln!(out, "include {include:?}")?;
+        Ok(())
+    }
+
+    fn emit_lib_include(&self, out: &mut dyn Write, lib: &Path, section: &str) -> Result<()> {
+        writeln!(out, "include {lib:?} section={section}")?;
+        Ok(())
+    }
+
+    fn emit_begin(&self, out: &mut dyn Write) -> Result<()> {
+        writeln!(out, "simulator lang=spectre")?;
+        Ok(())
+    }
+}
+
+/// Escapes the characters in `name` that are not allowed in Spectre identifiers.
+///
+/// For example, the bus bit `data[3]` is written as `data\[3\]`.
+fn escape(name: &str) -> String {
+    let mut escaped = String::with_capacity(name.len());
+    for c in name.chars() {
+        if !(c.is_ascii_alphanumeric() || c == '_' || c == '!') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Formats an [`SiValue`] using Spectre scale factors.
+fn si(value: SiValue) -> String {
+    let suffix = match value.prefix() {
+        SiPrefix::Atto => "a",
+        SiPrefix::Femto => "f",
+        SiPrefix::Pico => "p",
+        SiPrefix::Nano => "n",
+        SiPrefix::Micro => "u",
+        SiPrefix::Milli => "m",
+        SiPrefix::None => "",
+        SiPrefix::Kilo => "k",
+        SiPrefix::Mega => "M",
+        SiPrefix::Giga => "G",
+        SiPrefix::Tera => "T",
+        prefix => return format!("{}e{}", value.value(), prefix.multiplier().log10().round()),
+    };
+    format!("{}{suffix}", value.value())
+}
+
+/// Formats a parameter value using Spectre syntax.
+///
+/// Expressions are enclosed in parentheses.
+pub(crate) fn format_value(value: &Value) -> String {
+    match value {
+        Value::Int(x) => x.to_string(),
+        Value::Float(x) => format!("{x:e}"),
+        Value::String(s) => format!("{s:?}"),
+        Value::Expr(expr) => format!("({expr})"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use slotmap::SlotMap;
+
+    use super::*;
+    use crate::schematic::circuit::{Direction, Param, Port};
+    use crate::schematic::signal::{Signal, SignalInfo, Slice, SliceOne};
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(escape("vdd"), "vdd");
+        assert_eq!(escape("data[3]"), "data\\[3\\]");
+        assert_eq!(escape("a.b-c"), "a\\.b\\-c");
+        assert_eq!(escape("0"), "0");
+    }
+
+    #[test]
+    fn test_spectre_netlist() {
+        let mut signals = SlotMap::with_key();
+        let vdd = signals.insert(SignalInfo::new("vdd", 1, true));
+        let data = signals.insert(SignalInfo::new("data", 2, true));
+        let ports = [
+            Port::new(vdd, Direction::InOut),
+            Port::new(data, Direction::Input),
+        ];
+
+        let netlister = SpectreNetlister::new();
+        let mut out = Vec::new();
+        netlister
+            .emit_begin_subcircuit(
+                &mut out,
+                SubcircuitInfo {
+                    name: "buf",
+                    ports: &ports,
+                    params: &HashMap::from([("lmin".into(), Param::new("lmin", 0.15e-6))]),
+                    signals: &signals,
+                },
+            )
+            .unwrap();
+
+        let vdd = Signal::from(Slice::with_width(vdd, 1));
+        let data = Signal::from(Slice::with_width(data, 2));
+        let params = HashMap::from([
+            ("w".into(), Value::Float(1.5e-6)),
+            ("nf".into(), Value::Int(2)),
+            ("l".into(), Value::Expr("2*lmin".into())),
+        ]);
+        netlister
+            .emit_instance(
+                &mut out,
+                InstanceInfo {
+                    name: "inv[0]",
+                    ports: &[&vdd, &data],
+                    port_names: &[&arcstr::literal!("vdd"), &arcstr::literal!("data")],
+                    params: &params,
+                    signals: &signals,
+                    subcircuit_name: "inv",
+                },
+            )
+            .unwrap();
+        netlister.emit_raw_spice(&mut out, "R1 a b 1k").unwrap();
+        let vdd = SliceOne::new(vdd.parts()[0].signal(), 0);
+        let data0 = SliceOne::new(data.parts()[0].signal(), 0);
+        let data1 = SliceOne::new(data.parts()[0].signal(), 1);
+        netlister
+            .emit_primitive(
+                &mut out,
+                PrimitiveInfo {
+                    name: "res",
+                    kind: &PrimitiveKind::Resistor(SiValue::new(10, SiPrefix::Mega)),
+                    ports: &[vdd, data0],
+                    signals: &signals,
+                },
+            )
+            .unwrap();
+        netlister
+            .emit_primitive(
+                &mut out,
+                PrimitiveInfo {
+                    name: "src",
+                    kind: &PrimitiveKind::Cccs(SiValue::new(2, SiPrefix::None)),
+                    ports: &[vdd, data0, data1, vdd],
+                    signals: &signals,
+                },
+            )
+            .unwrap();
+        netlister.emit_end_subcircuit(&mut out, "buf").unwrap();
+        netlister
+            .emit_lib_include(&mut out, Path::new("/pdk/models.scs"), "tt")
+            .unwrap();
+
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "\nsubckt buf vdd data\\[0\\] data\\[1\\]\nparameters lmin=1.5e-7\n\n\
+            inv\\[0\\] (vdd data\\[0\\] data\\[1\\]) inv l=(2*lmin) nf=2 w=1.5e-6\n\
+            simulator lang=spice\nR1 a b 1k\nsimulator lang=spectre\n\
+            res (vdd data\\[0\\]) resistor r=10M\n\
+            src_sense (data\\[1\\] vdd) vsource dc=0\n\
+            src (vdd data\\[0\\]) cccs gain=2 probe=src_sense\n\
+            ends buf\n\n\
+            include \"/pdk/models.scs\" section=tt\n"
+        );
+    }
+}
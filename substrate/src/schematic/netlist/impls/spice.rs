@@ -3,9 +3,13 @@
 use std::path::Path;
 
 use crate::fmt::signal::format_signal;
+use crate::schematic::circuit::Value;
+use crate::schematic::elements::bsource::BsourceOutput;
 use crate::schematic::netlist::interface::{
-    InstanceInfo, NetlistOpts, Netlister, Result, SubcircuitInfo,
+    InstanceInfo, NetlistOpts, Netlister, PrimitiveInfo, Result, SubcircuitInfo,
 };
+use crate::schematic::primitive::PrimitiveKind;
+use crate::verification::simulation::waveform::TimeWaveform;
 
 /// A SPICE netlister.
 #[derive(Clone, Debug, Default)]
@@ -51,6 +55,14 @@ impl Netlister for SpiceNetlister {
                 )?;
             }
         }
+        if !info.params.is_empty() {
+            let mut params = info.params.values().collect::<Vec<_>>();
+            params.sort_by(|a, b| a.name().cmp(b.name()));
+            writeln!(out, "+ params:")?;
+            for param in params {
+                writeln!(out, "+ {}={}", param.name(), format_value(param.value()))?;
+            }
+        }
         // Write a newline
         writeln!(out)?;
         Ok(())
@@ -81,6 +93,95 @@ impl Netlister for SpiceNetlister {
             }
         }
         writeln!(out, "+ {}", instance.subcircuit_name)?;
+
+        let mut params = instance.params.iter().collect::<Vec<_>>();
+        params.sort_by(|a, b| a.0.cmp(b.0));
+        for (name, value) in params {
+            writeln!(out, "+ {}={}", name, format_value(value))?;
+        }
+        Ok(())
+    }
+
+    fn emit_primitive(&self, out: &mut dyn std::io::Write, primitive: PrimitiveInfo) -> Result<()> {
+        let name = primitive.name;
+        let ports = primitive
+            .ports
+            .iter()
+            .map(|port| {
+                let info = &primitive.signals[port.signal];
+                format_signal(info.name(), port.idx, info.width(), self.opts().bus_format)
+            })
+            .collect::<Vec<_>>();
+        let nodes = ports.join(" ");
+
+        match primitive.kind {
+            PrimitiveKind::Resistor(r) => writeln!(out, "R{name} {nodes} {r}")?,
+            PrimitiveKind::Capacitor(c) => writeln!(out, "C{name} {nodes} {c}")?,
+            PrimitiveKind::Inductor(l) => writeln!(out, "L{name} {nodes} {l}")?,
+            PrimitiveKind::Diode(d) => {
+                write!(out, "D{name} {nodes} {}", d.model)?;
+                if let Some(area) = d.area {
+                    write!(out, " area={area}")?;
+                }
+                writeln!(out)?;
+            }
+            PrimitiveKind::Vdc(v) => writeln!(out, "V{name} {nodes} dc {v}")?,
+            PrimitiveKind::Idc(i) => writeln!(out, "I{name} {nodes} dc {i}")?,
+            PrimitiveKind::Vac(v) => writeln!(out, "V{name} {nodes} ac {v}")?,
+            PrimitiveKind::Iac(i) => writeln!(out, "I{name} {nodes} ac {i}")?,
+            PrimitiveKind::Vpulse(p) => writeln!(
+                out,
+                "V{name} {nodes} PULSE({} {} {} {} {} {} {})",
+                p.v1, p.v2, p.td, p.tr, p.tf, p.pw, p.period
+            )?,
+            PrimitiveKind::Vpwl(waveform) => {
+                write!(out, "V{name} {nodes} PWL(")?;
+                for pt in waveform.values() {
+                    write!(out, " {} {}", pt.t(), pt.x())?;
+                }
+                writeln!(out, " )")?;
+            }
+            PrimitiveKind::Vexp(e) => writeln!(
+                out,
+                "V{name} {nodes} EXP({} {} {} {} {} {})",
+                e.v1, e.v2, e.td1, e.tau1, e.td2, e.tau2
+            )?,
+            PrimitiveKind::Vsin(s) => writeln!(
+                out,
+                "V{name} {nodes} SIN({} {} {} {} {} {})",
+                s.vo, s.va, s.freq, s.td, s.theta, s.phase
+            )?,
+            PrimitiveKind::Vcvs(gain) => writeln!(out, "E{name} {nodes} {gain}")?,
+            PrimitiveKind::Vccs(gm) => writeln!(out, "G{name} {nodes} {gm}")?,
+            // The controlling current is sensed by a zero-volt source from `cp` to `cn`.
+            PrimitiveKind::Ccvs(rm) => {
+                writeln!(out, "V{name}_sense {} {} 0", ports[2], ports[3])?;
+                writeln!(out, "H{name} {} {} V{name}_sense {rm}", ports[0], ports[1])?;
+            }
+            PrimitiveKind::Cccs(gain) => {
+                writeln!(out, "V{name}_sense {} {} 0", ports[2], ports[3])?;
+                writeln!(
+                    out,
+                    "F{name} {} {} V{name}_sense {gain}",
+                    ports[0], ports[1]
+                )?;
+            }
+            PrimitiveKind::Switch(sw) => {
+                writeln!(
+                    out,
+                    ".model {name}_sw sw vt={} ron={} roff={}",
+                    sw.vt, sw.ron, sw.roff
+                )?;
+                writeln!(out, "S{name} {nodes} {name}_sw")?;
+            }
+            PrimitiveKind::Bsource(b) => {
+                let quantity = match b.output {
+                    BsourceOutput::Voltage => "V",
+                    BsourceOutput::Current => "I",
+                };
+                writeln!(out, "B{name} {nodes} {quantity}={}", b.expr)?;
+            }
+        }
         Ok(())
     }
 
@@ -99,3 +200,15 @@ impl Netlister for SpiceNetlister {
         Ok(())
     }
 }
+
+/// Formats a parameter value using SPICE syntax.
+///
+/// Expressions are enclosed in braces.
+pub(crate) fn format_value(value: &Value) -> String {
+    match value {
+        Value::Int(x) => x.to_string(),
+        Value::Float(x) => format!("{x:e}"),
+        Value::String(s) => format!("{s:?}"),
+        Value::Expr(expr) => format!("{{{expr}}}"),
+    }
+}
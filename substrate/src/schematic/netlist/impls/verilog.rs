@@ -0,0 +1,455 @@
+//! A built-in structural Verilog netlister implementation.
+
+use std::borrow::Cow;
+use std::path::Path;
+
+use slotmap::SlotMap;
+
+use super::spectre::format_value;
+use crate::fmt::signal::format_signal;
+use crate::schematic::circuit::Direction;
+use crate::schematic::netlist::interface::{
+    InstanceInfo, NetlistFormat, NetlistOpts, Netlister, Result, SubcircuitInfo,
+};
+use crate::schematic::signal::{Signal, SignalInfo, SignalKey};
+
+/// A structural Verilog netlister.
+///
+/// Each module is written with `input`, `output` and `inout` port declarations
+/// and a `wire` declaration for each internal signal. Multi-bit signals are declared
+/// as vectors, and instances are connected by port name.
+///
+/// [`ExternalModule`](crate::schematic::module::ExternalModule)s are treated as black boxes:
+/// they are instantiated, but not defined. Their SPICE sources, as well as any raw SPICE,
+/// are written as comments. Primitive devices cannot be written to Verilog netlists.
+#[derive(Clone, Debug, Default)]
+pub struct VerilogNetlister;
+
+impl VerilogNetlister {
+    /// Creates a new [`VerilogNetlister`].
+    #[inline]
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Formats a connection to a port of an instance.
+    ///
+    /// Signals that span multiple slices are written as concatenations,
+    /// with the most significant bit first.
+    fn format_connection(
+        &self,
+        signal: &Signal,
+        signals: &SlotMap<SignalKey, SignalInfo>,
+    ) -> String {
+        let mut bits = Vec::with_capacity(signal.width());
+        for part in signal.parts() {
+            let info = &signals[part.signal()];
+            let range = part.range();
+            if signal.parts().len() == 1 && range.width() == info.width() {
+                return escape(info.name()).into_owned();
+            }
+            for i in range {
+                bits.push(
+                    format_signal(
+                        escape(info.name()).as_ref(),
+                        i,
+                        info.width(),
+                        self.opts().bus_format,
+                    )
+                    .to_string(),
+                );
+            }
+        }
+        bits.reverse();
+        if bits.len() == 1 {
+            bits.pop().unwrap()
+        } else {
+            format!("{{{}}}", bits.join(", "))
+        }
+    }
+}
+
+/// Reserved keywords of Verilog (IEEE 1364-2005), which cannot be used as simple identifiers.
+///
+/// Sorted so that lookups can use a binary search.
+const KEYWORDS: &[&str] = &[
+    "always",
+    "and",
+    "assign",
+    "automatic",
+    "begin",
+    "buf",
+    "bufif0",
+    "bufif1",
+    "case",
+    "casex",
+    "casez",
+    "cell",
+    "cmos",
+    "config",
+    "deassign",
+    "default",
+    "defparam",
+    "design",
+    "disable",
+    "edge",
+    "else",
+    "end",
+    "endcase",
+    "endconfig",
+    "endfunction",
+    "endgenerate",
+    "endmodule",
+    "endprimitive",
+    "endspecify",
+    "endtable",
+    "endtask",
+    "event",
+    "for",
+    "force",
+    "forever",
+    "fork",
+    "function",
+    "generate",
+    "genvar",
+    "highz0",
+    "highz1",
+    "if",
+    "ifnone",
+    "incdir",
+    "include",
+    "initial",
+    "inout",
+    "input",
+    "instance",
+    "integer",
+    "join",
+    "large",
+    "liblist",
+    "library",
+    "localparam",
+    "macromodule",
+    "medium",
+    "module",
+    "nand",
+    "negedge",
+    "nmos",
+    "nor",
+    "noshowcancelled",
+    "not",
+    "notif0",
+    "notif1",
+    "or",
+    "output",
+    "parameter",
+    "pmos",
+    "posedge",
+    "primitive",
+    "pull0",
+    "pull1",
+    "pulldown",
+    "pullup",
+    "pulsestyle_ondetect",
+    "pulsestyle_onevent",
+    "rcmos",
+    "real",
+    "realtime",
+    "reg",
+    "release",
+    "repeat",
+    "rnmos",
+    "rpmos",
+    "rtran",
+    "rtranif0",
+    "rtranif1",
+    "scalared",
+    "showcancelled",
+    "signed",
+    "small",
+    "specify",
+    "specparam",
+    "strong0",
+    "strong1",
+    "supply0",
+    "supply1",
+    "table",
+    "task",
+    "time",
+    "tran",
+    "tranif0",
+    "tranif1",
+    "tri",
+    "tri0",
+    "tri1",
+    "triand",
+    "trior",
+    "trireg",
+    "unsigned",
+    "use",
+    "uwire",
+    "vectored",
+    "wait",
+    "wand",
+    "weak0",
+    "weak1",
+    "while",
+    "wire",
+    "wor",
+    "xnor",
+    "xor",
+];
+
+/// Escapes names that are not valid Verilog simple identifiers.
+///
+/// Escaped identifiers begin with a backslash and end with whitespace.
+pub(crate) fn escape(name: &str) -> Cow<'_, str> {
+    let mut chars = name.chars();
+    let valid = matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$')
+        && KEYWORDS.binary_search(&name).is_err();
+
+    if valid {
+        Cow::Borrowed(name)
+    } else {
+        Cow::Owned(format!("\\{name} "))
+    }
+}
+
+impl Netlister for VerilogNetlister {
+    fn opts(&self) -> NetlistOpts {
+        NetlistOpts {
+            netlist_format: NetlistFormat::Verilog,
+            global_ground_net: arcstr::literal!("vss"),
+            ..Default::default()
+        }
+    }
+
+    fn emit_comment(&self, out: &mut dyn std::io::Write, comment: &str) -> Result<()> {
+        for line in comment.lines() {
+            writeln!(out, "// {line}")?;
+        }
+        Ok(())
+    }
+
+    fn emit_begin_subcircuit(
+        &self,
+        out: &mut dyn std::io::Write,
+        info: SubcircuitInfo,
+    ) -> Result<()> {
+        let ports = info
+            .ports
+            .iter()
+            .map(|port| escape(info.signals[port.signal].name()))
+            .collect::<Vec<_>>();
+        writeln!(out, "\nmodule {}(", escape(info.name))?;
+        writeln!(out, "  {}", ports.join(",\n  "))?;
+        writeln!(out, ");")?;
+
+        for port in info.ports {
+            let sig = &info.signals[port.signal];
+            let direction = match port.direction() {
+                Direction::Input => "input",
+                Direction::Output => "output",
+                Direction::InOut => "inout",
+            };
+            write!(out, "  {direction} ")?;
+            if sig.width() > 1 {
+                write!(out, "[{}:0] ", sig.width() - 1)?;
+            }
+            writeln!(out, "{};", escape(sig.name()))?;
+        }
+
+        for sig in info.signals.values().filter(|sig| !sig.is_port()) {
+            write!(out, "  wire ")?;
+            if sig.width() > 1 {
+                write!(out, "[{}:0] ", sig.width() - 1)?;
+            }
+            writeln!(out, "{};", escape(sig.name()))?;
+        }
+        writeln!(out)?;
+        Ok(())
+    }
+
+    fn emit_end_subcircuit(&self, out: &mut dyn std::io::Write, _name: &str) -> Result<()> {
+        writeln!(out, "endmodule\n")?;
+        Ok(())
+    }
+
+    fn emit_raw_spice(&self, out: &mut dyn std::io::Write, spice: &str) -> Result<()> {
+        for line in spice.lines().filter(|line| !line.trim().is_empty()) {
+            writeln!(out, "// {line}")?;
+        }
+        Ok(())
+    }
+
+    fn emit_instance(&self, out: &mut dyn std::io::Write, instance: InstanceInfo) -> Result<()> {
+        write!(out, "  {}", escape(instance.subcircuit_name))?;
+
+        if !instance.params.is_empty() {
+            let mut params = instance.params.iter().collect::<Vec<_>>();
+            params.sort_by(|a, b| a.0.cmp(b.0));
+            let params = params
+                .into_iter()
+                .map(|(name, value)| format!(".{}({})", escape(name), format_value(value)))
+                .collect::<Vec<_>>();
+            write!(out, " #({})", params.join(", "))?;
+        }
+
+        writeln!(out, " {} (", escape(instance.name))?;
+        let conns = instance
+            .port_names
+            .iter()
+            .zip(instance.ports)
+            .map(|(name, &signal)| {
+                format!(
+                    ".{}({})",
+                    escape(name),
+                    self.format_connection(signal, instance.signals)
+                )
+            })
+            .collect::<Vec<_>>();
+        writeln!(out, "    {}", conns.join(",\n    "))?;
+        writeln!(out, "  );")?;
+        Ok(())
+    }
+
+    fn emit_include(&self, out: &mut dyn std::io::Write, include: &Path) -> Result<()> {
+        writeln!(out, "// Black box definitions: {include:?}")?;
+        Ok(())
+    }
+
+    fn emit_lib_include(
+        &self,
+        out: &mut dyn std::io::Write,
+        lib: &Path,
+        section: &str,
+    ) -> Result<()> {
+        writeln!(out, "// Library: {lib:?} section {section}")?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::schematic::circuit::{Port, Value};
+    use crate::schematic::netlist::interface::{NetlistError, PrimitiveInfo};
+    use crate::schematic::primitive::PrimitiveKind;
+    use crate::schematic::signal::{Slice, SliceOne, SliceRange};
+    use crate::units::{SiPrefix, SiValue};
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(escape("din"), "din");
+        assert_eq!(escape("din[3]"), "\\din[3] ");
+        assert_eq!(escape("0"), "\\0 ");
+        assert_eq!(escape("nand"), "\\nand ");
+        for keyword in [
+            "posedge", "negedge", "event", "fork", "join", "real", "time",
+        ] {
+            assert_eq!(escape(keyword), format!("\\{keyword} "));
+        }
+        assert!(KEYWORDS.windows(2).all(|w| w[0] < w[1]));
+    }
+
+    #[test]
+    fn test_verilog_netlist() {
+        let mut signals = SlotMap::with_key();
+        let vdd = signals.insert(SignalInfo::new("vdd", 1, true));
+        let din = signals.insert(SignalInfo::new("din", 2, true));
+        let dout = signals.insert(SignalInfo::new("dout", 1, true));
+        let x = signals.insert(SignalInfo::new("x", 2, false));
+        let ports = [
+            Port::new(vdd, Direction::InOut),
+            Port::new(din, Direction::Input),
+            Port::new(dout, Direction::Output),
+        ];
+
+        let netlister = VerilogNetlister::new();
+        let mut out = Vec::new();
+        netlister
+            .emit_begin_subcircuit(
+                &mut out,
+                SubcircuitInfo {
+                    name: "buffer",
+                    ports: &ports,
+                    params: &HashMap::new(),
+                    signals: &signals,
+                },
+            )
+            .unwrap();
+
+        let din_sig = Signal::from(Slice::with_width(din, 2));
+        let x0 = Signal::from(Slice::new(x, SliceRange::new(0, 1)));
+        let swapped = Signal::new(vec![
+            Slice::new(din, SliceRange::new(1, 2)),
+            Slice::new(din, SliceRange::new(0, 1)),
+        ]);
+        let dout_sig = Signal::from(Slice::with_width(dout, 1));
+        netlister
+            .emit_instance(
+                &mut out,
+                InstanceInfo {
+                    name: "inv[0]",
+                    ports: &[&din_sig, &x0],
+                    port_names: &[&arcstr::literal!("a"), &arcstr::literal!("y")],
+                    params: &HashMap::new(),
+                    signals: &signals,
+                    subcircuit_name: "inv",
+                },
+            )
+            .unwrap();
+        netlister
+            .emit_instance(
+                &mut out,
+                InstanceInfo {
+                    name: "x1",
+                    ports: &[&swapped, &dout_sig],
+                    port_names: &[&arcstr::literal!("a"), &arcstr::literal!("y")],
+                    params: &HashMap::from([
+                        ("n".into(), Value::Int(2)),
+                        ("w".into(), Value::Expr("2*l".into())),
+                    ]),
+                    signals: &signals,
+                    subcircuit_name: "nand2",
+                },
+            )
+            .unwrap();
+        netlister.emit_end_subcircuit(&mut out, "buffer").unwrap();
+
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "\nmodule buffer(\n  vdd,\n  din,\n  dout\n);\n\
+            \x20 inout vdd;\n  input [1:0] din;\n  output dout;\n  wire [1:0] x;\n\n\
+            \x20 inv \\inv[0]  (\n    .a(din),\n    .y(x[0])\n  );\n\
+            \x20 nand2 #(.n(2), .w((2*l))) x1 (\n    .a({din[0], din[1]}),\n    .y(dout)\n  );\n\
+            endmodule\n\n"
+        );
+    }
+
+    #[test]
+    fn test_verilog_unsupported_primitive() {
+        let mut signals = SlotMap::with_key();
+        let p = signals.insert(SignalInfo::new("p", 1, false));
+        let n = signals.insert(SignalInfo::new("n", 1, false));
+        let kind = PrimitiveKind::Resistor(SiValue::new(1, SiPrefix::Kilo));
+
+        let err = VerilogNetlister::new()
+            .emit_primitive(
+                &mut Vec::new(),
+                PrimitiveInfo {
+                    name: "r1",
+                    kind: &kind,
+                    ports: &[SliceOne::new(p, 0), SliceOne::new(n, 0)],
+                    signals: &signals,
+                },
+            )
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            NetlistError::UnsupportedPrimitive { ref name, .. } if name == "r1"
+        ));
+    }
+}
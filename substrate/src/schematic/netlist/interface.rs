@@ -12,7 +12,8 @@ use thiserror::Error;
 use crate::deps::arcstr::ArcStr;
 use crate::fmt::signal::BusFmt;
 use crate::schematic::circuit::{Param, Port, Value};
-use crate::schematic::signal::{Signal, SignalInfo, SignalKey};
+use crate::schematic::primitive::PrimitiveKind;
+use crate::schematic::signal::{Signal, SignalInfo, SignalKey, SliceOne};
 
 /// Options describing the output of a nestlister.
 #[derive(Debug, Default, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
@@ -33,6 +34,10 @@ pub enum NetlistFormat {
     Spice,
     /// NgSpice-compatible SPICE netlist format.
     NgSpice,
+    /// CDL netlist format, used by LVS tools.
+    Cdl,
+    /// Structural Verilog netlist format.
+    Verilog,
     /// A custom netlist format.
     Other(String),
 }
@@ -50,6 +55,8 @@ impl Display for NetlistFormat {
             Self::SpectreSpice => write!(f, "spectre-spice"),
             Self::Spice => write!(f, "spice"),
             Self::NgSpice => write!(f, "ngspice"),
+            Self::Cdl => write!(f, "cdl"),
+            Self::Verilog => write!(f, "verilog"),
             Self::Other(ref s) => write!(f, "other-{s}"),
         }
     }
@@ -78,6 +85,17 @@ pub trait Netlister {
     /// Emits an instance to the provided output stream.
     fn emit_instance(&self, out: &mut dyn Write, instance: InstanceInfo) -> Result<()>;
 
+    /// Emits a primitive device to the provided output stream.
+    ///
+    /// By default, returns [`NetlistError::UnsupportedPrimitive`].
+    #[allow(unused_variables)]
+    fn emit_primitive(&self, out: &mut dyn Write, primitive: PrimitiveInfo) -> Result<()> {
+        Err(NetlistError::UnsupportedPrimitive {
+            name: primitive.name.to_string(),
+            kind: primitive.kind.clone(),
+        })
+    }
+
     /// Emits an include directive to the provided output stream.
     fn emit_include(&self, out: &mut dyn Write, include: &Path) -> Result<()>;
 
@@ -105,6 +123,8 @@ pub struct InstanceInfo<'a> {
     pub name: &'a str,
     /// A list of instance ports.
     pub ports: &'a [&'a Signal],
+    /// The names of the ports of the instantiated module, in the same order as `ports`.
+    pub port_names: &'a [&'a ArcStr],
     /// An unstructured map of parameters.
     pub params: &'a HashMap<ArcStr, Value>,
     /// A map of signals associated with the instance.
@@ -113,6 +133,18 @@ pub struct InstanceInfo<'a> {
     pub subcircuit_name: &'a str,
 }
 
+/// A description of a primitive device.
+pub struct PrimitiveInfo<'a> {
+    /// The instance name.
+    pub name: &'a str,
+    /// The kind of device, along with its parameters.
+    pub kind: &'a PrimitiveKind,
+    /// The signals connected to each of the device's terminals.
+    pub ports: &'a [SliceOne],
+    /// A map of signals associated with the device.
+    pub signals: &'a SlotMap<SignalKey, SignalInfo>,
+}
+
 /// A description of a schematic subcircuit.
 pub struct SubcircuitInfo<'a> {
     /// The name of the subcircuit.
@@ -131,6 +163,9 @@ pub enum NetlistError {
     /// General I/O errors.
     #[error("I/O error: {0}")]
     Io(#[from] std::io::Error),
+    /// Primitive devices that the netlister cannot emit.
+    #[error("unsupported primitive device {name}: {kind:?}")]
+    UnsupportedPrimitive { name: String, kind: PrimitiveKind },
     /// Unexpected errors.
     #[error("unexpected error: {0}")]
     Other(String),
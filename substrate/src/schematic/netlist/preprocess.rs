@@ -126,7 +126,7 @@ impl<'a> NetlistPreprocessor<'a> {
         self.mod_names.insert(module.name().to_owned());
     }
 
-    /// Renames instances with conflicting names within a single module.
+    /// Renames instances and primitive devices with conflicting names within a single module.
     fn rename_instances(&self, module: &mut Module) {
         let mut names = HashSet::new();
         for inst in module.instances_mut() {
@@ -143,6 +143,20 @@ impl<'a> NetlistPreprocessor<'a> {
             }
             names.insert(inst.name().to_owned());
         }
+        for device in module.primitives_mut() {
+            if names.contains(device.name()) {
+                let mut i = 1;
+                let name = loop {
+                    let name = format!("{}_{}", device.name(), i);
+                    if !names.contains(&*name) {
+                        break name;
+                    }
+                    i += 1;
+                };
+                device.set_name(name);
+            }
+            names.insert(device.name().to_owned());
+        }
     }
 }
 
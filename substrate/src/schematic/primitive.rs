@@ -0,0 +1,132 @@
+//! Primitive devices.
+//!
+//! Primitive devices, such as resistors and voltage sources, are stored in a
+//! [`Module`](super::module::Module) as structured data rather than as raw SPICE.
+//! Each [`Netlister`](super::netlist::interface::Netlister) renders them in its own syntax.
+
+use std::sync::Arc;
+
+use super::elements::bsource::Bsource;
+use super::elements::diode::Diode;
+use super::elements::switch::Switch;
+use super::elements::vexp::Vexp;
+use super::elements::vpulse::Vpulse;
+use super::elements::vsin::Vsin;
+use super::signal::{Slice, SliceOne};
+use crate::deps::arcstr::ArcStr;
+use crate::units::SiValue;
+use crate::verification::simulation::waveform::Waveform;
+
+/// An instance of a primitive device.
+#[derive(Clone, Debug)]
+pub struct PrimitiveDevice {
+    name: ArcStr,
+    kind: PrimitiveKind,
+    connections: Vec<SliceOne>,
+}
+
+/// An enumeration of primitive device kinds, along with their parameters.
+#[derive(Clone, Debug, PartialEq)]
+pub enum PrimitiveKind {
+    /// A resistor, parametrized by resistance.
+    Resistor(SiValue),
+    /// A capacitor, parametrized by capacitance.
+    Capacitor(SiValue),
+    /// An inductor, parametrized by inductance.
+    Inductor(SiValue),
+    /// A diode.
+    Diode(Diode),
+    /// A DC voltage source, parametrized by DC voltage.
+    Vdc(SiValue),
+    /// A DC current source, parametrized by DC current.
+    Idc(SiValue),
+    /// An AC voltage source, parametrized by AC amplitude.
+    Vac(SiValue),
+    /// An AC current source, parametrized by AC amplitude.
+    Iac(SiValue),
+    /// A pulse voltage source.
+    Vpulse(Vpulse),
+    /// A piece-wise linear voltage source.
+    Vpwl(Arc<Waveform>),
+    /// An exponential voltage source.
+    Vexp(Vexp),
+    /// A sinusoidal voltage source.
+    Vsin(Vsin),
+    /// A voltage-controlled voltage source, parametrized by voltage gain.
+    Vcvs(SiValue),
+    /// A voltage-controlled current source, parametrized by transconductance.
+    Vccs(SiValue),
+    /// A current-controlled voltage source, parametrized by transresistance.
+    Ccvs(SiValue),
+    /// A current-controlled current source, parametrized by current gain.
+    Cccs(SiValue),
+    /// A voltage-controlled switch.
+    Switch(Switch),
+    /// A behavioral source.
+    Bsource(Bsource),
+}
+
+impl PrimitiveKind {
+    /// Returns the names of the device's terminals, in netlist order.
+    ///
+    /// Controlled sources and switches are controlled by the voltage across,
+    /// or the current flowing from `cp` to `cn`.
+    pub fn terminals(&self) -> &'static [&'static str] {
+        match self {
+            Self::Diode(_) => &["a", "c"],
+            Self::Vcvs(_) | Self::Vccs(_) | Self::Ccvs(_) | Self::Cccs(_) | Self::Switch(_) => {
+                &["p", "n", "cp", "cn"]
+            }
+            _ => &["p", "n"],
+        }
+    }
+}
+
+impl PrimitiveDevice {
+    /// Creates a new [`PrimitiveDevice`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of connections does not match the number of
+    /// [terminals](PrimitiveKind::terminals), or if any connection is wider than 1 bit.
+    pub fn new(
+        name: impl Into<ArcStr>,
+        kind: PrimitiveKind,
+        connections: impl IntoIterator<Item = Slice>,
+    ) -> Self {
+        let connections = connections
+            .into_iter()
+            .map(SliceOne::from_slice)
+            .collect::<Vec<_>>();
+        assert_eq!(connections.len(), kind.terminals().len());
+        Self {
+            name: name.into(),
+            kind,
+            connections,
+        }
+    }
+
+    /// Returns the device's instance name.
+    #[inline]
+    pub fn name(&self) -> &ArcStr {
+        &self.name
+    }
+
+    /// Sets the device's instance name.
+    #[inline]
+    pub fn set_name(&mut self, name: impl Into<ArcStr>) {
+        self.name = name.into();
+    }
+
+    /// Returns the device's kind and parameters.
+    #[inline]
+    pub fn kind(&self) -> &PrimitiveKind {
+        &self.kind
+    }
+
+    /// Returns the signals connected to the device's terminals, in netlist order.
+    #[inline]
+    pub fn connections(&self) -> &[SliceOne] {
+        &self.connections
+    }
+}
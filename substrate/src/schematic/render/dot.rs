@@ -0,0 +1,119 @@
+//! Graphviz DOT output.
+
+use std::collections::HashSet;
+use std::fmt::Write;
+
+use super::{device_label, device_summary, net_label, SchematicRenderOpts};
+use crate::schematic::circuit::Direction;
+use crate::schematic::flatten::FlatSchematic;
+
+pub(crate) fn render(flat: &FlatSchematic, opts: &SchematicRenderOpts) -> String {
+    let mut out = String::new();
+    writeln!(out, "digraph {} {{", quote(flat.name())).unwrap();
+    writeln!(out, "  rankdir=LR;").unwrap();
+    writeln!(out, "  node [fontname=\"monospace\", fontsize=10];").unwrap();
+    writeln!(out, "  edge [arrowsize=0.6];").unwrap();
+
+    // Only draw nets that connect to something.
+    let mut used = HashSet::new();
+    for (_, device) in flat.devices() {
+        used.extend(device.terminals().iter().map(|(_, net)| *net));
+    }
+    used.extend(flat.ports().iter().map(|port| port.net));
+
+    for (id, net) in flat.nets() {
+        if used.contains(&id) {
+            writeln!(
+                out,
+                "  n{} [label={}, shape=box, style=rounded, height=0.2];",
+                id.index(),
+                quote(&net_label(net.name()))
+            )
+            .unwrap();
+        }
+    }
+
+    for (i, port) in flat.ports().iter().enumerate() {
+        let name = match port.idx {
+            Some(idx) => format!("{}[{idx}]", port.name),
+            None => port.name.to_string(),
+        };
+        let (shape, edge) = match port.direction {
+            Direction::Input => ("rarrow", format!("p{i} -> n{}", port.net.index())),
+            Direction::Output => ("rarrow", format!("n{} -> p{i}", port.net.index())),
+            Direction::InOut => (
+                "hexagon",
+                format!("p{i} -> n{} [dir=both]", port.net.index()),
+            ),
+        };
+        writeln!(
+            out,
+            "  p{i} [label={}, shape={shape}, style=filled, fillcolor=\"#e0e0e0\"];",
+            quote(&name)
+        )
+        .unwrap();
+        writeln!(out, "  {edge};").unwrap();
+    }
+
+    for (id, device) in flat.devices() {
+        let d = id.index();
+        let mut inputs = Vec::new();
+        let mut outputs = Vec::new();
+        for (i, (terminal, _)) in device.terminals().iter().enumerate() {
+            let field = format!("<t{i}> {}", escape_record(terminal));
+            match device.terminal_direction(terminal) {
+                Some(Direction::Output) => outputs.push(field),
+                _ => inputs.push(field),
+            }
+        }
+        let mut label = format!(
+            "{{{}}}|{}\\n{}",
+            inputs.join("|"),
+            escape_record(&device_label(device)),
+            escape_record(&device_summary(device, opts.params))
+        );
+        if !outputs.is_empty() {
+            write!(label, "|{{{}}}", outputs.join("|")).unwrap();
+        }
+        writeln!(out, "  d{d} [shape=record, label=\"{label}\"];").unwrap();
+
+        for (i, (terminal, net)) in device.terminals().iter().enumerate() {
+            let net = net.index();
+            let edge = match device.terminal_direction(terminal) {
+                Some(Direction::Input) => format!("n{net} -> d{d}:t{i}"),
+                Some(Direction::Output) => format!("d{d}:t{i} -> n{net}"),
+                _ => format!("n{net} -> d{d}:t{i} [dir=none]"),
+            };
+            writeln!(out, "  {edge};").unwrap();
+        }
+    }
+
+    writeln!(out, "}}").unwrap();
+    out
+}
+
+/// Quotes a string as a DOT identifier.
+fn quote(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        if c == '"' || c == '\\' {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out.push('"');
+    out
+}
+
+/// Escapes text for inclusion in a record label.
+fn escape_record(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        if matches!(c, '{' | '}' | '|' | '<' | '>' | '"' | '\\') {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
@@ -0,0 +1,231 @@
+//! Rendering of schematics to Graphviz DOT graphs and SVG images.
+//!
+//! Renders are intended to help review generator output, not to replace
+//! a schematic editor. Both formats are produced from a [`FlatSchematic`]:
+//!
+//! * The DOT export draws each device or unexpanded instance as a record
+//!   node with one field per terminal, and each net as a small node connected
+//!   to the terminals on that net. Edges point from nets to input terminals
+//!   and from output terminals to nets. Top-level ports are drawn as arrows
+//!   pointing into (inputs), out of (outputs), or both ways (inouts) of the
+//!   schematic.
+//! * The SVG export draws a symbol for each device on an automatically
+//!   laid out grid. Connectivity is shown with net labels on device pins
+//!   rather than with routed wires. It is best suited to primitive-level
+//!   modules with a modest number of devices.
+//!
+//! Hierarchy is expanded up to [`SchematicRenderOpts::max_depth`] levels.
+//! Deeper instances are drawn as black boxes.
+//!
+//! # Examples
+//!
+//! ```ignore
+//! let opts = SchematicRenderOpts::builder().max_depth(0).build().unwrap();
+//! ctx.write_schematic_image::<MyComponent>(&params, "build/my_component.dot", opts)?;
+//! ```
+use std::path::Path;
+
+use derive_builder::Builder;
+
+use super::circuit::Value;
+use super::flatten::{FlatDevice, FlatDeviceKind, FlatSchematic};
+use super::primitive::PrimitiveKind;
+use super::signal::NamedSignalPathBuf;
+use crate::error::Result;
+
+mod dot;
+mod svg;
+
+/// Options for rendering a schematic.
+#[derive(Debug, Clone, Builder)]
+pub struct SchematicRenderOpts {
+    /// The maximum depth of hierarchy to expand.
+    ///
+    /// The top module is at depth 0, so a maximum depth of 0 shows only
+    /// the contents of the top module. Deeper instances are drawn as
+    /// black boxes. If [`None`], the entire hierarchy is flattened.
+    #[builder(default, setter(strip_option))]
+    pub max_depth: Option<usize>,
+    /// Whether or not to show device values and instance parameters.
+    #[builder(default = "true")]
+    pub params: bool,
+}
+
+/// A schematic format supported by [`SchematicRenderer`].
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub enum SchematicFormat {
+    #[default]
+    Dot,
+    Svg,
+}
+
+/// Renders flattened schematics to DOT graphs and SVG images.
+///
+/// The output format is chosen per call. The same [`SchematicRenderOpts`]
+/// apply to both formats.
+#[derive(Debug, Clone)]
+pub struct SchematicRenderer {
+    opts: SchematicRenderOpts,
+}
+
+impl Default for SchematicRenderOpts {
+    fn default() -> Self {
+        Self::builder().build().unwrap()
+    }
+}
+
+impl SchematicRenderOpts {
+    #[inline]
+    pub fn builder() -> SchematicRenderOptsBuilder {
+        SchematicRenderOptsBuilder::default()
+    }
+}
+
+impl SchematicFormat {
+    /// Infers the schematic format from the extension of `path`.
+    ///
+    /// Returns [`None`] if the extension is not recognized.
+    pub fn from_path(path: impl AsRef<Path>) -> Option<Self> {
+        let ext = path.as_ref().extension()?.to_str()?.to_ascii_lowercase();
+        match ext.as_str() {
+            "dot" | "gv" => Some(Self::Dot),
+            "svg" => Some(Self::Svg),
+            _ => None,
+        }
+    }
+}
+
+impl SchematicRenderer {
+    /// Creates a new [`SchematicRenderer`].
+    pub fn new(opts: SchematicRenderOpts) -> Self {
+        Self { opts }
+    }
+
+    /// Renders `flat` to a Graphviz DOT graph.
+    pub fn dot(&self, flat: &FlatSchematic) -> String {
+        dot::render(flat, &self.opts)
+    }
+
+    /// Renders `flat` to an SVG document.
+    pub fn svg(&self, flat: &FlatSchematic) -> String {
+        svg::render(flat, &self.opts)
+    }
+
+    /// Renders `flat` to the given file in the given format.
+    pub fn write(
+        &self,
+        flat: &FlatSchematic,
+        path: impl AsRef<Path>,
+        format: SchematicFormat,
+    ) -> Result<()> {
+        let data = match format {
+            SchematicFormat::Dot => self.dot(flat),
+            SchematicFormat::Svg => self.svg(flat),
+        };
+        std::fs::write(path, data)?;
+        Ok(())
+    }
+}
+
+/// Formats a net name as `inst1/inst2/signal[idx]`.
+pub(crate) fn net_label(name: &NamedSignalPathBuf) -> String {
+    let mut label = String::new();
+    for inst in name.insts.iter() {
+        label.push_str(inst);
+        label.push('/');
+    }
+    label.push_str(&name.signal);
+    if let Some(idx) = name.idx {
+        label.push_str(&format!("[{idx}]"));
+    }
+    label
+}
+
+/// Formats a device path as `inst1/inst2/name`.
+pub(crate) fn device_label(device: &FlatDevice) -> String {
+    device
+        .path()
+        .iter()
+        .map(|name| name.as_str())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Summarizes the kind of a device, such as `resistor 1K`.
+///
+/// Values and parameters are only included if `params` is true.
+pub(crate) fn device_summary(device: &FlatDevice, params: bool) -> String {
+    match device.kind() {
+        FlatDeviceKind::Primitive(kind) => {
+            let name = primitive_name(kind);
+            match primitive_value(kind) {
+                Some(value) if params => format!("{name} {value}"),
+                _ => name.to_string(),
+            }
+        }
+        FlatDeviceKind::External {
+            module,
+            params: values,
+        } => {
+            let mut summary = module.to_string();
+            if params {
+                let mut values = values.iter().collect::<Vec<_>>();
+                values.sort_by(|a, b| a.0.cmp(b.0));
+                for (key, value) in values {
+                    let value = match value {
+                        Value::Int(x) => x.to_string(),
+                        Value::Float(x) => format!("{x:e}"),
+                        Value::String(s) | Value::Expr(s) => s.clone(),
+                    };
+                    summary.push_str(&format!(" {key}={value}"));
+                }
+            }
+            summary
+        }
+        FlatDeviceKind::RawSpice { module, .. } | FlatDeviceKind::Instance { module } => {
+            module.to_string()
+        }
+    }
+}
+
+/// The name of a kind of primitive device.
+pub(crate) fn primitive_name(kind: &PrimitiveKind) -> &'static str {
+    match kind {
+        PrimitiveKind::Resistor(_) => "resistor",
+        PrimitiveKind::Capacitor(_) => "capacitor",
+        PrimitiveKind::Inductor(_) => "inductor",
+        PrimitiveKind::Diode(_) => "diode",
+        PrimitiveKind::Vdc(_) => "vdc",
+        PrimitiveKind::Idc(_) => "idc",
+        PrimitiveKind::Vac(_) => "vac",
+        PrimitiveKind::Iac(_) => "iac",
+        PrimitiveKind::Vpulse(_) => "vpulse",
+        PrimitiveKind::Vpwl(_) => "vpwl",
+        PrimitiveKind::Vexp(_) => "vexp",
+        PrimitiveKind::Vsin(_) => "vsin",
+        PrimitiveKind::Vcvs(_) => "vcvs",
+        PrimitiveKind::Vccs(_) => "vccs",
+        PrimitiveKind::Ccvs(_) => "ccvs",
+        PrimitiveKind::Cccs(_) => "cccs",
+        PrimitiveKind::Switch(_) => "switch",
+        PrimitiveKind::Bsource(_) => "bsource",
+    }
+}
+
+/// The value of a primitive device, if it is parametrized by a single value.
+fn primitive_value(kind: &PrimitiveKind) -> Option<String> {
+    match kind {
+        PrimitiveKind::Resistor(x)
+        | PrimitiveKind::Capacitor(x)
+        | PrimitiveKind::Inductor(x)
+        | PrimitiveKind::Vdc(x)
+        | PrimitiveKind::Idc(x)
+        | PrimitiveKind::Vac(x)
+        | PrimitiveKind::Iac(x)
+        | PrimitiveKind::Vcvs(x)
+        | PrimitiveKind::Vccs(x)
+        | PrimitiveKind::Ccvs(x)
+        | PrimitiveKind::Cccs(x) => Some(x.to_string()),
+        _ => None,
+    }
+}
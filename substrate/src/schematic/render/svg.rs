@@ -0,0 +1,328 @@
+//! SVG output.
+//!
+//! Devices are placed on a grid, one symbol per cell. Each pin ends in a
+//! short stub labeled with the name of the net to which it is connected.
+
+use std::fmt::Write;
+
+use super::{device_label, device_summary, net_label, SchematicRenderOpts};
+use crate::fmt::svg::write_text;
+use crate::schematic::circuit::Direction;
+use crate::schematic::flatten::{FlatDevice, FlatDeviceKind, FlatSchematic};
+use crate::schematic::primitive::PrimitiveKind;
+
+/// The font size of labels, in pixels.
+const FONT_SIZE: f64 = 10.;
+
+/// The width of a grid cell, in pixels.
+const CELL_WIDTH: f64 = 200.;
+
+/// The width of the column of top-level ports, in pixels.
+const PORT_WIDTH: f64 = 160.;
+
+/// The height of the title, in pixels.
+const TITLE_HEIGHT: f64 = 40.;
+
+/// The spacing between pins of a block symbol, in pixels.
+const PIN_PITCH: f64 = 20.;
+
+/// The length of a pin stub, in pixels.
+const STUB: f64 = 20.;
+
+/// A pin of a symbol, relative to the center of the symbol.
+struct Pin {
+    /// The index of the pin in the device's terminal list.
+    terminal: usize,
+    /// The end of the pin.
+    at: [f64; 2],
+    /// The direction in which the net label extends from the pin.
+    side: Side,
+}
+
+#[derive(Copy, Clone, Eq, PartialEq)]
+enum Side {
+    Left,
+    Right,
+    Top,
+    Bottom,
+}
+
+/// A device symbol, relative to its center.
+struct Symbol {
+    /// SVG path data for the body of the symbol.
+    body: String,
+    pins: Vec<Pin>,
+    /// Text drawn at the center of the symbol, if any.
+    text: Option<String>,
+}
+
+pub(crate) fn render(flat: &FlatSchematic, opts: &SchematicRenderOpts) -> String {
+    let symbols = flat
+        .devices()
+        .map(|(_, device)| symbol(device))
+        .collect::<Vec<_>>();
+
+    // Every cell has room for the tallest symbol and its labels.
+    let half_height = symbols
+        .iter()
+        .flat_map(|symbol| symbol.pins.iter().map(|pin| pin.at[1].abs()))
+        .fold(40., f64::max);
+    let cell_height = 2. * half_height + 90.;
+    let cols = (symbols.len() as f64).sqrt().ceil().max(1.) as usize;
+    let rows = symbols.len().div_ceil(cols);
+    let port_height = 24. * flat.ports().len() as f64 + 20.;
+
+    let width = PORT_WIDTH + cols as f64 * CELL_WIDTH;
+    let height = TITLE_HEIGHT + f64::max(rows as f64 * cell_height, port_height);
+
+    let mut out = String::new();
+    writeln!(
+        out,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{0}" height="{1}" viewBox="0 0 {0} {1}">"#,
+        width, height
+    )
+    .unwrap();
+    writeln!(out, r#"<rect width="100%" height="100%" fill="white"/>"#).unwrap();
+    write_text(&mut out, [10., 24.], flat.name(), "start", 14.);
+
+    for (i, port) in flat.ports().iter().enumerate() {
+        let y = TITLE_HEIGHT + 20. + 24. * i as f64;
+        let flag = match port.direction {
+            Direction::Input => "M10 -8 L30 -8 L40 0 L30 8 L10 8 Z",
+            Direction::Output => "M20 -8 L40 -8 L40 8 L20 8 L10 0 Z",
+            Direction::InOut => "M20 -8 L30 -8 L40 0 L30 8 L20 8 L10 0 Z",
+        };
+        writeln!(
+            out,
+            r##"<path d="{flag}" transform="translate(0 {y})" fill="#e0e0e0" stroke="black" stroke-width="1"/>"##
+        )
+        .unwrap();
+        write_text(
+            &mut out,
+            [46., y + FONT_SIZE / 3.],
+            &net_label(flat.net(port.net).name()),
+            "start",
+            FONT_SIZE,
+        );
+    }
+
+    for (i, ((_, device), symbol)) in flat.devices().zip(symbols.iter()).enumerate() {
+        let x0 = PORT_WIDTH + (i % cols) as f64 * CELL_WIDTH;
+        let y0 = TITLE_HEIGHT + (i / cols) as f64 * cell_height;
+        let center = [x0 + CELL_WIDTH / 2., y0 + half_height + 30.];
+
+        writeln!(
+            out,
+            r#"<g transform="translate({} {})"><path d="{}" fill="none" stroke="black" stroke-width="1.5"/></g>"#,
+            center[0], center[1], symbol.body
+        )
+        .unwrap();
+        if let Some(text) = &symbol.text {
+            write_text(
+                &mut out,
+                [center[0], center[1] + FONT_SIZE / 3.],
+                text,
+                "middle",
+                FONT_SIZE,
+            );
+        }
+
+        for pin in symbol.pins.iter() {
+            let net = device.terminals()[pin.terminal].1;
+            let [x, y] = [center[0] + pin.at[0], center[1] + pin.at[1]];
+            writeln!(out, r#"<circle cx="{x}" cy="{y}" r="2" fill="black"/>"#).unwrap();
+            let (at, anchor) = match pin.side {
+                Side::Left => ([x - 4., y + FONT_SIZE / 3.], "end"),
+                Side::Right => ([x + 4., y + FONT_SIZE / 3.], "start"),
+                Side::Top => ([x, y - 6.], "middle"),
+                Side::Bottom => ([x, y + FONT_SIZE + 4.], "middle"),
+            };
+            write_text(
+                &mut out,
+                at,
+                &net_label(flat.net(net).name()),
+                anchor,
+                FONT_SIZE,
+            );
+        }
+
+        let bottom = y0 + cell_height;
+        write_text(
+            &mut out,
+            [center[0], bottom - 22.],
+            &device_label(device),
+            "middle",
+            FONT_SIZE,
+        );
+        write_text(
+            &mut out,
+            [center[0], bottom - 8.],
+            &device_summary(device, opts.params),
+            "middle",
+            FONT_SIZE,
+        );
+    }
+
+    writeln!(out, "</svg>").unwrap();
+    out
+}
+
+/// Chooses a symbol for a device.
+fn symbol(device: &FlatDevice) -> Symbol {
+    let names = device
+        .terminals()
+        .iter()
+        .map(|(name, _)| name.as_str())
+        .collect::<Vec<_>>();
+    match device.kind() {
+        FlatDeviceKind::Primitive(kind) => primitive_symbol(kind),
+        FlatDeviceKind::RawSpice { .. } if names == ["d", "g", "s", "b"] => mos_symbol(),
+        _ => block_symbol(device),
+    }
+}
+
+/// A vertical two-terminal symbol with terminal 0 on top.
+fn two_terminal(body: &str, text: Option<&str>) -> Symbol {
+    Symbol {
+        body: format!("M0 -40 L0 -20 M0 20 L0 40 {body}"),
+        pins: vec![
+            Pin {
+                terminal: 0,
+                at: [0., -40.],
+                side: Side::Top,
+            },
+            Pin {
+                terminal: 1,
+                at: [0., 40.],
+                side: Side::Bottom,
+            },
+        ],
+        text: text.map(|s| s.to_string()),
+    }
+}
+
+fn primitive_symbol(kind: &PrimitiveKind) -> Symbol {
+    const CIRCLE: &str = "M-20 0 A20 20 0 1 0 20 0 A20 20 0 1 0 -20 0";
+    const DIAMOND: &str = "M0 -20 L20 0 L0 20 L-20 0 Z";
+    match kind {
+        PrimitiveKind::Resistor(_) => {
+            two_terminal("M0 -20 L8 -16 L-8 -8 L8 0 L-8 8 L8 16 L0 20", None)
+        }
+        PrimitiveKind::Capacitor(_) => {
+            two_terminal("M0 -20 L0 -4 M-14 -4 L14 -4 M-14 4 L14 4 M0 4 L0 20", None)
+        }
+        PrimitiveKind::Inductor(_) => two_terminal(
+            "M0 -20 a5 5 0 0 1 0 10 a5 5 0 0 1 0 10 a5 5 0 0 1 0 10 a5 5 0 0 1 0 10",
+            None,
+        ),
+        PrimitiveKind::Diode(_) => two_terminal(
+            "M0 -20 L0 -10 M-10 -10 L10 -10 L0 8 Z M-10 8 L10 8 M0 8 L0 20",
+            None,
+        ),
+        PrimitiveKind::Idc(_) | PrimitiveKind::Iac(_) => {
+            two_terminal(&format!("{CIRCLE} M0 -10 L0 10 M-5 4 L0 10 L5 4"), None)
+        }
+        PrimitiveKind::Bsource(_) => two_terminal(DIAMOND, Some("B")),
+        PrimitiveKind::Vcvs(_)
+        | PrimitiveKind::Vccs(_)
+        | PrimitiveKind::Ccvs(_)
+        | PrimitiveKind::Cccs(_)
+        | PrimitiveKind::Switch(_) => {
+            let mut symbol = two_terminal(
+                &format!("{DIAMOND} M-40 -20 L-20 -20 L-10 -10 M-40 20 L-20 20 L-10 10"),
+                None,
+            );
+            symbol.pins.push(Pin {
+                terminal: 2,
+                at: [-40., -20.],
+                side: Side::Left,
+            });
+            symbol.pins.push(Pin {
+                terminal: 3,
+                at: [-40., 20.],
+                side: Side::Left,
+            });
+            symbol
+        }
+        _ => two_terminal(
+            &format!("{CIRCLE} M0 -14 L0 -6 M-4 -10 L4 -10 M-4 10 L4 10"),
+            None,
+        ),
+    }
+}
+
+/// A MOSFET with the gate on the left and the body on the right.
+fn mos_symbol() -> Symbol {
+    Symbol {
+        body: "M-40 0 L-8 0 M-8 -16 L-8 16 M0 -18 L0 18 \
+               M0 -12 L12 -12 L12 -40 M0 12 L12 12 L12 40 M0 0 L40 0"
+            .to_string(),
+        pins: vec![
+            Pin {
+                terminal: 0,
+                at: [12., -40.],
+                side: Side::Top,
+            },
+            Pin {
+                terminal: 1,
+                at: [-40., 0.],
+                side: Side::Left,
+            },
+            Pin {
+                terminal: 2,
+                at: [12., 40.],
+                side: Side::Bottom,
+            },
+            Pin {
+                terminal: 3,
+                at: [40., 0.],
+                side: Side::Right,
+            },
+        ],
+        text: None,
+    }
+}
+
+/// A box with input pins on the left and all other pins on the right.
+fn block_symbol(device: &FlatDevice) -> Symbol {
+    let (left, right): (Vec<_>, Vec<_>) = device
+        .terminals()
+        .iter()
+        .enumerate()
+        .map(|(i, (name, _))| (i, device.terminal_direction(name)))
+        .partition(|(_, direction)| *direction == Some(Direction::Input));
+    let rows = std::cmp::max(std::cmp::max(left.len(), right.len()), 1);
+    let half = PIN_PITCH * rows as f64 / 2.;
+    let half_width = 30.;
+
+    let mut body = format!(
+        "M{0} {1} L{2} {1} L{2} {3} L{0} {3} Z",
+        -half_width, -half, half_width, half
+    );
+    let mut pins = Vec::new();
+    for (terminals, x, side) in [
+        (&left, -half_width, Side::Left),
+        (&right, half_width, Side::Right),
+    ] {
+        let end = if side == Side::Left {
+            x - STUB
+        } else {
+            x + STUB
+        };
+        for (j, (i, _)) in terminals.iter().enumerate() {
+            let y = -half + PIN_PITCH * (j as f64 + 0.5);
+            write!(body, " M{x} {y} L{end} {y}").unwrap();
+            pins.push(Pin {
+                terminal: *i,
+                at: [end, y],
+                side,
+            });
+        }
+    }
+
+    Symbol {
+        body,
+        pins,
+        text: None,
+    }
+}
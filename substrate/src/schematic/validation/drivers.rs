@@ -337,6 +337,13 @@ impl<'a> DriverValidator<'a> {
             }
         }
 
+        // Primitive device terminals are treated as bidirectional.
+        for device in module.primitives() {
+            for conn in device.connections() {
+                net_states[conn.signal][conn.idx].inouts += 1;
+            }
+        }
+
         for (sig, states) in net_states {
             for (i, state) in states.iter().enumerate() {
                 let loc = Location::new(
@@ -0,0 +1,480 @@
+//! Electrical rule checks on flattened schematics.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt::Display;
+
+use super::super::flatten::{FlatMos, FlatSchematic, NetId};
+use super::super::signal::NamedSignalPathBuf;
+use crate::deps::arcstr::ArcStr;
+use crate::log::Log;
+use crate::pdk::mos::db::MosDb;
+use crate::pdk::mos::spec::MosKind;
+use crate::pdk::{Supplies, SupplyId};
+use crate::validation::ValidatorOutput;
+
+/// Configuration for electrical rule checks.
+///
+/// Supply and ground nets are identified by signal name at any level of hierarchy,
+/// so a net is a supply if any of its hierarchical names matches a configured supply.
+#[derive(Debug, Clone, Default)]
+pub struct ErcConfig {
+    supplies: Vec<(ArcStr, SupplyId)>,
+    grounds: Vec<ArcStr>,
+}
+
+impl ErcConfig {
+    /// Creates a new [`ErcConfig`] with no supply or ground nets.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A consuming method to declare nets with the given name as the given supply.
+    pub fn with_supply(mut self, net: impl Into<ArcStr>, supply: SupplyId) -> Self {
+        self.supplies.push((net.into(), supply));
+        self
+    }
+
+    /// A consuming method to declare nets with the given name as ground.
+    pub fn with_ground(mut self, net: impl Into<ArcStr>) -> Self {
+        self.grounds.push(net.into());
+        self
+    }
+}
+
+/// Runs electrical rule checks on a flattened schematic.
+///
+/// MOSFETs are identified by parsing the raw SPICE of leaf modules and
+/// looking up their models in the given [`MosDb`].
+pub fn validate_erc(
+    flat: &FlatSchematic,
+    mos_db: &MosDb,
+    supplies: &Supplies,
+    config: &ErcConfig,
+) -> ErcValidatorOutput {
+    ErcValidator {
+        flat,
+        mos_db,
+        supplies,
+        config,
+    }
+    .validate()
+}
+
+/// Flags floating gates, incorrect bulk connections, supply shorts,
+/// overvoltage devices, and nets connected only to MOSFET sources and drains.
+pub struct ErcValidator<'a> {
+    flat: &'a FlatSchematic,
+    mos_db: &'a MosDb,
+    supplies: &'a Supplies,
+    config: &'a ErcConfig,
+}
+
+#[derive(Debug, Default)]
+pub struct ErcValidatorData {
+    /// Devices with raw SPICE that could not be parsed.
+    ///
+    /// Their terminals are treated as ordinary connections.
+    skipped: Vec<Vec<ArcStr>>,
+}
+
+impl Log for ErcValidatorData {
+    fn log(&self) {
+        use crate::log::info;
+
+        for path in self.skipped.iter() {
+            info!(
+                "ERC could not parse the raw spice of device {}",
+                path.join(".")
+            );
+        }
+    }
+}
+
+pub type ErcValidatorOutput = ValidatorOutput<Info, Warning, Error, ErcValidatorData>;
+
+/// The location of an ERC violation.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum Location {
+    /// A net, identified by its canonical name.
+    Net(NamedSignalPathBuf),
+    /// A device, identified by its hierarchical path.
+    Device(Vec<ArcStr>),
+}
+
+impl Display for Location {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Net(path) => {
+                write!(f, "net ")?;
+                for inst in path.insts.iter() {
+                    write!(f, "{inst}.")?;
+                }
+                write!(f, "{}", path.signal)?;
+                if let Some(idx) = path.idx {
+                    write!(f, "[{idx}]")?;
+                }
+                Ok(())
+            }
+            Self::Device(path) => write!(f, "device {}", path.join(".")),
+        }
+    }
+}
+
+/// Data for an info-level debug message.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct Info {
+    loc: Location,
+    cause: InfoCause,
+}
+
+/// An enumeration of causes for an info message.
+#[non_exhaustive]
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum InfoCause {
+    /// A net connected only to MOSFET sources and drains.
+    ///
+    /// This is expected for internal nodes of transistor stacks.
+    SourceDrainOnly,
+}
+
+/// Data for a warning.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct Warning {
+    loc: Location,
+    cause: WarningCause,
+}
+
+/// An enumeration of causes for a warning.
+#[non_exhaustive]
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum WarningCause {
+    /// The bulk of an NMOS device is not connected to a ground net.
+    NmosBulkNotGround,
+    /// The bulk of a PMOS device is not connected to a supply net.
+    PmosBulkNotSupply,
+}
+
+/// Data for an error.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Error {
+    loc: Location,
+    cause: ErrorCause,
+}
+
+/// An enumeration of causes for an error.
+#[non_exhaustive]
+#[derive(Debug, Clone, PartialEq)]
+pub enum ErrorCause {
+    /// A net connected only to MOSFET gates.
+    FloatingGate,
+    /// Two supply or ground nets with different identities are shorted together.
+    SupplyShort { a: ArcStr, b: ArcStr },
+    /// A device terminal is connected to a supply above the rating of the device.
+    Overvoltage {
+        terminal: ArcStr,
+        voltage: f64,
+        rating: f64,
+    },
+}
+
+impl Info {
+    /// Creates a new [`Info`].
+    pub fn new(loc: Location, cause: InfoCause) -> Self {
+        Self { loc, cause }
+    }
+
+    /// The location of the message.
+    #[inline]
+    pub fn loc(&self) -> &Location {
+        &self.loc
+    }
+
+    /// The cause of the message.
+    #[inline]
+    pub fn cause(&self) -> &InfoCause {
+        &self.cause
+    }
+}
+
+impl Display for Info {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.cause {
+            InfoCause::SourceDrainOnly => write!(
+                f,
+                "net is only connected to MOSFET sources and drains: {}",
+                self.loc
+            ),
+        }
+    }
+}
+
+impl Log for Info {
+    fn log(&self) {
+        use crate::log::info;
+        info!("{self}");
+    }
+}
+
+impl Warning {
+    /// Creates a new [`Warning`].
+    pub fn new(loc: Location, cause: WarningCause) -> Self {
+        Self { loc, cause }
+    }
+
+    /// The location of the warning.
+    #[inline]
+    pub fn loc(&self) -> &Location {
+        &self.loc
+    }
+
+    /// The cause of the warning.
+    #[inline]
+    pub fn cause(&self) -> &WarningCause {
+        &self.cause
+    }
+}
+
+impl Display for Warning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.cause {
+            WarningCause::NmosBulkNotGround => {
+                write!(f, "NMOS bulk is not connected to ground: {}", self.loc)
+            }
+            WarningCause::PmosBulkNotSupply => {
+                write!(f, "PMOS bulk is not connected to a supply: {}", self.loc)
+            }
+        }
+    }
+}
+
+impl Log for Warning {
+    fn log(&self) {
+        use crate::log::warn;
+        warn!("{self}");
+    }
+}
+
+impl Error {
+    /// Creates a new [`Error`].
+    pub fn new(loc: Location, cause: ErrorCause) -> Self {
+        Self { loc, cause }
+    }
+
+    /// The location of the error.
+    #[inline]
+    pub fn loc(&self) -> &Location {
+        &self.loc
+    }
+
+    /// The cause of the error.
+    #[inline]
+    pub fn cause(&self) -> &ErrorCause {
+        &self.cause
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.cause {
+            ErrorCause::FloatingGate => {
+                write!(f, "net is only connected to MOSFET gates: {}", self.loc)
+            }
+            ErrorCause::SupplyShort { a, b } => {
+                write!(f, "supplies `{a}` and `{b}` are shorted: {}", self.loc)
+            }
+            ErrorCause::Overvoltage {
+                terminal,
+                voltage,
+                rating,
+            } => write!(
+                f,
+                "terminal {terminal} is connected to {voltage} V, above the device rating of {rating} V: {}",
+                self.loc
+            ),
+        }
+    }
+}
+
+impl Log for Error {
+    /// Logs the error to `stderr`.
+    fn log(&self) {
+        use crate::log::error;
+        error!("{self}");
+    }
+}
+
+/// The role of a device terminal, for the purposes of ERC.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum Role {
+    Gate,
+    SourceDrain,
+    Bulk,
+    Other,
+}
+
+/// The names of MOSFET terminals, in `d`, `g`, `s`, `b` order.
+const MOS_TERMINALS: [&str; 4] = ["d", "g", "s", "b"];
+
+impl<'a> ErcValidator<'a> {
+    /// Validates the flattened schematic.
+    fn validate(&self) -> ErcValidatorOutput {
+        let mut output = ErcValidatorOutput::default();
+
+        let (supplies, grounds) = self.supply_nets(&mut output);
+        let mut skipped = Vec::new();
+        let mos = self.flat.find_mos(self.mos_db, &mut skipped);
+        output.data.skipped.extend(
+            skipped
+                .into_iter()
+                .map(|id| self.flat.device(id).path().to_vec()),
+        );
+
+        let mut roles: HashMap<NetId, Vec<Role>> = HashMap::new();
+        let mos_devices = mos.iter().map(|m| m.device).collect::<HashSet<_>>();
+        for m in mos.iter() {
+            for (i, &net) in m.nets.iter().enumerate() {
+                let role = match i {
+                    1 => Role::Gate,
+                    3 => Role::Bulk,
+                    _ => Role::SourceDrain,
+                };
+                roles.entry(net).or_default().push(role);
+            }
+        }
+        for (id, device) in self.flat.devices() {
+            if mos_devices.contains(&id) {
+                continue;
+            }
+            for &(_, net) in device.terminals() {
+                roles.entry(net).or_default().push(Role::Other);
+            }
+        }
+
+        let ports = self
+            .flat
+            .ports()
+            .iter()
+            .map(|port| port.net)
+            .collect::<HashSet<_>>();
+        let mut nets = roles.into_iter().collect::<Vec<_>>();
+        nets.sort_by_key(|(net, _)| *net);
+        for (net, roles) in nets {
+            if ports.contains(&net) || supplies.contains_key(&net) || grounds.contains(&net) {
+                continue;
+            }
+            let loc = Location::Net(self.flat.net(net).name().clone());
+            let has = |role| roles.contains(&role);
+            if has(Role::Gate) && !has(Role::SourceDrain) && !has(Role::Other) {
+                output
+                    .errors
+                    .push(Error::new(loc, ErrorCause::FloatingGate));
+            } else if has(Role::SourceDrain) && !has(Role::Gate) && !has(Role::Other) {
+                output
+                    .infos
+                    .push(Info::new(loc, InfoCause::SourceDrainOnly));
+            }
+        }
+
+        for m in mos.iter() {
+            self.validate_mos(m, &supplies, &grounds, &mut output);
+        }
+
+        output
+    }
+
+    /// Resolves the configured supply and ground nets, flagging shorts between them.
+    fn supply_nets(
+        &self,
+        output: &mut ErcValidatorOutput,
+    ) -> (HashMap<NetId, (ArcStr, SupplyId)>, HashSet<NetId>) {
+        let mut matches: HashMap<NetId, Vec<(&ArcStr, Option<&SupplyId>)>> = HashMap::new();
+        for (path, net) in self.flat.hierarchical_nets() {
+            if path.idx.is_some() {
+                continue;
+            }
+            for (name, id) in self.config.supplies.iter() {
+                if *name == path.signal {
+                    matches.entry(net).or_default().push((name, Some(id)));
+                }
+            }
+            for name in self.config.grounds.iter() {
+                if *name == path.signal {
+                    matches.entry(net).or_default().push((name, None));
+                }
+            }
+        }
+
+        let mut matches = matches.into_iter().collect::<Vec<_>>();
+        matches.sort_by_key(|(net, _)| *net);
+
+        let mut supplies = HashMap::new();
+        let mut grounds = HashSet::new();
+        for (net, mut names) in matches {
+            names.sort_by(|a, b| a.0.cmp(b.0));
+            names.dedup();
+            let (name, id) = names[0];
+            if let Some(&(other, _)) = names.iter().find(|(_, other_id)| *other_id != id) {
+                output.errors.push(Error::new(
+                    Location::Net(self.flat.net(net).name().clone()),
+                    ErrorCause::SupplyShort {
+                        a: name.clone(),
+                        b: other.clone(),
+                    },
+                ));
+            }
+            match id {
+                Some(id) => {
+                    supplies.insert(net, (name.clone(), id.clone()));
+                }
+                None => {
+                    grounds.insert(net);
+                }
+            }
+        }
+
+        (supplies, grounds)
+    }
+
+    /// Validates the bulk connection and terminal voltages of a single MOSFET.
+    fn validate_mos(
+        &self,
+        m: &FlatMos,
+        supplies: &HashMap<NetId, (ArcStr, SupplyId)>,
+        grounds: &HashSet<NetId>,
+        output: &mut ErcValidatorOutput,
+    ) {
+        let loc = Location::Device(self.flat.device(m.device).path().to_vec());
+        let bulk = m.nets[3];
+        match m.spec.kind {
+            MosKind::Nmos if !grounds.contains(&bulk) => output
+                .warnings
+                .push(Warning::new(loc.clone(), WarningCause::NmosBulkNotGround)),
+            MosKind::Pmos if !supplies.contains_key(&bulk) => output
+                .warnings
+                .push(Warning::new(loc.clone(), WarningCause::PmosBulkNotSupply)),
+            _ => (),
+        }
+
+        let rating = match self.supplies.values.get(&m.spec.supply) {
+            Some(supply) => supply.max.unwrap_or(supply.typ),
+            None => return,
+        };
+        for (terminal, net) in MOS_TERMINALS.iter().zip(m.nets) {
+            let voltage = supplies
+                .get(&net)
+                .and_then(|(_, id)| self.supplies.values.get(id))
+                .map(|supply| supply.typ);
+            if let Some(voltage) = voltage.filter(|&voltage| voltage > rating) {
+                output.errors.push(Error::new(
+                    loc.clone(),
+                    ErrorCause::Overvoltage {
+                        terminal: ArcStr::from(*terminal),
+                        voltage,
+                        rating,
+                    },
+                ));
+            }
+        }
+    }
+}
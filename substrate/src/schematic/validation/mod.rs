@@ -2,4 +2,5 @@
 
 pub mod connectivity;
 pub mod drivers;
+pub mod erc;
 pub mod naming;
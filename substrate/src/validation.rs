@@ -60,6 +60,24 @@ where
         }
     }
 
+    /// Returns the info-level messages.
+    #[inline]
+    pub fn infos(&self) -> &[I] {
+        &self.infos
+    }
+
+    /// Returns the warnings.
+    #[inline]
+    pub fn warnings(&self) -> &[W] {
+        &self.warnings
+    }
+
+    /// Returns the errors.
+    #[inline]
+    pub fn errors(&self) -> &[E] {
+        &self.errors
+    }
+
     /// Returns `true` is any errors were encountered.
     #[inline]
     pub fn has_errors(&self) -> bool {
@@ -8,6 +8,8 @@ use serde::{Deserialize, Serialize};
 use crate::deps::arcstr::ArcStr;
 use crate::error::Result;
 use crate::layout::LayoutFormat;
+use crate::pdk::mos::db::MosDb;
+use crate::schematic::netlist::impls::cdl::CdlNetlister;
 
 /// Inputs passed to a [`LvsTool`].
 #[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
@@ -71,4 +73,15 @@ pub struct LvsOutput {
 pub trait LvsTool {
     /// Runs the LVS tool on the provided input files.
     fn run_lvs(&self, input: LvsInput) -> Result<LvsOutput>;
+
+    /// Returns the [`CdlNetlister`] to use for source netlists,
+    /// if the tool expects them in CDL format.
+    ///
+    /// If a netlister is returned, [`SubstrateCtx::write_lvs`](crate::data::SubstrateCtx::write_lvs)
+    /// writes the source netlist using it instead of the configured netlister.
+    /// Tools can use [`CdlNetlister::from_mos_db`] to recognize the PDK's MOSFETs,
+    /// and declare the global and substrate nets their rule decks expect.
+    fn cdl_netlister(&self, _mos_db: &MosDb) -> Option<CdlNetlister> {
+        None
+    }
 }
@@ -1,10 +1,12 @@
 use std::path::PathBuf;
 
+use super::oppoint::MosOpSelect;
 use super::{Analysis, OutputFormat, Save, SimInput, SimOutput};
 use crate::units::SiValue;
 
 pub struct PreSimCtx {
     pub(crate) input: SimInput,
+    pub(crate) mos_op: MosOpSelect,
 }
 
 pub struct PostSimCtx {
@@ -14,7 +16,10 @@ pub struct PostSimCtx {
 impl PreSimCtx {
     #[inline]
     pub(crate) fn new(input: SimInput) -> Self {
-        Self { input }
+        Self {
+            input,
+            mos_op: MosOpSelect::None,
+        }
     }
 
     pub fn add_analysis(&mut self, analysis: impl Into<Analysis>) -> &mut Self {
@@ -54,6 +59,14 @@ impl PreSimCtx {
         self
     }
 
+    /// Saves the operating points of the selected MOSFETs during operating point analyses.
+    ///
+    /// The results are available in [`OpData::mos`](super::OpData::mos).
+    pub fn save_mos_op(&mut self, select: MosOpSelect) -> &mut Self {
+        self.mos_op = select;
+        self
+    }
+
     pub fn set_ic(&mut self, node: impl Into<String>, value: SiValue) -> &mut Self {
         self.input.ic.insert(node.into(), value);
         self
@@ -5,6 +5,7 @@ use derive_builder::Builder;
 use itertools::Itertools;
 use serde::{Deserialize, Serialize};
 
+use self::oppoint::{MosInstance, MosOpPoint, MosOpTarget};
 use self::waveform::{binary_search_before, SharedWaveform};
 use crate::error::Result;
 use crate::schematic::signal::NamedSignalPathBuf;
@@ -12,6 +13,7 @@ use crate::units::SiValue;
 
 pub mod bits;
 pub mod context;
+pub mod oppoint;
 pub mod testbench;
 pub mod waveform;
 
@@ -27,6 +29,8 @@ pub struct SimInput {
     pub measurements: Vec<Measurement>,
     pub analyses: Vec<Analysis>,
     pub output_format: OutputFormat,
+    /// MOSFETs whose operating points should be saved during operating point analyses.
+    pub mos_op: Vec<MosOpTarget>,
 }
 
 #[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
@@ -117,6 +121,10 @@ impl OpAnalysis {
 pub struct OpData {
     /// All saved signals.
     pub data: HashMap<String, ScalarSignal>,
+    /// The operating points of the MOSFETs in [`SimInput::mos_op`],
+    /// keyed by leaf instance path and element name.
+    #[serde(default)]
+    pub mos: HashMap<MosInstance, MosOpPoint>,
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -477,6 +485,17 @@ impl std::ops::IndexMut<usize> for RealSignal {
     }
 }
 
+impl OpData {
+    /// Returns the operating point of the MOSFET named `element`
+    /// in the leaf instance at the given hierarchical path.
+    pub fn mos_op<S: AsRef<str>>(&self, path: &[S], element: &str) -> Option<&MosOpPoint> {
+        self.mos.get(&MosInstance::new(
+            path.iter().map(|inst| inst.as_ref()),
+            element,
+        ))
+    }
+}
+
 impl TranData {
     pub fn signal(&self, name: &str) -> Option<&RealSignal> {
         self.data.get(name)
@@ -0,0 +1,291 @@
+//! MOSFET operating point back-annotation.
+//!
+//! Testbenches request operating point parameters using
+//! [`PreSimCtx::save_mos_op`](super::context::PreSimCtx::save_mos_op).
+//! Before simulating, Substrate finds the selected MOSFETs in the flattened
+//! testbench schematic and passes them to the [`Simulator`](super::Simulator)
+//! as [`MosOpTarget`]s in [`SimInput::mos_op`](super::SimInput::mos_op).
+//! Simulators save the corresponding device parameters and return them in
+//! [`OpData::mos`](super::OpData::mos), keyed by [`MosInstance`].
+
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+
+use crate::deps::arcstr::ArcStr;
+use crate::pdk::mos::spec::MosKind;
+
+/// A hierarchical path to a device, as a list of instance names.
+///
+/// For MOSFETs, this is the path to the instance of the module containing
+/// the raw SPICE of the transistor, such as a `SchematicMos`.
+pub type InstancePath = Vec<ArcStr>;
+
+/// A MOSFET in a flattened schematic.
+///
+/// A single leaf instance may contain several MOSFETs, so each one is identified
+/// by the path to its leaf instance together with its SPICE element name.
+#[derive(Debug, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub struct MosInstance {
+    /// The hierarchical path to the leaf instance containing the MOSFET.
+    pub path: InstancePath,
+    /// The name of the SPICE element of the MOSFET within the leaf, such as `M0` or `X0`.
+    pub element: ArcStr,
+}
+
+/// A selection of MOSFETs whose operating points should be saved.
+#[derive(Debug, Default, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub enum MosOpSelect {
+    /// Do not save any MOSFET operating points.
+    #[default]
+    None,
+    /// Save the operating points of all MOSFETs.
+    All,
+    /// Save the operating points of the given MOSFETs.
+    Instances(Vec<MosInstance>),
+}
+
+/// A MOSFET whose operating point should be saved by a simulator.
+#[derive(Debug, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub struct MosOpTarget {
+    /// The hierarchical path to the leaf instance containing the MOSFET.
+    pub path: InstancePath,
+    /// The name of the SPICE element of the MOSFET, such as `M0` or `X0`.
+    ///
+    /// Elements beginning with `X` are PDK subcircuits that wrap a single
+    /// MOSFET named `M` followed by the model name.
+    pub element: ArcStr,
+    /// The name of the MOSFET model.
+    pub model: ArcStr,
+    /// The MOSFET type.
+    pub kind: MosKind,
+}
+
+/// An operating point parameter of a MOSFET.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub enum MosOpParam {
+    /// Drain current.
+    Id,
+    /// Transconductance.
+    Gm,
+    /// Output conductance.
+    Gds,
+    /// Body transconductance.
+    Gmbs,
+    /// Threshold voltage.
+    Vth,
+    /// Drain-source saturation voltage.
+    Vdsat,
+    /// Gate-source voltage.
+    Vgs,
+    /// Drain-source voltage.
+    Vds,
+    /// Bulk-source voltage.
+    Vbs,
+}
+
+/// The region of operation of a MOSFET.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub enum MosRegion {
+    Off,
+    Triode,
+    Saturation,
+    Subthreshold,
+    Breakdown,
+}
+
+/// The operating point of a MOSFET.
+///
+/// Voltages and currents use the simulator's sign conventions,
+/// so they are typically negative for PMOS devices.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MosOpPoint {
+    pub id: f64,
+    pub gm: f64,
+    pub gds: f64,
+    pub gmbs: f64,
+    pub vth: f64,
+    pub vdsat: f64,
+    pub vgs: f64,
+    pub vds: f64,
+    pub vbs: f64,
+    pub region: MosRegion,
+}
+
+impl MosInstance {
+    /// Creates a new [`MosInstance`] from a leaf instance path and an element name.
+    pub fn new<S: Into<ArcStr>>(
+        path: impl IntoIterator<Item = S>,
+        element: impl Into<ArcStr>,
+    ) -> Self {
+        Self {
+            path: path.into_iter().map(Into::into).collect(),
+            element: element.into(),
+        }
+    }
+}
+
+impl Display for MosInstance {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for inst in self.path.iter() {
+            write!(f, "{inst}/")?;
+        }
+        write!(f, "{}", self.element)
+    }
+}
+
+impl MosOpTarget {
+    /// The [`MosInstance`] identifying this MOSFET.
+    pub fn instance(&self) -> MosInstance {
+        MosInstance {
+            path: self.path.clone(),
+            element: self.element.clone(),
+        }
+    }
+}
+
+impl MosOpParam {
+    /// All operating point parameters.
+    pub const ALL: [MosOpParam; 9] = [
+        Self::Id,
+        Self::Gm,
+        Self::Gds,
+        Self::Gmbs,
+        Self::Vth,
+        Self::Vdsat,
+        Self::Vgs,
+        Self::Vds,
+        Self::Vbs,
+    ];
+
+    /// The name of the parameter, as used by BSIM models.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Id => "id",
+            Self::Gm => "gm",
+            Self::Gds => "gds",
+            Self::Gmbs => "gmbs",
+            Self::Vth => "vth",
+            Self::Vdsat => "vdsat",
+            Self::Vgs => "vgs",
+            Self::Vds => "vds",
+            Self::Vbs => "vbs",
+        }
+    }
+}
+
+impl MosRegion {
+    /// Infers the region of operation from terminal voltages.
+    ///
+    /// Cannot distinguish between [`MosRegion::Off`] and [`MosRegion::Subthreshold`],
+    /// or detect [`MosRegion::Breakdown`].
+    pub fn infer(kind: MosKind, vgs: f64, vds: f64, vth: f64, vdsat: f64) -> Self {
+        let sign = match kind {
+            MosKind::Nmos => 1.,
+            MosKind::Pmos => -1.,
+        };
+        if sign * vgs < vth.abs() {
+            Self::Subthreshold
+        } else if sign * vds < vdsat.abs() {
+            Self::Triode
+        } else {
+            Self::Saturation
+        }
+    }
+
+    /// Converts a Spectre `region` operating point value to a [`MosRegion`].
+    pub fn from_spectre(value: f64) -> Option<Self> {
+        Some(match value.round() as i64 {
+            0 => Self::Off,
+            1 => Self::Triode,
+            2 => Self::Saturation,
+            3 => Self::Subthreshold,
+            4 => Self::Breakdown,
+            _ => return None,
+        })
+    }
+}
+
+impl MosOpPoint {
+    /// Assembles an operating point from the values of its parameters.
+    ///
+    /// If `region` is [`None`], the region is [inferred](MosRegion::infer) from the
+    /// terminal voltages. Returns [`None`] if any parameter is missing.
+    pub fn from_params(
+        kind: MosKind,
+        region: Option<MosRegion>,
+        mut value: impl FnMut(MosOpParam) -> Option<f64>,
+    ) -> Option<Self> {
+        let vgs = value(MosOpParam::Vgs)?;
+        let vds = value(MosOpParam::Vds)?;
+        let vth = value(MosOpParam::Vth)?;
+        let vdsat = value(MosOpParam::Vdsat)?;
+        Some(Self {
+            id: value(MosOpParam::Id)?,
+            gm: value(MosOpParam::Gm)?,
+            gds: value(MosOpParam::Gds)?,
+            gmbs: value(MosOpParam::Gmbs)?,
+            vth,
+            vdsat,
+            vgs,
+            vds,
+            vbs: value(MosOpParam::Vbs)?,
+            region: region.unwrap_or_else(|| MosRegion::infer(kind, vgs, vds, vth, vdsat)),
+        })
+    }
+
+    /// The transconductance efficiency, `gm / |id|`.
+    #[inline]
+    pub fn gm_over_id(&self) -> f64 {
+        self.gm / self.id.abs()
+    }
+
+    /// The intrinsic gain, `gm / gds`.
+    #[inline]
+    pub fn intrinsic_gain(&self) -> f64 {
+        self.gm / self.gds
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_infer_region() {
+        let nmos = |vgs, vds| MosRegion::infer(MosKind::Nmos, vgs, vds, 0.4, 0.2);
+        assert_eq!(nmos(0.2, 1.0), MosRegion::Subthreshold);
+        assert_eq!(nmos(0.8, 0.1), MosRegion::Triode);
+        assert_eq!(nmos(0.8, 1.0), MosRegion::Saturation);
+
+        let pmos = |vgs, vds| MosRegion::infer(MosKind::Pmos, vgs, vds, -0.4, -0.2);
+        assert_eq!(pmos(-0.2, -1.0), MosRegion::Subthreshold);
+        assert_eq!(pmos(-0.8, -0.1), MosRegion::Triode);
+        assert_eq!(pmos(-0.8, -1.0), MosRegion::Saturation);
+    }
+
+    #[test]
+    fn test_from_params() {
+        let op = MosOpPoint::from_params(MosKind::Nmos, None, |param| {
+            Some(match param {
+                MosOpParam::Id => 1e-4,
+                MosOpParam::Gm => 1e-3,
+                MosOpParam::Gds => 1e-5,
+                MosOpParam::Vgs => 0.8,
+                MosOpParam::Vds => 1.0,
+                MosOpParam::Vth => 0.4,
+                MosOpParam::Vdsat => 0.2,
+                _ => 0.,
+            })
+        })
+        .unwrap();
+        assert_eq!(op.region, MosRegion::Saturation);
+        assert_eq!(op.gm_over_id(), 10.);
+        assert_eq!(op.intrinsic_gain(), 100.);
+
+        let op = MosOpPoint::from_params(MosKind::Nmos, Some(MosRegion::Off), |param| {
+            (param != MosOpParam::Gmbs).then_some(0.)
+        });
+        assert!(op.is_none());
+    }
+}
@@ -4,13 +4,14 @@ use std::path::PathBuf;
 
 use ngspice::Ngspice;
 use sky130_open_pdk::Sky130OpenPdk;
-use substrate::data::{SubstrateConfig, SubstrateCtx};
+use substrate::data::{SubstrateConfig, SubstrateConfigBuilder, SubstrateCtx};
 use substrate::pdk::PdkParams;
 use substrate::schematic::netlist::impls::spice::SpiceNetlister;
 use substrate::verification::simulation::{Simulator, SimulatorOpts};
 use substrate::verification::timing::TimingConfig;
 
 pub mod common_source;
+pub mod nets;
 pub mod sp_cell;
 pub mod vdivider;
 
@@ -32,6 +33,12 @@ pub fn out_path(test_name: &str, file_name: &str) -> PathBuf {
 }
 
 pub fn setup_ctx() -> SubstrateCtx {
+    SubstrateCtx::from_config(config_builder().build()).unwrap()
+}
+
+/// Returns a [`SubstrateConfigBuilder`] with the settings used by [`setup_ctx`],
+/// for tests that need to configure additional tools.
+pub fn config_builder() -> SubstrateConfigBuilder {
     let simulator = Ngspice::new(SimulatorOpts::default()).unwrap();
     let pdk_root = std::env::var("SKY130_OPEN_PDK_ROOT").expect("the SKY130_OPEN_PDK_ROOT environment variable should be set to the root of the skywater-pdk repository").into();
 
@@ -41,11 +48,11 @@ pub fn setup_ctx() -> SubstrateCtx {
         .build()
         .unwrap();
 
-    let cfg = SubstrateConfig::builder()
+    let mut builder = SubstrateConfig::builder();
+    builder
         .netlister(SpiceNetlister::new())
         .simulator(simulator)
         .timing_config(timing_config)
-        .pdk(Sky130OpenPdk::new(&PdkParams { pdk_root }).unwrap())
-        .build();
-    SubstrateCtx::from_config(cfg).unwrap()
+        .pdk(Sky130OpenPdk::new(&PdkParams { pdk_root }).unwrap());
+    builder
 }
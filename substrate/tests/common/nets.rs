@@ -0,0 +1,56 @@
+use arcstr::ArcStr;
+use subgeom::{Point, Rect};
+use substrate::component::{Component, NoParams};
+use substrate::data::SubstrateCtx;
+use substrate::layout::cell::{Element, Instance};
+use substrate::layout::context::LayoutCtx;
+use substrate::layout::layers::selector::Selector;
+use substrate::layout::layers::LayerSpec;
+
+/// A metal 1 wire on net `x`.
+pub struct NetLeaf;
+
+impl Component for NetLeaf {
+    type Params = NoParams;
+    fn new(_params: &Self::Params, _ctx: &SubstrateCtx) -> substrate::error::Result<Self> {
+        Ok(Self)
+    }
+    fn name(&self) -> ArcStr {
+        arcstr::literal!("net_leaf")
+    }
+
+    fn layout(&self, ctx: &mut LayoutCtx) -> substrate::error::Result<()> {
+        let m1 = ctx.layers().get(Selector::Metal(1))?;
+        let x = Rect::new(Point::new(0, 0), Point::new(1_000, 200));
+        ctx.draw(Element::with_net_name("x", LayerSpec::drawing(m1), x))?;
+        Ok(())
+    }
+}
+
+/// A [`NetLeaf`] instance named `leaf0`, and a metal 2 wire on net `Y`.
+pub struct NetTop;
+
+impl Component for NetTop {
+    type Params = NoParams;
+    fn new(_params: &Self::Params, _ctx: &SubstrateCtx) -> substrate::error::Result<Self> {
+        Ok(Self)
+    }
+    fn name(&self) -> ArcStr {
+        arcstr::literal!("net_top")
+    }
+
+    fn layout(&self, ctx: &mut LayoutCtx) -> substrate::error::Result<()> {
+        let m2 = ctx.layers().get(Selector::Metal(2))?;
+        let leaf = ctx.instantiate::<NetLeaf>(&NoParams)?;
+        let leaf = Instance::builder()
+            .name(arcstr::literal!("leaf0"))
+            .cell(leaf.cell().clone())
+            .loc(Point::new(500, 1_000))
+            .build()
+            .unwrap();
+        ctx.draw(leaf)?;
+        let y = Rect::new(Point::new(0, 0), Point::new(200, 2_000));
+        ctx.draw(Element::with_net_name("Y", LayerSpec::drawing(m2), y))?;
+        Ok(())
+    }
+}
@@ -0,0 +1,255 @@
+use std::collections::HashMap;
+
+use arcstr::ArcStr;
+use subgeom::orientation::Named;
+use subgeom::{Point, Rect, Shape};
+use substrate::component::{Component, NoParams};
+use substrate::data::SubstrateCtx;
+use substrate::layout::cell::{CellPort, Element, PortId};
+use substrate::layout::context::LayoutCtx;
+use substrate::layout::convert::def::{DefExportOpts, DefImportOpts};
+use substrate::layout::convert::lef::PlacementBoundary;
+use substrate::layout::layers::selector::Selector;
+use substrate::layout::layers::LayerSpec;
+
+mod common;
+use common::{out_path, setup_ctx};
+
+const LEF: &str = r#"
+VERSION 5.8 ;
+UNITS
+  DATABASE MICRONS 1000 ;
+END UNITS
+
+LAYER met1
+  TYPE ROUTING ;
+  DIRECTION HORIZONTAL ;
+  PITCH 0.34 ;
+  WIDTH 0.14 ;
+END met1
+
+MACRO inv
+  CLASS CORE ;
+  ORIGIN 0 0 ;
+  SIZE 1.38 BY 2.72 ;
+  PIN A
+    DIRECTION INPUT ;
+    PORT
+      LAYER li1 ;
+        RECT 0.1 0.2 0.3 0.4 ;
+    END
+  END A
+  OBS
+    LAYER li1 ;
+      RECT 0.5 0 1.38 2.72 ;
+  END
+END inv
+
+END LIBRARY
+"#;
+
+const DEF: &str = r#"
+VERSION 5.8 ;
+DESIGN inv_top ;
+UNITS DISTANCE MICRONS 1000 ;
+DIEAREA ( 0 0 ) ( 5000 8000 ) ;
+COMPONENTS 1 ;
+- inv0 inv + PLACED ( 1000 2000 ) FS ;
+END COMPONENTS
+PINS 1 ;
+- a + NET a + DIRECTION INPUT
+  + PORT
+    + LAYER met1 ( -70 -70 ) ( 70 70 )
+    + FIXED ( 0 4420 ) N ;
+END PINS
+NETS 1 ;
+- a ( PIN a ) ( inv0 A )
+  + ROUTED met1 ( 0 4420 ) ( 1200 4420 ) ;
+END NETS
+END DESIGN
+"#;
+
+/// A leaf cell with an input and output port on metal 1.
+pub struct DefLeaf;
+
+impl Component for DefLeaf {
+    type Params = NoParams;
+    fn new(_params: &Self::Params, _ctx: &SubstrateCtx) -> substrate::error::Result<Self> {
+        Ok(Self)
+    }
+    fn name(&self) -> ArcStr {
+        arcstr::literal!("def_leaf")
+    }
+
+    fn layout(&self, ctx: &mut LayoutCtx) -> substrate::error::Result<()> {
+        let m1 = ctx.layers().get(Selector::Metal(1))?;
+        ctx.draw_rect(m1, Rect::new(Point::new(0, 0), Point::new(1_000, 500)));
+        let a = Rect::new(Point::new(0, 0), Point::new(200, 500));
+        ctx.add_port(CellPort::with_shape("a", m1, a))?;
+        let y = Rect::new(Point::new(800, 0), Point::new(1_000, 500));
+        ctx.add_port(CellPort::with_shape("y", m1, y))?;
+        Ok(())
+    }
+}
+
+/// Two leaf cells connected in series, with a routed input, a bus output, and a power strap.
+pub struct DefTop;
+
+impl Component for DefTop {
+    type Params = NoParams;
+    fn new(_params: &Self::Params, _ctx: &SubstrateCtx) -> substrate::error::Result<Self> {
+        Ok(Self)
+    }
+    fn name(&self) -> ArcStr {
+        arcstr::literal!("def_top")
+    }
+
+    fn layout(&self, ctx: &mut LayoutCtx) -> substrate::error::Result<()> {
+        let m1 = ctx.layers().get(Selector::Metal(1))?;
+        let m2 = ctx.layers().get(Selector::Metal(2))?;
+
+        let leaf0 = ctx.instantiate::<DefLeaf>(&NoParams)?;
+        let mut leaf1 = ctx.instantiate::<DefLeaf>(&NoParams)?;
+        leaf1.set_orientation(Named::ReflectVert);
+        leaf1.set_loc(Point::new(2_000, 500));
+        ctx.draw(leaf0)?;
+        ctx.draw(leaf1)?;
+
+        let din = Rect::new(Point::new(-500, 200), Point::new(0, 300));
+        ctx.draw(Element::with_net_name("din", LayerSpec::drawing(m1), din))?;
+        let din_pin = Rect::new(Point::new(-500, 200), Point::new(-400, 300));
+        ctx.add_port(CellPort::with_shape("din", m1, din_pin))?;
+
+        let mid = Rect::new(Point::new(1_000, 200), Point::new(2_000, 300));
+        ctx.draw(Element::with_net_name("mid", LayerSpec::drawing(m1), mid))?;
+
+        for i in 0..2 {
+            let y = 100 + 200 * i as i64;
+            let dout = Rect::new(Point::new(3_000, y), Point::new(3_500, y + 50));
+            let name = arcstr::format!("dout[{i}]");
+            ctx.draw(Element::with_net_name(name, LayerSpec::drawing(m1), dout))?;
+            ctx.add_port(CellPort::with_shape(PortId::new("dout", i), m1, dout))?;
+        }
+
+        let vdd = Rect::new(Point::new(0, 600), Point::new(3_000, 700));
+        ctx.draw(Element::with_net_name("vdd", LayerSpec::drawing(m2), vdd))?;
+        Ok(())
+    }
+}
+
+#[test]
+fn test_def_round_trip() {
+    let ctx = setup_ctx();
+    let path = out_path("test_def_round_trip", "layout.def");
+    let opts = DefExportOpts {
+        special_nets: ["vdd".into()].into_iter().collect(),
+    };
+    ctx.write_def::<DefTop>(&NoParams, &path, &opts)
+        .expect("failed to write DEF");
+
+    let design = lefdef::DefDesign::load(&path).expect("failed to read DEF");
+    assert_eq!(design.components.len(), 2);
+    assert_eq!(design.pins.len(), 3);
+    assert_eq!(design.special_nets.len(), 1);
+    assert_eq!(design.nets.len(), 4);
+    let mid = design.nets.iter().find(|n| n.name == "mid").unwrap();
+    assert_eq!(mid.connections.len(), 2);
+
+    let leaf = ctx.instantiate_layout::<DefLeaf>(&NoParams).unwrap();
+    let macros = HashMap::from([(leaf.cell().name().clone(), leaf.cell().clone())]);
+    let imported = ctx
+        .from_def(&path, &macros, &DefImportOpts::default())
+        .expect("failed to import DEF");
+    assert_eq!(imported.insts().count(), 2);
+
+    let top = ctx.instantiate_layout::<DefTop>(&NoParams).unwrap();
+    let diff = ctx.diff_layouts(top.cell(), &imported).unwrap();
+    assert!(diff.is_empty(), "{diff}");
+
+    // Regular nets need a default wire width for each routing layer.
+    let mut design = design;
+    design.nets[0].wires[0].points.truncate(1);
+    design.nets[0].wires[0]
+        .points
+        .push(lefdef::DefRoutePoint::Point {
+            x: 0,
+            y: 0,
+            ext: None,
+        });
+    assert!(ctx
+        .from_def_design(&design, &macros, &DefImportOpts::default())
+        .is_err());
+    assert!(ctx
+        .from_def_design(&design, &HashMap::new(), &DefImportOpts::default())
+        .is_err());
+}
+
+#[test]
+fn test_def_import_with_lef() {
+    let ctx = setup_ctx();
+    let lef = out_path("test_def_import_with_lef", "cells.lef");
+    let def = out_path("test_def_import_with_lef", "layout.def");
+    std::fs::create_dir_all(lef.parent().unwrap()).unwrap();
+    std::fs::write(&lef, LEF).unwrap();
+    std::fs::write(&def, DEF).unwrap();
+
+    let li1 = ctx.layers().get(Selector::Name("li1")).unwrap();
+    let m1 = ctx.layers().get(Selector::Metal(1)).unwrap();
+
+    let macros = ctx.from_lef(&lef).expect("failed to import LEF");
+    let inv = &macros["inv"];
+    assert_eq!(
+        inv.try_get_metadata::<PlacementBoundary>(),
+        Some(&PlacementBoundary(Rect::new(
+            Point::zero(),
+            Point::new(1_380, 2_720)
+        )))
+    );
+    let a = inv.port("A").unwrap();
+    assert_eq!(
+        a.shapes(li1).cloned().collect::<Vec<_>>(),
+        vec![Shape::Rect(Rect::new(
+            Point::new(100, 200),
+            Point::new(300, 400)
+        ))]
+    );
+    assert_eq!(inv.blockages().count(), 1);
+
+    let cell = ctx
+        .from_def_with_lef(&def, &lef)
+        .expect("failed to import DEF");
+    assert_eq!(
+        cell.try_get_metadata::<PlacementBoundary>(),
+        Some(&PlacementBoundary(Rect::new(
+            Point::zero(),
+            Point::new(5_000, 8_000)
+        )))
+    );
+
+    // The component is flipped within its placement boundary.
+    let inst = cell.insts().next().unwrap();
+    let a = inst.ports().find(|p| p.name() == "A").unwrap();
+    assert_eq!(
+        a.shapes(li1).cloned().collect::<Vec<_>>(),
+        vec![Shape::Rect(Rect::new(
+            Point::new(1_100, 4_320),
+            Point::new(1_300, 4_520)
+        ))]
+    );
+
+    let a = cell.port("a").unwrap();
+    assert_eq!(
+        a.shapes(m1).cloned().collect::<Vec<_>>(),
+        vec![Shape::Rect(Rect::new(
+            Point::new(-70, 4_350),
+            Point::new(70, 4_490)
+        ))]
+    );
+    let wires: Vec<_> = cell.elems().collect();
+    assert_eq!(wires.len(), 1);
+    assert_eq!(wires[0].net.as_deref(), Some("a"));
+    assert_eq!(
+        wires[0].inner,
+        Shape::Rect(Rect::new(Point::new(-70, 4_350), Point::new(1_270, 4_490)))
+    );
+}
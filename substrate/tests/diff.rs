@@ -0,0 +1,97 @@
+use arcstr::ArcStr;
+use subgeom::{Point, Rect};
+use substrate::component::{Component, NoParams};
+use substrate::data::SubstrateCtx;
+use substrate::layout::cell::CellPort;
+use substrate::layout::context::LayoutCtx;
+use substrate::layout::layers::selector::Selector;
+use substrate::layout::layers::LayerSpec;
+
+mod common;
+use common::vdivider::array::VDividerArray;
+use common::{out_path, setup_ctx};
+
+/// A divider array with a metal 2 strap, optionally shifted to the right.
+pub struct DiffTarget {
+    shifted: bool,
+}
+
+impl Component for DiffTarget {
+    type Params = bool;
+    fn new(params: &Self::Params, _ctx: &SubstrateCtx) -> substrate::error::Result<Self> {
+        Ok(Self { shifted: *params })
+    }
+    fn name(&self) -> ArcStr {
+        if self.shifted {
+            arcstr::literal!("diff_target_shifted")
+        } else {
+            arcstr::literal!("diff_target")
+        }
+    }
+
+    fn layout(&self, ctx: &mut LayoutCtx) -> substrate::error::Result<()> {
+        let array = ctx.instantiate::<VDividerArray>(&NoParams)?;
+        ctx.draw(array)?;
+
+        let m2 = ctx.layers().get(Selector::Metal(2))?;
+        let dx = if self.shifted { 100 } else { 0 };
+        let rect = Rect::new(Point::new(dx, 600), Point::new(2_500 + dx, 700));
+        ctx.draw_rect(m2, rect);
+        let port = Rect::new(Point::new(1_000, 600), Point::new(1_100, 700));
+        ctx.add_port(CellPort::with_shape("vout", m2, port))?;
+        Ok(())
+    }
+}
+
+#[test]
+fn test_layout_diff() {
+    let ctx = setup_ctx();
+    let m2 = ctx.layers().get(Selector::Metal(2)).unwrap();
+
+    let a = ctx.instantiate_layout::<DiffTarget>(&false).unwrap();
+    let b = ctx.instantiate_layout::<DiffTarget>(&true).unwrap();
+
+    let diff = ctx.diff_layouts(a.cell(), a.cell()).unwrap();
+    diff.assert_empty();
+
+    let diff = ctx.diff_layouts(a.cell(), b.cell()).unwrap();
+    assert!(!diff.is_empty());
+    assert_eq!(diff.layers().len(), 1);
+    let layer = diff
+        .layer(&LayerSpec::drawing(m2))
+        .expect("metal 2 should differ");
+    assert_eq!(
+        layer.only_in_a(),
+        &[Rect::new(Point::new(0, 600), Point::new(100, 700))]
+    );
+    assert_eq!(
+        layer.only_in_b(),
+        &[Rect::new(Point::new(2_500, 600), Point::new(2_600, 700))]
+    );
+    assert_eq!(layer.area(), 20_000);
+
+    let report = diff.to_string();
+    assert!(report.contains("only in diff_target: (0, 600) to (100, 700)"));
+    assert!(report.contains("only in diff_target_shifted: (2500, 600) to (2600, 700)"));
+
+    diff.write_gds(&ctx, out_path("test_layout_diff", "xor.gds"))
+        .expect("failed to write layout XOR");
+}
+
+#[test]
+fn test_layout_diff_golden_gds() {
+    let ctx = setup_ctx();
+    let golden = out_path("test_layout_diff_golden_gds", "golden.gds");
+    ctx.write_layout::<DiffTarget>(&false, &golden)
+        .expect("failed to write golden layout");
+
+    let diff = ctx
+        .diff_layout_with_gds::<DiffTarget>(&false, &golden)
+        .expect("failed to compare layout against golden file");
+    assert!(diff.is_empty(), "{diff}");
+    ctx.assert_layout_matches_gds::<DiffTarget>(&false, &golden);
+
+    let diff = ctx.diff_gds(&golden, &golden, "diff_target").unwrap();
+    assert!(diff.is_empty(), "{diff}");
+    assert!(ctx.diff_gds(&golden, &golden, "no_such_cell").is_err());
+}
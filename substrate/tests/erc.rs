@@ -0,0 +1,148 @@
+use arcstr::ArcStr;
+use common::setup_ctx;
+use substrate::component::{Component, NoParams};
+use substrate::data::SubstrateCtx;
+use substrate::pdk::mos::spec::MosId;
+use substrate::pdk::mos::MosParams;
+use substrate::pdk::SupplyId;
+use substrate::schematic::circuit::Direction;
+use substrate::schematic::context::SchematicCtx;
+use substrate::schematic::elements::mos::SchematicMos;
+use substrate::schematic::elements::resistor::Resistor;
+use substrate::schematic::validation::erc::{
+    ErcConfig, ErrorCause, InfoCause, Location, WarningCause,
+};
+use substrate::units::{SiPrefix, SiValue};
+
+mod common;
+
+/// A resistor between `vdd` and `vss`.
+struct Rail;
+
+impl Component for Rail {
+    type Params = NoParams;
+
+    fn new(_params: &Self::Params, _ctx: &SubstrateCtx) -> substrate::error::Result<Self> {
+        Ok(Self)
+    }
+
+    fn name(&self) -> ArcStr {
+        arcstr::literal!("rail")
+    }
+
+    fn schematic(&self, ctx: &mut SchematicCtx) -> substrate::error::Result<()> {
+        let vdd = ctx.port("vdd", Direction::InOut);
+        let vss = ctx.port("vss", Direction::InOut);
+        ctx.instantiate::<Resistor>(&SiValue::new(1, SiPrefix::Kilo))?
+            .with_connections([("p", &vdd), ("n", &vss)])
+            .named("R1")
+            .add_to(ctx);
+        Ok(())
+    }
+}
+
+/// A circuit with a number of electrical rule violations.
+struct ErcViolations;
+
+impl Component for ErcViolations {
+    type Params = NoParams;
+
+    fn new(_params: &Self::Params, _ctx: &SubstrateCtx) -> substrate::error::Result<Self> {
+        Ok(Self)
+    }
+
+    fn name(&self) -> ArcStr {
+        arcstr::literal!("erc_violations")
+    }
+
+    fn schematic(&self, ctx: &mut SchematicCtx) -> substrate::error::Result<()> {
+        let vdd = ctx.port("vdd", Direction::InOut);
+        let vss = ctx.port("vss", Direction::InOut);
+        let a = ctx.port("a", Direction::Input);
+        let y = ctx.port("y", Direction::Output);
+        let x = ctx.signal("x");
+        let fg = ctx.signal("fg");
+
+        let nmos = MosParams {
+            w: 1_000,
+            l: 150,
+            m: 1,
+            nf: 1,
+            id: MosId::new(0),
+        };
+        let pmos = MosParams {
+            id: MosId::new(1),
+            ..nmos.clone()
+        };
+
+        ctx.instantiate::<SchematicMos>(&nmos)?
+            .with_connections([("d", &y), ("g", &a), ("s", &x), ("b", &vss)])
+            .named("n1")
+            .add_to(ctx);
+        ctx.instantiate::<SchematicMos>(&nmos)?
+            .with_connections([("d", &x), ("g", &a), ("s", &vss), ("b", &vdd)])
+            .named("n2")
+            .add_to(ctx);
+        ctx.instantiate::<SchematicMos>(&pmos)?
+            .with_connections([("d", &y), ("g", &fg), ("s", &vdd), ("b", &vss)])
+            .named("p1")
+            .add_to(ctx);
+        ctx.instantiate::<SchematicMos>(&pmos)?
+            .with_connections([("d", &vdd), ("g", &fg), ("s", &vdd), ("b", &vdd)])
+            .named("p2")
+            .add_to(ctx);
+
+        let x_rail = ctx.signal("x_rail");
+        ctx.instantiate::<Rail>(&NoParams)?
+            .with_connections([("vdd", &x_rail), ("vss", &x_rail)])
+            .named("rail")
+            .add_to(ctx);
+        ctx.instantiate::<Resistor>(&SiValue::new(1, SiPrefix::Kilo))?
+            .with_connections([("p", &x_rail), ("n", &y)])
+            .named("R1")
+            .add_to(ctx);
+        Ok(())
+    }
+}
+
+#[test]
+fn test_erc() {
+    let ctx = setup_ctx();
+    let config = ErcConfig::new()
+        .with_supply("vdd", SupplyId::Core)
+        .with_ground("vss");
+    let output = ctx
+        .run_erc::<ErcViolations>(&NoParams, &config)
+        .expect("failed to run ERC");
+
+    let device = |name: &str| Location::Device(vec![name.into()]);
+    let net_name = |loc: &Location| match loc {
+        Location::Net(path) => path.signal.to_string(),
+        Location::Device(_) => panic!("expected a net"),
+    };
+
+    assert_eq!(output.errors().len(), 2);
+    assert!(output
+        .errors()
+        .iter()
+        .any(|e| *e.cause() == ErrorCause::FloatingGate && net_name(e.loc()) == "fg"));
+    assert!(output
+        .errors()
+        .iter()
+        .any(|e| matches!(e.cause(), ErrorCause::SupplyShort { .. })
+            && net_name(e.loc()) == "x_rail"));
+
+    assert_eq!(output.warnings().len(), 2);
+    assert!(output
+        .warnings()
+        .iter()
+        .any(|w| *w.cause() == WarningCause::NmosBulkNotGround && *w.loc() == device("n2")));
+    assert!(output
+        .warnings()
+        .iter()
+        .any(|w| *w.cause() == WarningCause::PmosBulkNotSupply && *w.loc() == device("p1")));
+
+    assert_eq!(output.infos().len(), 1);
+    assert_eq!(*output.infos()[0].cause(), InfoCause::SourceDrainOnly);
+    assert_eq!(net_name(output.infos()[0].loc()), "x");
+}
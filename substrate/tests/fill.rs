@@ -0,0 +1,103 @@
+use arcstr::ArcStr;
+use subgeom::bbox::BoundBox;
+use subgeom::{Point, Rect};
+use substrate::component::Component;
+use substrate::data::SubstrateCtx;
+use substrate::layout::context::LayoutCtx;
+use substrate::layout::fill::Fill;
+use substrate::layout::layers::selector::Selector;
+
+mod common;
+use common::{out_path, setup_ctx};
+
+fn drawn() -> [Rect; 2] {
+    [
+        Rect::new(Point::new(0, 0), Point::new(100_000, 10_000)),
+        Rect::new(Point::new(80_000, 80_000), Point::new(100_000, 100_000)),
+    ]
+}
+
+/// Draws a sparse set of metal 1 shapes, optionally filling the result.
+pub struct SparseMetal {
+    fill: bool,
+}
+
+impl Component for SparseMetal {
+    type Params = bool;
+    fn new(params: &Self::Params, _ctx: &SubstrateCtx) -> substrate::error::Result<Self> {
+        Ok(Self { fill: *params })
+    }
+    fn name(&self) -> ArcStr {
+        if self.fill {
+            arcstr::literal!("sparse_metal_filled")
+        } else {
+            arcstr::literal!("sparse_metal")
+        }
+    }
+
+    fn layout(&self, ctx: &mut LayoutCtx) -> substrate::error::Result<()> {
+        let m1 = ctx.layers().get(Selector::Metal(1))?;
+        for rect in drawn() {
+            ctx.draw_rect(m1, rect);
+        }
+        if self.fill {
+            let report = ctx.fill()?;
+            assert!(report.is_clean());
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn test_fill() {
+    let ctx = setup_ctx();
+    let m1 = ctx.layers().get(Selector::Metal(1)).unwrap();
+
+    let sparse = ctx.instantiate_layout::<SparseMetal>(&false).unwrap();
+    let fill = Fill::compute(&ctx, sparse.cell()).expect("failed to compute fill");
+    let density = fill.report().layer(m1).expect("metal 1 should be filled");
+    let rules = density.rules();
+    assert_eq!(density.windows().len(), 1);
+    let window = density.windows()[0];
+    assert_eq!(window.window, sparse.brect());
+    assert_eq!(window.before, 0.14);
+    assert!(window.after >= rules.min_density && window.after <= rules.max_density);
+    assert!(fill.report().is_clean());
+
+    let poly = ctx.layers().get(Selector::Name("poly")).unwrap();
+    let density = fill.report().layer(poly).expect("poly should be filled");
+    assert!(density.rules().min_density < density.rules().max_density);
+    assert!(density.violations().next().is_none());
+
+    let filled = ctx.instantiate_layout::<SparseMetal>(&true).unwrap();
+    let insts = filled.cell().insts().collect::<Vec<_>>();
+    assert_eq!(insts.len(), 1);
+    assert_eq!(insts[0].name(), "fill");
+    assert_eq!(insts[0].cell().name(), "sparse_metal_filled_fill");
+    assert_eq!(filled.brect(), sparse.brect());
+
+    let fill_shapes = insts[0].shapes_on(m1).collect::<Vec<_>>();
+    assert!(!fill_shapes.is_empty());
+    for shape in fill_shapes {
+        let rect = shape.brect();
+        assert_eq!(rect.width(), rules.fill_width);
+        assert_eq!(rect.height(), rules.fill_height);
+        for other in drawn() {
+            let keepout = other.expand(rules.space_to_drawn);
+            assert!(
+                rect.right() <= keepout.left()
+                    || rect.left() >= keepout.right()
+                    || rect.top() <= keepout.bottom()
+                    || rect.bottom() >= keepout.top(),
+                "fill shape {rect:?} is too close to drawn shape {other:?}"
+            );
+        }
+    }
+
+    // A filled cell should not need any more fill.
+    let refill = Fill::compute(&ctx, filled.cell()).expect("failed to compute fill");
+    assert!(refill.is_empty());
+
+    ctx.write_layout::<SparseMetal>(&true, out_path("test_fill", "layout.gds"))
+        .expect("failed to write layout");
+}
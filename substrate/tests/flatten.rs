@@ -0,0 +1,50 @@
+use common::setup_ctx;
+use common::vdivider::array::VDividerArrayWrapper;
+use substrate::component::NoParams;
+use substrate::schematic::flatten::FlatDeviceKind;
+use substrate::schematic::primitive::PrimitiveKind;
+use substrate::schematic::signal::NamedSignalPathBuf;
+
+mod common;
+
+#[test]
+fn test_flatten_vdivider_array() {
+    let ctx = setup_ctx();
+    let flat = ctx
+        .flatten_schematic::<VDividerArrayWrapper>(&NoParams)
+        .expect("failed to flatten schematic");
+
+    assert_eq!(flat.name(), "vdivider_array_wrapper");
+    assert_eq!(flat.devices().count(), 20);
+    assert!(flat.devices().all(|(_, device)| matches!(
+        device.kind(),
+        FlatDeviceKind::Primitive(PrimitiveKind::Resistor(_))
+    )));
+
+    let vdd = flat.port("vdd", None).unwrap();
+    let vss = flat.port("vss", None).unwrap();
+    let out3 = flat.port("out", Some(3)).unwrap();
+    assert_eq!(flat.devices_on(vdd).len(), 10);
+    assert_eq!(flat.fanout("out", Some(3)), Some(2));
+    assert_eq!(flat.fanout("out", None), None);
+
+    // Hierarchical references resolve to the canonical top-level net.
+    let inner = NamedSignalPathBuf {
+        insts: vec!["0".into(), "vdivider_3".into()],
+        signal: "out".into(),
+        idx: None,
+    };
+    assert_eq!(flat.find_net(&inner), Some(out3));
+    assert_eq!(flat.net(out3).name().insts, Vec::<arcstr::ArcStr>::new());
+    assert!(flat.aliases(out3).any(|path| *path == inner));
+
+    let device = flat.devices_on(out3)[0];
+    assert_eq!(flat.device(device).path()[1].as_str(), "vdivider_3");
+
+    let path = flat.path(vdd, vss).unwrap();
+    assert_eq!(path.devices.len(), 2);
+    assert_eq!(path.nets.len(), 3);
+    assert!(flat.path_excluding(out3, vdd, &[vss]).is_some());
+    let path = flat.path_excluding(out3, flat.port("out", Some(4)).unwrap(), &[vdd, vss]);
+    assert!(path.is_none());
+}
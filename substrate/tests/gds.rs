@@ -2,12 +2,14 @@ use std::path::PathBuf;
 
 use arcstr::ArcStr;
 use codegen::hard_macro;
-use subgeom::Shape;
+use subgeom::{Point, Rect, Shape};
 use substrate::component::{Component, NoParams, View};
 use substrate::data::SubstrateCtx;
+use substrate::layout::convert::gds::{GdsExportOpts, NET_NAME_PROPATTR};
 use substrate::layout::layers::{GdsLayerSpec, LayerPurpose};
 
 mod common;
+use common::nets::NetTop;
 use common::vdivider::array::VDividerArray;
 use common::{gds_path, out_path, setup_ctx};
 
@@ -192,3 +194,83 @@ fn test_gds_reexport() {
     assert_eq!(b_port_0.name(), "gnd", "expected a GND port in cell B");
     assert!(b_ports.next().is_none(), "expected only 1 port in cell B");
 }
+
+#[test]
+fn test_gds_net_names() {
+    let ctx = setup_ctx();
+    let top = ctx.instantiate_layout::<NetTop>(&NoParams).unwrap();
+    let x = top.cell().shapes_on_net("leaf0/x");
+    assert_eq!(x.len(), 1);
+    assert_eq!(x[0].net.as_deref(), Some("leaf0/x"));
+    assert_eq!(
+        x[0].inner,
+        Shape::Rect(Rect::new(Point::new(500, 1_000), Point::new(1_500, 1_200)))
+    );
+    assert!(top.cell().shapes_on_net("x").is_empty());
+    assert!(top.cell().shapes_on_net("leaf1/x").is_empty());
+
+    let opts = GdsExportOpts { net_labels: true };
+    let lib = ctx.to_gds_lib_with_opts(&opts).unwrap();
+    let strukt = lib.structs.iter().find(|s| s.name == "net_top").unwrap();
+    let props: Vec<_> = strukt
+        .elems
+        .iter()
+        .filter_map(|elem| match elem {
+            gds21::GdsElement::GdsBoundary(x) => Some(&x.properties),
+            gds21::GdsElement::GdsTextElem(x) => Some(&x.properties),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(props.len(), 2, "expected a boundary and a net label");
+    for props in props {
+        assert_eq!(
+            props,
+            &vec![gds21::GdsProperty {
+                attr: NET_NAME_PROPATTR,
+                value: arcstr::literal!("Y"),
+            }]
+        );
+    }
+
+    let ctx_new = setup_ctx();
+    let cell_map = ctx_new.from_gds_lib(&lib).expect("failed to import GDS");
+    let top = &cell_map["net_top"];
+    // Net labels are not imported as ports or annotations.
+    assert_eq!(top.ports().count(), 0);
+    assert_eq!(top.annotations().count(), 0);
+    let y = top.shapes_on_net("Y");
+    assert_eq!(y.len(), 1);
+    assert_eq!(
+        y[0].inner,
+        Shape::Rect(Rect::new(Point::new(0, 0), Point::new(200, 2_000)))
+    );
+    assert_eq!(top.shapes_on_net("leaf0/x").len(), 1);
+}
+
+#[test]
+fn test_gds_write_layout_with_opts() {
+    let labels = |path: PathBuf| {
+        let lib = gds21::GdsLibrary::load(path).expect("failed to load GDS file");
+        let strukt = lib.structs.iter().find(|s| s.name == "net_top").unwrap();
+        strukt
+            .elems
+            .iter()
+            .filter_map(|elem| match elem {
+                gds21::GdsElement::GdsTextElem(x) => Some(x.string.clone()),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+    };
+
+    let ctx = setup_ctx();
+    let default_path = out_path("test_gds_write_layout_with_opts", "default.gds");
+    ctx.write_layout::<NetTop>(&NoParams, &default_path)
+        .expect("failed to write layout");
+    assert!(labels(default_path).is_empty());
+
+    let labeled_path = out_path("test_gds_write_layout_with_opts", "labeled.gds");
+    let opts = GdsExportOpts { net_labels: true };
+    ctx.write_layout_with_opts::<NetTop>(&NoParams, &labeled_path, &opts)
+        .expect("failed to write layout");
+    assert_eq!(labels(labeled_path), vec![arcstr::literal!("Y")]);
+}
@@ -0,0 +1,76 @@
+use arcstr::ArcStr;
+use subgeom::bbox::BoundBox;
+use subgeom::{Point, Rect};
+use substrate::component::{Component, NoParams};
+use substrate::data::SubstrateCtx;
+use substrate::error::ErrorSource;
+use substrate::layout::context::LayoutCtx;
+use substrate::layout::elements::guard_ring::{
+    GuardRing, GuardRingKind, GuardRingParams, GUARD_RING_PORT,
+};
+
+mod common;
+use common::{out_path, setup_ctx};
+
+pub struct GuardRings;
+
+impl Component for GuardRings {
+    type Params = NoParams;
+    fn new(_params: &Self::Params, _ctx: &SubstrateCtx) -> substrate::error::Result<Self> {
+        Ok(Self)
+    }
+    fn name(&self) -> ArcStr {
+        arcstr::literal!("guard_rings")
+    }
+
+    fn layout(&self, ctx: &mut LayoutCtx) -> substrate::error::Result<()> {
+        let enclosed = Rect::new(Point::zero(), Point::new(4_000, 2_000));
+
+        let psub =
+            ctx.instantiate::<GuardRing>(&GuardRingParams::new(GuardRingKind::PSub, enclosed))?;
+        psub.port(GUARD_RING_PORT)?;
+        let bbox = psub.brect();
+        assert!(bbox.p0.x < enclosed.p0.x && bbox.p0.y < enclosed.p0.y);
+        assert!(bbox.p1.x > enclosed.p1.x && bbox.p1.y > enclosed.p1.y);
+        ctx.draw(psub)?;
+
+        let enclosed = Rect::new(Point::new(8_000, 0), Point::new(12_000, 2_000));
+        let mut params = GuardRingParams::new(GuardRingKind::NWell, enclosed);
+        params.deep_nwell = true;
+        let nwell = ctx.instantiate::<GuardRing>(&params)?;
+        ctx.draw(nwell)?;
+
+        Ok(())
+    }
+}
+
+#[test]
+fn test_guard_rings() {
+    let ctx = setup_ctx();
+    ctx.write_layout::<GuardRings>(&NoParams, out_path("test_guard_rings", "layout.gds"))
+        .expect("failed to write layout");
+}
+
+#[test]
+fn test_guard_ring_invalid_width() {
+    let ctx = setup_ctx();
+    let enclosed = Rect::new(Point::zero(), Point::new(4_000, 2_000));
+    let mut params = GuardRingParams::new(GuardRingKind::PSub, enclosed);
+    params.width = Some(10);
+    let err = ctx
+        .instantiate_layout::<GuardRing>(&params)
+        .expect_err("guard ring narrower than the minimum width should be rejected");
+    assert!(matches!(err.source(), ErrorSource::InvalidArgs(_)));
+}
+
+#[test]
+fn test_guard_ring_psub_deep_nwell() {
+    let ctx = setup_ctx();
+    let enclosed = Rect::new(Point::zero(), Point::new(4_000, 2_000));
+    let mut params = GuardRingParams::new(GuardRingKind::PSub, enclosed);
+    params.deep_nwell = true;
+    let err = ctx
+        .instantiate_layout::<GuardRing>(&params)
+        .expect_err("P-substrate guard ring with deep N-well should be rejected");
+    assert!(matches!(err.source(), ErrorSource::InvalidArgs(_)));
+}
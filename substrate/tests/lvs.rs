@@ -0,0 +1,56 @@
+use common::{config_builder, out_path};
+use subgates::Inv;
+use substrate::data::SubstrateCtx;
+use substrate::deps::arcstr::ArcStr;
+use substrate::pdk::mos::db::MosDb;
+use substrate::schematic::netlist::impls::cdl::CdlNetlister;
+use substrate::verification::lvs::{LvsError, LvsInput, LvsOutput, LvsSummary, LvsTool};
+
+mod common;
+
+/// An LVS tool that expects CDL source netlists, and only checks their contents.
+struct CdlLvsTool;
+
+impl LvsTool for CdlLvsTool {
+    fn run_lvs(&self, input: LvsInput) -> substrate::error::Result<LvsOutput> {
+        let path = &input.source_paths[0];
+        let netlist = std::fs::read_to_string(path)?;
+        let expected = [
+            ".GLOBAL vdd",
+            ".SUBCKT inv",
+            "*.PININFO",
+            "M0 d g s b sky130_fd_pr__nfet_01v8",
+        ];
+        let errors = expected
+            .iter()
+            .filter(|line| !netlist.contains(*line))
+            .map(|line| LvsError {
+                name: ArcStr::from(format!("missing `{line}`")),
+                desc: None,
+            })
+            .collect::<Vec<_>>();
+        Ok(LvsOutput {
+            summary: if path.ends_with("netlist.cdl") && errors.is_empty() {
+                LvsSummary::Pass
+            } else {
+                LvsSummary::Fail
+            },
+            errors,
+        })
+    }
+
+    fn cdl_netlister(&self, mos_db: &MosDb) -> Option<CdlNetlister> {
+        Some(CdlNetlister::from_mos_db(mos_db).with_global("vdd"))
+    }
+}
+
+#[test]
+fn test_write_lvs_cdl() {
+    let cfg = config_builder().lvs_tool(CdlLvsTool).build();
+    let ctx = SubstrateCtx::from_config(cfg).unwrap();
+
+    let output = ctx
+        .write_lvs::<Inv>(&Inv::dec_params(), out_path("test_write_lvs_cdl", "lvs"))
+        .expect("failed to run LVS");
+    assert_eq!(output.summary, LvsSummary::Pass, "{:?}", output.errors);
+}
@@ -0,0 +1,156 @@
+use arcstr::ArcStr;
+use magic::{MagCell, MagRect, MagTransform};
+use subgeom::orientation::Named;
+use subgeom::{Point, Rect, Shape};
+use substrate::component::{Component, NoParams};
+use substrate::data::SubstrateCtx;
+use substrate::layout::cell::{CellPort, Element, PortId};
+use substrate::layout::context::LayoutCtx;
+use substrate::layout::convert::lef::PlacementBoundary;
+use substrate::layout::convert::magic::{MagicOpts, MagicOptsBuilder};
+use substrate::layout::layers::selector::Selector;
+use substrate::layout::layers::LayerSpec;
+
+mod common;
+use common::{out_path, setup_ctx};
+
+/// A metal 1 rectangle with an input port.
+pub struct MagicLeaf;
+
+impl Component for MagicLeaf {
+    type Params = NoParams;
+    fn new(_params: &Self::Params, _ctx: &SubstrateCtx) -> substrate::error::Result<Self> {
+        Ok(Self)
+    }
+    fn name(&self) -> ArcStr {
+        arcstr::literal!("magic_leaf")
+    }
+
+    fn layout(&self, ctx: &mut LayoutCtx) -> substrate::error::Result<()> {
+        let m1 = ctx.layers().get(Selector::Metal(1))?;
+        ctx.draw_rect(m1, Rect::new(Point::new(0, 0), Point::new(1_000, 500)));
+        let a = Rect::new(Point::new(0, 0), Point::new(200, 500));
+        ctx.add_port(CellPort::with_shape("a", m1, a))?;
+        ctx.set_metadata(PlacementBoundary(Rect::new(
+            Point::zero(),
+            Point::new(1_000, 600),
+        )));
+        Ok(())
+    }
+}
+
+/// Two leaf cells, one of them flipped, with a named net and a bus port.
+pub struct MagicTop;
+
+impl Component for MagicTop {
+    type Params = NoParams;
+    fn new(_params: &Self::Params, _ctx: &SubstrateCtx) -> substrate::error::Result<Self> {
+        Ok(Self)
+    }
+    fn name(&self) -> ArcStr {
+        arcstr::literal!("magic_top")
+    }
+
+    fn layout(&self, ctx: &mut LayoutCtx) -> substrate::error::Result<()> {
+        let m2 = ctx.layers().get(Selector::Metal(2))?;
+
+        let leaf0 = ctx.instantiate::<MagicLeaf>(&NoParams)?;
+        let mut leaf1 = ctx.instantiate::<MagicLeaf>(&NoParams)?;
+        leaf1.set_orientation(Named::ReflectVert);
+        leaf1.set_loc(Point::new(2_000, 1_500));
+        ctx.draw(leaf0)?;
+        ctx.draw(leaf1)?;
+
+        let mid = Rect::new(Point::new(0, 700), Point::new(3_000, 800));
+        ctx.draw(Element::with_net_name("mid", LayerSpec::drawing(m2), mid))?;
+
+        for i in 0..2 {
+            let y = 100 + 200 * i as i64;
+            let dout = Rect::new(Point::new(3_000, y), Point::new(3_500, y + 50));
+            ctx.add_port(CellPort::with_shape(PortId::new("dout", i), m2, dout))?;
+        }
+        Ok(())
+    }
+}
+
+fn sky130_opts() -> MagicOpts {
+    MagicOptsBuilder::default()
+        .tech("sky130A")
+        .magscale((1, 2))
+        .dbu_per_unit(5)
+        .build()
+        .unwrap()
+}
+
+#[test]
+fn test_magic_round_trip() {
+    let ctx = setup_ctx();
+    let dir = out_path("test_magic_round_trip", "mag");
+    let opts = sky130_opts();
+    ctx.write_magic::<MagicTop>(&NoParams, &dir, &opts)
+        .expect("failed to write Magic cells");
+
+    let top = MagCell::load(dir.join("magic_top.mag")).expect("failed to read Magic cell");
+    assert_eq!(top.tech, "sky130A");
+    assert_eq!(top.magscale, (1, 2));
+    assert_eq!(top.uses.len(), 2);
+    assert_eq!(top.uses[1].cell, "magic_leaf");
+    assert_eq!(
+        top.uses[1].transform,
+        MagTransform::new(1, 0, 400, 0, -1, 300)
+    );
+    assert_eq!(top.uses[1].bbox, MagRect::new(0, 0, 200, 100));
+
+    let mid = top.labels.iter().find(|l| l.text == "mid").unwrap();
+    assert_eq!(mid.layer, "metal2");
+    assert_eq!(mid.port, None);
+    let dout: Vec<_> = top
+        .labels
+        .iter()
+        .filter_map(|l| Some((l.text.as_str(), l.port.as_ref()?.index)))
+        .collect();
+    assert_eq!(dout, vec![("dout[0]", 1), ("dout[1]", 2)]);
+
+    let leaf = MagCell::load(dir.join("magic_leaf.mag")).expect("failed to read Magic cell");
+    assert_eq!(
+        leaf.property("FIXED_BBOX").map(|v| v.as_str()),
+        Some("0 0 200 120")
+    );
+
+    let imported = ctx
+        .from_magic(dir.join("magic_top.mag"), &opts)
+        .expect("failed to import Magic cells");
+    assert_eq!(imported.insts().count(), 2);
+    let elems: Vec<_> = imported.elems().filter(|e| e.net.is_some()).collect();
+    assert_eq!(elems.len(), 1);
+    assert_eq!(elems[0].net.as_deref(), Some("mid"));
+    let dout1 = imported.port(PortId::new("dout", 1)).unwrap();
+    let m2 = ctx.layers().get(Selector::Metal(2)).unwrap();
+    assert_eq!(
+        dout1.shapes(m2).cloned().collect::<Vec<_>>(),
+        vec![Shape::Rect(Rect::new(
+            Point::new(3_000, 300),
+            Point::new(3_500, 350)
+        ))]
+    );
+    let leaf = imported.insts().next().unwrap().cell();
+    assert_eq!(
+        leaf.try_get_metadata::<PlacementBoundary>(),
+        Some(&PlacementBoundary(Rect::new(
+            Point::zero(),
+            Point::new(1_000, 600)
+        )))
+    );
+
+    let top = ctx.instantiate_layout::<MagicTop>(&NoParams).unwrap();
+    let diff = ctx.diff_layouts(top.cell(), &imported).unwrap();
+    assert!(diff.is_empty(), "{diff}");
+
+    // Coordinates must lie on the Magic grid.
+    let coarse = MagicOptsBuilder::default()
+        .tech("sky130A")
+        .dbu_per_unit(300)
+        .build()
+        .unwrap();
+    assert!(ctx.to_magic_cells(top.cell(), &coarse).is_err());
+}
@@ -0,0 +1,213 @@
+use subgeom::{Point, Rect, Shape};
+use substrate::component::{Component, NoParams};
+use substrate::layout::convert::gds::GdsExportOpts;
+use substrate::layout::convert::oasis::NET_NAME_PROPNAME;
+use substrate::layout::LayoutFormat;
+
+mod common;
+use common::nets::NetTop;
+use common::vdivider::array::VDividerArray;
+use common::{out_path, setup_ctx};
+
+#[test]
+fn test_oasis_export() {
+    let oas_path = out_path("test_oasis_export", "layout.oas");
+    let ctx_original = setup_ctx();
+    ctx_original
+        .write_layout::<VDividerArray>(&NoParams, &oas_path)
+        .expect("failed to write layout");
+    oasis::OasisLibrary::load(&oas_path).expect("layout should be written in OASIS format");
+
+    let name = VDividerArray::new(&NoParams, &ctx_original)
+        .expect("failed to create VDividerArray struct")
+        .name();
+
+    let ctx_new = setup_ctx();
+    let cell_map = ctx_new
+        .from_oasis(oas_path)
+        .expect("failed to import OASIS file");
+    let array = cell_map.get(&name).unwrap();
+    // The uniformly spaced dividers are written as a single repeated placement.
+    assert_eq!(array.insts().count(), 0);
+    let arrays = array.arrays().collect::<Vec<_>>();
+    assert_eq!(arrays.len(), 1);
+    assert_eq!((arrays[0].rows(), arrays[0].cols()), (1, 10));
+
+    let original = ctx_original
+        .instantiate_layout::<VDividerArray>(&NoParams)
+        .unwrap();
+    assert_eq!(array.ports().count(), original.cell().ports().count());
+}
+
+#[test]
+fn test_oasis_explicit_format() {
+    let path = out_path("test_oasis_explicit_format", "layout.bin");
+    let ctx = setup_ctx();
+    ctx.write_layout_with_format::<VDividerArray>(&NoParams, &path, LayoutFormat::Oasis)
+        .expect("failed to write layout");
+    oasis::OasisLibrary::load(&path).expect("layout should be written in OASIS format");
+}
+
+#[test]
+fn test_oasis_import_repetitions() {
+    let mut a = oasis::OasisCell::new("A");
+    a.elems
+        .push(oasis::OasisRectangle::new(68, 20, 0, 0, 100, 200).into());
+
+    let mut b = oasis::OasisCell::new("B");
+    b.elems.push(
+        oasis::OasisPlacement {
+            repetition: Some(oasis::OasisRepetition::Matrix {
+                nx: 3,
+                ny: 2,
+                dx: 500,
+                dy: 1000,
+            }),
+            ..oasis::OasisPlacement::new("A", 100, -100)
+        }
+        .into(),
+    );
+    b.elems.push(
+        oasis::OasisPlacement {
+            angle: 90.0,
+            repetition: Some(oasis::OasisRepetition::Explicit(vec![
+                oasis::OasisPoint::zero(),
+                oasis::OasisPoint::new(0, 4000),
+            ])),
+            ..oasis::OasisPlacement::new("A", 0, 0)
+        }
+        .into(),
+    );
+    let mut rect = oasis::OasisRectangle::new(68, 20, 0, 0, 50, 50);
+    rect.repetition = Some(oasis::OasisRepetition::Matrix {
+        nx: 4,
+        ny: 1,
+        dx: 100,
+        dy: 0,
+    });
+    b.elems.push(rect.into());
+
+    let lib = oasis::OasisLibrary {
+        unit: 1000.0,
+        cells: vec![b, a],
+    };
+    let path = out_path("test_oasis_import_repetitions", "layout.oas");
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    lib.save(&path).expect("failed to write OASIS file");
+
+    let ctx = setup_ctx();
+    let cell_map = ctx.from_oasis(&path).expect("failed to import OASIS file");
+    let a = cell_map.get("A").unwrap();
+    let b = cell_map.get("B").unwrap();
+
+    let arrays = b.arrays().collect::<Vec<_>>();
+    assert_eq!(
+        arrays.len(),
+        1,
+        "expected the grid to be imported as an array"
+    );
+    let grid = arrays[0];
+    assert_eq!(grid.cell().id(), a.id());
+    assert_eq!((grid.rows(), grid.cols()), (2, 3));
+    assert_eq!(grid.loc(), Point::new(100, -100));
+    assert_eq!(grid.inst_loc(1, 2), Point::new(1_100, 900));
+
+    let insts = b.insts().collect::<Vec<_>>();
+    assert_eq!(insts.len(), 2, "expected 2 instances in cell B");
+    assert!(insts.iter().all(|inst| inst.cell().id() == a.id()));
+    let explicit = insts.iter().find(|inst| inst.name() == "A[1]").unwrap();
+    assert_eq!(explicit.loc(), Point::new(0, 4_000));
+    assert_eq!(explicit.orientation().angle(), 90.0);
+
+    assert_eq!(
+        b.elems().count(),
+        4,
+        "expected repeated shapes to be expanded"
+    );
+}
+
+#[test]
+fn test_oasis_path_extensions() {
+    let path = |start_extension, end_extension| oasis::OasisPath {
+        layer: 68,
+        datatype: 20,
+        half_width: 100,
+        start_extension,
+        end_extension,
+        points: vec![oasis::OasisPoint::zero(), oasis::OasisPoint::new(0, 1_000)],
+        repetition: None,
+        properties: Vec::new(),
+    };
+    let mut cell = oasis::OasisCell::new("paths");
+    cell.elems.push(
+        path(
+            oasis::OasisPathExtension::HalfWidth,
+            oasis::OasisPathExtension::HalfWidth,
+        )
+        .into(),
+    );
+    cell.elems.push(
+        path(
+            oasis::OasisPathExtension::Explicit(50),
+            oasis::OasisPathExtension::Explicit(-20),
+        )
+        .into(),
+    );
+    let lib = oasis::OasisLibrary {
+        unit: 1000.0,
+        cells: vec![cell],
+    };
+    let path = out_path("test_oasis_path_extensions", "layout.oas");
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    lib.save(&path).expect("failed to write OASIS file");
+
+    let ctx = setup_ctx();
+    let cell_map = ctx.from_oasis(&path).expect("failed to import OASIS file");
+    let paths = cell_map["paths"]
+        .elems()
+        .filter(|elem| matches!(elem.inner, Shape::Path(_)))
+        .count();
+    assert_eq!(paths, 2);
+}
+
+#[test]
+fn test_oasis_net_names() {
+    let path = out_path("test_oasis_net_names", "layout.oas");
+    let ctx = setup_ctx();
+    let opts = GdsExportOpts { net_labels: true };
+    ctx.write_layout_with_opts::<NetTop>(&NoParams, &path, &opts)
+        .expect("failed to write layout");
+
+    let lib = oasis::OasisLibrary::load(&path).expect("failed to load OASIS file");
+    let top = lib.cell("net_top").unwrap();
+    let net = oasis::OasisProperty::new(
+        NET_NAME_PROPNAME,
+        vec![oasis::OasisPropValue::String(arcstr::literal!("Y"))],
+    );
+    let props = top
+        .elems
+        .iter()
+        .map(|elem| elem.properties())
+        .filter(|props| !props.is_empty())
+        .collect::<Vec<_>>();
+    assert_eq!(props.len(), 2, "expected a rectangle and a net label");
+    for props in props {
+        assert_eq!(props, &[net.clone()]);
+    }
+
+    let ctx_new = setup_ctx();
+    let cell_map = ctx_new
+        .from_oasis(&path)
+        .expect("failed to import OASIS file");
+    let top = &cell_map["net_top"];
+    // Net labels are not imported as ports or annotations.
+    assert_eq!(top.ports().count(), 0);
+    assert_eq!(top.annotations().count(), 0);
+    let y = top.shapes_on_net("Y");
+    assert_eq!(y.len(), 1);
+    assert_eq!(
+        y[0].inner,
+        Shape::Rect(Rect::new(Point::new(0, 0), Point::new(200, 2_000)))
+    );
+    assert_eq!(top.shapes_on_net("leaf0/x").len(), 1);
+}
@@ -0,0 +1,215 @@
+use std::collections::HashMap;
+
+use arcstr::ArcStr;
+use common::common_source::CommonSourceAmp;
+use common::{out_path, setup_ctx};
+use substrate::component::{Component, NoParams};
+use substrate::data::SubstrateCtx;
+use substrate::error::ErrorSource;
+use substrate::schematic::circuit::Direction;
+use substrate::schematic::context::SchematicCtx;
+use substrate::schematic::elements::vdc::Vdc;
+use substrate::units::{SiPrefix, SiValue};
+use substrate::verification::simulation::context::{PostSimCtx, PreSimCtx};
+use substrate::verification::simulation::oppoint::{
+    MosInstance, MosOpPoint, MosOpSelect, MosRegion,
+};
+use substrate::verification::simulation::testbench::Testbench;
+use substrate::verification::simulation::OpAnalysis;
+
+mod common;
+
+/// A common source amplifier biased in saturation.
+struct CommonSourceTb {
+    select: MosOpSelect,
+}
+
+impl Component for CommonSourceTb {
+    type Params = MosOpSelect;
+
+    fn new(params: &Self::Params, _ctx: &SubstrateCtx) -> substrate::error::Result<Self> {
+        Ok(Self {
+            select: params.clone(),
+        })
+    }
+
+    fn name(&self) -> ArcStr {
+        arcstr::literal!("common_source_tb")
+    }
+
+    fn schematic(&self, ctx: &mut SchematicCtx) -> substrate::error::Result<()> {
+        let vss = ctx.port("vss", Direction::InOut);
+        let vdd = ctx.signal("vdd");
+        let vin = ctx.signal("vin");
+        let vout = ctx.signal("vout");
+
+        ctx.instantiate::<CommonSourceAmp>(&NoParams)?
+            .with_connections([("vin", &vin), ("vout", &vout), ("vdd", &vdd), ("vss", &vss)])
+            .named("dut")
+            .add_to(ctx);
+        ctx.instantiate::<Vdc>(&SiValue::new(1_800, SiPrefix::Milli))?
+            .with_connections([("p", &vdd), ("n", &vss)])
+            .named("vdd")
+            .add_to(ctx);
+        ctx.instantiate::<Vdc>(&SiValue::new(700, SiPrefix::Milli))?
+            .with_connections([("p", &vin), ("n", &vss)])
+            .named("vin")
+            .add_to(ctx);
+        Ok(())
+    }
+}
+
+impl Testbench for CommonSourceTb {
+    type Output = HashMap<MosInstance, MosOpPoint>;
+
+    fn setup(&mut self, ctx: &mut PreSimCtx) -> substrate::error::Result<()> {
+        ctx.add_analysis(OpAnalysis::new())
+            .save_mos_op(self.select.clone());
+        Ok(())
+    }
+
+    fn measure(&mut self, ctx: &PostSimCtx) -> substrate::error::Result<Self::Output> {
+        Ok(ctx.output().data[0].op().mos.clone())
+    }
+}
+
+/// Two NMOS transistors of different widths, defined in a single block of raw SPICE.
+struct NmosPair;
+
+impl Component for NmosPair {
+    type Params = NoParams;
+
+    fn new(_params: &Self::Params, _ctx: &SubstrateCtx) -> substrate::error::Result<Self> {
+        Ok(Self)
+    }
+
+    fn name(&self) -> ArcStr {
+        arcstr::literal!("nmos_pair")
+    }
+
+    fn schematic(&self, ctx: &mut SchematicCtx) -> substrate::error::Result<()> {
+        ctx.port("d", Direction::InOut);
+        ctx.port("g", Direction::Input);
+        ctx.port("s", Direction::InOut);
+        ctx.set_spice(
+            "X0 d g s s sky130_fd_pr__nfet_01v8 w=1.000 l=0.150\n\
+             X1 d g s s sky130_fd_pr__nfet_01v8 w=2.000 l=0.150",
+        );
+        Ok(())
+    }
+}
+
+struct NmosPairTb {
+    select: MosOpSelect,
+}
+
+impl Component for NmosPairTb {
+    type Params = MosOpSelect;
+
+    fn new(params: &Self::Params, _ctx: &SubstrateCtx) -> substrate::error::Result<Self> {
+        Ok(Self {
+            select: params.clone(),
+        })
+    }
+
+    fn name(&self) -> ArcStr {
+        arcstr::literal!("nmos_pair_tb")
+    }
+
+    fn schematic(&self, ctx: &mut SchematicCtx) -> substrate::error::Result<()> {
+        let vss = ctx.port("vss", Direction::InOut);
+        let vdd = ctx.signal("vdd");
+        let vg = ctx.signal("vg");
+
+        ctx.instantiate::<NmosPair>(&NoParams)?
+            .with_connections([("d", &vdd), ("g", &vg), ("s", &vss)])
+            .named("pair")
+            .add_to(ctx);
+        ctx.instantiate::<Vdc>(&SiValue::new(1_800, SiPrefix::Milli))?
+            .with_connections([("p", &vdd), ("n", &vss)])
+            .named("vdd")
+            .add_to(ctx);
+        ctx.instantiate::<Vdc>(&SiValue::new(700, SiPrefix::Milli))?
+            .with_connections([("p", &vg), ("n", &vss)])
+            .named("vg")
+            .add_to(ctx);
+        Ok(())
+    }
+}
+
+impl Testbench for NmosPairTb {
+    type Output = HashMap<MosInstance, MosOpPoint>;
+
+    fn setup(&mut self, ctx: &mut PreSimCtx) -> substrate::error::Result<()> {
+        ctx.add_analysis(OpAnalysis::new())
+            .save_mos_op(self.select.clone());
+        Ok(())
+    }
+
+    fn measure(&mut self, ctx: &PostSimCtx) -> substrate::error::Result<Self::Output> {
+        Ok(ctx.output().data[0].op().mos.clone())
+    }
+}
+
+#[test]
+#[ignore = "slow"]
+fn test_mos_op_all() {
+    let ctx = setup_ctx();
+    let ops = ctx
+        .write_simulation::<CommonSourceTb>(&MosOpSelect::All, out_path("test_mos_op_all", "sim"))
+        .expect("failed to run simulation");
+
+    assert_eq!(ops.len(), 1);
+    let op = &ops[&MosInstance::new(["dut", "M1"], "X0")];
+    assert!(op.id > 0.);
+    assert!(op.gm > 0.);
+    assert!(op.gds > 0.);
+    assert!(op.intrinsic_gain() > 1.);
+    assert!((op.vgs - 0.7).abs() < 1e-6);
+    assert_eq!(op.region, MosRegion::Saturation);
+}
+
+#[test]
+fn test_mos_op_missing_instance() {
+    let ctx = setup_ctx();
+    let select = MosOpSelect::Instances(vec![MosInstance::new(["dut", "M1"], "X1")]);
+    let err = ctx
+        .write_simulation::<CommonSourceTb>(
+            &select,
+            out_path("test_mos_op_missing_instance", "sim"),
+        )
+        .expect_err("selecting a missing MOSFET should fail");
+    assert!(matches!(err.source(), ErrorSource::MosNotFound(path) if path == "dut/M1/X1"));
+}
+
+#[test]
+#[ignore = "slow"]
+fn test_mos_op_same_leaf() {
+    let ctx = setup_ctx();
+    let select = MosOpSelect::Instances(vec![
+        MosInstance::new(["pair"], "X0"),
+        MosInstance::new(["pair"], "X1"),
+    ]);
+    let ops = ctx
+        .write_simulation::<NmosPairTb>(&select, out_path("test_mos_op_same_leaf", "sim"))
+        .expect("failed to run simulation");
+
+    assert_eq!(ops.len(), 2);
+    let narrow = &ops[&MosInstance::new(["pair"], "X0")];
+    let wide = &ops[&MosInstance::new(["pair"], "X1")];
+    assert!(narrow.id > 0.);
+    assert!(wide.id > 1.5 * narrow.id);
+}
+
+#[test]
+fn test_mos_op_missing_element() {
+    let ctx = setup_ctx();
+    let select = MosOpSelect::Instances(vec![
+        MosInstance::new(["pair"], "X0"),
+        MosInstance::new(["pair"], "X2"),
+    ]);
+    let err = ctx
+        .write_simulation::<NmosPairTb>(&select, out_path("test_mos_op_missing_element", "sim"))
+        .expect_err("selecting a missing MOSFET should fail");
+    assert!(matches!(err.source(), ErrorSource::MosNotFound(path) if path == "pair/X2"));
+}
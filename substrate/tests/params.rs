@@ -0,0 +1,91 @@
+use arcstr::ArcStr;
+use substrate::component::{Component, NoParams};
+use substrate::data::SubstrateCtx;
+use substrate::schematic::circuit::{Direction, Value};
+use substrate::schematic::context::SchematicCtx;
+use substrate::schematic::elements::resistor::Resistor;
+use substrate::units::{SiPrefix, SiValue};
+
+mod common;
+use common::setup_ctx;
+
+struct ParamResistor;
+
+impl Component for ParamResistor {
+    type Params = NoParams;
+
+    fn new(_params: &Self::Params, _ctx: &SubstrateCtx) -> substrate::error::Result<Self> {
+        Ok(Self)
+    }
+
+    fn name(&self) -> ArcStr {
+        arcstr::literal!("param_resistor")
+    }
+
+    fn schematic(&self, ctx: &mut SchematicCtx) -> substrate::error::Result<()> {
+        let p = ctx.port("p", Direction::InOut);
+        let n = ctx.port("n", Direction::InOut);
+        ctx.param("w", 1i64);
+
+        ctx.instantiate::<Resistor>(&SiValue::new(1, SiPrefix::Kilo))?
+            .with_connections([("p", &p), ("n", &n)])
+            .named("R1")
+            .add_to(ctx);
+        Ok(())
+    }
+}
+
+struct ParamTop;
+
+impl Component for ParamTop {
+    type Params = NoParams;
+
+    fn new(_params: &Self::Params, _ctx: &SubstrateCtx) -> substrate::error::Result<Self> {
+        Ok(Self)
+    }
+
+    fn name(&self) -> ArcStr {
+        arcstr::literal!("param_top")
+    }
+
+    fn schematic(&self, ctx: &mut SchematicCtx) -> substrate::error::Result<()> {
+        let a = ctx.port("a", Direction::InOut);
+        let b = ctx.port("b", Direction::InOut);
+        let c = ctx.port("c", Direction::InOut);
+        let w = ctx.param("w", 0.5);
+
+        ctx.instantiate::<ParamResistor>(&NoParams)?
+            .with_connections([("p", &a), ("n", &b)])
+            .with_param("w", 2i64)
+            .named("X1")
+            .add_to(ctx);
+
+        ctx.instantiate::<ParamResistor>(&NoParams)?
+            .with_connections([("p", &b), ("n", &c)])
+            .with_param("w", w)
+            .named("X2")
+            .add_to(ctx);
+
+        ctx.instantiate::<ParamResistor>(&NoParams)?
+            .with_connections([("p", &c), ("n", &a)])
+            .with_param("w", Value::expr("2*w"))
+            .named("X3")
+            .add_to(ctx);
+        Ok(())
+    }
+}
+
+#[test]
+fn test_param_netlist() {
+    let ctx = setup_ctx();
+    let mut out = Vec::new();
+    ctx.write_schematic::<ParamTop>(&NoParams, &mut out)
+        .expect("failed to write schematic");
+    let out = String::from_utf8(out).unwrap();
+
+    assert!(out.contains("+ params:\n+ w=1\n"));
+    assert!(out.contains("+ params:\n+ w=5e-1\n"));
+    assert!(out.contains("+ param_resistor\n+ w=2\n"));
+    assert!(out.contains("+ param_resistor\n+ w={w}\n"));
+    assert!(out.contains("+ param_resistor\n+ w={2*w}\n"));
+}
@@ -0,0 +1,206 @@
+use common::{out_path, setup_ctx};
+use subgeom::{Dir, Point, Rect};
+use substrate::component::{Component, NoParams};
+use substrate::error::ErrorSource;
+use substrate::layout::layers::selector::Selector;
+use substrate::layout::straps::grid::{GridTarget, PowerGrid};
+use substrate::layout::straps::ir::IrDropAnalysis;
+use substrate::layout::straps::{LayerStraps, PowerStrapError, SingleSupplyNet};
+
+mod common;
+
+pub struct PowerGridWithRails;
+
+impl Component for PowerGridWithRails {
+    type Params = NoParams;
+    fn new(
+        _params: &Self::Params,
+        _ctx: &substrate::data::SubstrateCtx,
+    ) -> substrate::error::Result<Self> {
+        Ok(Self)
+    }
+    fn name(&self) -> arcstr::ArcStr {
+        arcstr::literal!("power_grid_with_rails")
+    }
+    fn layout(
+        &self,
+        ctx: &mut substrate::layout::context::LayoutCtx,
+    ) -> substrate::error::Result<()> {
+        let layers = ctx.layers();
+        let m1 = layers.get(Selector::Metal(1))?;
+        let m2 = layers.get(Selector::Metal(2))?;
+        let m3 = layers.get(Selector::Metal(3))?;
+
+        let region = Rect::new(Point::new(0, 0), Point::new(20_000, 20_000));
+
+        let mut grid = PowerGrid::new(region);
+        grid.add_layer(
+            LayerStraps::builder()
+                .layer(m2, 2)
+                .dir(Dir::Vert)
+                .nets([SingleSupplyNet::Vdd, SingleSupplyNet::Vss])
+                .width(800)
+                .spacing(400)
+                .group_pitch(5_000)
+                .offset(500)
+                .build()?,
+        );
+        grid.add_layer(
+            LayerStraps::builder()
+                .layer(m3, 3)
+                .dir(Dir::Horiz)
+                .nets([SingleSupplyNet::Vdd, SingleSupplyNet::Vss])
+                .width(800)
+                .spacing(400)
+                .group_pitch(5_000)
+                .offset(500)
+                .build()?,
+        );
+        grid.add_blockage(
+            m3,
+            Rect::new(Point::new(8_000, 5_000), Point::new(12_000, 7_000)),
+        );
+
+        let mut rails = Vec::new();
+        for i in 0..10 {
+            let net = if i % 2 == 0 {
+                SingleSupplyNet::Vss
+            } else {
+                SingleSupplyNet::Vdd
+            };
+            let rail = Rect::new(
+                Point::new(0, 2_000 * i - 240),
+                Point::new(20_000, 2_000 * i + 240),
+            );
+            ctx.draw_rect(m1, rail);
+            grid.add_target(GridTarget::new(m1, net, rail));
+            rails.push((net, rail));
+        }
+
+        // A target on a strap layer connects only if it overlaps a strap of the same net.
+        let hit = GridTarget::new(
+            m2,
+            SingleSupplyNet::Vdd,
+            Rect::new(Point::new(500, 0), Point::new(1_300, 300)),
+        );
+        let miss = GridTarget::new(
+            m2,
+            SingleSupplyNet::Vdd,
+            Rect::new(Point::new(3_000, 0), Point::new(3_500, 300)),
+        );
+        grid.add_target(hit).add_target(miss.clone());
+
+        let placed = grid.draw(ctx)?;
+        assert_eq!(placed.unconnected_targets(), &[miss]);
+        assert!(placed.num_vias() > 0);
+        assert!(placed.port(&SingleSupplyNet::Vdd).is_some());
+        assert!(placed.port(&SingleSupplyNet::Vss).is_some());
+        // The blockage splits two of the eight m3 straps into two segments each.
+        assert_eq!(placed.straps_on(m3).count(), 10);
+
+        let mut ir = IrDropAnalysis::from_grid(&placed, &SingleSupplyNet::Vdd);
+        let supply = placed
+            .straps_on(m3)
+            .find(|s| s.net == SingleSupplyNet::Vdd)
+            .unwrap()
+            .rect;
+        ir.add_supply(m3, Point::new(supply.p0.x, supply.center().y));
+        for (_, rail) in rails.iter().filter(|(n, _)| *n == SingleSupplyNet::Vdd) {
+            ir.add_metal(m1, *rail);
+            ir.add_tap(m1, rail.center(), 1e-3);
+        }
+        let report = ir.solve(ctx.inner())?;
+        assert!(report.worst_drop() > 0.0);
+        assert!(report.straps().iter().all(|s| s.heat <= 1.0));
+        ctx.draw(report.annotations())?;
+
+        ctx.add_ports(placed.into_ports())?;
+
+        Ok(())
+    }
+}
+
+#[test]
+fn test_power_grid_with_rails() {
+    let ctx = setup_ctx();
+    ctx.write_layout::<PowerGridWithRails>(
+        &NoParams,
+        out_path("test_power_grid_with_rails", "layout.gds"),
+    )
+    .expect("failed to write layout");
+}
+
+#[test]
+fn test_layer_straps_missing_field() {
+    let ctx = setup_ctx();
+    let m2 = ctx.layers().get(Selector::Metal(2)).unwrap();
+    let err = LayerStraps::builder()
+        .layer(m2, 2)
+        .nets([SingleSupplyNet::Vdd, SingleSupplyNet::Vss])
+        .width(800)
+        .spacing(400)
+        .group_pitch(5_000)
+        .build()
+        .expect_err("building straps without a direction should fail");
+    assert!(matches!(
+        err.source(),
+        ErrorSource::PowerStrapError(PowerStrapError::MissingField("dir"))
+    ));
+}
+
+#[test]
+fn test_ir_drop_hand_computed() {
+    let ctx = setup_ctx();
+    let layers = ctx.layers();
+    let m1 = layers.get(Selector::Metal(1)).unwrap();
+    let m2 = layers.get(Selector::Metal(2)).unwrap();
+    let via = layers.get(Selector::Via(1)).unwrap();
+
+    // Two 10 x 1 um met1 rails joined by a 5 x 1 um met2 strap at their right ends,
+    // with the supply at the left end of the bottom rail and the load at the
+    // left end of the top rail.
+    let bot = Rect::new(Point::new(0, 0), Point::new(10_000, 1_000));
+    let top = Rect::new(Point::new(0, 4_000), Point::new(10_000, 5_000));
+    let strap = Rect::new(Point::new(9_000, 0), Point::new(10_000, 5_000));
+    let mut ir = IrDropAnalysis::new();
+    ir.add_metal(m1, bot)
+        .add_metal(m1, top)
+        .add_metal(m2, strap)
+        .add_via(
+            m1,
+            m2,
+            Rect::new(Point::new(9_000, 0), Point::new(10_000, 1_000)),
+            via,
+            4,
+        )
+        .add_via(
+            m1,
+            m2,
+            Rect::new(Point::new(9_000, 4_000), Point::new(10_000, 5_000)),
+            via,
+            4,
+        )
+        .add_supply(m1, Point::new(0, 500))
+        .add_tap(m1, Point::new(0, 4_500), 1e-3);
+    let report = ir.solve(&ctx).expect("failed to solve IR drop");
+
+    // Each rail carries the load over 9.5 squares of met1 (0.125 ohm/sq), each via array has
+    // 4 cuts of 4.5 ohms in parallel, and the strap spans 4 squares of met2 (0.125 ohm/sq).
+    let rail = 9.5 * 0.125;
+    let via_array = 4.5 / 4.0;
+    let expected = 1e-3 * (2.0 * rail + 2.0 * via_array + 4.0 * 0.125);
+    assert!((report.worst_drop() - expected).abs() < 1e-6 * expected);
+    assert_eq!(report.worst_location(), Some((m1, Point::new(0, 4_500))));
+
+    let drop_on = |rect: Rect| {
+        report
+            .straps()
+            .iter()
+            .find(|s| s.rect == rect)
+            .unwrap()
+            .max_drop
+    };
+    assert!((drop_on(bot) - 1e-3 * rail).abs() < 1e-6 * expected);
+    let strap_top = 1e-3 * (rail + 2.0 * via_array + 4.0 * 0.125);
+    assert!((drop_on(strap) - strap_top).abs() < 1e-6 * expected);
+}
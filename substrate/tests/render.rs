@@ -0,0 +1,135 @@
+use arcstr::ArcStr;
+use subgeom::{Point, Rect};
+use substrate::component::{Component, NoParams};
+use substrate::data::SubstrateCtx;
+use substrate::layout::cell::CellPort;
+use substrate::layout::context::LayoutCtx;
+use substrate::layout::layers::selector::Selector;
+use substrate::layout::render::{
+    Color, LayerStyles, RenderOpts, RenderOptsBuilder, Renderer, Stipple,
+};
+
+mod common;
+use common::vdivider::array::VDividerArray;
+use common::{out_path, setup_ctx};
+
+const LYP: &str = r##"<?xml version="1.0" encoding="utf-8"?>
+<layer-properties>
+ <properties>
+  <frame-color>#800000</frame-color>
+  <fill-color>#ff0000</fill-color>
+  <dither-pattern>I5</dither-pattern>
+  <visible>true</visible>
+  <name>met1.drawing - 68/20</name>
+  <source>68/20@1</source>
+ </properties>
+ <properties>
+  <frame-color>#000080</frame-color>
+  <fill-color>#0000ff</fill-color>
+  <dither-pattern>I1</dither-pattern>
+  <name>met1.pin - 68/16</name>
+  <source>68/16@1</source>
+ </properties>
+ <properties>
+  <fill-color>#00ff00</fill-color>
+  <source>*/*@*</source>
+ </properties>
+</layer-properties>
+"##;
+
+/// A divider array with an output port on metal 2.
+pub struct RenderSnapshot;
+
+impl Component for RenderSnapshot {
+    type Params = NoParams;
+    fn new(_params: &Self::Params, _ctx: &SubstrateCtx) -> substrate::error::Result<Self> {
+        Ok(Self)
+    }
+    fn name(&self) -> ArcStr {
+        arcstr::literal!("render_snapshot")
+    }
+
+    fn layout(&self, ctx: &mut LayoutCtx) -> substrate::error::Result<()> {
+        let array = ctx.instantiate::<VDividerArray>(&NoParams)?;
+        ctx.draw(array)?;
+
+        let m2 = ctx.layers().get(Selector::Metal(2))?;
+        let rect = Rect::new(Point::new(0, 600), Point::new(2_500, 700));
+        ctx.draw_rect(m2, rect);
+        ctx.add_port(CellPort::with_shape("vout", m2, rect))?;
+        Ok(())
+    }
+}
+
+fn render(ctx: &SubstrateCtx, styles: &LayerStyles, opts: &mut RenderOptsBuilder) -> String {
+    let inst = ctx.instantiate_layout::<RenderSnapshot>(&NoParams).unwrap();
+    Renderer::new(styles, opts.build().unwrap())
+        .svg(inst.cell())
+        .expect("failed to render layout")
+}
+
+#[test]
+fn test_render_svg() {
+    let ctx = setup_ctx();
+    let styles = LayerStyles::new();
+
+    let svg = render(&ctx, &styles, &mut RenderOpts::builder());
+    assert!(svg.starts_with("<svg"));
+    assert_eq!(svg.matches("<path").count(), 11);
+    assert_eq!(svg.matches("<text").count(), 0);
+
+    // Hide the contents of the dividers.
+    let svg = render(&ctx, &styles, RenderOpts::builder().max_depth(1));
+    assert_eq!(svg.matches("<path").count(), 1);
+    assert_eq!(svg.matches(">vdivider</text>").count(), 10);
+
+    let svg = render(
+        &ctx,
+        &styles,
+        RenderOpts::builder().ports(true).bboxes(true),
+    );
+    assert_eq!(svg.matches(">vout</text>").count(), 1);
+    assert!(svg.contains("stroke-dasharray"));
+    // One outline for the top cell, the array, and each divider.
+    assert_eq!(svg.matches(r#"<rect x="#).count(), 12);
+
+    std::fs::create_dir_all(out_path("test_render_svg", "")).unwrap();
+    std::fs::write(out_path("test_render_svg", "layout.svg"), svg).unwrap();
+}
+
+#[test]
+fn test_render_lyp_styles() {
+    let ctx = setup_ctx();
+    let layers = ctx.layers();
+    let m1 = layers.get(Selector::Metal(1)).unwrap();
+    let m2 = layers.get(Selector::Metal(2)).unwrap();
+
+    let styles = LayerStyles::from_lyp_str(LYP, &layers).expect("failed to parse layer properties");
+    let style = styles.get(m1).expect("metal 1 should have a style");
+    assert_eq!(style.fill, Color::new(0xff, 0, 0));
+    assert_eq!(style.frame, Color::new(0x80, 0, 0));
+    assert_eq!(style.stipple, Stipple::LeftHatch);
+    assert_eq!(style.name.as_deref(), Some("met1.drawing - 68/20"));
+    assert!(styles.get(m2).is_none());
+
+    let svg = render(&ctx, &styles, &mut RenderOpts::builder());
+    assert!(svg.contains(r#"<pattern id="p0""#));
+    assert!(svg.contains(r#"fill="url(#p0)" stroke="#800000""#));
+
+    assert!(LayerStyles::from_lyp_str("<layer-properties>", &layers).is_err());
+}
+
+#[test]
+fn test_render_png() {
+    let ctx = setup_ctx();
+    let path = out_path("test_render_png", "layout.png");
+    let opts = RenderOpts::builder()
+        .width(400)
+        .ports(true)
+        .build()
+        .unwrap();
+    ctx.write_layout_image::<RenderSnapshot>(&NoParams, &path, &LayerStyles::new(), opts)
+        .expect("failed to render layout");
+    let data = std::fs::read(&path).unwrap();
+    assert_eq!(&data[..8], b"\x89PNG\r\n\x1a\n");
+}
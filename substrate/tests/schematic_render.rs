@@ -0,0 +1,64 @@
+use common::vdivider::array::{VDividerArray, VDividerArrayWrapper};
+use common::{out_path, setup_ctx};
+use substrate::component::NoParams;
+use substrate::schematic::render::{SchematicRenderOpts, SchematicRenderer};
+
+mod common;
+
+#[test]
+fn test_render_schematic_dot() {
+    let ctx = setup_ctx();
+    let flat = ctx
+        .flatten_schematic_to_depth::<VDividerArray>(&NoParams, Some(0))
+        .expect("failed to flatten schematic");
+    let dot = SchematicRenderer::new(SchematicRenderOpts::default()).dot(&flat);
+
+    assert!(dot.starts_with("digraph \"vdivider_array\" {"));
+    // Each divider is drawn as a black box with its output on the right.
+    assert_eq!(dot.matches("shape=record").count(), 10);
+    assert!(dot.contains(r"{<t1> vdd|<t2> vss}|vdivider_3\nvdivider|{<t0> out}"));
+    assert!(dot.contains("d3:t0 -> n"));
+    // Ports `vss`, `vdd`, and 10 bits of `out`.
+    assert_eq!(dot.matches("shape=rarrow").count(), 10);
+    assert_eq!(dot.matches("shape=hexagon").count(), 2);
+    assert!(dot.contains(r#"[label="out[3]", shape=rarrow"#));
+}
+
+#[test]
+fn test_render_schematic_svg() {
+    let ctx = setup_ctx();
+    let flat = ctx
+        .flatten_schematic::<VDividerArrayWrapper>(&NoParams)
+        .expect("failed to flatten schematic");
+
+    let svg = SchematicRenderer::new(SchematicRenderOpts::default()).svg(&flat);
+    assert!(svg.starts_with("<svg"));
+    assert_eq!(svg.matches(">resistor 1K</text>").count(), 10);
+    assert_eq!(svg.matches(">resistor 2K</text>").count(), 10);
+    assert_eq!(svg.matches(">0/vdivider_3/R1</text>").count(), 1);
+    assert_eq!(svg.matches(">out[3]</text>").count(), 3);
+
+    let opts = SchematicRenderOpts::builder()
+        .params(false)
+        .build()
+        .unwrap();
+    let svg = SchematicRenderer::new(opts).svg(&flat);
+    assert_eq!(svg.matches(">resistor</text>").count(), 20);
+}
+
+#[test]
+fn test_write_schematic_image() {
+    let ctx = setup_ctx();
+    let opts = SchematicRenderOpts::builder().max_depth(1).build().unwrap();
+    let path = out_path("test_write_schematic_image", "vdivider_array_wrapper.svg");
+    ctx.write_schematic_image::<VDividerArrayWrapper>(&NoParams, &path, opts)
+        .expect("failed to render schematic");
+    let svg = std::fs::read_to_string(&path).unwrap();
+    assert_eq!(svg.matches(">vdivider</text>").count(), 10);
+
+    let path = out_path("test_write_schematic_image", "vdivider_array_wrapper.dot");
+    ctx.write_schematic_image::<VDividerArrayWrapper>(&NoParams, &path, Default::default())
+        .expect("failed to render schematic");
+    let dot = std::fs::read_to_string(&path).unwrap();
+    assert_eq!(dot.matches("shape=record").count(), 20);
+}
@@ -0,0 +1,73 @@
+use std::path::PathBuf;
+
+use substrate::schematic::import::{SpiceSubckt, SpiceSubcktParams};
+use substrate::units::SiPrefix;
+
+mod common;
+use common::{out_path, setup_ctx, DATA_DIR};
+
+fn spice_path() -> PathBuf {
+    PathBuf::from(DATA_DIR).join("schematics/spice_import.spice")
+}
+
+#[test]
+fn test_spice_import() {
+    let ctx = setup_ctx();
+    let params = SpiceSubcktParams::new(spice_path(), "tb").with_length_unit(SiPrefix::Micro);
+
+    let mut out = Vec::new();
+    ctx.write_schematic::<SpiceSubckt>(&params, &mut out)
+        .expect("failed to write schematic");
+    let out = String::from_utf8(out).unwrap();
+
+    assert!(out.contains(".subckt inv din dout vdd vss"));
+    assert!(out.contains(".subckt buf_load din dout vdd vss"));
+    assert!(out.contains("+ params:\n+ cl=1e-14\n"));
+    assert!(out.contains("+ buf_load\n+ cl=2e-14\n"));
+    assert!(out.contains("+ esd_diode\n+ area=2\n"));
+    assert!(out.contains("R1 dout load 100"));
+    assert!(out.contains("C1 load vss 10f"));
+    assert!(out.contains("V1 vdd vss dc 1800m"));
+    assert!(out.contains("I1 vdd mid dc 10u"));
+
+    ctx.write_schematic_to_file::<SpiceSubckt>(&params, out_path("test_spice_import", "tb.spice"))
+        .expect("failed to write schematic");
+}
+
+#[test]
+fn test_spice_import_expression_value() {
+    let ctx = setup_ctx();
+    let params = SpiceSubcktParams::new(spice_path(), "expr_cap");
+
+    let err = ctx
+        .write_schematic::<SpiceSubckt>(&params, &mut Vec::new())
+        .expect_err("capacitor with an expression value should not be imported");
+    let err = format!("{err:?}");
+    assert!(err.contains("C1"));
+    assert!(err.contains("parameter expression `{c}`"));
+}
+
+#[test]
+fn test_spice_import_unsupported_line() {
+    let ctx = setup_ctx();
+    let params = SpiceSubcktParams::new(spice_path(), "amp");
+
+    let err = ctx
+        .write_schematic::<SpiceSubckt>(&params, &mut Vec::new())
+        .expect_err("voltage-controlled voltage source should not be imported");
+    assert!(format!("{err:?}").contains("E1 out 0 in 0 10"));
+}
+
+#[test]
+fn test_spice_import_without_trailing_newline() {
+    let ctx = setup_ctx();
+    let path = PathBuf::from(DATA_DIR).join("schematics/spice_import_no_newline.spice");
+    let params = SpiceSubcktParams::new(path, "res_div");
+
+    let mut out = Vec::new();
+    ctx.write_schematic::<SpiceSubckt>(&params, &mut out)
+        .expect("failed to write schematic");
+    let out = String::from_utf8(out).unwrap();
+    assert!(out.contains("R1 p mid 1k"));
+    assert!(out.contains("R2 mid n 1k"));
+}
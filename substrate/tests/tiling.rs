@@ -1,15 +1,17 @@
 use arcstr::ArcStr;
 use common::{out_path, setup_ctx};
 use serde::{Deserialize, Serialize};
+use subgeom::bbox::BoundBox;
 use subgeom::orientation::Named;
 use subgeom::{Corner as SubCorner, Point, Rect};
 use substrate::component::{Component, NoParams};
 use substrate::data::SubstrateCtx;
 use substrate::into_grid;
-use substrate::layout::cell::{CellPort, PortConflictStrategy, PortId};
+use substrate::layout::cell::{CellPort, Port, PortConflictStrategy, PortId};
 use substrate::layout::context::LayoutCtx;
 use substrate::layout::layers::selector::Selector;
 use substrate::layout::placement::grid::GridTiler;
+use substrate::layout::placement::matching::{MatchPattern, MatchedArray, MatchedSlot};
 use substrate::layout::placement::nine_patch::{NpTiler, Region};
 use substrate::layout::placement::place_bbox::PlaceBbox;
 use substrate::layout::placement::tile::{Pad, Padding};
@@ -24,6 +26,8 @@ pub struct Center;
 pub struct TiledPorts;
 pub struct MergeTiledPorts;
 pub struct TiledCells(TilingParams);
+pub struct MatchedCells;
+pub struct ArrayedCells;
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct TilingParams {
@@ -189,6 +193,83 @@ impl Component for TiledPorts {
     }
 }
 
+impl Component for ArrayedCells {
+    type Params = NoParams;
+    fn new(_params: &Self::Params, _ctx: &SubstrateCtx) -> substrate::error::Result<Self> {
+        Ok(Self)
+    }
+
+    fn name(&self) -> ArcStr {
+        arcstr::literal!("arrayed_cells")
+    }
+
+    fn layout(&self, ctx: &mut LayoutCtx) -> substrate::error::Result<()> {
+        let edge = ctx.instantiate::<Edge>(&NoParams)?;
+
+        let mut tiler = GridTiler::new(into_grid![
+            [edge.clone(), edge.clone(), edge.clone(), edge.clone()]
+            [edge.clone(), edge.clone(), edge.clone(), edge.clone()]
+            [edge.clone(), edge.clone(), edge.clone(), edge]
+        ]);
+        tiler.use_instance_array(true);
+        tiler.expose_ports(
+            |port: CellPort, (i, j)| Some(port.named(format!("vdd_{i}_{j}"))),
+            PortConflictStrategy::Error,
+        )?;
+
+        ctx.add_ports(tiler.ports().cloned()).unwrap();
+        ctx.draw(tiler)?;
+
+        Ok(())
+    }
+}
+
+impl Component for MatchedCells {
+    type Params = NoParams;
+    fn new(_params: &Self::Params, _ctx: &SubstrateCtx) -> substrate::error::Result<Self> {
+        Ok(Self)
+    }
+
+    fn name(&self) -> ArcStr {
+        arcstr::literal!("matched_cells")
+    }
+
+    fn layout(&self, ctx: &mut LayoutCtx) -> substrate::error::Result<()> {
+        let unit = ctx.instantiate::<Edge>(&NoParams)?;
+        let dummy = ctx.instantiate::<Center>(&NoParams)?;
+
+        let array = MatchedArray::builder()
+            .group("a", unit.clone(), 4)
+            .group("b", unit, 4)
+            .pattern(MatchPattern::CommonCentroid)
+            .rows(2)
+            .dummy(dummy)
+            .dummy_cols(1)
+            .mirror_alternate(true)
+            .build()?;
+
+        assert_eq!(array.rows(), 2);
+        assert_eq!(array.cols(), 6);
+        assert_eq!(array.slot(0, 0), MatchedSlot::Dummy { index: 0 });
+        for (i, j) in [(0, 1), (0, 3), (1, 2), (1, 4)] {
+            assert_eq!(array.group_name(i, j).unwrap(), "a");
+        }
+
+        let tiler = array.tiler()?;
+        tiler.port_map().port(PortId::new("a_vdd", 3))?;
+        tiler.port_map().port(PortId::new("b_vdd", 3))?;
+        tiler
+            .port_map()
+            .port(PortId::new("dummy_vdd", 0))
+            .unwrap_err();
+
+        ctx.add_ports(tiler.ports().cloned())?;
+        ctx.draw(tiler)?;
+
+        Ok(())
+    }
+}
+
 impl Component for MergeTiledPorts {
     type Params = NoParams;
     fn new(_params: &Self::Params, _ctx: &SubstrateCtx) -> substrate::error::Result<Self> {
@@ -365,3 +446,68 @@ fn test_tiling_ninepatch_4x9_padded_rect_mode() {
         )
         .expect("failed to write layout");
 }
+
+#[test]
+fn test_tiling_matched_common_centroid() {
+    setup_ctx()
+        .write_layout::<MatchedCells>(
+            &NoParams,
+            out_path("test_tiling_matched_common_centroid", "layout.gds"),
+        )
+        .expect("failed to write layout");
+}
+
+#[test]
+fn test_tiling_instance_array() {
+    let gds_path = out_path("test_tiling_instance_array", "layout.gds");
+    let ctx = setup_ctx();
+    ctx.write_layout::<ArrayedCells>(&NoParams, &gds_path)
+        .expect("failed to write layout");
+
+    let inst = ctx.instantiate_layout::<ArrayedCells>(&NoParams).unwrap();
+    let cell = inst.cell();
+    assert_eq!(cell.insts().count(), 0);
+    let arrays = cell.arrays().collect::<Vec<_>>();
+    assert_eq!(arrays.len(), 1, "expected a single instance array");
+    let array = arrays[0];
+    assert_eq!((array.rows(), array.cols()), (3, 4));
+    assert_eq!(array.insts().count(), 12);
+    assert_eq!(cell.brect(), Rect::new(Point::zero(), Point::new(800, 600)));
+
+    // Row 0 of the grid is the top-most row.
+    let layer = ctx.layers().get(Selector::Metal(1)).unwrap();
+    let port = array
+        .port(0, 3, "vdd")
+        .unwrap()
+        .largest_rect(layer)
+        .unwrap();
+    assert_eq!(port, Rect::new(Point::new(600, 450), Point::new(700, 550)));
+    assert_eq!(
+        cell.port("vdd_0_3").unwrap().largest_rect(layer).unwrap(),
+        port
+    );
+
+    let lib = gds21::GdsLibrary::load(&gds_path).expect("failed to load GDS file");
+    let top = lib
+        .structs
+        .iter()
+        .find(|s| s.name == "arrayed_cells")
+        .unwrap();
+    let arefs = top
+        .elems
+        .iter()
+        .filter_map(|elem| match elem {
+            gds21::GdsElement::GdsArrayRef(aref) => Some(aref),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(arefs.len(), 1, "expected a single GDS array reference");
+    assert_eq!((arefs[0].rows, arefs[0].cols), (3, 4));
+
+    let cell_map = setup_ctx()
+        .from_gds(&gds_path)
+        .expect("failed to import GDS file");
+    let imported = cell_map.get("arrayed_cells").unwrap();
+    assert_eq!(imported.insts().count(), 12);
+    assert_eq!(imported.brect(), cell.brect());
+}